@@ -10,6 +10,8 @@ import { Badge } from '@/components/ui/badge';
 
 type ApiResponse<T> = { success: boolean; data?: T; message?: string };
 
+type PagedList<T> = { items: T[]; total: number; limit: number; offset: number };
+
 type SponsorshipRequestStatus = 'pending' | 'processed' | 'rejected';
 
 type SponsorshipRequest = {
@@ -131,6 +133,16 @@ export default function AdminSponsorshipPage() {
   const [busy, setBusy] = useState<Record<string, boolean>>({});
   const [drafts, setDrafts] = useState<Record<number, Draft>>({});
 
+  /**
+   * 通知邮件中的审核链接形如 /sponsorship?id=N：切到全部请求并按该 ID 过滤。
+   */
+  useEffect(() => {
+    const id = new URLSearchParams(window.location.search).get('id')?.trim();
+    if (!id || !/^\d+$/.test(id)) return;
+    setTab('all');
+    setQuery(id);
+  }, []);
+
   /**
    * buildDefaultDraft
    * 将请求默认参数转为可编辑草稿（用于处理/拒绝操作）。
@@ -189,12 +201,12 @@ export default function AdminSponsorshipPage() {
           headers: { Authorization: `Bearer ${token}`, 'Accept-Language': 'zh' },
           cache: 'no-store',
         });
-        const json = (await res.json().catch(() => null)) as ApiResponse<SponsorshipGrant[]> | null;
+        const json = (await res.json().catch(() => null)) as ApiResponse<PagedList<SponsorshipGrant>> | null;
         if (!res.ok || !json?.success) {
           setMessage(json?.message || '加载定价队列失败。');
           return;
         }
-        setGrants(Array.isArray(json.data) ? json.data : []);
+        setGrants(Array.isArray(json.data?.items) ? json.data.items : []);
         return;
       }
 
@@ -206,12 +218,12 @@ export default function AdminSponsorshipPage() {
         headers: { Authorization: `Bearer ${token}`, 'Accept-Language': 'zh' },
         cache: 'no-store',
       });
-      const json = (await res.json().catch(() => null)) as ApiResponse<SponsorshipRequest[]> | null;
+      const json = (await res.json().catch(() => null)) as ApiResponse<PagedList<SponsorshipRequest>> | null;
       if (!res.ok || !json?.success) {
         setMessage(json?.message || '加载定价请求失败。');
         return;
       }
-      setRequests(Array.isArray(json.data) ? json.data : []);
+      setRequests(Array.isArray(json.data?.items) ? json.data.items : []);
     } catch {
       setMessage('网络错误，请稍后重试。');
     } finally {
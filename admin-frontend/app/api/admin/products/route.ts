@@ -2,6 +2,7 @@ import { NextResponse, type NextRequest } from 'next/server';
 import { requireAdmin } from '../_auth';
 
 const BACKEND_API_URL = process.env.BACKEND_API_URL || 'http://localhost:8080/api';
+const BACKEND_ADMIN_TOKEN = (process.env.BACKEND_ADMIN_TOKEN || '').trim();
 
 type ApiResponse<T> = { success: boolean; data?: T; message?: string };
 
@@ -15,6 +16,12 @@ async function readJsonSafe<T>(response: Response): Promise<T | null> {
   }
 }
 
+function getBackendAdminToken() {
+  const token = BACKEND_ADMIN_TOKEN;
+  if (!token) throw new Error('Missing BACKEND_ADMIN_TOKEN');
+  return token;
+}
+
 export async function GET(request: NextRequest) {
   try {
     await requireAdmin(request);
@@ -28,6 +35,7 @@ export async function GET(request: NextRequest) {
     const response = await fetch(`${BACKEND_API_URL}/products?${params.toString()}`, {
       headers: {
         'Accept-Language': request.headers.get('Accept-Language') || 'zh',
+        'x-admin-token': getBackendAdminToken(),
       },
       cache: 'no-store',
     });
@@ -79,6 +87,7 @@ export async function POST(request: NextRequest) {
       headers: {
         'Content-Type': 'application/json',
         'Accept-Language': request.headers.get('Accept-Language') || 'zh',
+        'x-admin-token': getBackendAdminToken(),
       },
       body: JSON.stringify(
         status === 'rejected'
@@ -120,6 +129,7 @@ export async function DELETE(request: NextRequest) {
       method: 'DELETE',
       headers: {
         'Accept-Language': request.headers.get('Accept-Language') || 'zh',
+        'x-admin-token': getBackendAdminToken(),
       },
       cache: 'no-store',
     });
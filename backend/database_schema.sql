@@ -96,6 +96,7 @@ CREATE TABLE IF NOT EXISTS product_favorites (
 CREATE TABLE IF NOT EXISTS newsletter_subscriptions (
     email TEXT PRIMARY KEY,
     unsubscribed BOOLEAN NOT NULL DEFAULT FALSE,
+    format TEXT NOT NULL DEFAULT 'html' CHECK (format IN ('html', 'text')),
     last_sent_week TEXT,
     last_sent_at TIMESTAMPTZ,
     created_at TIMESTAMPTZ DEFAULT NOW(),
@@ -139,6 +140,13 @@ CREATE TABLE IF NOT EXISTS sponsorship_requests (
     updated_at TIMESTAMPTZ DEFAULT NOW()
 );
 
+-- Prior product slugs, used to redirect links after a product is renamed
+CREATE TABLE IF NOT EXISTS product_slug_history (
+    slug TEXT PRIMARY KEY,
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ DEFAULT NOW()
+);
+
 -- Create indexes for better performance
 CREATE INDEX IF NOT EXISTS idx_products_status ON products(status);
 CREATE INDEX IF NOT EXISTS idx_products_category ON products(category);
@@ -159,8 +167,10 @@ CREATE INDEX IF NOT EXISTS idx_newsletter_subscriptions_unsubscribed ON newslett
 CREATE INDEX IF NOT EXISTS idx_sponsorship_grants_product_id ON sponsorship_grants(product_id);
 CREATE INDEX IF NOT EXISTS idx_sponsorship_grants_placement ON sponsorship_grants(placement);
 CREATE INDEX IF NOT EXISTS idx_sponsorship_grants_active_range ON sponsorship_grants(starts_at, ends_at);
+CREATE INDEX IF NOT EXISTS idx_sponsorship_grants_placement_active_range ON sponsorship_grants(placement, starts_at, ends_at);
 CREATE INDEX IF NOT EXISTS idx_sponsorship_requests_status ON sponsorship_requests(status);
 CREATE INDEX IF NOT EXISTS idx_sponsorship_requests_created_at ON sponsorship_requests(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_product_slug_history_product_id ON product_slug_history(product_id);
 
 -- Create function to update updated_at timestamp
 CREATE OR REPLACE FUNCTION update_updated_at_column()
@@ -1,35 +1,131 @@
 use crate::models::{
-    Category, CreateProductRequest, CreateSponsorshipGrantFromRequest, CreateSponsorshipRequest,
-    Developer, DeveloperCenterStats, DeveloperPopularity, DeveloperWithFollowers, PaymentsSummary,
-    PricingPlan, Product, QueryParams, SponsorshipGrant, SponsorshipOrder, SponsorshipRequest,
-    UpdateProductRequest, UpsertPricingPlanRequest,
+    AdminAuditEntry, Category, CreateManualSponsorshipGrant, CreateProductRequest,
+    CreateSponsorshipGrantFromRequest, CreateSponsorshipRequest, Developer, DeveloperCenterStats,
+    DeveloperCenterWindowStats, DeveloperMergeResult, DeveloperPopularity, DeveloperWithFollowers,
+    FieldError, ManualSponsorshipGrantResult, MigrationStatus, NewsletterWeekStats, OrphanMaker,
+    OutgoingWebhook, OutgoingWebhookDelivery, OverviewStats, PaymentsSummary, Placement,
+    PlacementPricing, PricingPlan, PricingPlanBenefitGroups, Product, ProductChange,
+    ProductChangesPage, ProductClaim, ProductEngagementDay, ProductRefCandidate, ProductRefMatch,
+    QueryParams, ReorderPricingPlansRequest, ResolvedProductRef, SponsorshipGrant,
+    SponsorshipGrantState, SponsorshipOrder, SponsorshipRequest, UpdateProductRequest,
+    UpsertPricingPlanRequest, UserSponsorshipOrder,
 };
 use anyhow::Result;
 use base64::{engine::general_purpose, Engine as _};
 use chrono::{Datelike, TimeZone, Timelike};
+use futures_util::StreamExt;
 use hmac::{Hmac, Mac};
 use reqwest::{Client, Url};
 use sha2::Sha256;
 use sqlx::postgres::{PgConnectOptions, PgPool, PgPoolOptions};
 use sqlx::{Postgres, QueryBuilder};
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
 use std::str::FromStr;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const OVERVIEW_STATS_CACHE_TTL: Duration = Duration::from_secs(60);
 
 pub struct Database {
     supabase: Option<SupabaseDatabase>,
     postgres: Option<PgPool>,
+    overview_stats_cache: Mutex<Option<(Instant, OverviewStats)>>,
+    featured_history_recorded: Mutex<Option<(chrono::NaiveDate, Vec<String>)>>,
+}
+
+/**
+ * PostgresNotConfiguredError
+ * 仅配置 Supabase 时调用依赖 Postgres 的方法（赞助、定价管理、审计等写路径）返回的错误，handler 映射为 501。
+ */
+#[derive(Debug)]
+pub struct PostgresNotConfiguredError;
+
+impl std::fmt::Display for PostgresNotConfiguredError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Postgres is not configured")
+    }
 }
 
+impl std::error::Error for PostgresNotConfiguredError {}
+
 struct SupabaseDatabase {
     client: Client,
     supabase_url: String,
     supabase_key: String,
 }
 
+const SUPABASE_GET_MAX_ATTEMPTS: u32 = 3;
+
+/**
+ * is_retryable_supabase_status
+ * Supabase 返回的 5xx 与 429 视为瞬时错误，可对幂等 GET 重试。
+ */
+fn is_retryable_supabase_status(status: reqwest::StatusCode) -> bool {
+    status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+}
+
+/**
+ * supabase_retry_delay
+ * 第 attempt 次失败后的退避时间：200ms * 2^attempt，加 0~99ms 随机抖动。
+ */
+fn supabase_retry_delay(attempt: u32) -> Duration {
+    let jitter_ms = (uuid::Uuid::new_v4().as_u128() % 100) as u64;
+    Duration::from_millis(200 * 2u64.pow(attempt) + jitter_ms)
+}
+
+impl SupabaseDatabase {
+    /**
+     * get_with_retry
+     * 对 Supabase REST 的幂等 GET 请求做带抖动的重试（最多 3 次）；超时与连接错误、5xx/429 会重试，
+     * 最终响应原样返回，由调用方按状态码与响应体生成错误。请求超时沿用 client 的 connect_timeout/timeout。
+     */
+    async fn get_with_retry(
+        &self,
+        url: Url,
+        headers: &[(&str, &str)],
+    ) -> Result<reqwest::Response> {
+        let mut attempt = 0;
+        loop {
+            let mut request = self
+                .client
+                .get(url.clone())
+                .header("apikey", &self.supabase_key)
+                .header("Authorization", &format!("Bearer {}", self.supabase_key))
+                .header("Accept", "application/json");
+            for (name, value) in headers {
+                request = request.header(*name, *value);
+            }
+
+            attempt += 1;
+            let can_retry = attempt < SUPABASE_GET_MAX_ATTEMPTS;
+            match request.send().await {
+                Ok(response) if can_retry && is_retryable_supabase_status(response.status()) => {
+                    log::warn!(
+                        "Supabase GET retrying path={} status={} attempt={}",
+                        url.path(),
+                        response.status(),
+                        attempt
+                    );
+                }
+                Ok(response) => return Ok(response),
+                Err(e) if can_retry && (e.is_timeout() || e.is_connect()) => {
+                    log::warn!(
+                        "Supabase GET retrying path={} err={} attempt={}",
+                        url.path(),
+                        e,
+                        attempt
+                    );
+                }
+                Err(e) => return Err(e.into()),
+            }
+            tokio::time::sleep(supabase_retry_delay(attempt - 1)).await;
+        }
+    }
+}
+
 #[derive(sqlx::FromRow)]
 struct ProductRow {
     id: String,
@@ -52,6 +148,10 @@ struct ProductRow {
     updated_at: chrono::DateTime<chrono::Utc>,
     likes: i64,
     favorites: i64,
+    #[sqlx(default)]
+    has_liked: Option<bool>,
+    #[sqlx(default)]
+    has_favorited: Option<bool>,
 }
 
 #[derive(sqlx::FromRow)]
@@ -81,6 +181,31 @@ struct DeveloperRow {
     website: Option<String>,
     sponsor_role: Option<String>,
     sponsor_verified: bool,
+    created_at: Option<chrono::DateTime<chrono::Utc>>,
+    updated_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(sqlx::FromRow)]
+struct OutgoingWebhookRow {
+    id: i64,
+    url: String,
+    events: Vec<String>,
+    enabled: bool,
+    consecutive_failures: i32,
+    disabled_at: Option<chrono::DateTime<chrono::Utc>>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Clone, sqlx::FromRow)]
+struct ProductClaimRow {
+    id: i64,
+    product_id: String,
+    claimant_email: String,
+    status: String,
+    note: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    decided_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 #[derive(sqlx::FromRow)]
@@ -92,6 +217,8 @@ struct DeveloperWithFollowersRow {
     sponsor_role: Option<String>,
     sponsor_verified: bool,
     followers: String,
+    created_at: Option<chrono::DateTime<chrono::Utc>>,
+    updated_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 #[derive(sqlx::FromRow)]
@@ -104,6 +231,8 @@ struct DeveloperPopularityRow {
     sponsor_verified: bool,
     likes: i64,
     favorites: i64,
+    #[sqlx(default)]
+    new_followers: i64,
     score: i64,
 }
 
@@ -112,6 +241,9 @@ struct DeveloperCenterStatsRow {
     followers: i64,
     total_likes: i64,
     total_favorites: i64,
+    window_followers: i64,
+    window_likes: i64,
+    window_favorites: i64,
 }
 
 #[derive(sqlx::FromRow)]
@@ -125,6 +257,7 @@ pub(crate) struct NewsletterTopProductRow {
     pub(crate) name: String,
     pub(crate) slogan: String,
     pub(crate) website: String,
+    pub(crate) logo_url: Option<String>,
     pub(crate) maker_name: String,
     pub(crate) maker_email: String,
     pub(crate) weekly_likes: i64,
@@ -145,6 +278,7 @@ pub struct HomeModuleStateRow {
 struct SponsorshipGrantRow {
     product_id: String,
     slot_index: Option<i32>,
+    ends_at: chrono::DateTime<chrono::Utc>,
 }
 
 #[derive(sqlx::FromRow)]
@@ -231,6 +365,13 @@ struct PricingPlanBenefitRow {
     available: bool,
 }
 
+#[derive(Debug, Clone)]
+pub struct ActiveSponsorshipGrant {
+    pub slot_index: Option<i32>,
+    pub product_id: String,
+    pub ends_at: chrono::DateTime<chrono::Utc>,
+}
+
 pub struct HomeModuleState {
     pub key: String,
     pub mode: Option<String>,
@@ -239,6 +380,17 @@ pub struct HomeModuleState {
     pub today_ids: Vec<String>,
 }
 
+/**
+ * HomeModuleStatePatch
+ * 首页模块状态的局部更新：只写入为 Some 的字段，其余列保持原值。
+ */
+#[derive(Debug, Clone, Default)]
+pub struct HomeModuleStatePatch {
+    pub mode: Option<String>,
+    pub remaining_ids: Option<Vec<String>>,
+    pub today_ids: Option<Vec<String>>,
+}
+
 fn map_home_module_state_row(row: HomeModuleStateRow) -> HomeModuleState {
     HomeModuleState {
         key: row.key,
@@ -249,6 +401,157 @@ fn map_home_module_state_row(row: HomeModuleStateRow) -> HomeModuleState {
     }
 }
 
+/**
+ * HomeModuleMode
+ * 首页模块的选品模式：manual 使用管理员固定的 today_ids；paid 仅展示生效中的付费 grants；
+ * free 仅展示免费轮播候选（忽略 grants）；mixed 先填付费位，再用免费轮播补齐，最后用兜底列表补齐。
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HomeModuleMode {
+    Manual,
+    Free,
+    Paid,
+    Mixed,
+}
+
+impl HomeModuleMode {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "manual" => Some(Self::Manual),
+            "free" => Some(Self::Free),
+            "paid" => Some(Self::Paid),
+            "mixed" => Some(Self::Mixed),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Manual => "manual",
+            Self::Free => "free",
+            Self::Paid => "paid",
+            Self::Mixed => "mixed",
+        }
+    }
+
+    /**
+     * resolve
+     * 解析存储的 mode；为空或无法识别时使用模块自身的默认模式。
+     */
+    pub fn resolve(stored: Option<&str>, default: Self) -> Self {
+        stored.and_then(Self::parse).unwrap_or(default)
+    }
+
+    pub fn uses_paid(self) -> bool {
+        matches!(self, Self::Paid | Self::Mixed)
+    }
+
+    pub fn uses_free(self) -> bool {
+        matches!(self, Self::Free | Self::Mixed)
+    }
+}
+
+/**
+ * compose_home_module_ids
+ * 按模式组合首页模块的产品 id（去重，最多 capacity 个）。
+ * 填充顺序：1) 付费位按 paid_slots 的槽位放置；2) free 按顺序补齐空槽位；3) 压缩空位后追加 fallback。
+ * paid 模式只保留第 1 步；free 模式跳过第 1 步。
+ */
+pub(crate) fn compose_home_module_ids(
+    mode: HomeModuleMode,
+    paid_slots: &[Option<String>],
+    free: &[String],
+    fallback: &[String],
+    capacity: usize,
+) -> Vec<String> {
+    let mut exclude: HashSet<String> = HashSet::new();
+    let mut slots: Vec<Option<String>> = vec![None; capacity];
+
+    if mode.uses_paid() {
+        for (slot, id) in slots.iter_mut().zip(paid_slots.iter()) {
+            if let Some(id) = id {
+                if exclude.insert(id.clone()) {
+                    *slot = Some(id.clone());
+                }
+            }
+        }
+    }
+
+    if mode.uses_free() {
+        let mut free_iter = free.iter();
+        for slot in slots.iter_mut() {
+            if slot.is_some() {
+                continue;
+            }
+            for id in free_iter.by_ref() {
+                if exclude.insert(id.clone()) {
+                    *slot = Some(id.clone());
+                    break;
+                }
+            }
+        }
+    }
+
+    let mut chosen: Vec<String> = slots.into_iter().flatten().collect();
+    if mode.uses_free() {
+        for id in fallback {
+            if chosen.len() >= capacity {
+                break;
+            }
+            if exclude.insert(id.clone()) {
+                chosen.push(id.clone());
+            }
+        }
+    }
+    chosen
+}
+
+/**
+ * featured_cooldown_days
+ * FEATURED_COOLDOWN_DAYS：首页精选的冷却天数，过去 N 天（不含今天）入选过的产品暂不再入选；
+ * 未配置或为 0 时关闭，最大 90 天。
+ */
+pub(crate) fn featured_cooldown_days() -> i64 {
+    cooldown_days_from_env_value(env::var("FEATURED_COOLDOWN_DAYS").ok().as_deref())
+}
+
+fn cooldown_days_from_env_value(raw: Option<&str>) -> i64 {
+    raw.and_then(|v| v.trim().parse::<i64>().ok())
+        .unwrap_or(0)
+        .clamp(0, 90)
+}
+
+/**
+ * pick_featured_with_cooldown
+ * 按候选顺序（热度）挑选精选 id：优先跳过冷却期内入选过的产品；
+ * 候选不足 limit 时放宽限制，按原顺序用冷却中的产品补齐。
+ */
+pub(crate) fn pick_featured_with_cooldown(
+    candidates: &[String],
+    recently_featured: &HashSet<String>,
+    limit: usize,
+) -> Vec<String> {
+    let mut seen: HashSet<&String> = HashSet::new();
+    let mut picked: Vec<String> = Vec::new();
+    for id in candidates {
+        if picked.len() >= limit {
+            break;
+        }
+        if !recently_featured.contains(id) && seen.insert(id) {
+            picked.push(id.clone());
+        }
+    }
+    for id in candidates {
+        if picked.len() >= limit {
+            break;
+        }
+        if seen.insert(id) {
+            picked.push(id.clone());
+        }
+    }
+    picked
+}
+
 fn map_sponsorship_request_row(row: SponsorshipRequestRow) -> SponsorshipRequest {
     let mut email = row.email;
     let mut product_ref = row.product_ref;
@@ -275,7 +578,25 @@ fn map_sponsorship_request_row(row: SponsorshipRequestRow) -> SponsorshipRequest
     }
 }
 
+/**
+ * SPONSORSHIP_GRANT_ACTIVE_AT_PARAM
+ * grant 在某时刻生效的区间条件（别名 s，$2 为该时刻）：starts_at <= t < ends_at。
+ */
+const SPONSORSHIP_GRANT_ACTIVE_AT_PARAM: &str = "s.starts_at <= $2 AND s.ends_at > $2";
+
 fn map_sponsorship_grant_full_row(row: SponsorshipGrantFullRow) -> SponsorshipGrant {
+    map_sponsorship_grant_full_row_at(row, chrono::Utc::now())
+}
+
+/**
+ * map_sponsorship_grant_full_row_at
+ * 映射 grant 行，并按 now 计算 active/state（原始 starts_at/ends_at 保留）。
+ */
+fn map_sponsorship_grant_full_row_at(
+    row: SponsorshipGrantFullRow,
+    now: chrono::DateTime<chrono::Utc>,
+) -> SponsorshipGrant {
+    let state = SponsorshipGrantState::at(row.starts_at, row.ends_at, now);
     let mut product_id = row.product_id;
     let mut placement = row.placement;
     let mut source = row.source;
@@ -292,6 +613,8 @@ fn map_sponsorship_grant_full_row(row: SponsorshipGrantFullRow) -> SponsorshipGr
         source,
         amount_usd_cents: row.amount_usd_cents,
         created_at: row.created_at,
+        active: state == SponsorshipGrantState::Active,
+        state,
     }
 }
 
@@ -301,6 +624,45 @@ fn map_sponsorship_order_row(row: SponsorshipOrderRow) -> (String, String) {
     (row.id.to_string(), user_email)
 }
 
+/**
+ * map_pricing_plan_benefit_rows
+ * 转换权益行并按 (sort_order, id) 排序，保证前台展示顺序稳定；available 原样保留。
+ */
+fn map_pricing_plan_benefit_rows(
+    benefit_rows: Vec<PricingPlanBenefitRow>,
+) -> Vec<crate::models::PricingPlanBenefit> {
+    let mut benefits = benefit_rows
+        .into_iter()
+        .map(|mut b| {
+            strip_nul_in_place(&mut b.text_en);
+            strip_nul_in_place(&mut b.text_zh);
+            crate::models::PricingPlanBenefit {
+                id: b.id,
+                sort_order: b.sort_order,
+                text_en: b.text_en,
+                text_zh: b.text_zh,
+                available: b.available,
+            }
+        })
+        .collect::<Vec<_>>();
+    benefits.sort_by_key(|b| (b.sort_order, b.id));
+    benefits
+}
+
+/**
+ * group_pricing_plan_benefits
+ * 将已排序的权益拆分为可用/不可用两组，组内保持原顺序。
+ */
+pub(crate) fn group_pricing_plan_benefits(
+    benefits: &[crate::models::PricingPlanBenefit],
+) -> PricingPlanBenefitGroups {
+    let (available, unavailable) = benefits.iter().cloned().partition(|b| b.available);
+    PricingPlanBenefitGroups {
+        available,
+        unavailable,
+    }
+}
+
 /**
  * map_pricing_plan_row_to_model
  * 将 pricing_plans + pricing_plan_benefits 行映射为对外返回的 PricingPlan。
@@ -320,20 +682,7 @@ fn map_pricing_plan_row_to_model(
     strip_nul_in_place_opt(&mut row.campaign_title_en);
     strip_nul_in_place_opt(&mut row.campaign_title_zh);
 
-    let benefits = benefit_rows
-        .into_iter()
-        .map(|mut b| {
-            strip_nul_in_place(&mut b.text_en);
-            strip_nul_in_place(&mut b.text_zh);
-            crate::models::PricingPlanBenefit {
-                id: b.id,
-                sort_order: b.sort_order,
-                text_en: b.text_en,
-                text_zh: b.text_zh,
-                available: b.available,
-            }
-        })
-        .collect::<Vec<_>>();
+    let benefits = map_pricing_plan_benefit_rows(benefit_rows);
 
     PricingPlan {
         id: row.id.to_string(),
@@ -379,7 +728,7 @@ fn parse_product_status(raw: &str) -> crate::models::ProductStatus {
  * serialize_product_status
  * 将 ProductStatus 序列化为数据库可用的小写字符串。
  */
-fn serialize_product_status(status: &crate::models::ProductStatus) -> &'static str {
+pub(crate) fn serialize_product_status(status: &crate::models::ProductStatus) -> &'static str {
     match status {
         crate::models::ProductStatus::Pending => "pending",
         crate::models::ProductStatus::Approved => "approved",
@@ -389,15 +738,118 @@ fn serialize_product_status(status: &crate::models::ProductStatus) -> &'static s
 
 /**
  * dev_include_pending_in_approved
- * 开发环境下将 approved 视为 (approved | pending)，用于在 RLS 限制下展示 seed 数据。
+ * 开发环境下将 approved 视为 (approved | pending)，用于在 RLS 限制下展示 seed 数据；生产构建始终忽略该开关。
  */
 fn dev_include_pending_in_approved() -> bool {
-    matches!(
-        env::var("DEV_INCLUDE_PENDING_IN_APPROVED"),
-        Ok(v) if v.eq_ignore_ascii_case("1") || v.eq_ignore_ascii_case("true")
+    dev_include_pending_from_env_value(
+        env::var("DEV_INCLUDE_PENDING_IN_APPROVED").ok().as_deref(),
+        !cfg!(debug_assertions),
     )
 }
 
+fn dev_include_pending_from_env_value(raw: Option<&str>, is_production: bool) -> bool {
+    !is_production
+        && matches!(
+            raw.map(|v| v.trim().to_ascii_lowercase()).as_deref(),
+            Some("1") | Some("true")
+        )
+}
+
+/**
+ * new_submission_grace_hours
+ * NEW_SUBMISSION_GRACE_HOURS：新提交的 pending 产品在创建后 N 小时内也出现在公开列表（"新品"宽限期）；
+ * 未配置或非正数时关闭，最大 720 小时。与 DEV_INCLUDE_PENDING_IN_APPROVED 调试开关相互独立。
+ */
+fn new_submission_grace_hours() -> Option<i64> {
+    grace_hours_from_env_value(env::var("NEW_SUBMISSION_GRACE_HOURS").ok().as_deref())
+}
+
+fn grace_hours_from_env_value(raw: Option<&str>) -> Option<i64> {
+    raw.and_then(|v| v.trim().parse::<i64>().ok())
+        .filter(|v| *v > 0)
+        .map(|v| v.min(720))
+}
+
+/**
+ * ProductVisibility
+ * 公开列表的产品可见性规则（唯一出处）：管理员 include_pending 或 dev 开关 → approved | pending；
+ * 宽限期 → approved | (pending 且 created_at 在 N 小时内)；否则仅 approved。
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ProductVisibility {
+    include_pending: bool,
+    grace_hours: Option<i64>,
+}
+
+impl ProductVisibility {
+    fn from_env() -> Self {
+        Self {
+            include_pending: dev_include_pending_in_approved(),
+            grace_hours: new_submission_grace_hours(),
+        }
+    }
+
+    /**
+     * for_request
+     * 在环境规则基础上叠加单次请求的 include_pending（仅管理员请求可传 true，由处理器校验）。
+     */
+    fn for_request(include_pending: bool) -> Self {
+        let mut visibility = Self::from_env();
+        visibility.include_pending |= include_pending;
+        visibility
+    }
+
+    /**
+     * predicate
+     * 不含绑定参数的 SQL 谓词（alias 为表别名，空串表示不加前缀），可直接拼进 WHERE。
+     */
+    fn predicate(&self, alias: &str) -> String {
+        let a = if alias.is_empty() {
+            String::new()
+        } else {
+            format!("{}.", alias)
+        };
+        if self.include_pending {
+            return format!("{a}status::text IN ('approved','pending')");
+        }
+        match self.grace_hours {
+            Some(hours) => format!(
+                "({a}status::text = 'approved' OR ({a}status::text = 'pending' AND {a}created_at > NOW() - INTERVAL '{hours} hours'))"
+            ),
+            None => format!("{a}status::text = 'approved'"),
+        }
+    }
+
+    /**
+     * postgrest_filter
+     * Supabase REST 回退路径使用的等价过滤参数 (key, value)。
+     */
+    fn postgrest_filter(&self, now: chrono::DateTime<chrono::Utc>) -> (&'static str, String) {
+        if self.include_pending {
+            return ("status", "in.(approved,pending)".to_string());
+        }
+        match self.grace_hours {
+            Some(hours) => (
+                "or",
+                format!(
+                    "(status.eq.approved,and(status.eq.pending,created_at.gt.{}))",
+                    (now - chrono::Duration::hours(hours))
+                        .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+                ),
+            ),
+            None => ("status", "eq.approved".to_string()),
+        }
+    }
+}
+
+/**
+ * product_visibility_clause
+ * 按当前环境（DEV_INCLUDE_PENDING_IN_APPROVED / NEW_SUBMISSION_GRACE_HOURS）生成公开可见性谓词。
+ */
+fn product_visibility_clause(alias: &str) -> String {
+    ProductVisibility::from_env().predicate(alias)
+}
+
 fn is_retryable_db_error(err: &anyhow::Error) -> bool {
     let msg = format!("{:?}", err).to_ascii_lowercase();
     msg.contains("prepared statement")
@@ -420,81 +872,219 @@ fn is_missing_relation_error(err: &anyhow::Error, relation: &str) -> bool {
     msg.contains("relation") && msg.contains(relation) && msg.contains("does not exist")
 }
 
+/**
+ * ConstraintViolation
+ * 写入时触发的数据库约束冲突类别：唯一约束（SQLSTATE 23505）或外键约束（23503）。
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstraintViolation {
+    Unique,
+    ForeignKey,
+}
+
+impl ConstraintViolation {
+    pub fn from_sqlstate(code: &str) -> Option<Self> {
+        match code {
+            "23505" => Some(Self::Unique),
+            "23503" => Some(Self::ForeignKey),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * constraint_violation
+ * 识别错误链中的约束冲突：优先读取 sqlx 数据库错误的 SQLSTATE，其次匹配 Supabase REST 响应体中的 "code":"235xx"。
+ */
+pub(crate) fn constraint_violation(err: &anyhow::Error) -> Option<ConstraintViolation> {
+    for cause in err.chain() {
+        if let Some(sqlx::Error::Database(db_err)) = cause.downcast_ref::<sqlx::Error>() {
+            if let Some(violation) = db_err
+                .code()
+                .and_then(|code| ConstraintViolation::from_sqlstate(&code))
+            {
+                return Some(violation);
+            }
+        }
+    }
+    let msg = format!("{:#}", err).replace(' ', "");
+    ["23505", "23503"]
+        .into_iter()
+        .find(|code| msg.contains(&format!("\"code\":\"{}\"", code)))
+        .and_then(ConstraintViolation::from_sqlstate)
+}
+
+fn is_missing_on_conflict_constraint_error(err: &anyhow::Error) -> bool {
+    let msg = format!("{:?}", err).to_ascii_lowercase();
+    msg.contains("no unique or exclusion constraint matching the on conflict specification")
+}
+
 static PRODUCTS_REJECTION_REASON_READY: AtomicBool = AtomicBool::new(false);
 static PRICING_TEXT_MIGRATION_READY: AtomicBool = AtomicBool::new(false);
+static ENGAGEMENT_UNIQUE_INDEXES_READY: AtomicBool = AtomicBool::new(false);
 
-async fn ensure_products_rejection_reason_column(pool: &PgPool) -> Result<()> {
-    if PRODUCTS_REJECTION_REASON_READY.load(Ordering::Relaxed) {
+/**
+ * ensure_engagement_unique_indexes
+ * 为 product_likes / product_favorites 补齐 (product_id, user_id) 唯一索引（先清理重复行），
+ * 避免旧库缺少约束时 ON CONFLICT 失败或重复点赞/收藏导致计数虚高。
+ */
+async fn ensure_engagement_unique_indexes(pool: &PgPool) -> Result<()> {
+    if ENGAGEMENT_UNIQUE_INDEXES_READY.load(Ordering::Relaxed) {
         return Ok(());
     }
-    sqlx::query("ALTER TABLE products ADD COLUMN IF NOT EXISTS rejection_reason TEXT")
+
+    for table in ["product_likes", "product_favorites"] {
+        let mut tx = pool.begin().await?;
+        sqlx::query(&format!("LOCK TABLE {} IN SHARE ROW EXCLUSIVE MODE", table))
+            .persistent(false)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(&format!(
+            "DELETE FROM {t} a USING {t} b \
+             WHERE a.product_id = b.product_id AND a.user_id = b.user_id AND a.ctid > b.ctid",
+            t = table
+        ))
         .persistent(false)
-        .execute(pool)
+        .execute(&mut *tx)
         .await?;
-    PRODUCTS_REJECTION_REASON_READY.store(true, Ordering::Relaxed);
+        sqlx::query(&format!(
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_{t}_product_user_unique ON {t}(product_id, user_id)",
+            t = table
+        ))
+        .persistent(false)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+    }
+
+    ENGAGEMENT_UNIQUE_INDEXES_READY.store(true, Ordering::Relaxed);
     Ok(())
 }
 
-static DEVELOPERS_SPONSOR_COLUMNS_READY: AtomicBool = AtomicBool::new(false);
+static PRODUCTS_BUMPED_AT_READY: AtomicBool = AtomicBool::new(false);
 
 /**
- * ensure_developers_sponsor_columns
- * 自动补齐 developers 表的 sponsor_role / sponsor_verified 字段，避免旧库缺列导致查询失败。
+ * ensure_products_bumped_at_column
+ * 自动补齐 products.bumped_at（创作者“顶一下”的时间），sort=recent 按 COALESCE(bumped_at, created_at) 排序。
  */
-async fn ensure_developers_sponsor_columns(pool: &PgPool) -> Result<()> {
-    if DEVELOPERS_SPONSOR_COLUMNS_READY.load(Ordering::Relaxed) {
+async fn ensure_products_bumped_at_column(pool: &PgPool) -> Result<()> {
+    if PRODUCTS_BUMPED_AT_READY.load(Ordering::Relaxed) {
         return Ok(());
     }
-
-    sqlx::query("ALTER TABLE developers ADD COLUMN IF NOT EXISTS sponsor_role TEXT")
+    sqlx::query("ALTER TABLE products ADD COLUMN IF NOT EXISTS bumped_at TIMESTAMPTZ")
         .persistent(false)
         .execute(pool)
         .await?;
-    sqlx::query(
-        "ALTER TABLE developers ADD COLUMN IF NOT EXISTS sponsor_verified BOOLEAN NOT NULL DEFAULT FALSE",
-    )
-    .persistent(false)
-    .execute(pool)
-    .await?;
+    PRODUCTS_BUMPED_AT_READY.store(true, Ordering::Relaxed);
+    Ok(())
+}
 
-    DEVELOPERS_SPONSOR_COLUMNS_READY.store(true, Ordering::Relaxed);
+async fn ensure_products_rejection_reason_column(pool: &PgPool) -> Result<()> {
+    if PRODUCTS_REJECTION_REASON_READY.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    sqlx::query("ALTER TABLE products ADD COLUMN IF NOT EXISTS rejection_reason TEXT")
+        .persistent(false)
+        .execute(pool)
+        .await?;
+    PRODUCTS_REJECTION_REASON_READY.store(true, Ordering::Relaxed);
     Ok(())
 }
 
-static SPONSORSHIP_TABLES_READY: AtomicBool = AtomicBool::new(false);
+static ENGAGEMENT_IP_HASH_READY: AtomicBool = AtomicBool::new(false);
 
-static PRICING_TABLES_READY: AtomicBool = AtomicBool::new(false);
+/**
+ * ensure_engagement_ip_hash_columns
+ * 自动补齐 product_likes / product_favorites 的 ip_hash 列，记录互动来源 IP 的哈希供刷量分析。
+ */
+async fn ensure_engagement_ip_hash_columns(pool: &PgPool) -> Result<()> {
+    if ENGAGEMENT_IP_HASH_READY.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    for sql in [
+        "ALTER TABLE product_likes ADD COLUMN IF NOT EXISTS ip_hash TEXT",
+        "ALTER TABLE product_favorites ADD COLUMN IF NOT EXISTS ip_hash TEXT",
+    ] {
+        sqlx::query(sql).persistent(false).execute(pool).await?;
+    }
+    ENGAGEMENT_IP_HASH_READY.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+static LOWER_EMAIL_INDEXES_READY: AtomicBool = AtomicBool::new(false);
+
+const LOWER_EMAIL_INDEXES_SQL: [&str; 2] = [
+    "CREATE INDEX IF NOT EXISTS idx_developers_lower_email ON developers(lower(email))",
+    "CREATE INDEX IF NOT EXISTS idx_products_lower_maker_email ON products(lower(maker_email))",
+];
 
 /**
- * ensure_pricing_tables
- * 自动创建 pricing_plans / pricing_plan_benefits 表与必要索引，避免旧库缺表导致接口失败。
+ * ensure_lower_email_indexes
+ * 懒创建 lower(email) / lower(maker_email) 函数索引，让大小写不敏感的邮箱查询与 developers 关联能走索引；失败仅记录日志。
  */
-async fn ensure_pricing_tables(pool: &PgPool) -> Result<()> {
-    if PRICING_TABLES_READY.load(Ordering::Relaxed) {
+async fn ensure_lower_email_indexes(pool: &PgPool) {
+    if LOWER_EMAIL_INDEXES_READY.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    for sql in LOWER_EMAIL_INDEXES_SQL {
+        if let Err(e) = sqlx::query(sql).persistent(false).execute(pool).await {
+            log::warn!("Failed to create lower(email) index: {:?}", e);
+            LOWER_EMAIL_INDEXES_READY.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+static DEVELOPERS_SPONSOR_COLUMNS_READY: AtomicBool = AtomicBool::new(false);
+
+/**
+ * ensure_developers_sponsor_columns
+ * 自动补齐 developers 表的 sponsor_role / sponsor_verified 字段，避免旧库缺列导致查询失败。
+ */
+async fn ensure_developers_sponsor_columns(pool: &PgPool) -> Result<()> {
+    if DEVELOPERS_SPONSOR_COLUMNS_READY.load(Ordering::Relaxed) {
         return Ok(());
     }
 
+    sqlx::query("ALTER TABLE developers ADD COLUMN IF NOT EXISTS sponsor_role TEXT")
+        .persistent(false)
+        .execute(pool)
+        .await?;
     sqlx::query(
-        "CREATE TABLE IF NOT EXISTS pricing_plans ( \
-            id UUID PRIMARY KEY, \
-            plan_key TEXT NOT NULL UNIQUE, \
-            placement TEXT CHECK (placement IN ('home_top', 'home_right')), \
-            monthly_usd_cents INT, \
-            title_en TEXT NOT NULL, \
-            title_zh TEXT NOT NULL, \
-            badge_en TEXT, \
-            badge_zh TEXT, \
-            description_en TEXT, \
-            description_zh TEXT, \
-            is_active BOOLEAN NOT NULL DEFAULT TRUE, \
-            is_default BOOLEAN NOT NULL DEFAULT FALSE, \
-            sort_order INT NOT NULL DEFAULT 0, \
-            campaign_active BOOLEAN NOT NULL DEFAULT FALSE, \
-            campaign_percent_off INT, \
-            campaign_title_en TEXT, \
-            campaign_title_zh TEXT, \
-            campaign_starts_at TIMESTAMPTZ, \
-            campaign_ends_at TIMESTAMPTZ, \
+        "ALTER TABLE developers ADD COLUMN IF NOT EXISTS sponsor_verified BOOLEAN NOT NULL DEFAULT FALSE",
+    )
+    .persistent(false)
+    .execute(pool)
+    .await?;
+
+    DEVELOPERS_SPONSOR_COLUMNS_READY.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+static NEWSLETTER_FORMAT_COLUMN_READY: AtomicBool = AtomicBool::new(false);
+static PRODUCT_CLAIMS_TABLE_READY: AtomicBool = AtomicBool::new(false);
+static PRODUCT_DELETIONS_TABLE_READY: AtomicBool = AtomicBool::new(false);
+static OUTGOING_WEBHOOK_TABLES_READY: AtomicBool = AtomicBool::new(false);
+static HOME_FEATURED_HISTORY_TABLE_READY: AtomicBool = AtomicBool::new(false);
+static ADMIN_AUDIT_LOG_TABLE_READY: AtomicBool = AtomicBool::new(false);
+
+/**
+ * ensure_outgoing_webhook_tables
+ * 自动创建对外 webhook 订阅表 outgoing_webhooks 与投递记录表 outgoing_webhook_deliveries。
+ */
+async fn ensure_outgoing_webhook_tables(pool: &PgPool) -> Result<()> {
+    if OUTGOING_WEBHOOK_TABLES_READY.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS outgoing_webhooks ( \
+            id BIGSERIAL PRIMARY KEY, \
+            url TEXT NOT NULL, \
+            secret TEXT NOT NULL, \
+            events TEXT[] NOT NULL DEFAULT ARRAY[]::text[], \
+            enabled BOOLEAN NOT NULL DEFAULT TRUE, \
+            consecutive_failures INT NOT NULL DEFAULT 0, \
+            disabled_at TIMESTAMPTZ, \
             created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(), \
             updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW() \
         )",
@@ -502,1330 +1092,9341 @@ async fn ensure_pricing_tables(pool: &PgPool) -> Result<()> {
     .persistent(false)
     .execute(pool)
     .await?;
-
     sqlx::query(
-        "CREATE TABLE IF NOT EXISTS pricing_plan_benefits ( \
+        "CREATE TABLE IF NOT EXISTS outgoing_webhook_deliveries ( \
             id BIGSERIAL PRIMARY KEY, \
-            plan_id UUID NOT NULL REFERENCES pricing_plans(id) ON DELETE CASCADE, \
-            sort_order INT NOT NULL DEFAULT 0, \
-            text_en TEXT NOT NULL, \
-            text_zh TEXT NOT NULL, \
-            available BOOLEAN NOT NULL DEFAULT TRUE \
+            webhook_id BIGINT NOT NULL REFERENCES outgoing_webhooks(id) ON DELETE CASCADE, \
+            event TEXT NOT NULL, \
+            payload TEXT NOT NULL, \
+            status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'delivered', 'failed')), \
+            attempts INT NOT NULL DEFAULT 0, \
+            last_status_code INT, \
+            last_error TEXT, \
+            next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(), \
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(), \
+            delivered_at TIMESTAMPTZ \
         )",
     )
     .persistent(false)
     .execute(pool)
     .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_outgoing_webhook_deliveries_due \
+         ON outgoing_webhook_deliveries(next_attempt_at) WHERE status = 'pending'",
+    )
+    .persistent(false)
+    .execute(pool)
+    .await?;
+
+    OUTGOING_WEBHOOK_TABLES_READY.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/**
+ * ensure_admin_audit_log_table
+ * 自动创建管理操作审计表 admin_audit_log（操作者、动作、对象类型/ID、载荷摘要、时间）。
+ */
+async fn ensure_admin_audit_log_table(pool: &PgPool) -> Result<()> {
+    if ADMIN_AUDIT_LOG_TABLE_READY.load(Ordering::Relaxed) {
+        return Ok(());
+    }
 
     sqlx::query(
-        "CREATE INDEX IF NOT EXISTS idx_pricing_plans_active_sort ON pricing_plans(is_active, sort_order)",
+        "CREATE TABLE IF NOT EXISTS admin_audit_log ( \
+            id BIGSERIAL PRIMARY KEY, \
+            actor TEXT NOT NULL, \
+            action TEXT NOT NULL, \
+            entity_type TEXT NOT NULL, \
+            entity_id TEXT, \
+            payload_summary TEXT, \
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW() \
+        )",
     )
     .persistent(false)
     .execute(pool)
     .await?;
     sqlx::query(
-        "CREATE INDEX IF NOT EXISTS idx_pricing_plans_placement ON pricing_plans(placement)",
+        "CREATE INDEX IF NOT EXISTS idx_admin_audit_log_created_at ON admin_audit_log(created_at DESC)",
     )
     .persistent(false)
     .execute(pool)
     .await?;
     sqlx::query(
-        "CREATE INDEX IF NOT EXISTS idx_pricing_plan_benefits_plan_id_sort ON pricing_plan_benefits(plan_id, sort_order)",
+        "CREATE INDEX IF NOT EXISTS idx_admin_audit_log_entity ON admin_audit_log(entity_type, entity_id)",
     )
     .persistent(false)
     .execute(pool)
     .await?;
 
-    let existing_count: i64 = sqlx::query_scalar("SELECT COUNT(1) FROM pricing_plans")
-        .persistent(false)
-        .fetch_one(pool)
-        .await
-        .unwrap_or(0);
-    if existing_count == 0 {
-        let free_id = uuid::Uuid::new_v4();
-        let top_id = uuid::Uuid::new_v4();
-        let right_id = uuid::Uuid::new_v4();
-
-        let _ = sqlx::query(
-            "INSERT INTO pricing_plans \
-             (id, plan_key, placement, monthly_usd_cents, title_en, title_zh, badge_en, badge_zh, description_en, description_zh, is_active, is_default, sort_order) \
-             VALUES \
-             ($1, 'free', NULL, NULL, 'Free', 'Free', 'Limited', '限量', 'Limited free exposure opportunity.', '限量免费曝光机会。', TRUE, FALSE, 10), \
-             ($2, 'home_top', 'home_top', 1000, 'Pro · Top', 'Pro · 顶部', 'Pricing', '定价', 'Highest exposure in homepage top module.', '展示在首页顶部定价模块（最高曝光）。', TRUE, TRUE, 20), \
-             ($3, 'home_right', 'home_right', 500, 'Pro · Right', 'Pro · 右侧', 'Pricing', '定价', 'Stable exposure in homepage right module.', '展示在首页右侧定价模块（稳定曝光）。', TRUE, FALSE, 30)",
-        )
-        .persistent(false)
-        .bind(free_id)
-        .bind(top_id)
-        .bind(right_id)
-        .execute(pool)
-        .await;
+    ADMIN_AUDIT_LOG_TABLE_READY.store(true, Ordering::Relaxed);
+    Ok(())
+}
 
-        let _ = sqlx::query(
-            "INSERT INTO pricing_plan_benefits (plan_id, sort_order, text_en, text_zh, available) VALUES \
-             ($1, 10, 'Eligible for random free slots', '每天随机出现在免费位', TRUE), \
-             ($1, 20, 'Up to 48 hours exposure', '每个产品最多 48 小时展示机会', TRUE), \
-             ($2, 10, 'Homepage top slot', '首页顶部定价位', TRUE), \
-             ($2, 20, 'Pricing badge', '定价认证标识', TRUE), \
-             ($3, 10, 'Homepage right slot', '首页右侧定价位', TRUE), \
-             ($3, 20, 'Pricing badge', '定价认证标识', TRUE)",
-        )
-        .persistent(false)
-        .bind(free_id)
-        .bind(top_id)
-        .bind(right_id)
-        .execute(pool)
-        .await;
+/**
+ * ensure_home_featured_history_table
+ * 自动创建首页精选入选记录表 home_featured_history（产品 id + 入选日期），供 FEATURED_COOLDOWN_DAYS 冷却使用。
+ */
+async fn ensure_home_featured_history_table(pool: &PgPool) -> Result<()> {
+    if HOME_FEATURED_HISTORY_TABLE_READY.load(Ordering::Relaxed) {
+        return Ok(());
     }
 
-    let _ = sqlx::query(
-        "UPDATE pricing_plans \
-         SET badge_zh = REPLACE(badge_zh, '赞助', '定价'), \
-             description_zh = REPLACE(description_zh, '赞助', '定价')",
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS home_featured_history ( \
+            product_id TEXT NOT NULL, \
+            day_key DATE NOT NULL, \
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(), \
+            PRIMARY KEY (product_id, day_key) \
+        )",
     )
     .persistent(false)
     .execute(pool)
-    .await;
-
-    let _ = sqlx::query(
-        "UPDATE pricing_plan_benefits \
-         SET text_zh = REPLACE(text_zh, '赞助', '定价')",
+    .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_home_featured_history_day_key ON home_featured_history(day_key)",
     )
     .persistent(false)
     .execute(pool)
-    .await;
+    .await?;
 
-    PRICING_TABLES_READY.store(true, Ordering::Relaxed);
+    HOME_FEATURED_HISTORY_TABLE_READY.store(true, Ordering::Relaxed);
     Ok(())
 }
 
 /**
- * ensure_pricing_text_migration
- * 将历史文案中的“赞助”批量迁移为“定价”，避免旧数据导致前台仍显示旧词。
+ * ensure_product_deletions_table
+ * 自动创建产品删除墓碑表 product_deletions，供增量同步接口向镜像方下发删除标记。
  */
-async fn ensure_pricing_text_migration(pool: &PgPool) -> Result<()> {
-    if PRICING_TEXT_MIGRATION_READY.load(Ordering::Relaxed) {
+async fn ensure_product_deletions_table(pool: &PgPool) -> Result<()> {
+    if PRODUCT_DELETIONS_TABLE_READY.load(Ordering::Relaxed) {
         return Ok(());
     }
 
     sqlx::query(
-        "UPDATE pricing_plans \
-         SET badge_en = REPLACE(badge_en, 'Sponsor', 'Pricing'), \
-             badge_zh = REPLACE(badge_zh, '赞助', '定价'), \
-             description_zh = REPLACE(description_zh, '赞助', '定价')",
+        "CREATE TABLE IF NOT EXISTS product_deletions ( \
+            product_id UUID PRIMARY KEY, \
+            deleted_at TIMESTAMPTZ NOT NULL DEFAULT NOW() \
+        )",
     )
     .persistent(false)
     .execute(pool)
     .await?;
-
     sqlx::query(
-        "UPDATE pricing_plan_benefits \
-         SET text_en = REPLACE(text_en, 'Sponsor', 'Pricing'), \
-             text_zh = REPLACE(text_zh, '赞助', '定价')",
+        "CREATE INDEX IF NOT EXISTS idx_product_deletions_deleted_at ON product_deletions(deleted_at)",
     )
     .persistent(false)
     .execute(pool)
     .await?;
 
-    PRICING_TEXT_MIGRATION_READY.store(true, Ordering::Relaxed);
+    PRODUCT_DELETIONS_TABLE_READY.store(true, Ordering::Relaxed);
     Ok(())
 }
 
 /**
- * ensure_sponsorship_tables
- * 自动创建 sponsorship_requests / sponsorship_grants 表与必要索引，避免旧库缺表导致接口失败。
+ * ensure_product_claims_table
+ * 自动创建产品认领表 product_claims；每个产品最多一条 approved 认领，同一认领人最多一条 pending 认领。
  */
-async fn ensure_sponsorship_tables(pool: &PgPool) -> Result<()> {
-    if SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed) {
+async fn ensure_product_claims_table(pool: &PgPool) -> Result<()> {
+    if PRODUCT_CLAIMS_TABLE_READY.load(Ordering::Relaxed) {
         return Ok(());
     }
 
     sqlx::query(
-        "CREATE TABLE IF NOT EXISTS sponsorship_grants ( \
+        "CREATE TABLE IF NOT EXISTS product_claims ( \
             id BIGSERIAL PRIMARY KEY, \
-            order_id UUID, \
             product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE, \
-            placement TEXT NOT NULL CHECK (placement IN ('home_top', 'home_right')), \
-            slot_index INT, \
-            starts_at TIMESTAMPTZ NOT NULL DEFAULT NOW(), \
-            ends_at TIMESTAMPTZ NOT NULL, \
-            source TEXT NOT NULL DEFAULT 'manual', \
-            amount_usd_cents INT, \
-            created_at TIMESTAMPTZ DEFAULT NOW() \
+            claimant_email TEXT NOT NULL, \
+            status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'approved', 'rejected')), \
+            note TEXT, \
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(), \
+            decided_at TIMESTAMPTZ \
         )",
     )
     .persistent(false)
     .execute(pool)
     .await?;
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS uniq_product_claims_approved \
+         ON product_claims(product_id) WHERE status = 'approved'",
+    )
+    .persistent(false)
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS uniq_product_claims_pending_claimant \
+         ON product_claims(product_id, claimant_email) WHERE status = 'pending'",
+    )
+    .persistent(false)
+    .execute(pool)
+    .await?;
 
-    sqlx::query("ALTER TABLE sponsorship_grants ADD COLUMN IF NOT EXISTS order_id UUID")
-        .persistent(false)
-        .execute(pool)
-        .await?;
+    PRODUCT_CLAIMS_TABLE_READY.store(true, Ordering::Relaxed);
+    Ok(())
+}
+static NEWSLETTER_TRACKING_TABLES_READY: AtomicBool = AtomicBool::new(false);
+
+/**
+ * ensure_newsletter_tracking_tables
+ * 自动创建周报打开/点击追踪表：newsletter_sends（每次发送一个 token）与 newsletter_events。
+ */
+async fn ensure_newsletter_tracking_tables(pool: &PgPool) -> Result<()> {
+    if NEWSLETTER_TRACKING_TABLES_READY.load(Ordering::Relaxed) {
+        return Ok(());
+    }
 
     sqlx::query(
-        "CREATE TABLE IF NOT EXISTS sponsorship_requests ( \
-            id BIGSERIAL PRIMARY KEY, \
+        "CREATE TABLE IF NOT EXISTS newsletter_sends ( \
+            token UUID PRIMARY KEY, \
             email TEXT NOT NULL, \
-            product_ref TEXT NOT NULL, \
-            placement TEXT NOT NULL CHECK (placement IN ('home_top', 'home_right')), \
-            slot_index INT, \
-            duration_days INT NOT NULL, \
-            note TEXT, \
-            status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'processed', 'rejected')), \
-            processed_grant_id BIGINT, \
-            created_at TIMESTAMPTZ DEFAULT NOW(), \
-            updated_at TIMESTAMPTZ DEFAULT NOW() \
+            week_key TEXT NOT NULL, \
+            link_hosts TEXT[] NOT NULL DEFAULT '{}', \
+            sent_at TIMESTAMPTZ DEFAULT NOW() \
         )",
     )
     .persistent(false)
     .execute(pool)
     .await?;
-
     sqlx::query(
-        "CREATE TABLE IF NOT EXISTS sponsorship_orders ( \
-            id UUID PRIMARY KEY, \
-            user_email TEXT NOT NULL, \
-            user_id TEXT, \
-            product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE, \
-            placement TEXT NOT NULL CHECK (placement IN ('home_top', 'home_right')), \
-            slot_index INT, \
-            requested_months INT NOT NULL, \
-            paid_months INT, \
-            status TEXT NOT NULL DEFAULT 'created' CHECK (status IN ('created', 'paid', 'canceled', 'failed')), \
-            provider TEXT NOT NULL DEFAULT 'manual', \
-            provider_checkout_id TEXT, \
-            provider_order_id TEXT, \
-            amount_usd_cents INT, \
-            pricing_plan_id UUID, \
-            pricing_plan_key TEXT, \
-            monthly_usd_cents INT, \
-            discount_percent_off INT, \
-            grant_id BIGINT, \
-            created_at TIMESTAMPTZ DEFAULT NOW(), \
-            updated_at TIMESTAMPTZ DEFAULT NOW() \
+        "ALTER TABLE newsletter_sends ADD COLUMN IF NOT EXISTS link_hosts TEXT[] NOT NULL DEFAULT '{}'",
+    )
+    .persistent(false)
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS newsletter_events ( \
+            id BIGSERIAL PRIMARY KEY, \
+            token UUID NOT NULL REFERENCES newsletter_sends(token) ON DELETE CASCADE, \
+            kind TEXT NOT NULL CHECK (kind IN ('open', 'click')), \
+            url TEXT, \
+            created_at TIMESTAMPTZ DEFAULT NOW() \
         )",
     )
     .persistent(false)
     .execute(pool)
     .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_newsletter_sends_week_key ON newsletter_sends(week_key)",
+    )
+    .persistent(false)
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_newsletter_events_token ON newsletter_events(token)",
+    )
+    .persistent(false)
+    .execute(pool)
+    .await?;
 
-    sqlx::query("ALTER TABLE sponsorship_orders ADD COLUMN IF NOT EXISTS pricing_plan_id UUID")
-        .persistent(false)
-        .execute(pool)
-        .await?;
-    sqlx::query("ALTER TABLE sponsorship_orders ADD COLUMN IF NOT EXISTS pricing_plan_key TEXT")
-        .persistent(false)
-        .execute(pool)
-        .await?;
-    sqlx::query("ALTER TABLE sponsorship_orders ADD COLUMN IF NOT EXISTS monthly_usd_cents INT")
-        .persistent(false)
-        .execute(pool)
-        .await?;
-    sqlx::query("ALTER TABLE sponsorship_orders ADD COLUMN IF NOT EXISTS discount_percent_off INT")
-        .persistent(false)
-        .execute(pool)
-        .await?;
+    NEWSLETTER_TRACKING_TABLES_READY.store(true, Ordering::Relaxed);
+    Ok(())
+}
 
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_sponsorship_grants_product_id ON sponsorship_grants(product_id)")
-        .persistent(false)
-        .execute(pool)
-        .await?;
+/**
+ * ensure_newsletter_format_column
+ * 自动补齐 newsletter_subscriptions.format 字段（html / text 发送偏好）。
+ */
+async fn ensure_newsletter_format_column(pool: &PgPool) -> Result<()> {
+    if NEWSLETTER_FORMAT_COLUMN_READY.load(Ordering::Relaxed) {
+        return Ok(());
+    }
     sqlx::query(
-        "CREATE UNIQUE INDEX IF NOT EXISTS idx_sponsorship_grants_order_id_unique \
-         ON sponsorship_grants(order_id) WHERE order_id IS NOT NULL",
+        "ALTER TABLE newsletter_subscriptions ADD COLUMN IF NOT EXISTS format TEXT NOT NULL DEFAULT 'html'",
     )
     .persistent(false)
     .execute(pool)
     .await?;
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_sponsorship_grants_placement ON sponsorship_grants(placement)")
-        .persistent(false)
-        .execute(pool)
-        .await?;
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_sponsorship_grants_active_range ON sponsorship_grants(starts_at, ends_at)")
-        .persistent(false)
-        .execute(pool)
-        .await?;
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_sponsorship_requests_status ON sponsorship_requests(status)")
-        .persistent(false)
-        .execute(pool)
-        .await?;
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_sponsorship_requests_created_at ON sponsorship_requests(created_at DESC)")
-        .persistent(false)
-        .execute(pool)
-        .await?;
+    NEWSLETTER_FORMAT_COLUMN_READY.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+static PRODUCT_SLUG_HISTORY_READY: AtomicBool = AtomicBool::new(false);
+
+/**
+ * ensure_product_slug_history_table
+ * 自动创建 product_slug_history 表，记录产品改名前的旧 slug，用于旧链接跳转。
+ */
+async fn ensure_product_slug_history_table(pool: &PgPool) -> Result<()> {
+    if PRODUCT_SLUG_HISTORY_READY.load(Ordering::Relaxed) {
+        return Ok(());
+    }
 
     sqlx::query(
-        "CREATE INDEX IF NOT EXISTS idx_sponsorship_orders_status ON sponsorship_orders(status)",
+        "CREATE TABLE IF NOT EXISTS product_slug_history ( \
+            slug TEXT PRIMARY KEY, \
+            product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE, \
+            created_at TIMESTAMPTZ DEFAULT NOW() \
+        )",
+    )
+    .persistent(false)
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_product_slug_history_product_id ON product_slug_history(product_id)",
     )
     .persistent(false)
     .execute(pool)
     .await?;
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_sponsorship_orders_user_email ON sponsorship_orders(user_email)")
-        .persistent(false)
-        .execute(pool)
-        .await?;
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_sponsorship_orders_created_at ON sponsorship_orders(created_at DESC)")
-        .persistent(false)
-        .execute(pool)
-        .await?;
 
-    SPONSORSHIP_TABLES_READY.store(true, Ordering::Relaxed);
+    PRODUCT_SLUG_HISTORY_READY.store(true, Ordering::Relaxed);
     Ok(())
 }
 
 /**
- * map_sponsorship_order_row_to_model
- * 将 sponsorship_orders 行映射为对外返回的 SponsorshipOrder。
+ * slugify_product_name
+ * 由产品名生成 slug：字母数字转小写保留，其余字符折叠为单个连字符。
  */
-fn map_sponsorship_order_row_to_model(mut row: SponsorshipOrderRow) -> SponsorshipOrder {
-    let id = row.id.to_string();
-    strip_nul_in_place(&mut row.user_email);
-    strip_nul_in_place_opt(&mut row.user_id);
-    strip_nul_in_place(&mut row.product_id);
-    strip_nul_in_place(&mut row.placement);
-    strip_nul_in_place_opt(&mut row.provider_checkout_id);
-    strip_nul_in_place_opt(&mut row.provider_order_id);
-    strip_nul_in_place(&mut row.status);
-    strip_nul_in_place(&mut row.provider);
-    SponsorshipOrder {
-        id,
-        user_email: row.user_email,
-        user_id: row.user_id,
-        product_id: row.product_id,
-        placement: row.placement,
-        slot_index: row.slot_index,
-        requested_months: row.requested_months,
-        paid_months: row.paid_months,
-        status: row.status,
-        provider: row.provider,
-        provider_checkout_id: row.provider_checkout_id,
-        provider_order_id: row.provider_order_id,
-        amount_usd_cents: row.amount_usd_cents,
-        grant_id: row.grant_id,
-        created_at: row.created_at,
-        updated_at: row.updated_at,
+fn slugify_product_name(name: &str) -> String {
+    let mut out = String::new();
+    let mut pending_dash = false;
+    for c in name.trim().chars() {
+        if c.is_alphanumeric() {
+            if pending_dash && !out.is_empty() {
+                out.push('-');
+            }
+            pending_dash = false;
+            out.extend(c.to_lowercase());
+        } else {
+            pending_dash = true;
+        }
     }
+    out
 }
 
-fn strip_nul_in_place(value: &mut String) {
-    if value.as_bytes().contains(&0) {
-        value.retain(|c| c != '\u{0000}');
+/**
+ * record_product_slug_change
+ * 产品改名导致 slug 变化时，把旧 slug 写入 product_slug_history（失败只记录日志，不影响更新）。
+ * 旧 slug 已被其他产品占用时保留原跳转，不允许后改名的产品接管。
+ */
+async fn record_product_slug_change(
+    pool: &PgPool,
+    product_id: &str,
+    old_name: &str,
+    new_name: &str,
+) {
+    let old_slug = slugify_product_name(old_name);
+    if old_slug.is_empty() || old_slug == slugify_product_name(new_name) {
+        return;
     }
-}
+    let Ok(product_uuid) = uuid::Uuid::parse_str(product_id.trim()) else {
+        return;
+    };
 
-fn strip_nul_in_place_opt(value: &mut Option<String>) {
-    if let Some(v) = value.as_mut() {
-        strip_nul_in_place(v);
-    }
-}
+    for _attempt_idx in 0..2 {
+        let attempt = sqlx::query(
+            "INSERT INTO product_slug_history (slug, product_id) VALUES ($1, $2) \
+             ON CONFLICT (slug) DO NOTHING",
+        )
+        .persistent(false)
+        .bind(old_slug.as_str())
+        .bind(product_uuid)
+        .execute(pool)
+        .await;
 
-fn strip_nul_str(value: &str) -> Cow<'_, str> {
-    if value.as_bytes().contains(&0) {
-        Cow::Owned(value.replace('\u{0000}', ""))
-    } else {
-        Cow::Borrowed(value)
+        match attempt {
+            Ok(_) => return,
+            Err(e) => {
+                let e: anyhow::Error = e.into();
+                if is_missing_relation_error(&e, "product_slug_history")
+                    && !PRODUCT_SLUG_HISTORY_READY.load(Ordering::Relaxed)
+                    && ensure_product_slug_history_table(pool).await.is_ok()
+                {
+                    continue;
+                }
+                log::warn!("Failed to record product slug history: {:?}", e);
+                return;
+            }
+        }
     }
 }
 
-/**
- * normalize_base_url
- * 规范化站点 base url（去掉末尾的 /），便于拼接 path。
- */
-fn normalize_base_url(raw: &str) -> String {
-    raw.trim().trim_end_matches('/').to_string()
-}
+static SPONSORSHIP_TABLES_READY: AtomicBool = AtomicBool::new(false);
 
-/**
- * build_product_detail_url
- * 生成产品详情页链接（前端路由：/products/[slug]，slug 使用产品 id）。
- */
-fn build_product_detail_url(frontend_base_url: &str, locale: &str, product_id: &str) -> String {
-    let base = normalize_base_url(frontend_base_url);
-    let locale = locale.trim();
-    let slug = urlencoding::encode(product_id);
-    if locale.is_empty() {
-        format!("{}/products/{}", base, slug)
-    } else {
-        format!("{}/{}/products/{}", base, urlencoding::encode(locale), slug)
-    }
-}
+static PRICING_TABLES_READY: AtomicBool = AtomicBool::new(false);
 
 /**
- * compute_admin_review_token
- * 计算管理员邮件审核 token（HMAC-SHA256 + URL-safe base64，无 padding）。
+ * ensure_pricing_tables
+ * 自动创建 pricing_plans / pricing_plan_benefits 表与必要索引，避免旧库缺表导致接口失败。
  */
-fn compute_admin_review_token(
-    product_id: &str,
-    action: &str,
-    exp_ts: i64,
-    secret: &str,
-) -> Result<String> {
-    type HmacSha256 = Hmac<Sha256>;
-    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
-        .map_err(|_| anyhow::anyhow!("Invalid ADMIN_REVIEW_TOKEN_SECRET"))?;
-    mac.update(product_id.as_bytes());
-    mac.update(b"|");
-    mac.update(action.as_bytes());
-    mac.update(b"|");
-    mac.update(exp_ts.to_string().as_bytes());
-    let bytes = mac.finalize().into_bytes();
-    Ok(general_purpose::URL_SAFE_NO_PAD.encode(bytes))
-}
+async fn ensure_pricing_tables(pool: &PgPool) -> Result<()> {
+    if PRICING_TABLES_READY.load(Ordering::Relaxed) {
+        return Ok(());
+    }
 
-/**
- * build_admin_review_url
- * 拼装管理员邮件一键审核链接（指向后端 /api/admin/review-product 接口）。
- */
-fn build_admin_review_url(
-    public_api_base_url: &str,
-    product_id: &str,
-    action: &str,
-    exp_ts: i64,
-    token: &str,
-) -> String {
-    let base = normalize_base_url(public_api_base_url);
-    let pid_q = urlencoding::encode(product_id);
-    let action_q = urlencoding::encode(action);
-    let exp_s = exp_ts.to_string();
-    let exp_q = urlencoding::encode(&exp_s);
-    let sig_q = urlencoding::encode(token);
-    format!(
-        "{}/api/admin/review-product?product_id={}&action={}&exp={}&sig={}",
-        base, pid_q, action_q, exp_q, sig_q
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS pricing_plans ( \
+            id UUID PRIMARY KEY, \
+            plan_key TEXT NOT NULL UNIQUE, \
+            placement TEXT CHECK (placement IN ('home_top', 'home_right')), \
+            monthly_usd_cents INT, \
+            title_en TEXT NOT NULL, \
+            title_zh TEXT NOT NULL, \
+            badge_en TEXT, \
+            badge_zh TEXT, \
+            description_en TEXT, \
+            description_zh TEXT, \
+            is_active BOOLEAN NOT NULL DEFAULT TRUE, \
+            is_default BOOLEAN NOT NULL DEFAULT FALSE, \
+            sort_order INT NOT NULL DEFAULT 0, \
+            campaign_active BOOLEAN NOT NULL DEFAULT FALSE, \
+            campaign_percent_off INT, \
+            campaign_title_en TEXT, \
+            campaign_title_zh TEXT, \
+            campaign_starts_at TIMESTAMPTZ, \
+            campaign_ends_at TIMESTAMPTZ, \
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(), \
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW() \
+        )",
     )
-}
-
-/**
- * compute_newsletter_unsubscribe_token
- * 计算退订 token（HMAC-SHA256 + URL-safe base64，无 padding）。
- */
-fn compute_newsletter_unsubscribe_token(email: &str, secret: &str) -> Result<String> {
-    type HmacSha256 = Hmac<Sha256>;
-    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
-        .map_err(|_| anyhow::anyhow!("Invalid NEWSLETTER_TOKEN_SECRET"))?;
-    mac.update(email.as_bytes());
-    let bytes = mac.finalize().into_bytes();
-    Ok(general_purpose::URL_SAFE_NO_PAD.encode(bytes))
-}
+    .persistent(false)
+    .execute(pool)
+    .await?;
 
-/**
- * build_newsletter_unsubscribe_url
- * 拼装退订链接（指向后端 /api/newsletter/unsubscribe 接口）。
- */
-fn build_newsletter_unsubscribe_url(public_api_base_url: &str, email: &str, token: &str) -> String {
-    let base = normalize_base_url(public_api_base_url);
-    let email_q = urlencoding::encode(email);
-    let token_q = urlencoding::encode(token);
-    format!(
-        "{}/api/newsletter/unsubscribe?email={}&token={}",
-        base, email_q, token_q
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS pricing_plan_benefits ( \
+            id BIGSERIAL PRIMARY KEY, \
+            plan_id UUID NOT NULL REFERENCES pricing_plans(id) ON DELETE CASCADE, \
+            sort_order INT NOT NULL DEFAULT 0, \
+            text_en TEXT NOT NULL, \
+            text_zh TEXT NOT NULL, \
+            available BOOLEAN NOT NULL DEFAULT TRUE \
+        )",
     )
-}
+    .persistent(false)
+    .execute(pool)
+    .await?;
 
-/**
- * build_weekly_newsletter_content
- * 构建周报邮件内容（中英双语 + 产品详情链接 + 退订链接）。
- */
-pub(crate) fn build_weekly_newsletter_content(
-    now: chrono::DateTime<chrono::Utc>,
-    since: chrono::DateTime<chrono::Utc>,
-    products: &[NewsletterTopProductRow],
-    frontend_base_url: &str,
-    unsubscribe_url: &str,
-) -> (String, String, String) {
-    let subject = format!("SoloForge Weekly ({})", now.format("%Y-%m-%d"));
-
-    let mut text = String::new();
-    text.push_str(&format!(
-        "SoloForge Weekly\nTime range: {} – {}\n\nTop 5 products this week:\n\n",
-        since.format("%Y-%m-%d"),
-        now.format("%Y-%m-%d")
-    ));
-
-    let mut html = String::new();
-    let range_en = format!("{} – {}", since.format("%Y-%m-%d"), now.format("%Y-%m-%d"));
-
-    html.push_str("<!doctype html><html><body style=\"margin:0;padding:0;background:#f6f7fb;\">");
-    html.push_str("<table role=\"presentation\" width=\"100%\" cellpadding=\"0\" cellspacing=\"0\" style=\"background:#f6f7fb;padding:24px 0;\">");
-    html.push_str("<tr><td align=\"center\" style=\"padding:0 12px;\">");
-    html.push_str("<table role=\"presentation\" width=\"600\" cellpadding=\"0\" cellspacing=\"0\" style=\"width:100%;max-width:600px;background:#ffffff;border:1px solid #eaecef;border-radius:16px;overflow:hidden;\">");
-
-    html.push_str("<tr><td style=\"padding:22px 24px;background:#111827;color:#ffffff;\">");
-    html.push_str("<div style=\"font-size:18px;font-weight:700;letter-spacing:0.2px;\">SoloForge Weekly</div>");
-    html.push_str(&format!(
-        "<div style=\"margin-top:6px;font-size:12px;opacity:0.9;\">{}</div>",
-        html_escape(&range_en)
-    ));
-    html.push_str("</td></tr>");
-
-    html.push_str("<tr><td style=\"padding:22px 24px;\">");
-    html.push_str("<div style=\"font-family:-apple-system,BlinkMacSystemFont,Segoe UI,Roboto,Helvetica,Arial,sans-serif;line-height:1.6;color:#111827;\">");
-
-    html.push_str("<h2 style=\"margin:0 0 6px 0;font-size:18px;\">SoloForge Weekly</h2>");
-    html.push_str(&format!(
-        "<div style=\"margin:0 0 14px 0;font-size:12px;color:#6b7280;\">Time range: {}</div>",
-        html_escape(&range_en)
-    ));
-    html.push_str("<div style=\"font-size:14px;font-weight:700;margin:0 0 12px 0;\">Top 5 products this week</div>");
-
-    for (idx, p) in products.iter().enumerate() {
-        let n = idx + 1;
-        let score = p.score;
-        let likes = p.weekly_likes;
-        let favorites = p.weekly_favorites;
-        let website = p.website.trim();
-        let detail_url_en = build_product_detail_url(frontend_base_url, "en", &p.id);
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_pricing_plans_active_sort ON pricing_plans(is_active, sort_order)",
+    )
+    .persistent(false)
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_pricing_plans_placement ON pricing_plans(placement)",
+    )
+    .persistent(false)
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_pricing_plan_benefits_plan_id_sort ON pricing_plan_benefits(plan_id, sort_order)",
+    )
+    .persistent(false)
+    .execute(pool)
+    .await?;
 
-        html.push_str("<table role=\"presentation\" width=\"100%\" cellpadding=\"0\" cellspacing=\"0\" style=\"margin:0 0 12px 0;border:1px solid #e5e7eb;border-radius:12px;overflow:hidden;\">");
-        html.push_str("<tr><td style=\"padding:14px 14px 12px 14px;\">");
+    let existing_count: i64 = sqlx::query_scalar("SELECT COUNT(1) FROM pricing_plans")
+        .persistent(false)
+        .fetch_one(pool)
+        .await
+        .unwrap_or(0);
+    if existing_count == 0 {
+        let free_id = uuid::Uuid::new_v4();
+        let top_id = uuid::Uuid::new_v4();
+        let right_id = uuid::Uuid::new_v4();
 
-        html.push_str("<div style=\"display:block;\">");
-        html.push_str(&format!(
-            "<span style=\"display:inline-block;min-width:22px;height:22px;line-height:22px;text-align:center;border-radius:999px;background:#eef2ff;color:#3730a3;font-size:12px;font-weight:700;margin-right:8px;\">{}</span>",
-            n
-        ));
-        html.push_str(&format!(
-            "<span style=\"font-size:15px;font-weight:800;\">{}</span>",
-            html_escape(&p.name)
-        ));
-        html.push_str("</div>");
+        let _ = sqlx::query(
+            "INSERT INTO pricing_plans \
+             (id, plan_key, placement, monthly_usd_cents, title_en, title_zh, badge_en, badge_zh, description_en, description_zh, is_active, is_default, sort_order) \
+             VALUES \
+             ($1, 'free', NULL, NULL, 'Free', 'Free', 'Limited', '限量', 'Limited free exposure opportunity.', '限量免费曝光机会。', TRUE, FALSE, 10), \
+             ($2, 'home_top', 'home_top', 1000, 'Pro · Top', 'Pro · 顶部', 'Pricing', '定价', 'Highest exposure in homepage top module.', '展示在首页顶部定价模块（最高曝光）。', TRUE, TRUE, 20), \
+             ($3, 'home_right', 'home_right', 500, 'Pro · Right', 'Pro · 右侧', 'Pricing', '定价', 'Stable exposure in homepage right module.', '展示在首页右侧定价模块（稳定曝光）。', TRUE, FALSE, 30)",
+        )
+        .persistent(false)
+        .bind(free_id)
+        .bind(top_id)
+        .bind(right_id)
+        .execute(pool)
+        .await;
 
-        if !p.slogan.trim().is_empty() {
-            html.push_str(&format!(
-                "<div style=\"margin-top:4px;font-size:13px;color:#4b5563;\">{}</div>",
-                html_escape(&p.slogan)
-            ));
-        }
+        let _ = sqlx::query(
+            "INSERT INTO pricing_plan_benefits (plan_id, sort_order, text_en, text_zh, available) VALUES \
+             ($1, 10, 'Eligible for random free slots', '每天随机出现在免费位', TRUE), \
+             ($1, 20, 'Up to 48 hours exposure', '每个产品最多 48 小时展示机会', TRUE), \
+             ($2, 10, 'Homepage top slot', '首页顶部定价位', TRUE), \
+             ($2, 20, 'Pricing badge', '定价认证标识', TRUE), \
+             ($3, 10, 'Homepage right slot', '首页右侧定价位', TRUE), \
+             ($3, 20, 'Pricing badge', '定价认证标识', TRUE)",
+        )
+        .persistent(false)
+        .bind(free_id)
+        .bind(top_id)
+        .bind(right_id)
+        .execute(pool)
+        .await;
+    }
 
-        text.push_str(&format!(
-            "{}. {} - {}\nDetails: {}\nWebsite: {}\nWeekly score: {} (likes {} / favorites {})\nMaker: {} ({})\n\n",
-            n,
-            p.name,
-            p.slogan,
-            detail_url_en,
-            website,
-            score,
-            likes,
-            favorites,
-            p.maker_name,
-            p.maker_email
-        ));
+    let _ = sqlx::query(
+        "UPDATE pricing_plans \
+         SET badge_zh = REPLACE(badge_zh, '赞助', '定价'), \
+             description_zh = REPLACE(description_zh, '赞助', '定价')",
+    )
+    .persistent(false)
+    .execute(pool)
+    .await;
 
-        html.push_str("<div style=\"margin-top:10px;\">");
-        html.push_str(&format!(
-            "<a href=\"{}\" target=\"_blank\" rel=\"noreferrer\" style=\"display:inline-block;padding:8px 12px;margin:0 8px 8px 0;background:#111827;color:#ffffff;text-decoration:none;border-radius:10px;font-size:12px;font-weight:700;\">View details</a>",
-            html_attr_escape(&detail_url_en)
-        ));
-        if !website.is_empty() {
-            html.push_str(&format!(
-                "<a href=\"{}\" target=\"_blank\" rel=\"noreferrer\" style=\"display:inline-block;padding:8px 12px;margin:0 8px 8px 0;background:#ffffff;color:#111827;text-decoration:none;border:1px solid #e5e7eb;border-radius:10px;font-size:12px;font-weight:700;\">Visit website</a>",
-                html_attr_escape(website)
-            ));
-        }
-        html.push_str("</div>");
+    let _ = sqlx::query(
+        "UPDATE pricing_plan_benefits \
+         SET text_zh = REPLACE(text_zh, '赞助', '定价')",
+    )
+    .persistent(false)
+    .execute(pool)
+    .await;
 
-        html.push_str(&format!(
-            "<div style=\"margin-top:6px;font-size:12px;color:#6b7280;\">Weekly score <strong style=\"color:#111827;\">{}</strong> · likes {} · favorites {}</div>",
-            score, likes, favorites
-        ));
-        html.push_str(&format!(
-            "<div style=\"margin-top:4px;font-size:12px;color:#6b7280;\">Maker: {} ({})</div>",
-            html_escape(&p.maker_name),
-            html_escape(&p.maker_email)
-        ));
+    PRICING_TABLES_READY.store(true, Ordering::Relaxed);
+    Ok(())
+}
 
-        html.push_str("</td></tr></table>");
+/**
+ * ensure_pricing_text_migration
+ * 将历史文案中的“赞助”批量迁移为“定价”，避免旧数据导致前台仍显示旧词。
+ */
+async fn ensure_pricing_text_migration(pool: &PgPool) -> Result<()> {
+    if PRICING_TEXT_MIGRATION_READY.load(Ordering::Relaxed) {
+        return Ok(());
     }
 
-    text.push_str(&format!("Unsubscribe: {}\n", unsubscribe_url));
-
-    html.push_str(&format!(
-        "<div style=\"margin-top:14px;padding-top:14px;border-top:1px solid #e5e7eb;\"><div style=\"font-size:12px;color:#6b7280;\">Unsubscribe: <a href=\"{}\" target=\"_blank\" rel=\"noreferrer\" style=\"color:#111827;text-decoration:underline;\">click here</a></div></div>",
-        html_attr_escape(unsubscribe_url)
-    ));
-    html.push_str("<div style=\"margin-top:16px;font-size:11px;color:#9ca3af;\">You are receiving this email because you subscribed to the SoloForge weekly brief.</div>");
-    html.push_str("</div></td></tr>");
-    html.push_str("</table></td></tr></table>");
-    html.push_str("</body></html>");
+    sqlx::query(
+        "UPDATE pricing_plans \
+         SET badge_en = REPLACE(badge_en, 'Sponsor', 'Pricing'), \
+             badge_zh = REPLACE(badge_zh, '赞助', '定价'), \
+             description_zh = REPLACE(description_zh, '赞助', '定价')",
+    )
+    .persistent(false)
+    .execute(pool)
+    .await?;
 
-    (subject, html, text)
-}
+    sqlx::query(
+        "UPDATE pricing_plan_benefits \
+         SET text_en = REPLACE(text_en, 'Sponsor', 'Pricing'), \
+             text_zh = REPLACE(text_zh, '赞助', '定价')",
+    )
+    .persistent(false)
+    .execute(pool)
+    .await?;
 
-fn html_escape(raw: &str) -> String {
-    raw.replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-        .replace('"', "&quot;")
-        .replace('\'', "&#39;")
+    PRICING_TEXT_MIGRATION_READY.store(true, Ordering::Relaxed);
+    Ok(())
 }
 
-fn html_attr_escape(raw: &str) -> String {
-    html_escape(raw).replace(['\n', '\r'], " ")
-}
+static SPONSORSHIP_GRANTS_ACTIVE_INDEX_READY: AtomicBool = AtomicBool::new(false);
 
-async fn send_email_resend(
-    client: &Client,
-    api_key: &str,
-    from: &str,
-    to: &str,
-    subject: &str,
-    html: &str,
-    text: &str,
-) -> Result<()> {
-    let payload = serde_json::json!({
-        "from": from,
-        "to": [to],
-        "subject": subject,
-        "html": html,
-        "text": text
-    });
+const SPONSORSHIP_GRANTS_PLACEMENT_ACTIVE_INDEX_SQL: &str =
+    "CREATE INDEX IF NOT EXISTS idx_sponsorship_grants_placement_active_range \
+     ON sponsorship_grants(placement, starts_at, ends_at)";
 
-    let resp = client
-        .post("https://api.resend.com/emails")
-        .bearer_auth(api_key)
-        .json(&payload)
-        .send()
-        .await?;
+/**
+ * active_sponsorship_grants_sql
+ * 查询某展示位在 $2 时刻生效的 grants（$1 placement，with_language 时 $3 为产品语言），供首页与索引测试共用。
+ */
+fn active_sponsorship_grants_sql(with_language: bool) -> String {
+    format!(
+        "SELECT s.id, p.id::text as product_id, s.slot_index, s.ends_at \
+         FROM sponsorship_grants s \
+         JOIN products p ON p.id = s.product_id \
+         WHERE s.placement = $1 AND {} AND {}{} \
+         ORDER BY s.slot_index NULLS LAST, s.created_at ASC, p.created_at DESC, p.id ASC",
+        SPONSORSHIP_GRANT_ACTIVE_AT_PARAM,
+        product_visibility_clause("p"),
+        if with_language {
+            " AND p.language = $3"
+        } else {
+            ""
+        }
+    )
+}
 
-    if resp.status().is_success() {
-        return Ok(());
+/**
+ * ensure_sponsorship_grants_active_index
+ * 懒创建 (placement, starts_at, ends_at) 复合索引，供首页查询生效 grants 走范围扫描；失败仅记录日志。
+ */
+async fn ensure_sponsorship_grants_active_index(pool: &PgPool) {
+    if SPONSORSHIP_GRANTS_ACTIVE_INDEX_READY.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    if let Err(e) = sqlx::query(SPONSORSHIP_GRANTS_PLACEMENT_ACTIVE_INDEX_SQL)
+        .persistent(false)
+        .execute(pool)
+        .await
+    {
+        log::warn!("Failed to create sponsorship grants active index: {:?}", e);
+        SPONSORSHIP_GRANTS_ACTIVE_INDEX_READY.store(false, Ordering::Relaxed);
     }
-
-    let status = resp.status();
-    let body = resp.text().await.unwrap_or_default();
-    Err(anyhow::anyhow!("Resend error: {} {}", status, body))
 }
 
+static SPONSORSHIP_REQUEST_NOTIFY_READY: AtomicBool = AtomicBool::new(false);
+
 /**
- * build_admin_product_submission_email_content
- * 构建“产品提交待审核”的管理员通知邮件内容（包含一键通过/拒绝链接）。
+ * ensure_sponsorship_request_notify_column
+ * 自动补齐 sponsorship_requests.admin_notified_at（新申请管理员通知的发送标记，保证每个申请只通知一次）。
  */
-fn build_admin_product_submission_email_content(
-    product: &Product,
-    frontend_base_url: &str,
-    public_api_base_url: &str,
-    token_secret: &str,
-) -> (String, String, String) {
-    let subject = format!("New product submitted: {}", product.name.trim());
+async fn ensure_sponsorship_request_notify_column(pool: &PgPool) -> Result<()> {
+    if SPONSORSHIP_REQUEST_NOTIFY_READY.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    ensure_sponsorship_tables(pool).await?;
+    sqlx::query(
+        "ALTER TABLE sponsorship_requests ADD COLUMN IF NOT EXISTS admin_notified_at TIMESTAMPTZ",
+    )
+    .persistent(false)
+    .execute(pool)
+    .await?;
+    SPONSORSHIP_REQUEST_NOTIFY_READY.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/**
+ * ensure_sponsorship_tables
+ * 自动创建 sponsorship_requests / sponsorship_grants 表与必要索引，避免旧库缺表导致接口失败。
+ */
+async fn ensure_sponsorship_tables(pool: &PgPool) -> Result<()> {
+    if SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS sponsorship_grants ( \
+            id BIGSERIAL PRIMARY KEY, \
+            order_id UUID, \
+            product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE, \
+            placement TEXT NOT NULL CHECK (placement IN ('home_top', 'home_right')), \
+            slot_index INT, \
+            starts_at TIMESTAMPTZ NOT NULL DEFAULT NOW(), \
+            ends_at TIMESTAMPTZ NOT NULL, \
+            source TEXT NOT NULL DEFAULT 'manual', \
+            amount_usd_cents INT, \
+            created_at TIMESTAMPTZ DEFAULT NOW() \
+        )",
+    )
+    .persistent(false)
+    .execute(pool)
+    .await?;
+
+    sqlx::query("ALTER TABLE sponsorship_grants ADD COLUMN IF NOT EXISTS order_id UUID")
+        .persistent(false)
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS sponsorship_requests ( \
+            id BIGSERIAL PRIMARY KEY, \
+            email TEXT NOT NULL, \
+            product_ref TEXT NOT NULL, \
+            placement TEXT NOT NULL CHECK (placement IN ('home_top', 'home_right')), \
+            slot_index INT, \
+            duration_days INT NOT NULL, \
+            note TEXT, \
+            status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'processed', 'rejected')), \
+            processed_grant_id BIGINT, \
+            created_at TIMESTAMPTZ DEFAULT NOW(), \
+            updated_at TIMESTAMPTZ DEFAULT NOW() \
+        )",
+    )
+    .persistent(false)
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS sponsorship_orders ( \
+            id UUID PRIMARY KEY, \
+            user_email TEXT NOT NULL, \
+            user_id TEXT, \
+            product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE, \
+            placement TEXT NOT NULL CHECK (placement IN ('home_top', 'home_right')), \
+            slot_index INT, \
+            requested_months INT NOT NULL, \
+            paid_months INT, \
+            status TEXT NOT NULL DEFAULT 'created' CHECK (status IN ('created', 'paid', 'canceled', 'failed')), \
+            provider TEXT NOT NULL DEFAULT 'manual', \
+            provider_checkout_id TEXT, \
+            provider_order_id TEXT, \
+            amount_usd_cents INT, \
+            pricing_plan_id UUID, \
+            pricing_plan_key TEXT, \
+            monthly_usd_cents INT, \
+            discount_percent_off INT, \
+            grant_id BIGINT, \
+            receipt_sent_at TIMESTAMPTZ, \
+            created_at TIMESTAMPTZ DEFAULT NOW(), \
+            updated_at TIMESTAMPTZ DEFAULT NOW() \
+        )",
+    )
+    .persistent(false)
+    .execute(pool)
+    .await?;
+
+    sqlx::query("ALTER TABLE sponsorship_orders ADD COLUMN IF NOT EXISTS pricing_plan_id UUID")
+        .persistent(false)
+        .execute(pool)
+        .await?;
+    sqlx::query("ALTER TABLE sponsorship_orders ADD COLUMN IF NOT EXISTS pricing_plan_key TEXT")
+        .persistent(false)
+        .execute(pool)
+        .await?;
+    sqlx::query("ALTER TABLE sponsorship_orders ADD COLUMN IF NOT EXISTS monthly_usd_cents INT")
+        .persistent(false)
+        .execute(pool)
+        .await?;
+    sqlx::query("ALTER TABLE sponsorship_orders ADD COLUMN IF NOT EXISTS discount_percent_off INT")
+        .persistent(false)
+        .execute(pool)
+        .await?;
+    sqlx::query(
+        "ALTER TABLE sponsorship_orders ADD COLUMN IF NOT EXISTS receipt_sent_at TIMESTAMPTZ",
+    )
+    .persistent(false)
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_sponsorship_grants_product_id ON sponsorship_grants(product_id)")
+        .persistent(false)
+        .execute(pool)
+        .await?;
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_sponsorship_grants_order_id_unique \
+         ON sponsorship_grants(order_id) WHERE order_id IS NOT NULL",
+    )
+    .persistent(false)
+    .execute(pool)
+    .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_sponsorship_grants_placement ON sponsorship_grants(placement)")
+        .persistent(false)
+        .execute(pool)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_sponsorship_grants_active_range ON sponsorship_grants(starts_at, ends_at)")
+        .persistent(false)
+        .execute(pool)
+        .await?;
+    sqlx::query(SPONSORSHIP_GRANTS_PLACEMENT_ACTIVE_INDEX_SQL)
+        .persistent(false)
+        .execute(pool)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_sponsorship_requests_status ON sponsorship_requests(status)")
+        .persistent(false)
+        .execute(pool)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_sponsorship_requests_created_at ON sponsorship_requests(created_at DESC)")
+        .persistent(false)
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_sponsorship_orders_status ON sponsorship_orders(status)",
+    )
+    .persistent(false)
+    .execute(pool)
+    .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_sponsorship_orders_user_email ON sponsorship_orders(user_email)")
+        .persistent(false)
+        .execute(pool)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_sponsorship_orders_created_at ON sponsorship_orders(created_at DESC)")
+        .persistent(false)
+        .execute(pool)
+        .await?;
+
+    SPONSORSHIP_TABLES_READY.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/**
+ * SchemaObject
+ * 懒迁移预期创建的数据库对象，用于迁移状态检查。
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SchemaObject {
+    Table(&'static str),
+    Column(&'static str, &'static str),
+    Index(&'static str),
+}
+
+impl std::fmt::Display for SchemaObject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaObject::Table(t) => write!(f, "table {}", t),
+            SchemaObject::Column(t, c) => write!(f, "column {}.{}", t, c),
+            SchemaObject::Index(i) => write!(f, "index {}", i),
+        }
+    }
+}
+
+struct MigrationSpec {
+    name: &'static str,
+    ready: &'static AtomicBool,
+    expects: &'static [SchemaObject],
+}
+
+/**
+ * MIGRATIONS
+ * 全部懒迁移及其 *_READY 标记与预期对象（唯一出处）；新增 ensure_* 时需同步登记，并在 run_all_migrations 中调用。
+ */
+static MIGRATIONS: &[MigrationSpec] = &[
+    MigrationSpec {
+        name: "engagement_unique_indexes",
+        ready: &ENGAGEMENT_UNIQUE_INDEXES_READY,
+        expects: &[
+            SchemaObject::Index("idx_product_likes_product_user_unique"),
+            SchemaObject::Index("idx_product_favorites_product_user_unique"),
+        ],
+    },
+    MigrationSpec {
+        name: "engagement_ip_hash_columns",
+        ready: &ENGAGEMENT_IP_HASH_READY,
+        expects: &[
+            SchemaObject::Column("product_likes", "ip_hash"),
+            SchemaObject::Column("product_favorites", "ip_hash"),
+        ],
+    },
+    MigrationSpec {
+        name: "products_bumped_at_column",
+        ready: &PRODUCTS_BUMPED_AT_READY,
+        expects: &[SchemaObject::Column("products", "bumped_at")],
+    },
+    MigrationSpec {
+        name: "products_rejection_reason_column",
+        ready: &PRODUCTS_REJECTION_REASON_READY,
+        expects: &[SchemaObject::Column("products", "rejection_reason")],
+    },
+    MigrationSpec {
+        name: "lower_email_indexes",
+        ready: &LOWER_EMAIL_INDEXES_READY,
+        expects: &[
+            SchemaObject::Index("idx_developers_lower_email"),
+            SchemaObject::Index("idx_products_lower_maker_email"),
+        ],
+    },
+    MigrationSpec {
+        name: "developers_sponsor_columns",
+        ready: &DEVELOPERS_SPONSOR_COLUMNS_READY,
+        expects: &[
+            SchemaObject::Column("developers", "sponsor_role"),
+            SchemaObject::Column("developers", "sponsor_verified"),
+        ],
+    },
+    MigrationSpec {
+        name: "outgoing_webhook_tables",
+        ready: &OUTGOING_WEBHOOK_TABLES_READY,
+        expects: &[
+            SchemaObject::Table("outgoing_webhooks"),
+            SchemaObject::Table("outgoing_webhook_deliveries"),
+            SchemaObject::Index("idx_outgoing_webhook_deliveries_due"),
+        ],
+    },
+    MigrationSpec {
+        name: "admin_audit_log_table",
+        ready: &ADMIN_AUDIT_LOG_TABLE_READY,
+        expects: &[
+            SchemaObject::Table("admin_audit_log"),
+            SchemaObject::Index("idx_admin_audit_log_created_at"),
+            SchemaObject::Index("idx_admin_audit_log_entity"),
+        ],
+    },
+    MigrationSpec {
+        name: "home_featured_history_table",
+        ready: &HOME_FEATURED_HISTORY_TABLE_READY,
+        expects: &[
+            SchemaObject::Table("home_featured_history"),
+            SchemaObject::Index("idx_home_featured_history_day_key"),
+        ],
+    },
+    MigrationSpec {
+        name: "product_deletions_table",
+        ready: &PRODUCT_DELETIONS_TABLE_READY,
+        expects: &[
+            SchemaObject::Table("product_deletions"),
+            SchemaObject::Index("idx_product_deletions_deleted_at"),
+        ],
+    },
+    MigrationSpec {
+        name: "product_claims_table",
+        ready: &PRODUCT_CLAIMS_TABLE_READY,
+        expects: &[
+            SchemaObject::Table("product_claims"),
+            SchemaObject::Index("uniq_product_claims_approved"),
+            SchemaObject::Index("uniq_product_claims_pending_claimant"),
+        ],
+    },
+    MigrationSpec {
+        name: "newsletter_tracking_tables",
+        ready: &NEWSLETTER_TRACKING_TABLES_READY,
+        expects: &[
+            SchemaObject::Table("newsletter_sends"),
+            SchemaObject::Column("newsletter_sends", "link_hosts"),
+            SchemaObject::Table("newsletter_events"),
+            SchemaObject::Index("idx_newsletter_sends_week_key"),
+            SchemaObject::Index("idx_newsletter_events_token"),
+        ],
+    },
+    MigrationSpec {
+        name: "newsletter_format_column",
+        ready: &NEWSLETTER_FORMAT_COLUMN_READY,
+        expects: &[SchemaObject::Column("newsletter_subscriptions", "format")],
+    },
+    MigrationSpec {
+        name: "product_slug_history_table",
+        ready: &PRODUCT_SLUG_HISTORY_READY,
+        expects: &[
+            SchemaObject::Table("product_slug_history"),
+            SchemaObject::Index("idx_product_slug_history_product_id"),
+        ],
+    },
+    MigrationSpec {
+        name: "pricing_tables",
+        ready: &PRICING_TABLES_READY,
+        expects: &[
+            SchemaObject::Table("pricing_plans"),
+            SchemaObject::Table("pricing_plan_benefits"),
+            SchemaObject::Index("idx_pricing_plans_active_sort"),
+            SchemaObject::Index("idx_pricing_plans_placement"),
+            SchemaObject::Index("idx_pricing_plan_benefits_plan_id_sort"),
+        ],
+    },
+    MigrationSpec {
+        name: "pricing_text_migration",
+        ready: &PRICING_TEXT_MIGRATION_READY,
+        expects: &[],
+    },
+    MigrationSpec {
+        name: "sponsorship_tables",
+        ready: &SPONSORSHIP_TABLES_READY,
+        expects: &[
+            SchemaObject::Table("sponsorship_grants"),
+            SchemaObject::Table("sponsorship_requests"),
+            SchemaObject::Table("sponsorship_orders"),
+            SchemaObject::Column("sponsorship_grants", "order_id"),
+            SchemaObject::Column("sponsorship_orders", "pricing_plan_id"),
+            SchemaObject::Column("sponsorship_orders", "pricing_plan_key"),
+            SchemaObject::Column("sponsorship_orders", "monthly_usd_cents"),
+            SchemaObject::Column("sponsorship_orders", "discount_percent_off"),
+            SchemaObject::Column("sponsorship_orders", "receipt_sent_at"),
+            SchemaObject::Index("idx_sponsorship_grants_product_id"),
+            SchemaObject::Index("idx_sponsorship_grants_order_id_unique"),
+            SchemaObject::Index("idx_sponsorship_grants_placement"),
+            SchemaObject::Index("idx_sponsorship_grants_active_range"),
+            SchemaObject::Index("idx_sponsorship_requests_status"),
+            SchemaObject::Index("idx_sponsorship_requests_created_at"),
+            SchemaObject::Index("idx_sponsorship_orders_status"),
+            SchemaObject::Index("idx_sponsorship_orders_user_email"),
+            SchemaObject::Index("idx_sponsorship_orders_created_at"),
+        ],
+    },
+    MigrationSpec {
+        name: "sponsorship_grants_active_index",
+        ready: &SPONSORSHIP_GRANTS_ACTIVE_INDEX_READY,
+        expects: &[SchemaObject::Index(
+            "idx_sponsorship_grants_placement_active_range",
+        )],
+    },
+    MigrationSpec {
+        name: "sponsorship_request_notify_column",
+        ready: &SPONSORSHIP_REQUEST_NOTIFY_READY,
+        expects: &[SchemaObject::Column(
+            "sponsorship_requests",
+            "admin_notified_at",
+        )],
+    },
+];
+
+/**
+ * missing_schema_objects
+ * 对照 information_schema / pg_indexes 的查询结果，列出预期但不存在的对象。
+ */
+fn missing_schema_objects(
+    expects: &[SchemaObject],
+    tables: &HashSet<String>,
+    columns: &HashSet<(String, String)>,
+    indexes: &HashSet<String>,
+) -> Vec<String> {
+    expects
+        .iter()
+        .filter(|obj| match obj {
+            SchemaObject::Table(t) => !tables.contains(*t),
+            SchemaObject::Column(t, c) => !columns.contains(&(t.to_string(), c.to_string())),
+            SchemaObject::Index(i) => !indexes.contains(*i),
+        })
+        .map(|obj| obj.to_string())
+        .collect()
+}
+
+/**
+ * run_migration
+ * 按名称执行对应的 ensure_*（名称与 MIGRATIONS 一致）；返回 () 的 ensure 失败时只记日志，以 READY 标记反映结果。
+ */
+async fn run_migration(pool: &PgPool, name: &str) -> Result<()> {
+    match name {
+        "engagement_unique_indexes" => ensure_engagement_unique_indexes(pool).await,
+        "engagement_ip_hash_columns" => ensure_engagement_ip_hash_columns(pool).await,
+        "products_bumped_at_column" => ensure_products_bumped_at_column(pool).await,
+        "products_rejection_reason_column" => ensure_products_rejection_reason_column(pool).await,
+        "lower_email_indexes" => {
+            ensure_lower_email_indexes(pool).await;
+            Ok(())
+        }
+        "developers_sponsor_columns" => ensure_developers_sponsor_columns(pool).await,
+        "outgoing_webhook_tables" => ensure_outgoing_webhook_tables(pool).await,
+        "admin_audit_log_table" => ensure_admin_audit_log_table(pool).await,
+        "home_featured_history_table" => ensure_home_featured_history_table(pool).await,
+        "product_deletions_table" => ensure_product_deletions_table(pool).await,
+        "product_claims_table" => ensure_product_claims_table(pool).await,
+        "newsletter_tracking_tables" => ensure_newsletter_tracking_tables(pool).await,
+        "newsletter_format_column" => ensure_newsletter_format_column(pool).await,
+        "product_slug_history_table" => ensure_product_slug_history_table(pool).await,
+        "pricing_tables" => ensure_pricing_tables(pool).await,
+        "pricing_text_migration" => ensure_pricing_text_migration(pool).await,
+        "sponsorship_tables" => ensure_sponsorship_tables(pool).await,
+        "sponsorship_grants_active_index" => {
+            ensure_sponsorship_grants_active_index(pool).await;
+            Ok(())
+        }
+        "sponsorship_request_notify_column" => ensure_sponsorship_request_notify_column(pool).await,
+        other => Err(anyhow::anyhow!("Unknown migration: {}", other)),
+    }
+}
+
+/**
+ * map_sponsorship_order_row_to_model
+ * 将 sponsorship_orders 行映射为对外返回的 SponsorshipOrder。
+ */
+fn map_sponsorship_order_row_to_model(mut row: SponsorshipOrderRow) -> SponsorshipOrder {
+    let id = row.id.to_string();
+    strip_nul_in_place(&mut row.user_email);
+    strip_nul_in_place_opt(&mut row.user_id);
+    strip_nul_in_place(&mut row.product_id);
+    strip_nul_in_place(&mut row.placement);
+    strip_nul_in_place_opt(&mut row.provider_checkout_id);
+    strip_nul_in_place_opt(&mut row.provider_order_id);
+    strip_nul_in_place(&mut row.status);
+    strip_nul_in_place(&mut row.provider);
+    SponsorshipOrder {
+        id,
+        user_email: row.user_email,
+        user_id: row.user_id,
+        product_id: row.product_id,
+        placement: row.placement,
+        slot_index: row.slot_index,
+        requested_months: row.requested_months,
+        paid_months: row.paid_months,
+        status: row.status,
+        provider: row.provider,
+        provider_checkout_id: row.provider_checkout_id,
+        provider_order_id: row.provider_order_id,
+        amount_usd_cents: row.amount_usd_cents,
+        grant_id: row.grant_id,
+        created_at: row.created_at,
+        updated_at: row.updated_at,
+    }
+}
+
+/**
+ * compute_order_amount
+ * 按月单价 * 月数计算原价，再扣除百分比折扣得到实付金额（美分）；结算与人工标记已支付共用同一套公式。
+ */
+fn compute_order_amount(
+    monthly_usd_cents: Option<i32>,
+    discount_percent_off: Option<i32>,
+    months: i32,
+) -> i32 {
+    let unit = monthly_usd_cents.unwrap_or(0).max(0);
+    let pct = discount_percent_off.unwrap_or(0).clamp(0, 100);
+    let gross = (unit as i64).saturating_mul(months as i64);
+    let discount = gross.saturating_mul(pct as i64) / 100;
+    let net = gross.saturating_sub(discount).max(0);
+    i32::try_from(net.min(i32::MAX as i64)).unwrap_or(i32::MAX)
+}
+
+/**
+ * days_per_month
+ * 赞助时长的月份换算天数（DAYS_PER_MONTH，默认 30，范围 28~31），申请与订单两条授权路径共用。
+ */
+pub(crate) fn days_per_month() -> i32 {
+    env::var("DAYS_PER_MONTH")
+        .ok()
+        .and_then(|v| v.trim().parse::<i32>().ok())
+        .unwrap_or(30)
+        .clamp(28, 31)
+}
+
+/**
+ * months_to_days
+ * 将月数换算为规范的天数（至少 1 天）；所有授权均以天数存储。
+ */
+pub(crate) fn months_to_days(months: i32, days_per_month: i32) -> i32 {
+    months.saturating_mul(days_per_month).max(1)
+}
+
+/**
+ * random_sort_seed
+ * sort=random 的排序种子：优先使用请求的 seed（最多 64 字符），缺省为当天 UTC 日期；
+ * 同一 seed 下按 md5(id || seed) 排序，翻页结果一致，跨天自然变化。
+ */
+pub(crate) fn random_sort_seed(seed: Option<&str>, today: chrono::NaiveDate) -> String {
+    seed.map(|v| strip_nul_str(v.trim()).chars().take(64).collect::<String>())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| today.format("%Y-%m-%d").to_string())
+}
+
+/**
+ * MAX_SPONSORSHIP_MONTHS
+ * 单次赞助申请/订单允许购买的最大月数，通过 capabilities 接口对前端公开。
+ */
+pub const MAX_SPONSORSHIP_MONTHS: i32 = 24;
+
+/**
+ * RequestedMonthsOutOfRangeError
+ * 请求的赞助月数不在 1..=MAX_SPONSORSHIP_MONTHS 之内；接口层直接拒绝，不再静默夹取。
+ */
+#[derive(Debug)]
+pub struct RequestedMonthsOutOfRangeError {
+    pub requested: i32,
+    pub max: i32,
+}
+
+impl RequestedMonthsOutOfRangeError {
+    pub fn localized_message(&self, lang: &str) -> String {
+        if lang.starts_with("zh") {
+            format!(
+                "赞助时长必须为 1~{} 个月（当前 {}）",
+                self.max, self.requested
+            )
+        } else {
+            format!(
+                "Sponsorship duration must be between 1 and {} months (got {})",
+                self.max, self.requested
+            )
+        }
+    }
+}
+
+impl std::fmt::Display for RequestedMonthsOutOfRangeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "requested_months {} is out of range 1..={}",
+            self.requested, self.max
+        )
+    }
+}
+
+impl std::error::Error for RequestedMonthsOutOfRangeError {}
+
+/**
+ * max_sponsorship_duration_days
+ * 赞助展示天数上限（MAX_SPONSORSHIP_MONTHS 个月折算），申请、审批与处理路径共用。
+ */
+pub(crate) fn max_sponsorship_duration_days() -> i32 {
+    months_to_days(MAX_SPONSORSHIP_MONTHS, days_per_month())
+}
+
+/**
+ * check_requested_months
+ * 校验赞助月数位于 1..=MAX_SPONSORSHIP_MONTHS 之内。
+ */
+pub(crate) fn check_requested_months(
+    months: i32,
+) -> std::result::Result<i32, RequestedMonthsOutOfRangeError> {
+    if (1..=MAX_SPONSORSHIP_MONTHS).contains(&months) {
+        Ok(months)
+    } else {
+        Err(RequestedMonthsOutOfRangeError {
+            requested: months,
+            max: MAX_SPONSORSHIP_MONTHS,
+        })
+    }
+}
+
+/**
+ * sponsorship_grant_window
+ * 计算授权的起止时间：同一槽位已有授权未结束时顺延到其 ends_at 之后，持续 duration_days 天。
+ */
+pub(crate) fn sponsorship_grant_window(
+    requested_start: chrono::DateTime<chrono::Utc>,
+    slot_max_end: Option<chrono::DateTime<chrono::Utc>>,
+    duration_days: i32,
+) -> (chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>) {
+    let starts_at = match slot_max_end {
+        Some(end) if end > requested_start => end,
+        _ => requested_start,
+    };
+    let ends_at = starts_at + chrono::Duration::days(duration_days.max(1) as i64);
+    (starts_at, ends_at)
+}
+
+/**
+ * lock_sponsorship_slot
+ * 在事务内对 (placement, slot_index) 加 advisory 锁，保证并发排期读取 MAX(ends_at) 后写入的 grant 不会相互重叠；事务结束自动释放。
+ */
+async fn lock_sponsorship_slot(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    placement: &str,
+    slot_index: Option<i32>,
+) -> Result<()> {
+    let key = format!(
+        "sponsorship_slot:{}:{}",
+        placement,
+        slot_index.map(|v| v.to_string()).unwrap_or_default()
+    );
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+        .persistent(false)
+        .bind(key)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/**
+ * mark_developer_as_sponsor
+ * 在授权事务内把申请邮箱标记为已认证赞助者；已有 sponsor_role（如 gold）时保留原角色。
+ */
+async fn mark_developer_as_sponsor(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    email: &str,
+) -> Result<()> {
+    let email_lower = email.trim().to_ascii_lowercase();
+    let email_clean = strip_nul_str(email_lower.as_str());
+    sqlx::query(
+        "INSERT INTO developers (email, name, sponsor_role, sponsor_verified) \
+         VALUES ($1, $1, 'sponsor', TRUE) \
+         ON CONFLICT (email) DO UPDATE SET \
+            sponsor_role = COALESCE(developers.sponsor_role, EXCLUDED.sponsor_role), \
+            sponsor_verified = TRUE, \
+            updated_at = NOW()",
+    )
+    .persistent(false)
+    .bind(email_clean.as_ref())
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/**
+ * placement_slot_count
+ * 各展示位的槽位数量：默认 home_top 2 个（左/右），home_right 3 个，
+ * 可通过 SPONSORSHIP_HOME_TOP_SLOTS / SPONSORSHIP_HOME_RIGHT_SLOTS 调整（1~10）。
+ */
+pub(crate) fn placement_slot_count(placement: Placement) -> i32 {
+    let (env_key, default_count) = match placement {
+        Placement::HomeTop => ("SPONSORSHIP_HOME_TOP_SLOTS", 2),
+        Placement::HomeRight => ("SPONSORSHIP_HOME_RIGHT_SLOTS", 3),
+    };
+    slot_count_from_env_value(env::var(env_key).ok().as_deref(), default_count)
+}
+
+fn slot_count_from_env_value(raw: Option<&str>, default_count: i32) -> i32 {
+    raw.and_then(|v| v.trim().parse::<i32>().ok())
+        .unwrap_or(default_count)
+        .clamp(1, 10)
+}
+
+/**
+ * SponsorshipSlotOutOfRangeError
+ * slot_index 缺失或超出展示位容量（0..capacity），由处理器按请求语言转换为 400。
+ */
+#[derive(Debug)]
+pub struct SponsorshipSlotOutOfRangeError {
+    pub placement: String,
+    pub slot_index: Option<i32>,
+    pub capacity: i32,
+}
+
+impl SponsorshipSlotOutOfRangeError {
+    pub fn localized_message(&self, lang: &str) -> String {
+        let max_index = self.capacity - 1;
+        if lang.starts_with("zh") {
+            format!(
+                "展示位 {} 必须指定 slot_index=0~{}",
+                self.placement, max_index
+            )
+        } else {
+            format!(
+                "{} requires slot_index between 0 and {}",
+                self.placement, max_index
+            )
+        }
+    }
+}
+
+impl std::fmt::Display for SponsorshipSlotOutOfRangeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "slot_index {:?} is out of range for {} (capacity {})",
+            self.slot_index, self.placement, self.capacity
+        )
+    }
+}
+
+impl std::error::Error for SponsorshipSlotOutOfRangeError {}
+
+/**
+ * check_sponsorship_slot
+ * 校验 slot_index 已指定且位于展示位容量 0..capacity 之内；申请、授权与订单创建共用。
+ */
+pub(crate) fn check_sponsorship_slot(
+    placement: Placement,
+    slot_index: Option<i32>,
+) -> std::result::Result<(), SponsorshipSlotOutOfRangeError> {
+    let capacity = placement_slot_count(placement);
+    match slot_index {
+        Some(i) if (0..capacity).contains(&i) => Ok(()),
+        _ => Err(SponsorshipSlotOutOfRangeError {
+            placement: placement.to_string(),
+            slot_index,
+            capacity,
+        }),
+    }
+}
+
+/**
+ * PricingPlanValidationError
+ * 定价方案字段校验失败，逐项列出无效字段，供管理端表单定位。
+ */
+#[derive(Debug)]
+pub struct PricingPlanValidationError {
+    pub fields: Vec<FieldError>,
+}
+
+impl std::fmt::Display for PricingPlanValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<&str> = self.fields.iter().map(|e| e.field.as_str()).collect();
+        write!(f, "Invalid pricing plan fields: {}", names.join(", "))
+    }
+}
+
+impl std::error::Error for PricingPlanValidationError {}
+
+/**
+ * validate_pricing_plan_input
+ * 校验定价方案：plan_key/双语标题必填，展示位合法，月价非负，至少一条权益，活动折扣与时间窗口合法。
+ */
+pub(crate) fn validate_pricing_plan_input(input: &UpsertPricingPlanRequest) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    let mut push = |field: &str, message: &str| {
+        errors.push(FieldError {
+            field: field.to_string(),
+            message: message.to_string(),
+        })
+    };
+
+    if input.plan_key.trim().is_empty() {
+        push("plan_key", "plan_key is required");
+    }
+    if input.title_en.trim().is_empty() {
+        push("title_en", "title_en is required");
+    }
+    if input.title_zh.trim().is_empty() {
+        push("title_zh", "title_zh is required");
+    }
+    if let Some(placement) = input.placement.as_deref().map(str::trim) {
+        if !placement.is_empty() && placement.parse::<Placement>().is_err() {
+            push("placement", "placement must be home_top or home_right");
+        }
+    }
+    if input.monthly_usd_cents.is_some_and(|v| v < 0) {
+        push("monthly_usd_cents", "monthly_usd_cents must be >= 0");
+    }
+    let has_benefit = input
+        .benefits
+        .iter()
+        .any(|b| !b.text_en.trim().is_empty() || !b.text_zh.trim().is_empty());
+    if !has_benefit {
+        push("benefits", "at least one benefit is required");
+    }
+
+    let campaign = &input.campaign;
+    match campaign.percent_off {
+        Some(pct) if !(1..=100).contains(&pct) => {
+            push(
+                "campaign.percent_off",
+                "percent_off must be between 1 and 100",
+            );
+        }
+        None if campaign.active => {
+            push(
+                "campaign.percent_off",
+                "percent_off is required when the campaign is active",
+            );
+        }
+        _ => {}
+    }
+    if let (Some(starts_at), Some(ends_at)) = (campaign.starts_at, campaign.ends_at) {
+        if ends_at <= starts_at {
+            push("campaign.ends_at", "ends_at must be after starts_at");
+        }
+    }
+
+    errors
+}
+
+/**
+ * SponsorshipRequestSort
+ * 赞助申请列表排序字段（均为倒序）。
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SponsorshipRequestSort {
+    #[default]
+    CreatedAt,
+    DurationDays,
+}
+
+impl SponsorshipRequestSort {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim() {
+            "" | "created_at" => Some(Self::CreatedAt),
+            "duration_days" => Some(Self::DurationDays),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * SponsorshipRequestListParams
+ * 赞助申请列表查询参数；Default 即不过滤、按创建时间倒序、最多 200 条。
+ */
+#[derive(Debug, Clone)]
+pub struct SponsorshipRequestListParams {
+    pub status: Option<String>,
+    pub q: Option<String>,
+    pub sort: SponsorshipRequestSort,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+impl Default for SponsorshipRequestListParams {
+    fn default() -> Self {
+        Self {
+            status: None,
+            q: None,
+            sort: SponsorshipRequestSort::CreatedAt,
+            limit: 200,
+            offset: 0,
+        }
+    }
+}
+
+/**
+ * product_ref_match_rank_sql
+ * product_ref 匹配等级表达式（0 UUID / 1 官网完全匹配 / 2 名称完全匹配 / 3 前缀 / 4 子串）；
+ * 参数为 SQL 表达式：原始 ref 与前缀模式。resolve_product_candidates 与 resolve_product_refs 共用。
+ */
+fn product_ref_match_rank_sql(product_ref: &str, prefix: &str) -> String {
+    format!(
+        "CASE \
+             WHEN p.id::text = lower({r}) THEN 0 \
+             WHEN rtrim(regexp_replace(lower(p.website), '^https?://(www\\.)?', ''), '/') \
+                  = rtrim(regexp_replace(lower({r}), '^https?://(www\\.)?', ''), '/') THEN 1 \
+             WHEN lower(p.name) = lower({r}) THEN 2 \
+             WHEN p.name ILIKE {prefix} ESCAPE '\\' \
+                  OR regexp_replace(p.website, '^https?://(www\\.)?', '', 'i') ILIKE {prefix} ESCAPE '\\' THEN 3 \
+             ELSE 4 \
+         END::int",
+        r = product_ref,
+        prefix = prefix,
+    )
+}
+
+/**
+ * product_ref_match_filter_sql
+ * product_ref 候选过滤条件（UUID、名称/官网子串、规范化官网相等）；参数为 SQL 表达式：原始 ref 与子串模式。
+ */
+fn product_ref_match_filter_sql(product_ref: &str, substring: &str) -> String {
+    format!(
+        "(p.id::text = lower({r}) \
+           OR p.name ILIKE {substring} ESCAPE '\\' \
+           OR p.website ILIKE {substring} ESCAPE '\\' \
+           OR rtrim(regexp_replace(lower(p.website), '^https?://(www\\.)?', ''), '/') \
+              = rtrim(regexp_replace(lower({r}), '^https?://(www\\.)?', ''), '/'))",
+        r = product_ref,
+        substring = substring,
+    )
+}
+
+/**
+ * pick_unambiguous_product_match
+ * 从 (匹配等级, 产品) 候选中选出唯一匹配：只看最高匹配等级，该等级命中多个产品即歧义。
+ */
+pub(crate) fn pick_unambiguous_product_match(
+    candidates: Vec<(i32, ResolvedProductRef)>,
+) -> Option<ResolvedProductRef> {
+    let best_rank = candidates.iter().map(|(rank, _)| *rank).min()?;
+    let mut tier: Vec<ResolvedProductRef> = candidates
+        .into_iter()
+        .filter(|(rank, _)| *rank == best_rank)
+        .map(|(_, m)| m)
+        .collect();
+    tier.dedup_by(|a, b| a.id == b.id);
+    if tier.len() == 1 {
+        tier.pop()
+    } else {
+        None
+    }
+}
+
+/**
+ * SponsorshipGrantListParams
+ * 授权列表查询参数；Default 即仅按展示位过滤（不限状态/产品）、最多 200 条。
+ */
+#[derive(Debug, Clone)]
+pub struct SponsorshipGrantListParams {
+    pub placement: Option<String>,
+    pub active: Option<bool>,
+    pub product_id: Option<String>,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+impl Default for SponsorshipGrantListParams {
+    fn default() -> Self {
+        Self {
+            placement: None,
+            active: None,
+            product_id: None,
+            limit: 200,
+            offset: 0,
+        }
+    }
+}
+
+/**
+ * AdminAuditLogListParams
+ * 审计日志查询参数：可按操作者、动作、对象类型/ID 过滤；Default 为不过滤、最多 50 条。
+ */
+#[derive(Debug, Clone)]
+pub struct AdminAuditLogListParams {
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub entity_type: Option<String>,
+    pub entity_id: Option<String>,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+impl Default for AdminAuditLogListParams {
+    fn default() -> Self {
+        Self {
+            actor: None,
+            action: None,
+            entity_type: None,
+            entity_id: None,
+            limit: 50,
+            offset: 0,
+        }
+    }
+}
+
+/**
+ * ADMIN_AUDIT_SUMMARY_MAX_CHARS
+ * 审计载荷摘要的最大字符数，超出部分截断并以 … 结尾。
+ */
+const ADMIN_AUDIT_SUMMARY_MAX_CHARS: usize = 500;
+
+/**
+ * SPONSORSHIP_ORDER_STREAM_BUFFER
+ * 订单流式读取时后台任务与 HTTP 响应之间的缓冲行数。
+ */
+const SPONSORSHIP_ORDER_STREAM_BUFFER: usize = 64;
+
+fn truncate_audit_summary(raw: &str) -> String {
+    let trimmed = raw.trim();
+    if trimmed.chars().count() <= ADMIN_AUDIT_SUMMARY_MAX_CHARS {
+        return trimmed.to_string();
+    }
+    let mut out: String = trimmed
+        .chars()
+        .take(ADMIN_AUDIT_SUMMARY_MAX_CHARS - 1)
+        .collect();
+    out.push('…');
+    out
+}
+
+/**
+ * SponsorshipRequestNotPendingError
+ * 赞助申请已被处理或拒绝，不能再次生成授权。
+ */
+#[derive(Debug)]
+pub struct SponsorshipRequestNotPendingError;
+
+impl std::fmt::Display for SponsorshipRequestNotPendingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Sponsorship request is not pending")
+    }
+}
+
+impl std::error::Error for SponsorshipRequestNotPendingError {}
+
+/**
+ * DeveloperNotFoundError
+ * developers 行不存在：合并开发者时 role 为 source / target，调整赞助身份时为 sponsor。
+ */
+#[derive(Debug)]
+pub struct DeveloperNotFoundError {
+    pub role: &'static str,
+    pub email: String,
+}
+
+impl std::fmt::Display for DeveloperNotFoundError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} developer not found: {}", self.role, self.email)
+    }
+}
+
+impl std::error::Error for DeveloperNotFoundError {}
+
+/**
+ * ProductAlreadyClaimedError
+ * 产品已被其他创作者认领（存在他人的 approved 认领）。
+ */
+#[derive(Debug)]
+pub struct ProductAlreadyClaimedError {
+    pub product_id: String,
+}
+
+impl std::fmt::Display for ProductAlreadyClaimedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Product {} is already claimed", self.product_id)
+    }
+}
+
+impl std::error::Error for ProductAlreadyClaimedError {}
+
+/**
+ * ProductClaimNotPendingError
+ * 管理员审核的认领已不是 pending 状态。
+ */
+#[derive(Debug)]
+pub struct ProductClaimNotPendingError;
+
+impl std::fmt::Display for ProductClaimNotPendingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Product claim is not pending")
+    }
+}
+
+impl std::error::Error for ProductClaimNotPendingError {}
+
+/**
+ * PricingPlanInUseError
+ * 定价方案仍被赞助订单引用，不能硬删除。
+ */
+#[derive(Debug)]
+pub struct PricingPlanInUseError {
+    pub order_refs: i64,
+}
+
+impl std::fmt::Display for PricingPlanInUseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Pricing plan is referenced by {} sponsorship order(s)",
+            self.order_refs
+        )
+    }
+}
+
+impl std::error::Error for PricingPlanInUseError {}
+
+/**
+ * resolve_pricing_plan_order
+ * 校验排序请求中的方案 id：必须是合法 UUID、不重复、且恰好覆盖全部现有方案；返回按请求顺序排列的 id。
+ */
+pub(crate) fn resolve_pricing_plan_order(
+    requested: &[String],
+    existing: &[uuid::Uuid],
+) -> std::result::Result<Vec<uuid::Uuid>, Vec<FieldError>> {
+    let mut errors = Vec::new();
+    let mut ordered = Vec::with_capacity(requested.len());
+    let mut seen = HashSet::new();
+    let existing_set: HashSet<uuid::Uuid> = existing.iter().copied().collect();
+
+    for raw in requested {
+        let raw = raw.trim();
+        match uuid::Uuid::parse_str(raw) {
+            Ok(id) if !existing_set.contains(&id) => errors.push(FieldError {
+                field: "ids".to_string(),
+                message: format!("unknown plan id: {}", raw),
+            }),
+            Ok(id) if !seen.insert(id) => errors.push(FieldError {
+                field: "ids".to_string(),
+                message: format!("duplicate plan id: {}", raw),
+            }),
+            Ok(id) => ordered.push(id),
+            Err(_) => errors.push(FieldError {
+                field: "ids".to_string(),
+                message: format!("invalid plan id: {}", raw),
+            }),
+        }
+    }
+
+    let missing: Vec<String> = existing
+        .iter()
+        .filter(|id| !seen.contains(*id))
+        .map(|id| id.to_string())
+        .collect();
+    if !missing.is_empty() {
+        errors.push(FieldError {
+            field: "ids".to_string(),
+            message: format!("missing plan ids: {}", missing.join(", ")),
+        });
+    }
+
+    if errors.is_empty() {
+        Ok(ordered)
+    } else {
+        Err(errors)
+    }
+}
+
+/**
+ * live_campaign_percent_off
+ * 活动开启且处于起止时间窗口内时返回折扣百分比，否则 None。
+ */
+fn live_campaign_percent_off(
+    campaign: &crate::models::PricingPlanCampaign,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Option<i32> {
+    if !campaign.active {
+        return None;
+    }
+    if campaign.starts_at.is_some_and(|t| t > now) || campaign.ends_at.is_some_and(|t| t <= now) {
+        return None;
+    }
+    campaign
+        .percent_off
+        .map(|v| v.clamp(0, 100))
+        .filter(|v| *v > 0)
+}
+
+fn strip_nul_in_place(value: &mut String) {
+    if value.as_bytes().contains(&0) {
+        value.retain(|c| c != '\u{0000}');
+    }
+}
+
+fn strip_nul_in_place_opt(value: &mut Option<String>) {
+    if let Some(v) = value.as_mut() {
+        strip_nul_in_place(v);
+    }
+}
+
+fn strip_nul_str(value: &str) -> Cow<'_, str> {
+    if value.as_bytes().contains(&0) {
+        Cow::Owned(value.replace('\u{0000}', ""))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/**
+ * normalize_base_url
+ * 规范化站点 base url（去掉末尾的 /），便于拼接 path。
+ */
+fn normalize_base_url(raw: &str) -> String {
+    raw.trim().trim_end_matches('/').to_string()
+}
+
+/**
+ * resolve_locale_path_segment
+ * 将 locale 映射为前端路由的路径段：path_map（JSON 对象，如 {"zh":"zh-CN"}）优先，未命中时原样使用 locale；
+ * 与 default_locale 相同或映射为空串时省略路径段。两者都未配置时保持 /{locale}/ 的原有行为。
+ */
+fn resolve_locale_path_segment(
+    locale: &str,
+    path_map: Option<&str>,
+    default_locale: Option<&str>,
+) -> String {
+    let locale = locale.trim();
+    if locale.is_empty() {
+        return String::new();
+    }
+    if default_locale
+        .map(str::trim)
+        .is_some_and(|d| !d.is_empty() && d.eq_ignore_ascii_case(locale))
+    {
+        return String::new();
+    }
+    path_map
+        .and_then(|raw| serde_json::from_str::<HashMap<String, String>>(raw).ok())
+        .and_then(|map| {
+            map.into_iter()
+                .find(|(k, _)| k.trim().eq_ignore_ascii_case(locale))
+                .map(|(_, v)| v.trim().trim_matches('/').to_string())
+        })
+        .unwrap_or_else(|| locale.to_string())
+}
+
+fn build_product_detail_url_with(base: &str, segment: &str, product_id: &str) -> String {
+    let base = normalize_base_url(base);
+    let slug = urlencoding::encode(product_id);
+    if segment.is_empty() {
+        format!("{}/products/{}", base, slug)
+    } else {
+        format!(
+            "{}/{}/products/{}",
+            base,
+            urlencoding::encode(segment),
+            slug
+        )
+    }
+}
+
+/**
+ * build_product_detail_url
+ * 生成产品详情页链接（前端路由：/products/[slug]，slug 使用产品 id）；
+ * locale 路径段按 LOCALE_PATH_MAP / FRONTEND_DEFAULT_LOCALE 映射。
+ */
+pub(crate) fn build_product_detail_url(
+    frontend_base_url: &str,
+    locale: &str,
+    product_id: &str,
+) -> String {
+    let segment = resolve_locale_path_segment(
+        locale,
+        env::var("LOCALE_PATH_MAP").ok().as_deref(),
+        env::var("FRONTEND_DEFAULT_LOCALE").ok().as_deref(),
+    );
+    build_product_detail_url_with(frontend_base_url, &segment, product_id)
+}
+
+/**
+ * build_json_feed
+ * 生成 JSON Feed 1.1 文档（https://jsonfeed.org/version/1.1），条目与 RSS 共用 get_feed_products 的数据。
+ */
+pub(crate) fn build_json_feed(
+    products: &[Product],
+    frontend_base_url: &str,
+    feed_url: &str,
+    locale: &str,
+) -> serde_json::Value {
+    let home_page_url = if locale.trim().is_empty() {
+        normalize_base_url(frontend_base_url)
+    } else {
+        format!(
+            "{}/{}",
+            normalize_base_url(frontend_base_url),
+            urlencoding::encode(locale.trim())
+        )
+    };
+    let items: Vec<serde_json::Value> = products
+        .iter()
+        .map(|p| {
+            let url = build_product_detail_url(frontend_base_url, locale, &p.id);
+            serde_json::json!({
+                "id": p.id,
+                "url": url,
+                "title": p.name,
+                "content_text": p.slogan,
+                "date_published": p.created_at.to_rfc3339(),
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "version": "https://jsonfeed.org/version/1.1",
+        "title": "SoloForge",
+        "home_page_url": home_page_url,
+        "feed_url": feed_url,
+        "items": items,
+    })
+}
+
+/**
+ * compute_admin_review_token
+ * 计算管理员邮件审核 token（HMAC-SHA256 + URL-safe base64，无 padding）。
+ */
+fn compute_admin_review_token(
+    product_id: &str,
+    action: &str,
+    exp_ts: i64,
+    secret: &str,
+) -> Result<String> {
+    type HmacSha256 = Hmac<Sha256>;
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .map_err(|_| anyhow::anyhow!("Invalid ADMIN_REVIEW_TOKEN_SECRET"))?;
+    mac.update(product_id.as_bytes());
+    mac.update(b"|");
+    mac.update(action.as_bytes());
+    mac.update(b"|");
+    mac.update(exp_ts.to_string().as_bytes());
+    let bytes = mac.finalize().into_bytes();
+    Ok(general_purpose::URL_SAFE_NO_PAD.encode(bytes))
+}
+
+/**
+ * build_admin_review_url
+ * 拼装管理员邮件一键审核链接（指向后端 /api/admin/review-product 接口）。
+ */
+fn build_admin_review_url(
+    public_api_base_url: &str,
+    product_id: &str,
+    action: &str,
+    exp_ts: i64,
+    token: &str,
+) -> String {
+    let base = normalize_base_url(public_api_base_url);
+    let pid_q = urlencoding::encode(product_id);
+    let action_q = urlencoding::encode(action);
+    let exp_s = exp_ts.to_string();
+    let exp_q = urlencoding::encode(&exp_s);
+    let sig_q = urlencoding::encode(token);
+    format!(
+        "{}/api/admin/review-product?product_id={}&action={}&exp={}&sig={}",
+        base, pid_q, action_q, exp_q, sig_q
+    )
+}
+
+/**
+ * compute_newsletter_unsubscribe_token
+ * 计算退订 token（HMAC-SHA256 + URL-safe base64，无 padding）。
+ */
+fn compute_newsletter_unsubscribe_token(email: &str, secret: &str) -> Result<String> {
+    type HmacSha256 = Hmac<Sha256>;
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .map_err(|_| anyhow::anyhow!("Invalid NEWSLETTER_TOKEN_SECRET"))?;
+    mac.update(email.as_bytes());
+    let bytes = mac.finalize().into_bytes();
+    Ok(general_purpose::URL_SAFE_NO_PAD.encode(bytes))
+}
+
+/**
+ * build_newsletter_unsubscribe_url
+ * 拼装退订链接（指向后端 /api/newsletter/unsubscribe 接口）。
+ */
+fn build_newsletter_unsubscribe_url(public_api_base_url: &str, email: &str, token: &str) -> String {
+    let base = normalize_base_url(public_api_base_url);
+    let email_q = urlencoding::encode(email);
+    let token_q = urlencoding::encode(token);
+    format!(
+        "{}/api/newsletter/unsubscribe?email={}&token={}",
+        base, email_q, token_q
+    )
+}
+
+/**
+ * newsletter_flag_enabled
+ * 周报开关（NEWSLETTER_ENABLED 为 0/false 时关闭，默认开启）。
+ */
+pub(crate) fn newsletter_flag_enabled() -> bool {
+    !matches!(
+        env::var("NEWSLETTER_ENABLED").ok().as_deref(),
+        Some("0") | Some("false") | Some("FALSE")
+    )
+}
+
+/**
+ * supabase_fallback_disabled_from_env_value
+ * 解析 DISABLE_SUPABASE_FALLBACK：1/true 时关闭 Supabase 兜底，其余（含未设置）保持开启。
+ */
+pub(crate) fn supabase_fallback_disabled_from_env_value(value: Option<&str>) -> bool {
+    matches!(
+        value.map(|v| v.trim().to_ascii_lowercase()).as_deref(),
+        Some("1") | Some("true")
+    )
+}
+
+/**
+ * newsletter_sender_configured
+ * 是否配置了周报发送所需的 RESEND_API_KEY 与 NEWSLETTER_FROM。
+ */
+pub(crate) fn newsletter_sender_configured() -> bool {
+    let non_empty = |key: &str| env::var(key).ok().is_some_and(|v| !v.trim().is_empty());
+    non_empty("RESEND_API_KEY") && non_empty("NEWSLETTER_FROM")
+}
+
+/**
+ * newsletter_tracking_enabled
+ * 周报打开/点击追踪开关（NEWSLETTER_TRACKING_ENABLED=1/true，默认关闭）；
+ * 点击链接需要签名，因此未配置 NEWSLETTER_TOKEN_SECRET 时不启用。
+ */
+pub(crate) fn newsletter_tracking_enabled() -> bool {
+    let flag = matches!(
+        env::var("NEWSLETTER_TRACKING_ENABLED")
+            .ok()
+            .map(|v| v.trim().to_ascii_lowercase())
+            .as_deref(),
+        Some("1") | Some("true")
+    );
+    flag && env::var("NEWSLETTER_TOKEN_SECRET").is_ok_and(|v| !v.trim().is_empty())
+}
+
+fn newsletter_click_mac(send_token: &str, url: &str, secret: &str) -> Result<Hmac<Sha256>> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .map_err(|_| anyhow::anyhow!("Invalid NEWSLETTER_TOKEN_SECRET"))?;
+    mac.update(send_token.as_bytes());
+    mac.update(b"|");
+    mac.update(url.as_bytes());
+    Ok(mac)
+}
+
+/**
+ * compute_newsletter_click_signature
+ * 点击追踪链接签名：HMAC-SHA256(send_token|url)，URL-safe base64 无 padding，防止被用作开放跳转。
+ */
+pub(crate) fn compute_newsletter_click_signature(
+    send_token: &str,
+    url: &str,
+    secret: &str,
+) -> Result<String> {
+    let bytes = newsletter_click_mac(send_token, url, secret)?
+        .finalize()
+        .into_bytes();
+    Ok(general_purpose::URL_SAFE_NO_PAD.encode(bytes))
+}
+
+pub(crate) fn verify_newsletter_click_signature(
+    send_token: &str,
+    url: &str,
+    sig: &str,
+    secret: &str,
+) -> bool {
+    if secret.trim().is_empty() {
+        return false;
+    }
+    let Ok(sig) = general_purpose::URL_SAFE_NO_PAD.decode(sig.trim()) else {
+        return false;
+    };
+    newsletter_click_mac(send_token, url, secret).is_ok_and(|mac| mac.verify_slice(&sig).is_ok())
+}
+
+/**
+ * newsletter_issue_link_hosts
+ * 本期周报中产品官网的 host（小写去重），写入 newsletter_sends.link_hosts 作为点击跳转的默认白名单。
+ */
+pub(crate) fn newsletter_issue_link_hosts(products: &[NewsletterTopProductRow]) -> Vec<String> {
+    let mut hosts: Vec<String> = products
+        .iter()
+        .filter_map(|p| Url::parse(p.website.trim()).ok())
+        .filter_map(|u| u.host_str().map(|h| h.to_ascii_lowercase()))
+        .collect();
+    hosts.sort();
+    hosts.dedup();
+    hosts
+}
+
+/**
+ * newsletter_click_target
+ * 校验点击跳转目标：仅允许 http(s) 且带 host，且 host 必须是前台域名或白名单中的域名（含子域名）。
+ * 白名单取 NEWSLETTER_CLICK_ALLOWED_HOSTS（逗号分隔）；未配置时使用该期周报的产品官网 host（issue_hosts）。
+ */
+pub(crate) fn newsletter_click_target(
+    raw: &str,
+    frontend_base_url: &str,
+    allowed_hosts_raw: Option<&str>,
+    issue_hosts: &[String],
+) -> Option<Url> {
+    let url = Url::parse(raw.trim()).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    let host = url.host_str()?.to_ascii_lowercase();
+
+    let mut allowed: Vec<String> = allowed_hosts_raw
+        .unwrap_or("")
+        .split(',')
+        .map(|v| v.trim().trim_start_matches('.').to_ascii_lowercase())
+        .filter(|v| !v.is_empty())
+        .collect();
+    if allowed.is_empty() {
+        allowed = issue_hosts
+            .iter()
+            .map(|v| v.trim().to_ascii_lowercase())
+            .filter(|v| !v.is_empty())
+            .collect();
+    }
+    if let Some(frontend_host) = Url::parse(frontend_base_url.trim())
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_ascii_lowercase()))
+    {
+        allowed.push(frontend_host);
+    }
+    allowed
+        .iter()
+        .any(|a| host == *a || host.ends_with(&format!(".{}", a)))
+        .then_some(url)
+}
+
+/**
+ * NewsletterTracking
+ * 单次发送的追踪参数：链接改写为 /api/newsletter/click 签名跳转，并追加 1x1 打开像素。
+ */
+pub(crate) struct NewsletterTracking<'a> {
+    pub public_api_base_url: &'a str,
+    pub send_token: &'a str,
+    pub secret: &'a str,
+}
+
+impl NewsletterTracking<'_> {
+    fn click_url(&self, target: &str) -> String {
+        let Ok(sig) = compute_newsletter_click_signature(self.send_token, target, self.secret)
+        else {
+            return target.to_string();
+        };
+        format!(
+            "{}/api/newsletter/click?token={}&url={}&sig={}",
+            normalize_base_url(self.public_api_base_url),
+            urlencoding::encode(self.send_token),
+            urlencoding::encode(target),
+            urlencoding::encode(&sig)
+        )
+    }
+
+    fn open_pixel_url(&self) -> String {
+        format!(
+            "{}/api/newsletter/open/{}.gif",
+            normalize_base_url(self.public_api_base_url),
+            urlencoding::encode(self.send_token)
+        )
+    }
+}
+
+/**
+ * newsletter_top_n
+ * 周报展示的产品数量（NEWSLETTER_TOP_N，默认 5，范围 1~20）。
+ */
+fn newsletter_top_n() -> i64 {
+    env::var("NEWSLETTER_TOP_N")
+        .ok()
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .unwrap_or(5)
+        .clamp(1, 20)
+}
+
+/**
+ * newsletter_score_weights
+ * 周报评分权重 (点赞, 收藏)，分别读取 NEWSLETTER_LIKE_WEIGHT / NEWSLETTER_FAVORITE_WEIGHT，默认均为 1。
+ */
+fn newsletter_score_weights() -> (i64, i64) {
+    let read = |key: &str| {
+        env::var(key)
+            .ok()
+            .and_then(|v| v.trim().parse::<i64>().ok())
+            .unwrap_or(1)
+            .clamp(0, 100)
+    };
+    (
+        read("NEWSLETTER_LIKE_WEIGHT"),
+        read("NEWSLETTER_FAVORITE_WEIGHT"),
+    )
+}
+
+/**
+ * PopularityMetric
+ * 创作者人气榜的计分方式：default 为 likes + favorites；weighted 为按环境变量权重加权（含窗口内新增关注）。
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PopularityMetric {
+    Default,
+    Weighted,
+}
+
+impl PopularityMetric {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "" | "default" => Some(Self::Default),
+            "weighted" => Some(Self::Weighted),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * DeveloperDirectorySort
+ * 创作者目录的排序：name 升序、created_at 倒序、followers 倒序（均以 email 兜底保证翻页稳定）。
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeveloperDirectorySort {
+    Name,
+    CreatedAt,
+    Followers,
+}
+
+impl DeveloperDirectorySort {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "" | "name" => Some(Self::Name),
+            "created_at" => Some(Self::CreatedAt),
+            "followers" => Some(Self::Followers),
+            _ => None,
+        }
+    }
+
+    fn order_by(self) -> &'static str {
+        match self {
+            Self::Name => "d.name ASC, d.email ASC",
+            Self::CreatedAt => "d.created_at DESC, d.email ASC",
+            Self::Followers => "COUNT(f.id) DESC, d.email ASC",
+        }
+    }
+}
+
+/**
+ * DEVELOPER_SEARCH_PREDICATE
+ * 创作者搜索条件（$1 为 ILIKE 模式），search_developers 与创作者目录共用。
+ */
+const DEVELOPER_SEARCH_PREDICATE: &str =
+    "(d.name ILIKE $1 OR d.email ILIKE $1 OR d.website ILIKE $1)";
+
+/**
+ * StatsWindow
+ * 创作者中心统计的时间窗口：week 为最近 7 天，month 为最近 30 天（滚动窗口）。
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsWindow {
+    Week,
+    Month,
+}
+
+impl StatsWindow {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "week" | "7d" => Some(Self::Week),
+            "month" | "30d" => Some(Self::Month),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Week => "week",
+            Self::Month => "month",
+        }
+    }
+
+    pub fn since(self, now: chrono::DateTime<chrono::Utc>) -> chrono::DateTime<chrono::Utc> {
+        let days = match self {
+            Self::Week => 7,
+            Self::Month => 30,
+        };
+        now - chrono::Duration::days(days)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PopularityWeights {
+    pub likes: i64,
+    pub favorites: i64,
+    pub followers: i64,
+}
+
+/**
+ * developer_popularity_weights
+ * 读取 DEVELOPER_POPULARITY_{LIKE,FAVORITE,FOLLOWER}_WEIGHT（默认均为 1，范围 0..=100）。
+ */
+fn developer_popularity_weights() -> PopularityWeights {
+    let read = |key: &str| {
+        env::var(key)
+            .ok()
+            .and_then(|v| v.trim().parse::<i64>().ok())
+            .unwrap_or(1)
+            .clamp(0, 100)
+    };
+    PopularityWeights {
+        likes: read("DEVELOPER_POPULARITY_LIKE_WEIGHT"),
+        favorites: read("DEVELOPER_POPULARITY_FAVORITE_WEIGHT"),
+        followers: read("DEVELOPER_POPULARITY_FOLLOWER_WEIGHT"),
+    }
+}
+
+/**
+ * rank_developer_popularity
+ * 按权重重新计算 score 并排序（score、favorites、likes 降序，name 升序），截取前 limit 个。
+ */
+pub(crate) fn rank_developer_popularity(
+    mut list: Vec<DeveloperPopularity>,
+    weights: PopularityWeights,
+    limit: usize,
+) -> Vec<DeveloperPopularity> {
+    for d in list.iter_mut() {
+        d.score = d.likes.saturating_mul(weights.likes)
+            + d.favorites.saturating_mul(weights.favorites)
+            + d.new_followers.saturating_mul(weights.followers);
+    }
+    list.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then(b.favorites.cmp(&a.favorites))
+            .then(b.likes.cmp(&a.likes))
+            .then(a.name.cmp(&b.name))
+    });
+    list.truncate(limit);
+    list
+}
+
+/**
+ * newsletter_recipient_locale
+ * 订阅者语言：以 zh 开头为 zh，其余（含未知）默认 en，用于周报详情链接的本地化路径。
+ */
+pub(crate) fn newsletter_recipient_locale(language: Option<&str>) -> &'static str {
+    match language {
+        Some(v) if v.trim().to_ascii_lowercase().starts_with("zh") => "zh",
+        _ => "en",
+    }
+}
+
+/**
+ * build_weekly_newsletter_content
+ * 构建周报邮件内容（中英双语 + 产品详情链接 + 退订链接）；详情链接指向收件人语言（locale）对应的页面。
+ */
+pub(crate) fn build_weekly_newsletter_content(
+    now: chrono::DateTime<chrono::Utc>,
+    since: chrono::DateTime<chrono::Utc>,
+    products: &[NewsletterTopProductRow],
+    frontend_base_url: &str,
+    unsubscribe_url: &str,
+    locale: &str,
+    tracking: Option<&NewsletterTracking<'_>>,
+) -> (String, String, String) {
+    let tracked = |url: &str| match tracking {
+        Some(t) => t.click_url(url),
+        None => url.to_string(),
+    };
+    let subject = format!("SoloForge Weekly ({})", now.format("%Y-%m-%d"));
+
+    let mut text = String::new();
+    text.push_str(&format!(
+        "SoloForge Weekly\nTime range: {} – {}\n\nTop {} products this week:\n\n",
+        since.format("%Y-%m-%d"),
+        now.format("%Y-%m-%d"),
+        products.len()
+    ));
+
+    let mut html = String::new();
+    let range_en = format!("{} – {}", since.format("%Y-%m-%d"), now.format("%Y-%m-%d"));
+
+    html.push_str("<!doctype html><html><body style=\"margin:0;padding:0;background:#f6f7fb;\">");
+    html.push_str("<table role=\"presentation\" width=\"100%\" cellpadding=\"0\" cellspacing=\"0\" style=\"background:#f6f7fb;padding:24px 0;\">");
+    html.push_str("<tr><td align=\"center\" style=\"padding:0 12px;\">");
+    html.push_str("<table role=\"presentation\" width=\"600\" cellpadding=\"0\" cellspacing=\"0\" style=\"width:100%;max-width:600px;background:#ffffff;border:1px solid #eaecef;border-radius:16px;overflow:hidden;\">");
+
+    html.push_str("<tr><td style=\"padding:22px 24px;background:#111827;color:#ffffff;\">");
+    html.push_str("<div style=\"font-size:18px;font-weight:700;letter-spacing:0.2px;\">SoloForge Weekly</div>");
+    html.push_str(&format!(
+        "<div style=\"margin-top:6px;font-size:12px;opacity:0.9;\">{}</div>",
+        html_escape(&range_en)
+    ));
+    html.push_str("</td></tr>");
+
+    html.push_str("<tr><td style=\"padding:22px 24px;\">");
+    html.push_str("<div style=\"font-family:-apple-system,BlinkMacSystemFont,Segoe UI,Roboto,Helvetica,Arial,sans-serif;line-height:1.6;color:#111827;\">");
+
+    html.push_str("<h2 style=\"margin:0 0 6px 0;font-size:18px;\">SoloForge Weekly</h2>");
+    html.push_str(&format!(
+        "<div style=\"margin:0 0 14px 0;font-size:12px;color:#6b7280;\">Time range: {}</div>",
+        html_escape(&range_en)
+    ));
+    html.push_str(&format!(
+        "<div style=\"font-size:14px;font-weight:700;margin:0 0 12px 0;\">Top {} products this week</div>",
+        products.len()
+    ));
+
+    for (idx, p) in products.iter().enumerate() {
+        let n = idx + 1;
+        let score = p.score;
+        let likes = p.weekly_likes;
+        let favorites = p.weekly_favorites;
+        let website = p.website.trim();
+        let detail_url = build_product_detail_url(frontend_base_url, locale, &p.id);
+
+        html.push_str("<table role=\"presentation\" width=\"100%\" cellpadding=\"0\" cellspacing=\"0\" style=\"margin:0 0 12px 0;border:1px solid #e5e7eb;border-radius:12px;overflow:hidden;\">");
+        html.push_str("<tr><td style=\"padding:14px 14px 12px 14px;\">");
+
+        html.push_str("<div style=\"display:block;\">");
+        html.push_str(&build_email_logo_img(p.logo_url.as_deref(), &p.name, 40));
+        html.push_str(&format!(
+            "<span style=\"display:inline-block;min-width:22px;height:22px;line-height:22px;text-align:center;border-radius:999px;background:#eef2ff;color:#3730a3;font-size:12px;font-weight:700;margin-right:8px;\">{}</span>",
+            n
+        ));
+        html.push_str(&format!(
+            "<span style=\"font-size:15px;font-weight:800;\">{}</span>",
+            html_escape(&p.name)
+        ));
+        html.push_str("</div>");
+
+        if !p.slogan.trim().is_empty() {
+            html.push_str(&format!(
+                "<div style=\"margin-top:4px;font-size:13px;color:#4b5563;\">{}</div>",
+                html_escape(&p.slogan)
+            ));
+        }
+
+        text.push_str(&format!(
+            "{}. {} - {}\nDetails: {}\nWebsite: {}\nWeekly score: {} (likes {} / favorites {})\nMaker: {} ({})\n\n",
+            n,
+            p.name,
+            p.slogan,
+            detail_url,
+            website,
+            score,
+            likes,
+            favorites,
+            p.maker_name,
+            p.maker_email
+        ));
+
+        html.push_str("<div style=\"margin-top:10px;\">");
+        html.push_str(&format!(
+            "<a href=\"{}\" target=\"_blank\" rel=\"noreferrer\" style=\"display:inline-block;padding:8px 12px;margin:0 8px 8px 0;background:#111827;color:#ffffff;text-decoration:none;border-radius:10px;font-size:12px;font-weight:700;\">View details</a>",
+            html_attr_escape(&tracked(&detail_url))
+        ));
+        if !website.is_empty() {
+            html.push_str(&format!(
+                "<a href=\"{}\" target=\"_blank\" rel=\"noreferrer\" style=\"display:inline-block;padding:8px 12px;margin:0 8px 8px 0;background:#ffffff;color:#111827;text-decoration:none;border:1px solid #e5e7eb;border-radius:10px;font-size:12px;font-weight:700;\">Visit website</a>",
+                html_attr_escape(&tracked(website))
+            ));
+        }
+        html.push_str("</div>");
+
+        html.push_str(&format!(
+            "<div style=\"margin-top:6px;font-size:12px;color:#6b7280;\">Weekly score <strong style=\"color:#111827;\">{}</strong> · likes {} · favorites {}</div>",
+            score, likes, favorites
+        ));
+        html.push_str(&format!(
+            "<div style=\"margin-top:4px;font-size:12px;color:#6b7280;\">Maker: {} ({})</div>",
+            html_escape(&p.maker_name),
+            html_escape(&p.maker_email)
+        ));
+
+        html.push_str("</td></tr></table>");
+    }
+
+    text.push_str(&format!("Unsubscribe: {}\n", unsubscribe_url));
+
+    html.push_str(&format!(
+        "<div style=\"margin-top:14px;padding-top:14px;border-top:1px solid #e5e7eb;\"><div style=\"font-size:12px;color:#6b7280;\">Unsubscribe: <a href=\"{}\" target=\"_blank\" rel=\"noreferrer\" style=\"color:#111827;text-decoration:underline;\">click here</a></div></div>",
+        html_attr_escape(unsubscribe_url)
+    ));
+    html.push_str("<div style=\"margin-top:16px;font-size:11px;color:#9ca3af;\">You are receiving this email because you subscribed to the SoloForge weekly brief.</div>");
+    if let Some(t) = tracking {
+        html.push_str(&format!(
+            "<img src=\"{}\" alt=\"\" width=\"1\" height=\"1\" style=\"display:block;width:1px;height:1px;border:0;\" />",
+            html_attr_escape(&t.open_pixel_url())
+        ));
+    }
+    html.push_str("</div></td></tr>");
+    html.push_str("</table></td></tr></table>");
+    html.push_str("</body></html>");
+
+    (subject, html, text)
+}
+
+/**
+ * build_email_logo_img
+ * 生成邮件中的产品 logo <img>（限制尺寸，alt 取产品名）；logo_url 为空或不是合法 http(s) 地址时返回空串。
+ */
+fn build_email_logo_img(logo_url: Option<&str>, alt: &str, size_px: u32) -> String {
+    let Some(raw) = logo_url.map(|v| v.trim()).filter(|v| !v.is_empty()) else {
+        return String::new();
+    };
+    let Ok(parsed) = Url::parse(raw) else {
+        return String::new();
+    };
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        return String::new();
+    }
+    format!(
+        "<img src=\"{}\" alt=\"{}\" width=\"{}\" height=\"{}\" style=\"display:inline-block;width:{}px;height:{}px;max-width:{}px;max-height:{}px;border-radius:10px;object-fit:cover;vertical-align:middle;margin-right:10px;border:1px solid #e5e7eb;\" />",
+        html_attr_escape(parsed.as_str()),
+        html_attr_escape(alt),
+        size_px,
+        size_px,
+        size_px,
+        size_px,
+        size_px,
+        size_px
+    )
+}
+
+fn html_escape(raw: &str) -> String {
+    raw.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn html_attr_escape(raw: &str) -> String {
+    html_escape(raw).replace(['\n', '\r'], " ")
+}
+
+const RESEND_EMAILS_ENDPOINT: &str = "https://api.resend.com/emails";
+const HTTP_CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/**
+ * http_timeout
+ * 出站 HTTP（Supabase / Resend）的整体请求超时：HTTP_TIMEOUT_SECS（1~120 秒），未配置时使用各调用方的默认值。
+ */
+fn http_timeout(default_secs: u64) -> Duration {
+    Duration::from_secs(http_timeout_secs_from_env_value(
+        env::var("HTTP_TIMEOUT_SECS").ok().as_deref(),
+        default_secs,
+    ))
+}
+
+fn http_timeout_secs_from_env_value(raw: Option<&str>, default_secs: u64) -> u64 {
+    raw.and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(default_secs)
+        .clamp(1, 120)
+}
+
+/**
+ * resend_http_client
+ * Resend 发信专用 client：显式 connect/请求超时（默认 12 秒），避免慢网络下单封邮件卡住整个批次。
+ */
+fn resend_http_client() -> Client {
+    resend_http_client_with_timeout(http_timeout(12))
+}
+
+fn resend_http_client_with_timeout(timeout: Duration) -> Client {
+    Client::builder()
+        .connect_timeout(HTTP_CONNECT_TIMEOUT.min(timeout))
+        .timeout(timeout)
+        .http1_only()
+        .build()
+        .unwrap_or_else(|_| Client::new())
+}
+
+async fn send_email_resend(
+    client: &Client,
+    api_key: &str,
+    from: &str,
+    to: &str,
+    subject: &str,
+    html: Option<&str>,
+    text: &str,
+) -> Result<()> {
+    let mut payload = serde_json::json!({
+        "from": from,
+        "to": [to],
+        "subject": subject,
+        "text": text
+    });
+    if let Some(html) = html {
+        payload["html"] = serde_json::Value::String(html.to_string());
+    }
+    post_resend_email(client, RESEND_EMAILS_ENDPOINT, api_key, &payload).await
+}
+
+/**
+ * post_resend_email
+ * 向 Resend 投递单封邮件；超时（client 的请求超时）以 reqwest 超时错误返回，由调用方记录后继续下一位收件人。
+ */
+async fn post_resend_email(
+    client: &Client,
+    endpoint: &str,
+    api_key: &str,
+    payload: &serde_json::Value,
+) -> Result<()> {
+    let resp = client
+        .post(endpoint)
+        .bearer_auth(api_key)
+        .json(payload)
+        .send()
+        .await?;
+
+    if resp.status().is_success() {
+        return Ok(());
+    }
+
+    let status = resp.status().as_u16();
+    let retry_after = resp
+        .headers()
+        .get("retry-after")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let body = resp.text().await.unwrap_or_default();
+    Err(classify_resend_failure(status, retry_after.as_deref(), body, chrono::Utc::now()).into())
+}
+
+fn is_http_timeout_error(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<reqwest::Error>()
+        .is_some_and(|re| re.is_timeout())
+}
+
+/**
+ * ResendSendError
+ * Resend 非 2xx 响应的分类：429 限流（携带 Retry-After 延迟）、收件人级的不可重试错误（400 / 422）、
+ * API Key 失效等鉴权错误（401 / 403，与收件人无关），以及可稍后重试的其余状态。
+ */
+#[derive(Debug)]
+pub enum ResendSendError {
+    RateLimited { retry_after: Option<Duration> },
+    Permanent { status: u16, body: String },
+    Unauthorized { status: u16, body: String },
+    Transient { status: u16, body: String },
+}
+
+impl std::fmt::Display for ResendSendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResendSendError::RateLimited {
+                retry_after: Some(d),
+            } => write!(f, "Resend rate limited, retry after {}s", d.as_secs()),
+            ResendSendError::RateLimited { retry_after: None } => {
+                write!(f, "Resend rate limited")
+            }
+            ResendSendError::Permanent { status, body } => {
+                write!(f, "Resend error (permanent): {} {}", status, body)
+            }
+            ResendSendError::Unauthorized { status, body } => {
+                write!(f, "Resend error (unauthorized): {} {}", status, body)
+            }
+            ResendSendError::Transient { status, body } => {
+                write!(f, "Resend error: {} {}", status, body)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ResendSendError {}
+
+/**
+ * parse_retry_after
+ * 解析 Retry-After 头：秒数或 HTTP-date（已过去的时间视为 0）。
+ */
+fn parse_retry_after(value: &str, now: chrono::DateTime<chrono::Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let secs = (at.with_timezone(&chrono::Utc) - now).num_seconds().max(0);
+    Some(Duration::from_secs(secs as u64))
+}
+
+fn classify_resend_failure(
+    status: u16,
+    retry_after: Option<&str>,
+    body: String,
+    now: chrono::DateTime<chrono::Utc>,
+) -> ResendSendError {
+    match status {
+        429 => ResendSendError::RateLimited {
+            retry_after: retry_after.and_then(|v| parse_retry_after(v, now)),
+        },
+        400 | 422 => ResendSendError::Permanent { status, body },
+        401 | 403 => ResendSendError::Unauthorized { status, body },
+        _ => ResendSendError::Transient { status, body },
+    }
+}
+
+/**
+ * NEWSLETTER_MAX_RETRY_AFTER
+ * 周报发送遇到 429 时最多原地等待的时长；更长的 Retry-After 留给下一轮任务。
+ */
+const NEWSLETTER_MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/**
+ * newsletter_concurrency
+ * NEWSLETTER_CONCURRENCY：周报同时在途的发送数（默认 5，1~20）。
+ */
+fn newsletter_concurrency() -> usize {
+    newsletter_concurrency_from_env_value(env::var("NEWSLETTER_CONCURRENCY").ok().as_deref())
+}
+
+fn newsletter_concurrency_from_env_value(raw: Option<&str>) -> usize {
+    raw.and_then(|v| v.trim().parse::<usize>().ok())
+        .unwrap_or(5)
+        .clamp(1, 20)
+}
+
+/**
+ * run_bounded_concurrent
+ * 以最多 concurrency 个并发任务处理 items，结果按输入顺序返回；单个任务 panic 时该项结果为 None。
+ */
+async fn run_bounded_concurrent<T, R, F, Fut>(
+    items: Vec<T>,
+    concurrency: usize,
+    task: F,
+) -> Vec<Option<R>>
+where
+    T: Send + 'static,
+    R: Send + 'static,
+    F: Fn(T) -> Fut,
+    Fut: std::future::Future<Output = R> + Send + 'static,
+{
+    let concurrency = concurrency.max(1);
+    let mut results: Vec<Option<R>> = (0..items.len()).map(|_| None).collect();
+    let mut set: tokio::task::JoinSet<(usize, R)> = tokio::task::JoinSet::new();
+    for (idx, item) in items.into_iter().enumerate() {
+        while set.len() >= concurrency {
+            if let Some(Ok((i, r))) = set.join_next().await {
+                results[i] = Some(r);
+            }
+        }
+        let fut = task(item);
+        set.spawn(async move { (idx, fut.await) });
+    }
+    while let Some(joined) = set.join_next().await {
+        if let Ok((i, r)) = joined {
+            results[i] = Some(r);
+        }
+    }
+    results
+}
+
+/**
+ * NewsletterRunOptions
+ * 周报发送选项：ignore_schedule 跳过发送时间窗，dry_run 只渲染统计不发送。
+ */
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NewsletterRunOptions {
+    pub ignore_schedule: bool,
+    pub dry_run: bool,
+}
+
+/**
+ * NewsletterRunReport
+ * 一次周报发送的结果：待发收件人数、实际发送成功数，dry_run 时附带第一位收件人的渲染样例。
+ */
+#[derive(Debug, Default)]
+pub struct NewsletterRunReport {
+    pub recipients: usize,
+    pub sent: usize,
+    pub sample: Option<NewsletterSample>,
+}
+
+#[derive(Debug)]
+pub struct NewsletterSample {
+    pub to: String,
+    pub subject: String,
+    pub html: Option<String>,
+    pub text: String,
+}
+
+/**
+ * NewsletterMessage
+ * 已渲染好的一封周报（含追踪 token），发送阶段与数据库写入阶段解耦，便于并发发送。
+ */
+struct NewsletterMessage {
+    to: String,
+    subject: String,
+    html: Option<String>,
+    text: String,
+    send_token: Option<uuid::Uuid>,
+}
+
+/**
+ * send_newsletter_message
+ * 发送单封周报；遇到 429 且 Retry-After 不超过 NEWSLETTER_MAX_RETRY_AFTER 时等待后重试一次。
+ */
+async fn send_newsletter_message(
+    client: &Client,
+    api_key: &str,
+    from: &str,
+    msg: &NewsletterMessage,
+) -> Result<()> {
+    let res = send_email_resend(
+        client,
+        api_key,
+        from,
+        &msg.to,
+        &msg.subject,
+        msg.html.as_deref(),
+        &msg.text,
+    )
+    .await;
+    let rate_limited_delay = match res
+        .as_ref()
+        .err()
+        .and_then(|e| e.downcast_ref::<ResendSendError>())
+    {
+        Some(ResendSendError::RateLimited {
+            retry_after: Some(delay),
+        }) => Some(*delay),
+        _ => None,
+    };
+    match rate_limited_delay {
+        Some(delay) if delay <= NEWSLETTER_MAX_RETRY_AFTER => {
+            tokio::time::sleep(delay).await;
+            send_email_resend(
+                client,
+                api_key,
+                from,
+                &msg.to,
+                &msg.subject,
+                msg.html.as_deref(),
+                &msg.text,
+            )
+            .await
+        }
+        _ => res,
+    }
+}
+
+/**
+ * build_admin_product_submission_email_content
+ * 构建“产品提交待审核”的管理员通知邮件内容（包含一键通过/拒绝链接）。
+ */
+fn build_admin_product_submission_email_content(
+    product: &Product,
+    frontend_base_url: &str,
+    public_api_base_url: &str,
+    token_secret: &str,
+) -> (String, String, String) {
+    let subject = format!("New product submitted: {}", product.name.trim());
+
+    let product_name = product.name.trim();
+    let product_slogan = product.slogan.trim();
+    let product_desc = product.description.trim();
+    let product_website = product.website.trim();
+    let maker_name = product.maker_name.trim();
+    let maker_email = product.maker_email.trim();
+    let category = product.category.trim();
+    let product_id = product.id.trim();
+
+    let detail_url = build_product_detail_url(frontend_base_url, "en", product_id);
+
+    let exp_ts = (chrono::Utc::now() + chrono::Duration::days(7)).timestamp();
+    let approve_token =
+        compute_admin_review_token(product_id, "approve", exp_ts, token_secret).unwrap_or_default();
+    let reject_token =
+        compute_admin_review_token(product_id, "reject", exp_ts, token_secret).unwrap_or_default();
+    let approve_url = if !approve_token.trim().is_empty() {
+        build_admin_review_url(
+            public_api_base_url,
+            product_id,
+            "approve",
+            exp_ts,
+            &approve_token,
+        )
+    } else {
+        String::new()
+    };
+    let reject_url = if !reject_token.trim().is_empty() {
+        build_admin_review_url(
+            public_api_base_url,
+            product_id,
+            "reject",
+            exp_ts,
+            &reject_token,
+        )
+    } else {
+        String::new()
+    };
+
+    let mut text = String::new();
+    text.push_str("New product submitted (pending review)\n\n");
+    text.push_str(&format!("Name: {}\n", product_name));
+    if !product_slogan.is_empty() {
+        text.push_str(&format!("Slogan: {}\n", product_slogan));
+    }
+    text.push_str(&format!("Category: {}\n", category));
+    text.push_str(&format!("Website: {}\n", product_website));
+    text.push_str(&format!("Maker: {} ({})\n", maker_name, maker_email));
+    text.push_str(&format!("Product ID: {}\n", product_id));
+    text.push_str(&format!("Details: {}\n", detail_url));
+    if !approve_url.is_empty() && !reject_url.is_empty() {
+        text.push_str(&format!(
+            "\nApprove: {}\nReject: {}\n",
+            approve_url, reject_url
+        ));
+    } else {
+        text.push_str(
+            "\nOne-click review links are not configured (missing ADMIN_REVIEW_TOKEN_SECRET).\n",
+        );
+    }
+
+    let mut html = String::new();
+    html.push_str("<!doctype html><html><body style=\"margin:0;padding:0;background:#f6f7fb;\">");
+    html.push_str("<table role=\"presentation\" width=\"100%\" cellpadding=\"0\" cellspacing=\"0\" style=\"background:#f6f7fb;padding:24px 0;\">");
+    html.push_str("<tr><td align=\"center\" style=\"padding:0 12px;\">");
+    html.push_str("<table role=\"presentation\" width=\"640\" cellpadding=\"0\" cellspacing=\"0\" style=\"width:100%;max-width:640px;background:#ffffff;border:1px solid #eaecef;border-radius:16px;overflow:hidden;\">");
+    html.push_str("<tr><td style=\"padding:18px 22px;background:#111827;color:#ffffff;\">");
+    html.push_str(
+        "<div style=\"font-size:16px;font-weight:800;\">SoloForge · Product Review</div>",
+    );
+    html.push_str("<div style=\"margin-top:6px;font-size:12px;opacity:0.9;\">A new product is waiting for approval</div>");
+    html.push_str("</td></tr>");
+
+    html.push_str("<tr><td style=\"padding:18px 22px;\">");
+    html.push_str("<div style=\"font-family:-apple-system,BlinkMacSystemFont,Segoe UI,Roboto,Helvetica,Arial,sans-serif;line-height:1.6;color:#111827;\">");
+    html.push_str(&format!(
+        "<div style=\"font-size:18px;font-weight:800;margin:0 0 6px 0;\">{}{}</div>",
+        build_email_logo_img(product.logo_url.as_deref(), product_name, 48),
+        html_escape(product_name)
+    ));
+    if !product_slogan.is_empty() {
+        html.push_str(&format!(
+            "<div style=\"font-size:13px;color:#4b5563;margin:0 0 10px 0;\">{}</div>",
+            html_escape(product_slogan)
+        ));
+    }
+
+    html.push_str("<table role=\"presentation\" width=\"100%\" cellpadding=\"0\" cellspacing=\"0\" style=\"border:1px solid #e5e7eb;border-radius:12px;overflow:hidden;\">");
+    html.push_str("<tr><td style=\"padding:12px 14px;\">");
+    html.push_str(&format!(
+        "<div style=\"font-size:12px;color:#6b7280;\">Category</div><div style=\"font-size:14px;font-weight:700;\">{}</div>",
+        html_escape(category)
+    ));
+    html.push_str("</td></tr>");
+    html.push_str("<tr><td style=\"padding:0 14px 12px 14px;\">");
+    html.push_str(&format!(
+        "<div style=\"font-size:12px;color:#6b7280;\">Maker</div><div style=\"font-size:14px;font-weight:700;\">{} ({})</div>",
+        html_escape(maker_name),
+        html_escape(maker_email)
+    ));
+    html.push_str("</td></tr>");
+    html.push_str("<tr><td style=\"padding:0 14px 12px 14px;\">");
+    html.push_str(&format!(
+        "<div style=\"font-size:12px;color:#6b7280;\">Website</div><div style=\"font-size:14px;font-weight:700;\"><a href=\"{}\" target=\"_blank\" rel=\"noreferrer\" style=\"color:#111827;text-decoration:underline;\">{}</a></div>",
+        html_attr_escape(product_website),
+        html_escape(product_website)
+    ));
+    html.push_str("</td></tr>");
+    html.push_str("<tr><td style=\"padding:0 14px 14px 14px;\">");
+    html.push_str(&format!(
+        "<div style=\"font-size:12px;color:#6b7280;\">Product ID</div><div style=\"font-size:13px;font-family:ui-monospace,SFMono-Regular,Menlo,Monaco,Consolas,monospace;\">{}</div>",
+        html_escape(product_id)
+    ));
+    html.push_str("</td></tr></table>");
+
+    if !product_desc.is_empty() {
+        let clipped: String = product_desc.chars().take(600).collect();
+        html.push_str("<div style=\"margin-top:14px;\">");
+        html.push_str(
+            "<div style=\"font-size:12px;color:#6b7280;margin-bottom:6px;\">Description</div>",
+        );
+        html.push_str(&format!(
+            "<div style=\"font-size:13px;color:#111827;background:#f9fafb;border:1px solid #e5e7eb;border-radius:12px;padding:12px 14px;white-space:pre-wrap;\">{}</div>",
+            html_escape(&clipped)
+        ));
+        html.push_str("</div>");
+    }
+
+    html.push_str("<div style=\"margin-top:14px;\">");
+    html.push_str(&format!(
+        "<a href=\"{}\" target=\"_blank\" rel=\"noreferrer\" style=\"display:inline-block;padding:10px 12px;margin:0 10px 10px 0;background:#111827;color:#ffffff;text-decoration:none;border-radius:10px;font-size:12px;font-weight:800;\">View detail page</a>",
+        html_attr_escape(&detail_url)
+    ));
+
+    if !approve_url.is_empty() && !reject_url.is_empty() {
+        html.push_str(&format!(
+            "<a href=\"{}\" target=\"_blank\" rel=\"noreferrer\" style=\"display:inline-block;padding:10px 12px;margin:0 10px 10px 0;background:#16a34a;color:#ffffff;text-decoration:none;border-radius:10px;font-size:12px;font-weight:800;\">Approve</a>",
+            html_attr_escape(&approve_url)
+        ));
+        html.push_str(&format!(
+            "<a href=\"{}\" target=\"_blank\" rel=\"noreferrer\" style=\"display:inline-block;padding:10px 12px;margin:0 10px 10px 0;background:#dc2626;color:#ffffff;text-decoration:none;border-radius:10px;font-size:12px;font-weight:800;\">Reject</a>",
+            html_attr_escape(&reject_url)
+        ));
+    } else {
+        html.push_str("<div style=\"margin-top:8px;font-size:12px;color:#6b7280;\">One-click review links are not configured. Set ADMIN_REVIEW_TOKEN_SECRET to enable.</div>");
+    }
+    html.push_str("</div>");
+
+    html.push_str("<div style=\"margin-top:16px;font-size:11px;color:#9ca3af;\">This message is sent automatically when a product is submitted.</div>");
+    html.push_str("</div></td></tr>");
+    html.push_str("</table></td></tr></table>");
+    html.push_str("</body></html>");
+
+    (subject, html, text)
+}
+
+/**
+ * build_maker_product_review_email_content
+ * 构建“产品审核结果（通过/拒绝）”通知给提交者的邮件内容（拒绝包含理由）。
+ */
+fn build_maker_product_review_email_content(
+    product: &Product,
+    frontend_base_url: &str,
+) -> (String, String, String) {
+    let is_zh = product
+        .language
+        .trim()
+        .to_ascii_lowercase()
+        .starts_with("zh");
+    let product_id = product.id.trim();
+    let product_name = product.name.trim();
+    let detail_url = build_product_detail_url(
+        frontend_base_url,
+        if is_zh { "zh" } else { "en" },
+        product_id,
+    );
+
+    let status = match product.status {
+        crate::models::ProductStatus::Approved => "approved",
+        crate::models::ProductStatus::Rejected => "rejected",
+        crate::models::ProductStatus::Pending => "pending",
+    };
+
+    let (subject, title_zh, title_en) = match product.status {
+        crate::models::ProductStatus::Approved => (
+            if is_zh {
+                format!("你的产品已通过审核：{}", product_name)
+            } else {
+                format!("Your product is approved: {}", product_name)
+            },
+            "审核通过",
+            "Approved",
+        ),
+        crate::models::ProductStatus::Rejected => (
+            if is_zh {
+                format!("你的产品未通过审核：{}", product_name)
+            } else {
+                format!("Your product is rejected: {}", product_name)
+            },
+            "未通过审核",
+            "Rejected",
+        ),
+        crate::models::ProductStatus::Pending => (
+            if is_zh {
+                format!("你的产品状态已更新：{}", product_name)
+            } else {
+                format!("Your product status updated: {}", product_name)
+            },
+            "状态更新",
+            "Status updated",
+        ),
+    };
+
+    let reason = product
+        .rejection_reason
+        .as_deref()
+        .unwrap_or("")
+        .trim()
+        .to_string();
+
+    let mut text = String::new();
+    if is_zh {
+        text.push_str(&format!("{}\n\n", title_zh));
+        text.push_str(&format!("产品：{}\n", product_name));
+        text.push_str(&format!("状态：{}\n", status));
+        if matches!(product.status, crate::models::ProductStatus::Rejected) && !reason.is_empty() {
+            text.push_str(&format!("理由：{}\n", reason));
+        }
+        text.push_str(&format!("详情：{}\n", detail_url));
+        text.push_str("\n---\n");
+        text.push_str(&format!("{}\n\n", title_en));
+        text.push_str(&format!("Product: {}\n", product_name));
+        text.push_str(&format!("Status: {}\n", status));
+        if matches!(product.status, crate::models::ProductStatus::Rejected) && !reason.is_empty() {
+            text.push_str(&format!("Reason: {}\n", reason));
+        }
+        text.push_str(&format!("Details: {}\n", detail_url));
+    } else {
+        text.push_str(&format!("{}\n\n", title_en));
+        text.push_str(&format!("Product: {}\n", product_name));
+        text.push_str(&format!("Status: {}\n", status));
+        if matches!(product.status, crate::models::ProductStatus::Rejected) && !reason.is_empty() {
+            text.push_str(&format!("Reason: {}\n", reason));
+        }
+        text.push_str(&format!("Details: {}\n", detail_url));
+        text.push_str("\n---\n");
+        text.push_str(&format!("{}\n\n", title_zh));
+        text.push_str(&format!("产品：{}\n", product_name));
+        text.push_str(&format!("状态：{}\n", status));
+        if matches!(product.status, crate::models::ProductStatus::Rejected) && !reason.is_empty() {
+            text.push_str(&format!("理由：{}\n", reason));
+        }
+        text.push_str(&format!("详情：{}\n", detail_url));
+    }
+
+    let mut html = String::new();
+    html.push_str("<!doctype html><html><body style=\"margin:0;padding:0;background:#f6f7fb;\">");
+    html.push_str("<table role=\"presentation\" width=\"100%\" cellpadding=\"0\" cellspacing=\"0\" style=\"background:#f6f7fb;padding:24px 0;\">");
+    html.push_str("<tr><td align=\"center\" style=\"padding:0 12px;\">");
+    html.push_str("<table role=\"presentation\" width=\"600\" cellpadding=\"0\" cellspacing=\"0\" style=\"width:100%;max-width:600px;background:#ffffff;border:1px solid #eaecef;border-radius:16px;overflow:hidden;\">");
+    html.push_str("<tr><td style=\"padding:18px 22px;background:#111827;color:#ffffff;\">");
+    html.push_str(&format!(
+        "<div style=\"font-size:16px;font-weight:800;\">{}</div>",
+        html_escape(if is_zh { title_zh } else { title_en })
+    ));
+    html.push_str("</td></tr>");
+    html.push_str("<tr><td style=\"padding:18px 22px;\">");
+    html.push_str("<div style=\"font-family:-apple-system,BlinkMacSystemFont,Segoe UI,Roboto,Helvetica,Arial,sans-serif;line-height:1.6;color:#111827;font-size:14px;\">");
+    html.push_str(&format!(
+        "<div style=\"font-size:16px;font-weight:800;margin:0 0 8px 0;\">{}</div>",
+        html_escape(product_name)
+    ));
+    html.push_str(&format!(
+        "<div style=\"margin:0 0 12px 0;color:#6b7280;\">Status: <strong style=\"color:#111827;\">{}</strong></div>",
+        html_escape(status)
+    ));
+    if matches!(product.status, crate::models::ProductStatus::Rejected) && !reason.is_empty() {
+        html.push_str(&format!(
+            "<div style=\"margin:0 0 12px 0;\"><div style=\"font-weight:700;margin-bottom:6px;\">Reason / 理由</div><div style=\"white-space:pre-wrap;color:#111827;background:#f9fafb;border:1px solid #e5e7eb;border-radius:12px;padding:12px 14px;\">{}</div></div>",
+            html_escape(&reason)
+        ));
+    }
+    html.push_str(&format!(
+        "<a href=\"{}\" target=\"_blank\" rel=\"noreferrer\" style=\"display:inline-block;padding:10px 12px;background:#111827;color:#ffffff;text-decoration:none;border-radius:10px;font-size:12px;font-weight:800;\">{}</a>",
+        html_attr_escape(&detail_url),
+        if is_zh { "查看详情" } else { "View details" }
+    ));
+    html.push_str("</div></td></tr></table></td></tr></table>");
+    html.push_str("</body></html>");
+
+    (subject, html, text)
+}
+
+/**
+ * SponsorshipReceipt
+ * 赞助订单收据邮件所需的订单与生效区间信息。
+ */
+struct SponsorshipReceipt<'a> {
+    order_id: &'a str,
+    product_name: &'a str,
+    placement: &'a str,
+    months: i32,
+    amount_usd_cents: i32,
+    starts_at: chrono::DateTime<chrono::Utc>,
+    ends_at: chrono::DateTime<chrono::Utc>,
+}
+
+fn format_usd_cents(cents: i32) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    let abs = (cents as i64).abs();
+    format!("{}${}.{:02}", sign, abs / 100, abs % 100)
+}
+
+/**
+ * build_sponsorship_receipt_email_content
+ * 构建“赞助订单已支付”的收据邮件内容（订单号、产品、展示位、月数、实付金额、生效区间），中英双语。
+ */
+fn build_sponsorship_receipt_email_content(
+    receipt: &SponsorshipReceipt<'_>,
+    is_zh: bool,
+) -> (String, String, String) {
+    let product_name = receipt.product_name.trim();
+    let subject = if is_zh {
+        format!("赞助收据：{}", product_name)
+    } else {
+        format!("Sponsorship receipt: {}", product_name)
+    };
+
+    let amount = format_usd_cents(receipt.amount_usd_cents);
+    let starts = receipt.starts_at.format("%Y-%m-%d").to_string();
+    let ends = receipt.ends_at.format("%Y-%m-%d").to_string();
+
+    let zh_lines = [
+        ("订单号", receipt.order_id.to_string()),
+        ("产品", product_name.to_string()),
+        ("展示位", receipt.placement.to_string()),
+        ("月数", receipt.months.to_string()),
+        ("实付金额", format!("{} USD", amount)),
+        ("生效时间", format!("{} ~ {}", starts, ends)),
+    ];
+    let en_lines = [
+        ("Order", receipt.order_id.to_string()),
+        ("Product", product_name.to_string()),
+        ("Placement", receipt.placement.to_string()),
+        ("Months", receipt.months.to_string()),
+        ("Amount paid", format!("{} USD", amount)),
+        ("Active", format!("{} – {}", starts, ends)),
+    ];
+
+    let push_zh = |text: &mut String| {
+        text.push_str("赞助付款成功\n\n");
+        for (label, value) in &zh_lines {
+            text.push_str(&format!("{}：{}\n", label, value));
+        }
+    };
+    let push_en = |text: &mut String| {
+        text.push_str("Sponsorship payment received\n\n");
+        for (label, value) in &en_lines {
+            text.push_str(&format!("{}: {}\n", label, value));
+        }
+    };
+
+    let mut text = String::new();
+    if is_zh {
+        push_zh(&mut text);
+        text.push_str("\n---\n");
+        push_en(&mut text);
+    } else {
+        push_en(&mut text);
+        text.push_str("\n---\n");
+        push_zh(&mut text);
+    }
+
+    let mut html = String::new();
+    html.push_str("<!doctype html><html><body style=\"margin:0;padding:0;background:#f6f7fb;\">");
+    html.push_str("<table role=\"presentation\" width=\"100%\" cellpadding=\"0\" cellspacing=\"0\" style=\"background:#f6f7fb;padding:24px 0;\">");
+    html.push_str("<tr><td align=\"center\" style=\"padding:0 12px;\">");
+    html.push_str("<table role=\"presentation\" width=\"600\" cellpadding=\"0\" cellspacing=\"0\" style=\"width:100%;max-width:600px;background:#ffffff;border:1px solid #eaecef;border-radius:16px;overflow:hidden;\">");
+    html.push_str("<tr><td style=\"padding:18px 22px;background:#111827;color:#ffffff;\">");
+    html.push_str(&format!(
+        "<div style=\"font-size:16px;font-weight:800;\">{}</div>",
+        html_escape(if is_zh {
+            "赞助付款成功"
+        } else {
+            "Sponsorship payment received"
+        })
+    ));
+    html.push_str("</td></tr>");
+    html.push_str("<tr><td style=\"padding:18px 22px;\">");
+    html.push_str("<div style=\"font-family:-apple-system,BlinkMacSystemFont,Segoe UI,Roboto,Helvetica,Arial,sans-serif;line-height:1.6;color:#111827;font-size:14px;\">");
+    html.push_str(
+        "<table role=\"presentation\" cellpadding=\"0\" cellspacing=\"0\" style=\"width:100%;\">",
+    );
+    for ((zh_label, value), (en_label, _)) in zh_lines.iter().zip(en_lines.iter()) {
+        let label = if is_zh {
+            format!("{} / {}", zh_label, en_label)
+        } else {
+            format!("{} / {}", en_label, zh_label)
+        };
+        html.push_str(&format!(
+            "<tr><td style=\"padding:4px 12px 4px 0;color:#6b7280;white-space:nowrap;\">{}</td><td style=\"padding:4px 0;font-weight:700;\">{}</td></tr>",
+            html_escape(&label),
+            html_escape(value)
+        ));
+    }
+    html.push_str("</table>");
+    html.push_str("</div></td></tr></table></td></tr></table>");
+    html.push_str("</body></html>");
+
+    (subject, html, text)
+}
+
+/**
+ * admin_notify_recipients
+ * 管理员通知收件人：ADMIN_NOTIFY_EMAILS（逗号分隔），未配置时回退到 ADMIN_REVIEW_EMAIL。
+ */
+fn admin_notify_recipients() -> Vec<String> {
+    let recipients_raw = env::var("ADMIN_NOTIFY_EMAILS")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .or_else(|| env::var("ADMIN_REVIEW_EMAIL").ok())
+        .unwrap_or_else(|| "2217021563@qq.com".to_string());
+    parse_admin_notify_emails(&recipients_raw)
+}
+
+/**
+ * admin_notify_from
+ * 管理员通知发件人：ADMIN_REVIEW_FROM，回退到 NEWSLETTER_FROM；均未配置时返回 None 并记录日志。
+ */
+fn admin_notify_from() -> Option<String> {
+    let from = env::var("ADMIN_REVIEW_FROM")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .or_else(|| env::var("NEWSLETTER_FROM").ok())
+        .unwrap_or_default();
+    if from.trim().is_empty() {
+        log::warn!("Admin notify sender not configured: ADMIN_REVIEW_FROM/NEWSLETTER_FROM missing");
+        return None;
+    }
+    Some(from)
+}
+
+/**
+ * send_admin_notify_emails
+ * 逐个发送给管理员收件人；至少一人送达即视为成功，全部失败时返回最后一个错误。
+ */
+async fn send_admin_notify_emails(
+    client: &Client,
+    resend_key: &str,
+    from: &str,
+    recipients: &[String],
+    subject: &str,
+    html: &str,
+    text: &str,
+) -> Result<()> {
+    let mut delivered = 0usize;
+    let mut last_err: Option<anyhow::Error> = None;
+    for to in recipients {
+        match send_email_resend(client, resend_key, from, to, subject, Some(html), text).await {
+            Ok(()) => delivered += 1,
+            Err(e) => {
+                log::warn!("Admin notify send failed to={} err={:?}", to, e);
+                last_err = Some(e);
+            }
+        }
+    }
+
+    match last_err {
+        Some(e) if delivered == 0 => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/**
+ * build_admin_sponsorship_request_email_content
+ * 构建“新赞助申请”管理员通知邮件：申请邮箱、自动匹配的产品（匹配失败时提示手动指定）、展示位、时长与后台审核链接。
+ */
+fn build_admin_sponsorship_request_email_content(
+    request: &SponsorshipRequest,
+    product: Option<&Product>,
+    review_url: &str,
+) -> (String, String, String) {
+    let subject = format!(
+        "New sponsorship request #{}: {}",
+        request.id,
+        product
+            .map(|p| p.name.trim())
+            .unwrap_or_else(|| request.product_ref.trim())
+    );
+
+    let product_line = match product {
+        Some(p) => format!("{} ({})", p.name.trim(), p.id.trim()),
+        None => format!(
+            "{} (not matched, set product_id when approving)",
+            request.product_ref.trim()
+        ),
+    };
+    let slot = request
+        .slot_index
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| "any".to_string());
+    let lines = [
+        ("Request", format!("#{}", request.id)),
+        ("Email", request.email.trim().to_string()),
+        ("Product", product_line),
+        ("Product ref", request.product_ref.trim().to_string()),
+        (
+            "Placement",
+            format!("{} (slot {})", request.placement.trim(), slot),
+        ),
+        ("Duration", format!("{} days", request.duration_days)),
+    ];
+    let note = request
+        .note
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty());
+
+    let mut text = String::new();
+    text.push_str("New sponsorship request (pending review)\n\n");
+    for (label, value) in &lines {
+        text.push_str(&format!("{}: {}\n", label, value));
+    }
+    if let Some(note) = note {
+        text.push_str(&format!("Note: {}\n", note));
+    }
+    text.push_str(&format!("\nReview: {}\n", review_url));
+
+    let mut html = String::new();
+    html.push_str("<!doctype html><html><body style=\"margin:0;padding:0;background:#f6f7fb;\">");
+    html.push_str("<table role=\"presentation\" width=\"100%\" cellpadding=\"0\" cellspacing=\"0\" style=\"background:#f6f7fb;padding:24px 0;\">");
+    html.push_str("<tr><td align=\"center\" style=\"padding:0 12px;\">");
+    html.push_str("<table role=\"presentation\" width=\"600\" cellpadding=\"0\" cellspacing=\"0\" style=\"width:100%;max-width:600px;background:#ffffff;border:1px solid #eaecef;border-radius:16px;overflow:hidden;\">");
+    html.push_str("<tr><td style=\"padding:18px 22px;background:#111827;color:#ffffff;\">");
+    html.push_str(
+        "<div style=\"font-size:16px;font-weight:800;\">SoloForge · Sponsorship Request</div>",
+    );
+    html.push_str("<div style=\"margin-top:6px;font-size:12px;opacity:0.9;\">A new sponsorship request is waiting for review</div>");
+    html.push_str("</td></tr>");
+    html.push_str("<tr><td style=\"padding:18px 22px;\">");
+    html.push_str("<div style=\"font-family:-apple-system,BlinkMacSystemFont,Segoe UI,Roboto,Helvetica,Arial,sans-serif;line-height:1.6;color:#111827;font-size:14px;\">");
+    html.push_str(
+        "<table role=\"presentation\" cellpadding=\"0\" cellspacing=\"0\" style=\"width:100%;\">",
+    );
+    for (label, value) in &lines {
+        html.push_str(&format!(
+            "<tr><td style=\"padding:4px 12px 4px 0;color:#6b7280;white-space:nowrap;\">{}</td><td style=\"padding:4px 0;font-weight:700;\">{}</td></tr>",
+            html_escape(label),
+            html_escape(value)
+        ));
+    }
+    html.push_str("</table>");
+    if let Some(note) = note {
+        html.push_str(&format!(
+            "<div style=\"margin-top:14px;font-size:13px;background:#f9fafb;border:1px solid #e5e7eb;border-radius:12px;padding:12px 14px;white-space:pre-wrap;\">{}</div>",
+            html_escape(note)
+        ));
+    }
+    html.push_str(&format!(
+        "<div style=\"margin-top:14px;\"><a href=\"{}\" target=\"_blank\" rel=\"noreferrer\" style=\"display:inline-block;padding:10px 12px;background:#111827;color:#ffffff;text-decoration:none;border-radius:10px;font-size:12px;font-weight:800;\">Review request</a></div>",
+        html_attr_escape(review_url)
+    ));
+    html.push_str("<div style=\"margin-top:16px;font-size:11px;color:#9ca3af;\">This message is sent automatically when a sponsorship request is submitted.</div>");
+    html.push_str("</div></td></tr></table></td></tr></table>");
+    html.push_str("</body></html>");
+
+    (subject, html, text)
+}
+
+/**
+ * build_admin_sponsorship_request_review_url
+ * 管理后台中该赞助申请的审核链接：优先 ADMIN_APP_URL，未配置时回退到 FRONTEND_BASE_URL/admin。
+ */
+fn build_admin_sponsorship_request_review_url(
+    admin_app_url: Option<&str>,
+    frontend_base_url: &str,
+    request_id: i64,
+) -> String {
+    let base = admin_app_url
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(normalize_base_url)
+        .unwrap_or_else(|| format!("{}/admin", normalize_base_url(frontend_base_url)));
+    format!("{}/sponsorship?id={}", base, request_id)
+}
+
+pub(crate) const SEARCH_SNIPPET_MAX_CHARS: usize = 160;
+
+fn fold_char(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn find_folded(hay: &[char], needle: &[char], from: usize) -> Option<usize> {
+    if needle.is_empty() || hay.len() < needle.len() {
+        return None;
+    }
+    (from..=hay.len() - needle.len()).find(|&i| {
+        needle
+            .iter()
+            .enumerate()
+            .all(|(j, n)| fold_char(hay[i + j]) == fold_char(*n))
+    })
+}
+
+/**
+ * build_search_snippet
+ * 截取命中关键词附近的片段（最多 max_chars 个字符，不区分大小写），命中处包裹 <mark>，其余文本做 HTML 转义；
+ * 优先匹配完整查询，其次匹配最长的单个词；未命中返回 None。
+ */
+pub(crate) fn build_search_snippet(text: &str, query: &str, max_chars: usize) -> Option<String> {
+    let hay: Vec<char> = text.trim().chars().collect();
+    let mut terms: Vec<&str> = query.split_whitespace().collect();
+    terms.sort_by_key(|t| std::cmp::Reverse(t.chars().count()));
+    let whole = query.trim();
+    let (needle, first) = std::iter::once(whole)
+        .chain(terms)
+        .filter(|t| !t.is_empty())
+        .find_map(|t| {
+            let needle: Vec<char> = t.chars().collect();
+            find_folded(&hay, &needle, 0).map(|idx| (needle, idx))
+        })?;
+
+    let max_chars = max_chars.max(needle.len());
+    let context = (max_chars - needle.len()) / 3;
+    let mut start = first.saturating_sub(context);
+    let end = (start + max_chars).min(hay.len());
+    if end - start < max_chars {
+        start = end.saturating_sub(max_chars);
+    }
+
+    let mut out = String::new();
+    if start > 0 {
+        out.push('…');
+    }
+    let mut cursor = start;
+    while let Some(idx) = find_folded(&hay[..end], &needle, cursor) {
+        let plain: String = hay[cursor..idx].iter().collect();
+        let hit: String = hay[idx..idx + needle.len()].iter().collect();
+        out.push_str(&html_escape(&plain));
+        out.push_str("<mark>");
+        out.push_str(&html_escape(&hit));
+        out.push_str("</mark>");
+        cursor = idx + needle.len();
+    }
+    let rest: String = hay[cursor..end].iter().collect();
+    out.push_str(&html_escape(&rest));
+    if end < hay.len() {
+        out.push('…');
+    }
+    Some(out)
+}
+
+pub(crate) const OUTGOING_WEBHOOK_EVENTS: [&str; 3] =
+    ["product.created", "product.approved", "product.rejected"];
+const OUTGOING_WEBHOOK_MAX_ATTEMPTS: i32 = 6;
+
+/**
+ * validate_outgoing_webhook_request
+ * 校验 webhook 订阅：url 必须是 http(s)，events 非空且均为已知事件，新建时必须提供 secret。
+ */
+pub(crate) fn validate_outgoing_webhook_request(
+    req: &crate::models::UpsertOutgoingWebhookRequest,
+) -> std::result::Result<(), String> {
+    match Url::parse(req.url.trim()) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") && url.host_str().is_some() => {}
+        _ => return Err("url must be an absolute http(s) URL".to_string()),
+    }
+    if req.events.is_empty() {
+        return Err("events must not be empty".to_string());
+    }
+    if let Some(unknown) = req
+        .events
+        .iter()
+        .find(|e| !OUTGOING_WEBHOOK_EVENTS.contains(&e.trim()))
+    {
+        return Err(format!("Unknown event: {}", unknown));
+    }
+    let has_secret = req.secret.as_deref().is_some_and(|s| !s.trim().is_empty());
+    if req.id.is_none() && !has_secret {
+        return Err("secret is required".to_string());
+    }
+    Ok(())
+}
+
+/**
+ * compute_webhook_signature
+ * 对外 webhook 签名：HMAC-SHA256(secret, body)，URL-safe base64（无填充），放在 X-Signature 头。
+ */
+pub(crate) fn compute_webhook_signature(secret: &str, body: &str) -> Result<String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .map_err(|_| anyhow::anyhow!("Invalid webhook secret"))?;
+    mac.update(body.as_bytes());
+    Ok(general_purpose::URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes()))
+}
+
+/**
+ * webhook_retry_delay_secs
+ * 第 attempts 次投递失败后的重试间隔：30s 起指数退避，最长 1 小时。
+ */
+pub(crate) fn webhook_retry_delay_secs(attempts: i32) -> i64 {
+    let exp = attempts.clamp(1, 16) - 1;
+    (30i64 << exp).min(3600)
+}
+
+/**
+ * webhook_disable_after_failures
+ * WEBHOOK_DISABLE_AFTER_FAILURES：同一订阅连续失败达到该次数后自动停用（默认 10）。
+ */
+fn webhook_disable_after_failures() -> i32 {
+    env::var("WEBHOOK_DISABLE_AFTER_FAILURES")
+        .ok()
+        .and_then(|v| v.trim().parse::<i32>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(10)
+}
+
+/**
+ * fill_engagement_series
+ * 将按天聚合的 (day, likes, favorites) 补齐为从 today 往前共 days 天的连续序列（缺失日期计 0，按日期升序）。
+ */
+pub(crate) fn fill_engagement_series(
+    rows: &[(chrono::DateTime<chrono::Utc>, i64, i64)],
+    today: chrono::DateTime<chrono::Utc>,
+    days: i64,
+) -> Vec<ProductEngagementDay> {
+    let days = days.clamp(1, 365);
+    let today = today.date_naive();
+    let by_day: HashMap<chrono::NaiveDate, (i64, i64)> = rows
+        .iter()
+        .map(|(day, likes, favorites)| (day.date_naive(), (*likes, *favorites)))
+        .collect();
+    (0..days)
+        .rev()
+        .map(|offset| {
+            let date = today - chrono::Duration::days(offset);
+            let (likes, favorites) = by_day.get(&date).copied().unwrap_or((0, 0));
+            ProductEngagementDay {
+                day: chrono::Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap_or_default()),
+                likes,
+                favorites,
+            }
+        })
+        .collect()
+}
+
+/**
+ * encode_product_changes_cursor
+ * 将 (updated_at, id) 编码为增量同步接口的不透明游标。
+ */
+pub(crate) fn encode_product_changes_cursor(
+    updated_at: chrono::DateTime<chrono::Utc>,
+    id: &str,
+) -> String {
+    let raw = format!(
+        "{}|{}",
+        updated_at.to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+        id
+    );
+    general_purpose::URL_SAFE_NO_PAD.encode(raw.as_bytes())
+}
+
+/**
+ * decode_product_changes_cursor
+ * 解析增量同步游标；格式不合法时返回 None。
+ */
+pub(crate) fn decode_product_changes_cursor(
+    cursor: &str,
+) -> Option<(chrono::DateTime<chrono::Utc>, String)> {
+    let bytes = general_purpose::URL_SAFE_NO_PAD
+        .decode(cursor.trim())
+        .ok()?;
+    let raw = String::from_utf8(bytes).ok()?;
+    let (ts, id) = raw.split_once('|')?;
+    let updated_at = chrono::DateTime::parse_from_rfc3339(ts)
+        .ok()?
+        .with_timezone(&chrono::Utc);
+    let id = id.trim();
+    if id.is_empty() {
+        return None;
+    }
+    Some((updated_at, id.to_string()))
+}
+
+/**
+ * escape_like_pattern
+ * 转义 LIKE/ILIKE 模式中的通配符（% _ \），使用户输入按字面匹配。
+ */
+fn escape_like_pattern(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    for c in raw.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+pub(crate) const MAX_PRODUCT_TAGS: usize = 10;
+pub(crate) const MAX_PRODUCT_NAME_CHARS: usize = 80;
+pub(crate) const MAX_PRODUCT_SLOGAN_CHARS: usize = 160;
+pub(crate) const MAX_PRODUCT_DESCRIPTION_CHARS: usize = 5000;
+pub(crate) const MAX_PRODUCT_TAG_CHARS: usize = 32;
+
+/**
+ * ProductFieldTooLongError
+ * 产品字段超出长度上限（按 Unicode 字符计），由处理器按请求语言转换为 400。
+ */
+#[derive(Debug)]
+pub struct ProductFieldTooLongError {
+    pub field: &'static str,
+    pub max_chars: usize,
+    pub actual_chars: usize,
+}
+
+impl ProductFieldTooLongError {
+    pub fn localized_message(&self, lang: &str) -> String {
+        if lang.starts_with("zh") {
+            format!(
+                "字段 {} 最多 {} 个字符（当前 {}）。",
+                self.field, self.max_chars, self.actual_chars
+            )
+        } else {
+            format!(
+                "Field {} must be at most {} characters (current {}).",
+                self.field, self.max_chars, self.actual_chars
+            )
+        }
+    }
+}
+
+impl std::fmt::Display for ProductFieldTooLongError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Field {} exceeds {} characters ({})",
+            self.field, self.max_chars, self.actual_chars
+        )
+    }
+}
+
+impl std::error::Error for ProductFieldTooLongError {}
+
+/**
+ * MAX_PRODUCTS_BY_IDS
+ * get_products_by_ids 单次允许查询的 id 数量上限，超出时返回 TooManyProductIdsError。
+ */
+pub const MAX_PRODUCTS_BY_IDS: usize = 200;
+
+/**
+ * TooManyProductIdsError
+ * 批量查询的产品 id 超过 MAX_PRODUCTS_BY_IDS；目前只有内部调用方（按上限分批），不会直接返回给客户端。
+ */
+#[derive(Debug)]
+pub struct TooManyProductIdsError {
+    pub max: usize,
+    pub actual: usize,
+}
+
+impl std::fmt::Display for TooManyProductIdsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Too many product ids: {} (max {})",
+            self.actual, self.max
+        )
+    }
+}
+
+impl std::error::Error for TooManyProductIdsError {}
+
+/**
+ * bump_cooldown_days
+ * BUMP_COOLDOWN_DAYS：同一产品两次“顶一下”之间的最短间隔天数（默认 7，范围 1~365）。
+ */
+pub(crate) fn bump_cooldown_days() -> i64 {
+    bump_cooldown_days_from_env_value(env::var("BUMP_COOLDOWN_DAYS").ok().as_deref())
+}
+
+fn bump_cooldown_days_from_env_value(raw: Option<&str>) -> i64 {
+    raw.and_then(|v| v.trim().parse::<i64>().ok())
+        .unwrap_or(7)
+        .clamp(1, 365)
+}
+
+/**
+ * ProductBumpCooldownError
+ * 产品仍在顶帖冷却期内，next_allowed_at 为下一次允许的时间，由处理器转换为 429。
+ */
+#[derive(Debug)]
+pub struct ProductBumpCooldownError {
+    pub next_allowed_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ProductBumpCooldownError {
+    pub fn localized_message(&self, lang: &str) -> String {
+        let at = self
+            .next_allowed_at
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        if lang.starts_with("zh") {
+            format!("冷却中，{} 之后可以再次顶帖。", at)
+        } else {
+            format!("Bump is on cooldown until {}.", at)
+        }
+    }
+}
+
+impl std::fmt::Display for ProductBumpCooldownError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Product bump on cooldown until {}", self.next_allowed_at)
+    }
+}
+
+impl std::error::Error for ProductBumpCooldownError {}
+
+/**
+ * InvalidWebsiteUrlError
+ * 网站地址不是 http(s) 链接（如 ftp://、javascript:）或无法解析出主机，由处理器按请求语言转换为 400。
+ */
+#[derive(Debug, PartialEq)]
+pub struct InvalidWebsiteUrlError {
+    pub field: &'static str,
+    pub value: String,
+}
+
+impl InvalidWebsiteUrlError {
+    pub fn localized_message(&self, lang: &str) -> String {
+        if lang.starts_with("zh") {
+            format!("字段 {} 必须是 http(s) 网址。", self.field)
+        } else {
+            format!("Field {} must be an http(s) URL.", self.field)
+        }
+    }
+}
+
+impl std::fmt::Display for InvalidWebsiteUrlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Field {} is not an http(s) URL: {}",
+            self.field, self.value
+        )
+    }
+}
+
+impl std::error::Error for InvalidWebsiteUrlError {}
+
+/**
+ * normalize_website_url
+ * 规范化用户填写的网站：空值表示“无网站”；缺少 scheme 时补 https://；
+ * 只接受 http / https 且能解析出主机的地址。`example.com:8080` 这类“主机:端口”不视为 scheme。
+ */
+pub(crate) fn normalize_website_url(
+    field: &'static str,
+    raw: &str,
+) -> std::result::Result<Option<String>, InvalidWebsiteUrlError> {
+    let value = raw.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    let invalid = || InvalidWebsiteUrlError {
+        field,
+        value: value.to_string(),
+    };
+
+    let has_scheme = match value.split_once(':') {
+        Some((scheme, rest)) => {
+            let scheme_like = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+            scheme_like
+                && (rest.starts_with("//") || !rest.starts_with(|c: char| c.is_ascii_digit()))
+        }
+        None => false,
+    };
+    let candidate = if has_scheme {
+        value.to_string()
+    } else {
+        format!("https://{}", value)
+    };
+
+    let parsed = Url::parse(&candidate).map_err(|_| invalid())?;
+    if !matches!(parsed.scheme(), "http" | "https")
+        || parsed.host_str().is_none_or(|h| h.is_empty())
+    {
+        return Err(invalid());
+    }
+    Ok(Some(candidate))
+}
+
+fn check_field_length(
+    field: &'static str,
+    value: &str,
+    max_chars: usize,
+) -> std::result::Result<(), ProductFieldTooLongError> {
+    let actual_chars = value.trim().chars().count();
+    if actual_chars > max_chars {
+        return Err(ProductFieldTooLongError {
+            field,
+            max_chars,
+            actual_chars,
+        });
+    }
+    Ok(())
+}
+
+fn check_tag_lengths(tags: &[String]) -> std::result::Result<(), ProductFieldTooLongError> {
+    tags.iter()
+        .try_for_each(|tag| check_field_length("tags", tag, MAX_PRODUCT_TAG_CHARS))
+}
+
+/**
+ * normalize_tags
+ * 规范化标签：去首尾空白、转小写、内部空白折叠为单个连字符，并去掉空值与重复（保持原顺序）。
+ */
+pub(crate) fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = strip_nul_str(tag)
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join("-")
+            .to_lowercase();
+        if !tag.is_empty() && !out.contains(&tag) {
+            out.push(tag);
+        }
+    }
+    out
+}
+
+pub(crate) fn is_valid_email_basic(email: &str) -> bool {
+    let e = email.trim();
+    if e.is_empty() || e.len() > 320 {
+        return false;
+    }
+    let at = match e.find('@') {
+        Some(v) => v,
+        None => return false,
+    };
+    if at == 0 || at + 1 >= e.len() {
+        return false;
+    }
+    let domain = &e[at + 1..];
+    domain.contains('.') && !domain.starts_with('.') && !domain.ends_with('.')
+}
+
+/**
+ * parse_admin_notify_emails
+ * 解析管理员通知收件人列表（逗号分隔），去重并跳过格式不合法的地址。
+ */
+fn parse_admin_notify_emails(raw: &str) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for item in raw.split(',') {
+        let email = item.trim().to_ascii_lowercase();
+        if email.is_empty() {
+            continue;
+        }
+        if !is_valid_email_basic(&email) {
+            log::warn!("Skipping invalid admin notify email: {}", email);
+            continue;
+        }
+        if !out.contains(&email) {
+            out.push(email);
+        }
+    }
+    out
+}
+
+/**
+ * initial_product_status
+ * 计算新提交产品的初始状态：提交者已通过 Bearer token 证明拥有 maker_email（maker_verified），
+ * 且邮箱在 AUTO_APPROVE_EMAILS 白名单内或创作者已认证时直接 approved，否则 pending。
+ */
+fn initial_product_status(
+    maker_email: &str,
+    maker_verified: bool,
+    allowlist_raw: &str,
+    sponsor_verified: bool,
+) -> crate::models::ProductStatus {
+    if !maker_verified {
+        return crate::models::ProductStatus::Pending;
+    }
+    let email = maker_email.trim().to_ascii_lowercase();
+    let allowlisted = !email.is_empty()
+        && allowlist_raw
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .map(|v| v.trim())
+            .any(|v| !v.is_empty() && v.eq_ignore_ascii_case(&email));
+    if allowlisted || sponsor_verified {
+        crate::models::ProductStatus::Approved
+    } else {
+        crate::models::ProductStatus::Pending
+    }
+}
+
+/**
+ * DEVELOPER_SPONSOR_ROLES
+ * 管理端可设置的 sponsor_role 白名单（sponsor 为赞助审批流程写入的默认值）。
+ */
+pub(crate) const DEVELOPER_SPONSOR_ROLES: &[&str] = &["sponsor", "bronze", "silver", "gold"];
+
+/**
+ * UnsupportedFilterError
+ * Supabase（PostgREST）分支无法表达的过滤条件：直接报错而不是静默忽略、返回错误数据。
+ */
+#[derive(Debug)]
+pub struct UnsupportedFilterError {
+    pub filter: &'static str,
+}
+
+impl std::fmt::Display for UnsupportedFilterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Filter not supported by the Supabase backend: {}",
+            self.filter
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedFilterError {}
+
+/**
+ * review_on_edit_fields
+ * 解析 REVIEW_ON_EDIT_FIELDS（逗号分隔）：创作者修改这些字段时已通过的产品需重新审核。
+ * 未设置时默认 name,website；设置为空字符串时关闭该行为。
+ */
+pub(crate) fn review_on_edit_fields(raw: Option<&str>) -> Vec<String> {
+    raw.unwrap_or("name,website")
+        .split(',')
+        .map(|v| v.trim().to_ascii_lowercase())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+/**
+ * requires_re_review
+ * 判断本次更新是否需要把已通过的产品重置为 pending：仅针对非管理员修改，且修改了 fields 中的字段。
+ */
+pub(crate) fn requires_re_review(
+    existing: &Product,
+    updates: &UpdateProductRequest,
+    fields: &[String],
+    is_admin: bool,
+) -> bool {
+    if is_admin || !matches!(existing.status, crate::models::ProductStatus::Approved) {
+        return false;
+    }
+    let changed =
+        |next: Option<&str>, current: &str| next.is_some_and(|v| v.trim() != current.trim());
+    fields.iter().any(|field| match field.as_str() {
+        "name" => changed(updates.name.as_deref(), &existing.name),
+        "slogan" => changed(updates.slogan.as_deref(), &existing.slogan),
+        "description" => changed(updates.description.as_deref(), &existing.description),
+        "website" => changed(updates.website.as_deref(), &existing.website),
+        "logo_url" => changed(
+            updates.logo_url.as_deref(),
+            existing.logo_url.as_deref().unwrap_or(""),
+        ),
+        "category" => changed(updates.category.as_deref(), &existing.category),
+        "tags" => updates
+            .tags
+            .as_deref()
+            .is_some_and(|tags| normalize_tags(tags) != normalize_tags(&existing.tags)),
+        _ => false,
+    })
+}
+
+fn is_cjk_char(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF | 0x20000..=0x2A6DF
+    )
+}
+
+/**
+ * detect_language
+ * 根据名称/标语/描述中的汉字占比推断产品语言：汉字不少于 2 个且约占字母的 1/6 以上判为 zh，否则 en。
+ */
+pub(crate) fn detect_language(texts: &[&str]) -> &'static str {
+    let (mut cjk, mut latin) = (0usize, 0usize);
+    for c in texts.iter().flat_map(|t| t.chars()) {
+        if is_cjk_char(c) {
+            cjk += 1;
+        } else if c.is_ascii_alphabetic() {
+            latin += 1;
+        }
+    }
+    if cjk >= 2 && cjk * 5 >= latin {
+        "zh"
+    } else {
+        "en"
+    }
+}
+
+/**
+ * apply_detected_language
+ * language 为空或显式要求自动识别时写入识别结果；否则保留提交者填写的语言。
+ */
+pub(crate) fn apply_detected_language(product: &mut CreateProductRequest, force: bool) {
+    if force || product.language.trim().is_empty() {
+        product.language = detect_language(&[
+            product.name.as_str(),
+            product.slogan.as_str(),
+            product.description.as_str(),
+        ])
+        .to_string();
+    }
+}
+
+fn sanitize_create_product_request(
+    product: &mut CreateProductRequest,
+) -> std::result::Result<(), ProductFieldTooLongError> {
+    strip_nul_in_place(&mut product.name);
+    check_field_length("name", &product.name, MAX_PRODUCT_NAME_CHARS)?;
+    strip_nul_in_place(&mut product.slogan);
+    check_field_length("slogan", &product.slogan, MAX_PRODUCT_SLOGAN_CHARS)?;
+    strip_nul_in_place(&mut product.description);
+    check_field_length(
+        "description",
+        &product.description,
+        MAX_PRODUCT_DESCRIPTION_CHARS,
+    )?;
+    strip_nul_in_place(&mut product.website);
+    strip_nul_in_place_opt(&mut product.logo_url);
+    strip_nul_in_place(&mut product.category);
+    product.tags = normalize_tags(&product.tags);
+    product.tags.truncate(MAX_PRODUCT_TAGS);
+    check_tag_lengths(&product.tags)?;
+    strip_nul_in_place(&mut product.maker_name);
+    strip_nul_in_place(&mut product.maker_email);
+    product.maker_email = product.maker_email.trim().to_ascii_lowercase();
+    strip_nul_in_place_opt(&mut product.maker_website);
+    strip_nul_in_place(&mut product.language);
+    Ok(())
+}
+
+fn sanitize_update_product_request(
+    updates: &mut UpdateProductRequest,
+) -> std::result::Result<(), ProductFieldTooLongError> {
+    if let Some(v) = updates.name.as_mut() {
+        strip_nul_in_place(v);
+        check_field_length("name", v, MAX_PRODUCT_NAME_CHARS)?;
+    }
+    if let Some(v) = updates.slogan.as_mut() {
+        strip_nul_in_place(v);
+        check_field_length("slogan", v, MAX_PRODUCT_SLOGAN_CHARS)?;
+    }
+    if let Some(v) = updates.description.as_mut() {
+        strip_nul_in_place(v);
+        check_field_length("description", v, MAX_PRODUCT_DESCRIPTION_CHARS)?;
+    }
+    if let Some(v) = updates.website.as_mut() {
+        strip_nul_in_place(v);
+    }
+    if let Some(v) = updates.logo_url.as_mut() {
+        strip_nul_in_place(v);
+    }
+    if let Some(v) = updates.category.as_mut() {
+        strip_nul_in_place(v);
+    }
+    if let Some(tags) = updates.tags.as_mut() {
+        *tags = normalize_tags(tags);
+        tags.truncate(MAX_PRODUCT_TAGS);
+        check_tag_lengths(tags)?;
+    }
+    if let Some(v) = updates.rejection_reason.as_mut() {
+        strip_nul_in_place(v);
+    }
+    Ok(())
+}
+
+fn sanitize_categories(categories: &mut [Category]) {
+    for c in categories {
+        strip_nul_in_place(&mut c.id);
+        strip_nul_in_place(&mut c.name_en);
+        strip_nul_in_place(&mut c.name_zh);
+        strip_nul_in_place(&mut c.icon);
+        strip_nul_in_place(&mut c.color);
+    }
+}
+
+/**
+ * map_product_row
+ * 将 ProductRow 转换为对外 API 使用的 Product 结构。
+ */
+fn map_product_row(row: ProductRow) -> Product {
+    let mut maker_sponsor_role = row.maker_sponsor_role;
+    strip_nul_in_place_opt(&mut maker_sponsor_role);
+    Product {
+        id: row.id,
+        name: row.name,
+        slogan: row.slogan,
+        description: row.description,
+        website: row.website,
+        logo_url: row.logo_url,
+        category: row.category,
+        tags: row.tags,
+        maker_name: row.maker_name,
+        maker_email: row.maker_email,
+        maker_website: row.maker_website,
+        maker_sponsor_role,
+        maker_sponsor_verified: row.maker_sponsor_verified,
+        language: row.language,
+        status: parse_product_status(&row.status),
+        rejection_reason: row.rejection_reason,
+        created_at: row.created_at,
+        updated_at: row.updated_at,
+        likes: row.likes,
+        favorites: row.favorites,
+        has_liked: row.has_liked,
+        has_favorited: row.has_favorited,
+        category_detail: None,
+    }
+}
+
+/**
+ * map_category_row
+ * 将 CategoryRow 转换为对外 API 使用的 Category 结构。
+ */
+fn map_category_row(row: CategoryRow) -> Category {
+    let name_en = row.name_en;
+    let name_zh = row.name_zh.unwrap_or_else(|| name_en.clone());
+    Category {
+        id: row.id,
+        name_en,
+        name_zh,
+        icon: row.icon,
+        color: row.color,
+    }
+}
+
+fn map_outgoing_webhook_row(row: OutgoingWebhookRow) -> OutgoingWebhook {
+    OutgoingWebhook {
+        id: row.id,
+        url: row.url,
+        events: row.events,
+        enabled: row.enabled,
+        consecutive_failures: row.consecutive_failures,
+        disabled_at: row.disabled_at,
+        created_at: row.created_at,
+        updated_at: row.updated_at,
+    }
+}
+
+fn map_product_claim_row(row: ProductClaimRow) -> ProductClaim {
+    ProductClaim {
+        id: row.id,
+        product_id: row.product_id,
+        claimant_email: row.claimant_email,
+        status: row.status,
+        note: row.note,
+        created_at: row.created_at,
+        decided_at: row.decided_at,
+    }
+}
+
+fn map_category_with_count_row(row: CategoryWithCountRow) -> crate::models::CategoryWithCount {
+    let name_en = row.name_en;
+    let name_zh = row.name_zh.unwrap_or_else(|| name_en.clone());
+    crate::models::CategoryWithCount {
+        id: row.id,
+        name_en,
+        name_zh,
+        icon: row.icon,
+        color: row.color,
+        product_count: row.product_count,
+    }
+}
+
+fn map_developer_row(row: DeveloperRow) -> Developer {
+    let mut email = row.email;
+    let mut name = row.name;
+    let mut avatar_url = row.avatar_url;
+    let mut website = row.website;
+    let mut sponsor_role = row.sponsor_role;
+    strip_nul_in_place(&mut email);
+    strip_nul_in_place(&mut name);
+    strip_nul_in_place_opt(&mut avatar_url);
+    strip_nul_in_place_opt(&mut website);
+    strip_nul_in_place_opt(&mut sponsor_role);
+    Developer {
+        email,
+        name,
+        avatar_url,
+        website,
+        sponsor_role,
+        sponsor_verified: row.sponsor_verified,
+        created_at: row.created_at,
+        updated_at: row.updated_at,
+    }
+}
+
+fn map_developer_with_followers_row(row: DeveloperWithFollowersRow) -> DeveloperWithFollowers {
+    let mut email = row.email;
+    let mut name = row.name;
+    let mut avatar_url = row.avatar_url;
+    let mut website = row.website;
+    let mut sponsor_role = row.sponsor_role;
+    strip_nul_in_place(&mut email);
+    strip_nul_in_place(&mut name);
+    strip_nul_in_place_opt(&mut avatar_url);
+    strip_nul_in_place_opt(&mut website);
+    strip_nul_in_place_opt(&mut sponsor_role);
+    DeveloperWithFollowers {
+        email,
+        name,
+        avatar_url,
+        website,
+        sponsor_role,
+        sponsor_verified: row.sponsor_verified,
+        followers: row.followers.parse::<i64>().unwrap_or(0),
+        created_at: row.created_at,
+        updated_at: row.updated_at,
+    }
+}
+
+fn map_developer_popularity_row(row: DeveloperPopularityRow) -> DeveloperPopularity {
+    let mut email = row.email;
+    let mut name = row.name;
+    let mut avatar_url = row.avatar_url;
+    let mut website = row.website;
+    let mut sponsor_role = row.sponsor_role;
+    strip_nul_in_place(&mut email);
+    strip_nul_in_place(&mut name);
+    strip_nul_in_place_opt(&mut avatar_url);
+    strip_nul_in_place_opt(&mut website);
+    strip_nul_in_place_opt(&mut sponsor_role);
+    DeveloperPopularity {
+        email,
+        name,
+        avatar_url,
+        website,
+        sponsor_role,
+        sponsor_verified: row.sponsor_verified,
+        likes: row.likes,
+        favorites: row.favorites,
+        new_followers: row.new_followers,
+        score: row.score,
+    }
+}
+
+fn map_developer_center_stats_row(
+    row: DeveloperCenterStatsRow,
+    window: Option<(StatsWindow, chrono::DateTime<chrono::Utc>)>,
+) -> DeveloperCenterStats {
+    DeveloperCenterStats {
+        followers: row.followers,
+        total_likes: row.total_likes,
+        total_favorites: row.total_favorites,
+        windowed: window.map(|(window, since)| DeveloperCenterWindowStats {
+            window: window.as_str().to_string(),
+            since,
+            new_followers: row.window_followers,
+            likes: row.window_likes,
+            favorites: row.window_favorites,
+        }),
+    }
+}
+
+fn parse_supabase_content_range_total(value: &str) -> Option<i64> {
+    let after_slash = value.rsplit('/').next()?;
+    if after_slash.trim() == "*" {
+        return Some(0);
+    }
+    after_slash.trim().parse::<i64>().ok()
+}
+
+async fn supabase_count(
+    supabase: &SupabaseDatabase,
+    table: &str,
+    query: &[(&str, String)],
+) -> Result<i64> {
+    let mut url = Url::parse(&format!("{}/rest/v1/{}", supabase.supabase_url, table))?;
+    {
+        let mut qp = url.query_pairs_mut();
+        for (k, v) in query {
+            qp.append_pair(k, v);
+        }
+        qp.append_pair("limit", "1");
+    }
+
+    let response = supabase
+        .get_with_retry(url, &[("Prefer", "count=exact")])
+        .await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow::anyhow!(
+            "Failed to fetch count from {}: {}. Body: {}",
+            table,
+            status,
+            body
+        ));
+    }
+
+    let total = response
+        .headers()
+        .get("content-range")
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_supabase_content_range_total)
+        .unwrap_or(0);
+
+    Ok(total)
+}
+
+fn split_sql_statements(input: &str) -> Vec<String> {
+    let bytes = input.as_bytes();
+    let mut statements: Vec<String> = Vec::new();
+    let mut current = String::new();
+
+    let mut i: usize = 0;
+    let mut in_single = false;
+    let mut in_double = false;
+    let mut dollar_delim: Option<String> = None;
+
+    while i < bytes.len() {
+        if dollar_delim.is_none() && !in_single && !in_double {
+            if bytes[i] == b'-' && i + 1 < bytes.len() && bytes[i + 1] == b'-' {
+                i += 2;
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+                continue;
+            }
+
+            if bytes[i] == b'/' && i + 1 < bytes.len() && bytes[i + 1] == b'*' {
+                i += 2;
+                while i + 1 < bytes.len() && !(bytes[i] == b'*' && bytes[i + 1] == b'/') {
+                    i += 1;
+                }
+                if i + 1 < bytes.len() {
+                    i += 2;
+                }
+                continue;
+            }
+        }
+
+        if let Some(delim) = &dollar_delim {
+            if input[i..].starts_with(delim) {
+                current.push_str(delim);
+                i += delim.len();
+                dollar_delim = None;
+                continue;
+            }
+            current.push(bytes[i] as char);
+            i += 1;
+            continue;
+        }
+
+        if !in_double && bytes[i] == b'\'' {
+            if in_single && i + 1 < bytes.len() && bytes[i + 1] == b'\'' {
+                current.push('\'');
+                current.push('\'');
+                i += 2;
+                continue;
+            }
+            in_single = !in_single;
+            current.push('\'');
+            i += 1;
+            continue;
+        }
+
+        if !in_single && bytes[i] == b'"' {
+            if in_double && i + 1 < bytes.len() && bytes[i + 1] == b'"' {
+                current.push('"');
+                current.push('"');
+                i += 2;
+                continue;
+            }
+            in_double = !in_double;
+            current.push('"');
+            i += 1;
+            continue;
+        }
+
+        if !in_single && !in_double && bytes[i] == b'$' {
+            let mut j = i + 1;
+            while j < bytes.len()
+                && bytes[j] != b'$'
+                && (bytes[j].is_ascii_alphanumeric() || bytes[j] == b'_')
+            {
+                j += 1;
+            }
+            if j < bytes.len() && bytes[j] == b'$' {
+                let delim = &input[i..=j];
+                dollar_delim = Some(delim.to_string());
+                current.push_str(delim);
+                i = j + 1;
+                continue;
+            }
+        }
+
+        if !in_single && !in_double && bytes[i] == b';' {
+            let stmt = current.trim();
+            if !stmt.is_empty() {
+                statements.push(stmt.to_string());
+            }
+            current.clear();
+            i += 1;
+            continue;
+        }
+
+        current.push(bytes[i] as char);
+        i += 1;
+    }
+
+    let tail = current.trim();
+    if !tail.is_empty() {
+        statements.push(tail.to_string());
+    }
+
+    statements
+}
+
+impl Database {
+    /**
+     * with_postgres_pool
+     * 测试用：只接入给定的 Postgres 连接池（不读环境变量、不配置 Supabase）。
+     */
+    #[cfg(test)]
+    pub(crate) fn with_postgres_pool(pool: PgPool) -> Self {
+        Database {
+            supabase: None,
+            postgres: Some(pool),
+            overview_stats_cache: Mutex::new(None),
+            featured_history_recorded: Mutex::new(None),
+        }
+    }
+
+    pub fn new() -> Self {
+        let supabase = match (env::var("SUPABASE_URL").ok(), env::var("SUPABASE_KEY").ok()) {
+            (Some(supabase_url), Some(supabase_key)) => {
+                let supabase_url = supabase_url.trim_end_matches('/').to_string();
+
+                let client_builder = Client::builder()
+                    .connect_timeout(HTTP_CONNECT_TIMEOUT)
+                    .timeout(http_timeout(8))
+                    .http1_only();
+
+                let client = client_builder.build().expect("Failed to build HTTP client");
+                Some(SupabaseDatabase {
+                    client,
+                    supabase_url,
+                    supabase_key,
+                })
+            }
+            _ => None,
+        };
+
+        let postgres = env::var("DATABASE_URL").ok().and_then(|u| {
+            let options = PgConnectOptions::from_str(&u).ok()?;
+            let options = options.statement_cache_capacity(0);
+            Some(
+                PgPoolOptions::new()
+                    .max_connections(15)
+                    .min_connections(1)
+                    .acquire_timeout(Duration::from_secs(8))
+                    .test_before_acquire(true)
+                    .after_connect(|conn, _meta| {
+                        Box::pin(async move {
+                            sqlx::query("SET statement_timeout = 15000")
+                                .persistent(false)
+                                .execute(conn)
+                                .await?;
+                            Ok(())
+                        })
+                    })
+                    .connect_lazy_with(options),
+            )
+        });
+
+        if postgres.is_none() && supabase.is_none() {
+            panic!("DATABASE_URL or (SUPABASE_URL + SUPABASE_KEY) must be set");
+        }
+
+        // 显式关闭兜底时，Postgres 出错直接暴露，而不是静默返回 Supabase 的旧数据。
+        let fallback_disabled = supabase_fallback_disabled_from_env_value(
+            env::var("DISABLE_SUPABASE_FALLBACK").ok().as_deref(),
+        );
+        let supabase = if postgres.is_some() && supabase.is_some() && fallback_disabled {
+            log::info!("DISABLE_SUPABASE_FALLBACK is set; Supabase will not be used for reads");
+            None
+        } else {
+            if postgres.is_none() && fallback_disabled {
+                log::warn!(
+                    "DISABLE_SUPABASE_FALLBACK ignored: DATABASE_URL is not set, Supabase is the primary backend"
+                );
+            }
+            supabase
+        };
+
+        log::info!(
+            "Database backends: postgres={} supabase={}",
+            postgres.is_some(),
+            supabase.is_some()
+        );
+
+        Self {
+            postgres,
+            supabase,
+            overview_stats_cache: Mutex::new(None),
+            featured_history_recorded: Mutex::new(None),
+        }
+    }
+
+    /**
+     * invalidate_caches
+     * 清空进程内的聚合缓存（概览统计等），返回被清理的缓存名称。
+     */
+    pub fn invalidate_caches(&self) -> Vec<String> {
+        let mut invalidated = Vec::new();
+        if let Ok(mut guard) = self.overview_stats_cache.lock() {
+            if guard.take().is_some() {
+                invalidated.push("overview_stats".to_string());
+            }
+        }
+        invalidated
+    }
+
+    /**
+     * configured_backends
+     * 返回当前启用的数据后端 (postgres, supabase)，供健康检查上报配置状态。
+     */
+    pub fn configured_backends(&self) -> (bool, bool) {
+        (self.postgres.is_some(), self.supabase.is_some())
+    }
+
+    /**
+     * get_developer_created_at
+     * 查询创作者 developers 记录的创建时间（邮箱忽略大小写）；无记录返回 None。
+     */
+    pub async fn get_developer_created_at(
+        &self,
+        email: &str,
+    ) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+        ensure_lower_email_indexes(pool).await;
+
+        let created_at: Option<chrono::DateTime<chrono::Utc>> = sqlx::query_scalar(
+            "SELECT MIN(created_at) FROM developers WHERE lower(email) = lower($1)",
+        )
+        .persistent(false)
+        .bind(strip_nul_str(email.trim()).as_ref())
+        .fetch_one(pool)
+        .await?;
+        Ok(created_at)
+    }
+
+    pub async fn get_developer_by_email(&self, email: &str) -> Result<Option<Developer>> {
+        if let Some(pool) = &self.postgres {
+            ensure_lower_email_indexes(pool).await;
+            let email = strip_nul_str(email);
+            let mut last_err: Option<anyhow::Error> = None;
+            for attempt_idx in 0..2 {
+                let attempt = sqlx::query_as::<_, DeveloperRow>(
+                    "SELECT email, name, avatar_url, website, sponsor_role, sponsor_verified, created_at, updated_at \
+                     FROM developers \
+                     WHERE lower(email) = lower($1) \
+                     ORDER BY updated_at DESC NULLS LAST \
+                     LIMIT 1",
+                )
+                .persistent(false)
+                .bind(email.as_ref())
+                .fetch_optional(pool)
+                .await;
+
+                match attempt {
+                    Ok(row) => return Ok(row.map(map_developer_row)),
+                    Err(e) => {
+                        let e: anyhow::Error = e.into();
+                        if (is_missing_column_error(&e, "sponsor_role")
+                            || is_missing_column_error(&e, "sponsor_verified"))
+                            && !DEVELOPERS_SPONSOR_COLUMNS_READY.load(Ordering::Relaxed)
+                            && ensure_developers_sponsor_columns(pool).await.is_ok()
+                        {
+                            continue;
+                        }
+                        last_err = Some(e);
+                        let Some(ref err) = last_err else {
+                            continue;
+                        };
+                        if is_retryable_db_error(err) && self.supabase.is_some() {
+                            break;
+                        }
+                        if attempt_idx == 0 && is_retryable_db_error(err) {
+                            continue;
+                        }
+                        return Err(last_err.unwrap());
+                    }
+                }
+            }
+
+            if let Some(e) = last_err {
+                if !(is_retryable_db_error(&e) && self.supabase.is_some()) {
+                    return Err(e);
+                }
+            }
+        }
+
+        let supabase = match &self.supabase {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+
+        let email = strip_nul_str(email);
+        let mut url = Url::parse(&format!("{}/rest/v1/developers", supabase.supabase_url))?;
+        url.query_pairs_mut()
+            .append_pair(
+                "select",
+                "email,name,avatar_url,website,sponsor_role,sponsor_verified,created_at,updated_at",
+            )
+            .append_pair("email", &format!("eq.{}", email));
+
+        let response = supabase.get_with_retry(url, &[]).await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!(
+                "Failed to fetch developer: {}. Body: {}",
+                status,
+                body
+            ));
+        }
+
+        let developers: Vec<Developer> = response.json().await?;
+        Ok(developers.first().cloned())
+    }
+
+    pub async fn update_developer_profile(
+        &self,
+        email: &str,
+        name: Option<String>,
+        avatar_url: Option<Option<String>>,
+        website: Option<Option<String>>,
+    ) -> Result<Developer> {
+        let name_update = name.is_some();
+        let avatar_update = avatar_url.is_some();
+        let website_update = website.is_some();
+
+        let email_clean = strip_nul_str(email);
+        let name_value = name.clone().unwrap_or_else(|| email_clean.to_string());
+        let name_value = strip_nul_str(&name_value).into_owned();
+        let avatar_value = avatar_url
+            .clone()
+            .and_then(|v| v)
+            .map(|v| strip_nul_str(&v).into_owned());
+        let website_value = website
+            .clone()
+            .and_then(|v| v)
+            .map(|v| strip_nul_str(&v).into_owned());
+
+        if let Some(pool) = &self.postgres {
+            let mut last_err: Option<anyhow::Error> = None;
+            for attempt_idx in 0..2 {
+                let attempt: Result<Developer> = async {
+                    let row = sqlx::query_as::<_, DeveloperRow>(
+                        "INSERT INTO developers (email, name, avatar_url, website) \
+                         VALUES ($1, $2, $3, $4) \
+                         ON CONFLICT (email) DO UPDATE SET \
+                            name = CASE WHEN $5 THEN EXCLUDED.name ELSE developers.name END, \
+                            avatar_url = CASE WHEN $6 THEN EXCLUDED.avatar_url ELSE developers.avatar_url END, \
+                            website = CASE WHEN $7 THEN EXCLUDED.website ELSE developers.website END, \
+                            updated_at = NOW() \
+                         RETURNING email, name, avatar_url, website, sponsor_role, sponsor_verified, created_at, updated_at",
+                    )
+                    .persistent(false)
+                    .bind(email_clean.as_ref())
+                    .bind(name_value.as_str())
+                    .bind(avatar_value.as_deref())
+                    .bind(website_value.as_deref())
+                    .bind(name_update)
+                    .bind(avatar_update)
+                    .bind(website_update)
+                    .fetch_one(pool)
+                    .await?;
+
+                    Ok(map_developer_row(row))
+                }
+                .await;
+
+                match attempt {
+                    Ok(dev) => return Ok(dev),
+                    Err(e) => {
+                        if (is_missing_column_error(&e, "sponsor_role")
+                            || is_missing_column_error(&e, "sponsor_verified"))
+                            && !DEVELOPERS_SPONSOR_COLUMNS_READY.load(Ordering::Relaxed)
+                            && ensure_developers_sponsor_columns(pool).await.is_ok()
+                        {
+                            continue;
+                        }
+                        last_err = Some(e);
+                        let Some(ref err) = last_err else {
+                            continue;
+                        };
+                        if is_retryable_db_error(err) && self.supabase.is_some() {
+                            break;
+                        }
+                        if attempt_idx == 0 && is_retryable_db_error(err) {
+                            continue;
+                        }
+                        return Err(last_err.unwrap());
+                    }
+                }
+            }
+
+            if let Some(e) = last_err {
+                if !(is_retryable_db_error(&e) && self.supabase.is_some()) {
+                    return Err(e);
+                }
+            }
+        }
+
+        let supabase = self
+            .supabase
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No database configured"))?;
+
+        let name_value_raw = name_value.as_str().to_string();
+        let name_value = strip_nul_str(&name_value_raw).into_owned();
+
+        let mut payload = serde_json::Map::<String, serde_json::Value>::new();
+        payload.insert(
+            "email".to_string(),
+            serde_json::Value::String(email_clean.to_string()),
+        );
+        let exists = self.get_developer_by_email(email).await?.is_some();
+        if name_update || !exists {
+            payload.insert("name".to_string(), serde_json::Value::String(name_value));
+        }
+
+        if let Some(v) = avatar_url {
+            match v {
+                Some(s) => payload.insert("avatar_url".to_string(), serde_json::Value::String(s)),
+                None => payload.insert("avatar_url".to_string(), serde_json::Value::Null),
+            };
+        }
+        if let Some(v) = website {
+            match v {
+                Some(s) => payload.insert("website".to_string(), serde_json::Value::String(s)),
+                None => payload.insert("website".to_string(), serde_json::Value::Null),
+            };
+        }
+
+        let mut url = Url::parse(&format!("{}/rest/v1/developers", supabase.supabase_url))?;
+        url.query_pairs_mut().append_pair("on_conflict", "email");
+
+        let response = supabase
+            .client
+            .post(url)
+            .header("apikey", &supabase.supabase_key)
+            .header(
+                "Authorization",
+                &format!("Bearer {}", supabase.supabase_key),
+            )
+            .header("Accept", "application/json")
+            .header(
+                "Prefer",
+                "resolution=merge-duplicates,return=representation",
+            )
+            .json(&serde_json::Value::Object(payload))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!(
+                "Failed to update developer: {}. Body: {}",
+                status,
+                body
+            ));
+        }
+
+        let returned: Vec<Developer> = response.json().await?;
+        returned
+            .first()
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Invalid response from database"))
+    }
+
+    async fn upsert_developer_pg(
+        &self,
+        pool: &PgPool,
+        email: &str,
+        name: &str,
+        website: Option<&String>,
+    ) -> Result<()> {
+        let email = strip_nul_str(email);
+        let name = strip_nul_str(name);
+        let website = website.map(|v| strip_nul_str(v).into_owned());
+        sqlx::query(
+            "INSERT INTO developers (email, name, website) \
+             VALUES ($1, $2, $3) \
+             ON CONFLICT (email) DO UPDATE SET \
+                name = EXCLUDED.name, \
+                website = COALESCE(EXCLUDED.website, developers.website), \
+                updated_at = NOW()",
+        )
+        .persistent(false)
+        .bind(email.as_ref())
+        .bind(name.as_ref())
+        .bind(website.as_deref())
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /**
+     * find_orphan_maker_emails
+     * 找出 products.maker_email 在 developers 表中没有对应行的创作者（取其最新产品的 maker_name / maker_website）。
+     */
+    pub async fn find_orphan_maker_emails(&self) -> Result<Vec<OrphanMaker>> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        #[derive(sqlx::FromRow)]
+        struct Row {
+            email: String,
+            name: String,
+            website: Option<String>,
+        }
+
+        let rows = sqlx::query_as::<_, Row>(
+            "SELECT DISTINCT ON (lower(p.maker_email)) \
+                lower(p.maker_email) AS email, p.maker_name AS name, p.maker_website AS website \
+             FROM products p \
+             WHERE btrim(p.maker_email) <> '' \
+               AND NOT EXISTS (SELECT 1 FROM developers d WHERE lower(d.email) = lower(p.maker_email)) \
+             ORDER BY lower(p.maker_email), p.created_at DESC",
+        )
+        .persistent(false)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| OrphanMaker {
+                email: strip_nul_str(r.email.trim()).into_owned(),
+                name: strip_nul_str(r.name.trim()).into_owned(),
+                website: r
+                    .website
+                    .map(|v| strip_nul_str(v.trim()).into_owned())
+                    .filter(|v| !v.is_empty()),
+            })
+            .collect())
+    }
+
+    /**
+     * backfill_orphan_developers
+     * 为缺少 developers 行的创作者补齐最小记录；返回成功补齐的数量与失败的邮箱。
+     */
+    pub async fn backfill_orphan_developers(
+        &self,
+        orphans: &[OrphanMaker],
+    ) -> Result<(usize, Vec<String>)> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let mut created = 0usize;
+        let mut failed: Vec<String> = Vec::new();
+        for orphan in orphans {
+            let name = if orphan.name.is_empty() {
+                orphan
+                    .email
+                    .split('@')
+                    .next()
+                    .unwrap_or_default()
+                    .to_string()
+            } else {
+                orphan.name.clone()
+            };
+            match self
+                .upsert_developer_pg(pool, &orphan.email, &name, orphan.website.as_ref())
+                .await
+            {
+                Ok(()) => created += 1,
+                Err(e) => {
+                    log::warn!(
+                        "Developer backfill failed email={} err={:?}",
+                        orphan.email,
+                        e
+                    );
+                    failed.push(orphan.email.clone());
+                }
+            }
+        }
+        Ok((created, failed))
+    }
+
+    pub async fn get_products(&self, params: QueryParams) -> Result<Vec<Product>> {
+        let maker_scoped = params
+            .maker_email
+            .as_deref()
+            .is_some_and(|v| !v.trim().is_empty());
+        let visibility = ProductVisibility::for_request(params.include_pending.unwrap_or(false));
+
+        if let Some(pool) = &self.postgres {
+            ensure_lower_email_indexes(pool).await;
+            let mut last_err: Option<anyhow::Error> = None;
+            for attempt in 0..2 {
+                let attempt_result: Result<Vec<Product>> = async {
+                    let mut tx = pool.begin().await?;
+                    let mut qb: QueryBuilder<Postgres> = QueryBuilder::new(
+                        "SELECT \
+                            p.id::text as id, \
+                            p.name, \
+                            p.slogan, \
+                            p.description, \
+                            p.website, \
+                            p.logo_url, \
+                            p.category, \
+                            COALESCE(p.tags, ARRAY[]::text[]) as tags, \
+                            p.maker_name, \
+                            p.maker_email, \
+                            p.maker_website, \
+                            p.language, \
+                            p.status::text as status, \
+                            p.rejection_reason, \
+                            p.created_at, \
+                            p.updated_at, \
+                            (SELECT COUNT(*)::bigint FROM product_likes l WHERE l.product_id = p.id) as likes, \
+                            (SELECT COUNT(*)::bigint FROM product_favorites f WHERE f.product_id = p.id) as favorites, \
+                            COALESCE(d.sponsor_role, NULL::text) as maker_sponsor_role, \
+                            COALESCE(d.sponsor_verified, FALSE) as maker_sponsor_verified",
+                    );
+
+                    let viewer_id = params
+                        .user_id
+                        .as_deref()
+                        .map(|v| v.trim())
+                        .filter(|v| !v.is_empty());
+                    if let Some(viewer_id) = viewer_id {
+                        qb.push(
+                            ", EXISTS(SELECT 1 FROM product_likes l WHERE l.product_id = p.id AND l.user_id = ",
+                        );
+                        qb.push_bind(viewer_id.to_string());
+                        qb.push(
+                            ") as has_liked, EXISTS(SELECT 1 FROM product_favorites f WHERE f.product_id = p.id AND f.user_id = ",
+                        );
+                        qb.push_bind(viewer_id.to_string());
+                        qb.push(") as has_favorited");
+                    }
+
+                    qb.push(
+                        " FROM products p \
+                         LEFT JOIN developers d ON lower(d.email) = lower(p.maker_email)",
+                    );
+
+                    qb.push(" WHERE 1=1");
+                    if let Some(category) = &params.category {
+                        qb.push(" AND ");
+                        qb.push("p.category = ");
+                        qb.push_bind(category);
+                    }
+
+                    if let Some(language) = &params.language {
+                        qb.push(" AND ");
+                        qb.push("p.language = ");
+                        qb.push_bind(language);
+                    }
+
+                    if let Some(status) = &params.status {
+                        qb.push(" AND ");
+                        if status == "approved" && !maker_scoped {
+                            qb.push(visibility.predicate("p"));
+                        } else {
+                            qb.push("p.status::text = ");
+                            qb.push_bind(status);
+                        }
+                    }
+
+                    if let Some(search) = &params.search {
+                        let q = format!("%{}%", search);
+                        qb.push(" AND ");
+                        qb.push("(p.name ILIKE ");
+                        qb.push_bind(q.clone());
+                        qb.push(" OR p.slogan ILIKE ");
+                        qb.push_bind(q.clone());
+                        qb.push(" OR p.description ILIKE ");
+                        qb.push_bind(q.clone());
+                        qb.push(" OR p.maker_name ILIKE ");
+                        qb.push_bind(q.clone());
+                        qb.push(" OR p.maker_email ILIKE ");
+                        qb.push_bind(q);
+                        qb.push(")");
+                    }
+
+                    if let Some(tags) = &params.tags {
+                        let tag = tags.split(',').next().unwrap_or(tags).trim();
+                        if !tag.is_empty() {
+                            qb.push(" AND ");
+                            qb.push("p.tags @> ARRAY[");
+                            qb.push_bind(tag);
+                            qb.push("]::text[]");
+                        }
+                    }
+
+                    if let Some(maker_email) = &params.maker_email {
+                        let normalized = maker_email.trim().to_ascii_lowercase();
+                        if !normalized.is_empty() {
+                            qb.push(" AND lower(p.maker_email) = lower(");
+                            qb.push_bind(normalized);
+                            qb.push(")");
+                        }
+                    }
+
+                    if let Some(created_after) = params.created_after {
+                        qb.push(" AND p.created_at >= ");
+                        qb.push_bind(created_after);
+                    }
+
+                    if let Some(created_before) = params.created_before {
+                        qb.push(" AND p.created_at < ");
+                        qb.push_bind(created_before);
+                    }
+
+                    if let Some(min_likes) = params.min_likes {
+                        qb.push(
+                            " AND (SELECT COUNT(*)::bigint FROM product_likes l WHERE l.product_id = p.id) >= ",
+                        );
+                        qb.push_bind(min_likes);
+                    }
+
+                    if let Some(sponsor_verified) = params.sponsor_verified {
+                        qb.push(" AND COALESCE(d.sponsor_verified, FALSE) = ");
+                        qb.push_bind(sponsor_verified);
+                    }
+
+                    let sort_by = params
+                        .sort
+                        .as_deref()
+                        .unwrap_or("created_at")
+                        .trim()
+                        .to_ascii_lowercase();
+                    let sort_dir = params
+                        .dir
+                        .as_deref()
+                        .unwrap_or("desc")
+                        .trim()
+                        .to_ascii_lowercase();
+                    let asc = sort_dir == "asc" || sort_dir == "ascending";
+
+                    qb.push(" ORDER BY ");
+                    if sort_by.as_str() == "likes" {
+                        qb.push("(SELECT COUNT(*)::bigint FROM product_likes l WHERE l.product_id = p.id)");
+                    } else if sort_by.as_str() == "favorites" {
+                        qb.push(
+                            "(SELECT COUNT(*)::bigint FROM product_favorites f WHERE f.product_id = p.id)",
+                        );
+                    } else if sort_by.as_str() == "recent" {
+                        qb.push("COALESCE(p.bumped_at, p.created_at)");
+                    } else if sort_by.as_str() == "random" {
+                        qb.push("md5(p.id::text || ");
+                        qb.push_bind(random_sort_seed(
+                            params.seed.as_deref(),
+                            chrono::Utc::now().date_naive(),
+                        ));
+                        qb.push(")");
+                    } else if sort_by.as_str() == "popularity"
+                        || sort_by.as_str() == "score"
+                        || sort_by.as_str() == "featured"
+                    {
+                        qb.push("((");
+                        qb.push("(SELECT COUNT(*)::bigint FROM product_likes l WHERE l.product_id = p.id)");
+                        qb.push(") + (");
+                        qb.push(
+                            "(SELECT COUNT(*)::bigint FROM product_favorites f WHERE f.product_id = p.id)",
+                        );
+                        qb.push("))");
+                    } else {
+                        qb.push("p.created_at");
+                    }
+                    if asc {
+                        qb.push(" ASC");
+                    } else {
+                        qb.push(" DESC");
+                    }
+                    qb.push(", p.created_at DESC, p.id ASC");
+
+                    if let Some(limit) = params.limit {
+                        qb.push(" LIMIT ");
+                        qb.push_bind(limit);
+                    }
+
+                    if let Some(offset) = params.offset {
+                        qb.push(" OFFSET ");
+                        qb.push_bind(offset);
+                    }
+
+                    let rows = qb
+                        .build_query_as::<ProductRow>()
+                        .persistent(false)
+                        .fetch_all(&mut *tx)
+                        .await?;
+                    tx.commit().await?;
+                    Ok(rows.into_iter().map(map_product_row).collect())
+                }
+                .await;
+
+                match attempt_result {
+                    Ok(list) => return Ok(list),
+                    Err(e) => {
+                        if is_missing_column_error(&e, "bumped_at")
+                            && !PRODUCTS_BUMPED_AT_READY.load(Ordering::Relaxed)
+                            && ensure_products_bumped_at_column(pool).await.is_ok()
+                        {
+                            continue;
+                        }
+                        if is_missing_column_error(&e, "rejection_reason")
+                            && !PRODUCTS_REJECTION_REASON_READY.load(Ordering::Relaxed)
+                            && ensure_products_rejection_reason_column(pool).await.is_ok()
+                        {
+                            continue;
+                        }
+                        if (is_missing_column_error(&e, "sponsor_role")
+                            || is_missing_column_error(&e, "sponsor_verified"))
+                            && !DEVELOPERS_SPONSOR_COLUMNS_READY.load(Ordering::Relaxed)
+                            && ensure_developers_sponsor_columns(pool).await.is_ok()
+                        {
+                            continue;
+                        }
+                        last_err = Some(e);
+                        let Some(ref err) = last_err else {
+                            continue;
+                        };
+                        if is_retryable_db_error(err) && self.supabase.is_some() {
+                            break;
+                        }
+                        if attempt == 0 && is_retryable_db_error(err) {
+                            continue;
+                        }
+                        return Err(last_err.unwrap());
+                    }
+                }
+            }
+
+            if let Some(e) = last_err {
+                if !(is_retryable_db_error(&e) && self.supabase.is_some()) {
+                    return Err(e);
+                }
+            }
+        }
+
+        let supabase = self
+            .supabase
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No database configured"))?;
+
+        if params.min_likes.is_some() {
+            return Err(anyhow::Error::new(UnsupportedFilterError {
+                filter: "min_likes",
+            }));
+        }
+        if params.sponsor_verified.is_some() {
+            return Err(anyhow::Error::new(UnsupportedFilterError {
+                filter: "sponsor_verified",
+            }));
+        }
+
+        let mut url = Url::parse(&format!("{}/rest/v1/products", supabase.supabase_url))?;
+        {
+            let mut qp = url.query_pairs_mut();
+
+            if let Some(created_after) = params.created_after {
+                qp.append_pair("created_at", &format!("gte.{}", created_after.to_rfc3339()));
+            }
+
+            if let Some(created_before) = params.created_before {
+                qp.append_pair("created_at", &format!("lt.{}", created_before.to_rfc3339()));
+            }
+
+            if let Some(category) = &params.category {
+                qp.append_pair("category", &format!("eq.{}", category));
+            }
+
+            if let Some(language) = &params.language {
+                qp.append_pair("language", &format!("eq.{}", language));
+            }
+
+            if let Some(status) = &params.status {
+                if status == "approved" && !maker_scoped {
+                    let (key, value) = visibility.postgrest_filter(chrono::Utc::now());
+                    qp.append_pair(key, &value);
+                } else {
+                    qp.append_pair("status", &format!("eq.{}", status));
+                }
+            }
+
+            if let Some(tags) = &params.tags {
+                let tag = tags.split(',').next().unwrap_or(tags).trim();
+                if !tag.is_empty() {
+                    qp.append_pair("tags", &format!("cs.{{{}}}", tag));
+                }
+            }
+
+            if let Some(search) = &params.search {
+                qp.append_pair("name", &format!("ilike.%{}%", search));
+                qp.append_pair("slogan", &format!("ilike.%{}%", search));
+                qp.append_pair("description", &format!("ilike.%{}%", search));
+            }
+
+            if let Some(maker_email) = &params.maker_email {
+                let normalized = maker_email.trim().to_ascii_lowercase();
+                if !normalized.is_empty() {
+                    qp.append_pair("maker_email", &format!("eq.{}", normalized));
+                }
+            }
+
+            if let Some(limit) = params.limit {
+                qp.append_pair("limit", &limit.to_string());
+            }
+
+            if let Some(offset) = params.offset {
+                qp.append_pair("offset", &offset.to_string());
+            }
+
+            let sort_by = params
+                .sort
+                .as_deref()
+                .unwrap_or("created_at")
+                .trim()
+                .to_ascii_lowercase();
+            let sort_dir = params
+                .dir
+                .as_deref()
+                .unwrap_or("desc")
+                .trim()
+                .to_ascii_lowercase();
+            let asc = sort_dir == "asc" || sort_dir == "ascending";
+
+            let order_value = if sort_by == "created_at" {
+                if asc {
+                    "created_at.asc"
+                } else {
+                    "created_at.desc"
+                }
+            } else {
+                log::warn!(
+                    "Supabase products query cannot sort by {}; falling back to created_at.desc",
+                    sort_by
+                );
+                "created_at.desc"
+            };
+            qp.append_pair("order", order_value);
+        }
+
+        let response = supabase.get_with_retry(url, &[]).await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+
+            if status.as_u16() == 401 || status.as_u16() == 403 {
+                return Err(anyhow::anyhow!(
+                    "Supabase auth failed: {}. Check SUPABASE_KEY. Body: {}",
+                    status,
+                    body
+                ));
+            }
+
+            return Err(anyhow::anyhow!(
+                "Failed to fetch products: {}. Body: {}",
+                status,
+                body
+            ));
+        }
+
+        let products: Vec<Product> = response.json().await?;
+        Ok(products)
+    }
+
+    /**
+     * get_feed_products
+     * 订阅源（RSS / JSON Feed）数据：最近通过审核的产品，可按语言过滤。
+     */
+    pub async fn get_feed_products(
+        &self,
+        language: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<Product>> {
+        let params = QueryParams {
+            category: None,
+            tags: None,
+            language: language
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty()),
+            status: Some("approved".to_string()),
+            search: None,
+            maker_email: None,
+            sort: Some("created_at".to_string()),
+            dir: Some("desc".to_string()),
+            limit: Some(limit.clamp(1, 100)),
+            offset: None,
+            user_id: None,
+            created_after: None,
+            created_before: None,
+            min_likes: None,
+            sponsor_verified: None,
+            include_pending: None,
+            seed: None,
+        };
+        self.get_products(params).await
+    }
+
+    /**
+     * get_products_by_ids
+     * 按传入顺序返回产品（不存在的 id 跳过）；id 数量超过 MAX_PRODUCTS_BY_IDS 时报错，language 为 Some 时只返回该语言的产品。
+     */
+    pub async fn get_products_by_ids(
+        &self,
+        ids: &[String],
+        language: Option<&str>,
+    ) -> Result<Vec<Product>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        if ids.len() > MAX_PRODUCTS_BY_IDS {
+            return Err(TooManyProductIdsError {
+                max: MAX_PRODUCTS_BY_IDS,
+                actual: ids.len(),
+            }
+            .into());
+        }
+        let language = language.map(|v| v.trim()).filter(|v| !v.is_empty());
+
+        if let Some(pool) = &self.postgres {
+            let mut last_err: Option<anyhow::Error> = None;
+            for attempt in 0..2 {
+                let attempt_result: Result<Vec<ProductRow>> = async {
+                    let mut tx = pool.begin().await?;
+                    let mut qb: QueryBuilder<Postgres> = QueryBuilder::new(
+                        "SELECT \
+                            p.id::text as id, \
+                            p.name, \
+                            p.slogan, \
+                            p.description, \
+                            p.website, \
+                            p.logo_url, \
+                            p.category, \
+                            COALESCE(p.tags, ARRAY[]::text[]) as tags, \
+                            p.maker_name, \
+                            p.maker_email, \
+                            p.maker_website, \
+                            p.language, \
+                            p.status::text as status, \
+                            p.rejection_reason, \
+                            p.created_at, \
+                            p.updated_at, \
+                            (SELECT COUNT(*)::bigint FROM product_likes l WHERE l.product_id = p.id) as likes, \
+                            (SELECT COUNT(*)::bigint FROM product_favorites f WHERE f.product_id = p.id) as favorites, \
+                            COALESCE(d.sponsor_role, NULL::text) as maker_sponsor_role, \
+                            COALESCE(d.sponsor_verified, FALSE) as maker_sponsor_verified \
+                         FROM products p \
+                         LEFT JOIN developers d ON lower(d.email) = lower(p.maker_email) \
+                         WHERE p.id::text = ANY(",
+                    );
+                    qb.push_bind(ids);
+                    qb.push(")");
+                    if let Some(language) = language {
+                        qb.push(" AND p.language = ");
+                        qb.push_bind(strip_nul_str(language).into_owned());
+                    }
+
+                    let rows = qb
+                        .build_query_as::<ProductRow>()
+                        .persistent(false)
+                        .fetch_all(&mut *tx)
+                        .await?;
+                    tx.commit().await?;
+                    Ok(rows)
+                }
+                .await;
+
+                match attempt_result {
+                    Ok(rows) => {
+                        let mut map = std::collections::HashMap::<String, Product>::new();
+                        for row in rows {
+                            let product = map_product_row(row);
+                            map.insert(product.id.clone(), product);
+                        }
+
+                        let mut ordered = Vec::with_capacity(ids.len());
+                        for id in ids {
+                            if let Some(p) = map.remove(id) {
+                                ordered.push(p);
+                            }
+                        }
+                        return Ok(ordered);
+                    }
+                    Err(e) => {
+                        if is_missing_column_error(&e, "rejection_reason")
+                            && !PRODUCTS_REJECTION_REASON_READY.load(Ordering::Relaxed)
+                            && ensure_products_rejection_reason_column(pool).await.is_ok()
+                        {
+                            continue;
+                        }
+                        if (is_missing_column_error(&e, "sponsor_role")
+                            || is_missing_column_error(&e, "sponsor_verified"))
+                            && !DEVELOPERS_SPONSOR_COLUMNS_READY.load(Ordering::Relaxed)
+                            && ensure_developers_sponsor_columns(pool).await.is_ok()
+                        {
+                            continue;
+                        }
+                        last_err = Some(e);
+                        let Some(ref err) = last_err else {
+                            continue;
+                        };
+                        if is_retryable_db_error(err) && self.supabase.is_some() {
+                            break;
+                        }
+                        if attempt == 0 && is_retryable_db_error(err) {
+                            continue;
+                        }
+                        return Err(last_err.unwrap());
+                    }
+                }
+            }
+
+            if let Some(e) = last_err {
+                if !(is_retryable_db_error(&e) && self.supabase.is_some()) {
+                    return Err(e);
+                }
+            }
+        }
+
+        let mut ordered = Vec::new();
+        for id in ids {
+            if let Some(p) = self.get_product_by_id(id).await? {
+                if language.is_none_or(|lang| p.language == lang) {
+                    ordered.push(p);
+                }
+            }
+        }
+        Ok(ordered)
+    }
+
+    pub async fn get_home_module_state(&self, key: &str) -> Result<Option<HomeModuleState>> {
+        if let Some(pool) = &self.postgres {
+            let mut tx = pool.begin().await?;
+            let row = sqlx::query_as::<_, HomeModuleStateRow>(
+                "SELECT key, mode, day_key, remaining_ids, today_ids FROM home_module_state WHERE key = $1 LIMIT 1",
+            )
+            .persistent(false)
+            .bind(key)
+            .fetch_optional(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            return Ok(row.map(map_home_module_state_row));
+        }
+
+        Err(anyhow::Error::new(PostgresNotConfiguredError))
+    }
+
+    pub async fn upsert_home_module_state(&self, state: HomeModuleState) -> Result<()> {
+        if let Some(pool) = &self.postgres {
+            let mut tx = pool.begin().await?;
+            sqlx::query(
+                "INSERT INTO home_module_state (key, mode, day_key, remaining_ids, today_ids) \
+                 VALUES ($1, $2, $3, $4, $5) \
+                 ON CONFLICT (key) DO UPDATE SET \
+                    mode = EXCLUDED.mode, \
+                    day_key = EXCLUDED.day_key, \
+                    remaining_ids = EXCLUDED.remaining_ids, \
+                    today_ids = EXCLUDED.today_ids, \
+                    updated_at = NOW()",
+            )
+            .persistent(false)
+            .bind(&state.key)
+            .bind(&state.mode)
+            .bind(state.day_key)
+            .bind(&state.remaining_ids)
+            .bind(&state.today_ids)
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            return Ok(());
+        }
+
+        Ok(())
+    }
+
+    /**
+     * patch_home_module_state
+     * 局部更新首页模块状态：未提供的字段保留原值（记录不存在时以空列表新建）。
+     */
+    pub async fn patch_home_module_state(
+        &self,
+        key: &str,
+        patch: HomeModuleStatePatch,
+    ) -> Result<()> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let mode_update = patch.mode.is_some();
+        let remaining_update = patch.remaining_ids.is_some();
+        let today_update = patch.today_ids.is_some();
+        let clean_ids = |ids: Vec<String>| -> Vec<String> {
+            ids.iter()
+                .map(|id| strip_nul_str(id).into_owned())
+                .collect()
+        };
+        let mode = patch.mode.map(|v| strip_nul_str(&v).into_owned());
+        let remaining_ids = patch.remaining_ids.map(clean_ids).unwrap_or_default();
+        let today_ids = patch.today_ids.map(clean_ids).unwrap_or_default();
+
+        sqlx::query(
+            "INSERT INTO home_module_state (key, mode, remaining_ids, today_ids) \
+             VALUES ($1, $2, $3, $4) \
+             ON CONFLICT (key) DO UPDATE SET \
+                mode = CASE WHEN $5 THEN EXCLUDED.mode ELSE home_module_state.mode END, \
+                remaining_ids = CASE WHEN $6 THEN EXCLUDED.remaining_ids ELSE home_module_state.remaining_ids END, \
+                today_ids = CASE WHEN $7 THEN EXCLUDED.today_ids ELSE home_module_state.today_ids END, \
+                updated_at = NOW()",
+        )
+        .persistent(false)
+        .bind(strip_nul_str(key).as_ref())
+        .bind(mode.as_deref())
+        .bind(&remaining_ids)
+        .bind(&today_ids)
+        .bind(mode_update)
+        .bind(remaining_update)
+        .bind(today_update)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /**
+     * get_recently_featured_ids
+     * 返回 [today - days, today) 期间入选过首页精选的产品 id；今天的入选不计入，保证当天结果稳定。
+     */
+    pub async fn get_recently_featured_ids(
+        &self,
+        today: chrono::NaiveDate,
+        days: i64,
+    ) -> Result<HashSet<String>> {
+        if days <= 0 {
+            return Ok(HashSet::new());
+        }
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+        ensure_home_featured_history_table(pool).await?;
+
+        let since = today - chrono::Duration::days(days);
+        let ids: Vec<String> = sqlx::query_scalar(
+            "SELECT DISTINCT product_id FROM home_featured_history WHERE day_key >= $1 AND day_key < $2",
+        )
+        .persistent(false)
+        .bind(since)
+        .bind(today)
+        .fetch_all(pool)
+        .await?;
+        Ok(ids.into_iter().collect())
+    }
+
+    /**
+     * record_featured_ids
+     * 记录今天入选首页精选的产品 id（重复写入忽略），并清理 90 天前的旧记录；
+     * 与本进程上次写入的当天入选相同时直接跳过，避免每次读取首页都写库。
+     */
+    pub async fn record_featured_ids(
+        &self,
+        today: chrono::NaiveDate,
+        ids: &[String],
+    ) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let mut ids: Vec<String> = ids
+            .iter()
+            .map(|id| strip_nul_str(id).into_owned())
+            .collect();
+        ids.sort();
+        ids.dedup();
+        if let Ok(guard) = self.featured_history_recorded.lock() {
+            if guard
+                .as_ref()
+                .is_some_and(|(day, recorded)| *day == today && *recorded == ids)
+            {
+                return Ok(());
+            }
+        }
+
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+        ensure_home_featured_history_table(pool).await?;
+        sqlx::query(
+            "INSERT INTO home_featured_history (product_id, day_key) \
+             SELECT UNNEST($1::text[]), $2 \
+             ON CONFLICT (product_id, day_key) DO NOTHING",
+        )
+        .persistent(false)
+        .bind(&ids)
+        .bind(today)
+        .execute(pool)
+        .await?;
+        sqlx::query("DELETE FROM home_featured_history WHERE day_key < $1")
+            .persistent(false)
+            .bind(today - chrono::Duration::days(90))
+            .execute(pool)
+            .await?;
+        if let Ok(mut guard) = self.featured_history_recorded.lock() {
+            *guard = Some((today, ids));
+        }
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub async fn get_first_developer_emails_by_created_at(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<String>> {
+        let limit = limit.clamp(1, 5000);
+        if let Some(pool) = &self.postgres {
+            let rows = sqlx::query_as::<_, NewsletterRecipientRow>(
+                "SELECT email FROM developers ORDER BY created_at ASC, email ASC LIMIT $1",
+            )
+            .persistent(false)
+            .bind(limit)
+            .fetch_all(pool)
+            .await?;
+            return Ok(rows
+                .into_iter()
+                .map(|r| strip_nul_str(&r.email).into_owned())
+                .collect());
+        }
+        Err(anyhow::Error::new(PostgresNotConfiguredError))
+    }
+
+    #[allow(dead_code)]
+    pub async fn get_free_sponsorship_candidate_product_ids(
+        &self,
+        first_n_developers: i64,
+        window_days: i64,
+        now: chrono::DateTime<chrono::Utc>,
+        language: Option<&str>,
+    ) -> Result<Vec<String>> {
+        if let Some(pool) = &self.postgres {
+            let emails = self
+                .get_first_developer_emails_by_created_at(first_n_developers)
+                .await?;
+            if emails.is_empty() {
+                return Ok(Vec::new());
+            }
+
+            let since = now - chrono::Duration::days(window_days.max(1));
+            let status_clause = product_visibility_clause("p");
+
+            let rows = if let Some(language) = language {
+                let sql = format!(
+                    "SELECT p.id::text as email \
+                     FROM products p \
+                     WHERE {} AND p.created_at >= $1 AND p.maker_email = ANY($2) AND p.language = $3 \
+                     ORDER BY p.created_at DESC, p.id ASC \
+                     LIMIT 5000",
+                    status_clause
+                );
+                sqlx::query_as::<_, NewsletterRecipientRow>(&sql)
+                    .persistent(false)
+                    .bind(since)
+                    .bind(&emails)
+                    .bind(language)
+                    .fetch_all(pool)
+                    .await?
+            } else {
+                let sql = format!(
+                    "SELECT p.id::text as email \
+                     FROM products p \
+                     WHERE {} AND p.created_at >= $1 AND p.maker_email = ANY($2) \
+                     ORDER BY p.created_at DESC, p.id ASC \
+                     LIMIT 5000",
+                    status_clause
+                );
+                sqlx::query_as::<_, NewsletterRecipientRow>(&sql)
+                    .persistent(false)
+                    .bind(since)
+                    .bind(&emails)
+                    .fetch_all(pool)
+                    .await?
+            };
+
+            return Ok(rows
+                .into_iter()
+                .map(|r| strip_nul_str(&r.email).into_owned())
+                .collect());
+        }
+
+        Err(anyhow::Error::new(PostgresNotConfiguredError))
+    }
+
+    pub async fn get_first_product_ids_by_created_at(
+        &self,
+        limit: i64,
+        language: Option<&str>,
+    ) -> Result<Vec<String>> {
+        let limit = limit.clamp(1, 5000);
+        if let Some(pool) = &self.postgres {
+            let status_clause = product_visibility_clause("p");
+
+            let rows = if let Some(language) = language {
+                let sql = format!(
+                    "SELECT p.id::text as email \
+                     FROM products p \
+                     WHERE {} AND p.language = $2 \
+                     ORDER BY p.created_at ASC, p.id ASC \
+                     LIMIT $1",
+                    status_clause
+                );
+                sqlx::query_as::<_, NewsletterRecipientRow>(&sql)
+                    .persistent(false)
+                    .bind(limit)
+                    .bind(language)
+                    .fetch_all(pool)
+                    .await?
+            } else {
+                let sql = format!(
+                    "SELECT p.id::text as email \
+                     FROM products p \
+                     WHERE {} \
+                     ORDER BY p.created_at ASC, p.id ASC \
+                     LIMIT $1",
+                    status_clause
+                );
+                sqlx::query_as::<_, NewsletterRecipientRow>(&sql)
+                    .persistent(false)
+                    .bind(limit)
+                    .fetch_all(pool)
+                    .await?
+            };
+
+            return Ok(rows
+                .into_iter()
+                .map(|r| strip_nul_str(&r.email).into_owned())
+                .collect());
+        }
+
+        Err(anyhow::Error::new(PostgresNotConfiguredError))
+    }
+
+    pub async fn count_products_for_sponsorship_rotation(
+        &self,
+        language: Option<&str>,
+    ) -> Result<i64> {
+        if let Some(pool) = &self.postgres {
+            let status_clause = product_visibility_clause("p");
+
+            let row = if let Some(language) = language {
+                let sql = format!(
+                    "SELECT COUNT(*)::bigint \
+                     FROM products p \
+                     WHERE {} AND p.language = $1",
+                    status_clause
+                );
+                sqlx::query_as::<_, (i64,)>(&sql)
+                    .persistent(false)
+                    .bind(language)
+                    .fetch_one(pool)
+                    .await?
+            } else {
+                let sql = format!(
+                    "SELECT COUNT(*)::bigint \
+                     FROM products p \
+                     WHERE {}",
+                    status_clause
+                );
+                sqlx::query_as::<_, (i64,)>(&sql)
+                    .persistent(false)
+                    .fetch_one(pool)
+                    .await?
+            };
+
+            return Ok(row.0);
+        }
+
+        Err(anyhow::Error::new(PostgresNotConfiguredError))
+    }
+
+    pub async fn get_popular_product_ids_by_day(
+        &self,
+        day: chrono::NaiveDate,
+        limit: i64,
+        language: Option<&str>,
+    ) -> Result<Vec<String>> {
+        let limit = limit.clamp(1, 5000);
+        if let Some(pool) = &self.postgres {
+            let status_clause = product_visibility_clause("p");
+
+            let start = chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(
+                day.and_hms_opt(0, 0, 0).unwrap_or_default(),
+                chrono::Utc,
+            );
+            let end = start + chrono::Duration::days(1);
+
+            let rows = if let Some(language) = language {
+                let sql = format!(
+                    "WITH likes AS ( \
+                        SELECT product_id, COUNT(*)::bigint AS likes \
+                        FROM product_likes \
+                        WHERE created_at >= $1 AND created_at < $2 \
+                        GROUP BY product_id \
+                    ), favs AS ( \
+                        SELECT product_id, COUNT(*)::bigint AS favorites \
+                        FROM product_favorites \
+                        WHERE created_at >= $1 AND created_at < $2 \
+                        GROUP BY product_id \
+                    ) \
+                    SELECT p.id::text as email \
+                    FROM products p \
+                    LEFT JOIN likes l ON l.product_id = p.id \
+                    LEFT JOIN favs f ON f.product_id = p.id \
+                    WHERE {} AND p.language = $3 \
+                    ORDER BY (COALESCE(l.likes, 0) + COALESCE(f.favorites, 0)) DESC, p.created_at DESC, p.id ASC \
+                    LIMIT $4",
+                    status_clause
+                );
+                sqlx::query_as::<_, NewsletterRecipientRow>(&sql)
+                    .persistent(false)
+                    .bind(start)
+                    .bind(end)
+                    .bind(language)
+                    .bind(limit)
+                    .fetch_all(pool)
+                    .await?
+            } else {
+                let sql = format!(
+                    "WITH likes AS ( \
+                        SELECT product_id, COUNT(*)::bigint AS likes \
+                        FROM product_likes \
+                        WHERE created_at >= $1 AND created_at < $2 \
+                        GROUP BY product_id \
+                    ), favs AS ( \
+                        SELECT product_id, COUNT(*)::bigint AS favorites \
+                        FROM product_favorites \
+                        WHERE created_at >= $1 AND created_at < $2 \
+                        GROUP BY product_id \
+                    ) \
+                    SELECT p.id::text as email \
+                    FROM products p \
+                    LEFT JOIN likes l ON l.product_id = p.id \
+                    LEFT JOIN favs f ON f.product_id = p.id \
+                    WHERE {} \
+                    ORDER BY (COALESCE(l.likes, 0) + COALESCE(f.favorites, 0)) DESC, p.created_at DESC, p.id ASC \
+                    LIMIT $3",
+                    status_clause
+                );
+                sqlx::query_as::<_, NewsletterRecipientRow>(&sql)
+                    .persistent(false)
+                    .bind(start)
+                    .bind(end)
+                    .bind(limit)
+                    .fetch_all(pool)
+                    .await?
+            };
+
+            return Ok(rows
+                .into_iter()
+                .map(|r| strip_nul_str(&r.email).into_owned())
+                .collect());
+        }
+
+        Err(anyhow::Error::new(PostgresNotConfiguredError))
+    }
+
+    pub async fn get_active_sponsorship_grants(
+        &self,
+        placement: Placement,
+        now: chrono::DateTime<chrono::Utc>,
+        language: Option<&str>,
+    ) -> Result<Vec<ActiveSponsorshipGrant>> {
+        if let Some(pool) = &self.postgres {
+            ensure_sponsorship_grants_active_index(pool).await;
+            let capacity = placement_slot_count(placement);
+            let placement = placement.to_db_str();
+
+            let mut last_err: Option<anyhow::Error> = None;
+            for _attempt_idx in 0..2 {
+                let attempt = if let Some(language) = language {
+                    let sql = active_sponsorship_grants_sql(true);
+                    sqlx::query_as::<_, SponsorshipGrantRow>(&sql)
+                        .persistent(false)
+                        .bind(placement)
+                        .bind(now)
+                        .bind(language)
+                        .fetch_all(pool)
+                        .await
+                } else {
+                    let sql = active_sponsorship_grants_sql(false);
+                    sqlx::query_as::<_, SponsorshipGrantRow>(&sql)
+                        .persistent(false)
+                        .bind(placement)
+                        .bind(now)
+                        .fetch_all(pool)
+                        .await
+                };
+
+                match attempt {
+                    Ok(rows) => {
+                        return Ok(rows
+                            .into_iter()
+                            .filter(|r| r.slot_index.is_none_or(|i| (0..capacity).contains(&i)))
+                            .map(|r| ActiveSponsorshipGrant {
+                                slot_index: r.slot_index,
+                                product_id: strip_nul_str(&r.product_id).into_owned(),
+                                ends_at: r.ends_at,
+                            })
+                            .collect());
+                    }
+                    Err(e) => {
+                        let e: anyhow::Error = e.into();
+                        if is_missing_relation_error(&e, "sponsorship_grants")
+                            && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
+                            && ensure_sponsorship_tables(pool).await.is_ok()
+                        {
+                            continue;
+                        }
+                        last_err = Some(e);
+                        break;
+                    }
+                }
+            }
+
+            return Err(last_err.unwrap_or_else(|| {
+                anyhow::anyhow!("Failed to fetch active sponsorship grants after auto migration")
+            }));
+        }
+
+        Err(anyhow::Error::new(PostgresNotConfiguredError))
+    }
+
+    pub async fn create_sponsorship_request(
+        &self,
+        req: CreateSponsorshipRequest,
+    ) -> Result<SponsorshipRequest> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let email = strip_nul_str(req.email.trim());
+        let product_ref = strip_nul_str(req.product_ref.trim());
+        let placement = req.placement.to_db_str();
+        check_sponsorship_slot(req.placement, req.slot_index)?;
+        let note = req
+            .note
+            .as_ref()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+        for _attempt_idx in 0..2 {
+            let attempt = sqlx::query_as::<_, SponsorshipRequestRow>(
+                "INSERT INTO sponsorship_requests (email, product_ref, placement, slot_index, duration_days, note) \
+                 VALUES ($1, $2, $3, $4, $5, $6) \
+                 RETURNING id, email, product_ref, placement, slot_index, duration_days, note, status, processed_grant_id, created_at, updated_at",
+            )
+            .persistent(false)
+            .bind(email.as_ref())
+            .bind(product_ref.as_ref())
+            .bind(placement)
+            .bind(req.slot_index)
+            .bind(req.duration_days)
+            .bind(note.as_deref())
+            .fetch_one(pool)
+            .await;
+
+            match attempt {
+                Ok(row) => return Ok(map_sponsorship_request_row(row)),
+                Err(e) => {
+                    let e: anyhow::Error = e.into();
+                    if is_missing_relation_error(&e, "sponsorship_requests")
+                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_sponsorship_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        Err(anyhow::anyhow!(
+            "Failed to create sponsorship request after auto migration"
+        ))
+    }
+
+    /**
+     * list_sponsorship_requests
+     * 管理端赞助申请列表：可按状态过滤、按邮箱/product_ref 模糊搜索（ILIKE，已转义），
+     * 按 created_at 或 duration_days 倒序排序并分页；同时返回符合条件的总数。
+     */
+    pub async fn list_sponsorship_requests(
+        &self,
+        params: SponsorshipRequestListParams,
+    ) -> Result<(Vec<SponsorshipRequest>, i64)> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let limit = params.limit.clamp(1, 200);
+        let offset = params.offset.max(0);
+        let status = params
+            .status
+            .as_deref()
+            .map(|v| strip_nul_str(v.trim()).into_owned())
+            .filter(|v| !v.is_empty());
+        let pattern = params
+            .q
+            .as_deref()
+            .map(|v| strip_nul_str(v.trim()).into_owned())
+            .filter(|v| !v.is_empty())
+            .map(|v| format!("%{}%", escape_like_pattern(&v)));
+
+        let push_filters = |qb: &mut QueryBuilder<Postgres>| {
+            qb.push(" WHERE 1=1");
+            if let Some(status) = status.as_ref() {
+                qb.push(" AND status = ");
+                qb.push_bind(status.clone());
+            }
+            if let Some(pattern) = pattern.as_ref() {
+                qb.push(" AND (email ILIKE ");
+                qb.push_bind(pattern.clone());
+                qb.push(" ESCAPE '\\' OR product_ref ILIKE ");
+                qb.push_bind(pattern.clone());
+                qb.push(" ESCAPE '\\')");
+            }
+        };
+
+        let mut last_err: Option<anyhow::Error> = None;
+        for _attempt_idx in 0..2 {
+            let attempt: Result<(Vec<SponsorshipRequest>, i64), anyhow::Error> = async {
+                let mut qb: QueryBuilder<Postgres> = QueryBuilder::new(
+                    "SELECT id, email, product_ref, placement, slot_index, duration_days, note, status, processed_grant_id, created_at, updated_at \
+                     FROM sponsorship_requests",
+                );
+                push_filters(&mut qb);
+                qb.push(match params.sort {
+                    SponsorshipRequestSort::CreatedAt => " ORDER BY created_at DESC, id DESC",
+                    SponsorshipRequestSort::DurationDays => {
+                        " ORDER BY duration_days DESC, created_at DESC, id DESC"
+                    }
+                });
+                qb.push(" LIMIT ");
+                qb.push_bind(limit);
+                qb.push(" OFFSET ");
+                qb.push_bind(offset);
+                let rows = qb
+                    .build_query_as::<SponsorshipRequestRow>()
+                    .persistent(false)
+                    .fetch_all(pool)
+                    .await?;
+
+                let mut count_qb: QueryBuilder<Postgres> =
+                    QueryBuilder::new("SELECT COUNT(*)::bigint FROM sponsorship_requests");
+                push_filters(&mut count_qb);
+                let total = count_qb
+                    .build_query_scalar::<i64>()
+                    .persistent(false)
+                    .fetch_one(pool)
+                    .await?;
+
+                Ok((
+                    rows.into_iter().map(map_sponsorship_request_row).collect(),
+                    total,
+                ))
+            }
+            .await;
+
+            match attempt {
+                Ok(v) => return Ok(v),
+                Err(e) => {
+                    if is_missing_relation_error(&e, "sponsorship_requests")
+                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_sponsorship_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    last_err = Some(e);
+                    break;
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| {
+            anyhow::anyhow!("Failed to list sponsorship requests after auto migration")
+        }))
+    }
+
+    pub async fn get_sponsorship_request_by_id(
+        &self,
+        id: i64,
+    ) -> Result<Option<SponsorshipRequest>> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let mut last_err: Option<anyhow::Error> = None;
+        for _attempt_idx in 0..2 {
+            let attempt = sqlx::query_as::<_, SponsorshipRequestRow>(
+                "SELECT id, email, product_ref, placement, slot_index, duration_days, note, status, processed_grant_id, created_at, updated_at \
+                 FROM sponsorship_requests \
+                 WHERE id = $1",
+            )
+            .persistent(false)
+            .bind(id)
+            .fetch_optional(pool)
+            .await;
+
+            match attempt {
+                Ok(row) => return Ok(row.map(map_sponsorship_request_row)),
+                Err(e) => {
+                    let e: anyhow::Error = e.into();
+                    if is_missing_relation_error(&e, "sponsorship_requests")
+                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_sponsorship_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    last_err = Some(e);
+                    break;
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| {
+            anyhow::anyhow!("Failed to fetch sponsorship request after auto migration")
+        }))
+    }
+
+    pub async fn reject_sponsorship_request(&self, id: i64, note: Option<&str>) -> Result<bool> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let note = note.map(|v| v.trim()).filter(|v| !v.is_empty());
+        for _attempt_idx in 0..2 {
+            let attempt = sqlx::query(
+                "UPDATE sponsorship_requests \
+                 SET status = 'rejected', note = COALESCE($2, note), updated_at = NOW() \
+                 WHERE id = $1 AND status = 'pending'",
+            )
+            .persistent(false)
+            .bind(id)
+            .bind(note)
+            .execute(pool)
+            .await;
+
+            match attempt {
+                Ok(res) => return Ok(res.rows_affected() > 0),
+                Err(e) => {
+                    let e: anyhow::Error = e.into();
+                    if is_missing_relation_error(&e, "sponsorship_requests")
+                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_sponsorship_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
+    pub async fn upsert_developer_sponsor(
+        &self,
+        email: &str,
+        sponsor_role: Option<&str>,
+        sponsor_verified: bool,
+    ) -> Result<bool> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let email_lower = email.trim().to_ascii_lowercase();
+        let email_clean = strip_nul_str(email_lower.as_str());
+        let role = sponsor_role.map(|v| strip_nul_str(v.trim()).into_owned());
+
+        for _attempt_idx in 0..2 {
+            let attempt = sqlx::query(
+                "INSERT INTO developers (email, name, sponsor_role, sponsor_verified) \
+                 VALUES ($1, $2, $3, $4) \
+                 ON CONFLICT (email) DO UPDATE SET \
+                    sponsor_role = EXCLUDED.sponsor_role, \
+                    sponsor_verified = EXCLUDED.sponsor_verified, \
+                    updated_at = NOW()",
+            )
+            .persistent(false)
+            .bind(email_clean.as_ref())
+            .bind(email_clean.as_ref())
+            .bind(role.as_deref())
+            .bind(sponsor_verified)
+            .execute(pool)
+            .await;
+
+            match attempt {
+                Ok(res) => return Ok(res.rows_affected() > 0),
+                Err(e) => {
+                    let e: anyhow::Error = e.into();
+                    if (is_missing_column_error(&e, "sponsor_role")
+                        || is_missing_column_error(&e, "sponsor_verified"))
+                        && !DEVELOPERS_SPONSOR_COLUMNS_READY.load(Ordering::Relaxed)
+                        && ensure_developers_sponsor_columns(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
+    /**
+     * update_developer_sponsor
+     * 管理端部分更新已有开发者的赞助身份：sponsor_role 为 None 时保留原角色，Some(None) 清空；
+     * sponsor_verified 为 None 时保留原值。开发者不存在时返回 DeveloperNotFoundError。
+     */
+    pub async fn update_developer_sponsor(
+        &self,
+        email: &str,
+        sponsor_role: Option<Option<&str>>,
+        sponsor_verified: Option<bool>,
+    ) -> Result<()> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let email_lower = email.trim().to_ascii_lowercase();
+        let email_clean = strip_nul_str(email_lower.as_str());
+        let role = sponsor_role
+            .flatten()
+            .map(|v| strip_nul_str(v.trim()).into_owned());
+
+        for _attempt_idx in 0..2 {
+            let attempt = sqlx::query(
+                "UPDATE developers SET \
+                    sponsor_role = CASE WHEN $2 THEN $3 ELSE sponsor_role END, \
+                    sponsor_verified = COALESCE($4, sponsor_verified), \
+                    updated_at = NOW() \
+                 WHERE lower(email) = $1",
+            )
+            .persistent(false)
+            .bind(email_clean.as_ref())
+            .bind(sponsor_role.is_some())
+            .bind(role.as_deref())
+            .bind(sponsor_verified)
+            .execute(pool)
+            .await;
+
+            match attempt {
+                Ok(res) if res.rows_affected() == 0 => {
+                    return Err(DeveloperNotFoundError {
+                        role: "sponsor",
+                        email: email_clean.into_owned(),
+                    }
+                    .into());
+                }
+                Ok(_) => return Ok(()),
+                Err(e) => {
+                    let e: anyhow::Error = e.into();
+                    if (is_missing_column_error(&e, "sponsor_role")
+                        || is_missing_column_error(&e, "sponsor_verified"))
+                        && !DEVELOPERS_SPONSOR_COLUMNS_READY.load(Ordering::Relaxed)
+                        && ensure_developers_sponsor_columns(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        Err(anyhow::anyhow!(
+            "Failed to update developer sponsor after auto migration"
+        ))
+    }
+
+    /**
+     * resolve_product_id_by_ref
+     * 将 product_ref 解析为产品 ID：UUID 直接返回，否则与 resolve_product_refs 一致，
+     * 最高匹配等级只有一个产品时才返回；同级多个产品视为歧义返回 None，由管理员手动指定。
+     */
+    pub async fn resolve_product_id_by_ref(&self, product_ref: &str) -> Result<Option<String>> {
+        let raw = product_ref.trim();
+        if raw.is_empty() {
+            return Ok(None);
+        }
+
+        if let Ok(uuid) = uuid::Uuid::parse_str(raw) {
+            return Ok(Some(uuid.to_string()));
+        }
+
+        let key = strip_nul_str(raw).into_owned();
+        Ok(self
+            .resolve_product_refs(std::slice::from_ref(&key))
+            .await?
+            .remove(&key)
+            .map(|m| m.id))
+    }
+
+    /**
+     * resolve_product_candidates
+     * 列出与 product_ref 匹配的产品，按匹配程度（UUID > 官网完全匹配 > 名称完全匹配 > 前缀 > 子串）排序，
+     * 同级按创建时间倒序；供管理端在歧义时人工选择。官网比较忽略大小写、协议、www. 与末尾斜杠。
+     */
+    pub async fn resolve_product_candidates(
+        &self,
+        product_ref: &str,
+        limit: i64,
+    ) -> Result<Vec<ProductRefCandidate>> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let q = strip_nul_str(product_ref.trim()).into_owned();
+        if q.is_empty() {
+            return Ok(Vec::new());
+        }
+        let escaped = escape_like_pattern(&q);
+        let prefix = format!("{}%", escaped);
+        let substring = format!("%{}%", escaped);
+
+        #[derive(sqlx::FromRow)]
+        struct Row {
+            id: String,
+            name: String,
+            website: String,
+            logo_url: Option<String>,
+            created_at: chrono::DateTime<chrono::Utc>,
+            match_rank: i32,
+        }
+
+        let sql = format!(
+            "SELECT id, name, website, logo_url, created_at, match_rank FROM ( \
+                SELECT p.id::text as id, p.name, p.website, p.logo_url, p.created_at, p.id as sort_id, \
+                       {} as match_rank \
+                FROM products p \
+                WHERE {} \
+             ) candidates \
+             ORDER BY match_rank ASC, created_at DESC, sort_id ASC \
+             LIMIT $4",
+            product_ref_match_rank_sql("$1", "$2"),
+            product_ref_match_filter_sql("$1", "$3"),
+        );
+        let rows = sqlx::query_as::<_, Row>(&sql)
+            .persistent(false)
+            .bind(&q)
+            .bind(&prefix)
+            .bind(&substring)
+            .bind(limit.clamp(1, 50))
+            .fetch_all(pool)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ProductRefCandidate {
+                id: strip_nul_str(&row.id).into_owned(),
+                name: strip_nul_str(&row.name).into_owned(),
+                website: strip_nul_str(&row.website).into_owned(),
+                logo_url: row.logo_url.map(|v| strip_nul_str(&v).into_owned()),
+                match_kind: ProductRefMatch::from_rank(row.match_rank),
+                created_at: row.created_at,
+            })
+            .collect())
+    }
+
+    /**
+     * resolve_product_refs
+     * 批量解析赞助申请的 product_ref，单次查询完成；匹配规则与排序同 resolve_product_candidates，
+     * 最高匹配等级命中多个产品视为歧义，不返回结果。
+     */
+    pub async fn resolve_product_refs(
+        &self,
+        refs: &[String],
+    ) -> Result<HashMap<String, ResolvedProductRef>> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let mut unique_refs: Vec<String> = Vec::new();
+        for raw in refs {
+            let r = strip_nul_str(raw.trim()).into_owned();
+            if !r.is_empty() && !unique_refs.contains(&r) {
+                unique_refs.push(r);
+            }
+        }
+        if unique_refs.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let prefixes: Vec<String> = unique_refs
+            .iter()
+            .map(|r| format!("{}%", escape_like_pattern(r)))
+            .collect();
+        let substrings: Vec<String> = unique_refs
+            .iter()
+            .map(|r| format!("%{}%", escape_like_pattern(r)))
+            .collect();
+
+        #[derive(sqlx::FromRow)]
+        struct Row {
+            product_ref: String,
+            match_rank: i32,
+            id: String,
+            name: String,
+            logo_url: Option<String>,
+        }
+
+        // 每个 ref 只取排序最前的两个候选：足以判断最高等级是否唯一
+        let sql = format!(
+            "SELECT product_ref, match_rank, id, name, logo_url FROM ( \
+                SELECT product_ref, match_rank, id, name, logo_url, \
+                       ROW_NUMBER() OVER ( \
+                           PARTITION BY product_ref \
+                           ORDER BY match_rank ASC, created_at DESC, sort_id ASC \
+                       ) as rn \
+                FROM ( \
+                    SELECT r.product_ref, p.id::text as id, p.name, p.logo_url, p.created_at, p.id as sort_id, \
+                           {} as match_rank \
+                    FROM unnest($1::text[], $2::text[], $3::text[]) AS r(product_ref, prefix, pattern) \
+                    JOIN products p ON {} \
+                ) matched \
+             ) candidates \
+             WHERE rn <= 2",
+            product_ref_match_rank_sql("r.product_ref", "r.prefix"),
+            product_ref_match_filter_sql("r.product_ref", "r.pattern"),
+        );
+        let rows = sqlx::query_as::<_, Row>(&sql)
+            .persistent(false)
+            .bind(&unique_refs)
+            .bind(&prefixes)
+            .bind(&substrings)
+            .fetch_all(pool)
+            .await?;
+
+        let mut candidates: HashMap<String, Vec<(i32, ResolvedProductRef)>> = HashMap::new();
+        for row in rows {
+            candidates.entry(row.product_ref).or_default().push((
+                row.match_rank,
+                ResolvedProductRef {
+                    id: strip_nul_str(&row.id).into_owned(),
+                    name: strip_nul_str(&row.name).into_owned(),
+                    logo_url: row.logo_url.map(|v| strip_nul_str(&v).into_owned()),
+                },
+            ));
+        }
+
+        Ok(candidates
+            .into_iter()
+            .filter_map(|(product_ref, matches)| {
+                pick_unambiguous_product_match(matches).map(|m| (product_ref, m))
+            })
+            .collect())
+    }
+
+    pub async fn create_sponsorship_grant_from_request(
+        &self,
+        input: CreateSponsorshipGrantFromRequest,
+    ) -> Result<SponsorshipGrant> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+        check_sponsorship_slot(input.placement, input.slot_index)?;
+
+        let mut last_err: Option<anyhow::Error> = None;
+        for _attempt_idx in 0..2 {
+            let mut tx = pool.begin().await?;
+
+            let attempt: Result<SponsorshipGrantFullRow, anyhow::Error> = async {
+                let requested_start = input.starts_at.unwrap_or_else(chrono::Utc::now);
+                lock_sponsorship_slot(&mut tx, input.placement.to_db_str(), input.slot_index)
+                    .await?;
+                let max_end: Option<chrono::DateTime<chrono::Utc>> = sqlx::query_scalar(
+                    "SELECT MAX(ends_at) FROM sponsorship_grants \
+                     WHERE placement = $1 AND slot_index IS NOT DISTINCT FROM $2",
+                )
+                .persistent(false)
+                .bind(input.placement.to_db_str())
+                .bind(input.slot_index)
+                .fetch_one(&mut *tx)
+                .await?;
+
+                let (starts_at, ends_at) =
+                    sponsorship_grant_window(requested_start, max_end, input.duration_days);
+
+                let product_id = strip_nul_str(&input.product_id);
+                let placement = input.placement.to_db_str();
+
+                let grant_row = sqlx::query_as::<_, SponsorshipGrantFullRow>(
+                    "INSERT INTO sponsorship_grants (product_id, placement, slot_index, starts_at, ends_at, source, amount_usd_cents) \
+                     VALUES ($1::uuid, $2, $3, $4, $5, 'request', $6) \
+                     RETURNING id, product_id::text as product_id, placement, slot_index, starts_at, ends_at, source, amount_usd_cents, created_at",
+                )
+                .persistent(false)
+                .bind(product_id.as_ref())
+                .bind(placement)
+                .bind(input.slot_index)
+                .bind(starts_at)
+                .bind(ends_at)
+                .bind(input.amount_usd_cents)
+                .fetch_one(&mut *tx)
+                .await?;
+
+                let updated = sqlx::query(
+                    "UPDATE sponsorship_requests \
+                     SET status = 'processed', processed_grant_id = $2, updated_at = NOW() \
+                     WHERE id = $1 AND status = 'pending'",
+                )
+                .persistent(false)
+                .bind(input.request_id)
+                .bind(grant_row.id)
+                .execute(&mut *tx)
+                .await?;
+
+                if updated.rows_affected() == 0 {
+                    return Err(SponsorshipRequestNotPendingError.into());
+                }
+
+                if let Some(email) = input.sponsor_email.as_deref() {
+                    mark_developer_as_sponsor(&mut tx, email).await?;
+                }
+
+                Ok(grant_row)
+            }
+            .await;
+
+            match attempt {
+                Ok(grant_row) => {
+                    tx.commit().await?;
+                    return Ok(map_sponsorship_grant_full_row(grant_row));
+                }
+                Err(e) => {
+                    let _ = tx.rollback().await;
+                    if (is_missing_relation_error(&e, "sponsorship_grants")
+                        || is_missing_relation_error(&e, "sponsorship_requests"))
+                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_sponsorship_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    if (is_missing_column_error(&e, "sponsor_role")
+                        || is_missing_column_error(&e, "sponsor_verified"))
+                        && !DEVELOPERS_SPONSOR_COLUMNS_READY.load(Ordering::Relaxed)
+                        && ensure_developers_sponsor_columns(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    last_err = Some(e);
+                    break;
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| {
+            anyhow::anyhow!("Failed to create sponsorship grant after auto migration")
+        }))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_sponsorship_order(
+        &self,
+        user_email: &str,
+        user_id: Option<&str>,
+        product_id: &str,
+        placement: Placement,
+        slot_index: Option<i32>,
+        requested_months: i32,
+        provider: &str,
+        pricing: Option<(&str, &str, Option<i32>, Option<i32>)>,
+    ) -> Result<String> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        // 接口层已校验范围，这里仅作兜底夹取。
+        let requested_months = requested_months.clamp(1, MAX_SPONSORSHIP_MONTHS);
+        let id = uuid::Uuid::new_v4();
+        let user_email = strip_nul_str(user_email.trim());
+        let user_id = user_id.map(|v| strip_nul_str(v.trim()).into_owned());
+        let product_id = strip_nul_str(product_id.trim());
+        if slot_index.is_some() {
+            check_sponsorship_slot(placement, slot_index)?;
+        }
+        let placement = placement.to_db_str();
+        let pricing_plan_id = pricing
+            .as_ref()
+            .and_then(|(id, _, _, _)| uuid::Uuid::parse_str(id.trim()).ok());
+        let pricing_plan_key = pricing
+            .as_ref()
+            .map(|(_, key, _, _)| strip_nul_str(key.trim()).into_owned())
+            .filter(|v| !v.is_empty());
+        let monthly_usd_cents = pricing.as_ref().and_then(|(_, _, cents, _)| *cents);
+        let discount_percent_off = pricing.as_ref().and_then(|(_, _, _, pct)| *pct);
+        let provider = strip_nul_str(provider.trim());
+
+        let mut last_err: Option<anyhow::Error> = None;
+        for _attempt_idx in 0..2 {
+            let attempt = sqlx::query_as::<_, SponsorshipOrderRow>(
+                "INSERT INTO sponsorship_orders (id, user_email, user_id, product_id, placement, slot_index, requested_months, status, provider, pricing_plan_id, pricing_plan_key, monthly_usd_cents, discount_percent_off) \
+                 VALUES ($1, $2, $3, $4::uuid, $5, $6, $7, 'created', $8, $9, $10, $11, $12) \
+                 RETURNING id, user_email, user_id, product_id::text as product_id, placement, slot_index, requested_months, paid_months, status, provider, provider_checkout_id, provider_order_id, amount_usd_cents, grant_id, created_at, updated_at",
+            )
+            .persistent(false)
+            .bind(id)
+            .bind(user_email.as_ref())
+            .bind(user_id.as_deref())
+            .bind(product_id.as_ref())
+            .bind(placement)
+            .bind(slot_index)
+            .bind(requested_months)
+            .bind(provider.as_ref())
+            .bind(pricing_plan_id)
+            .bind(pricing_plan_key.as_deref())
+            .bind(monthly_usd_cents)
+            .bind(discount_percent_off)
+            .fetch_one(pool)
+            .await;
+
+            match attempt {
+                Ok(row) => {
+                    let (id, _email) = map_sponsorship_order_row(row);
+                    return Ok(id);
+                }
+                Err(e) => {
+                    let e: anyhow::Error = e.into();
+                    if (is_missing_relation_error(&e, "sponsorship_orders")
+                        || is_missing_relation_error(&e, "sponsorship_grants")
+                        || is_missing_relation_error(&e, "sponsorship_requests"))
+                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_sponsorship_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    last_err = Some(e);
+                    break;
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to create sponsorship order")))
+    }
+
+    pub async fn set_sponsorship_order_provider_checkout_id(
+        &self,
+        order_id: &str,
+        provider_checkout_id: &str,
+    ) -> Result<bool> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let order_id = uuid::Uuid::parse_str(order_id.trim())
+            .map_err(|_| anyhow::anyhow!("Invalid order_id"))?;
+        let provider_checkout_id = strip_nul_str(provider_checkout_id.trim());
+
+        let mut last_err: Option<anyhow::Error> = None;
+        for _attempt_idx in 0..2 {
+            let attempt = sqlx::query(
+                "UPDATE sponsorship_orders SET provider_checkout_id = $2, updated_at = NOW() \
+                 WHERE id = $1",
+            )
+            .persistent(false)
+            .bind(order_id)
+            .bind(provider_checkout_id.as_ref())
+            .execute(pool)
+            .await;
+
+            match attempt {
+                Ok(res) => return Ok(res.rows_affected() > 0),
+                Err(e) => {
+                    let e: anyhow::Error = e.into();
+                    if is_missing_relation_error(&e, "sponsorship_orders")
+                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_sponsorship_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    last_err = Some(e);
+                    break;
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| {
+            anyhow::anyhow!("Failed to update sponsorship order after auto migration")
+        }))
+    }
+
+    pub async fn get_sponsorship_order_basic(
+        &self,
+        order_id: &str,
+    ) -> Result<
+        Option<(
+            String,
+            String,
+            String,
+            String,
+            Option<i32>,
+            i32,
+            Option<i64>,
+        )>,
+    > {
+        #[derive(sqlx::FromRow)]
+        struct Row {
+            status: String,
+            user_email: String,
+            product_id: String,
+            placement: String,
+            slot_index: Option<i32>,
+            requested_months: i32,
+            grant_id: Option<i64>,
+        }
+
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let order_uuid = uuid::Uuid::parse_str(order_id.trim())
+            .map_err(|_| anyhow::anyhow!("Invalid order_id"))?;
+
+        let mut last_err: Option<anyhow::Error> = None;
+        for _attempt_idx in 0..2 {
+            let attempt = sqlx::query_as::<_, Row>(
+                "SELECT status, user_email, product_id::text as product_id, placement, slot_index, requested_months, grant_id \
+                 FROM sponsorship_orders WHERE id = $1",
+            )
+            .persistent(false)
+            .bind(order_uuid)
+            .fetch_optional(pool)
+            .await;
+
+            match attempt {
+                Ok(Some(mut row)) => {
+                    strip_nul_in_place(&mut row.status);
+                    strip_nul_in_place(&mut row.user_email);
+                    strip_nul_in_place(&mut row.product_id);
+                    strip_nul_in_place(&mut row.placement);
+                    return Ok(Some((
+                        row.status,
+                        row.user_email,
+                        row.product_id,
+                        row.placement,
+                        row.slot_index,
+                        row.requested_months,
+                        row.grant_id,
+                    )));
+                }
+                Ok(None) => return Ok(None),
+                Err(e) => {
+                    let e: anyhow::Error = e.into();
+                    if is_missing_relation_error(&e, "sponsorship_orders")
+                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_sponsorship_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    last_err = Some(e);
+                    break;
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to fetch sponsorship order")))
+    }
+
+    pub async fn create_sponsorship_grant_and_mark_order_paid(
+        &self,
+        order_id: &str,
+        provider_order_id: Option<&str>,
+        amount_usd_cents: i32,
+        paid_months: i32,
+        source: &str,
+    ) -> Result<SponsorshipGrant> {
+        #[derive(sqlx::FromRow)]
+        struct OrderRow {
+            status: String,
+            product_id: String,
+            placement: String,
+            slot_index: Option<i32>,
+            grant_id: Option<i64>,
+        }
+
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let order_uuid = uuid::Uuid::parse_str(order_id.trim())
+            .map_err(|_| anyhow::anyhow!("Invalid order_id"))?;
+        let provider_order_id = provider_order_id
+            .map(|v| strip_nul_str(v.trim()).into_owned())
+            .filter(|v| !v.is_empty());
+        let paid_months = paid_months.clamp(1, 120);
+        let duration_days = months_to_days(paid_months, days_per_month());
+        let source = strip_nul_str(source.trim()).into_owned();
+        if source.is_empty() {
+            return Err(anyhow::anyhow!("Invalid source"));
+        }
+
+        let mut last_err: Option<anyhow::Error> = None;
+        for _attempt_idx in 0..2 {
+            let mut tx = pool.begin().await?;
+
+            let attempt: Result<SponsorshipGrantFullRow, anyhow::Error> = async {
+                let order = sqlx::query_as::<_, OrderRow>(
+                    "SELECT status, product_id::text as product_id, placement, slot_index, grant_id \
+                     FROM sponsorship_orders WHERE id = $1 FOR UPDATE",
+                )
+                .persistent(false)
+                .bind(order_uuid)
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("Sponsorship order not found"))?;
+
+                let slot_index = order.slot_index;
+                let order_grant_id = order.grant_id;
+                let mut status = order.status;
+                let mut product_id = order.product_id;
+                let mut placement = order.placement;
+                strip_nul_in_place(&mut status);
+                strip_nul_in_place(&mut product_id);
+                strip_nul_in_place(&mut placement);
+
+                if status == "paid" {
+                    if let Some(grant_id) = order_grant_id {
+                        return Ok(
+                            sqlx::query_as::<_, SponsorshipGrantFullRow>(
+                                "SELECT id, product_id::text as product_id, placement, slot_index, starts_at, ends_at, source, amount_usd_cents, created_at \
+                                 FROM sponsorship_grants WHERE id = $1",
+                            )
+                            .persistent(false)
+                            .bind(grant_id)
+                            .fetch_one(&mut *tx)
+                            .await?,
+                        );
+                    }
+                } else if status != "created" {
+                    return Err(anyhow::anyhow!(
+                        "Sponsorship order is not payable (status = {})",
+                        status
+                    ));
+                }
+
+                if let Some(existing) = sqlx::query_as::<_, SponsorshipGrantFullRow>(
+                    "SELECT id, product_id::text as product_id, placement, slot_index, starts_at, ends_at, source, amount_usd_cents, created_at \
+                     FROM sponsorship_grants WHERE order_id = $1",
+                )
+                .persistent(false)
+                .bind(order_uuid)
+                .fetch_optional(&mut *tx)
+                .await?
+                {
+                    let _ = sqlx::query(
+                        "UPDATE sponsorship_orders \
+                         SET status = 'paid', provider_order_id = $2, amount_usd_cents = $3, paid_months = $4, grant_id = $5, updated_at = NOW() \
+                         WHERE id = $1 AND status IN ('created', 'paid')",
+                    )
+                    .persistent(false)
+                    .bind(order_uuid)
+                    .bind(provider_order_id.as_deref())
+                    .bind(amount_usd_cents)
+                    .bind(paid_months)
+                    .bind(existing.id)
+                    .execute(&mut *tx)
+                    .await?;
+                    return Ok(existing);
+                }
+
+                lock_sponsorship_slot(&mut tx, &placement, slot_index).await?;
+                let max_end: Option<chrono::DateTime<chrono::Utc>> = sqlx::query_scalar(
+                    "SELECT MAX(ends_at) FROM sponsorship_grants \
+                     WHERE placement = $1 AND slot_index IS NOT DISTINCT FROM $2",
+                )
+                .persistent(false)
+                .bind(placement.as_str())
+                .bind(slot_index)
+                .fetch_one(&mut *tx)
+                .await?;
+
+                let (starts_at, ends_at) =
+                    sponsorship_grant_window(chrono::Utc::now(), max_end, duration_days);
+
+                let inserted = sqlx::query_as::<_, SponsorshipGrantFullRow>(
+                    "INSERT INTO sponsorship_grants (order_id, product_id, placement, slot_index, starts_at, ends_at, source, amount_usd_cents) \
+                     VALUES ($1, $2::uuid, $3, $4, $5, $6, $7, $8) \
+                     RETURNING id, product_id::text as product_id, placement, slot_index, starts_at, ends_at, source, amount_usd_cents, created_at",
+                )
+                .persistent(false)
+                .bind(order_uuid)
+                .bind(product_id.as_str())
+                .bind(placement.as_str())
+                .bind(slot_index)
+                .bind(starts_at)
+                .bind(ends_at)
+                .bind(source.as_str())
+                .bind(amount_usd_cents)
+                .fetch_one(&mut *tx)
+                .await?;
+
+                let updated = sqlx::query(
+                    "UPDATE sponsorship_orders \
+                     SET status = 'paid', provider_order_id = $2, amount_usd_cents = $3, paid_months = $4, grant_id = $5, updated_at = NOW() \
+                     WHERE id = $1 AND status IN ('created', 'paid')",
+                )
+                .persistent(false)
+                .bind(order_uuid)
+                .bind(provider_order_id.as_deref())
+                .bind(amount_usd_cents)
+                .bind(paid_months)
+                .bind(inserted.id)
+                .execute(&mut *tx)
+                .await?;
+
+                if updated.rows_affected() == 0 {
+                    if let Some(existing) = sqlx::query_as::<_, SponsorshipGrantFullRow>(
+                        "SELECT id, product_id::text as product_id, placement, slot_index, starts_at, ends_at, source, amount_usd_cents, created_at \
+                         FROM sponsorship_grants WHERE order_id = $1",
+                    )
+                    .persistent(false)
+                    .bind(order_uuid)
+                    .fetch_optional(&mut *tx)
+                    .await?
+                    {
+                        return Ok(existing);
+                    }
+                }
+
+                Ok(inserted)
+            }
+            .await;
+
+            match attempt {
+                Ok(grant_row) => {
+                    tx.commit().await?;
+                    return Ok(map_sponsorship_grant_full_row(grant_row));
+                }
+                Err(e) => {
+                    let _ = tx.rollback().await;
+                    if (is_missing_relation_error(&e, "sponsorship_grants")
+                        || is_missing_relation_error(&e, "sponsorship_orders"))
+                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_sponsorship_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    last_err = Some(e);
+                    break;
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| {
+            anyhow::anyhow!("Failed to create sponsorship grant from paid order")
+        }))
+    }
+
+    /**
+     * create_manual_sponsorship_grant
+     * 线下付款授权：单个事务内创建 provider='manual' 的已付订单（外部流水号记入 provider_order_id）、
+     * 按槽位排期创建 grant 并回写订单的 grant_id，任一步失败整体回滚。
+     * 以 external_reference 幂等：重复提交返回已有的订单与 grant。
+     */
+    pub async fn create_manual_sponsorship_grant(
+        &self,
+        input: &CreateManualSponsorshipGrant,
+    ) -> Result<ManualSponsorshipGrantResult> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        check_sponsorship_slot(input.placement, input.slot_index)?;
+        let months = check_requested_months(input.months)?;
+        let duration_days = months_to_days(months, days_per_month());
+        let user_email = strip_nul_str(input.user_email.trim()).into_owned();
+        let product_id = strip_nul_str(input.product_id.trim()).into_owned();
+        let external_reference = strip_nul_str(input.external_reference.trim()).into_owned();
+        if external_reference.is_empty() {
+            return Err(anyhow::anyhow!("Missing external reference"));
+        }
+        let placement = input.placement.to_db_str();
+        let slot_index = input.slot_index;
+        let amount_usd_cents = input.amount_usd_cents.max(0);
+
+        let mut last_err: Option<anyhow::Error> = None;
+        for _attempt_idx in 0..2 {
+            let mut tx = pool.begin().await?;
+
+            let attempt: Result<(SponsorshipOrderRow, SponsorshipGrantFullRow), anyhow::Error> =
+                async {
+                    // 同一 external_reference 只开通一次：串行化后先查已有的手动订单，重复提交直接返回原结果。
+                    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+                        .persistent(false)
+                        .bind(format!("manual_sponsorship_grant:{}", external_reference))
+                        .execute(&mut *tx)
+                        .await?;
+                    let existing = sqlx::query_as::<_, SponsorshipOrderRow>(
+                        "SELECT id, user_email, user_id, product_id::text as product_id, placement, slot_index, requested_months, paid_months, status, provider, provider_checkout_id, provider_order_id, amount_usd_cents, grant_id, created_at, updated_at \
+                         FROM sponsorship_orders \
+                         WHERE provider = 'manual' AND provider_order_id = $1 AND grant_id IS NOT NULL \
+                         ORDER BY created_at ASC \
+                         LIMIT 1",
+                    )
+                    .persistent(false)
+                    .bind(external_reference.as_str())
+                    .fetch_optional(&mut *tx)
+                    .await?;
+                    if let Some(order) = existing {
+                        let grant = sqlx::query_as::<_, SponsorshipGrantFullRow>(
+                            "SELECT id, product_id::text as product_id, placement, slot_index, starts_at, ends_at, source, amount_usd_cents, created_at \
+                             FROM sponsorship_grants WHERE id = $1",
+                        )
+                        .persistent(false)
+                        .bind(order.grant_id)
+                        .fetch_one(&mut *tx)
+                        .await?;
+                        return Ok((order, grant));
+                    }
+
+                    let order_id = uuid::Uuid::new_v4();
+                    sqlx::query(
+                        "INSERT INTO sponsorship_orders (id, user_email, product_id, placement, slot_index, requested_months, paid_months, status, provider, provider_order_id, amount_usd_cents) \
+                         VALUES ($1, $2, $3::uuid, $4, $5, $6, $6, 'paid', 'manual', $7, $8)",
+                    )
+                    .persistent(false)
+                    .bind(order_id)
+                    .bind(user_email.as_str())
+                    .bind(product_id.as_str())
+                    .bind(placement)
+                    .bind(slot_index)
+                    .bind(months)
+                    .bind(external_reference.as_str())
+                    .bind(amount_usd_cents)
+                    .execute(&mut *tx)
+                    .await?;
+
+                    lock_sponsorship_slot(&mut tx, placement, slot_index).await?;
+                    let max_end: Option<chrono::DateTime<chrono::Utc>> = sqlx::query_scalar(
+                        "SELECT MAX(ends_at) FROM sponsorship_grants \
+                         WHERE placement = $1 AND slot_index IS NOT DISTINCT FROM $2",
+                    )
+                    .persistent(false)
+                    .bind(placement)
+                    .bind(slot_index)
+                    .fetch_one(&mut *tx)
+                    .await?;
+
+                    let (starts_at, ends_at) =
+                        sponsorship_grant_window(chrono::Utc::now(), max_end, duration_days);
+
+                    let grant = sqlx::query_as::<_, SponsorshipGrantFullRow>(
+                        "INSERT INTO sponsorship_grants (order_id, product_id, placement, slot_index, starts_at, ends_at, source, amount_usd_cents) \
+                         VALUES ($1, $2::uuid, $3, $4, $5, $6, 'manual', $7) \
+                         RETURNING id, product_id::text as product_id, placement, slot_index, starts_at, ends_at, source, amount_usd_cents, created_at",
+                    )
+                    .persistent(false)
+                    .bind(order_id)
+                    .bind(product_id.as_str())
+                    .bind(placement)
+                    .bind(slot_index)
+                    .bind(starts_at)
+                    .bind(ends_at)
+                    .bind(amount_usd_cents)
+                    .fetch_one(&mut *tx)
+                    .await?;
+
+                    let order = sqlx::query_as::<_, SponsorshipOrderRow>(
+                        "UPDATE sponsorship_orders SET grant_id = $2, updated_at = NOW() \
+                         WHERE id = $1 \
+                         RETURNING id, user_email, user_id, product_id::text as product_id, placement, slot_index, requested_months, paid_months, status, provider, provider_checkout_id, provider_order_id, amount_usd_cents, grant_id, created_at, updated_at",
+                    )
+                    .persistent(false)
+                    .bind(order_id)
+                    .bind(grant.id)
+                    .fetch_one(&mut *tx)
+                    .await?;
+
+                    Ok((order, grant))
+                }
+                .await;
+
+            match attempt {
+                Ok((order, grant)) => {
+                    tx.commit().await?;
+                    return Ok(ManualSponsorshipGrantResult {
+                        order: map_sponsorship_order_row_to_model(order),
+                        grant: map_sponsorship_grant_full_row(grant),
+                    });
+                }
+                Err(e) => {
+                    let _ = tx.rollback().await;
+                    if (is_missing_relation_error(&e, "sponsorship_grants")
+                        || is_missing_relation_error(&e, "sponsorship_orders"))
+                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_sponsorship_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    last_err = Some(e);
+                    break;
+                }
+            }
+        }
+
+        Err(last_err
+            .unwrap_or_else(|| anyhow::anyhow!("Failed to create manual sponsorship grant")))
+    }
+
+    pub async fn admin_mark_sponsorship_order_paid(
+        &self,
+        order_id: &str,
+        provider_order_id: Option<&str>,
+        amount_usd_cents: Option<i32>,
+        paid_months: Option<i32>,
+    ) -> Result<SponsorshipGrant> {
+        #[derive(sqlx::FromRow)]
+        struct OrderPricingRow {
+            status: String,
+            provider: String,
+            requested_months: i32,
+            monthly_usd_cents: Option<i32>,
+            discount_percent_off: Option<i32>,
+        }
+
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let order_uuid = uuid::Uuid::parse_str(order_id.trim())
+            .map_err(|_| anyhow::anyhow!("Invalid order_id"))?;
+
+        let row = sqlx::query_as::<_, OrderPricingRow>(
+            "SELECT status, provider, requested_months, monthly_usd_cents, discount_percent_off \
+             FROM sponsorship_orders WHERE id = $1",
+        )
+        .persistent(false)
+        .bind(order_uuid)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Sponsorship order not found"))?;
+
+        let mut status = row.status;
+        let mut provider = row.provider;
+        strip_nul_in_place(&mut status);
+        strip_nul_in_place(&mut provider);
+
+        if status == "paid" {
+            let months = paid_months.unwrap_or(row.requested_months).clamp(1, 120);
+            let computed_amount =
+                compute_order_amount(row.monthly_usd_cents, row.discount_percent_off, months);
+            let amount = amount_usd_cents.unwrap_or(computed_amount);
+            return self
+                .create_sponsorship_grant_and_mark_order_paid(
+                    order_id,
+                    provider_order_id,
+                    amount,
+                    months,
+                    provider.as_str(),
+                )
+                .await;
+        }
+
+        if status != "created" {
+            return Err(anyhow::anyhow!(
+                "Sponsorship order is not payable (status = {})",
+                status
+            ));
+        }
+
+        let months = paid_months.unwrap_or(row.requested_months).clamp(1, 120);
+        let computed_amount =
+            compute_order_amount(row.monthly_usd_cents, row.discount_percent_off, months);
+        let amount = amount_usd_cents.unwrap_or(computed_amount);
+
+        self.create_sponsorship_grant_and_mark_order_paid(
+            order_id,
+            provider_order_id,
+            amount,
+            months,
+            provider.as_str(),
+        )
+        .await
+    }
+
+    /**
+     * list_sponsorship_grants
+     * 管理端授权列表：可按展示位、产品过滤，active=true 仅当前生效、active=false 仅已过期；
+     * 按 starts_at 倒序分页，并返回符合条件的总数。
+     */
+    pub async fn list_sponsorship_grants(
+        &self,
+        params: SponsorshipGrantListParams,
+    ) -> Result<(Vec<SponsorshipGrant>, i64)> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let limit = params.limit.clamp(1, 200);
+        let offset = params.offset.max(0);
+        let placement = params
+            .placement
+            .as_deref()
+            .map(|v| strip_nul_str(v.trim()).into_owned())
+            .filter(|v| !v.is_empty());
+        let product_id = match params
+            .product_id
+            .as_deref()
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+        {
+            Some(raw) => Some(
+                uuid::Uuid::parse_str(raw).map_err(|_| anyhow::anyhow!("Invalid product_id"))?,
+            ),
+            None => None,
+        };
+
+        let push_filters = |qb: &mut QueryBuilder<Postgres>| {
+            qb.push(" WHERE 1=1");
+            if let Some(placement) = placement.as_ref() {
+                qb.push(" AND placement = ");
+                qb.push_bind(placement.clone());
+            }
+            if let Some(product_id) = product_id {
+                qb.push(" AND product_id = ");
+                qb.push_bind(product_id);
+            }
+            match params.active {
+                Some(true) => {
+                    qb.push(" AND starts_at <= NOW() AND ends_at > NOW()");
+                }
+                Some(false) => {
+                    qb.push(" AND ends_at <= NOW()");
+                }
+                None => {}
+            }
+        };
+
+        let mut last_err: Option<anyhow::Error> = None;
+        for _attempt_idx in 0..2 {
+            let attempt: Result<(Vec<SponsorshipGrant>, i64), anyhow::Error> = async {
+                let mut qb: QueryBuilder<Postgres> = QueryBuilder::new(
+                    "SELECT id, product_id::text as product_id, placement, slot_index, starts_at, ends_at, source, amount_usd_cents, created_at \
+                     FROM sponsorship_grants",
+                );
+                push_filters(&mut qb);
+                qb.push(" ORDER BY starts_at DESC, id DESC LIMIT ");
+                qb.push_bind(limit);
+                qb.push(" OFFSET ");
+                qb.push_bind(offset);
+                let rows = qb
+                    .build_query_as::<SponsorshipGrantFullRow>()
+                    .persistent(false)
+                    .fetch_all(pool)
+                    .await?;
+
+                let mut count_qb: QueryBuilder<Postgres> =
+                    QueryBuilder::new("SELECT COUNT(*)::bigint FROM sponsorship_grants");
+                push_filters(&mut count_qb);
+                let total = count_qb
+                    .build_query_scalar::<i64>()
+                    .persistent(false)
+                    .fetch_one(pool)
+                    .await?;
+
+                Ok((
+                    rows.into_iter()
+                        .map(map_sponsorship_grant_full_row)
+                        .collect(),
+                    total,
+                ))
+            }
+            .await;
+
+            match attempt {
+                Ok(v) => return Ok(v),
+                Err(e) => {
+                    if is_missing_relation_error(&e, "sponsorship_grants")
+                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_sponsorship_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    last_err = Some(e);
+                    break;
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| {
+            anyhow::anyhow!("Failed to list sponsorship grants after auto migration")
+        }))
+    }
+
+    /**
+     * get_sponsorship_grant_by_id
+     * 按 id 读取单条赞助授权，供重复审批时返回已创建的授权。
+     */
+    pub async fn get_sponsorship_grant_by_id(&self, id: i64) -> Result<Option<SponsorshipGrant>> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let row = sqlx::query_as::<_, SponsorshipGrantFullRow>(
+            "SELECT id, product_id::text as product_id, placement, slot_index, starts_at, ends_at, source, amount_usd_cents, created_at \
+             FROM sponsorship_grants WHERE id = $1",
+        )
+        .persistent(false)
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(row.map(map_sponsorship_grant_full_row))
+    }
+
+    pub async fn delete_sponsorship_grant(&self, id: i64) -> Result<bool> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let mut last_err: Option<anyhow::Error> = None;
+        for _attempt_idx in 0..2 {
+            let attempt = sqlx::query("DELETE FROM sponsorship_grants WHERE id = $1")
+                .persistent(false)
+                .bind(id)
+                .execute(pool)
+                .await;
+
+            match attempt {
+                Ok(res) => return Ok(res.rows_affected() > 0),
+                Err(e) => {
+                    let e: anyhow::Error = e.into();
+                    if is_missing_relation_error(&e, "sponsorship_grants")
+                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_sponsorship_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    last_err = Some(e);
+                    break;
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| {
+            anyhow::anyhow!("Failed to delete sponsorship grant after auto migration")
+        }))
+    }
+
+    /**
+     * list_pricing_plans
+     * 读取定价方案列表（包含权益明细）。
+     */
+    pub async fn list_pricing_plans(&self, include_inactive: bool) -> Result<Vec<PricingPlan>> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let _ = ensure_pricing_text_migration(pool).await;
+
+        let mut last_err: Option<anyhow::Error> = None;
+        for _attempt_idx in 0..2 {
+            let attempt: Result<Vec<PricingPlan>, anyhow::Error> = async {
+                let plans = if include_inactive {
+                    sqlx::query_as::<_, PricingPlanRow>(
+                        "SELECT \
+                            id, plan_key, placement, monthly_usd_cents, \
+                            title_en, title_zh, badge_en, badge_zh, description_en, description_zh, \
+                            is_active, is_default, sort_order, \
+                            campaign_active, campaign_percent_off, campaign_title_en, campaign_title_zh, campaign_starts_at, campaign_ends_at, \
+                            created_at, updated_at \
+                         FROM pricing_plans \
+                         ORDER BY sort_order ASC, created_at ASC, id ASC",
+                    )
+                    .persistent(false)
+                    .fetch_all(pool)
+                    .await?
+                } else {
+                    sqlx::query_as::<_, PricingPlanRow>(
+                        "SELECT \
+                            id, plan_key, placement, monthly_usd_cents, \
+                            title_en, title_zh, badge_en, badge_zh, description_en, description_zh, \
+                            is_active, is_default, sort_order, \
+                            campaign_active, campaign_percent_off, campaign_title_en, campaign_title_zh, campaign_starts_at, campaign_ends_at, \
+                            created_at, updated_at \
+                         FROM pricing_plans \
+                         WHERE is_active = TRUE \
+                         ORDER BY sort_order ASC, created_at ASC, id ASC",
+                    )
+                    .persistent(false)
+                    .fetch_all(pool)
+                    .await?
+                };
+
+                if plans.is_empty() {
+                    return Ok(Vec::new());
+                }
+
+                let plan_ids: Vec<uuid::Uuid> = plans.iter().map(|p| p.id).collect();
+                let benefits = sqlx::query_as::<_, PricingPlanBenefitRow>(
+                    "SELECT id, plan_id, sort_order, text_en, text_zh, available \
+                     FROM pricing_plan_benefits \
+                     WHERE plan_id = ANY($1) \
+                     ORDER BY plan_id ASC, sort_order ASC, id ASC",
+                )
+                .persistent(false)
+                .bind(&plan_ids)
+                .fetch_all(pool)
+                .await?;
+
+                let mut benefits_by_plan: HashMap<uuid::Uuid, Vec<PricingPlanBenefitRow>> =
+                    HashMap::new();
+                for b in benefits {
+                    benefits_by_plan.entry(b.plan_id).or_default().push(b);
+                }
+
+                Ok(plans
+                    .into_iter()
+                    .map(|row| {
+                        let benefit_rows = benefits_by_plan.remove(&row.id).unwrap_or_default();
+                        map_pricing_plan_row_to_model(row, benefit_rows)
+                    })
+                    .collect())
+            }
+            .await;
+
+            match attempt {
+                Ok(v) => return Ok(v),
+                Err(e) => {
+                    if (is_missing_relation_error(&e, "pricing_plans")
+                        || is_missing_relation_error(&e, "pricing_plan_benefits"))
+                        && !PRICING_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_pricing_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    last_err = Some(e);
+                    break;
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to list pricing plans")))
+    }
+
+    /**
+     * upsert_pricing_plan
+     * 新增或更新定价方案，并同步权益列表；若标记为 default，会清理同 placement 的其它 default。
+     */
+    pub async fn upsert_pricing_plan(
+        &self,
+        input: UpsertPricingPlanRequest,
+    ) -> Result<PricingPlan> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let fields = validate_pricing_plan_input(&input);
+        if !fields.is_empty() {
+            return Err(PricingPlanValidationError { fields }.into());
+        }
+
+        let plan_key = strip_nul_str(input.plan_key.trim()).into_owned();
+        let placement = input
+            .placement
+            .as_deref()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+
+        let id = input
+            .id
+            .as_deref()
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+            .and_then(|v| uuid::Uuid::parse_str(v).ok());
+
+        let mut last_err: Option<anyhow::Error> = None;
+        for _attempt_idx in 0..2 {
+            let mut tx = pool.begin().await?;
+
+            let attempt: Result<PricingPlan, anyhow::Error> = async {
+                let existing_id = if let Some(id) = id {
+                    Some(id)
+                } else {
+                    sqlx::query_scalar::<_, uuid::Uuid>(
+                        "SELECT id FROM pricing_plans WHERE plan_key = $1 LIMIT 1",
+                    )
+                    .persistent(false)
+                    .bind(plan_key.as_str())
+                    .fetch_optional(&mut *tx)
+                    .await?
+                };
+                let plan_id = existing_id.unwrap_or_else(uuid::Uuid::new_v4);
+
+                let title_en = strip_nul_str(input.title_en.trim()).into_owned();
+                let title_zh = strip_nul_str(input.title_zh.trim()).into_owned();
+
+                let badge_en = input
+                    .badge_en
+                    .as_deref()
+                    .map(|v| strip_nul_str(v.trim()).into_owned())
+                    .filter(|v| !v.is_empty());
+                let badge_zh = input
+                    .badge_zh
+                    .as_deref()
+                    .map(|v| strip_nul_str(v.trim()).into_owned())
+                    .filter(|v| !v.is_empty());
+                let description_en = input
+                    .description_en
+                    .as_deref()
+                    .map(|v| strip_nul_str(v.trim()).into_owned())
+                    .filter(|v| !v.is_empty());
+                let description_zh = input
+                    .description_zh
+                    .as_deref()
+                    .map(|v| strip_nul_str(v.trim()).into_owned())
+                    .filter(|v| !v.is_empty());
+
+                let campaign = input.campaign.clone();
+
+                sqlx::query(
+                    "INSERT INTO pricing_plans \
+                     (id, plan_key, placement, monthly_usd_cents, title_en, title_zh, badge_en, badge_zh, description_en, description_zh, is_active, is_default, sort_order, \
+                      campaign_active, campaign_percent_off, campaign_title_en, campaign_title_zh, campaign_starts_at, campaign_ends_at, updated_at) \
+                     VALUES \
+                     ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15,$16,$17,$18,$19,NOW()) \
+                     ON CONFLICT (id) DO UPDATE SET \
+                       plan_key = EXCLUDED.plan_key, \
+                       placement = EXCLUDED.placement, \
+                       monthly_usd_cents = EXCLUDED.monthly_usd_cents, \
+                       title_en = EXCLUDED.title_en, \
+                       title_zh = EXCLUDED.title_zh, \
+                       badge_en = EXCLUDED.badge_en, \
+                       badge_zh = EXCLUDED.badge_zh, \
+                       description_en = EXCLUDED.description_en, \
+                       description_zh = EXCLUDED.description_zh, \
+                       is_active = EXCLUDED.is_active, \
+                       is_default = EXCLUDED.is_default, \
+                       sort_order = EXCLUDED.sort_order, \
+                       campaign_active = EXCLUDED.campaign_active, \
+                       campaign_percent_off = EXCLUDED.campaign_percent_off, \
+                       campaign_title_en = EXCLUDED.campaign_title_en, \
+                       campaign_title_zh = EXCLUDED.campaign_title_zh, \
+                       campaign_starts_at = EXCLUDED.campaign_starts_at, \
+                       campaign_ends_at = EXCLUDED.campaign_ends_at, \
+                       updated_at = NOW()",
+                )
+                .persistent(false)
+                .bind(plan_id)
+                .bind(plan_key.as_str())
+                .bind(placement.as_deref())
+                .bind(input.monthly_usd_cents)
+                .bind(title_en.as_str())
+                .bind(title_zh.as_str())
+                .bind(badge_en.as_deref())
+                .bind(badge_zh.as_deref())
+                .bind(description_en.as_deref())
+                .bind(description_zh.as_deref())
+                .bind(input.is_active)
+                .bind(input.is_default)
+                .bind(input.sort_order)
+                .bind(campaign.active)
+                .bind(campaign.percent_off)
+                .bind(
+                    campaign
+                        .title_en
+                        .as_deref()
+                        .map(|v| strip_nul_str(v.trim()).into_owned()),
+                )
+                .bind(
+                    campaign
+                        .title_zh
+                        .as_deref()
+                        .map(|v| strip_nul_str(v.trim()).into_owned()),
+                )
+                .bind(campaign.starts_at)
+                .bind(campaign.ends_at)
+                .execute(&mut *tx)
+                .await?;
+
+                if input.is_default {
+                    let placement_for_default = placement.as_deref();
+                    sqlx::query(
+                        "UPDATE pricing_plans SET is_default = FALSE, updated_at = NOW() \
+                         WHERE id <> $1 AND (placement IS NOT DISTINCT FROM $2)",
+                    )
+                    .persistent(false)
+                    .bind(plan_id)
+                    .bind(placement_for_default)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+
+                sqlx::query("DELETE FROM pricing_plan_benefits WHERE plan_id = $1")
+                    .persistent(false)
+                    .bind(plan_id)
+                    .execute(&mut *tx)
+                    .await?;
+
+                for b in input.benefits.iter() {
+                    let text_en = strip_nul_str(b.text_en.trim()).into_owned();
+                    let text_zh = strip_nul_str(b.text_zh.trim()).into_owned();
+                    if text_en.is_empty() && text_zh.is_empty() {
+                        continue;
+                    }
+                    let text_en = if text_en.is_empty() { text_zh.clone() } else { text_en };
+                    let text_zh = if text_zh.is_empty() { text_en.clone() } else { text_zh };
+
+                    sqlx::query(
+                        "INSERT INTO pricing_plan_benefits (plan_id, sort_order, text_en, text_zh, available) \
+                         VALUES ($1,$2,$3,$4,$5)",
+                    )
+                    .persistent(false)
+                    .bind(plan_id)
+                    .bind(b.sort_order)
+                    .bind(text_en.as_str())
+                    .bind(text_zh.as_str())
+                    .bind(b.available)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+
+                let plan_row = sqlx::query_as::<_, PricingPlanRow>(
+                    "SELECT \
+                        id, plan_key, placement, monthly_usd_cents, \
+                        title_en, title_zh, badge_en, badge_zh, description_en, description_zh, \
+                        is_active, is_default, sort_order, \
+                        campaign_active, campaign_percent_off, campaign_title_en, campaign_title_zh, campaign_starts_at, campaign_ends_at, \
+                        created_at, updated_at \
+                     FROM pricing_plans WHERE id = $1",
+                )
+                .persistent(false)
+                .bind(plan_id)
+                .fetch_one(&mut *tx)
+                .await?;
+
+                let benefit_rows = sqlx::query_as::<_, PricingPlanBenefitRow>(
+                    "SELECT id, plan_id, sort_order, text_en, text_zh, available \
+                     FROM pricing_plan_benefits WHERE plan_id = $1 ORDER BY sort_order ASC, id ASC",
+                )
+                .persistent(false)
+                .bind(plan_id)
+                .fetch_all(&mut *tx)
+                .await?;
+
+                Ok(map_pricing_plan_row_to_model(plan_row, benefit_rows))
+            }
+            .await;
+
+            match attempt {
+                Ok(v) => {
+                    tx.commit().await?;
+                    return Ok(v);
+                }
+                Err(e) => {
+                    let _ = tx.rollback().await;
+                    if (is_missing_relation_error(&e, "pricing_plans")
+                        || is_missing_relation_error(&e, "pricing_plan_benefits"))
+                        && !PRICING_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_pricing_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    last_err = Some(e);
+                    break;
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to upsert pricing plan")))
+    }
+
+    /**
+     * reorder_pricing_plans
+     * 按传入顺序为全部定价方案重新分配 sort_order（0 起连续），单个事务内完成，返回排序后的完整列表。
+     */
+    pub async fn reorder_pricing_plans(
+        &self,
+        input: ReorderPricingPlansRequest,
+    ) -> Result<Vec<PricingPlan>> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let mut last_err: Option<anyhow::Error> = None;
+        for _attempt_idx in 0..2 {
+            let attempt: Result<(), anyhow::Error> = async {
+                let mut tx = pool.begin().await?;
+                let existing = sqlx::query_scalar::<_, uuid::Uuid>(
+                    "SELECT id FROM pricing_plans ORDER BY sort_order ASC, created_at ASC, id ASC FOR UPDATE",
+                )
+                .persistent(false)
+                .fetch_all(&mut *tx)
+                .await?;
+
+                let ordered = resolve_pricing_plan_order(&input.ids, &existing)
+                    .map_err(|fields| PricingPlanValidationError { fields })?;
+
+                sqlx::query(
+                    "UPDATE pricing_plans p \
+                     SET sort_order = (o.ord - 1)::int, updated_at = NOW() \
+                     FROM unnest($1::uuid[]) WITH ORDINALITY AS o(id, ord) \
+                     WHERE p.id = o.id",
+                )
+                .persistent(false)
+                .bind(&ordered)
+                .execute(&mut *tx)
+                .await?;
+
+                tx.commit().await?;
+                Ok(())
+            }
+            .await;
+
+            match attempt {
+                Ok(()) => return self.list_pricing_plans(true).await,
+                Err(e) => {
+                    if is_missing_relation_error(&e, "pricing_plans")
+                        && !PRICING_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_pricing_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    last_err = Some(e);
+                    break;
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to reorder pricing plans")))
+    }
+
+    /**
+     * delete_pricing_plan
+     * 默认软停用定价方案（is_active = FALSE，保留行供历史订单引用）；force 为 true 时硬删除（连带删除权益），
+     * 但若仍有 sponsorship_orders 引用该方案则返回 PricingPlanInUseError。
+     */
+    pub async fn delete_pricing_plan(&self, id: &str, force: bool) -> Result<bool> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let plan_id =
+            uuid::Uuid::parse_str(id.trim()).map_err(|_| anyhow::anyhow!("Invalid id"))?;
+
+        let mut last_err: Option<anyhow::Error> = None;
+        for _attempt_idx in 0..2 {
+            let attempt: Result<bool, anyhow::Error> = async {
+                if !force {
+                    let res = sqlx::query(
+                        "UPDATE pricing_plans SET is_active = FALSE, updated_at = NOW() WHERE id = $1",
+                    )
+                    .persistent(false)
+                    .bind(plan_id)
+                    .execute(pool)
+                    .await?;
+                    return Ok(res.rows_affected() > 0);
+                }
+
+                let mut tx = pool.begin().await?;
+                let exists = sqlx::query_scalar::<_, uuid::Uuid>(
+                    "SELECT id FROM pricing_plans WHERE id = $1 FOR UPDATE",
+                )
+                .persistent(false)
+                .bind(plan_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+                if exists.is_none() {
+                    return Ok(false);
+                }
+
+                let order_refs = sqlx::query_scalar::<_, i64>(
+                    "SELECT COUNT(*)::bigint FROM sponsorship_orders WHERE pricing_plan_id = $1",
+                )
+                .persistent(false)
+                .bind(plan_id)
+                .fetch_one(&mut *tx)
+                .await?;
+                if order_refs > 0 {
+                    return Err(PricingPlanInUseError { order_refs }.into());
+                }
+
+                let res = sqlx::query("DELETE FROM pricing_plans WHERE id = $1")
+                    .persistent(false)
+                    .bind(plan_id)
+                    .execute(&mut *tx)
+                    .await?;
+                tx.commit().await?;
+                Ok(res.rows_affected() > 0)
+            }
+            .await;
+
+            match attempt {
+                Ok(v) => return Ok(v),
+                Err(e) => {
+                    if is_missing_relation_error(&e, "pricing_plans")
+                        && !PRICING_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_pricing_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    if is_missing_relation_error(&e, "sponsorship_orders")
+                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_sponsorship_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    last_err = Some(e);
+                    break;
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to delete pricing plan")))
+    }
+
+    pub async fn get_pricing_plan_by_id(&self, id: &str) -> Result<Option<PricingPlan>> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let plan_id =
+            uuid::Uuid::parse_str(id.trim()).map_err(|_| anyhow::anyhow!("Invalid id"))?;
+
+        let mut last_err: Option<anyhow::Error> = None;
+        for _attempt_idx in 0..2 {
+            let attempt: Result<Option<PricingPlan>, anyhow::Error> = async {
+                let plan_row = sqlx::query_as::<_, PricingPlanRow>(
+                    "SELECT \
+                        id, plan_key, placement, monthly_usd_cents, \
+                        title_en, title_zh, badge_en, badge_zh, description_en, description_zh, \
+                        is_active, is_default, sort_order, \
+                        campaign_active, campaign_percent_off, campaign_title_en, campaign_title_zh, campaign_starts_at, campaign_ends_at, \
+                        created_at, updated_at \
+                     FROM pricing_plans WHERE id = $1",
+                )
+                .persistent(false)
+                .bind(plan_id)
+                .fetch_optional(pool)
+                .await?;
+
+                let Some(plan_row) = plan_row else {
+                    return Ok(None);
+                };
+
+                let benefit_rows = sqlx::query_as::<_, PricingPlanBenefitRow>(
+                    "SELECT id, plan_id, sort_order, text_en, text_zh, available \
+                     FROM pricing_plan_benefits WHERE plan_id = $1 ORDER BY sort_order ASC, id ASC",
+                )
+                .persistent(false)
+                .bind(plan_id)
+                .fetch_all(pool)
+                .await?;
+
+                Ok(Some(map_pricing_plan_row_to_model(plan_row, benefit_rows)))
+            }
+            .await;
+
+            match attempt {
+                Ok(v) => return Ok(v),
+                Err(e) => {
+                    if (is_missing_relation_error(&e, "pricing_plans")
+                        || is_missing_relation_error(&e, "pricing_plan_benefits"))
+                        && !PRICING_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_pricing_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    last_err = Some(e);
+                    break;
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to get pricing plan")))
+    }
+
+    pub async fn get_pricing_plan_by_key(&self, plan_key: &str) -> Result<Option<PricingPlan>> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let plan_key = strip_nul_str(plan_key.trim()).into_owned();
+        if plan_key.is_empty() {
+            return Ok(None);
+        }
+
+        let mut last_err: Option<anyhow::Error> = None;
+        for _attempt_idx in 0..2 {
+            let attempt: Result<Option<PricingPlan>, anyhow::Error> = async {
+                let plan_row = sqlx::query_as::<_, PricingPlanRow>(
+                    "SELECT \
+                        id, plan_key, placement, monthly_usd_cents, \
+                        title_en, title_zh, badge_en, badge_zh, description_en, description_zh, \
+                        is_active, is_default, sort_order, \
+                        campaign_active, campaign_percent_off, campaign_title_en, campaign_title_zh, campaign_starts_at, campaign_ends_at, \
+                        created_at, updated_at \
+                     FROM pricing_plans WHERE plan_key = $1 LIMIT 1",
+                )
+                .persistent(false)
+                .bind(plan_key.as_str())
+                .fetch_optional(pool)
+                .await?;
+
+                let Some(plan_row) = plan_row else {
+                    return Ok(None);
+                };
+
+                let benefit_rows = sqlx::query_as::<_, PricingPlanBenefitRow>(
+                    "SELECT id, plan_id, sort_order, text_en, text_zh, available \
+                     FROM pricing_plan_benefits WHERE plan_id = $1 ORDER BY sort_order ASC, id ASC",
+                )
+                .persistent(false)
+                .bind(plan_row.id)
+                .fetch_all(pool)
+                .await?;
+
+                Ok(Some(map_pricing_plan_row_to_model(plan_row, benefit_rows)))
+            }
+            .await;
+
+            match attempt {
+                Ok(v) => return Ok(v),
+                Err(e) => {
+                    if (is_missing_relation_error(&e, "pricing_plans")
+                        || is_missing_relation_error(&e, "pricing_plan_benefits"))
+                        && !PRICING_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_pricing_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    last_err = Some(e);
+                    break;
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to get pricing plan")))
+    }
+
+    pub async fn get_default_pricing_plan_for_placement(
+        &self,
+        placement: Option<&str>,
+    ) -> Result<Option<PricingPlan>> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let placement = placement
+            .map(|v| strip_nul_str(v.trim()).into_owned())
+            .filter(|v| !v.is_empty());
+
+        let mut last_err: Option<anyhow::Error> = None;
+        for _attempt_idx in 0..2 {
+            let attempt: Result<Option<PricingPlan>, anyhow::Error> = async {
+                let plan_row = sqlx::query_as::<_, PricingPlanRow>(
+                    "SELECT \
+                        id, plan_key, placement, monthly_usd_cents, \
+                        title_en, title_zh, badge_en, badge_zh, description_en, description_zh, \
+                        is_active, is_default, sort_order, \
+                        campaign_active, campaign_percent_off, campaign_title_en, campaign_title_zh, campaign_starts_at, campaign_ends_at, \
+                        created_at, updated_at \
+                     FROM pricing_plans \
+                     WHERE is_default = TRUE AND is_active = TRUE AND (placement IS NOT DISTINCT FROM $1) \
+                     ORDER BY sort_order ASC, id ASC \
+                     LIMIT 1",
+                )
+                .persistent(false)
+                .bind(placement.as_deref())
+                .fetch_optional(pool)
+                .await?;
+
+                let Some(plan_row) = plan_row else {
+                    return Ok(None);
+                };
+
+                let benefit_rows = sqlx::query_as::<_, PricingPlanBenefitRow>(
+                    "SELECT id, plan_id, sort_order, text_en, text_zh, available \
+                     FROM pricing_plan_benefits WHERE plan_id = $1 ORDER BY sort_order ASC, id ASC",
+                )
+                .persistent(false)
+                .bind(plan_row.id)
+                .fetch_all(pool)
+                .await?;
+
+                Ok(Some(map_pricing_plan_row_to_model(plan_row, benefit_rows)))
+            }
+            .await;
+
+            match attempt {
+                Ok(v) => return Ok(v),
+                Err(e) => {
+                    if (is_missing_relation_error(&e, "pricing_plans")
+                        || is_missing_relation_error(&e, "pricing_plan_benefits"))
+                        && !PRICING_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_pricing_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    last_err = Some(e);
+                    break;
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to get pricing plan")))
+    }
+
+    /**
+     * get_next_available_slot
+     * 计算展示位最早可用的槽位与开始时间：各槽位取 MAX(ends_at) 与当前时间的较大者，再取最早者。
+     */
+    pub async fn get_next_available_slot(
+        &self,
+        placement: Placement,
+    ) -> Result<(i32, chrono::DateTime<chrono::Utc>)> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+        let slot_count = placement_slot_count(placement);
+
+        #[derive(sqlx::FromRow)]
+        struct Row {
+            slot_index: i32,
+            max_end: Option<chrono::DateTime<chrono::Utc>>,
+        }
+
+        let mut last_err: Option<anyhow::Error> = None;
+        for _attempt_idx in 0..2 {
+            let attempt = sqlx::query_as::<_, Row>(
+                "SELECT s.idx AS slot_index, MAX(g.ends_at) AS max_end \
+                 FROM generate_series(0, $2 - 1) AS s(idx) \
+                 LEFT JOIN sponsorship_grants g ON g.placement = $1 AND g.slot_index = s.idx \
+                 GROUP BY s.idx \
+                 ORDER BY s.idx ASC",
+            )
+            .persistent(false)
+            .bind(placement.to_db_str())
+            .bind(slot_count)
+            .fetch_all(pool)
+            .await;
+
+            match attempt {
+                Ok(rows) => {
+                    let now = chrono::Utc::now();
+                    let best = rows
+                        .into_iter()
+                        .map(|r| {
+                            let starts_at = match r.max_end {
+                                Some(end) if end > now => end,
+                                _ => now,
+                            };
+                            (r.slot_index, starts_at)
+                        })
+                        .min_by_key(|(slot_index, starts_at)| (*starts_at, *slot_index));
+                    return Ok(best.unwrap_or((0, now)));
+                }
+                Err(e) => {
+                    let e: anyhow::Error = e.into();
+                    if is_missing_relation_error(&e, "sponsorship_grants")
+                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_sponsorship_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    last_err = Some(e);
+                    break;
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to get next available slot")))
+    }
+
+    /**
+     * get_placement_pricing
+     * 组合展示位默认定价方案、最早可用时间与当前生效的活动折扣；无默认方案时返回 None。
+     */
+    pub async fn get_placement_pricing(
+        &self,
+        placement: Placement,
+    ) -> Result<Option<PlacementPricing>> {
+        let Some(plan) = self
+            .get_default_pricing_plan_for_placement(Some(placement.to_db_str()))
+            .await?
+        else {
+            return Ok(None);
+        };
+        let (slot_index, starts_at) = self.get_next_available_slot(placement).await?;
+
+        let discount = live_campaign_percent_off(&plan.campaign, chrono::Utc::now());
+        let effective = plan
+            .monthly_usd_cents
+            .map(|unit| compute_order_amount(Some(unit), discount, 1));
+
+        Ok(Some(PlacementPricing {
+            placement: placement.to_string(),
+            plan,
+            next_available_slot_index: slot_index,
+            next_available_starts_at: starts_at,
+            discount_percent_off: discount,
+            effective_monthly_usd_cents: effective,
+        }))
+    }
+
+    /**
+     * list_sponsorship_orders
+     * 查询支付订单列表（当前实现基于 sponsorship_orders）。
+     */
+    pub async fn list_sponsorship_orders(
+        &self,
+        status: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<SponsorshipOrder>> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let limit = limit.clamp(1, 200);
+        let offset = offset.max(0);
+        let status = status
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+
+        let mut last_err: Option<anyhow::Error> = None;
+        for _attempt_idx in 0..2 {
+            let attempt = if let Some(ref status) = status {
+                sqlx::query_as::<_, SponsorshipOrderRow>(
+                    "SELECT id, user_email, user_id, product_id::text as product_id, placement, slot_index, requested_months, paid_months, status, provider, provider_checkout_id, provider_order_id, amount_usd_cents, grant_id, created_at, updated_at \
+                     FROM sponsorship_orders \
+                     WHERE status = $1 \
+                     ORDER BY created_at DESC, id DESC \
+                     LIMIT $2 OFFSET $3",
+                )
+                .persistent(false)
+                .bind(status.as_str())
+                .bind(limit)
+                .bind(offset)
+                .fetch_all(pool)
+                .await
+            } else {
+                sqlx::query_as::<_, SponsorshipOrderRow>(
+                    "SELECT id, user_email, user_id, product_id::text as product_id, placement, slot_index, requested_months, paid_months, status, provider, provider_checkout_id, provider_order_id, amount_usd_cents, grant_id, created_at, updated_at \
+                     FROM sponsorship_orders \
+                     ORDER BY created_at DESC, id DESC \
+                     LIMIT $1 OFFSET $2",
+                )
+                .persistent(false)
+                .bind(limit)
+                .bind(offset)
+                .fetch_all(pool)
+                .await
+            };
+
+            match attempt {
+                Ok(rows) => {
+                    return Ok(rows
+                        .into_iter()
+                        .map(map_sponsorship_order_row_to_model)
+                        .collect())
+                }
+                Err(e) => {
+                    let e: anyhow::Error = e.into();
+                    if is_missing_relation_error(&e, "sponsorship_orders")
+                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_sponsorship_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    last_err = Some(e);
+                    break;
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to list sponsorship orders")))
+    }
+
+    /**
+     * get_migration_status
+     * 列出每个懒迁移的 *_READY 标记（仅反映本进程）与数据库中预期对象是否存在（information_schema / pg_indexes）。
+     */
+    pub async fn get_migration_status(&self) -> Result<Vec<MigrationStatus>> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let tables: HashSet<String> = sqlx::query_scalar::<_, String>(
+            "SELECT table_name::text FROM information_schema.tables WHERE table_schema = current_schema()",
+        )
+        .persistent(false)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .collect();
+        let columns: HashSet<(String, String)> = sqlx::query_as::<_, (String, String)>(
+            "SELECT table_name::text, column_name::text FROM information_schema.columns WHERE table_schema = current_schema()",
+        )
+        .persistent(false)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .collect();
+        let indexes: HashSet<String> = sqlx::query_scalar::<_, String>(
+            "SELECT indexname::text FROM pg_indexes WHERE schemaname = current_schema()",
+        )
+        .persistent(false)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .collect();
+
+        Ok(MIGRATIONS
+            .iter()
+            .map(|m| {
+                let missing = missing_schema_objects(m.expects, &tables, &columns, &indexes);
+                MigrationStatus {
+                    name: m.name.to_string(),
+                    ready_in_process: m.ready.load(Ordering::Relaxed),
+                    schema_ok: missing.is_empty(),
+                    missing,
+                    error: None,
+                }
+            })
+            .collect())
+    }
+
+    /**
+     * run_all_migrations
+     * 按 MIGRATIONS 顺序主动执行全部 ensure_*（已就绪的直接跳过），单个失败不影响其余，错误附在返回的状态上。
+     */
+    pub async fn run_all_migrations(&self) -> Result<Vec<MigrationStatus>> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let mut errors: HashMap<&'static str, String> = HashMap::new();
+        for m in MIGRATIONS {
+            if let Err(e) = run_migration(pool, m.name).await {
+                log::warn!("migration {} failed: {:?}", m.name, e);
+                errors.insert(m.name, format!("{:#}", e));
+            }
+        }
+
+        let mut statuses = self.get_migration_status().await?;
+        for status in &mut statuses {
+            status.error = errors.remove(status.name.as_str());
+        }
+        Ok(statuses)
+    }
+
+    /**
+     * stream_sponsorship_orders
+     * 以流的方式逐行读取支付订单：后台任务通过 `fetch` 游标读取并经有界 channel 推送，
+     * 调用方按需消费，内存占用不随行数增长。`limit` 为 None 时不限制条数。
+     */
+    pub async fn stream_sponsorship_orders(
+        &self,
+        status: Option<&str>,
+        limit: Option<i64>,
+        offset: i64,
+    ) -> Result<tokio::sync::mpsc::Receiver<Result<SponsorshipOrder>>> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?
+            .clone();
+
+        ensure_sponsorship_tables(&pool).await?;
+
+        let status = status
+            .map(|v| strip_nul_str(v.trim()).into_owned())
+            .filter(|v| !v.is_empty());
+        let limit = limit.map(|v| v.max(1));
+        let offset = offset.max(0);
+
+        let (tx, rx) = tokio::sync::mpsc::channel(SPONSORSHIP_ORDER_STREAM_BUFFER);
+        tokio::spawn(async move {
+            let mut rows = sqlx::query_as::<_, SponsorshipOrderRow>(
+                "SELECT id, user_email, user_id, product_id::text as product_id, placement, slot_index, requested_months, paid_months, status, provider, provider_checkout_id, provider_order_id, amount_usd_cents, grant_id, created_at, updated_at \
+                 FROM sponsorship_orders \
+                 WHERE ($1::text IS NULL OR status = $1) \
+                 ORDER BY created_at DESC, id DESC \
+                 LIMIT $2 OFFSET $3",
+            )
+            .persistent(false)
+            .bind(status)
+            .bind(limit)
+            .bind(offset)
+            .fetch(&pool);
+
+            while let Some(row) = rows.next().await {
+                let item = row
+                    .map(map_sponsorship_order_row_to_model)
+                    .map_err(anyhow::Error::from);
+                let failed = item.is_err();
+                if tx.send(item).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        Ok(rx)
+    }
+
+    /**
+     * list_sponsorship_orders_for_email
+     * 查询指定邮箱用户自己的订单（按创建时间倒序），并附带产品名与关联 grant 的生效区间。
+     */
+    pub async fn list_sponsorship_orders_for_email(
+        &self,
+        email: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<UserSponsorshipOrder>> {
+        #[derive(sqlx::FromRow)]
+        struct Row {
+            id: uuid::Uuid,
+            product_id: String,
+            product_name: Option<String>,
+            placement: String,
+            slot_index: Option<i32>,
+            requested_months: i32,
+            paid_months: Option<i32>,
+            status: String,
+            amount_usd_cents: Option<i32>,
+            grant_id: Option<i64>,
+            grant_starts_at: Option<chrono::DateTime<chrono::Utc>>,
+            grant_ends_at: Option<chrono::DateTime<chrono::Utc>>,
+            created_at: chrono::DateTime<chrono::Utc>,
+            updated_at: chrono::DateTime<chrono::Utc>,
+        }
+
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
 
-    let product_name = product.name.trim();
-    let product_slogan = product.slogan.trim();
-    let product_desc = product.description.trim();
-    let product_website = product.website.trim();
-    let maker_name = product.maker_name.trim();
-    let maker_email = product.maker_email.trim();
-    let category = product.category.trim();
-    let product_id = product.id.trim();
+        let email = strip_nul_str(email.trim()).to_ascii_lowercase();
+        if email.is_empty() {
+            return Ok(Vec::new());
+        }
+        let limit = limit.clamp(1, 100);
+        let offset = offset.max(0);
 
-    let detail_url = build_product_detail_url(frontend_base_url, "en", product_id);
+        let mut last_err: Option<anyhow::Error> = None;
+        for _attempt_idx in 0..2 {
+            let attempt = sqlx::query_as::<_, Row>(
+                "SELECT o.id, o.product_id::text as product_id, p.name as product_name, o.placement, o.slot_index, o.requested_months, o.paid_months, o.status, o.amount_usd_cents, o.grant_id, \
+                        g.starts_at as grant_starts_at, g.ends_at as grant_ends_at, o.created_at, o.updated_at \
+                 FROM sponsorship_orders o \
+                 LEFT JOIN products p ON p.id = o.product_id \
+                 LEFT JOIN sponsorship_grants g ON g.id = o.grant_id \
+                 WHERE LOWER(o.user_email) = $1 \
+                 ORDER BY o.created_at DESC, o.id DESC \
+                 LIMIT $2 OFFSET $3",
+            )
+            .persistent(false)
+            .bind(email.as_str())
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(pool)
+            .await;
 
-    let exp_ts = (chrono::Utc::now() + chrono::Duration::days(7)).timestamp();
-    let approve_token =
-        compute_admin_review_token(product_id, "approve", exp_ts, token_secret).unwrap_or_default();
-    let reject_token =
-        compute_admin_review_token(product_id, "reject", exp_ts, token_secret).unwrap_or_default();
-    let approve_url = if !approve_token.trim().is_empty() {
-        build_admin_review_url(
-            public_api_base_url,
-            product_id,
-            "approve",
-            exp_ts,
-            &approve_token,
-        )
-    } else {
-        String::new()
-    };
-    let reject_url = if !reject_token.trim().is_empty() {
-        build_admin_review_url(
-            public_api_base_url,
-            product_id,
-            "reject",
-            exp_ts,
-            &reject_token,
-        )
-    } else {
-        String::new()
-    };
+            match attempt {
+                Ok(rows) => {
+                    return Ok(rows
+                        .into_iter()
+                        .map(|mut row| {
+                            strip_nul_in_place(&mut row.product_id);
+                            strip_nul_in_place_opt(&mut row.product_name);
+                            strip_nul_in_place(&mut row.placement);
+                            strip_nul_in_place(&mut row.status);
+                            UserSponsorshipOrder {
+                                id: row.id.to_string(),
+                                product_id: row.product_id,
+                                product_name: row.product_name,
+                                placement: row.placement,
+                                slot_index: row.slot_index,
+                                requested_months: row.requested_months,
+                                paid_months: row.paid_months,
+                                status: row.status,
+                                amount_usd_cents: row.amount_usd_cents,
+                                grant_id: row.grant_id,
+                                grant_starts_at: row.grant_starts_at,
+                                grant_ends_at: row.grant_ends_at,
+                                created_at: row.created_at,
+                                updated_at: row.updated_at,
+                            }
+                        })
+                        .collect())
+                }
+                Err(e) => {
+                    let e: anyhow::Error = e.into();
+                    if (is_missing_relation_error(&e, "sponsorship_orders")
+                        || is_missing_relation_error(&e, "sponsorship_grants"))
+                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_sponsorship_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    last_err = Some(e);
+                    break;
+                }
+            }
+        }
 
-    let mut text = String::new();
-    text.push_str("New product submitted (pending review)\n\n");
-    text.push_str(&format!("Name: {}\n", product_name));
-    if !product_slogan.is_empty() {
-        text.push_str(&format!("Slogan: {}\n", product_slogan));
+        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to list sponsorship orders")))
     }
-    text.push_str(&format!("Category: {}\n", category));
-    text.push_str(&format!("Website: {}\n", product_website));
-    text.push_str(&format!("Maker: {} ({})\n", maker_name, maker_email));
-    text.push_str(&format!("Product ID: {}\n", product_id));
-    text.push_str(&format!("Details: {}\n", detail_url));
-    if !approve_url.is_empty() && !reject_url.is_empty() {
-        text.push_str(&format!(
-            "\nApprove: {}\nReject: {}\n",
-            approve_url, reject_url
-        ));
-    } else {
-        text.push_str(
-            "\nOne-click review links are not configured (missing ADMIN_REVIEW_TOKEN_SECRET).\n",
+
+    /**
+     * get_product_engagement_series
+     * 产品最近 days 天（最多 365）的每日点赞 / 收藏数（UTC 按天），缺失日期补 0。
+     */
+    pub async fn get_product_engagement_series(
+        &self,
+        product_id: &str,
+        days: i64,
+    ) -> Result<Vec<ProductEngagementDay>> {
+        #[derive(sqlx::FromRow)]
+        struct DayAggRow {
+            day: chrono::DateTime<chrono::Utc>,
+            likes: i64,
+            favorites: i64,
+        }
+
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let days = days.clamp(1, 365);
+        let now = chrono::Utc::now();
+        let since = chrono::Utc.from_utc_datetime(
+            &(now.date_naive() - chrono::Duration::days(days - 1))
+                .and_hms_opt(0, 0, 0)
+                .unwrap_or_default(),
         );
-    }
 
-    let mut html = String::new();
-    html.push_str("<!doctype html><html><body style=\"margin:0;padding:0;background:#f6f7fb;\">");
-    html.push_str("<table role=\"presentation\" width=\"100%\" cellpadding=\"0\" cellspacing=\"0\" style=\"background:#f6f7fb;padding:24px 0;\">");
-    html.push_str("<tr><td align=\"center\" style=\"padding:0 12px;\">");
-    html.push_str("<table role=\"presentation\" width=\"640\" cellpadding=\"0\" cellspacing=\"0\" style=\"width:100%;max-width:640px;background:#ffffff;border:1px solid #eaecef;border-radius:16px;overflow:hidden;\">");
-    html.push_str("<tr><td style=\"padding:18px 22px;background:#111827;color:#ffffff;\">");
-    html.push_str(
-        "<div style=\"font-size:16px;font-weight:800;\">SoloForge · Product Review</div>",
-    );
-    html.push_str("<div style=\"margin-top:6px;font-size:12px;opacity:0.9;\">A new product is waiting for approval</div>");
-    html.push_str("</td></tr>");
+        let rows = sqlx::query_as::<_, DayAggRow>(
+            "SELECT e.day, SUM(e.likes)::bigint as likes, SUM(e.favorites)::bigint as favorites \
+             FROM ( \
+                SELECT date_trunc('day', created_at AT TIME ZONE 'UTC') AT TIME ZONE 'UTC' as day, \
+                       1 as likes, 0 as favorites \
+                FROM product_likes \
+                WHERE product_id = $1::uuid AND created_at >= $2 \
+                UNION ALL \
+                SELECT date_trunc('day', created_at AT TIME ZONE 'UTC') AT TIME ZONE 'UTC' as day, \
+                       0 as likes, 1 as favorites \
+                FROM product_favorites \
+                WHERE product_id = $1::uuid AND created_at >= $2 \
+             ) e \
+             GROUP BY e.day \
+             ORDER BY e.day ASC",
+        )
+        .persistent(false)
+        .bind(product_id)
+        .bind(since)
+        .fetch_all(pool)
+        .await?;
 
-    html.push_str("<tr><td style=\"padding:18px 22px;\">");
-    html.push_str("<div style=\"font-family:-apple-system,BlinkMacSystemFont,Segoe UI,Roboto,Helvetica,Arial,sans-serif;line-height:1.6;color:#111827;\">");
-    html.push_str(&format!(
-        "<div style=\"font-size:18px;font-weight:800;margin:0 0 6px 0;\">{}</div>",
-        html_escape(product_name)
-    ));
-    if !product_slogan.is_empty() {
-        html.push_str(&format!(
-            "<div style=\"font-size:13px;color:#4b5563;margin:0 0 10px 0;\">{}</div>",
-            html_escape(product_slogan)
-        ));
+        let rows: Vec<_> = rows
+            .into_iter()
+            .map(|r| (r.day, r.likes, r.favorites))
+            .collect();
+        Ok(fill_engagement_series(&rows, now, days))
     }
 
-    html.push_str("<table role=\"presentation\" width=\"100%\" cellpadding=\"0\" cellspacing=\"0\" style=\"border:1px solid #e5e7eb;border-radius:12px;overflow:hidden;\">");
-    html.push_str("<tr><td style=\"padding:12px 14px;\">");
-    html.push_str(&format!(
-        "<div style=\"font-size:12px;color:#6b7280;\">Category</div><div style=\"font-size:14px;font-weight:700;\">{}</div>",
-        html_escape(category)
-    ));
-    html.push_str("</td></tr>");
-    html.push_str("<tr><td style=\"padding:0 14px 12px 14px;\">");
-    html.push_str(&format!(
-        "<div style=\"font-size:12px;color:#6b7280;\">Maker</div><div style=\"font-size:14px;font-weight:700;\">{} ({})</div>",
-        html_escape(maker_name),
-        html_escape(maker_email)
-    ));
-    html.push_str("</td></tr>");
-    html.push_str("<tr><td style=\"padding:0 14px 12px 14px;\">");
-    html.push_str(&format!(
-        "<div style=\"font-size:12px;color:#6b7280;\">Website</div><div style=\"font-size:14px;font-weight:700;\"><a href=\"{}\" target=\"_blank\" rel=\"noreferrer\" style=\"color:#111827;text-decoration:underline;\">{}</a></div>",
-        html_attr_escape(product_website),
-        html_escape(product_website)
-    ));
-    html.push_str("</td></tr>");
-    html.push_str("<tr><td style=\"padding:0 14px 14px 14px;\">");
-    html.push_str(&format!(
-        "<div style=\"font-size:12px;color:#6b7280;\">Product ID</div><div style=\"font-size:13px;font-family:ui-monospace,SFMono-Regular,Menlo,Monaco,Consolas,monospace;\">{}</div>",
-        html_escape(product_id)
-    ));
-    html.push_str("</td></tr></table>");
+    /**
+     * get_product_active_grants
+     * 产品在 now 时刻生效中的赞助 grants（按结束时间升序），供详情页“赞助至某日”标签与续费判断。
+     */
+    pub async fn get_product_active_grants(
+        &self,
+        product_id: &str,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<SponsorshipGrant>> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+        let product_id = strip_nul_str(product_id.trim());
 
-    if !product_desc.is_empty() {
-        let clipped: String = product_desc.chars().take(600).collect();
-        html.push_str("<div style=\"margin-top:14px;\">");
-        html.push_str(
-            "<div style=\"font-size:12px;color:#6b7280;margin-bottom:6px;\">Description</div>",
+        let sql = format!(
+            "SELECT s.id, s.product_id::text as product_id, s.placement, s.slot_index, s.starts_at, s.ends_at, s.source, s.amount_usd_cents, s.created_at \
+             FROM sponsorship_grants s \
+             WHERE s.product_id = $1::uuid AND {} \
+             ORDER BY s.ends_at ASC, s.id ASC",
+            SPONSORSHIP_GRANT_ACTIVE_AT_PARAM
         );
-        html.push_str(&format!(
-            "<div style=\"font-size:13px;color:#111827;background:#f9fafb;border:1px solid #e5e7eb;border-radius:12px;padding:12px 14px;white-space:pre-wrap;\">{}</div>",
-            html_escape(&clipped)
-        ));
-        html.push_str("</div>");
-    }
+        let mut last_err: Option<anyhow::Error> = None;
+        for _attempt_idx in 0..2 {
+            let attempt = sqlx::query_as::<_, SponsorshipGrantFullRow>(&sql)
+                .persistent(false)
+                .bind(product_id.as_ref())
+                .bind(now)
+                .fetch_all(pool)
+                .await;
 
-    html.push_str("<div style=\"margin-top:14px;\">");
-    html.push_str(&format!(
-        "<a href=\"{}\" target=\"_blank\" rel=\"noreferrer\" style=\"display:inline-block;padding:10px 12px;margin:0 10px 10px 0;background:#111827;color:#ffffff;text-decoration:none;border-radius:10px;font-size:12px;font-weight:800;\">View detail page</a>",
-        html_attr_escape(&detail_url)
-    ));
+            match attempt {
+                Ok(rows) => {
+                    return Ok(rows
+                        .into_iter()
+                        .map(map_sponsorship_grant_full_row)
+                        .collect())
+                }
+                Err(e) => {
+                    let e: anyhow::Error = e.into();
+                    if is_missing_relation_error(&e, "sponsorship_grants")
+                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_sponsorship_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    last_err = Some(e);
+                    break;
+                }
+            }
+        }
 
-    if !approve_url.is_empty() && !reject_url.is_empty() {
-        html.push_str(&format!(
-            "<a href=\"{}\" target=\"_blank\" rel=\"noreferrer\" style=\"display:inline-block;padding:10px 12px;margin:0 10px 10px 0;background:#16a34a;color:#ffffff;text-decoration:none;border-radius:10px;font-size:12px;font-weight:800;\">Approve</a>",
-            html_attr_escape(&approve_url)
-        ));
-        html.push_str(&format!(
-            "<a href=\"{}\" target=\"_blank\" rel=\"noreferrer\" style=\"display:inline-block;padding:10px 12px;margin:0 10px 10px 0;background:#dc2626;color:#ffffff;text-decoration:none;border-radius:10px;font-size:12px;font-weight:800;\">Reject</a>",
-            html_attr_escape(&reject_url)
-        ));
-    } else {
-        html.push_str("<div style=\"margin-top:8px;font-size:12px;color:#6b7280;\">One-click review links are not configured. Set ADMIN_REVIEW_TOKEN_SECRET to enable.</div>");
+        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to load product grants")))
     }
-    html.push_str("</div>");
 
-    html.push_str("<div style=\"margin-top:16px;font-size:11px;color:#9ca3af;\">This message is sent automatically when a product is submitted.</div>");
-    html.push_str("</div></td></tr>");
-    html.push_str("</table></td></tr></table>");
-    html.push_str("</body></html>");
+    /**
+     * get_payments_summary
+     * 汇总支付统计（订单状态分布 + 近 N 天收入按天聚合）。
+     */
+    pub async fn get_payments_summary(&self, days: i64) -> Result<PaymentsSummary> {
+        #[derive(sqlx::FromRow)]
+        struct StatusAggRow {
+            status: String,
+            count: i64,
+        }
 
-    (subject, html, text)
-}
+        #[derive(sqlx::FromRow)]
+        struct DayAggRow {
+            day: chrono::DateTime<chrono::Utc>,
+            paid_orders: i64,
+            gross_usd_cents: i64,
+        }
+
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let days = days.clamp(1, 365);
+        let since = chrono::Utc::now() - chrono::Duration::days(days);
+
+        let mut last_err: Option<anyhow::Error> = None;
+        for _attempt_idx in 0..2 {
+            let attempt: Result<PaymentsSummary, anyhow::Error> = async {
+                let status_rows = sqlx::query_as::<_, StatusAggRow>(
+                    "SELECT status, COUNT(1)::bigint as count \
+                     FROM sponsorship_orders \
+                     GROUP BY status \
+                     ORDER BY status ASC",
+                )
+                .persistent(false)
+                .fetch_all(pool)
+                .await?;
 
-/**
- * build_maker_product_review_email_content
- * 构建“产品审核结果（通过/拒绝）”通知给提交者的邮件内容（拒绝包含理由）。
- */
-fn build_maker_product_review_email_content(
-    product: &Product,
-    frontend_base_url: &str,
-) -> (String, String, String) {
-    let is_zh = product
-        .language
-        .trim()
-        .to_ascii_lowercase()
-        .starts_with("zh");
-    let product_id = product.id.trim();
-    let product_name = product.name.trim();
-    let detail_url = build_product_detail_url(
-        frontend_base_url,
-        if is_zh { "zh" } else { "en" },
-        product_id,
-    );
+                let mut created_orders = 0i64;
+                let mut paid_orders = 0i64;
+                let mut failed_orders = 0i64;
+                let mut canceled_orders = 0i64;
+                for mut r in status_rows {
+                    strip_nul_in_place(&mut r.status);
+                    match r.status.as_str() {
+                        "created" => created_orders = r.count,
+                        "paid" => paid_orders = r.count,
+                        "failed" => failed_orders = r.count,
+                        "canceled" => canceled_orders = r.count,
+                        _ => {}
+                    }
+                }
 
-    let status = match product.status {
-        crate::models::ProductStatus::Approved => "approved",
-        crate::models::ProductStatus::Rejected => "rejected",
-        crate::models::ProductStatus::Pending => "pending",
-    };
+                let gross_usd_cents: i64 = sqlx::query_scalar(
+                    "SELECT COALESCE(SUM(amount_usd_cents), 0)::bigint \
+                     FROM sponsorship_orders \
+                     WHERE status = 'paid'",
+                )
+                .persistent(false)
+                .fetch_one(pool)
+                .await
+                .unwrap_or(0);
 
-    let (subject, title_zh, title_en) = match product.status {
-        crate::models::ProductStatus::Approved => (
-            if is_zh {
-                format!("你的产品已通过审核：{}", product_name)
-            } else {
-                format!("Your product is approved: {}", product_name)
-            },
-            "审核通过",
-            "Approved",
-        ),
-        crate::models::ProductStatus::Rejected => (
-            if is_zh {
-                format!("你的产品未通过审核：{}", product_name)
-            } else {
-                format!("Your product is rejected: {}", product_name)
-            },
-            "未通过审核",
-            "Rejected",
-        ),
-        crate::models::ProductStatus::Pending => (
-            if is_zh {
-                format!("你的产品状态已更新：{}", product_name)
-            } else {
-                format!("Your product status updated: {}", product_name)
-            },
-            "状态更新",
-            "Status updated",
-        ),
-    };
+                let day_rows = sqlx::query_as::<_, DayAggRow>(
+                    "SELECT date_trunc('day', updated_at)::timestamptz as day, \
+                            COUNT(1)::bigint as paid_orders, \
+                            COALESCE(SUM(amount_usd_cents), 0)::bigint as gross_usd_cents \
+                     FROM sponsorship_orders \
+                     WHERE status = 'paid' AND updated_at >= $1 \
+                     GROUP BY 1 \
+                     ORDER BY 1 ASC",
+                )
+                .persistent(false)
+                .bind(since)
+                .fetch_all(pool)
+                .await?;
 
-    let reason = product
-        .rejection_reason
-        .as_deref()
-        .unwrap_or("")
-        .trim()
-        .to_string();
+                Ok(PaymentsSummary {
+                    created_orders,
+                    paid_orders,
+                    failed_orders,
+                    canceled_orders,
+                    gross_usd_cents,
+                    by_day: day_rows
+                        .into_iter()
+                        .map(|r| crate::models::PaymentsDayAgg {
+                            day: r.day,
+                            paid_orders: r.paid_orders,
+                            gross_usd_cents: r.gross_usd_cents,
+                        })
+                        .collect(),
+                })
+            }
+            .await;
 
-    let mut text = String::new();
-    if is_zh {
-        text.push_str(&format!("{}\n\n", title_zh));
-        text.push_str(&format!("产品：{}\n", product_name));
-        text.push_str(&format!("状态：{}\n", status));
-        if matches!(product.status, crate::models::ProductStatus::Rejected) && !reason.is_empty() {
-            text.push_str(&format!("理由：{}\n", reason));
-        }
-        text.push_str(&format!("详情：{}\n", detail_url));
-        text.push_str("\n---\n");
-        text.push_str(&format!("{}\n\n", title_en));
-        text.push_str(&format!("Product: {}\n", product_name));
-        text.push_str(&format!("Status: {}\n", status));
-        if matches!(product.status, crate::models::ProductStatus::Rejected) && !reason.is_empty() {
-            text.push_str(&format!("Reason: {}\n", reason));
-        }
-        text.push_str(&format!("Details: {}\n", detail_url));
-    } else {
-        text.push_str(&format!("{}\n\n", title_en));
-        text.push_str(&format!("Product: {}\n", product_name));
-        text.push_str(&format!("Status: {}\n", status));
-        if matches!(product.status, crate::models::ProductStatus::Rejected) && !reason.is_empty() {
-            text.push_str(&format!("Reason: {}\n", reason));
-        }
-        text.push_str(&format!("Details: {}\n", detail_url));
-        text.push_str("\n---\n");
-        text.push_str(&format!("{}\n\n", title_zh));
-        text.push_str(&format!("产品：{}\n", product_name));
-        text.push_str(&format!("状态：{}\n", status));
-        if matches!(product.status, crate::models::ProductStatus::Rejected) && !reason.is_empty() {
-            text.push_str(&format!("理由：{}\n", reason));
+            match attempt {
+                Ok(v) => return Ok(v),
+                Err(e) => {
+                    if is_missing_relation_error(&e, "sponsorship_orders")
+                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_sponsorship_tables(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    last_err = Some(e);
+                    break;
+                }
+            }
         }
-        text.push_str(&format!("详情：{}\n", detail_url));
-    }
 
-    let mut html = String::new();
-    html.push_str("<!doctype html><html><body style=\"margin:0;padding:0;background:#f6f7fb;\">");
-    html.push_str("<table role=\"presentation\" width=\"100%\" cellpadding=\"0\" cellspacing=\"0\" style=\"background:#f6f7fb;padding:24px 0;\">");
-    html.push_str("<tr><td align=\"center\" style=\"padding:0 12px;\">");
-    html.push_str("<table role=\"presentation\" width=\"600\" cellpadding=\"0\" cellspacing=\"0\" style=\"width:100%;max-width:600px;background:#ffffff;border:1px solid #eaecef;border-radius:16px;overflow:hidden;\">");
-    html.push_str("<tr><td style=\"padding:18px 22px;background:#111827;color:#ffffff;\">");
-    html.push_str(&format!(
-        "<div style=\"font-size:16px;font-weight:800;\">{}</div>",
-        html_escape(if is_zh { title_zh } else { title_en })
-    ));
-    html.push_str("</td></tr>");
-    html.push_str("<tr><td style=\"padding:18px 22px;\">");
-    html.push_str("<div style=\"font-family:-apple-system,BlinkMacSystemFont,Segoe UI,Roboto,Helvetica,Arial,sans-serif;line-height:1.6;color:#111827;font-size:14px;\">");
-    html.push_str(&format!(
-        "<div style=\"font-size:16px;font-weight:800;margin:0 0 8px 0;\">{}</div>",
-        html_escape(product_name)
-    ));
-    html.push_str(&format!(
-        "<div style=\"margin:0 0 12px 0;color:#6b7280;\">Status: <strong style=\"color:#111827;\">{}</strong></div>",
-        html_escape(status)
-    ));
-    if matches!(product.status, crate::models::ProductStatus::Rejected) && !reason.is_empty() {
-        html.push_str(&format!(
-            "<div style=\"margin:0 0 12px 0;\"><div style=\"font-weight:700;margin-bottom:6px;\">Reason / 理由</div><div style=\"white-space:pre-wrap;color:#111827;background:#f9fafb;border:1px solid #e5e7eb;border-radius:12px;padding:12px 14px;\">{}</div></div>",
-            html_escape(&reason)
-        ));
+        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to compute payments summary")))
     }
-    html.push_str(&format!(
-        "<a href=\"{}\" target=\"_blank\" rel=\"noreferrer\" style=\"display:inline-block;padding:10px 12px;background:#111827;color:#ffffff;text-decoration:none;border-radius:10px;font-size:12px;font-weight:800;\">{}</a>",
-        html_attr_escape(&detail_url),
-        if is_zh { "查看详情" } else { "View details" }
-    ));
-    html.push_str("</div></td></tr></table></td></tr></table>");
-    html.push_str("</body></html>");
 
-    (subject, html, text)
-}
+    /**
+     * get_favorite_products
+     * 按收藏时间倒序分页返回用户收藏的产品，并返回符合条件的收藏总数（用于翻页）。
+     */
+    pub async fn get_favorite_products(
+        &self,
+        user_id: &str,
+        language: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<Product>, i64)> {
+        let limit = limit.clamp(1, 200);
+        let offset = offset.max(0);
 
-fn sanitize_create_product_request(product: &mut CreateProductRequest) {
-    strip_nul_in_place(&mut product.name);
-    strip_nul_in_place(&mut product.slogan);
-    strip_nul_in_place(&mut product.description);
-    strip_nul_in_place(&mut product.website);
-    strip_nul_in_place_opt(&mut product.logo_url);
-    strip_nul_in_place(&mut product.category);
-    for tag in &mut product.tags {
-        strip_nul_in_place(tag);
-    }
-    strip_nul_in_place(&mut product.maker_name);
-    strip_nul_in_place(&mut product.maker_email);
-    product.maker_email = product.maker_email.trim().to_ascii_lowercase();
-    strip_nul_in_place_opt(&mut product.maker_website);
-    strip_nul_in_place(&mut product.language);
-}
+        if let Some(pool) = &self.postgres {
+            let status_clause = product_visibility_clause("p");
+            let language_clause = if language.is_some() {
+                " AND p.language = $2"
+            } else {
+                ""
+            };
+            let (limit_idx, offset_idx) = if language.is_some() { (3, 4) } else { (2, 3) };
+
+            let sql = format!(
+                "SELECT \
+                    p.id::text as id, \
+                    p.name, \
+                    p.slogan, \
+                    p.description, \
+                    p.website, \
+                    p.logo_url, \
+                    p.category, \
+                    COALESCE(p.tags, ARRAY[]::text[]) as tags, \
+                    p.maker_name, \
+                    p.maker_email, \
+                    p.maker_website, \
+                    p.language, \
+                    p.status::text as status, \
+                    p.rejection_reason, \
+                    p.created_at, \
+                    p.updated_at, \
+                    COALESCE(pl.likes, 0)::bigint as likes, \
+                    COALESCE(pf2.favorites, 0)::bigint as favorites, \
+                    COALESCE(d.sponsor_role, NULL::text) as maker_sponsor_role, \
+                    COALESCE(d.sponsor_verified, FALSE) as maker_sponsor_verified \
+                 FROM product_favorites f \
+                 JOIN products p ON p.id = f.product_id \
+                 LEFT JOIN developers d ON lower(d.email) = lower(p.maker_email) \
+                 LEFT JOIN (SELECT product_id, COUNT(*)::bigint as likes FROM product_likes GROUP BY product_id) pl ON pl.product_id = p.id \
+                 LEFT JOIN (SELECT product_id, COUNT(*)::bigint as favorites FROM product_favorites GROUP BY product_id) pf2 ON pf2.product_id = p.id \
+                 WHERE f.user_id = $1 AND {}{} \
+                 ORDER BY f.created_at DESC, p.id ASC \
+                 LIMIT ${} OFFSET ${}",
+                status_clause, language_clause, limit_idx, offset_idx
+            );
+            let count_sql = format!(
+                "SELECT COUNT(*)::bigint \
+                 FROM product_favorites f \
+                 JOIN products p ON p.id = f.product_id \
+                 WHERE f.user_id = $1 AND {}{}",
+                status_clause, language_clause
+            );
+
+            let mut rows: Option<Vec<ProductRow>> = None;
+            for _attempt_idx in 0..2 {
+                let mut query = sqlx::query_as::<_, ProductRow>(&sql)
+                    .persistent(false)
+                    .bind(user_id);
+                if let Some(language) = language {
+                    query = query.bind(language);
+                }
+                let attempt = query.bind(limit).bind(offset).fetch_all(pool).await;
+                match attempt {
+                    Ok(v) => {
+                        rows = Some(v);
+                        break;
+                    }
+                    Err(e) => {
+                        let e: anyhow::Error = e.into();
+                        if is_missing_column_error(&e, "rejection_reason")
+                            && !PRODUCTS_REJECTION_REASON_READY.load(Ordering::Relaxed)
+                            && ensure_products_rejection_reason_column(pool).await.is_ok()
+                        {
+                            continue;
+                        }
+                        if (is_missing_column_error(&e, "sponsor_role")
+                            || is_missing_column_error(&e, "sponsor_verified"))
+                            && !DEVELOPERS_SPONSOR_COLUMNS_READY.load(Ordering::Relaxed)
+                            && ensure_developers_sponsor_columns(pool).await.is_ok()
+                        {
+                            continue;
+                        }
+                        return Err(e);
+                    }
+                }
+            }
+            let rows = rows.ok_or_else(|| anyhow::anyhow!("Failed to load favorite products"))?;
 
-fn sanitize_update_product_request(updates: &mut UpdateProductRequest) {
-    if let Some(v) = updates.name.as_mut() {
-        strip_nul_in_place(v);
-    }
-    if let Some(v) = updates.slogan.as_mut() {
-        strip_nul_in_place(v);
-    }
-    if let Some(v) = updates.description.as_mut() {
-        strip_nul_in_place(v);
-    }
-    if let Some(v) = updates.website.as_mut() {
-        strip_nul_in_place(v);
-    }
-    if let Some(v) = updates.logo_url.as_mut() {
-        strip_nul_in_place(v);
-    }
-    if let Some(v) = updates.category.as_mut() {
-        strip_nul_in_place(v);
-    }
-    if let Some(tags) = updates.tags.as_mut() {
-        for tag in tags {
-            strip_nul_in_place(tag);
+            let mut count_query = sqlx::query_scalar::<_, i64>(&count_sql)
+                .persistent(false)
+                .bind(user_id);
+            if let Some(language) = language {
+                count_query = count_query.bind(language);
+            }
+            let total = count_query.fetch_one(pool).await?;
+
+            return Ok((rows.into_iter().map(map_product_row).collect(), total));
         }
-    }
-    if let Some(v) = updates.rejection_reason.as_mut() {
-        strip_nul_in_place(v);
-    }
-}
 
-fn sanitize_categories(categories: &mut [Category]) {
-    for c in categories {
-        strip_nul_in_place(&mut c.id);
-        strip_nul_in_place(&mut c.name_en);
-        strip_nul_in_place(&mut c.name_zh);
-        strip_nul_in_place(&mut c.icon);
-        strip_nul_in_place(&mut c.color);
+        Err(anyhow::Error::new(PostgresNotConfiguredError))
     }
-}
 
-/**
- * map_product_row
- * 将 ProductRow 转换为对外 API 使用的 Product 结构。
- */
-fn map_product_row(row: ProductRow) -> Product {
-    let mut maker_sponsor_role = row.maker_sponsor_role;
-    strip_nul_in_place_opt(&mut maker_sponsor_role);
-    Product {
-        id: row.id,
-        name: row.name,
-        slogan: row.slogan,
-        description: row.description,
-        website: row.website,
-        logo_url: row.logo_url,
-        category: row.category,
-        tags: row.tags,
-        maker_name: row.maker_name,
-        maker_email: row.maker_email,
-        maker_website: row.maker_website,
-        maker_sponsor_role,
-        maker_sponsor_verified: row.maker_sponsor_verified,
-        language: row.language,
-        status: parse_product_status(&row.status),
-        rejection_reason: row.rejection_reason,
-        created_at: row.created_at,
-        updated_at: row.updated_at,
-        likes: row.likes,
-        favorites: row.favorites,
+    async fn record_product_deletion(&self, pool: &PgPool, product_id: uuid::Uuid) -> Result<()> {
+        ensure_product_deletions_table(pool).await?;
+        sqlx::query(
+            "INSERT INTO product_deletions (product_id, deleted_at) VALUES ($1, NOW()) \
+             ON CONFLICT (product_id) DO UPDATE SET deleted_at = EXCLUDED.deleted_at",
+        )
+        .persistent(false)
+        .bind(product_id)
+        .execute(pool)
+        .await?;
+        Ok(())
     }
-}
 
-/**
- * map_category_row
- * 将 CategoryRow 转换为对外 API 使用的 Category 结构。
- */
-fn map_category_row(row: CategoryRow) -> Category {
-    let name_en = row.name_en;
-    let name_zh = row.name_zh.unwrap_or_else(|| name_en.clone());
-    Category {
-        id: row.id,
-        name_en,
-        name_zh,
-        icon: row.icon,
-        color: row.color,
-    }
-}
+    /**
+     * get_product_changes
+     * 增量同步：按 (updated_at, id) 升序返回 after 之后变更的产品。已删除或不再公开的产品只返回 deleted=true 标记，
+     * 不附带产品内容；after_id 为 None 时表示严格 updated_at > after。
+     */
+    pub async fn get_product_changes(
+        &self,
+        after: chrono::DateTime<chrono::Utc>,
+        after_id: Option<&str>,
+        limit: i64,
+    ) -> Result<ProductChangesPage> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+        ensure_product_deletions_table(pool).await?;
+        let limit = limit.clamp(1, 500);
 
-fn map_category_with_count_row(row: CategoryWithCountRow) -> crate::models::CategoryWithCount {
-    let name_en = row.name_en;
-    let name_zh = row.name_zh.unwrap_or_else(|| name_en.clone());
-    crate::models::CategoryWithCount {
-        id: row.id,
-        name_en,
-        name_zh,
-        icon: row.icon,
-        color: row.color,
-        product_count: row.product_count,
-    }
-}
+        #[derive(sqlx::FromRow)]
+        struct Row {
+            id: String,
+            updated_at: chrono::DateTime<chrono::Utc>,
+            deleted: bool,
+        }
 
-fn map_developer_row(row: DeveloperRow) -> Developer {
-    let mut email = row.email;
-    let mut name = row.name;
-    let mut avatar_url = row.avatar_url;
-    let mut website = row.website;
-    let mut sponsor_role = row.sponsor_role;
-    strip_nul_in_place(&mut email);
-    strip_nul_in_place(&mut name);
-    strip_nul_in_place_opt(&mut avatar_url);
-    strip_nul_in_place_opt(&mut website);
-    strip_nul_in_place_opt(&mut sponsor_role);
-    Developer {
-        email,
-        name,
-        avatar_url,
-        website,
-        sponsor_role,
-        sponsor_verified: row.sponsor_verified,
-    }
-}
+        let sql = format!(
+            "SELECT c.id, c.updated_at, c.deleted FROM ( \
+                SELECT p.id::text as id, COALESCE(p.updated_at, p.created_at, NOW()) as updated_at, NOT ({}) as deleted \
+                FROM products p \
+                UNION ALL \
+                SELECT d.product_id::text as id, d.deleted_at as updated_at, TRUE as deleted \
+                FROM product_deletions d \
+                WHERE NOT EXISTS (SELECT 1 FROM products p2 WHERE p2.id = d.product_id) \
+             ) c \
+             WHERE c.updated_at > $1 OR (c.updated_at = $1 AND c.id > $2) \
+             ORDER BY c.updated_at ASC, c.id ASC \
+             LIMIT $3",
+            product_visibility_clause("p")
+        );
+        let after_id = after_id.map(strip_nul_str);
+        let rows = sqlx::query_as::<_, Row>(&sql)
+            .persistent(false)
+            .bind(after)
+            .bind(after_id.as_deref())
+            .bind(limit)
+            .fetch_all(pool)
+            .await?;
 
-fn map_developer_with_followers_row(row: DeveloperWithFollowersRow) -> DeveloperWithFollowers {
-    let mut email = row.email;
-    let mut name = row.name;
-    let mut avatar_url = row.avatar_url;
-    let mut website = row.website;
-    let mut sponsor_role = row.sponsor_role;
-    strip_nul_in_place(&mut email);
-    strip_nul_in_place(&mut name);
-    strip_nul_in_place_opt(&mut avatar_url);
-    strip_nul_in_place_opt(&mut website);
-    strip_nul_in_place_opt(&mut sponsor_role);
-    DeveloperWithFollowers {
-        email,
-        name,
-        avatar_url,
-        website,
-        sponsor_role,
-        sponsor_verified: row.sponsor_verified,
-        followers: row.followers.parse::<i64>().unwrap_or(0),
-    }
-}
+        let live_ids: Vec<String> = rows
+            .iter()
+            .filter(|r| !r.deleted)
+            .map(|r| r.id.clone())
+            .collect();
+        let mut products: HashMap<String, Product> = HashMap::new();
+        for chunk in live_ids.chunks(MAX_PRODUCTS_BY_IDS) {
+            for p in self.get_products_by_ids(chunk, None).await? {
+                products.insert(p.id.clone(), p);
+            }
+        }
 
-fn map_developer_popularity_row(row: DeveloperPopularityRow) -> DeveloperPopularity {
-    let mut email = row.email;
-    let mut name = row.name;
-    let mut avatar_url = row.avatar_url;
-    let mut website = row.website;
-    let mut sponsor_role = row.sponsor_role;
-    strip_nul_in_place(&mut email);
-    strip_nul_in_place(&mut name);
-    strip_nul_in_place_opt(&mut avatar_url);
-    strip_nul_in_place_opt(&mut website);
-    strip_nul_in_place_opt(&mut sponsor_role);
-    DeveloperPopularity {
-        email,
-        name,
-        avatar_url,
-        website,
-        sponsor_role,
-        sponsor_verified: row.sponsor_verified,
-        likes: row.likes,
-        favorites: row.favorites,
-        score: row.score,
+        let next_cursor = if rows.len() as i64 == limit {
+            rows.last()
+                .map(|r| encode_product_changes_cursor(r.updated_at, &r.id))
+        } else {
+            None
+        };
+        let items = rows
+            .into_iter()
+            .map(|r| {
+                let product = if r.deleted {
+                    None
+                } else {
+                    products.remove(&r.id)
+                };
+                ProductChange {
+                    deleted: r.deleted || product.is_none(),
+                    id: r.id,
+                    updated_at: r.updated_at,
+                    product,
+                }
+            })
+            .collect();
+
+        Ok(ProductChangesPage { items, next_cursor })
     }
-}
 
-fn map_developer_center_stats_row(row: DeveloperCenterStatsRow) -> DeveloperCenterStats {
-    DeveloperCenterStats {
-        followers: row.followers,
-        total_likes: row.total_likes,
-        total_favorites: row.total_favorites,
-    }
-}
+    pub async fn get_product_by_id(&self, id: &str) -> Result<Option<Product>> {
+        if let Some(pool) = &self.postgres {
+            let mut last_err: Option<anyhow::Error> = None;
+            for attempt_idx in 0..2 {
+                let attempt = sqlx::query_as::<_, ProductRow>(
+                    "SELECT \
+                        p.id::text as id, \
+                        p.name, \
+                        p.slogan, \
+                        p.description, \
+                        p.website, \
+                        p.logo_url, \
+                        p.category, \
+                        COALESCE(p.tags, ARRAY[]::text[]) as tags, \
+                        p.maker_name, \
+                        p.maker_email, \
+                        p.maker_website, \
+                        p.language, \
+                        p.status::text as status, \
+                        p.rejection_reason, \
+                        p.created_at, \
+                        p.updated_at, \
+                        (SELECT COUNT(*)::bigint FROM product_likes l WHERE l.product_id = p.id) as likes, \
+                        (SELECT COUNT(*)::bigint FROM product_favorites f WHERE f.product_id = p.id) as favorites, \
+                        COALESCE(d.sponsor_role, NULL::text) as maker_sponsor_role, \
+                        COALESCE(d.sponsor_verified, FALSE) as maker_sponsor_verified \
+                     FROM products p \
+                     LEFT JOIN developers d ON lower(d.email) = lower(p.maker_email) \
+                     WHERE p.id::text = $1 \
+                     LIMIT 1",
+                )
+                .persistent(false)
+                .bind(id)
+                .fetch_optional(pool)
+                .await;
+
+                match attempt {
+                    Ok(row) => return Ok(row.map(map_product_row)),
+                    Err(e) => {
+                        let e: anyhow::Error = e.into();
+                        if is_missing_column_error(&e, "rejection_reason")
+                            && !PRODUCTS_REJECTION_REASON_READY.load(Ordering::Relaxed)
+                            && ensure_products_rejection_reason_column(pool).await.is_ok()
+                        {
+                            continue;
+                        }
+                        if (is_missing_column_error(&e, "sponsor_role")
+                            || is_missing_column_error(&e, "sponsor_verified"))
+                            && !DEVELOPERS_SPONSOR_COLUMNS_READY.load(Ordering::Relaxed)
+                            && ensure_developers_sponsor_columns(pool).await.is_ok()
+                        {
+                            continue;
+                        }
+                        last_err = Some(e);
+                        let Some(ref err) = last_err else {
+                            continue;
+                        };
+                        if is_retryable_db_error(err) && self.supabase.is_some() {
+                            break;
+                        }
+                        if attempt_idx == 0 && is_retryable_db_error(err) {
+                            continue;
+                        }
+                        return Err(last_err.unwrap());
+                    }
+                }
+            }
+
+            if let Some(e) = last_err {
+                if !(is_retryable_db_error(&e) && self.supabase.is_some()) {
+                    return Err(e);
+                }
+            }
+        }
 
-fn parse_supabase_content_range_total(value: &str) -> Option<i64> {
-    let after_slash = value.rsplit('/').next()?;
-    if after_slash.trim() == "*" {
-        return Some(0);
-    }
-    after_slash.trim().parse::<i64>().ok()
-}
+        let supabase = self
+            .supabase
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No database configured"))?;
 
-async fn supabase_count(
-    supabase: &SupabaseDatabase,
-    table: &str,
-    query: &[(&str, String)],
-) -> Result<i64> {
-    let mut url = Url::parse(&format!("{}/rest/v1/{}", supabase.supabase_url, table))?;
-    {
-        let mut qp = url.query_pairs_mut();
-        for (k, v) in query {
-            qp.append_pair(k, v);
+        let Ok(product_uuid) = uuid::Uuid::parse_str(id.trim()) else {
+            return Ok(None);
+        };
+
+        let mut url = Url::parse(&format!("{}/rest/v1/products", supabase.supabase_url))?;
+        url.query_pairs_mut()
+            .append_pair("id", &format!("eq.{}", product_uuid));
+
+        let response = supabase.get_with_retry(url, &[]).await?;
+
+        if response.status() == 404 {
+            return Ok(None);
         }
-        qp.append_pair("limit", "1");
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            if status.as_u16() == 400 && body.contains("22P02") {
+                return Ok(None);
+            }
+            return Err(anyhow::anyhow!(
+                "Failed to fetch product: {}. Body: {}",
+                status,
+                body
+            ));
+        }
+
+        let products: Vec<Product> = response.json().await?;
+        Ok(products.first().cloned())
     }
 
-    let response = supabase
-        .client
-        .get(url)
-        .header("apikey", &supabase.supabase_key)
-        .header(
-            "Authorization",
-            &format!("Bearer {}", supabase.supabase_key),
+    /**
+     * get_product_interaction_flags
+     * 查询指定用户是否已点赞/收藏某产品（仅在识别到用户时调用）。
+     */
+    pub async fn get_product_interaction_flags(
+        &self,
+        product_id: &str,
+        user_id: &str,
+    ) -> Result<(bool, bool)> {
+        #[derive(sqlx::FromRow)]
+        struct Row {
+            has_liked: bool,
+            has_favorited: bool,
+        }
+
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        // 直接按 uuid 比较才能命中 (product_id, user_id) 索引；非法 id 不可能有互动记录
+        let Ok(product_uuid) = uuid::Uuid::parse_str(product_id.trim()) else {
+            return Ok((false, false));
+        };
+
+        let row = sqlx::query_as::<_, Row>(
+            "SELECT \
+                EXISTS(SELECT 1 FROM product_likes l WHERE l.product_id = $1 AND l.user_id = $2) as has_liked, \
+                EXISTS(SELECT 1 FROM product_favorites f WHERE f.product_id = $1 AND f.user_id = $2) as has_favorited",
         )
-        .header("Accept", "application/json")
-        .header("Prefer", "count=exact")
-        .send()
+        .persistent(false)
+        .bind(product_uuid)
+        .bind(user_id.trim())
+        .fetch_one(pool)
         .await?;
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
-        return Err(anyhow::anyhow!(
-            "Failed to fetch count from {}: {}. Body: {}",
-            table,
-            status,
-            body
-        ));
+        Ok((row.has_liked, row.has_favorited))
     }
 
-    let total = response
-        .headers()
-        .get("content-range")
-        .and_then(|v| v.to_str().ok())
-        .and_then(parse_supabase_content_range_total)
-        .unwrap_or(0);
-
-    Ok(total)
-}
+    /**
+     * resolve_product_current_slug
+     * 按产品名派生的当前 slug 查找产品 id：先用 ILIKE 粗筛候选，再用 slugify_product_name 精确比对；重名时取最早创建的产品。
+     */
+    pub async fn resolve_product_current_slug(&self, slug: &str) -> Result<Option<String>> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
 
-fn split_sql_statements(input: &str) -> Vec<String> {
-    let bytes = input.as_bytes();
-    let mut statements: Vec<String> = Vec::new();
-    let mut current = String::new();
+        let slug = slugify_product_name(&strip_nul_str(slug));
+        if slug.is_empty() {
+            return Ok(None);
+        }
 
-    let mut i: usize = 0;
-    let mut in_single = false;
-    let mut in_double = false;
-    let mut dollar_delim: Option<String> = None;
+        // slug 只含字母数字与 '-'，不会带入 LIKE 通配符
+        let pattern = format!("%{}%", slug.replace('-', "%"));
+        let candidates = sqlx::query_as::<_, (String, String)>(
+            "SELECT id::text, name FROM products WHERE name ILIKE $1 \
+             ORDER BY created_at ASC, id ASC LIMIT 50",
+        )
+        .persistent(false)
+        .bind(pattern)
+        .fetch_all(pool)
+        .await?;
 
-    while i < bytes.len() {
-        if dollar_delim.is_none() && !in_single && !in_double {
-            if bytes[i] == b'-' && i + 1 < bytes.len() && bytes[i + 1] == b'-' {
-                i += 2;
-                while i < bytes.len() && bytes[i] != b'\n' {
-                    i += 1;
-                }
-                continue;
-            }
+        Ok(candidates
+            .into_iter()
+            .find(|(_, name)| slugify_product_name(name) == slug)
+            .map(|(id, _)| id))
+    }
 
-            if bytes[i] == b'/' && i + 1 < bytes.len() && bytes[i + 1] == b'*' {
-                i += 2;
-                while i + 1 < bytes.len() && !(bytes[i] == b'*' && bytes[i + 1] == b'/') {
-                    i += 1;
-                }
-                if i + 1 < bytes.len() {
-                    i += 2;
-                }
-                continue;
-            }
-        }
+    /**
+     * resolve_product_slug_redirect
+     * 在 product_slug_history 中查找旧 slug，返回当前产品 id（用于 301 跳转）。
+     */
+    pub async fn resolve_product_slug_redirect(&self, slug: &str) -> Result<Option<String>> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
 
-        if let Some(delim) = &dollar_delim {
-            if input[i..].starts_with(delim) {
-                current.push_str(delim);
-                i += delim.len();
-                dollar_delim = None;
-                continue;
-            }
-            current.push(bytes[i] as char);
-            i += 1;
-            continue;
+        let slug = slugify_product_name(&strip_nul_str(slug));
+        if slug.is_empty() {
+            return Ok(None);
         }
 
-        if !in_double && bytes[i] == b'\'' {
-            if in_single && i + 1 < bytes.len() && bytes[i + 1] == b'\'' {
-                current.push('\'');
-                current.push('\'');
-                i += 2;
-                continue;
-            }
-            in_single = !in_single;
-            current.push('\'');
-            i += 1;
-            continue;
-        }
+        let attempt = sqlx::query_scalar::<_, String>(
+            "SELECT product_id::text FROM product_slug_history WHERE slug = $1",
+        )
+        .persistent(false)
+        .bind(slug.as_str())
+        .fetch_optional(pool)
+        .await;
 
-        if !in_single && bytes[i] == b'"' {
-            if in_double && i + 1 < bytes.len() && bytes[i + 1] == b'"' {
-                current.push('"');
-                current.push('"');
-                i += 2;
-                continue;
+        match attempt {
+            Ok(v) => Ok(v),
+            Err(e) => {
+                let e: anyhow::Error = e.into();
+                if is_missing_relation_error(&e, "product_slug_history") {
+                    return Ok(None);
+                }
+                Err(e)
             }
-            in_double = !in_double;
-            current.push('"');
-            i += 1;
-            continue;
         }
+    }
 
-        if !in_single && !in_double && bytes[i] == b'$' {
-            let mut j = i + 1;
-            while j < bytes.len()
-                && bytes[j] != b'$'
-                && (bytes[j].is_ascii_alphanumeric() || bytes[j] == b'_')
-            {
-                j += 1;
-            }
-            if j < bytes.len() && bytes[j] == b'$' {
-                let delim = &input[i..=j];
-                dollar_delim = Some(delim.to_string());
-                current.push_str(delim);
-                i = j + 1;
-                continue;
-            }
-        }
+    /**
+     * create_product
+     * 创建产品；maker_verified 表示请求方已登录且邮箱与 maker_email 一致，只有此时才可能自动通过审核。
+     */
+    pub async fn create_product(
+        &self,
+        product: CreateProductRequest,
+        maker_verified: bool,
+    ) -> Result<Product> {
+        let mut product = product;
+        sanitize_create_product_request(&mut product)?;
 
-        if !in_single && !in_double && bytes[i] == b';' {
-            let stmt = current.trim();
-            if !stmt.is_empty() {
-                statements.push(stmt.to_string());
+        let sponsor_verified = if maker_verified {
+            match self.get_developer_by_email(&product.maker_email).await {
+                Ok(Some(dev)) => dev.sponsor_verified,
+                _ => false,
             }
-            current.clear();
-            i += 1;
-            continue;
-        }
-
-        current.push(bytes[i] as char);
-        i += 1;
-    }
+        } else {
+            false
+        };
+        let status = initial_product_status(
+            &product.maker_email,
+            maker_verified,
+            &env::var("AUTO_APPROVE_EMAILS").unwrap_or_default(),
+            sponsor_verified,
+        );
 
-    let tail = current.trim();
-    if !tail.is_empty() {
-        statements.push(tail.to_string());
-    }
+        if let Some(pool) = &self.postgres {
+            let row = sqlx::query_as::<_, ProductRow>(
+                "INSERT INTO products \
+                    (name, slogan, description, website, logo_url, category, tags, maker_name, maker_email, maker_website, language, status) \
+                 VALUES \
+                    ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12) \
+                 RETURNING \
+                    id::text as id, \
+                    name, \
+                    slogan, \
+                    description, \
+                    website, \
+                    logo_url, \
+                    category, \
+                    COALESCE(tags, ARRAY[]::text[]) as tags, \
+                    maker_name, \
+                    maker_email, \
+                    maker_website, \
+                    language, \
+                    status::text as status, \
+                    rejection_reason, \
+                    created_at, \
+                    updated_at, \
+                    0::bigint as likes, \
+                    0::bigint as favorites, \
+                    NULL::text as maker_sponsor_role, \
+                    FALSE as maker_sponsor_verified",
+            )
+            .persistent(false)
+            .bind(&product.name)
+            .bind(&product.slogan)
+            .bind(&product.description)
+            .bind(&product.website)
+            .bind(&product.logo_url)
+            .bind(&product.category)
+            .bind(&product.tags)
+            .bind(&product.maker_name)
+            .bind(&product.maker_email)
+            .bind(&product.maker_website)
+            .bind(&product.language)
+            .bind(serialize_product_status(&status))
+            .fetch_one(pool)
+            .await?;
 
-    statements
-}
+            self.upsert_developer_pg(
+                pool,
+                &product.maker_email,
+                &product.maker_name,
+                product.maker_website.as_ref(),
+            )
+            .await?;
 
-impl Database {
-    pub fn new() -> Self {
-        let supabase = match (env::var("SUPABASE_URL").ok(), env::var("SUPABASE_KEY").ok()) {
-            (Some(supabase_url), Some(supabase_key)) => {
-                let supabase_url = supabase_url.trim_end_matches('/').to_string();
+            return Ok(map_product_row(row));
+        }
 
-                let client_builder = Client::builder()
-                    .connect_timeout(Duration::from_secs(3))
-                    .timeout(Duration::from_secs(8))
-                    .http1_only();
+        let supabase = self
+            .supabase
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No database configured"))?;
 
-                let client = client_builder.build().expect("Failed to build HTTP client");
-                Some(SupabaseDatabase {
-                    client,
-                    supabase_url,
-                    supabase_key,
-                })
-            }
-            _ => None,
-        };
+        let url = Url::parse(&format!("{}/rest/v1/products", supabase.supabase_url))?;
+        let mut payload = serde_json::to_value(&product)?;
+        payload["status"] =
+            serde_json::Value::String(serialize_product_status(&status).to_string());
 
-        let postgres = env::var("DATABASE_URL").ok().and_then(|u| {
-            let options = PgConnectOptions::from_str(&u).ok()?;
-            let options = options.statement_cache_capacity(0);
-            Some(
-                PgPoolOptions::new()
-                    .max_connections(15)
-                    .min_connections(1)
-                    .acquire_timeout(Duration::from_secs(8))
-                    .test_before_acquire(true)
-                    .after_connect(|conn, _meta| {
-                        Box::pin(async move {
-                            sqlx::query("SET statement_timeout = 15000")
-                                .persistent(false)
-                                .execute(conn)
-                                .await?;
-                            Ok(())
-                        })
-                    })
-                    .connect_lazy_with(options),
+        let response = supabase
+            .client
+            .post(url)
+            .header("apikey", &supabase.supabase_key)
+            .header(
+                "Authorization",
+                &format!("Bearer {}", supabase.supabase_key),
             )
-        });
+            .header("Accept", "application/json")
+            .header("Prefer", "return=representation")
+            .json(&payload)
+            .send()
+            .await?;
 
-        if postgres.is_none() && supabase.is_none() {
-            panic!("DATABASE_URL or (SUPABASE_URL + SUPABASE_KEY) must be set");
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!(
+                "Failed to create product: {}. Body: {}",
+                status,
+                body
+            ));
         }
 
-        Self { postgres, supabase }
+        let new_product: Product = response.json().await?;
+        Ok(new_product)
     }
 
-    pub async fn get_developer_by_email(&self, email: &str) -> Result<Option<Developer>> {
+    pub async fn update_product(
+        &self,
+        id: &str,
+        updates: UpdateProductRequest,
+    ) -> Result<Option<Product>> {
+        let mut updates = updates;
+        sanitize_update_product_request(&mut updates)?;
         if let Some(pool) = &self.postgres {
-            let email = strip_nul_str(email);
+            if updates.name.is_none()
+                && updates.slogan.is_none()
+                && updates.description.is_none()
+                && updates.website.is_none()
+                && updates.logo_url.is_none()
+                && updates.category.is_none()
+                && updates.tags.is_none()
+                && updates.status.is_none()
+                && updates.rejection_reason.is_none()
+            {
+                return self.get_product_by_id(id).await;
+            }
+            let previous_name = if updates.name.is_some() {
+                sqlx::query_scalar::<_, String>("SELECT name FROM products WHERE id::text = $1")
+                    .persistent(false)
+                    .bind(id)
+                    .fetch_optional(pool)
+                    .await
+                    .ok()
+                    .flatten()
+            } else {
+                None
+            };
+
             let mut last_err: Option<anyhow::Error> = None;
             for attempt_idx in 0..2 {
-                let attempt = sqlx::query_as::<_, DeveloperRow>(
-                    "SELECT email, name, avatar_url, website, sponsor_role, sponsor_verified \
-                     FROM developers \
-                     WHERE lower(email) = lower($1) \
-                     ORDER BY updated_at DESC NULLS LAST \
-                     LIMIT 1",
-                )
-                .persistent(false)
-                .bind(email.as_ref())
-                .fetch_optional(pool)
-                .await;
+                let mut qb: QueryBuilder<Postgres> = QueryBuilder::new("UPDATE products SET ");
+                let mut first = true;
+                let push_comma = |qb: &mut QueryBuilder<Postgres>, first: &mut bool| {
+                    if !*first {
+                        qb.push(", ");
+                    }
+                    *first = false;
+                };
+
+                if let Some(name) = &updates.name {
+                    push_comma(&mut qb, &mut first);
+                    qb.push("name = ");
+                    qb.push_bind(name);
+                }
+                if let Some(slogan) = &updates.slogan {
+                    push_comma(&mut qb, &mut first);
+                    qb.push("slogan = ");
+                    qb.push_bind(slogan);
+                }
+                if let Some(description) = &updates.description {
+                    push_comma(&mut qb, &mut first);
+                    qb.push("description = ");
+                    qb.push_bind(description);
+                }
+                if let Some(website) = &updates.website {
+                    push_comma(&mut qb, &mut first);
+                    qb.push("website = ");
+                    qb.push_bind(website);
+                }
+                if let Some(logo_url) = &updates.logo_url {
+                    push_comma(&mut qb, &mut first);
+                    qb.push("logo_url = ");
+                    qb.push_bind(logo_url);
+                }
+                if let Some(category) = &updates.category {
+                    push_comma(&mut qb, &mut first);
+                    qb.push("category = ");
+                    qb.push_bind(category);
+                }
+                if let Some(tags) = &updates.tags {
+                    push_comma(&mut qb, &mut first);
+                    qb.push("tags = ");
+                    qb.push_bind(tags);
+                }
+                if let Some(status) = &updates.status {
+                    push_comma(&mut qb, &mut first);
+                    qb.push("status = ");
+                    qb.push_bind(serialize_product_status(status));
+                }
+                if let Some(reason) = &updates.rejection_reason {
+                    push_comma(&mut qb, &mut first);
+                    if reason.trim().is_empty() {
+                        qb.push("rejection_reason = NULL");
+                    } else {
+                        qb.push("rejection_reason = ");
+                        qb.push_bind(reason);
+                    }
+                }
+
+                push_comma(&mut qb, &mut first);
+                qb.push("updated_at = now()");
+
+                qb.push(" WHERE id::text = ");
+                qb.push_bind(id);
+
+                qb.push(
+                    " RETURNING \
+                        id::text as id, \
+                        name, \
+                        slogan, \
+                        description, \
+                        website, \
+                        logo_url, \
+                        category, \
+                        COALESCE(tags, ARRAY[]::text[]) as tags, \
+                        maker_name, \
+                        maker_email, \
+                        maker_website, \
+                        language, \
+                        status::text as status, \
+                        rejection_reason, \
+                        created_at, \
+                        updated_at, \
+                        (SELECT COUNT(*)::bigint FROM product_likes l WHERE l.product_id = products.id) as likes, \
+                        (SELECT COUNT(*)::bigint FROM product_favorites f WHERE f.product_id = products.id) as favorites, \
+                        COALESCE((SELECT d.sponsor_role FROM developers d WHERE lower(d.email) = lower(products.maker_email) LIMIT 1), NULL::text) as maker_sponsor_role, \
+                        COALESCE((SELECT d.sponsor_verified FROM developers d WHERE lower(d.email) = lower(products.maker_email) LIMIT 1), FALSE) as maker_sponsor_verified",
+                );
+
+                let attempt = qb
+                    .build_query_as::<ProductRow>()
+                    .persistent(false)
+                    .fetch_optional(pool)
+                    .await;
 
                 match attempt {
-                    Ok(row) => return Ok(row.map(map_developer_row)),
+                    Ok(row) => {
+                        if let (Some(previous_name), Some(row)) = (previous_name.as_deref(), &row) {
+                            record_product_slug_change(pool, &row.id, previous_name, &row.name)
+                                .await;
+                        }
+                        return Ok(row.map(map_product_row));
+                    }
                     Err(e) => {
                         let e: anyhow::Error = e.into();
                         if (is_missing_column_error(&e, "sponsor_role")
@@ -1836,13 +10437,7 @@ impl Database {
                             continue;
                         }
                         last_err = Some(e);
-                        let Some(ref err) = last_err else {
-                            continue;
-                        };
-                        if is_retryable_db_error(err) && self.supabase.is_some() {
-                            break;
-                        }
-                        if attempt_idx == 0 && is_retryable_db_error(err) {
+                        if attempt_idx == 0 {
                             continue;
                         }
                         return Err(last_err.unwrap());
@@ -1850,135 +10445,387 @@ impl Database {
                 }
             }
 
-            if let Some(e) = last_err {
-                if !(is_retryable_db_error(&e) && self.supabase.is_some()) {
-                    return Err(e);
+            if let Some(e) = last_err {
+                return Err(e);
+            }
+        }
+
+        let supabase = self
+            .supabase
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No database configured"))?;
+
+        let mut url = Url::parse(&format!("{}/rest/v1/products", supabase.supabase_url))?;
+        url.query_pairs_mut()
+            .append_pair("id", &format!("eq.{}", id));
+
+        let mut payload = serde_json::to_value(&updates)?;
+        if let serde_json::Value::Object(ref mut map) = payload {
+            if let Some(reason) = &updates.rejection_reason {
+                if reason.trim().is_empty() {
+                    map.insert("rejection_reason".to_string(), serde_json::Value::Null);
+                }
+            }
+        }
+
+        let response = supabase
+            .client
+            .patch(url)
+            .header("apikey", &supabase.supabase_key)
+            .header(
+                "Authorization",
+                &format!("Bearer {}", supabase.supabase_key),
+            )
+            .header("Accept", "application/json")
+            .header("Prefer", "return=representation")
+            .json(&payload)
+            .send()
+            .await?;
+
+        if response.status() == 404 {
+            return Ok(None);
+        }
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!(
+                "Failed to update product: {}. Body: {}",
+                status,
+                body
+            ));
+        }
+
+        let updated_product: Product = response.json().await?;
+        Ok(Some(updated_product))
+    }
+
+    pub async fn delete_product(&self, id: &str) -> Result<bool> {
+        if let Some(pool) = &self.postgres {
+            let deleted: Option<uuid::Uuid> =
+                sqlx::query_scalar("DELETE FROM products WHERE id::text = $1 RETURNING id")
+                    .persistent(false)
+                    .bind(id)
+                    .fetch_optional(pool)
+                    .await?;
+            if let Some(product_id) = deleted {
+                if let Err(e) = self.record_product_deletion(pool, product_id).await {
+                    log::warn!(
+                        "Failed to record product deletion tombstone {}: {:?}",
+                        product_id,
+                        e
+                    );
                 }
             }
+            return Ok(deleted.is_some());
         }
 
-        let supabase = match &self.supabase {
-            Some(v) => v,
-            None => return Ok(None),
-        };
+        let supabase = self
+            .supabase
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No database configured"))?;
 
-        let email = strip_nul_str(email);
-        let mut url = Url::parse(&format!("{}/rest/v1/developers", supabase.supabase_url))?;
+        let mut url = Url::parse(&format!("{}/rest/v1/products", supabase.supabase_url))?;
         url.query_pairs_mut()
-            .append_pair(
-                "select",
-                "email,name,avatar_url,website,sponsor_role,sponsor_verified",
-            )
-            .append_pair("email", &format!("eq.{}", email));
+            .append_pair("id", &format!("eq.{}", id));
 
         let response = supabase
             .client
-            .get(url)
+            .delete(url)
             .header("apikey", &supabase.supabase_key)
             .header(
                 "Authorization",
                 &format!("Bearer {}", supabase.supabase_key),
             )
-            .header("Accept", "application/json")
             .send()
             .await?;
 
+        Ok(response.status() == 204)
+    }
+
+    pub async fn get_categories(&self) -> Result<Vec<Category>> {
+        if let Some(pool) = &self.postgres {
+            let rows = sqlx::query_as::<_, CategoryRow>(
+                "SELECT id::text as id, name_en, name_zh, icon, color FROM categories ORDER BY id",
+            )
+            .persistent(false)
+            .fetch_all(pool)
+            .await?;
+            return Ok(rows.into_iter().map(map_category_row).collect());
+        }
+
+        let supabase = self
+            .supabase
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No database configured"))?;
+
+        let url = Url::parse(&format!("{}/rest/v1/categories", supabase.supabase_url))?;
+
+        let response = supabase.get_with_retry(url, &[]).await?;
+
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
             return Err(anyhow::anyhow!(
-                "Failed to fetch developer: {}. Body: {}",
+                "Failed to fetch categories: {}. Body: {}",
                 status,
                 body
             ));
         }
 
-        let developers: Vec<Developer> = response.json().await?;
-        Ok(developers.first().cloned())
+        let categories: Vec<Category> = response.json().await?;
+        Ok(categories)
     }
 
-    pub async fn update_developer_profile(
-        &self,
-        email: &str,
-        name: Option<String>,
-        avatar_url: Option<Option<String>>,
-        website: Option<Option<String>>,
-    ) -> Result<Developer> {
-        let name_update = name.is_some();
-        let avatar_update = avatar_url.is_some();
-        let website_update = website.is_some();
-
-        let email_clean = strip_nul_str(email);
-        let name_value = name.clone().unwrap_or_else(|| email_clean.to_string());
-        let name_value = strip_nul_str(&name_value).into_owned();
-        let avatar_value = avatar_url
-            .clone()
-            .and_then(|v| v)
-            .map(|v| strip_nul_str(&v).into_owned());
-        let website_value = website
-            .clone()
-            .and_then(|v| v)
-            .map(|v| strip_nul_str(&v).into_owned());
+    /**
+     * get_categories_by_ids
+     * 按 id 批量读取分类；Supabase 回退时分类表很小，直接全量读取后过滤。
+     */
+    pub async fn get_categories_by_ids(&self, ids: &[String]) -> Result<Vec<Category>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
 
         if let Some(pool) = &self.postgres {
-            let mut last_err: Option<anyhow::Error> = None;
-            for attempt_idx in 0..2 {
-                let attempt: Result<Developer> = async {
-                    let row = sqlx::query_as::<_, DeveloperRow>(
-                        "INSERT INTO developers (email, name, avatar_url, website) \
-                         VALUES ($1, $2, $3, $4) \
-                         ON CONFLICT (email) DO UPDATE SET \
-                            name = CASE WHEN $5 THEN EXCLUDED.name ELSE developers.name END, \
-                            avatar_url = CASE WHEN $6 THEN EXCLUDED.avatar_url ELSE developers.avatar_url END, \
-                            website = CASE WHEN $7 THEN EXCLUDED.website ELSE developers.website END, \
-                            updated_at = NOW() \
-                         RETURNING email, name, avatar_url, website, sponsor_role, sponsor_verified",
-                    )
-                    .persistent(false)
-                    .bind(email_clean.as_ref())
-                    .bind(name_value.as_str())
-                    .bind(avatar_value.as_deref())
-                    .bind(website_value.as_deref())
-                    .bind(name_update)
-                    .bind(avatar_update)
-                    .bind(website_update)
-                    .fetch_one(pool)
-                    .await?;
+            let ids: Vec<String> = ids
+                .iter()
+                .map(|v| strip_nul_str(v.trim()).into_owned())
+                .collect();
+            let rows = sqlx::query_as::<_, CategoryRow>(
+                "SELECT id::text as id, name_en, name_zh, icon, color FROM categories WHERE id::text = ANY($1) ORDER BY id",
+            )
+            .persistent(false)
+            .bind(&ids)
+            .fetch_all(pool)
+            .await?;
+            return Ok(rows.into_iter().map(map_category_row).collect());
+        }
 
-                    Ok(map_developer_row(row))
-                }
-                .await;
+        let wanted: HashSet<&str> = ids.iter().map(|v| v.trim()).collect();
+        Ok(self
+            .get_categories()
+            .await?
+            .into_iter()
+            .filter(|c| wanted.contains(c.id.as_str()))
+            .collect())
+    }
 
-                match attempt {
-                    Ok(dev) => return Ok(dev),
-                    Err(e) => {
-                        if (is_missing_column_error(&e, "sponsor_role")
-                            || is_missing_column_error(&e, "sponsor_verified"))
-                            && !DEVELOPERS_SPONSOR_COLUMNS_READY.load(Ordering::Relaxed)
-                            && ensure_developers_sponsor_columns(pool).await.is_ok()
-                        {
-                            continue;
-                        }
-                        last_err = Some(e);
-                        let Some(ref err) = last_err else {
-                            continue;
-                        };
-                        if is_retryable_db_error(err) && self.supabase.is_some() {
-                            break;
-                        }
-                        if attempt_idx == 0 && is_retryable_db_error(err) {
-                            continue;
-                        }
-                        return Err(last_err.unwrap());
-                    }
+    /**
+     * expand_product_categories
+     * 为产品附加分类详情（?expand=category）：先收集去重后的分类 id，一次查询取回，避免 N+1。
+     */
+    pub async fn expand_product_categories(&self, products: &mut [Product]) -> Result<()> {
+        let mut ids: Vec<String> = products
+            .iter()
+            .map(|p| p.category.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .collect();
+        ids.sort();
+        ids.dedup();
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let by_id: HashMap<String, Category> = self
+            .get_categories_by_ids(&ids)
+            .await?
+            .into_iter()
+            .map(|c| (c.id.clone(), c))
+            .collect();
+        for product in products.iter_mut() {
+            product.category_detail = by_id.get(product.category.trim()).cloned();
+        }
+        Ok(())
+    }
+
+    /**
+     * get_overview_stats
+     * 首页概览统计（产品数/创作者数/点赞收藏总数/本周新增），单次查询完成，并在进程内缓存 60 秒。
+     */
+    pub async fn get_overview_stats(&self) -> Result<OverviewStats> {
+        #[derive(sqlx::FromRow)]
+        struct Row {
+            total_products: i64,
+            approved_products: i64,
+            total_developers: i64,
+            total_likes: i64,
+            total_favorites: i64,
+            products_this_week: i64,
+        }
+
+        if let Ok(guard) = self.overview_stats_cache.lock() {
+            if let Some((cached_at, stats)) = guard.as_ref() {
+                if cached_at.elapsed() < OVERVIEW_STATS_CACHE_TTL {
+                    return Ok(stats.clone());
                 }
             }
+        }
+
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let status_clause = product_visibility_clause("p");
+
+        let sql = format!(
+            "SELECT \
+                (SELECT COUNT(*)::bigint FROM products) as total_products, \
+                (SELECT COUNT(*)::bigint FROM products p WHERE {status_clause}) as approved_products, \
+                (SELECT COUNT(*)::bigint FROM developers) as total_developers, \
+                (SELECT COUNT(*)::bigint FROM product_likes) as total_likes, \
+                (SELECT COUNT(*)::bigint FROM product_favorites) as total_favorites, \
+                (SELECT COUNT(*)::bigint FROM products p WHERE {status_clause} AND p.created_at >= NOW() - INTERVAL '7 days') as products_this_week",
+            status_clause = status_clause
+        );
+
+        let row = sqlx::query_as::<_, Row>(&sql)
+            .persistent(false)
+            .fetch_one(pool)
+            .await?;
+
+        let stats = OverviewStats {
+            total_products: row.total_products,
+            approved_products: row.approved_products,
+            total_developers: row.total_developers,
+            total_likes: row.total_likes,
+            total_favorites: row.total_favorites,
+            products_this_week: row.products_this_week,
+        };
+
+        if let Ok(mut guard) = self.overview_stats_cache.lock() {
+            *guard = Some((Instant::now(), stats.clone()));
+        }
+
+        Ok(stats)
+    }
+
+    /**
+     * suggest_tags
+     * 标签自动补全：展开已公开产品的 tags，按规范化前缀匹配并按使用次数排序。
+     */
+    pub async fn suggest_tags(&self, prefix: &str, limit: i64) -> Result<Vec<TagSuggestion>> {
+        #[derive(sqlx::FromRow)]
+        struct Row {
+            tag: String,
+            usage_count: i64,
+        }
+
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let limit = limit.clamp(1, 50);
+        let prefix = normalize_tags(&[prefix.to_string()])
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        let pattern = format!("{}%", escape_like_pattern(&prefix));
+
+        let status_clause = product_visibility_clause("p");
+
+        let sql = format!(
+            "SELECT t.tag, COUNT(*)::bigint as usage_count \
+             FROM ( \
+                SELECT lower(regexp_replace(trim(raw_tag), '\\s+', '-', 'g')) as tag \
+                FROM products p, unnest(COALESCE(p.tags, ARRAY[]::text[])) as raw_tag \
+                WHERE {} \
+             ) t \
+             WHERE t.tag <> '' AND t.tag LIKE $1 ESCAPE '\\' \
+             GROUP BY t.tag \
+             ORDER BY usage_count DESC, t.tag ASC \
+             LIMIT $2",
+            status_clause
+        );
+
+        let rows = sqlx::query_as::<_, Row>(&sql)
+            .persistent(false)
+            .bind(pattern)
+            .bind(limit)
+            .fetch_all(pool)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| TagSuggestion {
+                tag: strip_nul_str(&r.tag).into_owned(),
+                count: r.usage_count,
+            })
+            .collect())
+    }
+
+    pub async fn get_top_categories_by_product_count(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<crate::models::CategoryWithCount>> {
+        let limit = limit.clamp(1, 50);
+
+        if let Some(pool) = &self.postgres {
+            let status_clause = product_visibility_clause("p");
+
+            let sql = format!(
+                "SELECT \
+                    c.id::text as id, \
+                    c.name_en, \
+                    c.name_zh, \
+                    c.icon, \
+                    c.color, \
+                    COUNT(p.id)::bigint as product_count \
+                 FROM categories c \
+                 JOIN products p ON p.category = c.id \
+                 WHERE {} \
+                 GROUP BY c.id, c.name_en, c.name_zh, c.icon, c.color \
+                 ORDER BY product_count DESC, c.id ASC \
+                 LIMIT $1",
+                status_clause
+            );
+
+            let rows = sqlx::query_as::<_, CategoryWithCountRow>(&sql)
+                .persistent(false)
+                .bind(limit)
+                .fetch_all(pool)
+                .await?;
+
+            return Ok(rows.into_iter().map(map_category_with_count_row).collect());
+        }
+
+        Err(anyhow::Error::new(PostgresNotConfiguredError))
+    }
+
+    /**
+     * upsert_categories
+     * 批量插入/更新 categories，用于开发阶段快速初始化数据。
+     */
+    pub async fn upsert_categories(&self, categories: Vec<Category>) -> Result<usize> {
+        let mut categories = categories;
+        if categories.is_empty() {
+            return Ok(0);
+        }
+        sanitize_categories(&mut categories);
 
-            if let Some(e) = last_err {
-                if !(is_retryable_db_error(&e) && self.supabase.is_some()) {
-                    return Err(e);
-                }
-            }
+        if let Some(pool) = &self.postgres {
+            let mut qb: QueryBuilder<Postgres> =
+                QueryBuilder::new("INSERT INTO categories (id, name_en, name_zh, icon, color) ");
+
+            qb.push_values(categories.iter(), |mut b, c| {
+                b.push_bind(&c.id)
+                    .push_bind(&c.name_en)
+                    .push_bind(&c.name_zh)
+                    .push_bind(&c.icon)
+                    .push_bind(&c.color);
+            });
+
+            qb.push(
+                " ON CONFLICT (id) DO UPDATE SET \
+                    name_en = EXCLUDED.name_en, \
+                    name_zh = EXCLUDED.name_zh, \
+                    icon = EXCLUDED.icon, \
+                    color = EXCLUDED.color",
+            );
+
+            let res = qb.build().persistent(false).execute(pool).await?;
+            return Ok(res.rows_affected() as usize);
         }
 
         let supabase = self
@@ -1986,34 +10833,8 @@ impl Database {
             .as_ref()
             .ok_or_else(|| anyhow::anyhow!("No database configured"))?;
 
-        let name_value_raw = name_value.as_str().to_string();
-        let name_value = strip_nul_str(&name_value_raw).into_owned();
-
-        let mut payload = serde_json::Map::<String, serde_json::Value>::new();
-        payload.insert(
-            "email".to_string(),
-            serde_json::Value::String(email_clean.to_string()),
-        );
-        let exists = self.get_developer_by_email(email).await?.is_some();
-        if name_update || !exists {
-            payload.insert("name".to_string(), serde_json::Value::String(name_value));
-        }
-
-        if let Some(v) = avatar_url {
-            match v {
-                Some(s) => payload.insert("avatar_url".to_string(), serde_json::Value::String(s)),
-                None => payload.insert("avatar_url".to_string(), serde_json::Value::Null),
-            };
-        }
-        if let Some(v) = website {
-            match v {
-                Some(s) => payload.insert("website".to_string(), serde_json::Value::String(s)),
-                None => payload.insert("website".to_string(), serde_json::Value::Null),
-            };
-        }
-
-        let mut url = Url::parse(&format!("{}/rest/v1/developers", supabase.supabase_url))?;
-        url.query_pairs_mut().append_pair("on_conflict", "email");
+        let mut url = Url::parse(&format!("{}/rest/v1/categories", supabase.supabase_url))?;
+        url.query_pairs_mut().append_pair("on_conflict", "id");
 
         let response = supabase
             .client
@@ -2028,7 +10849,7 @@ impl Database {
                 "Prefer",
                 "resolution=merge-duplicates,return=representation",
             )
-            .json(&serde_json::Value::Object(payload))
+            .json(&categories)
             .send()
             .await?;
 
@@ -2036,1343 +10857,1889 @@ impl Database {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
             return Err(anyhow::anyhow!(
-                "Failed to update developer: {}. Body: {}",
+                "Failed to upsert categories: {}. Body: {}",
                 status,
                 body
             ));
         }
 
-        let returned: Vec<Developer> = response.json().await?;
-        returned
-            .first()
-            .cloned()
-            .ok_or_else(|| anyhow::anyhow!("Invalid response from database"))
-    }
-
-    async fn upsert_developer_pg(
-        &self,
-        pool: &PgPool,
-        email: &str,
-        name: &str,
-        website: Option<&String>,
-    ) -> Result<()> {
-        let email = strip_nul_str(email);
-        let name = strip_nul_str(name);
-        let website = website.map(|v| strip_nul_str(v).into_owned());
-        sqlx::query(
-            "INSERT INTO developers (email, name, website) \
-             VALUES ($1, $2, $3) \
-             ON CONFLICT (email) DO UPDATE SET \
-                name = EXCLUDED.name, \
-                website = COALESCE(EXCLUDED.website, developers.website), \
-                updated_at = NOW()",
-        )
-        .persistent(false)
-        .bind(email.as_ref())
-        .bind(name.as_ref())
-        .bind(website.as_deref())
-        .execute(pool)
-        .await?;
-        Ok(())
+        let returned: Vec<Category> = response.json().await?;
+        Ok(returned.len())
     }
 
-    pub async fn get_products(&self, params: QueryParams) -> Result<Vec<Product>> {
+    pub async fn delete_category(&self, id: &str) -> Result<bool> {
         if let Some(pool) = &self.postgres {
-            let mut last_err: Option<anyhow::Error> = None;
-            for attempt in 0..2 {
-                let attempt_result: Result<Vec<Product>> = async {
-                    let mut tx = pool.begin().await?;
-                    let mut qb: QueryBuilder<Postgres> = QueryBuilder::new(
-                        "SELECT \
-                            p.id::text as id, \
-                            p.name, \
-                            p.slogan, \
-                            p.description, \
-                            p.website, \
-                            p.logo_url, \
-                            p.category, \
-                            COALESCE(p.tags, ARRAY[]::text[]) as tags, \
-                            p.maker_name, \
-                            p.maker_email, \
-                            p.maker_website, \
-                            p.language, \
-                            p.status::text as status, \
-                            p.rejection_reason, \
-                            p.created_at, \
-                            p.updated_at, \
-                            (SELECT COUNT(*)::bigint FROM product_likes l WHERE l.product_id = p.id) as likes, \
-                            (SELECT COUNT(*)::bigint FROM product_favorites f WHERE f.product_id = p.id) as favorites, \
-                            COALESCE(d.sponsor_role, NULL::text) as maker_sponsor_role, \
-                            COALESCE(d.sponsor_verified, FALSE) as maker_sponsor_verified \
-                         FROM products p \
-                         LEFT JOIN developers d ON lower(d.email) = lower(p.maker_email)",
-                    );
-
-                    qb.push(" WHERE 1=1");
-                    if let Some(category) = &params.category {
-                        qb.push(" AND ");
-                        qb.push("p.category = ");
-                        qb.push_bind(category);
-                    }
+            let id = strip_nul_str(id);
+            let res = sqlx::query("DELETE FROM categories WHERE id = $1")
+                .persistent(false)
+                .bind(id.as_ref())
+                .execute(pool)
+                .await?;
+            return Ok(res.rows_affected() > 0);
+        }
 
-                    if let Some(language) = &params.language {
-                        qb.push(" AND ");
-                        qb.push("p.language = ");
-                        qb.push_bind(language);
-                    }
+        let supabase = self
+            .supabase
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No database configured"))?;
 
-                    if let Some(status) = &params.status {
-                        qb.push(" AND ");
-                        if dev_include_pending_in_approved() && status == "approved" {
-                            qb.push("p.status::text IN ('approved','pending')");
-                        } else {
-                            qb.push("p.status::text = ");
-                            qb.push_bind(status);
-                        }
-                    }
+        let url = Url::parse(&format!(
+            "{}/rest/v1/categories?id=eq.{}",
+            supabase.supabase_url,
+            urlencoding::encode(id)
+        ))?;
 
-                    if let Some(search) = &params.search {
-                        let q = format!("%{}%", search);
-                        qb.push(" AND ");
-                        qb.push("(p.name ILIKE ");
-                        qb.push_bind(q.clone());
-                        qb.push(" OR p.slogan ILIKE ");
-                        qb.push_bind(q.clone());
-                        qb.push(" OR p.description ILIKE ");
-                        qb.push_bind(q.clone());
-                        qb.push(" OR p.maker_name ILIKE ");
-                        qb.push_bind(q.clone());
-                        qb.push(" OR p.maker_email ILIKE ");
-                        qb.push_bind(q);
-                        qb.push(")");
-                    }
+        let response = supabase
+            .client
+            .delete(url)
+            .header("apikey", &supabase.supabase_key)
+            .header(
+                "Authorization",
+                &format!("Bearer {}", supabase.supabase_key),
+            )
+            .send()
+            .await?;
 
-                    if let Some(tags) = &params.tags {
-                        let tag = tags.split(',').next().unwrap_or(tags).trim();
-                        if !tag.is_empty() {
-                            qb.push(" AND ");
-                            qb.push("p.tags @> ARRAY[");
-                            qb.push_bind(tag);
-                            qb.push("]::text[]");
-                        }
-                    }
+        Ok(response.status() == 204)
+    }
 
-                    if let Some(maker_email) = &params.maker_email {
-                        let normalized = maker_email.trim().to_ascii_lowercase();
-                        if !normalized.is_empty() {
-                            qb.push(" AND lower(p.maker_email) = lower(");
-                            qb.push_bind(normalized);
-                            qb.push(")");
-                        }
-                    }
+    pub async fn search_developers(&self, query: &str, limit: i64) -> Result<Vec<Developer>> {
+        let limit = limit.clamp(1, 50);
 
-                    let sort_by = params
-                        .sort
-                        .as_deref()
-                        .unwrap_or("created_at")
-                        .trim()
-                        .to_ascii_lowercase();
-                    let sort_dir = params
-                        .dir
-                        .as_deref()
-                        .unwrap_or("desc")
-                        .trim()
-                        .to_ascii_lowercase();
-                    let asc = sort_dir == "asc" || sort_dir == "ascending";
+        if let Some(pool) = &self.postgres {
+            let query = strip_nul_str(query);
+            let q = format!("%{}%", query);
+            let sql = format!(
+                "SELECT d.email, d.name, d.avatar_url, d.website, d.sponsor_role, d.sponsor_verified, d.created_at, d.updated_at \
+                 FROM developers d \
+                 WHERE {} \
+                 ORDER BY d.name ASC \
+                 LIMIT $2",
+                DEVELOPER_SEARCH_PREDICATE
+            );
+            let attempt = sqlx::query_as::<_, DeveloperRow>(&sql)
+                .persistent(false)
+                .bind(q.as_str())
+                .bind(limit)
+                .fetch_all(pool)
+                .await;
 
-                    qb.push(" ORDER BY ");
-                    if sort_by.as_str() == "likes" {
-                        qb.push("(SELECT COUNT(*)::bigint FROM product_likes l WHERE l.product_id = p.id)");
-                    } else if sort_by.as_str() == "favorites" {
-                        qb.push(
-                            "(SELECT COUNT(*)::bigint FROM product_favorites f WHERE f.product_id = p.id)",
-                        );
-                    } else if sort_by.as_str() == "popularity"
-                        || sort_by.as_str() == "score"
-                        || sort_by.as_str() == "featured"
+            match attempt {
+                Ok(rows) => return Ok(rows.into_iter().map(map_developer_row).collect()),
+                Err(e) => {
+                    let e: anyhow::Error = e.into();
+                    if is_missing_column_error(&e, "sponsor_role")
+                        || is_missing_column_error(&e, "sponsor_verified")
                     {
-                        qb.push("((");
-                        qb.push("(SELECT COUNT(*)::bigint FROM product_likes l WHERE l.product_id = p.id)");
-                        qb.push(") + (");
-                        qb.push(
-                            "(SELECT COUNT(*)::bigint FROM product_favorites f WHERE f.product_id = p.id)",
+                        let sql = format!(
+                            "SELECT d.email, d.name, d.avatar_url, d.website, NULL::text as sponsor_role, FALSE as sponsor_verified, d.created_at, d.updated_at \
+                             FROM developers d \
+                             WHERE {} \
+                             ORDER BY d.name ASC \
+                             LIMIT $2",
+                            DEVELOPER_SEARCH_PREDICATE
                         );
-                        qb.push("))");
-                    } else {
-                        qb.push("p.created_at");
-                    }
-                    if asc {
-                        qb.push(" ASC");
-                    } else {
-                        qb.push(" DESC");
+                        let rows = sqlx::query_as::<_, DeveloperRow>(&sql)
+                            .persistent(false)
+                            .bind(q.as_str())
+                            .bind(limit)
+                            .fetch_all(pool)
+                            .await?;
+                        return Ok(rows.into_iter().map(map_developer_row).collect());
                     }
-                    qb.push(", p.created_at DESC, p.id ASC");
+                    return Err(e);
+                }
+            }
+        }
 
-                    if let Some(limit) = params.limit {
-                        qb.push(" LIMIT ");
-                        qb.push_bind(limit);
-                    }
+        Ok(Vec::new())
+    }
 
-                    if let Some(offset) = params.offset {
-                        qb.push(" OFFSET ");
-                        qb.push_bind(offset);
-                    }
+    /**
+     * list_developers
+     * 创作者目录：分页返回全部创作者及关注数，可选 q 搜索（与 search_developers 同一条件），同时返回总数。
+     */
+    pub async fn list_developers(
+        &self,
+        q: Option<&str>,
+        sort: DeveloperDirectorySort,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<DeveloperWithFollowers>, i64)> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+        let limit = limit.clamp(1, 100);
+        let offset = offset.max(0);
+        let pattern = q
+            .map(|v| strip_nul_str(v.trim()).into_owned())
+            .filter(|v| !v.is_empty())
+            .map(|v| format!("%{}%", escape_like_pattern(&v)));
+        let where_clause = if pattern.is_some() {
+            DEVELOPER_SEARCH_PREDICATE
+        } else {
+            "($1::text IS NULL)"
+        };
 
-                    let rows = qb
-                        .build_query_as::<ProductRow>()
-                        .persistent(false)
-                        .fetch_all(&mut *tx)
-                        .await?;
-                    tx.commit().await?;
-                    Ok(rows.into_iter().map(map_product_row).collect())
-                }
+        let sql = format!(
+            "SELECT \
+                d.email, \
+                d.name, \
+                d.avatar_url, \
+                d.website, \
+                d.sponsor_role, \
+                d.sponsor_verified, \
+                COUNT(f.id)::bigint::text as followers, \
+                d.created_at, \
+                d.updated_at \
+             FROM developers d \
+             LEFT JOIN developer_follows f ON lower(f.developer_email) = lower(d.email) \
+             WHERE {} \
+             GROUP BY d.email, d.name, d.avatar_url, d.website, d.sponsor_role, d.sponsor_verified, d.created_at, d.updated_at \
+             ORDER BY {} \
+             LIMIT $2 OFFSET $3",
+            where_clause,
+            sort.order_by()
+        );
+
+        for _attempt_idx in 0..2 {
+            let attempt = sqlx::query_as::<_, DeveloperWithFollowersRow>(&sql)
+                .persistent(false)
+                .bind(pattern.as_deref())
+                .bind(limit)
+                .bind(offset)
+                .fetch_all(pool)
                 .await;
 
-                match attempt_result {
-                    Ok(list) => return Ok(list),
-                    Err(e) => {
-                        if is_missing_column_error(&e, "rejection_reason")
-                            && !PRODUCTS_REJECTION_REASON_READY.load(Ordering::Relaxed)
-                            && ensure_products_rejection_reason_column(pool).await.is_ok()
-                        {
-                            continue;
-                        }
-                        if (is_missing_column_error(&e, "sponsor_role")
-                            || is_missing_column_error(&e, "sponsor_verified"))
-                            && !DEVELOPERS_SPONSOR_COLUMNS_READY.load(Ordering::Relaxed)
-                            && ensure_developers_sponsor_columns(pool).await.is_ok()
-                        {
-                            continue;
-                        }
-                        last_err = Some(e);
-                        let Some(ref err) = last_err else {
-                            continue;
-                        };
-                        if is_retryable_db_error(err) && self.supabase.is_some() {
-                            break;
-                        }
-                        if attempt == 0 && is_retryable_db_error(err) {
-                            continue;
-                        }
-                        return Err(last_err.unwrap());
-                    }
+            match attempt {
+                Ok(rows) => {
+                    let total: i64 = sqlx::query_scalar(&format!(
+                        "SELECT COUNT(*)::bigint FROM developers d WHERE {}",
+                        where_clause
+                    ))
+                    .persistent(false)
+                    .bind(pattern.as_deref())
+                    .fetch_one(pool)
+                    .await?;
+                    return Ok((
+                        rows.into_iter()
+                            .map(map_developer_with_followers_row)
+                            .collect(),
+                        total,
+                    ));
                 }
-            }
-
-            if let Some(e) = last_err {
-                if !(is_retryable_db_error(&e) && self.supabase.is_some()) {
+                Err(e) => {
+                    let e: anyhow::Error = e.into();
+                    if (is_missing_column_error(&e, "sponsor_role")
+                        || is_missing_column_error(&e, "sponsor_verified"))
+                        && !DEVELOPERS_SPONSOR_COLUMNS_READY.load(Ordering::Relaxed)
+                        && ensure_developers_sponsor_columns(pool).await.is_ok()
+                    {
+                        continue;
+                    }
                     return Err(e);
                 }
             }
         }
 
-        let supabase = self
-            .supabase
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No database configured"))?;
-
-        let mut url = Url::parse(&format!("{}/rest/v1/products", supabase.supabase_url))?;
-        {
-            let mut qp = url.query_pairs_mut();
+        Err(anyhow::anyhow!("Failed to list developers"))
+    }
 
-            if let Some(category) = &params.category {
-                qp.append_pair("category", &format!("eq.{}", category));
-            }
+    pub async fn get_top_developers_by_followers(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<DeveloperWithFollowers>> {
+        let limit = limit.clamp(1, 50);
 
-            if let Some(language) = &params.language {
-                qp.append_pair("language", &format!("eq.{}", language));
-            }
+        if let Some(pool) = &self.postgres {
+            let mut tx = pool.begin().await?;
+            let attempt = sqlx::query_as::<_, DeveloperWithFollowersRow>(
+                "SELECT \
+                    d.email, \
+                    d.name, \
+                    d.avatar_url, \
+                    d.website, \
+                    d.sponsor_role, \
+                    d.sponsor_verified, \
+                    COUNT(f.id)::bigint::text as followers, \
+                d.created_at, \
+                d.updated_at \
+                 FROM developers d \
+                 LEFT JOIN developer_follows f ON lower(f.developer_email) = lower(d.email) \
+                 GROUP BY d.email, d.name, d.avatar_url, d.website, d.sponsor_role, d.sponsor_verified, d.created_at, d.updated_at \
+                 HAVING COUNT(f.id) > 0 \
+                 ORDER BY COUNT(f.id) DESC, d.name ASC \
+                 LIMIT $1",
+            )
+            .persistent(false)
+            .bind(limit)
+            .fetch_all(&mut *tx)
+            .await;
 
-            if let Some(status) = &params.status {
-                if dev_include_pending_in_approved() && status == "approved" {
-                    qp.append_pair("status", "in.(approved,pending)");
-                } else {
-                    qp.append_pair("status", &format!("eq.{}", status));
+            match attempt {
+                Ok(rows) => {
+                    tx.commit().await?;
+                    return Ok(rows
+                        .into_iter()
+                        .map(map_developer_with_followers_row)
+                        .collect());
                 }
-            }
-
-            if let Some(tags) = &params.tags {
-                let tag = tags.split(',').next().unwrap_or(tags).trim();
-                if !tag.is_empty() {
-                    qp.append_pair("tags", &format!("cs.{{{}}}", tag));
+                Err(e) => {
+                    let _ = tx.rollback().await;
+                    let e: anyhow::Error = e.into();
+                    if is_missing_column_error(&e, "sponsor_role")
+                        || is_missing_column_error(&e, "sponsor_verified")
+                    {
+                        let mut tx = pool.begin().await?;
+                        let rows = sqlx::query_as::<_, DeveloperWithFollowersRow>(
+                            "SELECT \
+                                d.email, \
+                                d.name, \
+                                d.avatar_url, \
+                                d.website, \
+                                NULL::text as sponsor_role, \
+                                FALSE as sponsor_verified, \
+                                COUNT(f.id)::bigint::text as followers, \
+                d.created_at, \
+                d.updated_at \
+                             FROM developers d \
+                             LEFT JOIN developer_follows f ON lower(f.developer_email) = lower(d.email) \
+                             GROUP BY d.email, d.name, d.avatar_url, d.website, d.created_at, d.updated_at \
+                             HAVING COUNT(f.id) > 0 \
+                             ORDER BY COUNT(f.id) DESC, d.name ASC \
+                             LIMIT $1",
+                        )
+                        .persistent(false)
+                        .bind(limit)
+                        .fetch_all(&mut *tx)
+                        .await?;
+                        tx.commit().await?;
+                        return Ok(rows
+                            .into_iter()
+                            .map(map_developer_with_followers_row)
+                            .collect());
+                    }
+                    return Err(e);
                 }
             }
+        }
 
-            if let Some(search) = &params.search {
-                qp.append_pair("name", &format!("ilike.%{}%", search));
-                qp.append_pair("slogan", &format!("ilike.%{}%", search));
-                qp.append_pair("description", &format!("ilike.%{}%", search));
-            }
+        Ok(Vec::new())
+    }
 
-            if let Some(maker_email) = &params.maker_email {
-                let normalized = maker_email.trim().to_ascii_lowercase();
-                if !normalized.is_empty() {
-                    qp.append_pair("maker_email", &format!("eq.{}", normalized));
+    pub async fn get_recent_developers_by_created_at(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<DeveloperWithFollowers>> {
+        let limit = limit.clamp(1, 50);
+
+        if let Some(pool) = &self.postgres {
+            let mut tx = pool.begin().await?;
+            let attempt = sqlx::query_as::<_, DeveloperWithFollowersRow>(
+                "SELECT \
+                    d.email, \
+                    d.name, \
+                    d.avatar_url, \
+                    d.website, \
+                    d.sponsor_role, \
+                    d.sponsor_verified, \
+                    COUNT(f.id)::bigint::text as followers, \
+                d.created_at, \
+                d.updated_at \
+                 FROM developers d \
+                 LEFT JOIN developer_follows f ON lower(f.developer_email) = lower(d.email) \
+                 GROUP BY d.email, d.name, d.avatar_url, d.website, d.sponsor_role, d.sponsor_verified, d.created_at, d.updated_at \
+                 ORDER BY d.created_at DESC, d.name ASC \
+                 LIMIT $1",
+            )
+            .persistent(false)
+            .bind(limit)
+            .fetch_all(&mut *tx)
+            .await;
+
+            match attempt {
+                Ok(rows) => {
+                    tx.commit().await?;
+                    return Ok(rows
+                        .into_iter()
+                        .map(map_developer_with_followers_row)
+                        .collect());
+                }
+                Err(e) => {
+                    let _ = tx.rollback().await;
+                    let e: anyhow::Error = e.into();
+                    if is_missing_column_error(&e, "sponsor_role")
+                        || is_missing_column_error(&e, "sponsor_verified")
+                    {
+                        let mut tx = pool.begin().await?;
+                        let rows = sqlx::query_as::<_, DeveloperWithFollowersRow>(
+                            "SELECT \
+                                d.email, \
+                                d.name, \
+                                d.avatar_url, \
+                                d.website, \
+                                NULL::text as sponsor_role, \
+                                FALSE as sponsor_verified, \
+                                COUNT(f.id)::bigint::text as followers, \
+                d.created_at, \
+                d.updated_at \
+                             FROM developers d \
+                             LEFT JOIN developer_follows f ON lower(f.developer_email) = lower(d.email) \
+                             GROUP BY d.email, d.name, d.avatar_url, d.website, d.created_at, d.updated_at \
+                             ORDER BY d.created_at DESC, d.name ASC \
+                             LIMIT $1",
+                        )
+                        .persistent(false)
+                        .bind(limit)
+                        .fetch_all(&mut *tx)
+                        .await?;
+                        tx.commit().await?;
+                        return Ok(rows
+                            .into_iter()
+                            .map(map_developer_with_followers_row)
+                            .collect());
+                    }
+                    return Err(e);
                 }
             }
+        }
 
-            if let Some(limit) = params.limit {
-                qp.append_pair("limit", &limit.to_string());
-            }
-
-            if let Some(offset) = params.offset {
-                qp.append_pair("offset", &offset.to_string());
-            }
-
-            let sort_by = params
-                .sort
-                .as_deref()
-                .unwrap_or("created_at")
-                .trim()
-                .to_ascii_lowercase();
-            let sort_dir = params
-                .dir
-                .as_deref()
-                .unwrap_or("desc")
-                .trim()
-                .to_ascii_lowercase();
-            let asc = sort_dir == "asc" || sort_dir == "ascending";
+        Err(anyhow::Error::new(PostgresNotConfiguredError))
+    }
 
-            let order_value = if sort_by == "created_at" {
-                if asc {
-                    "created_at.asc"
-                } else {
-                    "created_at.desc"
-                }
-            } else {
-                "created_at.desc"
-            };
-            qp.append_pair("order", order_value);
-        }
+    /**
+     * get_developer_popularity_weighted
+     * 统计窗口 [since, until) 内的 likes / favorites / 新增关注，按权重排序（仅包含有活动的创作者）；
+     * 加权分数与 LIMIT 在 SQL 内完成，只取回前 limit 个。
+     */
+    async fn get_developer_popularity_weighted(
+        &self,
+        since: chrono::DateTime<chrono::Utc>,
+        until: chrono::DateTime<chrono::Utc>,
+        limit: i64,
+    ) -> Result<Vec<DeveloperPopularity>> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+        let weights = developer_popularity_weights();
+        let limit = limit.clamp(1, 50);
 
-        let response = supabase
-            .client
-            .get(url)
-            .header("apikey", &supabase.supabase_key)
-            .header(
-                "Authorization",
-                &format!("Bearer {}", supabase.supabase_key),
+        let mut last_err: Option<anyhow::Error> = None;
+        for _attempt_idx in 0..2 {
+            let attempt = sqlx::query_as::<_, DeveloperPopularityRow>(
+                "WITH likes AS ( \
+                    SELECT p.maker_email as email, COUNT(l.id)::bigint as likes \
+                    FROM products p \
+                    JOIN product_likes l ON l.product_id = p.id \
+                    WHERE l.created_at >= $1 AND l.created_at < $2 \
+                    GROUP BY p.maker_email \
+                 ), \
+                 favorites AS ( \
+                    SELECT p.maker_email as email, COUNT(f.id)::bigint as favorites \
+                    FROM products p \
+                    JOIN product_favorites f ON f.product_id = p.id \
+                    WHERE f.created_at >= $1 AND f.created_at < $2 \
+                    GROUP BY p.maker_email \
+                 ), \
+                 followers AS ( \
+                    SELECT developer_email as email, COUNT(*)::bigint as new_followers \
+                    FROM developer_follows \
+                    WHERE created_at >= $1 AND created_at < $2 \
+                    GROUP BY developer_email \
+                 ) \
+                 SELECT \
+                    d.email, \
+                    d.name, \
+                    d.avatar_url, \
+                    d.website, \
+                    d.sponsor_role, \
+                    d.sponsor_verified, \
+                    COALESCE(l.likes, 0)::bigint as likes, \
+                    COALESCE(f.favorites, 0)::bigint as favorites, \
+                    COALESCE(fo.new_followers, 0)::bigint as new_followers, \
+                    (COALESCE(l.likes, 0) * $3 + COALESCE(f.favorites, 0) * $4 \
+                        + COALESCE(fo.new_followers, 0) * $5)::bigint as score \
+                 FROM developers d \
+                 LEFT JOIN likes l ON l.email = d.email \
+                 LEFT JOIN favorites f ON f.email = d.email \
+                 LEFT JOIN followers fo ON fo.email = d.email \
+                 WHERE (COALESCE(l.likes, 0) + COALESCE(f.favorites, 0) + COALESCE(fo.new_followers, 0)) > 0 \
+                 ORDER BY score DESC, favorites DESC, likes DESC, d.name ASC \
+                 LIMIT $6",
             )
-            .header("Accept", "application/json")
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
+            .persistent(false)
+            .bind(since)
+            .bind(until)
+            .bind(weights.likes)
+            .bind(weights.favorites)
+            .bind(weights.followers)
+            .bind(limit)
+            .fetch_all(pool)
+            .await;
 
-            if status.as_u16() == 401 || status.as_u16() == 403 {
-                return Err(anyhow::anyhow!(
-                    "Supabase auth failed: {}. Check SUPABASE_KEY. Body: {}",
-                    status,
-                    body
-                ));
+            match attempt {
+                Ok(rows) => {
+                    // SQL 已按同一权重截取前 limit 个；这里再排一次，保证同分时的名称顺序与纯函数一致
+                    return Ok(rank_developer_popularity(
+                        rows.into_iter().map(map_developer_popularity_row).collect(),
+                        weights,
+                        limit as usize,
+                    ));
+                }
+                Err(e) => {
+                    let e: anyhow::Error = e.into();
+                    if (is_missing_column_error(&e, "sponsor_role")
+                        || is_missing_column_error(&e, "sponsor_verified"))
+                        && !DEVELOPERS_SPONSOR_COLUMNS_READY.load(Ordering::Relaxed)
+                        && ensure_developers_sponsor_columns(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    last_err = Some(e);
+                    break;
+                }
             }
-
-            return Err(anyhow::anyhow!(
-                "Failed to fetch products: {}. Body: {}",
-                status,
-                body
-            ));
         }
 
-        let products: Vec<Product> = response.json().await?;
-        Ok(products)
+        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to compute developer popularity")))
     }
 
-    pub async fn get_products_by_ids(&self, ids: &[String]) -> Result<Vec<Product>> {
-        if ids.is_empty() {
-            return Ok(Vec::new());
-        }
+    pub async fn get_developer_popularity_last_month(
+        &self,
+        limit: i64,
+        metric: PopularityMetric,
+    ) -> Result<Vec<DeveloperPopularity>> {
+        let limit = limit.clamp(1, 50);
 
         if let Some(pool) = &self.postgres {
-            let mut last_err: Option<anyhow::Error> = None;
-            for attempt in 0..2 {
-                let attempt_result: Result<Vec<ProductRow>> = async {
-                    let mut tx = pool.begin().await?;
-                    let mut qb: QueryBuilder<Postgres> = QueryBuilder::new(
-                        "SELECT \
-                            p.id::text as id, \
-                            p.name, \
-                            p.slogan, \
-                            p.description, \
-                            p.website, \
-                            p.logo_url, \
-                            p.category, \
-                            COALESCE(p.tags, ARRAY[]::text[]) as tags, \
-                            p.maker_name, \
-                            p.maker_email, \
-                            p.maker_website, \
-                            p.language, \
-                            p.status::text as status, \
-                            p.rejection_reason, \
-                            p.created_at, \
-                            p.updated_at, \
-                            (SELECT COUNT(*)::bigint FROM product_likes l WHERE l.product_id = p.id) as likes, \
-                            (SELECT COUNT(*)::bigint FROM product_favorites f WHERE f.product_id = p.id) as favorites, \
-                            COALESCE(d.sponsor_role, NULL::text) as maker_sponsor_role, \
-                            COALESCE(d.sponsor_verified, FALSE) as maker_sponsor_verified \
-                         FROM products p \
-                         LEFT JOIN developers d ON lower(d.email) = lower(p.maker_email) \
-                         WHERE p.id::text = ANY(",
-                    );
-                    qb.push_bind(ids);
-                    qb.push(")");
+            let now = chrono::Utc::now();
+            let first_day_current_month = chrono::Utc
+                .with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
+                .single()
+                .unwrap_or_else(chrono::Utc::now);
+            let first_day_last_month = (first_day_current_month - chrono::Duration::days(1))
+                .with_day(1)
+                .unwrap_or(first_day_current_month - chrono::Duration::days(30));
 
-                    let rows = qb
-                        .build_query_as::<ProductRow>()
+            if metric == PopularityMetric::Weighted {
+                return self
+                    .get_developer_popularity_weighted(
+                        first_day_last_month,
+                        first_day_current_month,
+                        limit,
+                    )
+                    .await;
+            }
+
+            let mut tx = pool.begin().await?;
+            let attempt = sqlx::query_as::<_, DeveloperPopularityRow>(
+                "WITH likes AS ( \
+                    SELECT p.maker_email as email, COUNT(l.id)::bigint as likes \
+                    FROM products p \
+                    JOIN product_likes l ON l.product_id = p.id \
+                    WHERE l.created_at >= $1 AND l.created_at < $2 \
+                    GROUP BY p.maker_email \
+                 ), \
+                 favorites AS ( \
+                    SELECT p.maker_email as email, COUNT(f.id)::bigint as favorites \
+                    FROM products p \
+                    JOIN product_favorites f ON f.product_id = p.id \
+                    WHERE f.created_at >= $1 AND f.created_at < $2 \
+                    GROUP BY p.maker_email \
+                 ) \
+                 SELECT \
+                    d.email, \
+                    d.name, \
+                    d.avatar_url, \
+                    d.website, \
+                    d.sponsor_role, \
+                    d.sponsor_verified, \
+                    COALESCE(l.likes, 0)::bigint as likes, \
+                    COALESCE(f.favorites, 0)::bigint as favorites, \
+                    (COALESCE(l.likes, 0) + COALESCE(f.favorites, 0))::bigint as score \
+                 FROM developers d \
+                 LEFT JOIN likes l ON l.email = d.email \
+                 LEFT JOIN favorites f ON f.email = d.email \
+                 ORDER BY score DESC, favorites DESC, likes DESC, d.name ASC \
+                 LIMIT $3",
+            )
+            .persistent(false)
+            .bind(first_day_last_month)
+            .bind(first_day_current_month)
+            .bind(limit)
+            .fetch_all(&mut *tx)
+            .await;
+
+            match attempt {
+                Ok(rows) => {
+                    tx.commit().await?;
+                    return Ok(rows.into_iter().map(map_developer_popularity_row).collect());
+                }
+                Err(e) => {
+                    let _ = tx.rollback().await;
+                    let e: anyhow::Error = e.into();
+                    if is_missing_column_error(&e, "sponsor_role")
+                        || is_missing_column_error(&e, "sponsor_verified")
+                    {
+                        let mut tx = pool.begin().await?;
+                        let rows = sqlx::query_as::<_, DeveloperPopularityRow>(
+                            "WITH likes AS ( \
+                                SELECT p.maker_email as email, COUNT(l.id)::bigint as likes \
+                                FROM products p \
+                                JOIN product_likes l ON l.product_id = p.id \
+                                WHERE l.created_at >= $1 AND l.created_at < $2 \
+                                GROUP BY p.maker_email \
+                             ), \
+                             favorites AS ( \
+                                SELECT p.maker_email as email, COUNT(f.id)::bigint as favorites \
+                                FROM products p \
+                                JOIN product_favorites f ON f.product_id = p.id \
+                                WHERE f.created_at >= $1 AND f.created_at < $2 \
+                                GROUP BY p.maker_email \
+                             ) \
+                             SELECT \
+                                d.email, \
+                                d.name, \
+                                d.avatar_url, \
+                                d.website, \
+                                NULL::text as sponsor_role, \
+                                FALSE as sponsor_verified, \
+                                COALESCE(l.likes, 0)::bigint as likes, \
+                                COALESCE(f.favorites, 0)::bigint as favorites, \
+                                (COALESCE(l.likes, 0) + COALESCE(f.favorites, 0))::bigint as score \
+                             FROM developers d \
+                             LEFT JOIN likes l ON l.email = d.email \
+                             LEFT JOIN favorites f ON f.email = d.email \
+                             ORDER BY score DESC, favorites DESC, likes DESC, d.name ASC \
+                             LIMIT $3",
+                        )
                         .persistent(false)
+                        .bind(first_day_last_month)
+                        .bind(first_day_current_month)
+                        .bind(limit)
                         .fetch_all(&mut *tx)
                         .await?;
-                    tx.commit().await?;
-                    Ok(rows)
-                }
-                .await;
-
-                match attempt_result {
-                    Ok(rows) => {
-                        let mut map = std::collections::HashMap::<String, Product>::new();
-                        for row in rows {
-                            let product = map_product_row(row);
-                            map.insert(product.id.clone(), product);
-                        }
-
-                        let mut ordered = Vec::with_capacity(ids.len());
-                        for id in ids {
-                            if let Some(p) = map.remove(id) {
-                                ordered.push(p);
-                            }
-                        }
-                        return Ok(ordered);
-                    }
-                    Err(e) => {
-                        if is_missing_column_error(&e, "rejection_reason")
-                            && !PRODUCTS_REJECTION_REASON_READY.load(Ordering::Relaxed)
-                            && ensure_products_rejection_reason_column(pool).await.is_ok()
-                        {
-                            continue;
-                        }
-                        if (is_missing_column_error(&e, "sponsor_role")
-                            || is_missing_column_error(&e, "sponsor_verified"))
-                            && !DEVELOPERS_SPONSOR_COLUMNS_READY.load(Ordering::Relaxed)
-                            && ensure_developers_sponsor_columns(pool).await.is_ok()
-                        {
-                            continue;
-                        }
-                        last_err = Some(e);
-                        let Some(ref err) = last_err else {
-                            continue;
-                        };
-                        if is_retryable_db_error(err) && self.supabase.is_some() {
-                            break;
-                        }
-                        if attempt == 0 && is_retryable_db_error(err) {
-                            continue;
-                        }
-                        return Err(last_err.unwrap());
+                        tx.commit().await?;
+                        return Ok(rows.into_iter().map(map_developer_popularity_row).collect());
                     }
-                }
-            }
-
-            if let Some(e) = last_err {
-                if !(is_retryable_db_error(&e) && self.supabase.is_some()) {
                     return Err(e);
                 }
             }
         }
 
-        let mut ordered = Vec::new();
-        for id in ids {
-            if let Some(p) = self.get_product_by_id(id).await? {
-                ordered.push(p);
-            }
-        }
-        Ok(ordered)
+        Err(anyhow::Error::new(PostgresNotConfiguredError))
     }
 
-    pub async fn get_home_module_state(&self, key: &str) -> Result<Option<HomeModuleState>> {
-        if let Some(pool) = &self.postgres {
-            let mut tx = pool.begin().await?;
-            let row = sqlx::query_as::<_, HomeModuleStateRow>(
-                "SELECT key, mode, day_key, remaining_ids, today_ids FROM home_module_state WHERE key = $1 LIMIT 1",
-            )
-            .persistent(false)
-            .bind(key)
-            .fetch_optional(&mut *tx)
-            .await?;
-            tx.commit().await?;
-            return Ok(row.map(map_home_module_state_row));
-        }
-
-        Ok(None)
-    }
+    pub async fn get_developer_popularity_last_week(
+        &self,
+        limit: i64,
+        metric: PopularityMetric,
+    ) -> Result<Vec<DeveloperPopularity>> {
+        let limit = limit.clamp(1, 50);
 
-    pub async fn upsert_home_module_state(&self, state: HomeModuleState) -> Result<()> {
         if let Some(pool) = &self.postgres {
-            let mut tx = pool.begin().await?;
-            sqlx::query(
-                "INSERT INTO home_module_state (key, mode, day_key, remaining_ids, today_ids) \
-                 VALUES ($1, $2, $3, $4, $5) \
-                 ON CONFLICT (key) DO UPDATE SET \
-                    mode = EXCLUDED.mode, \
-                    day_key = EXCLUDED.day_key, \
-                    remaining_ids = EXCLUDED.remaining_ids, \
-                    today_ids = EXCLUDED.today_ids, \
-                    updated_at = NOW()",
-            )
-            .persistent(false)
-            .bind(&state.key)
-            .bind(&state.mode)
-            .bind(state.day_key)
-            .bind(&state.remaining_ids)
-            .bind(&state.today_ids)
-            .execute(&mut *tx)
-            .await?;
-            tx.commit().await?;
-            return Ok(());
-        }
+            let now = chrono::Utc::now();
+            let since = now - chrono::Duration::days(7);
 
-        Ok(())
-    }
+            if metric == PopularityMetric::Weighted {
+                return self
+                    .get_developer_popularity_weighted(since, now, limit)
+                    .await;
+            }
 
-    #[allow(dead_code)]
-    pub async fn get_first_developer_emails_by_created_at(
-        &self,
-        limit: i64,
-    ) -> Result<Vec<String>> {
-        let limit = limit.clamp(1, 5000);
-        if let Some(pool) = &self.postgres {
-            let rows = sqlx::query_as::<_, NewsletterRecipientRow>(
-                "SELECT email FROM developers ORDER BY created_at ASC, email ASC LIMIT $1",
+            let mut tx = pool.begin().await?;
+            let attempt = sqlx::query_as::<_, DeveloperPopularityRow>(
+                "WITH likes AS ( \
+                    SELECT p.maker_email as email, COUNT(l.id)::bigint as likes \
+                    FROM products p \
+                    JOIN product_likes l ON l.product_id = p.id \
+                    WHERE l.created_at >= $1 \
+                    GROUP BY p.maker_email \
+                 ), \
+                 favorites AS ( \
+                    SELECT p.maker_email as email, COUNT(f.id)::bigint as favorites \
+                    FROM products p \
+                    JOIN product_favorites f ON f.product_id = p.id \
+                    WHERE f.created_at >= $1 \
+                    GROUP BY p.maker_email \
+                 ) \
+                 SELECT \
+                    d.email, \
+                    d.name, \
+                    d.avatar_url, \
+                    d.website, \
+                    d.sponsor_role, \
+                    d.sponsor_verified, \
+                    COALESCE(l.likes, 0)::bigint as likes, \
+                    COALESCE(f.favorites, 0)::bigint as favorites, \
+                    (COALESCE(l.likes, 0) + COALESCE(f.favorites, 0))::bigint as score \
+                 FROM developers d \
+                 LEFT JOIN likes l ON l.email = d.email \
+                 LEFT JOIN favorites f ON f.email = d.email \
+                 WHERE (COALESCE(l.likes, 0) + COALESCE(f.favorites, 0)) > 0 \
+                 ORDER BY score DESC, favorites DESC, likes DESC, d.name ASC \
+                 LIMIT $2",
             )
             .persistent(false)
+            .bind(since)
             .bind(limit)
-            .fetch_all(pool)
-            .await?;
-            return Ok(rows
-                .into_iter()
-                .map(|r| strip_nul_str(&r.email).into_owned())
-                .collect());
-        }
-        Ok(Vec::new())
-    }
-
-    #[allow(dead_code)]
-    pub async fn get_free_sponsorship_candidate_product_ids(
-        &self,
-        first_n_developers: i64,
-        window_days: i64,
-        now: chrono::DateTime<chrono::Utc>,
-        language: Option<&str>,
-    ) -> Result<Vec<String>> {
-        if let Some(pool) = &self.postgres {
-            let emails = self
-                .get_first_developer_emails_by_created_at(first_n_developers)
-                .await?;
-            if emails.is_empty() {
-                return Ok(Vec::new());
-            }
-
-            let since = now - chrono::Duration::days(window_days.max(1));
-            let status_clause = if dev_include_pending_in_approved() {
-                "p.status::text IN ('approved','pending')"
-            } else {
-                "p.status::text = 'approved'"
-            };
-
-            let rows = if let Some(language) = language {
-                let sql = format!(
-                    "SELECT p.id::text as email \
-                     FROM products p \
-                     WHERE {} AND p.created_at >= $1 AND p.maker_email = ANY($2) AND p.language = $3 \
-                     ORDER BY p.created_at DESC, p.id ASC \
-                     LIMIT 5000",
-                    status_clause
-                );
-                sqlx::query_as::<_, NewsletterRecipientRow>(&sql)
-                    .persistent(false)
-                    .bind(since)
-                    .bind(&emails)
-                    .bind(language)
-                    .fetch_all(pool)
-                    .await?
-            } else {
-                let sql = format!(
-                    "SELECT p.id::text as email \
-                     FROM products p \
-                     WHERE {} AND p.created_at >= $1 AND p.maker_email = ANY($2) \
-                     ORDER BY p.created_at DESC, p.id ASC \
-                     LIMIT 5000",
-                    status_clause
-                );
-                sqlx::query_as::<_, NewsletterRecipientRow>(&sql)
-                    .persistent(false)
-                    .bind(since)
-                    .bind(&emails)
-                    .fetch_all(pool)
-                    .await?
-            };
+            .fetch_all(&mut *tx)
+            .await;
 
-            return Ok(rows
-                .into_iter()
-                .map(|r| strip_nul_str(&r.email).into_owned())
-                .collect());
+            match attempt {
+                Ok(rows) => {
+                    tx.commit().await?;
+                    return Ok(rows.into_iter().map(map_developer_popularity_row).collect());
+                }
+                Err(e) => {
+                    let _ = tx.rollback().await;
+                    let e: anyhow::Error = e.into();
+                    if is_missing_column_error(&e, "sponsor_role")
+                        || is_missing_column_error(&e, "sponsor_verified")
+                    {
+                        let mut tx = pool.begin().await?;
+                        let rows = sqlx::query_as::<_, DeveloperPopularityRow>(
+                            "WITH likes AS ( \
+                                SELECT p.maker_email as email, COUNT(l.id)::bigint as likes \
+                                FROM products p \
+                                JOIN product_likes l ON l.product_id = p.id \
+                                WHERE l.created_at >= $1 \
+                                GROUP BY p.maker_email \
+                             ), \
+                             favorites AS ( \
+                                SELECT p.maker_email as email, COUNT(f.id)::bigint as favorites \
+                                FROM products p \
+                                JOIN product_favorites f ON f.product_id = p.id \
+                                WHERE f.created_at >= $1 \
+                                GROUP BY p.maker_email \
+                             ) \
+                             SELECT \
+                                d.email, \
+                                d.name, \
+                                d.avatar_url, \
+                                d.website, \
+                                NULL::text as sponsor_role, \
+                                FALSE as sponsor_verified, \
+                                COALESCE(l.likes, 0)::bigint as likes, \
+                                COALESCE(f.favorites, 0)::bigint as favorites, \
+                                (COALESCE(l.likes, 0) + COALESCE(f.favorites, 0))::bigint as score \
+                             FROM developers d \
+                             LEFT JOIN likes l ON l.email = d.email \
+                             LEFT JOIN favorites f ON f.email = d.email \
+                             WHERE (COALESCE(l.likes, 0) + COALESCE(f.favorites, 0)) > 0 \
+                             ORDER BY score DESC, favorites DESC, likes DESC, d.name ASC \
+                             LIMIT $2",
+                        )
+                        .persistent(false)
+                        .bind(since)
+                        .bind(limit)
+                        .fetch_all(&mut *tx)
+                        .await?;
+                        tx.commit().await?;
+                        return Ok(rows.into_iter().map(map_developer_popularity_row).collect());
+                    }
+                    return Err(e);
+                }
+            }
         }
 
-        Ok(Vec::new())
+        Err(anyhow::Error::new(PostgresNotConfiguredError))
     }
 
-    pub async fn get_first_product_ids_by_created_at(
-        &self,
-        limit: i64,
-        language: Option<&str>,
-    ) -> Result<Vec<String>> {
-        let limit = limit.clamp(1, 5000);
-        if let Some(pool) = &self.postgres {
-            let status_clause = if dev_include_pending_in_approved() {
-                "p.status::text IN ('approved','pending')"
-            } else {
-                "p.status::text = 'approved'"
-            };
+    /**
+     * get_product_status_counts
+     * 管理端看板：各审核状态的产品数量（pending/approved/rejected 始终返回，缺失为 0），Postgres 单次 GROUP BY 查询。
+     */
+    pub async fn get_product_status_counts(&self) -> Result<BTreeMap<String, i64>> {
+        let mut counts: BTreeMap<String, i64> = ["pending", "approved", "rejected"]
+            .iter()
+            .map(|status| (status.to_string(), 0))
+            .collect();
 
-            let rows = if let Some(language) = language {
-                let sql = format!(
-                    "SELECT p.id::text as email \
-                     FROM products p \
-                     WHERE {} AND p.language = $2 \
-                     ORDER BY p.created_at ASC, p.id ASC \
-                     LIMIT $1",
-                    status_clause
-                );
-                sqlx::query_as::<_, NewsletterRecipientRow>(&sql)
-                    .persistent(false)
-                    .bind(limit)
-                    .bind(language)
-                    .fetch_all(pool)
-                    .await?
-            } else {
-                let sql = format!(
-                    "SELECT p.id::text as email \
-                     FROM products p \
-                     WHERE {} \
-                     ORDER BY p.created_at ASC, p.id ASC \
-                     LIMIT $1",
-                    status_clause
-                );
-                sqlx::query_as::<_, NewsletterRecipientRow>(&sql)
-                    .persistent(false)
-                    .bind(limit)
-                    .fetch_all(pool)
-                    .await?
-            };
+        if let Some(pool) = &self.postgres {
+            let rows = sqlx::query_as::<_, (String, i64)>(
+                "SELECT status, COUNT(*)::bigint FROM products GROUP BY status",
+            )
+            .persistent(false)
+            .fetch_all(pool)
+            .await?;
+            for (status, count) in rows {
+                counts.insert(status, count);
+            }
+            return Ok(counts);
+        }
 
-            return Ok(rows
-                .into_iter()
-                .map(|r| strip_nul_str(&r.email).into_owned())
-                .collect());
+        let supabase = self
+            .supabase
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let statuses: Vec<String> = counts.keys().cloned().collect();
+        for status in statuses {
+            let count = supabase_count(
+                supabase,
+                "products",
+                &[
+                    ("select", "id".to_string()),
+                    ("status", format!("eq.{}", status)),
+                ],
+            )
+            .await?;
+            counts.insert(status, count);
         }
+        Ok(counts)
+    }
 
-        Ok(Vec::new())
+    pub async fn get_developer_center_stats(&self, email: &str) -> Result<DeveloperCenterStats> {
+        self.get_developer_center_stats_windowed(email, None).await
     }
 
-    pub async fn count_products_for_sponsorship_rotation(
+    /**
+     * get_developer_center_stats_windowed
+     * 创作者中心统计：始终返回累计值；指定 window 时额外返回窗口内新增的点赞/收藏/关注，便于展示增量。
+     */
+    pub async fn get_developer_center_stats_windowed(
         &self,
-        language: Option<&str>,
-    ) -> Result<i64> {
-        if let Some(pool) = &self.postgres {
-            let status_clause = if dev_include_pending_in_approved() {
-                "p.status::text IN ('approved','pending')"
-            } else {
-                "p.status::text = 'approved'"
-            };
+        email: &str,
+        window: Option<StatsWindow>,
+    ) -> Result<DeveloperCenterStats> {
+        let window = window.map(|w| (w, w.since(chrono::Utc::now())));
+        let since = window.map(|(_, since)| since);
 
-            let row = if let Some(language) = language {
-                let sql = format!(
-                    "SELECT COUNT(*)::bigint \
-                     FROM products p \
-                     WHERE {} AND p.language = $1",
-                    status_clause
-                );
-                sqlx::query_as::<_, (i64,)>(&sql)
-                    .persistent(false)
-                    .bind(language)
-                    .fetch_one(pool)
-                    .await?
-            } else {
-                let sql = format!(
-                    "SELECT COUNT(*)::bigint \
-                     FROM products p \
-                     WHERE {}",
-                    status_clause
-                );
-                sqlx::query_as::<_, (i64,)>(&sql)
-                    .persistent(false)
-                    .fetch_one(pool)
-                    .await?
-            };
+        if let Some(pool) = &self.postgres {
+            let email = strip_nul_str(email);
+            let row = sqlx::query_as::<_, DeveloperCenterStatsRow>(
+                "WITH followers AS ( \
+                    SELECT COUNT(*)::bigint as total, \
+                        COUNT(*) FILTER (WHERE f.created_at >= $2)::bigint as windowed \
+                    FROM developer_follows f WHERE lower(f.developer_email) = lower($1) \
+                 ), \
+                 likes AS ( \
+                    SELECT COUNT(*)::bigint as total, \
+                        COUNT(*) FILTER (WHERE l.created_at >= $2)::bigint as windowed \
+                    FROM product_likes l JOIN products p ON p.id = l.product_id \
+                    WHERE lower(p.maker_email) = lower($1) \
+                 ), \
+                 favorites AS ( \
+                    SELECT COUNT(*)::bigint as total, \
+                        COUNT(*) FILTER (WHERE f.created_at >= $2)::bigint as windowed \
+                    FROM product_favorites f JOIN products p ON p.id = f.product_id \
+                    WHERE lower(p.maker_email) = lower($1) \
+                 ) \
+                 SELECT \
+                    fo.total as followers, li.total as total_likes, fa.total as total_favorites, \
+                    fo.windowed as window_followers, li.windowed as window_likes, fa.windowed as window_favorites \
+                 FROM followers fo, likes li, favorites fa",
+            )
+            .persistent(false)
+            .bind(email.as_ref())
+            .bind(since)
+            .fetch_one(pool)
+            .await?;
 
-            return Ok(row.0);
+            return Ok(map_developer_center_stats_row(row, window));
         }
 
-        Ok(0)
-    }
+        let supabase = self
+            .supabase
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
 
-    pub async fn get_popular_product_ids_by_day(
-        &self,
-        day: chrono::NaiveDate,
-        limit: i64,
-        language: Option<&str>,
-    ) -> Result<Vec<String>> {
-        let limit = limit.clamp(1, 5000);
-        if let Some(pool) = &self.postgres {
-            let status_clause = if dev_include_pending_in_approved() {
-                "p.status::text IN ('approved','pending')"
-            } else {
-                "p.status::text = 'approved'"
-            };
+        let email = strip_nul_str(email).into_owned();
 
-            let start = chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(
-                day.and_hms_opt(0, 0, 0).unwrap_or_default(),
-                chrono::Utc,
-            );
-            let end = start + chrono::Duration::days(1);
+        let followers = supabase_count(
+            supabase,
+            "developer_follows",
+            &[
+                ("select", "id".to_string()),
+                ("developer_email", format!("eq.{}", email)),
+            ],
+        )
+        .await?;
 
-            let rows = if let Some(language) = language {
-                let sql = format!(
-                    "WITH likes AS ( \
-                        SELECT product_id, COUNT(*)::bigint AS likes \
-                        FROM product_likes \
-                        WHERE created_at >= $1 AND created_at < $2 \
-                        GROUP BY product_id \
-                    ), favs AS ( \
-                        SELECT product_id, COUNT(*)::bigint AS favorites \
-                        FROM product_favorites \
-                        WHERE created_at >= $1 AND created_at < $2 \
-                        GROUP BY product_id \
-                    ) \
-                    SELECT p.id::text as email \
-                    FROM products p \
-                    LEFT JOIN likes l ON l.product_id = p.id \
-                    LEFT JOIN favs f ON f.product_id = p.id \
-                    WHERE {} AND p.language = $3 \
-                    ORDER BY (COALESCE(l.likes, 0) + COALESCE(f.favorites, 0)) DESC, p.created_at DESC, p.id ASC \
-                    LIMIT $4",
-                    status_clause
-                );
-                sqlx::query_as::<_, NewsletterRecipientRow>(&sql)
-                    .persistent(false)
-                    .bind(start)
-                    .bind(end)
-                    .bind(language)
-                    .bind(limit)
-                    .fetch_all(pool)
-                    .await?
-            } else {
-                let sql = format!(
-                    "WITH likes AS ( \
-                        SELECT product_id, COUNT(*)::bigint AS likes \
-                        FROM product_likes \
-                        WHERE created_at >= $1 AND created_at < $2 \
-                        GROUP BY product_id \
-                    ), favs AS ( \
-                        SELECT product_id, COUNT(*)::bigint AS favorites \
-                        FROM product_favorites \
-                        WHERE created_at >= $1 AND created_at < $2 \
-                        GROUP BY product_id \
-                    ) \
-                    SELECT p.id::text as email \
-                    FROM products p \
-                    LEFT JOIN likes l ON l.product_id = p.id \
-                    LEFT JOIN favs f ON f.product_id = p.id \
-                    WHERE {} \
-                    ORDER BY (COALESCE(l.likes, 0) + COALESCE(f.favorites, 0)) DESC, p.created_at DESC, p.id ASC \
-                    LIMIT $3",
-                    status_clause
-                );
-                sqlx::query_as::<_, NewsletterRecipientRow>(&sql)
-                    .persistent(false)
-                    .bind(start)
-                    .bind(end)
-                    .bind(limit)
-                    .fetch_all(pool)
-                    .await?
-            };
+        let total_likes = supabase_count(
+            supabase,
+            "product_likes",
+            &[
+                ("select", "id,products!inner(maker_email)".to_string()),
+                ("products.maker_email", format!("eq.{}", email)),
+            ],
+        )
+        .await?;
+
+        let total_favorites = supabase_count(
+            supabase,
+            "product_favorites",
+            &[
+                ("select", "id,products!inner(maker_email)".to_string()),
+                ("products.maker_email", format!("eq.{}", email)),
+            ],
+        )
+        .await?;
+
+        let windowed = match window {
+            Some((window, since)) => {
+                let since_filter = ("created_at", format!("gte.{}", since.to_rfc3339()));
+                let new_followers = supabase_count(
+                    supabase,
+                    "developer_follows",
+                    &[
+                        ("select", "id".to_string()),
+                        ("developer_email", format!("eq.{}", email)),
+                        since_filter.clone(),
+                    ],
+                )
+                .await?;
+                let likes = supabase_count(
+                    supabase,
+                    "product_likes",
+                    &[
+                        ("select", "id,products!inner(maker_email)".to_string()),
+                        ("products.maker_email", format!("eq.{}", email)),
+                        since_filter.clone(),
+                    ],
+                )
+                .await?;
+                let favorites = supabase_count(
+                    supabase,
+                    "product_favorites",
+                    &[
+                        ("select", "id,products!inner(maker_email)".to_string()),
+                        ("products.maker_email", format!("eq.{}", email)),
+                        since_filter,
+                    ],
+                )
+                .await?;
+                Some(DeveloperCenterWindowStats {
+                    window: window.as_str().to_string(),
+                    since,
+                    new_followers,
+                    likes,
+                    favorites,
+                })
+            }
+            None => None,
+        };
 
-            return Ok(rows
-                .into_iter()
-                .map(|r| strip_nul_str(&r.email).into_owned())
-                .collect());
+        Ok(DeveloperCenterStats {
+            followers,
+            total_likes,
+            total_favorites,
+            windowed,
+        })
+    }
+
+    /**
+     * merge_developers
+     * 将 from_email 的产品、关注者与赞助信息并入 into_email（小写），随后删除源 developers 行；
+     * 同一用户同时关注两者时只保留一条关注，目标缺失的资料字段用源值补齐。整个过程在一个事务内完成。
+     */
+    pub async fn merge_developers(
+        &self,
+        from_email: &str,
+        into_email: &str,
+    ) -> Result<DeveloperMergeResult> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let from_email = strip_nul_str(from_email.trim()).into_owned();
+        let into_email = strip_nul_str(into_email.trim()).to_ascii_lowercase();
+        if from_email.is_empty() || into_email.is_empty() || from_email == into_email {
+            return Err(anyhow::anyhow!("from_email and into_email must differ"));
         }
+        // 事务内缺列会导致整个事务中止，先补齐 sponsor 字段再开始
+        ensure_developers_sponsor_columns(pool).await?;
 
-        Ok(Vec::new())
+        let mut tx = pool.begin().await?;
+
+        // 目标按邮箱忽略大小写匹配（排除源行本身，便于合并仅大小写不同的重复记录），之后统一使用库中的实际邮箱
+        let target_email: Option<String> = sqlx::query_scalar(
+            "SELECT email FROM developers WHERE lower(email) = lower($1) AND email <> $2 \
+             ORDER BY (email = $1) DESC LIMIT 1 FOR UPDATE",
+        )
+        .persistent(false)
+        .bind(into_email.as_str())
+        .bind(from_email.as_str())
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(into_email) = target_email else {
+            return Err(DeveloperNotFoundError {
+                role: "target",
+                email: into_email,
+            }
+            .into());
+        };
+        let source_exists: Option<String> =
+            sqlx::query_scalar("SELECT email FROM developers WHERE email = $1 FOR UPDATE")
+                .persistent(false)
+                .bind(from_email.as_str())
+                .fetch_optional(&mut *tx)
+                .await?;
+        if source_exists.is_none() {
+            return Err(DeveloperNotFoundError {
+                role: "source",
+                email: from_email,
+            }
+            .into());
+        }
+
+        let products_moved = sqlx::query(
+            "UPDATE products SET maker_email = $2, updated_at = NOW() \
+             WHERE lower(maker_email) = lower($1) AND maker_email <> $2",
+        )
+        .persistent(false)
+        .bind(from_email.as_str())
+        .bind(into_email.as_str())
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        let follows_moved = sqlx::query(
+            "INSERT INTO developer_follows (developer_email, user_id, created_at) \
+             SELECT $2, user_id, created_at FROM developer_follows WHERE developer_email = $1 \
+             ON CONFLICT (developer_email, user_id) DO NOTHING",
+        )
+        .persistent(false)
+        .bind(from_email.as_str())
+        .bind(into_email.as_str())
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        sqlx::query(
+            "UPDATE developers t SET \
+                sponsor_role = COALESCE(t.sponsor_role, s.sponsor_role), \
+                sponsor_verified = t.sponsor_verified OR s.sponsor_verified, \
+                avatar_url = COALESCE(t.avatar_url, s.avatar_url), \
+                website = COALESCE(t.website, s.website), \
+                updated_at = NOW() \
+             FROM developers s \
+             WHERE t.email = $2 AND s.email = $1",
+        )
+        .persistent(false)
+        .bind(from_email.as_str())
+        .bind(into_email.as_str())
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM developer_follows WHERE developer_email = $1")
+            .persistent(false)
+            .bind(from_email.as_str())
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM developers WHERE email = $1")
+            .persistent(false)
+            .bind(from_email.as_str())
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(DeveloperMergeResult {
+            from_email,
+            into_email,
+            products_moved,
+            follows_moved,
+        })
     }
 
-    pub async fn get_active_sponsorship_grants(
+    /**
+     * bump_product
+     * 将产品 bumped_at 更新为 now；距上次顶帖不足 cooldown_days 时返回 ProductBumpCooldownError。
+     * 条件更新保证并发请求只有一次生效；产品不存在返回 None。
+     */
+    pub async fn bump_product(
         &self,
-        placement: &str,
+        product_id: &str,
         now: chrono::DateTime<chrono::Utc>,
-        language: Option<&str>,
-    ) -> Result<Vec<(Option<i32>, String)>> {
-        if let Some(pool) = &self.postgres {
-            let placement = strip_nul_str(placement);
-            let status_clause = if dev_include_pending_in_approved() {
-                "p.status::text IN ('approved','pending')"
-            } else {
-                "p.status::text = 'approved'"
-            };
-
-            let mut last_err: Option<anyhow::Error> = None;
-            for _attempt_idx in 0..2 {
-                let attempt = if let Some(language) = language {
-                    let sql = format!(
-                        "SELECT s.id, p.id::text as product_id, s.slot_index \
-                         FROM sponsorship_grants s \
-                         JOIN products p ON p.id = s.product_id \
-                         WHERE s.placement = $1 AND s.starts_at <= $2 AND s.ends_at > $2 AND {} AND p.language = $3 \
-                         ORDER BY s.slot_index NULLS LAST, s.created_at ASC, p.created_at DESC, p.id ASC",
-                        status_clause
-                    );
-                    sqlx::query_as::<_, SponsorshipGrantRow>(&sql)
-                        .persistent(false)
-                        .bind(placement.as_ref())
-                        .bind(now)
-                        .bind(language)
-                        .fetch_all(pool)
-                        .await
-                } else {
-                    let sql = format!(
-                        "SELECT s.id, p.id::text as product_id, s.slot_index \
-                         FROM sponsorship_grants s \
-                         JOIN products p ON p.id = s.product_id \
-                         WHERE s.placement = $1 AND s.starts_at <= $2 AND s.ends_at > $2 AND {} \
-                         ORDER BY s.slot_index NULLS LAST, s.created_at ASC, p.created_at DESC, p.id ASC",
-                        status_clause
-                    );
-                    sqlx::query_as::<_, SponsorshipGrantRow>(&sql)
-                        .persistent(false)
-                        .bind(placement.as_ref())
-                        .bind(now)
-                        .fetch_all(pool)
-                        .await
-                };
+        cooldown_days: i64,
+    ) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+        ensure_products_bumped_at_column(pool).await?;
 
-                match attempt {
-                    Ok(rows) => {
-                        return Ok(rows
-                            .into_iter()
-                            .map(|r| (r.slot_index, strip_nul_str(&r.product_id).into_owned()))
-                            .collect())
-                    }
-                    Err(e) => {
-                        let e: anyhow::Error = e.into();
-                        if is_missing_relation_error(&e, "sponsorship_grants")
-                            && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
-                            && ensure_sponsorship_tables(pool).await.is_ok()
-                        {
-                            continue;
-                        }
-                        last_err = Some(e);
-                        break;
-                    }
-                }
-            }
+        let product_uuid = uuid::Uuid::parse_str(product_id.trim())
+            .map_err(|_| anyhow::anyhow!("Invalid product id"))?;
+        let cooldown = chrono::Duration::days(cooldown_days.max(1));
 
-            return Err(last_err.unwrap_or_else(|| {
-                anyhow::anyhow!("Failed to fetch active sponsorship grants after auto migration")
-            }));
+        let bumped: Option<chrono::DateTime<chrono::Utc>> = sqlx::query_scalar(
+            "UPDATE products SET bumped_at = $2 \
+             WHERE id = $1 AND (bumped_at IS NULL OR bumped_at <= $3) \
+             RETURNING bumped_at",
+        )
+        .persistent(false)
+        .bind(product_uuid)
+        .bind(now)
+        .bind(now - cooldown)
+        .fetch_optional(pool)
+        .await?;
+        if bumped.is_some() {
+            return Ok(bumped);
         }
 
-        Ok(Vec::new())
+        let last: Option<Option<chrono::DateTime<chrono::Utc>>> =
+            sqlx::query_scalar("SELECT bumped_at FROM products WHERE id = $1")
+                .persistent(false)
+                .bind(product_uuid)
+                .fetch_optional(pool)
+                .await?;
+        match last {
+            None => Ok(None),
+            Some(last) => Err(ProductBumpCooldownError {
+                next_allowed_at: last.unwrap_or(now) + cooldown,
+            }
+            .into()),
+        }
     }
 
-    pub async fn create_sponsorship_request(
+    /**
+     * claim_product
+     * 创作者认领产品：maker_email 与已验证邮箱一致时直接生成 approved 认领，否则生成 pending 认领等待管理员审核；
+     * 已被他人认领时返回 ProductAlreadyClaimedError，重复认领返回已有记录。产品不存在返回 None。
+     */
+    pub async fn claim_product(
         &self,
-        req: CreateSponsorshipRequest,
-    ) -> Result<SponsorshipRequest> {
+        product_id: &str,
+        claimant_email: &str,
+        note: Option<&str>,
+    ) -> Result<Option<ProductClaim>> {
         let pool = self
             .postgres
             .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Postgres is not configured"))?;
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+        ensure_product_claims_table(pool).await?;
 
-        let email = strip_nul_str(req.email.trim());
-        let product_ref = strip_nul_str(req.product_ref.trim());
-        let placement = strip_nul_str(req.placement.trim());
-        let note = req
-            .note
-            .as_ref()
-            .map(|v| v.trim().to_string())
+        let claimant_email = strip_nul_str(claimant_email.trim()).to_ascii_lowercase();
+        let note = note
+            .map(|v| strip_nul_str(v.trim()).into_owned())
             .filter(|v| !v.is_empty());
-        for _attempt_idx in 0..2 {
-            let attempt = sqlx::query_as::<_, SponsorshipRequestRow>(
-                "INSERT INTO sponsorship_requests (email, product_ref, placement, slot_index, duration_days, note) \
-                 VALUES ($1, $2, $3, $4, $5, $6) \
-                 RETURNING id, email, product_ref, placement, slot_index, duration_days, note, status, processed_grant_id, created_at, updated_at",
-            )
-            .persistent(false)
-            .bind(email.as_ref())
-            .bind(product_ref.as_ref())
-            .bind(placement.as_ref())
-            .bind(req.slot_index)
-            .bind(req.duration_days)
-            .bind(note.as_deref())
-            .fetch_one(pool)
-            .await;
 
-            match attempt {
-                Ok(row) => return Ok(map_sponsorship_request_row(row)),
-                Err(e) => {
-                    let e: anyhow::Error = e.into();
-                    if is_missing_relation_error(&e, "sponsorship_requests")
-                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
-                        && ensure_sponsorship_tables(pool).await.is_ok()
-                    {
-                        continue;
-                    }
-                    return Err(e);
+        let mut tx = pool.begin().await?;
+
+        let maker_email: Option<String> =
+            sqlx::query_scalar("SELECT maker_email FROM products WHERE id = $1::uuid FOR UPDATE")
+                .persistent(false)
+                .bind(product_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        let Some(maker_email) = maker_email else {
+            return Ok(None);
+        };
+
+        let existing: Vec<ProductClaimRow> = sqlx::query_as(
+            "SELECT id, product_id::text as product_id, claimant_email, status, note, created_at, decided_at \
+             FROM product_claims \
+             WHERE product_id = $1::uuid AND status IN ('approved', 'pending') \
+             ORDER BY (status = 'approved') DESC, id ASC",
+        )
+        .persistent(false)
+        .bind(product_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        if let Some(approved) = existing.iter().find(|c| c.status == "approved") {
+            if approved.claimant_email != claimant_email {
+                return Err(ProductAlreadyClaimedError {
+                    product_id: product_id.to_string(),
                 }
+                .into());
             }
+            return Ok(Some(map_product_claim_row(approved.clone())));
         }
 
-        Err(anyhow::anyhow!(
-            "Failed to create sponsorship request after auto migration"
-        ))
+        let auto_approve = maker_email.trim().eq_ignore_ascii_case(&claimant_email);
+        if !auto_approve {
+            if let Some(pending) = existing.iter().find(|c| c.claimant_email == claimant_email) {
+                return Ok(Some(map_product_claim_row(pending.clone())));
+            }
+        }
+
+        let claim: ProductClaimRow = sqlx::query_as(
+            "INSERT INTO product_claims (product_id, claimant_email, status, note, decided_at) \
+             VALUES ($1::uuid, $2, $3, $4, CASE WHEN $3 = 'approved' THEN NOW() END) \
+             RETURNING id, product_id::text as product_id, claimant_email, status, note, created_at, decided_at",
+        )
+        .persistent(false)
+        .bind(product_id)
+        .bind(claimant_email.as_str())
+        .bind(if auto_approve { "approved" } else { "pending" })
+        .bind(note.as_deref())
+        .fetch_one(&mut *tx)
+        .await?;
+        if auto_approve {
+            sqlx::query(
+                "UPDATE product_claims SET status = 'rejected', decided_at = NOW() \
+                 WHERE product_id = $1::uuid AND status = 'pending'",
+            )
+            .persistent(false)
+            .bind(product_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(Some(map_product_claim_row(claim)))
     }
 
-    pub async fn list_sponsorship_requests(
+    /**
+     * decide_product_claim
+     * 管理员审核 pending 认领：approve 时将 products.maker_email 转给认领人并驳回该产品其余 pending 认领；
+     * 认领不存在返回 None，非 pending 返回 ProductClaimNotPendingError，产品已被他人认领返回 ProductAlreadyClaimedError。
+     */
+    pub async fn decide_product_claim(
         &self,
-        status: Option<&str>,
-        limit: i64,
-        offset: i64,
-    ) -> Result<Vec<SponsorshipRequest>> {
+        claim_id: i64,
+        approve: bool,
+    ) -> Result<Option<ProductClaim>> {
         let pool = self
             .postgres
             .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Postgres is not configured"))?;
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+        ensure_product_claims_table(pool).await?;
 
-        let limit = limit.clamp(1, 200);
-        let offset = offset.max(0);
+        let mut tx = pool.begin().await?;
 
-        let mut last_err: Option<anyhow::Error> = None;
-        for _attempt_idx in 0..2 {
-            let attempt = if let Some(status) = status {
-                let status = strip_nul_str(status.trim());
-                sqlx::query_as::<_, SponsorshipRequestRow>(
-                    "SELECT id, email, product_ref, placement, slot_index, duration_days, note, status, processed_grant_id, created_at, updated_at \
-                     FROM sponsorship_requests \
-                     WHERE status = $1 \
-                     ORDER BY created_at DESC, id DESC \
-                     LIMIT $2 OFFSET $3",
-                )
-                .persistent(false)
-                .bind(status.as_ref())
-                .bind(limit)
-                .bind(offset)
-                .fetch_all(pool)
-                .await
-            } else {
-                sqlx::query_as::<_, SponsorshipRequestRow>(
-                    "SELECT id, email, product_ref, placement, slot_index, duration_days, note, status, processed_grant_id, created_at, updated_at \
-                     FROM sponsorship_requests \
-                     ORDER BY created_at DESC, id DESC \
-                     LIMIT $1 OFFSET $2",
-                )
-                .persistent(false)
-                .bind(limit)
-                .bind(offset)
-                .fetch_all(pool)
-                .await
-            };
+        let claim: Option<ProductClaimRow> = sqlx::query_as(
+            "SELECT id, product_id::text as product_id, claimant_email, status, note, created_at, decided_at \
+             FROM product_claims WHERE id = $1 FOR UPDATE",
+        )
+        .persistent(false)
+        .bind(claim_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(claim) = claim else {
+            return Ok(None);
+        };
+        if claim.status != "pending" {
+            return Err(ProductClaimNotPendingError.into());
+        }
 
-            match attempt {
-                Ok(rows) => return Ok(rows.into_iter().map(map_sponsorship_request_row).collect()),
-                Err(e) => {
-                    let e: anyhow::Error = e.into();
-                    if is_missing_relation_error(&e, "sponsorship_requests")
-                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
-                        && ensure_sponsorship_tables(pool).await.is_ok()
-                    {
-                        continue;
-                    }
-                    last_err = Some(e);
-                    break;
+        if approve {
+            let approved_elsewhere: Option<i64> = sqlx::query_scalar(
+                "SELECT id FROM product_claims \
+                 WHERE product_id = $1::uuid AND status = 'approved' LIMIT 1",
+            )
+            .persistent(false)
+            .bind(claim.product_id.as_str())
+            .fetch_optional(&mut *tx)
+            .await?;
+            if approved_elsewhere.is_some() {
+                return Err(ProductAlreadyClaimedError {
+                    product_id: claim.product_id.clone(),
                 }
+                .into());
             }
+            sqlx::query(
+                "UPDATE products SET maker_email = $2, updated_at = NOW() WHERE id = $1::uuid",
+            )
+            .persistent(false)
+            .bind(claim.product_id.as_str())
+            .bind(claim.claimant_email.as_str())
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query(
+                "UPDATE product_claims SET status = 'rejected', decided_at = NOW() \
+                 WHERE product_id = $1::uuid AND status = 'pending' AND id <> $2",
+            )
+            .persistent(false)
+            .bind(claim.product_id.as_str())
+            .bind(claim_id)
+            .execute(&mut *tx)
+            .await?;
         }
 
-        Err(last_err.unwrap_or_else(|| {
-            anyhow::anyhow!("Failed to list sponsorship requests after auto migration")
-        }))
+        let decided: ProductClaimRow = sqlx::query_as(
+            "UPDATE product_claims SET status = $2, decided_at = NOW() WHERE id = $1 \
+             RETURNING id, product_id::text as product_id, claimant_email, status, note, created_at, decided_at",
+        )
+        .persistent(false)
+        .bind(claim_id)
+        .bind(if approve { "approved" } else { "rejected" })
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(map_product_claim_row(decided)))
+    }
+
+    /**
+     * list_product_claims
+     * 管理端：按状态（可选）列出产品认领，最新在前。
+     */
+    pub async fn list_product_claims(
+        &self,
+        status: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ProductClaim>> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+        ensure_product_claims_table(pool).await?;
+
+        let rows: Vec<ProductClaimRow> = sqlx::query_as(
+            "SELECT id, product_id::text as product_id, claimant_email, status, note, created_at, decided_at \
+             FROM product_claims \
+             WHERE ($1::text IS NULL OR status = $1) \
+             ORDER BY created_at DESC, id DESC \
+             LIMIT $2 OFFSET $3",
+        )
+        .persistent(false)
+        .bind(status)
+        .bind(limit.clamp(1, 200))
+        .bind(offset.max(0))
+        .fetch_all(pool)
+        .await?;
+        Ok(rows.into_iter().map(map_product_claim_row).collect())
+    }
+
+    pub async fn list_outgoing_webhooks(&self) -> Result<Vec<OutgoingWebhook>> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+        ensure_outgoing_webhook_tables(pool).await?;
+
+        let rows = sqlx::query_as::<_, OutgoingWebhookRow>(
+            "SELECT id, url, events, enabled, consecutive_failures, disabled_at, created_at, updated_at \
+             FROM outgoing_webhooks ORDER BY id ASC",
+        )
+        .persistent(false)
+        .fetch_all(pool)
+        .await?;
+        Ok(rows.into_iter().map(map_outgoing_webhook_row).collect())
     }
 
-    pub async fn get_sponsorship_request_by_id(
+    /**
+     * upsert_outgoing_webhook
+     * 新建或更新 webhook 订阅（调用方需先 validate_outgoing_webhook_request）；更新时未传 secret 保留原值，
+     * enabled=true 会清零连续失败计数并重新启用。id 不存在时返回 None。
+     */
+    pub async fn upsert_outgoing_webhook(
         &self,
-        id: i64,
-    ) -> Result<Option<SponsorshipRequest>> {
+        req: crate::models::UpsertOutgoingWebhookRequest,
+    ) -> Result<Option<OutgoingWebhook>> {
         let pool = self
             .postgres
             .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Postgres is not configured"))?;
-
-        let mut last_err: Option<anyhow::Error> = None;
-        for _attempt_idx in 0..2 {
-            let attempt = sqlx::query_as::<_, SponsorshipRequestRow>(
-                "SELECT id, email, product_ref, placement, slot_index, duration_days, note, status, processed_grant_id, created_at, updated_at \
-                 FROM sponsorship_requests \
-                 WHERE id = $1",
-            )
-            .persistent(false)
-            .bind(id)
-            .fetch_optional(pool)
-            .await;
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+        ensure_outgoing_webhook_tables(pool).await?;
 
-            match attempt {
-                Ok(row) => return Ok(row.map(map_sponsorship_request_row)),
-                Err(e) => {
-                    let e: anyhow::Error = e.into();
-                    if is_missing_relation_error(&e, "sponsorship_requests")
-                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
-                        && ensure_sponsorship_tables(pool).await.is_ok()
-                    {
-                        continue;
-                    }
-                    last_err = Some(e);
-                    break;
-                }
+        let url = strip_nul_str(req.url.trim()).into_owned();
+        let secret = req
+            .secret
+            .as_deref()
+            .map(|s| strip_nul_str(s.trim()).into_owned())
+            .filter(|s| !s.is_empty());
+        let mut events: Vec<String> = req.events.iter().map(|e| e.trim().to_string()).collect();
+        events.sort();
+        events.dedup();
+
+        let row = match req.id {
+            None => Some(
+                sqlx::query_as::<_, OutgoingWebhookRow>(
+                    "INSERT INTO outgoing_webhooks (url, secret, events, enabled) \
+                     VALUES ($1, $2, $3, $4) \
+                     RETURNING id, url, events, enabled, consecutive_failures, disabled_at, created_at, updated_at",
+                )
+                .persistent(false)
+                .bind(url.as_str())
+                .bind(secret.as_deref().unwrap_or_default())
+                .bind(&events)
+                .bind(req.enabled.unwrap_or(true))
+                .fetch_one(pool)
+                .await?,
+            ),
+            Some(id) => {
+                sqlx::query_as::<_, OutgoingWebhookRow>(
+                    "UPDATE outgoing_webhooks SET \
+                        url = $2, \
+                        secret = COALESCE($3, secret), \
+                        events = $4, \
+                        enabled = COALESCE($5, enabled), \
+                        consecutive_failures = CASE WHEN $5 IS TRUE THEN 0 ELSE consecutive_failures END, \
+                        disabled_at = CASE WHEN $5 IS TRUE THEN NULL ELSE disabled_at END, \
+                        updated_at = NOW() \
+                     WHERE id = $1 \
+                     RETURNING id, url, events, enabled, consecutive_failures, disabled_at, created_at, updated_at",
+                )
+                .persistent(false)
+                .bind(id)
+                .bind(url.as_str())
+                .bind(secret.as_deref())
+                .bind(&events)
+                .bind(req.enabled)
+                .fetch_optional(pool)
+                .await?
             }
-        }
-
-        Err(last_err.unwrap_or_else(|| {
-            anyhow::anyhow!("Failed to fetch sponsorship request after auto migration")
-        }))
+        };
+        Ok(row.map(map_outgoing_webhook_row))
     }
 
-    pub async fn reject_sponsorship_request(&self, id: i64, note: Option<&str>) -> Result<bool> {
+    pub async fn delete_outgoing_webhook(&self, id: i64) -> Result<bool> {
         let pool = self
             .postgres
             .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Postgres is not configured"))?;
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+        ensure_outgoing_webhook_tables(pool).await?;
 
-        let note = note.map(|v| v.trim()).filter(|v| !v.is_empty());
-        for _attempt_idx in 0..2 {
-            let attempt = sqlx::query(
-                "UPDATE sponsorship_requests \
-                 SET status = 'rejected', note = COALESCE($2, note), updated_at = NOW() \
-                 WHERE id = $1 AND status = 'pending'",
-            )
+        let res = sqlx::query("DELETE FROM outgoing_webhooks WHERE id = $1")
             .persistent(false)
             .bind(id)
-            .bind(note)
             .execute(pool)
-            .await;
+            .await?;
+        Ok(res.rows_affected() > 0)
+    }
 
-            match attempt {
-                Ok(res) => return Ok(res.rows_affected() > 0),
-                Err(e) => {
-                    let e: anyhow::Error = e.into();
-                    if is_missing_relation_error(&e, "sponsorship_requests")
-                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
-                        && ensure_sponsorship_tables(pool).await.is_ok()
-                    {
-                        continue;
+    /**
+     * list_outgoing_webhook_deliveries
+     * 管理端：查看某个订阅最近的投递记录（含尝试次数、最后状态码与错误）。
+     */
+    pub async fn list_outgoing_webhook_deliveries(
+        &self,
+        webhook_id: i64,
+        limit: i64,
+    ) -> Result<Vec<OutgoingWebhookDelivery>> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+        ensure_outgoing_webhook_tables(pool).await?;
+
+        #[derive(sqlx::FromRow)]
+        struct Row {
+            id: i64,
+            webhook_id: i64,
+            event: String,
+            status: String,
+            attempts: i32,
+            last_status_code: Option<i32>,
+            last_error: Option<String>,
+            next_attempt_at: chrono::DateTime<chrono::Utc>,
+            created_at: chrono::DateTime<chrono::Utc>,
+            delivered_at: Option<chrono::DateTime<chrono::Utc>>,
+        }
+
+        let rows = sqlx::query_as::<_, Row>(
+            "SELECT id, webhook_id, event, status, attempts, last_status_code, last_error, \
+                    next_attempt_at, created_at, delivered_at \
+             FROM outgoing_webhook_deliveries \
+             WHERE webhook_id = $1 \
+             ORDER BY created_at DESC, id DESC \
+             LIMIT $2",
+        )
+        .persistent(false)
+        .bind(webhook_id)
+        .bind(limit.clamp(1, 200))
+        .fetch_all(pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|r| OutgoingWebhookDelivery {
+                id: r.id,
+                webhook_id: r.webhook_id,
+                event: r.event,
+                status: r.status,
+                attempts: r.attempts,
+                last_status_code: r.last_status_code,
+                last_error: r.last_error,
+                next_attempt_at: r.next_attempt_at,
+                created_at: r.created_at,
+                delivered_at: r.delivered_at,
+            })
+            .collect())
+    }
+
+    /**
+     * enqueue_product_webhook_event
+     * 为订阅了 event 的已启用 webhook 各写入一条待投递记录（payload 固定后签名，重试时字节不变），返回入队数量。
+     * 未配置 Postgres 时直接跳过。
+     */
+    pub async fn enqueue_product_webhook_event(
+        &self,
+        event: &str,
+        product: &Product,
+    ) -> Result<u64> {
+        let Some(pool) = self.postgres.as_ref() else {
+            return Ok(0);
+        };
+        ensure_outgoing_webhook_tables(pool).await?;
+
+        let payload = serde_json::json!({
+            "event": event,
+            "occurred_at": chrono::Utc::now().to_rfc3339(),
+            "data": { "product": product },
+        })
+        .to_string();
+
+        let res = sqlx::query(
+            "INSERT INTO outgoing_webhook_deliveries (webhook_id, event, payload) \
+             SELECT id, $1, $2 FROM outgoing_webhooks WHERE enabled AND $1 = ANY(events)",
+        )
+        .persistent(false)
+        .bind(event)
+        .bind(strip_nul_str(&payload).as_ref())
+        .execute(pool)
+        .await?;
+        Ok(res.rows_affected())
+    }
+
+    /**
+     * dispatch_due_webhook_deliveries
+     * 后台任务：领取到期的待投递记录（租约 5 分钟，多实例不重复发送），POST 签名后的 JSON；
+     * 失败按指数退避重试，超过最大次数标记 failed；订阅连续失败达到阈值后自动停用。返回本轮成功投递数。
+     */
+    pub async fn dispatch_due_webhook_deliveries(&self, batch: i64) -> Result<usize> {
+        let Some(pool) = self.postgres.as_ref() else {
+            return Ok(0);
+        };
+        ensure_outgoing_webhook_tables(pool).await?;
+
+        #[derive(sqlx::FromRow)]
+        struct Row {
+            id: i64,
+            webhook_id: i64,
+            event: String,
+            payload: String,
+            attempts: i32,
+            url: String,
+            secret: String,
+        }
+
+        let due = sqlx::query_as::<_, Row>(
+            "WITH due AS ( \
+                SELECT d.id FROM outgoing_webhook_deliveries d \
+                JOIN outgoing_webhooks w ON w.id = d.webhook_id \
+                WHERE d.status = 'pending' AND d.next_attempt_at <= NOW() AND w.enabled \
+                ORDER BY d.next_attempt_at ASC \
+                LIMIT $1 \
+                FOR UPDATE OF d SKIP LOCKED \
+             ) \
+             UPDATE outgoing_webhook_deliveries d \
+             SET next_attempt_at = NOW() + INTERVAL '5 minutes' \
+             FROM due, outgoing_webhooks w \
+             WHERE d.id = due.id AND w.id = d.webhook_id \
+             RETURNING d.id, d.webhook_id, d.event, d.payload, d.attempts, w.url, w.secret",
+        )
+        .persistent(false)
+        .bind(batch.clamp(1, 100))
+        .fetch_all(pool)
+        .await?;
+        if due.is_empty() {
+            return Ok(0);
+        }
+
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .http1_only()
+            .build()
+            .unwrap_or_else(|_| Client::new());
+        let disable_after = webhook_disable_after_failures();
+
+        let mut delivered = 0usize;
+        for d in due {
+            let outcome: std::result::Result<i32, (Option<i32>, String)> =
+                match compute_webhook_signature(&d.secret, &d.payload) {
+                    Err(e) => Err((None, e.to_string())),
+                    Ok(signature) => {
+                        let sent = client
+                            .post(&d.url)
+                            .header("Content-Type", "application/json")
+                            .header("X-Signature", signature)
+                            .header("X-Webhook-Event", &d.event)
+                            .header("X-Webhook-Delivery", d.id.to_string())
+                            .body(d.payload.clone())
+                            .send()
+                            .await;
+                        match sent {
+                            Ok(resp) if resp.status().is_success() => {
+                                Ok(resp.status().as_u16() as i32)
+                            }
+                            Ok(resp) => Err((
+                                Some(resp.status().as_u16() as i32),
+                                format!("HTTP {}", resp.status()),
+                            )),
+                            Err(e) => Err((None, e.to_string())),
+                        }
                     }
-                    return Err(e);
+                };
+
+            match outcome {
+                Ok(code) => {
+                    delivered += 1;
+                    sqlx::query(
+                        "UPDATE outgoing_webhook_deliveries SET \
+                            status = 'delivered', attempts = attempts + 1, last_status_code = $2, \
+                            last_error = NULL, delivered_at = NOW() \
+                         WHERE id = $1",
+                    )
+                    .persistent(false)
+                    .bind(d.id)
+                    .bind(code)
+                    .execute(pool)
+                    .await?;
+                    sqlx::query(
+                        "UPDATE outgoing_webhooks SET consecutive_failures = 0 WHERE id = $1",
+                    )
+                    .persistent(false)
+                    .bind(d.webhook_id)
+                    .execute(pool)
+                    .await?;
+                }
+                Err((code, err)) => {
+                    let attempts = d.attempts + 1;
+                    let exhausted = attempts >= OUTGOING_WEBHOOK_MAX_ATTEMPTS;
+                    log::warn!(
+                        "Webhook delivery failed id={} webhook_id={} attempts={} err={}",
+                        d.id,
+                        d.webhook_id,
+                        attempts,
+                        err
+                    );
+                    sqlx::query(
+                        "UPDATE outgoing_webhook_deliveries SET \
+                            status = CASE WHEN $4 THEN 'failed' ELSE 'pending' END, \
+                            attempts = $2, last_status_code = $3, last_error = $5, \
+                            next_attempt_at = NOW() + make_interval(secs => $6) \
+                         WHERE id = $1",
+                    )
+                    .persistent(false)
+                    .bind(d.id)
+                    .bind(attempts)
+                    .bind(code)
+                    .bind(exhausted)
+                    .bind(strip_nul_str(&err).as_ref())
+                    .bind(webhook_retry_delay_secs(attempts) as f64)
+                    .execute(pool)
+                    .await?;
+                    sqlx::query(
+                        "UPDATE outgoing_webhooks SET \
+                            consecutive_failures = consecutive_failures + 1, \
+                            enabled = enabled AND consecutive_failures + 1 < $2, \
+                            disabled_at = CASE \
+                                WHEN enabled AND consecutive_failures + 1 >= $2 THEN NOW() \
+                                ELSE disabled_at END, \
+                            updated_at = NOW() \
+                         WHERE id = $1",
+                    )
+                    .persistent(false)
+                    .bind(d.webhook_id)
+                    .bind(disable_after)
+                    .execute(pool)
+                    .await?;
                 }
             }
         }
 
-        Ok(false)
+        Ok(delivered)
     }
 
-    pub async fn upsert_developer_sponsor(
-        &self,
-        email: &str,
-        sponsor_role: Option<&str>,
-        sponsor_verified: bool,
-    ) -> Result<bool> {
+    /**
+     * get_developer_following_count
+     * 该用户（以邮箱作为 user_id）关注的创作者数量。
+     */
+    pub async fn get_developer_following_count(&self, email: &str) -> Result<i64> {
         let pool = self
             .postgres
             .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Postgres is not configured"))?;
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*)::bigint FROM developer_follows WHERE lower(user_id) = lower($1)",
+        )
+        .persistent(false)
+        .bind(strip_nul_str(email).as_ref())
+        .fetch_one(pool)
+        .await?;
+        Ok(count)
+    }
 
-        let email_lower = email.trim().to_ascii_lowercase();
-        let email_clean = strip_nul_str(email_lower.as_str());
-        let role = sponsor_role.map(|v| strip_nul_str(v.trim()).into_owned());
+    pub async fn follow_developer(&self, email: &str, user_id: &str) -> Result<()> {
+        if let Some(pool) = &self.postgres {
+            let email = strip_nul_str(email);
+            let user_id = strip_nul_str(user_id);
+            sqlx::query(
+                "INSERT INTO developer_follows (developer_email, user_id) \
+                 VALUES ($1, $2) \
+                 ON CONFLICT (developer_email, user_id) DO NOTHING",
+            )
+            .persistent(false)
+            .bind(email.as_ref())
+            .bind(user_id.as_ref())
+            .execute(pool)
+            .await?;
+            return Ok(());
+        }
 
-        for _attempt_idx in 0..2 {
-            let attempt = sqlx::query(
-                "INSERT INTO developers (email, name, sponsor_role, sponsor_verified) \
-                 VALUES ($1, $2, $3, $4) \
-                 ON CONFLICT (email) DO UPDATE SET \
-                    sponsor_role = EXCLUDED.sponsor_role, \
-                    sponsor_verified = EXCLUDED.sponsor_verified, \
-                    updated_at = NOW()",
+        Err(anyhow::anyhow!("No database configured"))
+    }
+
+    pub async fn unfollow_developer(&self, email: &str, user_id: &str) -> Result<()> {
+        if let Some(pool) = &self.postgres {
+            let email = strip_nul_str(email);
+            let user_id = strip_nul_str(user_id);
+            sqlx::query(
+                "DELETE FROM developer_follows \
+                 WHERE developer_email = $1 AND user_id = $2",
             )
             .persistent(false)
-            .bind(email_clean.as_ref())
-            .bind(email_clean.as_ref())
-            .bind(role.as_deref())
-            .bind(sponsor_verified)
+            .bind(email.as_ref())
+            .bind(user_id.as_ref())
             .execute(pool)
-            .await;
+            .await?;
+            return Ok(());
+        }
 
-            match attempt {
-                Ok(res) => return Ok(res.rows_affected() > 0),
-                Err(e) => {
-                    let e: anyhow::Error = e.into();
-                    if (is_missing_column_error(&e, "sponsor_role")
-                        || is_missing_column_error(&e, "sponsor_verified"))
-                        && !DEVELOPERS_SPONSOR_COLUMNS_READY.load(Ordering::Relaxed)
-                        && ensure_developers_sponsor_columns(pool).await.is_ok()
-                    {
-                        continue;
+        Err(anyhow::anyhow!("No database configured"))
+    }
+
+    /**
+     * like_product
+     * 写入一条点赞（重复写入忽略）；ip_hash 为请求 IP 的加盐哈希，仅用于事后的刷量分析。
+     */
+    pub async fn like_product(
+        &self,
+        product_id: &str,
+        user_id: &str,
+        ip_hash: Option<&str>,
+    ) -> Result<()> {
+        if let Some(pool) = &self.postgres {
+            for _attempt_idx in 0..3 {
+                let attempt = sqlx::query(
+                    "INSERT INTO product_likes (product_id, user_id, ip_hash) \
+                     VALUES ($1::uuid, $2, $3) \
+                     ON CONFLICT (product_id, user_id) DO NOTHING",
+                )
+                .persistent(false)
+                .bind(product_id)
+                .bind(user_id)
+                .bind(ip_hash)
+                .execute(pool)
+                .await;
+
+                match attempt {
+                    Ok(_) => return Ok(()),
+                    Err(e) => {
+                        let e: anyhow::Error = e.into();
+                        if is_missing_on_conflict_constraint_error(&e)
+                            && !ENGAGEMENT_UNIQUE_INDEXES_READY.load(Ordering::Relaxed)
+                            && ensure_engagement_unique_indexes(pool).await.is_ok()
+                        {
+                            continue;
+                        }
+                        if is_missing_column_error(&e, "ip_hash")
+                            && !ENGAGEMENT_IP_HASH_READY.load(Ordering::Relaxed)
+                            && ensure_engagement_ip_hash_columns(pool).await.is_ok()
+                        {
+                            continue;
+                        }
+                        return Err(e);
                     }
-                    return Err(e);
                 }
             }
-        }
-
-        Ok(false)
-    }
-
-    pub async fn resolve_product_id_by_ref(&self, product_ref: &str) -> Result<Option<String>> {
-        let pool = self
-            .postgres
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Postgres is not configured"))?;
-
-        let raw = product_ref.trim();
-        if raw.is_empty() {
-            return Ok(None);
-        }
 
-        if let Ok(uuid) = uuid::Uuid::parse_str(raw) {
-            return Ok(Some(uuid.to_string()));
+            return Err(anyhow::anyhow!(
+                "Failed to write product_likes after auto migration"
+            ));
         }
 
-        let q = strip_nul_str(raw);
-        let like = format!("%{}%", q);
+        Err(anyhow::anyhow!("No database configured"))
+    }
 
-        if let Some(row) = sqlx::query_as::<_, (String,)>(
-            "SELECT id::text FROM products WHERE website = $1 ORDER BY created_at DESC, id ASC LIMIT 1",
-        )
-        .persistent(false)
-        .bind(q.as_ref())
-        .fetch_optional(pool)
-        .await?
-        {
-            return Ok(Some(strip_nul_str(&row.0).into_owned()));
+    pub async fn unlike_product(&self, product_id: &str, user_id: &str) -> Result<()> {
+        if let Some(pool) = &self.postgres {
+            sqlx::query(
+                "DELETE FROM product_likes \
+                 WHERE product_id = $1::uuid AND user_id = $2",
+            )
+            .persistent(false)
+            .bind(product_id)
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+            return Ok(());
         }
 
-        let row = sqlx::query_as::<_, (String,)>(
-            "SELECT id::text FROM products WHERE name ILIKE $1 OR website ILIKE $1 ORDER BY created_at DESC, id ASC LIMIT 1",
-        )
-        .persistent(false)
-        .bind(like)
-        .fetch_optional(pool)
-        .await?;
-
-        Ok(row.map(|r| strip_nul_str(&r.0).into_owned()))
+        Err(anyhow::anyhow!("No database configured"))
     }
 
-    pub async fn create_sponsorship_grant_from_request(
+    /**
+     * favorite_product
+     * 写入一条收藏（重复写入忽略）；ip_hash 为请求 IP 的加盐哈希，仅用于事后的刷量分析。
+     */
+    pub async fn favorite_product(
         &self,
-        input: CreateSponsorshipGrantFromRequest,
-    ) -> Result<SponsorshipGrant> {
-        let pool = self
-            .postgres
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Postgres is not configured"))?;
-
-        let mut last_err: Option<anyhow::Error> = None;
-        for _attempt_idx in 0..2 {
-            let mut tx = pool.begin().await?;
-
-            let attempt: Result<SponsorshipGrantFullRow, anyhow::Error> = async {
-                let requested_start = input.starts_at.unwrap_or_else(chrono::Utc::now);
-                let max_end: Option<chrono::DateTime<chrono::Utc>> = sqlx::query_scalar(
-                    "SELECT MAX(ends_at) FROM sponsorship_grants \
-                     WHERE placement = $1 AND slot_index IS NOT DISTINCT FROM $2",
+        product_id: &str,
+        user_id: &str,
+        ip_hash: Option<&str>,
+    ) -> Result<()> {
+        if let Some(pool) = &self.postgres {
+            for _attempt_idx in 0..3 {
+                let attempt = sqlx::query(
+                    "INSERT INTO product_favorites (product_id, user_id, ip_hash) \
+                     VALUES ($1::uuid, $2, $3) \
+                     ON CONFLICT (product_id, user_id) DO NOTHING",
                 )
                 .persistent(false)
-                .bind(strip_nul_str(&input.placement).as_ref())
-                .bind(input.slot_index)
-                .fetch_one(&mut *tx)
-                .await?;
+                .bind(product_id)
+                .bind(user_id)
+                .bind(ip_hash)
+                .execute(pool)
+                .await;
 
-                let starts_at = match max_end {
-                    Some(end) if end > requested_start => end,
-                    _ => requested_start,
-                };
+                match attempt {
+                    Ok(_) => return Ok(()),
+                    Err(e) => {
+                        let e: anyhow::Error = e.into();
+                        if is_missing_on_conflict_constraint_error(&e)
+                            && !ENGAGEMENT_UNIQUE_INDEXES_READY.load(Ordering::Relaxed)
+                            && ensure_engagement_unique_indexes(pool).await.is_ok()
+                        {
+                            continue;
+                        }
+                        if is_missing_column_error(&e, "ip_hash")
+                            && !ENGAGEMENT_IP_HASH_READY.load(Ordering::Relaxed)
+                            && ensure_engagement_ip_hash_columns(pool).await.is_ok()
+                        {
+                            continue;
+                        }
+                        return Err(e);
+                    }
+                }
+            }
 
-                let duration_days = input.duration_days.max(1);
-                let ends_at = starts_at + chrono::Duration::days(duration_days as i64);
+            return Err(anyhow::anyhow!(
+                "Failed to write product_favorites after auto migration"
+            ));
+        }
 
-                let product_id = strip_nul_str(&input.product_id);
-                let placement = strip_nul_str(&input.placement);
+        Err(anyhow::anyhow!("No database configured"))
+    }
 
-                let grant_row = sqlx::query_as::<_, SponsorshipGrantFullRow>(
-                    "INSERT INTO sponsorship_grants (product_id, placement, slot_index, starts_at, ends_at, source, amount_usd_cents) \
-                     VALUES ($1::uuid, $2, $3, $4, $5, 'request', $6) \
-                     RETURNING id, product_id::text as product_id, placement, slot_index, starts_at, ends_at, source, amount_usd_cents, created_at",
-                )
-                .persistent(false)
-                .bind(product_id.as_ref())
-                .bind(placement.as_ref())
-                .bind(input.slot_index)
-                .bind(starts_at)
-                .bind(ends_at)
-                .bind(input.amount_usd_cents)
-                .fetch_one(&mut *tx)
-                .await?;
+    pub async fn unfavorite_product(&self, product_id: &str, user_id: &str) -> Result<()> {
+        if let Some(pool) = &self.postgres {
+            sqlx::query(
+                "DELETE FROM product_favorites \
+                 WHERE product_id = $1::uuid AND user_id = $2",
+            )
+            .persistent(false)
+            .bind(product_id)
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+            return Ok(());
+        }
 
-                let updated = sqlx::query(
-                    "UPDATE sponsorship_requests \
-                     SET status = 'processed', processed_grant_id = $2, updated_at = NOW() \
-                     WHERE id = $1 AND status = 'pending'",
-                )
-                .persistent(false)
-                .bind(input.request_id)
-                .bind(grant_row.id)
-                .execute(&mut *tx)
-                .await?;
+        Err(anyhow::anyhow!("No database configured"))
+    }
 
-                if updated.rows_affected() == 0 {
-                    return Err(anyhow::anyhow!("Sponsorship request is not pending"));
-                }
+    /**
+     * subscribe_newsletter
+     * 订阅周报；format 为 html/text，未指定时沿用已有偏好（新订阅默认 html）。
+     */
+    pub async fn subscribe_newsletter(&self, email: &str, format: Option<&str>) -> Result<()> {
+        let email = strip_nul_str(email);
+        let normalized = email.trim().to_ascii_lowercase();
+        if normalized.is_empty() {
+            return Err(anyhow::anyhow!("Missing email"));
+        }
 
-                Ok(grant_row)
-            }
-            .await;
+        if let Some(pool) = &self.postgres {
+            let mut last_err: Option<anyhow::Error> = None;
+            for _attempt_idx in 0..2 {
+                let attempt = sqlx::query(
+                    "INSERT INTO newsletter_subscriptions (email, unsubscribed, format) \
+                     VALUES ($1, FALSE, COALESCE($2, 'html')) \
+                     ON CONFLICT (email) DO UPDATE SET \
+                        unsubscribed = FALSE, \
+                        format = COALESCE($2, newsletter_subscriptions.format), \
+                        updated_at = NOW()",
+                )
+                .persistent(false)
+                .bind(normalized.as_str())
+                .bind(format)
+                .execute(pool)
+                .await;
 
-            match attempt {
-                Ok(grant_row) => {
-                    tx.commit().await?;
-                    return Ok(map_sponsorship_grant_full_row(grant_row));
-                }
-                Err(e) => {
-                    let _ = tx.rollback().await;
-                    if (is_missing_relation_error(&e, "sponsorship_grants")
-                        || is_missing_relation_error(&e, "sponsorship_requests"))
-                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
-                        && ensure_sponsorship_tables(pool).await.is_ok()
-                    {
-                        continue;
+                match attempt {
+                    Ok(_) => return Ok(()),
+                    Err(e) => {
+                        let e: anyhow::Error = e.into();
+                        if is_missing_column_error(&e, "format")
+                            && !NEWSLETTER_FORMAT_COLUMN_READY.load(Ordering::Relaxed)
+                            && ensure_newsletter_format_column(pool).await.is_ok()
+                        {
+                            continue;
+                        }
+                        last_err = Some(e);
+                        break;
                     }
-                    last_err = Some(e);
-                    break;
                 }
             }
+
+            return Err(last_err.unwrap_or_else(|| {
+                anyhow::anyhow!("Failed to subscribe newsletter after auto migration")
+            }));
         }
 
-        Err(last_err.unwrap_or_else(|| {
-            anyhow::anyhow!("Failed to create sponsorship grant after auto migration")
-        }))
+        Err(anyhow::anyhow!("No database configured"))
     }
 
-    #[allow(clippy::too_many_arguments)]
-    pub async fn create_sponsorship_order(
-        &self,
-        user_email: &str,
-        user_id: Option<&str>,
-        product_id: &str,
-        placement: &str,
-        slot_index: Option<i32>,
-        requested_months: i32,
-        provider: &str,
-        pricing: Option<(&str, &str, Option<i32>, Option<i32>)>,
-    ) -> Result<String> {
-        let pool = self
-            .postgres
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Postgres is not configured"))?;
-
-        let requested_months = requested_months.clamp(1, 24);
-        let id = uuid::Uuid::new_v4();
-        let user_email = strip_nul_str(user_email.trim());
-        let user_id = user_id.map(|v| strip_nul_str(v.trim()).into_owned());
-        let product_id = strip_nul_str(product_id.trim());
-        let placement = strip_nul_str(placement.trim());
-        let pricing_plan_id = pricing
-            .as_ref()
-            .and_then(|(id, _, _, _)| uuid::Uuid::parse_str(id.trim()).ok());
-        let pricing_plan_key = pricing
-            .as_ref()
-            .map(|(_, key, _, _)| strip_nul_str(key.trim()).into_owned())
-            .filter(|v| !v.is_empty());
-        let monthly_usd_cents = pricing.as_ref().and_then(|(_, _, cents, _)| *cents);
-        let discount_percent_off = pricing.as_ref().and_then(|(_, _, _, pct)| *pct);
-        let provider = strip_nul_str(provider.trim());
-
-        let mut last_err: Option<anyhow::Error> = None;
-        for _attempt_idx in 0..2 {
-            let attempt = sqlx::query_as::<_, SponsorshipOrderRow>(
-                "INSERT INTO sponsorship_orders (id, user_email, user_id, product_id, placement, slot_index, requested_months, status, provider, pricing_plan_id, pricing_plan_key, monthly_usd_cents, discount_percent_off) \
-                 VALUES ($1, $2, $3, $4::uuid, $5, $6, $7, 'created', $8, $9, $10, $11, $12) \
-                 RETURNING id, user_email, user_id, product_id::text as product_id, placement, slot_index, requested_months, paid_months, status, provider, provider_checkout_id, provider_order_id, amount_usd_cents, grant_id, created_at, updated_at",
-            )
-            .persistent(false)
-            .bind(id)
-            .bind(user_email.as_ref())
-            .bind(user_id.as_deref())
-            .bind(product_id.as_ref())
-            .bind(placement.as_ref())
-            .bind(slot_index)
-            .bind(requested_months)
-            .bind(provider.as_ref())
-            .bind(pricing_plan_id)
-            .bind(pricing_plan_key.as_deref())
-            .bind(monthly_usd_cents)
-            .bind(discount_percent_off)
-            .fetch_one(pool)
-            .await;
+    pub async fn unsubscribe_newsletter(&self, email: &str) -> Result<()> {
+        let email = strip_nul_str(email);
+        let normalized = email.trim().to_ascii_lowercase();
+        if normalized.is_empty() {
+            return Err(anyhow::anyhow!("Missing email"));
+        }
 
-            match attempt {
-                Ok(row) => {
-                    let (id, _email) = map_sponsorship_order_row(row);
-                    return Ok(id);
-                }
-                Err(e) => {
-                    let e: anyhow::Error = e.into();
-                    if (is_missing_relation_error(&e, "sponsorship_orders")
-                        || is_missing_relation_error(&e, "sponsorship_grants")
-                        || is_missing_relation_error(&e, "sponsorship_requests"))
-                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
-                        && ensure_sponsorship_tables(pool).await.is_ok()
-                    {
-                        continue;
-                    }
-                    last_err = Some(e);
-                    break;
-                }
-            }
+        if let Some(pool) = &self.postgres {
+            sqlx::query(
+                "INSERT INTO newsletter_subscriptions (email, unsubscribed) \
+                 VALUES ($1, TRUE) \
+                 ON CONFLICT (email) DO UPDATE SET \
+                    unsubscribed = TRUE, \
+                    updated_at = NOW()",
+            )
+            .persistent(false)
+            .bind(normalized)
+            .execute(pool)
+            .await?;
+            return Ok(());
         }
 
-        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to create sponsorship order")))
+        Err(anyhow::anyhow!("No database configured"))
     }
 
-    pub async fn set_sponsorship_order_provider_checkout_id(
+    /**
+     * record_newsletter_event
+     * 记录周报打开/点击事件；未知 token 直接忽略（返回 false），不影响像素与跳转响应。
+     */
+    pub async fn record_newsletter_event(
         &self,
-        order_id: &str,
-        provider_checkout_id: &str,
+        send_token: uuid::Uuid,
+        kind: &str,
+        url: Option<&str>,
     ) -> Result<bool> {
         let pool = self
             .postgres
             .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Postgres is not configured"))?;
-
-        let order_id = uuid::Uuid::parse_str(order_id.trim())
-            .map_err(|_| anyhow::anyhow!("Invalid order_id"))?;
-        let provider_checkout_id = strip_nul_str(provider_checkout_id.trim());
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+        let url = url.map(|v| strip_nul_str(v).into_owned());
 
-        let mut last_err: Option<anyhow::Error> = None;
         for _attempt_idx in 0..2 {
             let attempt = sqlx::query(
-                "UPDATE sponsorship_orders SET provider_checkout_id = $2, updated_at = NOW() \
-                 WHERE id = $1",
+                "INSERT INTO newsletter_events (token, kind, url) \
+                 SELECT token, $2, $3 FROM newsletter_sends WHERE token = $1",
             )
             .persistent(false)
-            .bind(order_id)
-            .bind(provider_checkout_id.as_ref())
+            .bind(send_token)
+            .bind(kind)
+            .bind(url.as_deref())
             .execute(pool)
             .await;
 
@@ -3380,281 +12747,126 @@ impl Database {
                 Ok(res) => return Ok(res.rows_affected() > 0),
                 Err(e) => {
                     let e: anyhow::Error = e.into();
-                    if is_missing_relation_error(&e, "sponsorship_orders")
-                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
-                        && ensure_sponsorship_tables(pool).await.is_ok()
+                    if (is_missing_relation_error(&e, "newsletter_events")
+                        || is_missing_relation_error(&e, "newsletter_sends"))
+                        && !NEWSLETTER_TRACKING_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_newsletter_tracking_tables(pool).await.is_ok()
                     {
                         continue;
                     }
-                    last_err = Some(e);
-                    break;
+                    return Err(e);
                 }
             }
         }
 
-        Err(last_err.unwrap_or_else(|| {
-            anyhow::anyhow!("Failed to update sponsorship order after auto migration")
-        }))
+        Err(anyhow::anyhow!(
+            "Failed to record newsletter event after auto migration"
+        ))
     }
 
-    pub async fn get_sponsorship_order_basic(
+    /**
+     * get_newsletter_send_link_hosts
+     * 读取某次周报发送记录的 link_hosts（该期产品官网 host），token 不存在时返回空列表。
+     */
+    pub async fn get_newsletter_send_link_hosts(
         &self,
-        order_id: &str,
-    ) -> Result<
-        Option<(
-            String,
-            String,
-            String,
-            String,
-            Option<i32>,
-            i32,
-            Option<i64>,
-        )>,
-    > {
-        #[derive(sqlx::FromRow)]
-        struct Row {
-            status: String,
-            user_email: String,
-            product_id: String,
-            placement: String,
-            slot_index: Option<i32>,
-            requested_months: i32,
-            grant_id: Option<i64>,
-        }
-
+        send_token: uuid::Uuid,
+    ) -> Result<Vec<String>> {
         let pool = self
             .postgres
             .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Postgres is not configured"))?;
-
-        let order_uuid = uuid::Uuid::parse_str(order_id.trim())
-            .map_err(|_| anyhow::anyhow!("Invalid order_id"))?;
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
 
-        let mut last_err: Option<anyhow::Error> = None;
         for _attempt_idx in 0..2 {
-            let attempt = sqlx::query_as::<_, Row>(
-                "SELECT status, user_email, product_id::text as product_id, placement, slot_index, requested_months, grant_id \
-                 FROM sponsorship_orders WHERE id = $1",
+            let attempt = sqlx::query_scalar::<_, Vec<String>>(
+                "SELECT link_hosts FROM newsletter_sends WHERE token = $1",
             )
             .persistent(false)
-            .bind(order_uuid)
+            .bind(send_token)
             .fetch_optional(pool)
             .await;
 
             match attempt {
-                Ok(Some(mut row)) => {
-                    strip_nul_in_place(&mut row.status);
-                    strip_nul_in_place(&mut row.user_email);
-                    strip_nul_in_place(&mut row.product_id);
-                    strip_nul_in_place(&mut row.placement);
-                    return Ok(Some((
-                        row.status,
-                        row.user_email,
-                        row.product_id,
-                        row.placement,
-                        row.slot_index,
-                        row.requested_months,
-                        row.grant_id,
-                    )));
-                }
-                Ok(None) => return Ok(None),
+                Ok(hosts) => return Ok(hosts.unwrap_or_default()),
                 Err(e) => {
                     let e: anyhow::Error = e.into();
-                    if is_missing_relation_error(&e, "sponsorship_orders")
-                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
-                        && ensure_sponsorship_tables(pool).await.is_ok()
+                    if (is_missing_relation_error(&e, "newsletter_sends")
+                        || is_missing_column_error(&e, "link_hosts"))
+                        && !NEWSLETTER_TRACKING_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_newsletter_tracking_tables(pool).await.is_ok()
                     {
                         continue;
                     }
-                    last_err = Some(e);
-                    break;
+                    return Err(e);
                 }
             }
         }
 
-        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to fetch sponsorship order")))
-    }
-
-    pub async fn create_sponsorship_grant_and_mark_order_paid(
-        &self,
-        order_id: &str,
-        provider_order_id: Option<&str>,
-        amount_usd_cents: i32,
-        paid_months: i32,
-        source: &str,
-    ) -> Result<SponsorshipGrant> {
-        #[derive(sqlx::FromRow)]
-        struct OrderRow {
-            status: String,
-            product_id: String,
-            placement: String,
-            slot_index: Option<i32>,
-            grant_id: Option<i64>,
-        }
-
-        let pool = self
-            .postgres
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Postgres is not configured"))?;
-
-        let order_uuid = uuid::Uuid::parse_str(order_id.trim())
-            .map_err(|_| anyhow::anyhow!("Invalid order_id"))?;
-        let provider_order_id = provider_order_id
-            .map(|v| strip_nul_str(v.trim()).into_owned())
-            .filter(|v| !v.is_empty());
-        let paid_months = paid_months.clamp(1, 120);
-        let duration_days = paid_months.saturating_mul(30).max(1);
-        let source = strip_nul_str(source.trim()).into_owned();
-        if source.is_empty() {
-            return Err(anyhow::anyhow!("Invalid source"));
-        }
-
-        let mut last_err: Option<anyhow::Error> = None;
-        for _attempt_idx in 0..2 {
-            let mut tx = pool.begin().await?;
-
-            let attempt: Result<SponsorshipGrantFullRow, anyhow::Error> = async {
-                let order = sqlx::query_as::<_, OrderRow>(
-                    "SELECT status, product_id::text as product_id, placement, slot_index, grant_id \
-                     FROM sponsorship_orders WHERE id = $1",
-                )
-                .persistent(false)
-                .bind(order_uuid)
-                .fetch_optional(&mut *tx)
-                .await?
-                .ok_or_else(|| anyhow::anyhow!("Sponsorship order not found"))?;
-
-                let slot_index = order.slot_index;
-                let order_grant_id = order.grant_id;
-                let mut status = order.status;
-                let mut product_id = order.product_id;
-                let mut placement = order.placement;
-                strip_nul_in_place(&mut status);
-                strip_nul_in_place(&mut product_id);
-                strip_nul_in_place(&mut placement);
-
-                if status == "paid" {
-                    if let Some(grant_id) = order_grant_id {
-                        return Ok(
-                            sqlx::query_as::<_, SponsorshipGrantFullRow>(
-                                "SELECT id, product_id::text as product_id, placement, slot_index, starts_at, ends_at, source, amount_usd_cents, created_at \
-                                 FROM sponsorship_grants WHERE id = $1",
-                            )
-                            .persistent(false)
-                            .bind(grant_id)
-                            .fetch_one(&mut *tx)
-                            .await?,
-                        );
-                    }
-                } else if status != "created" {
-                    return Err(anyhow::anyhow!(
-                        "Sponsorship order is not payable (status = {})",
-                        status
-                    ));
-                }
-
-                if let Some(existing) = sqlx::query_as::<_, SponsorshipGrantFullRow>(
-                    "SELECT id, product_id::text as product_id, placement, slot_index, starts_at, ends_at, source, amount_usd_cents, created_at \
-                     FROM sponsorship_grants WHERE order_id = $1",
-                )
-                .persistent(false)
-                .bind(order_uuid)
-                .fetch_optional(&mut *tx)
-                .await?
-                {
-                    let _ = sqlx::query(
-                        "UPDATE sponsorship_orders \
-                         SET status = 'paid', provider_order_id = $2, amount_usd_cents = $3, paid_months = $4, grant_id = $5, updated_at = NOW() \
-                         WHERE id = $1 AND status IN ('created', 'paid')",
-                    )
-                    .persistent(false)
-                    .bind(order_uuid)
-                    .bind(provider_order_id.as_deref())
-                    .bind(amount_usd_cents)
-                    .bind(paid_months)
-                    .bind(existing.id)
-                    .execute(&mut *tx)
-                    .await?;
-                    return Ok(existing);
-                }
-
-                let max_end: Option<chrono::DateTime<chrono::Utc>> = sqlx::query_scalar(
-                    "SELECT MAX(ends_at) FROM sponsorship_grants \
-                     WHERE placement = $1 AND slot_index IS NOT DISTINCT FROM $2",
-                )
-                .persistent(false)
-                .bind(placement.as_str())
-                .bind(slot_index)
-                .fetch_one(&mut *tx)
-                .await?;
-
-                let requested_start = chrono::Utc::now();
-                let starts_at = match max_end {
-                    Some(end) if end > requested_start => end,
-                    _ => requested_start,
-                };
-                let ends_at = starts_at + chrono::Duration::days(duration_days as i64);
-
-                let inserted = sqlx::query_as::<_, SponsorshipGrantFullRow>(
-                    "INSERT INTO sponsorship_grants (order_id, product_id, placement, slot_index, starts_at, ends_at, source, amount_usd_cents) \
-                     VALUES ($1, $2::uuid, $3, $4, $5, $6, $7, $8) \
-                     RETURNING id, product_id::text as product_id, placement, slot_index, starts_at, ends_at, source, amount_usd_cents, created_at",
-                )
-                .persistent(false)
-                .bind(order_uuid)
-                .bind(product_id.as_str())
-                .bind(placement.as_str())
-                .bind(slot_index)
-                .bind(starts_at)
-                .bind(ends_at)
-                .bind(source.as_str())
-                .bind(amount_usd_cents)
-                .fetch_one(&mut *tx)
-                .await?;
-
-                let updated = sqlx::query(
-                    "UPDATE sponsorship_orders \
-                     SET status = 'paid', provider_order_id = $2, amount_usd_cents = $3, paid_months = $4, grant_id = $5, updated_at = NOW() \
-                     WHERE id = $1 AND status IN ('created', 'paid')",
-                )
-                .persistent(false)
-                .bind(order_uuid)
-                .bind(provider_order_id.as_deref())
-                .bind(amount_usd_cents)
-                .bind(paid_months)
-                .bind(inserted.id)
-                .execute(&mut *tx)
-                .await?;
-
-                if updated.rows_affected() == 0 {
-                    if let Some(existing) = sqlx::query_as::<_, SponsorshipGrantFullRow>(
-                        "SELECT id, product_id::text as product_id, placement, slot_index, starts_at, ends_at, source, amount_usd_cents, created_at \
-                         FROM sponsorship_grants WHERE order_id = $1",
-                    )
-                    .persistent(false)
-                    .bind(order_uuid)
-                    .fetch_optional(&mut *tx)
-                    .await?
-                    {
-                        return Ok(existing);
-                    }
-                }
+        Err(anyhow::anyhow!(
+            "Failed to load newsletter link hosts after auto migration"
+        ))
+    }
 
-                Ok(inserted)
-            }
+    /**
+     * get_newsletter_tracking_stats
+     * 管理端：按周汇总周报发送数、独立打开/点击数与总点击数（最近 weeks 周）。
+     */
+    pub async fn get_newsletter_tracking_stats(
+        &self,
+        weeks: i64,
+    ) -> Result<Vec<NewsletterWeekStats>> {
+        #[derive(sqlx::FromRow)]
+        struct Row {
+            week_key: String,
+            sends: i64,
+            unique_opens: i64,
+            unique_clicks: i64,
+            total_clicks: i64,
+        }
+
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let mut last_err: Option<anyhow::Error> = None;
+        for _attempt_idx in 0..2 {
+            let attempt = sqlx::query_as::<_, Row>(
+                "SELECT s.week_key, \
+                    COUNT(DISTINCT s.token)::bigint AS sends, \
+                    COUNT(DISTINCT e.token) FILTER (WHERE e.kind = 'open')::bigint AS unique_opens, \
+                    COUNT(DISTINCT e.token) FILTER (WHERE e.kind = 'click')::bigint AS unique_clicks, \
+                    COUNT(e.id) FILTER (WHERE e.kind = 'click')::bigint AS total_clicks \
+                 FROM newsletter_sends s \
+                 LEFT JOIN newsletter_events e ON e.token = s.token \
+                 GROUP BY s.week_key \
+                 ORDER BY s.week_key DESC \
+                 LIMIT $1",
+            )
+            .persistent(false)
+            .bind(weeks.clamp(1, 104))
+            .fetch_all(pool)
             .await;
 
             match attempt {
-                Ok(grant_row) => {
-                    tx.commit().await?;
-                    return Ok(map_sponsorship_grant_full_row(grant_row));
+                Ok(rows) => {
+                    return Ok(rows
+                        .into_iter()
+                        .map(|r| NewsletterWeekStats {
+                            week_key: strip_nul_str(&r.week_key).into_owned(),
+                            sends: r.sends,
+                            unique_opens: r.unique_opens,
+                            unique_clicks: r.unique_clicks,
+                            total_clicks: r.total_clicks,
+                        })
+                        .collect())
                 }
                 Err(e) => {
-                    let _ = tx.rollback().await;
-                    if (is_missing_relation_error(&e, "sponsorship_grants")
-                        || is_missing_relation_error(&e, "sponsorship_orders"))
-                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
-                        && ensure_sponsorship_tables(pool).await.is_ok()
+                    let e: anyhow::Error = e.into();
+                    if (is_missing_relation_error(&e, "newsletter_events")
+                        || is_missing_relation_error(&e, "newsletter_sends"))
+                        && !NEWSLETTER_TRACKING_TABLES_READY.load(Ordering::Relaxed)
+                        && ensure_newsletter_tracking_tables(pool).await.is_ok()
                     {
                         continue;
                     }
@@ -3664,155 +12876,360 @@ impl Database {
             }
         }
 
-        Err(last_err.unwrap_or_else(|| {
-            anyhow::anyhow!("Failed to create sponsorship grant from paid order")
-        }))
+        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to get newsletter stats")))
     }
 
-    pub async fn admin_mark_sponsorship_order_paid(
+    /**
+     * resubscribe_newsletter
+     * 将已退订的订阅记录恢复为订阅状态（保留原有格式偏好与发送历史）；记录不存在时返回 false。
+     */
+    pub async fn resubscribe_newsletter(&self, email: &str) -> Result<bool> {
+        let email = strip_nul_str(email);
+        let normalized = email.trim().to_ascii_lowercase();
+        if normalized.is_empty() {
+            return Err(anyhow::anyhow!("Missing email"));
+        }
+
+        if let Some(pool) = &self.postgres {
+            let res = sqlx::query(
+                "UPDATE newsletter_subscriptions \
+                 SET unsubscribed = FALSE, updated_at = NOW() \
+                 WHERE email = $1",
+            )
+            .persistent(false)
+            .bind(normalized)
+            .execute(pool)
+            .await?;
+            return Ok(res.rows_affected() > 0);
+        }
+
+        Err(anyhow::Error::new(PostgresNotConfiguredError))
+    }
+
+    /**
+     * send_admin_product_submission_notification
+     * 产品提交后给管理员发送通知邮件（可选：包含一键通过/拒绝链接）。
+     */
+    pub async fn send_admin_product_submission_notification(
         &self,
-        order_id: &str,
-        provider_order_id: Option<&str>,
-        amount_usd_cents: Option<i32>,
-        paid_months: Option<i32>,
-    ) -> Result<SponsorshipGrant> {
-        #[derive(sqlx::FromRow)]
-        struct OrderPricingRow {
-            status: String,
-            provider: String,
-            requested_months: i32,
-            monthly_usd_cents: Option<i32>,
-            discount_percent_off: Option<i32>,
+        product: &Product,
+    ) -> Result<()> {
+        let resend_key = env::var("RESEND_API_KEY").ok().unwrap_or_default();
+        if resend_key.trim().is_empty() {
+            return Ok(());
+        }
+
+        let recipients = admin_notify_recipients();
+        if recipients.is_empty() {
+            return Ok(());
         }
+        let Some(from) = admin_notify_from() else {
+            return Ok(());
+        };
+
+        let token_secret = env::var("ADMIN_REVIEW_TOKEN_SECRET")
+            .ok()
+            .unwrap_or_default();
+        let frontend_base_url = env::var("FRONTEND_BASE_URL")
+            .ok()
+            .unwrap_or_else(|| "http://localhost:3000".to_string());
+        let public_api_base_url = env::var("BACKEND_PUBLIC_URL")
+            .ok()
+            .unwrap_or_else(|| "http://localhost:8080".to_string());
+
+        let client = resend_http_client();
+
+        let (subject, html, text) = build_admin_product_submission_email_content(
+            product,
+            &frontend_base_url,
+            &public_api_base_url,
+            &token_secret,
+        );
+
+        send_admin_notify_emails(
+            &client,
+            &resend_key,
+            &from,
+            &recipients,
+            &subject,
+            &html,
+            &text,
+        )
+        .await
+    }
 
+    /**
+     * claim_sponsorship_request_admin_notification
+     * 抢占新赞助申请的管理员通知发送权（admin_notified_at 为空时置为当前时间并返回申请），已通知过返回 None。
+     */
+    async fn claim_sponsorship_request_admin_notification(
+        &self,
+        request_id: i64,
+    ) -> Result<Option<SponsorshipRequest>> {
         let pool = self
             .postgres
             .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Postgres is not configured"))?;
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
 
-        let order_uuid = uuid::Uuid::parse_str(order_id.trim())
-            .map_err(|_| anyhow::anyhow!("Invalid order_id"))?;
+        for _attempt_idx in 0..2 {
+            let attempt = sqlx::query_as::<_, SponsorshipRequestRow>(
+                "UPDATE sponsorship_requests SET admin_notified_at = NOW() \
+                 WHERE id = $1 AND admin_notified_at IS NULL \
+                 RETURNING id, email, product_ref, placement, slot_index, duration_days, note, status, processed_grant_id, created_at, updated_at",
+            )
+            .persistent(false)
+            .bind(request_id)
+            .fetch_optional(pool)
+            .await;
 
-        let row = sqlx::query_as::<_, OrderPricingRow>(
-            "SELECT status, provider, requested_months, monthly_usd_cents, discount_percent_off \
-             FROM sponsorship_orders WHERE id = $1",
-        )
-        .persistent(false)
-        .bind(order_uuid)
-        .fetch_optional(pool)
-        .await?
-        .ok_or_else(|| anyhow::anyhow!("Sponsorship order not found"))?;
+            match attempt {
+                Ok(row) => return Ok(row.map(map_sponsorship_request_row)),
+                Err(e) => {
+                    let e: anyhow::Error = e.into();
+                    if (is_missing_column_error(&e, "admin_notified_at")
+                        || is_missing_relation_error(&e, "sponsorship_requests"))
+                        && !SPONSORSHIP_REQUEST_NOTIFY_READY.load(Ordering::Relaxed)
+                        && ensure_sponsorship_request_notify_column(pool).await.is_ok()
+                    {
+                        continue;
+                    }
+                    return Err(e);
+                }
+            }
+        }
 
-        let mut status = row.status;
-        let mut provider = row.provider;
-        strip_nul_in_place(&mut status);
-        strip_nul_in_place(&mut provider);
+        Err(anyhow::anyhow!(
+            "Failed to claim sponsorship request notification after auto migration"
+        ))
+    }
 
-        if status == "paid" {
-            let months = paid_months.unwrap_or(row.requested_months).clamp(1, 120);
-            let computed_amount = {
-                let unit = row.monthly_usd_cents.unwrap_or(0) as i64;
-                let gross = unit.saturating_mul(months as i64);
-                let pct = row.discount_percent_off.unwrap_or(0).clamp(0, 100) as i64;
-                let discount = gross.saturating_mul(pct) / 100;
-                let net = gross.saturating_sub(discount).max(0);
-                i32::try_from(net.min(i32::MAX as i64)).unwrap_or(i32::MAX)
-            };
-            let amount = amount_usd_cents.unwrap_or(computed_amount);
-            return self
-                .create_sponsorship_grant_and_mark_order_paid(
-                    order_id,
-                    provider_order_id,
-                    amount,
-                    months,
-                    provider.as_str(),
+    /**
+     * send_admin_sponsorship_request_notification
+     * 新赞助申请创建后通知 ADMIN_NOTIFY_EMAILS（附自动匹配的产品与后台审核链接）；
+     * 通过 admin_notified_at 保证同一申请只通知一次，全部发送失败时释放标记以便重试。返回是否实际发送。
+     * 标记在发送前抢占（至多一次）：进程恰好在抢占与发送之间退出时该通知会丢失，
+     * 以此换取多实例并发下不重复发送；管理后台的待处理列表仍会展示该申请。
+     */
+    pub async fn send_admin_sponsorship_request_notification(
+        &self,
+        request_id: i64,
+    ) -> Result<bool> {
+        let resend_key = env::var("RESEND_API_KEY").ok().unwrap_or_default();
+        if resend_key.trim().is_empty() {
+            return Ok(false);
+        }
+        let recipients = admin_notify_recipients();
+        if recipients.is_empty() {
+            return Ok(false);
+        }
+        let Some(from) = admin_notify_from() else {
+            return Ok(false);
+        };
+
+        let Some(request) = self
+            .claim_sponsorship_request_admin_notification(request_id)
+            .await?
+        else {
+            return Ok(false);
+        };
+
+        let product = match self.resolve_product_id_by_ref(&request.product_ref).await {
+            Ok(Some(id)) => self.get_product_by_id(&id).await.unwrap_or_else(|e| {
+                log::warn!(
+                    "Sponsorship request {} product lookup failed: {:?}",
+                    request_id,
+                    e
+                );
+                None
+            }),
+            Ok(None) => None,
+            Err(e) => {
+                log::warn!(
+                    "Sponsorship request {} product_ref resolve failed: {:?}",
+                    request_id,
+                    e
+                );
+                None
+            }
+        };
+
+        let frontend_base_url = env::var("FRONTEND_BASE_URL")
+            .ok()
+            .unwrap_or_else(|| "http://localhost:3000".to_string());
+        let review_url = build_admin_sponsorship_request_review_url(
+            env::var("ADMIN_APP_URL").ok().as_deref(),
+            &frontend_base_url,
+            request.id,
+        );
+        let (subject, html, text) =
+            build_admin_sponsorship_request_email_content(&request, product.as_ref(), &review_url);
+
+        let client = resend_http_client();
+        if let Err(e) = send_admin_notify_emails(
+            &client,
+            &resend_key,
+            &from,
+            &recipients,
+            &subject,
+            &html,
+            &text,
+        )
+        .await
+        {
+            // 发送失败时释放抢占，后续重试可以重新通知
+            if let Some(pool) = &self.postgres {
+                let _ = sqlx::query(
+                    "UPDATE sponsorship_requests SET admin_notified_at = NULL WHERE id = $1",
                 )
+                .persistent(false)
+                .bind(request_id)
+                .execute(pool)
                 .await;
+            }
+            return Err(e);
         }
+        Ok(true)
+    }
 
-        if status != "created" {
-            return Err(anyhow::anyhow!(
-                "Sponsorship order is not payable (status = {})",
-                status
-            ));
+    /**
+     * send_maker_product_review_notification
+     * 产品审核状态变更为通过/拒绝时，给提交者发送通知邮件（拒绝包含理由）。
+     */
+    pub async fn send_maker_product_review_notification(&self, product: &Product) -> Result<()> {
+        let resend_key = env::var("RESEND_API_KEY").ok().unwrap_or_default();
+        if resend_key.trim().is_empty() {
+            return Ok(());
         }
 
-        let months = paid_months.unwrap_or(row.requested_months).clamp(1, 120);
-        let computed_amount = {
-            let unit = row.monthly_usd_cents.unwrap_or(0) as i64;
-            let gross = unit.saturating_mul(months as i64);
-            let pct = row.discount_percent_off.unwrap_or(0).clamp(0, 100) as i64;
-            let discount = gross.saturating_mul(pct) / 100;
-            let net = gross.saturating_sub(discount).max(0);
-            i32::try_from(net.min(i32::MAX as i64)).unwrap_or(i32::MAX)
-        };
-        let amount = amount_usd_cents.unwrap_or(computed_amount);
+        let to = product.maker_email.trim().to_string();
+        if to.is_empty() {
+            return Ok(());
+        }
+
+        let from = env::var("PRODUCT_REVIEW_FROM")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .or_else(|| {
+                env::var("ADMIN_REVIEW_FROM")
+                    .ok()
+                    .filter(|v| !v.trim().is_empty())
+            })
+            .or_else(|| {
+                env::var("NEWSLETTER_FROM")
+                    .ok()
+                    .filter(|v| !v.trim().is_empty())
+            })
+            .unwrap_or_default();
+        if from.trim().is_empty() {
+            log::warn!("Maker review sender not configured: PRODUCT_REVIEW_FROM/ADMIN_REVIEW_FROM/NEWSLETTER_FROM missing");
+            return Ok(());
+        }
+
+        let frontend_base_url = env::var("FRONTEND_BASE_URL")
+            .ok()
+            .unwrap_or_else(|| "http://localhost:3000".to_string());
+
+        let client = resend_http_client();
+
+        let (subject, html, text) =
+            build_maker_product_review_email_content(product, &frontend_base_url);
+        send_email_resend(
+            &client,
+            &resend_key,
+            &from,
+            &to,
+            &subject,
+            Some(&html),
+            &text,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /**
+     * send_sponsorship_order_receipt
+     * 订单标记为已支付后给下单邮箱发送收据；通过 receipt_sent_at 抢占发送权，重复回调不会重复发送。
+     * 返回是否实际发送。
+     */
+    pub async fn send_sponsorship_order_receipt(
+        &self,
+        order_id: &str,
+        grant: &SponsorshipGrant,
+    ) -> Result<bool> {
+        #[derive(sqlx::FromRow)]
+        struct ReceiptRow {
+            user_email: String,
+            placement: String,
+            months: i32,
+            amount_usd_cents: Option<i32>,
+            product_name: String,
+            product_language: Option<String>,
+        }
+
+        let resend_key = env::var("RESEND_API_KEY").ok().unwrap_or_default();
+        if resend_key.trim().is_empty() {
+            return Ok(false);
+        }
 
-        self.create_sponsorship_grant_and_mark_order_paid(
-            order_id,
-            provider_order_id,
-            amount,
-            months,
-            provider.as_str(),
-        )
-        .await
-    }
+        let from = env::var("SPONSORSHIP_RECEIPT_FROM")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .or_else(|| {
+                env::var("PRODUCT_REVIEW_FROM")
+                    .ok()
+                    .filter(|v| !v.trim().is_empty())
+            })
+            .or_else(|| {
+                env::var("ADMIN_REVIEW_FROM")
+                    .ok()
+                    .filter(|v| !v.trim().is_empty())
+            })
+            .or_else(|| {
+                env::var("NEWSLETTER_FROM")
+                    .ok()
+                    .filter(|v| !v.trim().is_empty())
+            })
+            .unwrap_or_default();
+        if from.trim().is_empty() {
+            log::warn!("Sponsorship receipt sender not configured: SPONSORSHIP_RECEIPT_FROM/PRODUCT_REVIEW_FROM/ADMIN_REVIEW_FROM/NEWSLETTER_FROM missing");
+            return Ok(false);
+        }
 
-    pub async fn list_sponsorship_grants(
-        &self,
-        placement: Option<&str>,
-        limit: i64,
-        offset: i64,
-    ) -> Result<Vec<SponsorshipGrant>> {
         let pool = self
             .postgres
             .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Postgres is not configured"))?;
-
-        let limit = limit.clamp(1, 200);
-        let offset = offset.max(0);
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+        let order_uuid = uuid::Uuid::parse_str(order_id.trim())
+            .map_err(|_| anyhow::anyhow!("Invalid order_id"))?;
 
+        let mut claimed: Option<ReceiptRow> = None;
         let mut last_err: Option<anyhow::Error> = None;
         for _attempt_idx in 0..2 {
-            let attempt = if let Some(placement) = placement {
-                let placement = strip_nul_str(placement.trim());
-                sqlx::query_as::<_, SponsorshipGrantFullRow>(
-                    "SELECT id, product_id::text as product_id, placement, slot_index, starts_at, ends_at, source, amount_usd_cents, created_at \
-                     FROM sponsorship_grants \
-                     WHERE placement = $1 \
-                     ORDER BY starts_at DESC, id DESC \
-                     LIMIT $2 OFFSET $3",
-                )
-                .persistent(false)
-                .bind(placement.as_ref())
-                .bind(limit)
-                .bind(offset)
-                .fetch_all(pool)
-                .await
-            } else {
-                sqlx::query_as::<_, SponsorshipGrantFullRow>(
-                    "SELECT id, product_id::text as product_id, placement, slot_index, starts_at, ends_at, source, amount_usd_cents, created_at \
-                     FROM sponsorship_grants \
-                     ORDER BY starts_at DESC, id DESC \
-                     LIMIT $1 OFFSET $2",
-                )
-                .persistent(false)
-                .bind(limit)
-                .bind(offset)
-                .fetch_all(pool)
-                .await
-            };
+            let attempt = sqlx::query_as::<_, ReceiptRow>(
+                "WITH claimed AS ( \
+                    UPDATE sponsorship_orders SET receipt_sent_at = NOW() \
+                    WHERE id = $1 AND status = 'paid' AND receipt_sent_at IS NULL \
+                    RETURNING user_email, product_id, placement, \
+                        COALESCE(paid_months, requested_months) AS months, amount_usd_cents \
+                 ) \
+                 SELECT c.user_email, c.placement, c.months, c.amount_usd_cents, \
+                    COALESCE(p.name, '') AS product_name, p.language AS product_language \
+                 FROM claimed c LEFT JOIN products p ON p.id = c.product_id",
+            )
+            .persistent(false)
+            .bind(order_uuid)
+            .fetch_optional(pool)
+            .await;
 
             match attempt {
-                Ok(rows) => {
-                    return Ok(rows
-                        .into_iter()
-                        .map(map_sponsorship_grant_full_row)
-                        .collect())
+                Ok(row) => {
+                    claimed = row;
+                    last_err = None;
+                    break;
                 }
                 Err(e) => {
                     let e: anyhow::Error = e.into();
-                    if is_missing_relation_error(&e, "sponsorship_grants")
+                    if is_missing_column_error(&e, "receipt_sent_at")
                         && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
                         && ensure_sponsorship_tables(pool).await.is_ok()
                     {
@@ -3823,2739 +13240,4352 @@ impl Database {
                 }
             }
         }
+        if let Some(e) = last_err {
+            return Err(e);
+        }
+        let Some(mut row) = claimed else {
+            return Ok(false);
+        };
+        strip_nul_in_place(&mut row.user_email);
+        strip_nul_in_place(&mut row.placement);
+        strip_nul_in_place(&mut row.product_name);
 
-        Err(last_err.unwrap_or_else(|| {
-            anyhow::anyhow!("Failed to list sponsorship grants after auto migration")
-        }))
+        let to = row.user_email.trim().to_string();
+        if to.is_empty() {
+            return Ok(false);
+        }
+
+        let is_zh = row
+            .product_language
+            .as_deref()
+            .unwrap_or("")
+            .trim()
+            .to_ascii_lowercase()
+            .starts_with("zh");
+        let order_id = order_uuid.to_string();
+        let receipt = SponsorshipReceipt {
+            order_id: &order_id,
+            product_name: &row.product_name,
+            placement: &row.placement,
+            months: row.months,
+            amount_usd_cents: row.amount_usd_cents.or(grant.amount_usd_cents).unwrap_or(0),
+            starts_at: grant.starts_at,
+            ends_at: grant.ends_at,
+        };
+        let (subject, html, text) = build_sponsorship_receipt_email_content(&receipt, is_zh);
+
+        let client = resend_http_client();
+
+        if let Err(e) = send_email_resend(
+            &client,
+            &resend_key,
+            &from,
+            &to,
+            &subject,
+            Some(&html),
+            &text,
+        )
+        .await
+        {
+            // 发送失败时释放抢占，后续重放回调可以重新发送
+            let _ =
+                sqlx::query("UPDATE sponsorship_orders SET receipt_sent_at = NULL WHERE id = $1")
+                    .persistent(false)
+                    .bind(order_uuid)
+                    .execute(pool)
+                    .await;
+            return Err(e);
+        }
+        Ok(true)
     }
 
-    pub async fn delete_sponsorship_grant(&self, id: i64) -> Result<bool> {
-        let pool = self
-            .postgres
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Postgres is not configured"))?;
+    pub async fn send_weekly_newsletter_if_due(&self) -> Result<usize> {
+        let report = self
+            .run_weekly_newsletter(NewsletterRunOptions {
+                ignore_schedule: false,
+                dry_run: false,
+            })
+            .await?;
+        Ok(report.sent)
+    }
 
-        let mut last_err: Option<anyhow::Error> = None;
+    /**
+     * run_weekly_newsletter
+     * 周报发送主流程：ignore_schedule 跳过“周四 8~10 点”的时间窗（管理员手动触发）；
+     * dry_run 只渲染并统计收件人，不调用 Resend、不写追踪记录，也不更新 last_sent_week。
+     */
+    pub async fn run_weekly_newsletter(
+        &self,
+        opts: NewsletterRunOptions,
+    ) -> Result<NewsletterRunReport> {
+        let pool = match &self.postgres {
+            Some(v) => v,
+            None => return Ok(NewsletterRunReport::default()),
+        };
+
+        let now = chrono::Utc::now();
+        if !opts.ignore_schedule {
+            if now.weekday() != chrono::Weekday::Thu {
+                return Ok(NewsletterRunReport::default());
+            }
+            let hour = now.hour();
+            if !(8..10).contains(&hour) {
+                return Ok(NewsletterRunReport::default());
+            }
+        }
+
+        let resend_key = env::var("RESEND_API_KEY").ok().unwrap_or_default();
+        let from = env::var("NEWSLETTER_FROM").ok().unwrap_or_default();
+        if !opts.dry_run && (resend_key.trim().is_empty() || from.trim().is_empty()) {
+            log::warn!("Newsletter sender not configured: RESEND_API_KEY/NEWSLETTER_FROM missing");
+            return Ok(NewsletterRunReport::default());
+        }
+
+        let iso = now.iso_week();
+        let week_key = format!("{}-W{:02}", iso.year(), iso.week());
+
+        let mut conn = pool.acquire().await?;
+        let lock_key: i64 = 9_876_543_210;
+        let locked = sqlx::query_scalar::<_, bool>("SELECT pg_try_advisory_lock($1)")
+            .persistent(false)
+            .bind(lock_key)
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap_or(false);
+        if !locked {
+            return Ok(NewsletterRunReport::default());
+        }
+
+        let since = now - chrono::Duration::days(7);
+        let (like_weight, favorite_weight) = newsletter_score_weights();
+        let products = sqlx::query_as::<_, NewsletterTopProductRow>(
+            "WITH likes AS ( \
+                SELECT product_id, COUNT(*)::bigint as likes \
+                FROM product_likes \
+                WHERE created_at >= $1 \
+                GROUP BY product_id \
+             ), favorites AS ( \
+                SELECT product_id, COUNT(*)::bigint as favorites \
+                FROM product_favorites \
+                WHERE created_at >= $1 \
+                GROUP BY product_id \
+             ) \
+             SELECT \
+                p.id::text as id, \
+                p.name, \
+                p.slogan, \
+                p.website, \
+                p.logo_url, \
+                p.maker_name, \
+                p.maker_email, \
+                COALESCE(l.likes, 0)::bigint as weekly_likes, \
+                COALESCE(f.favorites, 0)::bigint as weekly_favorites, \
+                (COALESCE(l.likes, 0) * $3 + COALESCE(f.favorites, 0) * $4)::bigint as score \
+             FROM products p \
+             LEFT JOIN likes l ON l.product_id = p.id \
+             LEFT JOIN favorites f ON f.product_id = p.id \
+             WHERE p.status = 'approved' \
+             ORDER BY score DESC, p.created_at DESC \
+             LIMIT $2",
+        )
+        .persistent(false)
+        .bind(since)
+        .bind(newsletter_top_n())
+        .bind(like_weight)
+        .bind(favorite_weight)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        #[derive(sqlx::FromRow)]
+        struct RecipientRow {
+            email: String,
+            format: Option<String>,
+            language: Option<String>,
+        }
+
+        let mut recipients: Vec<RecipientRow> = Vec::new();
         for _attempt_idx in 0..2 {
-            let attempt = sqlx::query("DELETE FROM sponsorship_grants WHERE id = $1")
-                .persistent(false)
-                .bind(id)
-                .execute(pool)
-                .await;
+            let attempt = sqlx::query_as::<_, RecipientRow>(
+                "SELECT s.email, s.format, \
+                    (SELECT p.language FROM products p \
+                     WHERE LOWER(p.maker_email) = LOWER(s.email) \
+                     ORDER BY p.created_at DESC LIMIT 1) AS language \
+                 FROM newsletter_subscriptions s \
+                 WHERE s.unsubscribed = FALSE AND (s.last_sent_week IS DISTINCT FROM $1) \
+                 ORDER BY s.created_at ASC \
+                 LIMIT 1000",
+            )
+            .persistent(false)
+            .bind(&week_key)
+            .fetch_all(&mut *conn)
+            .await;
 
             match attempt {
-                Ok(res) => return Ok(res.rows_affected() > 0),
+                Ok(rows) => {
+                    recipients = rows;
+                    break;
+                }
                 Err(e) => {
                     let e: anyhow::Error = e.into();
-                    if is_missing_relation_error(&e, "sponsorship_grants")
-                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
-                        && ensure_sponsorship_tables(pool).await.is_ok()
+                    if is_missing_column_error(&e, "format")
+                        && !NEWSLETTER_FORMAT_COLUMN_READY.load(Ordering::Relaxed)
+                        && ensure_newsletter_format_column(pool).await.is_ok()
                     {
                         continue;
                     }
-                    last_err = Some(e);
-                    break;
+                    let _ = sqlx::query("SELECT pg_advisory_unlock($1)")
+                        .persistent(false)
+                        .bind(lock_key)
+                        .execute(&mut *conn)
+                        .await;
+                    return Err(e);
                 }
             }
         }
 
-        Err(last_err.unwrap_or_else(|| {
-            anyhow::anyhow!("Failed to delete sponsorship grant after auto migration")
-        }))
-    }
-
-    /**
-     * list_pricing_plans
-     * 读取定价方案列表（包含权益明细）。
-     */
-    pub async fn list_pricing_plans(&self, include_inactive: bool) -> Result<Vec<PricingPlan>> {
-        let pool = self
-            .postgres
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Postgres is not configured"))?;
-
-        let _ = ensure_pricing_text_migration(pool).await;
-
-        let mut last_err: Option<anyhow::Error> = None;
-        for _attempt_idx in 0..2 {
-            let attempt: Result<Vec<PricingPlan>, anyhow::Error> = async {
-                let plans = if include_inactive {
-                    sqlx::query_as::<_, PricingPlanRow>(
-                        "SELECT \
-                            id, plan_key, placement, monthly_usd_cents, \
-                            title_en, title_zh, badge_en, badge_zh, description_en, description_zh, \
-                            is_active, is_default, sort_order, \
-                            campaign_active, campaign_percent_off, campaign_title_en, campaign_title_zh, campaign_starts_at, campaign_ends_at, \
-                            created_at, updated_at \
-                         FROM pricing_plans \
-                         ORDER BY sort_order ASC, created_at ASC, id ASC",
-                    )
-                    .persistent(false)
-                    .fetch_all(pool)
-                    .await?
-                } else {
-                    sqlx::query_as::<_, PricingPlanRow>(
-                        "SELECT \
-                            id, plan_key, placement, monthly_usd_cents, \
-                            title_en, title_zh, badge_en, badge_zh, description_en, description_zh, \
-                            is_active, is_default, sort_order, \
-                            campaign_active, campaign_percent_off, campaign_title_en, campaign_title_zh, campaign_starts_at, campaign_ends_at, \
-                            created_at, updated_at \
-                         FROM pricing_plans \
-                         WHERE is_active = TRUE \
-                         ORDER BY sort_order ASC, created_at ASC, id ASC",
-                    )
-                    .persistent(false)
-                    .fetch_all(pool)
-                    .await?
-                };
+        if recipients.is_empty() {
+            let _ = sqlx::query("SELECT pg_advisory_unlock($1)")
+                .persistent(false)
+                .bind(lock_key)
+                .execute(&mut *conn)
+                .await;
+            return Ok(NewsletterRunReport::default());
+        }
 
-                if plans.is_empty() {
-                    return Ok(Vec::new());
+        let frontend_base_url = env::var("FRONTEND_BASE_URL")
+            .ok()
+            .unwrap_or_else(|| "http://localhost:3000".to_string());
+        let public_api_base_url = env::var("BACKEND_PUBLIC_URL")
+            .ok()
+            .unwrap_or_else(|| "http://localhost:8080".to_string());
+        let token_secret = env::var("NEWSLETTER_TOKEN_SECRET").ok().unwrap_or_default();
+        let client = resend_http_client();
+        let tracking_enabled = !opts.dry_run
+            && newsletter_tracking_enabled()
+            && match ensure_newsletter_tracking_tables(pool).await {
+                Ok(()) => true,
+                Err(e) => {
+                    log::warn!("Newsletter tracking disabled for this run: {:?}", e);
+                    false
                 }
+            };
 
-                let plan_ids: Vec<uuid::Uuid> = plans.iter().map(|p| p.id).collect();
-                let benefits = sqlx::query_as::<_, PricingPlanBenefitRow>(
-                    "SELECT id, plan_id, sort_order, text_en, text_zh, available \
-                     FROM pricing_plan_benefits \
-                     WHERE plan_id = ANY($1) \
-                     ORDER BY plan_id ASC, sort_order ASC, id ASC",
+        let link_hosts = newsletter_issue_link_hosts(&products);
+        let mut messages: Vec<NewsletterMessage> = Vec::new();
+        for r in recipients {
+            let to = r.email.trim().to_string();
+            if to.is_empty() {
+                continue;
+            }
+            let token =
+                compute_newsletter_unsubscribe_token(&to, &token_secret).unwrap_or_default();
+            let unsubscribe_url = if token.trim().is_empty() {
+                let base = normalize_base_url(&public_api_base_url);
+                let email_q = urlencoding::encode(&to);
+                format!("{}/api/newsletter/unsubscribe?email={}", base, email_q)
+            } else {
+                build_newsletter_unsubscribe_url(&public_api_base_url, &to, &token)
+            };
+            let send_token = if tracking_enabled {
+                let token = uuid::Uuid::new_v4();
+                match sqlx::query(
+                    "INSERT INTO newsletter_sends (token, email, week_key, link_hosts) \
+                     VALUES ($1, $2, $3, $4)",
                 )
                 .persistent(false)
-                .bind(&plan_ids)
-                .fetch_all(pool)
-                .await?;
-
-                let mut benefits_by_plan: HashMap<uuid::Uuid, Vec<PricingPlanBenefitRow>> =
-                    HashMap::new();
-                for b in benefits {
-                    benefits_by_plan.entry(b.plan_id).or_default().push(b);
+                .bind(token)
+                .bind(to.as_str())
+                .bind(&week_key)
+                .bind(&link_hosts)
+                .execute(&mut *conn)
+                .await
+                {
+                    Ok(_) => Some(token),
+                    Err(e) => {
+                        log::warn!("Newsletter tracking token insert failed: {:?}", e);
+                        None
+                    }
                 }
+            } else {
+                None
+            };
+            let send_token_str = send_token.map(|t| t.to_string());
+            let tracking = send_token_str.as_deref().map(|t| NewsletterTracking {
+                public_api_base_url: &public_api_base_url,
+                send_token: t,
+                secret: &token_secret,
+            });
+            let (subject, html, text) = build_weekly_newsletter_content(
+                now,
+                since,
+                &products,
+                &frontend_base_url,
+                &unsubscribe_url,
+                newsletter_recipient_locale(r.language.as_deref()),
+                tracking.as_ref(),
+            );
+            let html = if r.format.as_deref() == Some("text") {
+                None
+            } else {
+                Some(html)
+            };
+            messages.push(NewsletterMessage {
+                to,
+                subject,
+                html,
+                text,
+                send_token,
+            });
+        }
 
-                Ok(plans
-                    .into_iter()
-                    .map(|row| {
-                        let benefit_rows = benefits_by_plan.remove(&row.id).unwrap_or_default();
-                        map_pricing_plan_row_to_model(row, benefit_rows)
-                    })
-                    .collect())
-            }
-            .await;
+        let recipient_count = messages.len();
+        if opts.dry_run {
+            let _ = sqlx::query("SELECT pg_advisory_unlock($1)")
+                .persistent(false)
+                .bind(lock_key)
+                .execute(&mut *conn)
+                .await;
+            return Ok(NewsletterRunReport {
+                recipients: recipient_count,
+                sent: 0,
+                sample: messages.into_iter().next().map(|m| NewsletterSample {
+                    to: m.to,
+                    subject: m.subject,
+                    html: m.html,
+                    text: m.text,
+                }),
+            });
+        }
 
-            match attempt {
-                Ok(v) => return Ok(v),
+        let resend_key: Arc<str> = Arc::from(resend_key.as_str());
+        let from: Arc<str> = Arc::from(from.as_str());
+        let messages: Vec<Arc<NewsletterMessage>> = messages.into_iter().map(Arc::new).collect();
+        // 鉴权失败（401 / 403）与收件人无关：一旦出现即停止派发剩余邮件，且不标记任何收件人。
+        let auth_failed = Arc::new(AtomicBool::new(false));
+        let results = run_bounded_concurrent(
+            messages.clone(),
+            newsletter_concurrency(),
+            move |msg: Arc<NewsletterMessage>| {
+                let client = client.clone();
+                let resend_key = resend_key.clone();
+                let from = from.clone();
+                let auth_failed = auth_failed.clone();
+                async move {
+                    if auth_failed.load(Ordering::Acquire) {
+                        return Err(anyhow::anyhow!(
+                            "Newsletter send skipped after Resend auth failure"
+                        ));
+                    }
+                    let res = send_newsletter_message(&client, &resend_key, &from, &msg).await;
+                    if matches!(
+                        res.as_ref()
+                            .err()
+                            .and_then(|e| e.downcast_ref::<ResendSendError>()),
+                        Some(ResendSendError::Unauthorized { .. })
+                    ) {
+                        auth_failed.store(true, Ordering::Release);
+                    }
+                    res
+                }
+            },
+        )
+        .await;
+
+        let mut sent: Vec<String> = Vec::new();
+        let mut permanently_failed: Vec<String> = Vec::new();
+        let mut auth_error: Option<anyhow::Error> = None;
+        for (msg, res) in messages.iter().zip(results) {
+            let res = res.unwrap_or_else(|| Err(anyhow::anyhow!("Newsletter send task panicked")));
+            match res {
+                Ok(()) => sent.push(msg.to.clone()),
                 Err(e) => {
-                    if (is_missing_relation_error(&e, "pricing_plans")
-                        || is_missing_relation_error(&e, "pricing_plan_benefits"))
-                        && !PRICING_TABLES_READY.load(Ordering::Relaxed)
-                        && ensure_pricing_tables(pool).await.is_ok()
+                    if is_http_timeout_error(&e) {
+                        log::warn!("Newsletter send timed out to={}", msg.to);
+                    } else {
+                        log::warn!("Newsletter send failed to={} err={:?}", msg.to, e);
+                    }
+                    if matches!(
+                        e.downcast_ref::<ResendSendError>(),
+                        Some(ResendSendError::Permanent { .. })
+                    ) {
+                        // 收件人无效等不可重试错误：本周不再重试该地址。
+                        permanently_failed.push(msg.to.clone());
+                    }
+                    if auth_error.is_none()
+                        && matches!(
+                            e.downcast_ref::<ResendSendError>(),
+                            Some(ResendSendError::Unauthorized { .. })
+                        )
                     {
-                        continue;
+                        auth_error = Some(e);
+                    }
+                    if let Some(token) = msg.send_token {
+                        let _ = sqlx::query("DELETE FROM newsletter_sends WHERE token = $1")
+                            .persistent(false)
+                            .bind(token)
+                            .execute(&mut *conn)
+                            .await;
                     }
-                    last_err = Some(e);
-                    break;
                 }
             }
         }
 
-        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to list pricing plans")))
+        if !sent.is_empty() {
+            sqlx::query(
+                "UPDATE newsletter_subscriptions \
+                 SET last_sent_week = $1, last_sent_at = NOW(), updated_at = NOW() \
+                 WHERE email = ANY($2)",
+            )
+            .persistent(false)
+            .bind(&week_key)
+            .bind(&sent)
+            .execute(&mut *conn)
+            .await?;
+        }
+        if !permanently_failed.is_empty() {
+            sqlx::query(
+                "UPDATE newsletter_subscriptions \
+                 SET last_sent_week = $1, updated_at = NOW() \
+                 WHERE email = ANY($2)",
+            )
+            .persistent(false)
+            .bind(&week_key)
+            .bind(&permanently_failed)
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        let _ = sqlx::query("SELECT pg_advisory_unlock($1)")
+            .persistent(false)
+            .bind(lock_key)
+            .execute(&mut *conn)
+            .await;
+
+        if let Some(e) = auth_error {
+            log::error!(
+                "Newsletter run aborted after Resend auth failure sent={} err={:?}",
+                sent.len(),
+                e
+            );
+            return Err(e);
+        }
+
+        Ok(NewsletterRunReport {
+            recipients: recipient_count,
+            sent: sent.len(),
+            sample: None,
+        })
     }
 
     /**
-     * upsert_pricing_plan
-     * 新增或更新定价方案，并同步权益列表；若标记为 default，会清理同 placement 的其它 default。
+     * record_admin_audit
+     * 写入一条管理操作审计记录；载荷摘要超过 ADMIN_AUDIT_SUMMARY_MAX_CHARS 时截断。
      */
-    pub async fn upsert_pricing_plan(
+    pub async fn record_admin_audit(
         &self,
-        input: UpsertPricingPlanRequest,
-    ) -> Result<PricingPlan> {
+        actor: &str,
+        action: &str,
+        entity_type: &str,
+        entity_id: Option<&str>,
+        payload_summary: Option<&str>,
+    ) -> Result<()> {
         let pool = self
             .postgres
             .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Postgres is not configured"))?;
-
-        let plan_key = strip_nul_str(input.plan_key.trim()).into_owned();
-        if plan_key.is_empty() {
-            return Err(anyhow::anyhow!("Missing plan_key"));
-        }
-
-        let placement = input
-            .placement
-            .as_deref()
-            .map(|v| v.trim().to_string())
-            .filter(|v| !v.is_empty());
-        if let Some(ref p) = placement {
-            if p != "home_top" && p != "home_right" {
-                return Err(anyhow::anyhow!("Invalid placement"));
-            }
-        }
-
-        let id = input
-            .id
-            .as_deref()
-            .map(|v| v.trim())
-            .filter(|v| !v.is_empty())
-            .and_then(|v| uuid::Uuid::parse_str(v).ok());
-
-        let mut last_err: Option<anyhow::Error> = None;
-        for _attempt_idx in 0..2 {
-            let mut tx = pool.begin().await?;
-
-            let attempt: Result<PricingPlan, anyhow::Error> = async {
-                let existing_id = if let Some(id) = id {
-                    Some(id)
-                } else {
-                    sqlx::query_scalar::<_, uuid::Uuid>(
-                        "SELECT id FROM pricing_plans WHERE plan_key = $1 LIMIT 1",
-                    )
-                    .persistent(false)
-                    .bind(plan_key.as_str())
-                    .fetch_optional(&mut *tx)
-                    .await?
-                };
-                let plan_id = existing_id.unwrap_or_else(uuid::Uuid::new_v4);
-
-                let title_en = strip_nul_str(input.title_en.trim()).into_owned();
-                let title_zh = strip_nul_str(input.title_zh.trim()).into_owned();
-                if title_en.is_empty() || title_zh.is_empty() {
-                    return Err(anyhow::anyhow!("Missing title"));
-                }
-
-                let badge_en = input
-                    .badge_en
-                    .as_deref()
-                    .map(|v| strip_nul_str(v.trim()).into_owned())
-                    .filter(|v| !v.is_empty());
-                let badge_zh = input
-                    .badge_zh
-                    .as_deref()
-                    .map(|v| strip_nul_str(v.trim()).into_owned())
-                    .filter(|v| !v.is_empty());
-                let description_en = input
-                    .description_en
-                    .as_deref()
-                    .map(|v| strip_nul_str(v.trim()).into_owned())
-                    .filter(|v| !v.is_empty());
-                let description_zh = input
-                    .description_zh
-                    .as_deref()
-                    .map(|v| strip_nul_str(v.trim()).into_owned())
-                    .filter(|v| !v.is_empty());
-
-                let campaign = input.campaign.clone();
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+        ensure_admin_audit_log_table(pool).await?;
 
-                sqlx::query(
-                    "INSERT INTO pricing_plans \
-                     (id, plan_key, placement, monthly_usd_cents, title_en, title_zh, badge_en, badge_zh, description_en, description_zh, is_active, is_default, sort_order, \
-                      campaign_active, campaign_percent_off, campaign_title_en, campaign_title_zh, campaign_starts_at, campaign_ends_at, updated_at) \
-                     VALUES \
-                     ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15,$16,$17,$18,$19,NOW()) \
-                     ON CONFLICT (id) DO UPDATE SET \
-                       plan_key = EXCLUDED.plan_key, \
-                       placement = EXCLUDED.placement, \
-                       monthly_usd_cents = EXCLUDED.monthly_usd_cents, \
-                       title_en = EXCLUDED.title_en, \
-                       title_zh = EXCLUDED.title_zh, \
-                       badge_en = EXCLUDED.badge_en, \
-                       badge_zh = EXCLUDED.badge_zh, \
-                       description_en = EXCLUDED.description_en, \
-                       description_zh = EXCLUDED.description_zh, \
-                       is_active = EXCLUDED.is_active, \
-                       is_default = EXCLUDED.is_default, \
-                       sort_order = EXCLUDED.sort_order, \
-                       campaign_active = EXCLUDED.campaign_active, \
-                       campaign_percent_off = EXCLUDED.campaign_percent_off, \
-                       campaign_title_en = EXCLUDED.campaign_title_en, \
-                       campaign_title_zh = EXCLUDED.campaign_title_zh, \
-                       campaign_starts_at = EXCLUDED.campaign_starts_at, \
-                       campaign_ends_at = EXCLUDED.campaign_ends_at, \
-                       updated_at = NOW()",
-                )
-                .persistent(false)
-                .bind(plan_id)
-                .bind(plan_key.as_str())
-                .bind(placement.as_deref())
-                .bind(input.monthly_usd_cents)
-                .bind(title_en.as_str())
-                .bind(title_zh.as_str())
-                .bind(badge_en.as_deref())
-                .bind(badge_zh.as_deref())
-                .bind(description_en.as_deref())
-                .bind(description_zh.as_deref())
-                .bind(input.is_active)
-                .bind(input.is_default)
-                .bind(input.sort_order)
-                .bind(campaign.active)
-                .bind(campaign.percent_off)
-                .bind(
-                    campaign
-                        .title_en
-                        .as_deref()
-                        .map(|v| strip_nul_str(v.trim()).into_owned()),
-                )
-                .bind(
-                    campaign
-                        .title_zh
-                        .as_deref()
-                        .map(|v| strip_nul_str(v.trim()).into_owned()),
-                )
-                .bind(campaign.starts_at)
-                .bind(campaign.ends_at)
-                .execute(&mut *tx)
-                .await?;
+        sqlx::query(
+            "INSERT INTO admin_audit_log (actor, action, entity_type, entity_id, payload_summary) \
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .persistent(false)
+        .bind(strip_nul_str(actor.trim()).as_ref())
+        .bind(strip_nul_str(action).as_ref())
+        .bind(strip_nul_str(entity_type).as_ref())
+        .bind(entity_id.map(|v| strip_nul_str(v.trim()).into_owned()))
+        .bind(payload_summary.map(|v| truncate_audit_summary(&strip_nul_str(v))))
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
 
-                if input.is_default {
-                    let placement_for_default = placement.as_deref();
-                    sqlx::query(
-                        "UPDATE pricing_plans SET is_default = FALSE, updated_at = NOW() \
-                         WHERE id <> $1 AND (placement IS NOT DISTINCT FROM $2)",
-                    )
-                    .persistent(false)
-                    .bind(plan_id)
-                    .bind(placement_for_default)
-                    .execute(&mut *tx)
-                    .await?;
-                }
+    /**
+     * list_admin_audit_log
+     * 管理端审计日志：按 created_at 倒序分页，支持 actor / action / entity_type / entity_id 精确过滤，并返回总数。
+     */
+    pub async fn list_admin_audit_log(
+        &self,
+        params: AdminAuditLogListParams,
+    ) -> Result<(Vec<AdminAuditEntry>, i64)> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+        ensure_admin_audit_log_table(pool).await?;
+
+        let limit = params.limit.clamp(1, 200);
+        let offset = params.offset.max(0);
+        let clean = |v: &Option<String>| {
+            v.as_deref()
+                .map(|s| strip_nul_str(s.trim()).into_owned())
+                .filter(|s| !s.is_empty())
+        };
+        let filters: Vec<(&str, String)> = [
+            ("actor", clean(&params.actor)),
+            ("action", clean(&params.action)),
+            ("entity_type", clean(&params.entity_type)),
+            ("entity_id", clean(&params.entity_id)),
+        ]
+        .into_iter()
+        .filter_map(|(column, value)| value.map(|v| (column, v)))
+        .collect();
+        let push_filters = |qb: &mut QueryBuilder<Postgres>| {
+            qb.push(" WHERE 1=1");
+            for (column, value) in &filters {
+                qb.push(format!(" AND {} = ", column));
+                qb.push_bind(value.clone());
+            }
+        };
 
-                sqlx::query("DELETE FROM pricing_plan_benefits WHERE plan_id = $1")
-                    .persistent(false)
-                    .bind(plan_id)
-                    .execute(&mut *tx)
-                    .await?;
+        #[derive(sqlx::FromRow)]
+        struct Row {
+            id: i64,
+            actor: String,
+            action: String,
+            entity_type: String,
+            entity_id: Option<String>,
+            payload_summary: Option<String>,
+            created_at: chrono::DateTime<chrono::Utc>,
+        }
 
-                for b in input.benefits.iter() {
-                    let text_en = strip_nul_str(b.text_en.trim()).into_owned();
-                    let text_zh = strip_nul_str(b.text_zh.trim()).into_owned();
-                    if text_en.is_empty() && text_zh.is_empty() {
-                        continue;
-                    }
-                    let text_en = if text_en.is_empty() { text_zh.clone() } else { text_en };
-                    let text_zh = if text_zh.is_empty() { text_en.clone() } else { text_zh };
+        let mut qb: QueryBuilder<Postgres> = QueryBuilder::new(
+            "SELECT id, actor, action, entity_type, entity_id, payload_summary, created_at \
+             FROM admin_audit_log",
+        );
+        push_filters(&mut qb);
+        qb.push(" ORDER BY created_at DESC, id DESC LIMIT ");
+        qb.push_bind(limit);
+        qb.push(" OFFSET ");
+        qb.push_bind(offset);
+        let rows = qb
+            .build_query_as::<Row>()
+            .persistent(false)
+            .fetch_all(pool)
+            .await?;
 
-                    sqlx::query(
-                        "INSERT INTO pricing_plan_benefits (plan_id, sort_order, text_en, text_zh, available) \
-                         VALUES ($1,$2,$3,$4,$5)",
-                    )
-                    .persistent(false)
-                    .bind(plan_id)
-                    .bind(b.sort_order)
-                    .bind(text_en.as_str())
-                    .bind(text_zh.as_str())
-                    .bind(b.available)
-                    .execute(&mut *tx)
-                    .await?;
-                }
+        let mut count_qb: QueryBuilder<Postgres> =
+            QueryBuilder::new("SELECT COUNT(*)::bigint FROM admin_audit_log");
+        push_filters(&mut count_qb);
+        let total = count_qb
+            .build_query_scalar::<i64>()
+            .persistent(false)
+            .fetch_one(pool)
+            .await?;
 
-                let plan_row = sqlx::query_as::<_, PricingPlanRow>(
-                    "SELECT \
-                        id, plan_key, placement, monthly_usd_cents, \
-                        title_en, title_zh, badge_en, badge_zh, description_en, description_zh, \
-                        is_active, is_default, sort_order, \
-                        campaign_active, campaign_percent_off, campaign_title_en, campaign_title_zh, campaign_starts_at, campaign_ends_at, \
-                        created_at, updated_at \
-                     FROM pricing_plans WHERE id = $1",
-                )
-                .persistent(false)
-                .bind(plan_id)
-                .fetch_one(&mut *tx)
-                .await?;
+        Ok((
+            rows.into_iter()
+                .map(|r| AdminAuditEntry {
+                    id: r.id,
+                    actor: r.actor,
+                    action: r.action,
+                    entity_type: r.entity_type,
+                    entity_id: r.entity_id,
+                    payload_summary: r.payload_summary,
+                    created_at: r.created_at,
+                })
+                .collect(),
+            total,
+        ))
+    }
 
-                let benefit_rows = sqlx::query_as::<_, PricingPlanBenefitRow>(
-                    "SELECT id, plan_id, sort_order, text_en, text_zh, available \
-                     FROM pricing_plan_benefits WHERE plan_id = $1 ORDER BY sort_order ASC, id ASC",
-                )
-                .persistent(false)
-                .bind(plan_id)
-                .fetch_all(&mut *tx)
-                .await?;
+    pub async fn seed_engagement(&self, product_ids: &[String]) -> Result<()> {
+        if product_ids.is_empty() {
+            return Ok(());
+        }
 
-                Ok(map_pricing_plan_row_to_model(plan_row, benefit_rows))
-            }
-            .await;
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let products = {
+            let sql = "SELECT \
+                p.id::text as id, \
+                p.name, \
+                p.slogan, \
+                p.description, \
+                p.website, \
+                p.logo_url, \
+                p.category, \
+                COALESCE(p.tags, ARRAY[]::text[]) as tags, \
+                p.maker_name, \
+                p.maker_email, \
+                p.maker_website, \
+                p.language, \
+                p.status::text as status, \
+                p.rejection_reason, \
+                p.created_at, \
+                p.updated_at, \
+                0::bigint as likes, \
+                0::bigint as favorites, \
+                COALESCE(d.sponsor_role, NULL::text) as maker_sponsor_role, \
+                COALESCE(d.sponsor_verified, FALSE) as maker_sponsor_verified \
+             FROM products p \
+             LEFT JOIN developers d ON lower(d.email) = lower(p.maker_email) \
+             WHERE p.id::text = ANY($1)";
+
+            let attempt = sqlx::query_as::<_, ProductRow>(sql)
+                .persistent(false)
+                .bind(product_ids)
+                .fetch_all(pool)
+                .await;
 
             match attempt {
-                Ok(v) => {
-                    tx.commit().await?;
-                    return Ok(v);
-                }
+                Ok(rows) => rows,
                 Err(e) => {
-                    let _ = tx.rollback().await;
-                    if (is_missing_relation_error(&e, "pricing_plans")
-                        || is_missing_relation_error(&e, "pricing_plan_benefits"))
-                        && !PRICING_TABLES_READY.load(Ordering::Relaxed)
-                        && ensure_pricing_tables(pool).await.is_ok()
+                    let e: anyhow::Error = e.into();
+                    if is_missing_column_error(&e, "rejection_reason")
+                        && !PRODUCTS_REJECTION_REASON_READY.load(Ordering::Relaxed)
                     {
-                        continue;
+                        if ensure_products_rejection_reason_column(pool).await.is_ok() {
+                            sqlx::query_as::<_, ProductRow>(sql)
+                                .persistent(false)
+                                .bind(product_ids)
+                                .fetch_all(pool)
+                                .await?
+                        } else {
+                            return Err(e);
+                        }
+                    } else if (is_missing_column_error(&e, "sponsor_role")
+                        || is_missing_column_error(&e, "sponsor_verified"))
+                        && !DEVELOPERS_SPONSOR_COLUMNS_READY.load(Ordering::Relaxed)
+                    {
+                        if ensure_developers_sponsor_columns(pool).await.is_ok() {
+                            sqlx::query_as::<_, ProductRow>(sql)
+                                .persistent(false)
+                                .bind(product_ids)
+                                .fetch_all(pool)
+                                .await?
+                        } else {
+                            return Err(e);
+                        }
+                    } else {
+                        return Err(e);
                     }
-                    last_err = Some(e);
-                    break;
                 }
             }
+        };
+
+        for p in &products {
+            self.upsert_developer_pg(
+                pool,
+                &p.maker_email,
+                &p.maker_name,
+                p.maker_website.as_ref(),
+            )
+            .await?;
+        }
+
+        let mut emails: Vec<String> = products.iter().map(|p| p.maker_email.clone()).collect();
+        emails.sort();
+        emails.dedup();
+
+        for (idx, email) in emails.iter().enumerate() {
+            let follows = 10 + (idx as i64 * 7);
+            let mut qb: QueryBuilder<Postgres> = QueryBuilder::new(
+                "INSERT INTO developer_follows (developer_email, user_id, created_at) ",
+            );
+            qb.push_values(0..follows, |mut b, i| {
+                let user_id = format!("seed_user_{}_{}", idx, i);
+                let created_at = if i % 3 == 0 {
+                    chrono::Utc::now() - chrono::Duration::days(35)
+                } else {
+                    chrono::Utc::now() - chrono::Duration::days(5)
+                };
+                b.push_bind(email).push_bind(user_id).push_bind(created_at);
+            });
+            qb.push(" ON CONFLICT (developer_email, user_id) DO NOTHING");
+            qb.build().persistent(false).execute(pool).await?;
+        }
+
+        for (idx, p) in products.iter().enumerate() {
+            let product_uuid = match uuid::Uuid::parse_str(&p.id) {
+                Ok(v) => v,
+                Err(_) => continue,
+            };
+            let likes = 20 + (idx as i64 * 9);
+            let favorites = 12 + (idx as i64 * 5);
+
+            let mut likes_qb: QueryBuilder<Postgres> =
+                QueryBuilder::new("INSERT INTO product_likes (product_id, user_id, created_at) ");
+            likes_qb.push_values(0..likes, |mut b, i| {
+                let user_id = format!("seed_like_{}_{}", idx, i);
+                let created_at = if i % 2 == 0 {
+                    chrono::Utc::now() - chrono::Duration::days(33)
+                } else {
+                    chrono::Utc::now() - chrono::Duration::days(3)
+                };
+                b.push_bind(product_uuid)
+                    .push_bind(user_id)
+                    .push_bind(created_at);
+            });
+            likes_qb.push(" ON CONFLICT (product_id, user_id) DO NOTHING");
+            likes_qb.build().persistent(false).execute(pool).await?;
+
+            let mut fav_qb: QueryBuilder<Postgres> = QueryBuilder::new(
+                "INSERT INTO product_favorites (product_id, user_id, created_at) ",
+            );
+            fav_qb.push_values(0..favorites, |mut b, i| {
+                let user_id = format!("seed_fav_{}_{}", idx, i);
+                let created_at = if i % 2 == 0 {
+                    chrono::Utc::now() - chrono::Duration::days(34)
+                } else {
+                    chrono::Utc::now() - chrono::Duration::days(4)
+                };
+                b.push_bind(product_uuid)
+                    .push_bind(user_id)
+                    .push_bind(created_at);
+            });
+            fav_qb.push(" ON CONFLICT (product_id, user_id) DO NOTHING");
+            fav_qb.build().persistent(false).execute(pool).await?;
+        }
+
+        Ok(())
+    }
+
+    /**
+     * bootstrap_schema
+     * 在直连 Postgres 的情况下自动创建必要表结构与索引（开发环境使用）。
+     */
+    pub async fn bootstrap_schema(&self) -> Result<()> {
+        let pool = self
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::Error::new(PostgresNotConfiguredError))?;
+
+        let sql = include_str!("../database_schema.sql");
+        for stmt in split_sql_statements(sql) {
+            sqlx::query(&stmt).persistent(false).execute(pool).await?;
         }
 
-        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to upsert pricing plan")))
+        Ok(())
     }
 
-    /**
-     * delete_pricing_plan
-     * 删除指定定价方案（连带删除权益）。
-     */
-    pub async fn delete_pricing_plan(&self, id: &str) -> Result<bool> {
-        let pool = self
-            .postgres
+    #[allow(dead_code)]
+    pub async fn get_products_count(&self) -> Result<i64> {
+        if let Some(pool) = &self.postgres {
+            let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*)::bigint FROM products")
+                .persistent(false)
+                .fetch_one(pool)
+                .await?;
+            return Ok(count);
+        }
+
+        let supabase = self
+            .supabase
             .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Postgres is not configured"))?;
+            .ok_or_else(|| anyhow::anyhow!("No database configured"))?;
 
-        let plan_id =
-            uuid::Uuid::parse_str(id.trim()).map_err(|_| anyhow::anyhow!("Invalid id"))?;
+        let mut url = Url::parse(&format!("{}/rest/v1/products", supabase.supabase_url))?;
+        url.query_pairs_mut().append_pair("select", "count");
 
-        let mut last_err: Option<anyhow::Error> = None;
-        for _attempt_idx in 0..2 {
-            let attempt = sqlx::query("DELETE FROM pricing_plans WHERE id = $1")
-                .persistent(false)
-                .bind(plan_id)
-                .execute(pool)
-                .await;
+        let response = supabase.get_with_retry(url, &[]).await?;
 
-            match attempt {
-                Ok(res) => return Ok(res.rows_affected() > 0),
-                Err(e) => {
-                    let e: anyhow::Error = e.into();
-                    if is_missing_relation_error(&e, "pricing_plans")
-                        && !PRICING_TABLES_READY.load(Ordering::Relaxed)
-                        && ensure_pricing_tables(pool).await.is_ok()
-                    {
-                        continue;
-                    }
-                    last_err = Some(e);
-                    break;
-                }
-            }
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!(
+                "Failed to fetch products count: {}. Body: {}",
+                status,
+                body
+            ));
         }
 
-        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to delete pricing plan")))
+        let result: Vec<serde_json::Value> = response.json().await?;
+        Ok(result
+            .first()
+            .and_then(|v| v.get("count"))
+            .and_then(|v| v.as_i64())
+            .unwrap_or(0))
     }
+}
 
-    pub async fn get_pricing_plan_by_id(&self, id: &str) -> Result<Option<PricingPlan>> {
-        let pool = self
-            .postgres
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Postgres is not configured"))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ProductStatus;
+
+    fn sample_newsletter_rows(count: usize) -> Vec<NewsletterTopProductRow> {
+        (0..count)
+            .map(|i| NewsletterTopProductRow {
+                id: format!("product-{}", i),
+                name: format!("Product {}", i),
+                slogan: "Slogan".to_string(),
+                website: "https://example.com".to_string(),
+                logo_url: None,
+                maker_name: "Maker".to_string(),
+                maker_email: "maker@example.com".to_string(),
+                weekly_likes: 2,
+                weekly_favorites: 1,
+                score: 3,
+            })
+            .collect()
+    }
 
-        let plan_id =
-            uuid::Uuid::parse_str(id.trim()).map_err(|_| anyhow::anyhow!("Invalid id"))?;
+    #[test]
+    fn test_weekly_newsletter_renders_top_n_cards() {
+        let now = chrono::Utc::now();
+        let since = now - chrono::Duration::days(7);
+        for n in [3usize, 5] {
+            let rows = sample_newsletter_rows(n);
+            let (_subject, html, text) = build_weekly_newsletter_content(
+                now,
+                since,
+                &rows,
+                "https://soloforge.dev",
+                "https://api.soloforge.dev/unsubscribe",
+                "en",
+                None,
+            );
+            assert_eq!(html.matches("View details").count(), n);
+            assert!(html.contains(&format!("Top {} products this week", n)));
+            assert!(text.contains(&format!("Top {} products this week", n)));
+        }
+    }
 
-        let mut last_err: Option<anyhow::Error> = None;
-        for _attempt_idx in 0..2 {
-            let attempt: Result<Option<PricingPlan>, anyhow::Error> = async {
-                let plan_row = sqlx::query_as::<_, PricingPlanRow>(
-                    "SELECT \
-                        id, plan_key, placement, monthly_usd_cents, \
-                        title_en, title_zh, badge_en, badge_zh, description_en, description_zh, \
-                        is_active, is_default, sort_order, \
-                        campaign_active, campaign_percent_off, campaign_title_en, campaign_title_zh, campaign_starts_at, campaign_ends_at, \
-                        created_at, updated_at \
-                     FROM pricing_plans WHERE id = $1",
-                )
-                .persistent(false)
-                .bind(plan_id)
-                .fetch_optional(pool)
-                .await?;
+    #[test]
+    fn test_build_email_logo_img_skips_invalid_urls() {
+        assert_eq!(build_email_logo_img(None, "Demo", 40), "");
+        assert_eq!(build_email_logo_img(Some("  "), "Demo", 40), "");
+        assert_eq!(
+            build_email_logo_img(Some("javascript:alert(1)"), "Demo", 40),
+            ""
+        );
+        let img = build_email_logo_img(Some("https://cdn.example.com/logo.png"), "A \"B\"", 40);
+        assert!(img.starts_with("<img src=\"https://cdn.example.com/logo.png\""));
+        assert!(img.contains("alt=\"A &quot;B&quot;\""));
+    }
 
-                let Some(plan_row) = plan_row else {
-                    return Ok(None);
-                };
+    #[test]
+    fn test_normalize_tags_dedups_case_and_whitespace() {
+        let tags = vec![
+            "AI".to_string(),
+            " ai ".to_string(),
+            "Machine   Learning".to_string(),
+            "machine-learning".to_string(),
+            "   ".to_string(),
+        ];
+        assert_eq!(
+            normalize_tags(&tags),
+            vec!["ai".to_string(), "machine-learning".to_string()]
+        );
+    }
 
-                let benefit_rows = sqlx::query_as::<_, PricingPlanBenefitRow>(
-                    "SELECT id, plan_id, sort_order, text_en, text_zh, available \
-                     FROM pricing_plan_benefits WHERE plan_id = $1 ORDER BY sort_order ASC, id ASC",
-                )
-                .persistent(false)
-                .bind(plan_id)
-                .fetch_all(pool)
-                .await?;
+    #[test]
+    fn test_sanitize_create_product_request_caps_tags() {
+        let mut product = CreateProductRequest {
+            name: "Demo".to_string(),
+            slogan: "Demo".to_string(),
+            description: "Demo".to_string(),
+            website: "https://example.com".to_string(),
+            logo_url: None,
+            category: "ai".to_string(),
+            tags: (0..15).map(|i| format!("tag{}", i)).collect(),
+            maker_name: "Maker".to_string(),
+            maker_email: "maker@example.com".to_string(),
+            maker_website: None,
+            language: "en".to_string(),
+        };
+        sanitize_create_product_request(&mut product).expect("within length caps");
+        assert_eq!(product.tags.len(), MAX_PRODUCT_TAGS);
+    }
 
-                Ok(Some(map_pricing_plan_row_to_model(plan_row, benefit_rows)))
-            }
-            .await;
+    #[test]
+    fn test_initial_product_status_allowlisted_email_is_approved() {
+        let status = initial_product_status(
+            " Maker@Example.com ",
+            true,
+            "someone@else.dev, maker@example.com",
+            false,
+        );
+        assert_eq!(status, ProductStatus::Approved);
+    }
 
-            match attempt {
-                Ok(v) => return Ok(v),
-                Err(e) => {
-                    if (is_missing_relation_error(&e, "pricing_plans")
-                        || is_missing_relation_error(&e, "pricing_plan_benefits"))
-                        && !PRICING_TABLES_READY.load(Ordering::Relaxed)
-                        && ensure_pricing_tables(pool).await.is_ok()
-                    {
-                        continue;
-                    }
-                    last_err = Some(e);
-                    break;
-                }
-            }
-        }
+    #[test]
+    fn test_initial_product_status_requires_verified_maker_email() {
+        assert_eq!(
+            initial_product_status("maker@example.com", false, "maker@example.com", false),
+            ProductStatus::Pending
+        );
+        assert_eq!(
+            initial_product_status("maker@example.com", false, "", true),
+            ProductStatus::Pending
+        );
+    }
 
-        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to get pricing plan")))
+    #[test]
+    fn test_initial_product_status_pending_unless_trusted() {
+        assert_eq!(
+            initial_product_status("maker@example.com", true, "", false),
+            ProductStatus::Pending
+        );
+        assert_eq!(
+            initial_product_status("maker@example.com", true, "other@example.com", true),
+            ProductStatus::Approved
+        );
     }
 
-    pub async fn get_pricing_plan_by_key(&self, plan_key: &str) -> Result<Option<PricingPlan>> {
-        let pool = self
-            .postgres
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Postgres is not configured"))?;
+    #[test]
+    fn test_parse_admin_notify_emails_skips_invalid_and_duplicates() {
+        let parsed = parse_admin_notify_emails(
+            " Admin@Example.com, not-an-email,,ops@example.com,admin@example.com ",
+        );
+        assert_eq!(
+            parsed,
+            vec![
+                "admin@example.com".to_string(),
+                "ops@example.com".to_string()
+            ]
+        );
+    }
 
-        let plan_key = strip_nul_str(plan_key.trim()).into_owned();
-        if plan_key.is_empty() {
-            return Ok(None);
-        }
+    fn ids(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
 
-        let mut last_err: Option<anyhow::Error> = None;
-        for _attempt_idx in 0..2 {
-            let attempt: Result<Option<PricingPlan>, anyhow::Error> = async {
-                let plan_row = sqlx::query_as::<_, PricingPlanRow>(
-                    "SELECT \
-                        id, plan_key, placement, monthly_usd_cents, \
-                        title_en, title_zh, badge_en, badge_zh, description_en, description_zh, \
-                        is_active, is_default, sort_order, \
-                        campaign_active, campaign_percent_off, campaign_title_en, campaign_title_zh, campaign_starts_at, campaign_ends_at, \
-                        created_at, updated_at \
-                     FROM pricing_plans WHERE plan_key = $1 LIMIT 1",
-                )
-                .persistent(false)
-                .bind(plan_key.as_str())
-                .fetch_optional(pool)
-                .await?;
+    #[test]
+    fn test_home_module_mode_paid_shows_only_grants() {
+        let paid = vec![None, Some("paid-1".to_string()), None];
+        let composed = compose_home_module_ids(
+            HomeModuleMode::Paid,
+            &paid,
+            &ids(&["free-1", "free-2"]),
+            &ids(&["fallback-1"]),
+            3,
+        );
+        assert_eq!(composed, ids(&["paid-1"]));
+    }
 
-                let Some(plan_row) = plan_row else {
-                    return Ok(None);
-                };
+    #[test]
+    fn test_home_module_mode_free_ignores_grants() {
+        let paid = vec![Some("paid-1".to_string()), None];
+        let composed = compose_home_module_ids(
+            HomeModuleMode::Free,
+            &paid,
+            &ids(&["free-1"]),
+            &ids(&["free-1", "fallback-1", "fallback-2"]),
+            2,
+        );
+        assert_eq!(composed, ids(&["free-1", "fallback-1"]));
+    }
 
-                let benefit_rows = sqlx::query_as::<_, PricingPlanBenefitRow>(
-                    "SELECT id, plan_id, sort_order, text_en, text_zh, available \
-                     FROM pricing_plan_benefits WHERE plan_id = $1 ORDER BY sort_order ASC, id ASC",
-                )
-                .persistent(false)
-                .bind(plan_row.id)
-                .fetch_all(pool)
-                .await?;
+    #[test]
+    fn test_home_module_mode_mixed_fills_paid_slots_first() {
+        let paid = vec![None, Some("paid-1".to_string()), None];
+        let composed = compose_home_module_ids(
+            HomeModuleMode::Mixed,
+            &paid,
+            &ids(&["paid-1", "free-1"]),
+            &ids(&["fallback-1", "fallback-2"]),
+            3,
+        );
+        assert_eq!(composed, ids(&["free-1", "paid-1", "fallback-1"]));
+    }
 
-                Ok(Some(map_pricing_plan_row_to_model(plan_row, benefit_rows)))
-            }
-            .await;
+    #[test]
+    fn test_featured_cooldown_skips_yesterdays_picks() {
+        let candidates = ids(&["a", "b", "c", "d", "e"]);
+        let day_one = pick_featured_with_cooldown(&candidates, &HashSet::new(), 2);
+        assert_eq!(day_one, ids(&["a", "b"]));
 
-            match attempt {
-                Ok(v) => return Ok(v),
-                Err(e) => {
-                    if (is_missing_relation_error(&e, "pricing_plans")
-                        || is_missing_relation_error(&e, "pricing_plan_benefits"))
-                        && !PRICING_TABLES_READY.load(Ordering::Relaxed)
-                        && ensure_pricing_tables(pool).await.is_ok()
-                    {
-                        continue;
-                    }
-                    last_err = Some(e);
-                    break;
-                }
-            }
+        let recent: HashSet<String> = day_one.into_iter().collect();
+        let day_two = pick_featured_with_cooldown(&candidates, &recent, 2);
+        assert_eq!(day_two, ids(&["c", "d"]));
+    }
+
+    #[test]
+    fn test_featured_cooldown_relaxes_when_pool_is_small() {
+        let candidates = ids(&["a", "b", "c"]);
+        let recent: HashSet<String> = ids(&["a", "b"]).into_iter().collect();
+        let picked = pick_featured_with_cooldown(&candidates, &recent, 2);
+        assert_eq!(picked, ids(&["c", "a"]));
+    }
+
+    #[test]
+    fn test_cooldown_days_from_env_value_defaults_and_clamps() {
+        assert_eq!(cooldown_days_from_env_value(None), 0);
+        assert_eq!(cooldown_days_from_env_value(Some("abc")), 0);
+        assert_eq!(cooldown_days_from_env_value(Some("-3")), 0);
+        assert_eq!(cooldown_days_from_env_value(Some(" 3 ")), 3);
+        assert_eq!(cooldown_days_from_env_value(Some("365")), 90);
+    }
+
+    #[test]
+    fn test_home_module_mode_resolve_defaults_unknown_values() {
+        assert_eq!(
+            HomeModuleMode::resolve(Some("2026-01-01"), HomeModuleMode::Mixed),
+            HomeModuleMode::Mixed
+        );
+        assert_eq!(
+            HomeModuleMode::resolve(Some(" Paid "), HomeModuleMode::Mixed),
+            HomeModuleMode::Paid
+        );
+        assert_eq!(
+            HomeModuleMode::resolve(None, HomeModuleMode::Free),
+            HomeModuleMode::Free
+        );
+    }
+
+    fn sample_product(status: ProductStatus) -> Product {
+        Product {
+            id: "00000000-0000-0000-0000-000000000001".to_string(),
+            name: "Forge".to_string(),
+            slogan: "Slogan".to_string(),
+            description: "Description".to_string(),
+            website: "https://forge.example.com".to_string(),
+            logo_url: None,
+            category: "developer".to_string(),
+            tags: vec!["rust".to_string()],
+            maker_name: "Maker".to_string(),
+            maker_email: "maker@example.com".to_string(),
+            maker_website: None,
+            maker_sponsor_role: None,
+            maker_sponsor_verified: false,
+            language: "en".to_string(),
+            status,
+            rejection_reason: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            likes: 0,
+            favorites: 0,
+            has_liked: None,
+            has_favorited: None,
+            category_detail: None,
         }
+    }
 
-        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to get pricing plan")))
+    fn website_update(website: &str) -> UpdateProductRequest {
+        UpdateProductRequest {
+            name: None,
+            slogan: None,
+            description: None,
+            website: Some(website.to_string()),
+            logo_url: None,
+            category: None,
+            tags: None,
+            status: None,
+            rejection_reason: None,
+        }
     }
 
-    pub async fn get_default_pricing_plan_for_placement(
-        &self,
-        placement: Option<&str>,
-    ) -> Result<Option<PricingPlan>> {
-        let pool = self
-            .postgres
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Postgres is not configured"))?;
+    #[test]
+    fn test_maker_edit_resets_approved_product_but_admin_edit_preserves() {
+        let existing = sample_product(ProductStatus::Approved);
+        let updates = website_update("https://new.example.com");
+        let fields = review_on_edit_fields(None);
 
-        let placement = placement
-            .map(|v| strip_nul_str(v.trim()).into_owned())
-            .filter(|v| !v.is_empty());
+        assert!(requires_re_review(&existing, &updates, &fields, false));
+        assert!(!requires_re_review(&existing, &updates, &fields, true));
+    }
 
-        let mut last_err: Option<anyhow::Error> = None;
-        for _attempt_idx in 0..2 {
-            let attempt: Result<Option<PricingPlan>, anyhow::Error> = async {
-                let plan_row = sqlx::query_as::<_, PricingPlanRow>(
-                    "SELECT \
-                        id, plan_key, placement, monthly_usd_cents, \
-                        title_en, title_zh, badge_en, badge_zh, description_en, description_zh, \
-                        is_active, is_default, sort_order, \
-                        campaign_active, campaign_percent_off, campaign_title_en, campaign_title_zh, campaign_starts_at, campaign_ends_at, \
-                        created_at, updated_at \
-                     FROM pricing_plans \
-                     WHERE is_default = TRUE AND is_active = TRUE AND (placement IS NOT DISTINCT FROM $1) \
-                     ORDER BY sort_order ASC, id ASC \
-                     LIMIT 1",
-                )
-                .persistent(false)
-                .bind(placement.as_deref())
-                .fetch_optional(pool)
-                .await?;
+    #[test]
+    fn test_re_review_ignores_unchanged_and_unlisted_fields() {
+        let existing = sample_product(ProductStatus::Approved);
+        let fields = review_on_edit_fields(Some("name"));
+
+        assert!(!requires_re_review(
+            &existing,
+            &website_update("https://new.example.com"),
+            &fields,
+            false
+        ));
+        assert!(!requires_re_review(
+            &existing,
+            &website_update(" https://forge.example.com "),
+            &review_on_edit_fields(None),
+            false
+        ));
+        assert!(review_on_edit_fields(Some("")).is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_active_grant_lookup_uses_index_range_scan() {
+        let pool = test_pool().await;
+        ensure_sponsorship_tables(&pool)
+            .await
+            .expect("ensure tables");
+        ensure_sponsorship_grants_active_index(&pool).await;
+
+        let mut tx = pool.begin().await.expect("begin");
+        sqlx::query("SET LOCAL enable_seqscan = off")
+            .execute(&mut *tx)
+            .await
+            .expect("disable seqscan");
+        // 对首页实际使用的生效 grants 查询（含 products 连接）做 EXPLAIN
+        let explain = format!("EXPLAIN {}", active_sponsorship_grants_sql(false));
+        let plan: Vec<(String,)> = sqlx::query_as(&explain)
+            .bind(Placement::HomeTop.to_db_str())
+            .bind(chrono::Utc::now())
+            .fetch_all(&mut *tx)
+            .await
+            .expect("explain");
+        tx.rollback().await.expect("rollback");
+
+        let plan = plan
+            .into_iter()
+            .map(|(line,)| line)
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert!(
+            plan.contains("idx_sponsorship_grants_placement_active_range"),
+            "unexpected plan: {}",
+            plan
+        );
+        assert!(
+            !plan.contains("Seq Scan on sponsorship_grants"),
+            "unexpected plan: {}",
+            plan
+        );
+    }
+
+    fn popularity_entry(
+        name: &str,
+        likes: i64,
+        favorites: i64,
+        followers: i64,
+    ) -> DeveloperPopularity {
+        DeveloperPopularity {
+            email: format!("{}@example.com", name),
+            name: name.to_string(),
+            avatar_url: None,
+            website: None,
+            sponsor_role: None,
+            sponsor_verified: false,
+            likes,
+            favorites,
+            new_followers: followers,
+            score: 0,
+        }
+    }
 
-                let Some(plan_row) = plan_row else {
-                    return Ok(None);
-                };
+    #[test]
+    fn test_developer_popularity_weights_reorder_results() {
+        let entries = || {
+            vec![
+                popularity_entry("liked", 10, 0, 0),
+                popularity_entry("followed", 1, 0, 6),
+            ]
+        };
 
-                let benefit_rows = sqlx::query_as::<_, PricingPlanBenefitRow>(
-                    "SELECT id, plan_id, sort_order, text_en, text_zh, available \
-                     FROM pricing_plan_benefits WHERE plan_id = $1 ORDER BY sort_order ASC, id ASC",
-                )
-                .persistent(false)
-                .bind(plan_row.id)
-                .fetch_all(pool)
-                .await?;
+        let default_weights = PopularityWeights {
+            likes: 1,
+            favorites: 1,
+            followers: 1,
+        };
+        let ranked = rank_developer_popularity(entries(), default_weights, 10);
+        assert_eq!(ranked[0].name, "liked");
+        assert_eq!(ranked[0].score, 10);
+
+        let follower_heavy = PopularityWeights {
+            likes: 1,
+            favorites: 1,
+            followers: 5,
+        };
+        let ranked = rank_developer_popularity(entries(), follower_heavy, 10);
+        assert_eq!(ranked[0].name, "followed");
+        assert_eq!(ranked[0].score, 31);
+        assert_eq!(
+            rank_developer_popularity(entries(), follower_heavy, 1).len(),
+            1
+        );
+    }
 
-                Ok(Some(map_pricing_plan_row_to_model(plan_row, benefit_rows)))
-            }
-            .await;
+    #[test]
+    fn test_supabase_retry_only_on_transient_statuses() {
+        use reqwest::StatusCode;
 
-            match attempt {
-                Ok(v) => return Ok(v),
-                Err(e) => {
-                    if (is_missing_relation_error(&e, "pricing_plans")
-                        || is_missing_relation_error(&e, "pricing_plan_benefits"))
-                        && !PRICING_TABLES_READY.load(Ordering::Relaxed)
-                        && ensure_pricing_tables(pool).await.is_ok()
-                    {
-                        continue;
-                    }
-                    last_err = Some(e);
-                    break;
-                }
-            }
+        assert!(is_retryable_supabase_status(StatusCode::BAD_GATEWAY));
+        assert!(is_retryable_supabase_status(
+            StatusCode::SERVICE_UNAVAILABLE
+        ));
+        assert!(is_retryable_supabase_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_retryable_supabase_status(StatusCode::BAD_REQUEST));
+        assert!(!is_retryable_supabase_status(StatusCode::UNAUTHORIZED));
+        assert!(!is_retryable_supabase_status(StatusCode::NOT_FOUND));
+
+        let first = supabase_retry_delay(0);
+        assert!(first >= Duration::from_millis(200) && first < Duration::from_millis(300));
+        let second = supabase_retry_delay(1);
+        assert!(second >= Duration::from_millis(400) && second < Duration::from_millis(500));
+    }
+
+    fn valid_pricing_plan_input() -> UpsertPricingPlanRequest {
+        UpsertPricingPlanRequest {
+            id: None,
+            plan_key: "home_top_monthly".to_string(),
+            placement: Some("home_top".to_string()),
+            monthly_usd_cents: Some(1000),
+            title_en: "Homepage top".to_string(),
+            title_zh: "首页顶部".to_string(),
+            badge_en: None,
+            badge_zh: None,
+            description_en: None,
+            description_zh: None,
+            is_active: true,
+            is_default: false,
+            sort_order: 0,
+            benefits: vec![crate::models::UpsertPricingPlanBenefit {
+                id: None,
+                sort_order: 0,
+                text_en: "Top slot".to_string(),
+                text_zh: "顶部展示".to_string(),
+                available: true,
+            }],
+            campaign: crate::models::PricingPlanCampaign {
+                active: false,
+                percent_off: None,
+                title_en: None,
+                title_zh: None,
+                starts_at: None,
+                ends_at: None,
+            },
         }
+    }
 
-        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to get pricing plan")))
+    fn invalid_fields(input: &UpsertPricingPlanRequest) -> Vec<String> {
+        validate_pricing_plan_input(input)
+            .into_iter()
+            .map(|e| e.field)
+            .collect()
     }
 
-    /**
-     * list_sponsorship_orders
-     * 查询支付订单列表（当前实现基于 sponsorship_orders）。
-     */
-    pub async fn list_sponsorship_orders(
-        &self,
-        status: Option<&str>,
-        limit: i64,
-        offset: i64,
-    ) -> Result<Vec<SponsorshipOrder>> {
-        let pool = self
-            .postgres
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Postgres is not configured"))?;
+    #[test]
+    fn test_validate_pricing_plan_accepts_valid_input() {
+        assert!(validate_pricing_plan_input(&valid_pricing_plan_input()).is_empty());
+    }
 
-        let limit = limit.clamp(1, 200);
-        let offset = offset.max(0);
-        let status = status
-            .map(|v| v.trim().to_string())
-            .filter(|v| !v.is_empty());
+    #[test]
+    fn test_validate_pricing_plan_required_fields() {
+        let mut input = valid_pricing_plan_input();
+        input.plan_key = "  ".to_string();
+        input.title_en = String::new();
+        input.title_zh = " ".to_string();
+        assert_eq!(
+            invalid_fields(&input),
+            vec!["plan_key", "title_en", "title_zh"]
+        );
+    }
 
-        let mut last_err: Option<anyhow::Error> = None;
-        for _attempt_idx in 0..2 {
-            let attempt = if let Some(ref status) = status {
-                sqlx::query_as::<_, SponsorshipOrderRow>(
-                    "SELECT id, user_email, user_id, product_id::text as product_id, placement, slot_index, requested_months, paid_months, status, provider, provider_checkout_id, provider_order_id, amount_usd_cents, grant_id, created_at, updated_at \
-                     FROM sponsorship_orders \
-                     WHERE status = $1 \
-                     ORDER BY created_at DESC, id DESC \
-                     LIMIT $2 OFFSET $3",
-                )
-                .persistent(false)
-                .bind(status.as_str())
-                .bind(limit)
-                .bind(offset)
-                .fetch_all(pool)
-                .await
-            } else {
-                sqlx::query_as::<_, SponsorshipOrderRow>(
-                    "SELECT id, user_email, user_id, product_id::text as product_id, placement, slot_index, requested_months, paid_months, status, provider, provider_checkout_id, provider_order_id, amount_usd_cents, grant_id, created_at, updated_at \
-                     FROM sponsorship_orders \
-                     ORDER BY created_at DESC, id DESC \
-                     LIMIT $1 OFFSET $2",
-                )
-                .persistent(false)
-                .bind(limit)
-                .bind(offset)
-                .fetch_all(pool)
-                .await
-            };
+    #[test]
+    fn test_validate_pricing_plan_rejects_unknown_placement() {
+        let mut input = valid_pricing_plan_input();
+        input.placement = Some("footer".to_string());
+        assert_eq!(invalid_fields(&input), vec!["placement"]);
 
-            match attempt {
-                Ok(rows) => {
-                    return Ok(rows
-                        .into_iter()
-                        .map(map_sponsorship_order_row_to_model)
-                        .collect())
-                }
-                Err(e) => {
-                    let e: anyhow::Error = e.into();
-                    if is_missing_relation_error(&e, "sponsorship_orders")
-                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
-                        && ensure_sponsorship_tables(pool).await.is_ok()
-                    {
-                        continue;
-                    }
-                    last_err = Some(e);
-                    break;
-                }
-            }
-        }
+        input.placement = Some(String::new());
+        assert!(invalid_fields(&input).is_empty());
+    }
 
-        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to list sponsorship orders")))
+    #[test]
+    fn test_validate_pricing_plan_rejects_negative_price() {
+        let mut input = valid_pricing_plan_input();
+        input.monthly_usd_cents = Some(-1);
+        assert_eq!(invalid_fields(&input), vec!["monthly_usd_cents"]);
+
+        input.monthly_usd_cents = Some(0);
+        assert!(invalid_fields(&input).is_empty());
     }
 
-    /**
-     * get_payments_summary
-     * 汇总支付统计（订单状态分布 + 近 N 天收入按天聚合）。
-     */
-    pub async fn get_payments_summary(&self, days: i64) -> Result<PaymentsSummary> {
-        #[derive(sqlx::FromRow)]
-        struct StatusAggRow {
-            status: String,
-            count: i64,
-        }
+    #[test]
+    fn test_validate_pricing_plan_requires_a_benefit() {
+        let mut input = valid_pricing_plan_input();
+        input.benefits[0].text_en = " ".to_string();
+        input.benefits[0].text_zh = String::new();
+        assert_eq!(invalid_fields(&input), vec!["benefits"]);
 
-        #[derive(sqlx::FromRow)]
-        struct DayAggRow {
-            day: chrono::DateTime<chrono::Utc>,
-            paid_orders: i64,
-            gross_usd_cents: i64,
-        }
+        input.benefits.clear();
+        assert_eq!(invalid_fields(&input), vec!["benefits"]);
+    }
 
-        let pool = self
-            .postgres
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Postgres is not configured"))?;
+    #[test]
+    fn test_validate_pricing_plan_campaign_fields() {
+        let mut input = valid_pricing_plan_input();
+        input.campaign.active = true;
+        assert_eq!(invalid_fields(&input), vec!["campaign.percent_off"]);
 
-        let days = days.clamp(1, 365);
-        let since = chrono::Utc::now() - chrono::Duration::days(days);
+        input.campaign.percent_off = Some(101);
+        assert_eq!(invalid_fields(&input), vec!["campaign.percent_off"]);
 
-        let mut last_err: Option<anyhow::Error> = None;
-        for _attempt_idx in 0..2 {
-            let attempt: Result<PaymentsSummary, anyhow::Error> = async {
-                let status_rows = sqlx::query_as::<_, StatusAggRow>(
-                    "SELECT status, COUNT(1)::bigint as count \
-                     FROM sponsorship_orders \
-                     GROUP BY status \
-                     ORDER BY status ASC",
-                )
-                .persistent(false)
-                .fetch_all(pool)
-                .await?;
+        input.campaign.percent_off = Some(20);
+        let now = chrono::Utc::now();
+        input.campaign.starts_at = Some(now);
+        input.campaign.ends_at = Some(now - chrono::Duration::days(1));
+        assert_eq!(invalid_fields(&input), vec!["campaign.ends_at"]);
+    }
 
-                let mut created_orders = 0i64;
-                let mut paid_orders = 0i64;
-                let mut failed_orders = 0i64;
-                let mut canceled_orders = 0i64;
-                for mut r in status_rows {
-                    strip_nul_in_place(&mut r.status);
-                    match r.status.as_str() {
-                        "created" => created_orders = r.count,
-                        "paid" => paid_orders = r.count,
-                        "failed" => failed_orders = r.count,
-                        "canceled" => canceled_orders = r.count,
-                        _ => {}
-                    }
-                }
+    #[test]
+    fn test_resolve_pricing_plan_order_validates_ids() {
+        let a = uuid::Uuid::new_v4();
+        let b = uuid::Uuid::new_v4();
+        let existing = vec![a, b];
 
-                let gross_usd_cents: i64 = sqlx::query_scalar(
-                    "SELECT COALESCE(SUM(amount_usd_cents), 0)::bigint \
-                     FROM sponsorship_orders \
-                     WHERE status = 'paid'",
-                )
-                .persistent(false)
-                .fetch_one(pool)
-                .await
-                .unwrap_or(0);
+        assert_eq!(
+            resolve_pricing_plan_order(&[b.to_string(), a.to_string()], &existing),
+            Ok(vec![b, a])
+        );
 
-                let day_rows = sqlx::query_as::<_, DayAggRow>(
-                    "SELECT date_trunc('day', updated_at)::timestamptz as day, \
-                            COUNT(1)::bigint as paid_orders, \
-                            COALESCE(SUM(amount_usd_cents), 0)::bigint as gross_usd_cents \
-                     FROM sponsorship_orders \
-                     WHERE status = 'paid' AND updated_at >= $1 \
-                     GROUP BY 1 \
-                     ORDER BY 1 ASC",
-                )
-                .persistent(false)
-                .bind(since)
-                .fetch_all(pool)
-                .await?;
+        let unknown = uuid::Uuid::new_v4().to_string();
+        let errors =
+            resolve_pricing_plan_order(&[b.to_string(), a.to_string(), unknown.clone()], &existing)
+                .unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "ids");
+        assert!(errors[0].message.contains(&unknown));
 
-                Ok(PaymentsSummary {
-                    created_orders,
-                    paid_orders,
-                    failed_orders,
-                    canceled_orders,
-                    gross_usd_cents,
-                    by_day: day_rows
-                        .into_iter()
-                        .map(|r| crate::models::PaymentsDayAgg {
-                            day: r.day,
-                            paid_orders: r.paid_orders,
-                            gross_usd_cents: r.gross_usd_cents,
-                        })
-                        .collect(),
-                })
-            }
-            .await;
+        let errors = resolve_pricing_plan_order(&[a.to_string()], &existing).unwrap_err();
+        assert!(errors[0].message.contains(&b.to_string()));
 
-            match attempt {
-                Ok(v) => return Ok(v),
-                Err(e) => {
-                    if is_missing_relation_error(&e, "sponsorship_orders")
-                        && !SPONSORSHIP_TABLES_READY.load(Ordering::Relaxed)
-                        && ensure_sponsorship_tables(pool).await.is_ok()
-                    {
-                        continue;
-                    }
-                    last_err = Some(e);
-                    break;
-                }
-            }
-        }
+        let errors = resolve_pricing_plan_order(
+            &[a.to_string(), a.to_string(), "nope".to_string()],
+            &existing,
+        )
+        .unwrap_err();
+        let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+        assert!(messages.iter().any(|m| m.starts_with("duplicate plan id")));
+        assert!(messages.iter().any(|m| m.starts_with("invalid plan id")));
+        assert!(messages.iter().any(|m| m.starts_with("missing plan ids")));
+    }
 
-        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Failed to compute payments summary")))
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_force_delete_rejects_pricing_plan_referenced_by_orders() {
+        let pool = test_pool().await;
+        ensure_pricing_tables(&pool).await.expect("ensure pricing");
+        ensure_sponsorship_tables(&pool)
+            .await
+            .expect("ensure sponsorship");
+        let db = test_database(&pool);
+
+        let mut input = valid_pricing_plan_input();
+        input.plan_key = format!("test_in_use_{}", uuid::Uuid::new_v4());
+        let plan = db.upsert_pricing_plan(input).await.expect("upsert plan");
+        let plan_id = uuid::Uuid::parse_str(&plan.id).expect("plan id");
+
+        let product_id = uuid::Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO products (id, name, slogan, description, website, category, maker_name, maker_email) \
+             VALUES ($1, 'Plan ref', 'Slogan', 'Description', 'https://example.com', 'test', 'Maker', 'maker@example.com')",
+        )
+        .bind(product_id)
+        .execute(&pool)
+        .await
+        .expect("insert product");
+        sqlx::query(
+            "INSERT INTO sponsorship_orders (id, user_email, product_id, placement, requested_months, pricing_plan_id) \
+             VALUES ($1, 'maker@example.com', $2, 'home_top', 1, $3)",
+        )
+        .bind(uuid::Uuid::new_v4())
+        .bind(product_id)
+        .bind(plan_id)
+        .execute(&pool)
+        .await
+        .expect("insert order");
+
+        let err = db
+            .delete_pricing_plan(&plan.id, true)
+            .await
+            .expect_err("referenced plan must not be force-deleted");
+        assert_eq!(
+            err.downcast_ref::<PricingPlanInUseError>()
+                .map(|e| e.order_refs),
+            Some(1)
+        );
+
+        assert!(db
+            .delete_pricing_plan(&plan.id, false)
+            .await
+            .expect("soft delete"));
+        let still_there = db
+            .get_pricing_plan_by_id(&plan.id)
+            .await
+            .expect("load plan");
+        assert_eq!(still_there.map(|p| p.is_active), Some(false));
+
+        sqlx::query("DELETE FROM products WHERE id = $1")
+            .bind(product_id)
+            .execute(&pool)
+            .await
+            .expect("cleanup product");
+        assert!(db
+            .delete_pricing_plan(&plan.id, true)
+            .await
+            .expect("force delete"));
     }
 
-    pub async fn get_favorite_products(
-        &self,
-        user_id: &str,
-        language: Option<&str>,
-        limit: i64,
-    ) -> Result<Vec<Product>> {
-        let limit = limit.clamp(1, 200);
+    #[test]
+    fn test_pricing_plan_benefits_keep_unavailable_in_sort_position() {
+        let plan_id = uuid::Uuid::new_v4();
+        let row = |id: i64, sort_order: i32, available: bool| PricingPlanBenefitRow {
+            id,
+            plan_id,
+            sort_order,
+            text_en: format!("benefit {}", id),
+            text_zh: format!("权益 {}", id),
+            available,
+        };
 
-        if let Some(pool) = &self.postgres {
-            let status_clause = if dev_include_pending_in_approved() {
-                "p.status::text IN ('approved','pending')"
-            } else {
-                "p.status::text = 'approved'"
-            };
+        let benefits =
+            map_pricing_plan_benefit_rows(vec![row(3, 2, true), row(2, 1, false), row(1, 0, true)]);
+        let ids: Vec<i64> = benefits.iter().map(|b| b.id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert!(!benefits[1].available);
+
+        let json = serde_json::to_value(&benefits).unwrap();
+        assert_eq!(json[1]["available"], serde_json::Value::Bool(false));
+
+        let groups = group_pricing_plan_benefits(&benefits);
+        let available: Vec<i64> = groups.available.iter().map(|b| b.id).collect();
+        let unavailable: Vec<i64> = groups.unavailable.iter().map(|b| b.id).collect();
+        assert_eq!(available, vec![1, 3]);
+        assert_eq!(unavailable, vec![2]);
+    }
 
-            let rows = if let Some(language) = language {
-                let sql = format!(
-                    "SELECT \
-                        p.id::text as id, \
-                        p.name, \
-                        p.slogan, \
-                        p.description, \
-                        p.website, \
-                        p.logo_url, \
-                        p.category, \
-                        COALESCE(p.tags, ARRAY[]::text[]) as tags, \
-                        p.maker_name, \
-                        p.maker_email, \
-                        p.maker_website, \
-                        p.language, \
-                        p.status::text as status, \
-                        p.rejection_reason, \
-                        p.created_at, \
-                        p.updated_at, \
-                        COALESCE(pl.likes, 0)::bigint as likes, \
-                        COALESCE(pf2.favorites, 0)::bigint as favorites, \
-                        COALESCE(d.sponsor_role, NULL::text) as maker_sponsor_role, \
-                        COALESCE(d.sponsor_verified, FALSE) as maker_sponsor_verified \
-                     FROM product_favorites f \
-                     JOIN products p ON p.id = f.product_id \
-                     LEFT JOIN developers d ON lower(d.email) = lower(p.maker_email) \
-                     LEFT JOIN (SELECT product_id, COUNT(*)::bigint as likes FROM product_likes GROUP BY product_id) pl ON pl.product_id = p.id \
-                     LEFT JOIN (SELECT product_id, COUNT(*)::bigint as favorites FROM product_favorites GROUP BY product_id) pf2 ON pf2.product_id = p.id \
-                     WHERE f.user_id = $1 AND {} AND p.language = $2 \
-                     ORDER BY f.created_at DESC \
-                     LIMIT $3",
-                    status_clause
-                );
+    #[test]
+    fn test_sanitize_rejects_overly_long_product_fields() {
+        let product = || CreateProductRequest {
+            name: "Demo".to_string(),
+            slogan: "Demo".to_string(),
+            description: "Demo".to_string(),
+            website: "https://example.com".to_string(),
+            logo_url: None,
+            category: "ai".to_string(),
+            tags: vec!["tools".to_string()],
+            maker_name: "Maker".to_string(),
+            maker_email: "maker@example.com".to_string(),
+            maker_website: None,
+            language: "en".to_string(),
+        };
 
-                {
-                    let attempt = sqlx::query_as::<_, ProductRow>(&sql)
-                        .persistent(false)
-                        .bind(user_id)
-                        .bind(language)
-                        .bind(limit)
-                        .fetch_all(pool)
-                        .await;
-                    match attempt {
-                        Ok(rows) => rows,
-                        Err(e) => {
-                            let e: anyhow::Error = e.into();
-                            if is_missing_column_error(&e, "rejection_reason")
-                                && !PRODUCTS_REJECTION_REASON_READY.load(Ordering::Relaxed)
-                                && ensure_products_rejection_reason_column(pool).await.is_ok()
-                            {
-                                let rows = sqlx::query_as::<_, ProductRow>(&sql)
-                                    .persistent(false)
-                                    .bind(user_id)
-                                    .bind(language)
-                                    .bind(limit)
-                                    .fetch_all(pool)
-                                    .await?;
-                                return Ok(rows.into_iter().map(map_product_row).collect());
-                            }
-                            if (is_missing_column_error(&e, "sponsor_role")
-                                || is_missing_column_error(&e, "sponsor_verified"))
-                                && !DEVELOPERS_SPONSOR_COLUMNS_READY.load(Ordering::Relaxed)
-                                && ensure_developers_sponsor_columns(pool).await.is_ok()
-                            {
-                                let rows = sqlx::query_as::<_, ProductRow>(&sql)
-                                    .persistent(false)
-                                    .bind(user_id)
-                                    .bind(language)
-                                    .bind(limit)
-                                    .fetch_all(pool)
-                                    .await?;
-                                return Ok(rows.into_iter().map(map_product_row).collect());
-                            }
-                            return Err(e);
-                        }
-                    }
-                }
-            } else {
-                let sql = format!(
-                    "SELECT \
-                        p.id::text as id, \
-                        p.name, \
-                        p.slogan, \
-                        p.description, \
-                        p.website, \
-                        p.logo_url, \
-                        p.category, \
-                        COALESCE(p.tags, ARRAY[]::text[]) as tags, \
-                        p.maker_name, \
-                        p.maker_email, \
-                        p.maker_website, \
-                        p.language, \
-                        p.status::text as status, \
-                        p.rejection_reason, \
-                        p.created_at, \
-                        p.updated_at, \
-                        COALESCE(pl.likes, 0)::bigint as likes, \
-                        COALESCE(pf2.favorites, 0)::bigint as favorites, \
-                        COALESCE(d.sponsor_role, NULL::text) as maker_sponsor_role, \
-                        COALESCE(d.sponsor_verified, FALSE) as maker_sponsor_verified \
-                     FROM product_favorites f \
-                     JOIN products p ON p.id = f.product_id \
-                     LEFT JOIN developers d ON lower(d.email) = lower(p.maker_email) \
-                     LEFT JOIN (SELECT product_id, COUNT(*)::bigint as likes FROM product_likes GROUP BY product_id) pl ON pl.product_id = p.id \
-                     LEFT JOIN (SELECT product_id, COUNT(*)::bigint as favorites FROM product_favorites GROUP BY product_id) pf2 ON pf2.product_id = p.id \
-                     WHERE f.user_id = $1 AND {} \
-                     ORDER BY f.created_at DESC \
-                     LIMIT $2",
-                    status_clause
-                );
+        let mut ok = product();
+        ok.name = "名".repeat(MAX_PRODUCT_NAME_CHARS);
+        assert!(sanitize_create_product_request(&mut ok).is_ok());
+
+        let mut long_name = product();
+        long_name.name = "n".repeat(MAX_PRODUCT_NAME_CHARS + 1);
+        let err = sanitize_create_product_request(&mut long_name).unwrap_err();
+        assert_eq!(err.field, "name");
+        assert_eq!(err.actual_chars, MAX_PRODUCT_NAME_CHARS + 1);
+        assert!(err.localized_message("zh-CN").starts_with("字段 name"));
+        assert!(err.localized_message("en").starts_with("Field name"));
+
+        let mut long_description = product();
+        long_description.description = "d".repeat(MAX_PRODUCT_DESCRIPTION_CHARS + 1);
+        let err = sanitize_create_product_request(&mut long_description).unwrap_err();
+        assert_eq!(err.field, "description");
+
+        let mut long_tag = product();
+        long_tag.tags = vec!["t".repeat(MAX_PRODUCT_TAG_CHARS + 1)];
+        let err = sanitize_create_product_request(&mut long_tag).unwrap_err();
+        assert_eq!(err.field, "tags");
+
+        let mut updates = UpdateProductRequest {
+            name: None,
+            slogan: Some("s".repeat(MAX_PRODUCT_SLOGAN_CHARS + 1)),
+            description: None,
+            website: None,
+            logo_url: None,
+            category: None,
+            tags: None,
+            status: None,
+            rejection_reason: None,
+        };
+        let err = sanitize_update_product_request(&mut updates).unwrap_err();
+        assert_eq!(err.field, "slogan");
+    }
 
-                {
-                    let attempt = sqlx::query_as::<_, ProductRow>(&sql)
-                        .persistent(false)
-                        .bind(user_id)
-                        .bind(limit)
-                        .fetch_all(pool)
-                        .await;
-                    match attempt {
-                        Ok(rows) => rows,
-                        Err(e) => {
-                            let e: anyhow::Error = e.into();
-                            if is_missing_column_error(&e, "rejection_reason")
-                                && !PRODUCTS_REJECTION_REASON_READY.load(Ordering::Relaxed)
-                                && ensure_products_rejection_reason_column(pool).await.is_ok()
-                            {
-                                let rows = sqlx::query_as::<_, ProductRow>(&sql)
-                                    .persistent(false)
-                                    .bind(user_id)
-                                    .bind(limit)
-                                    .fetch_all(pool)
-                                    .await?;
-                                return Ok(rows.into_iter().map(map_product_row).collect());
-                            }
-                            if (is_missing_column_error(&e, "sponsor_role")
-                                || is_missing_column_error(&e, "sponsor_verified"))
-                                && !DEVELOPERS_SPONSOR_COLUMNS_READY.load(Ordering::Relaxed)
-                                && ensure_developers_sponsor_columns(pool).await.is_ok()
-                            {
-                                let rows = sqlx::query_as::<_, ProductRow>(&sql)
-                                    .persistent(false)
-                                    .bind(user_id)
-                                    .bind(limit)
-                                    .fetch_all(pool)
-                                    .await?;
-                                return Ok(rows.into_iter().map(map_product_row).collect());
-                            }
-                            return Err(e);
-                        }
-                    }
-                }
-            };
+    #[test]
+    fn test_sponsorship_request_sort_parse() {
+        assert_eq!(
+            SponsorshipRequestSort::parse(""),
+            Some(SponsorshipRequestSort::CreatedAt)
+        );
+        assert_eq!(
+            SponsorshipRequestSort::parse("created_at"),
+            Some(SponsorshipRequestSort::CreatedAt)
+        );
+        assert_eq!(
+            SponsorshipRequestSort::parse(" duration_days "),
+            Some(SponsorshipRequestSort::DurationDays)
+        );
+        assert_eq!(SponsorshipRequestSort::parse("email"), None);
+        assert_eq!(
+            SponsorshipRequestListParams::default().sort,
+            SponsorshipRequestSort::CreatedAt
+        );
+    }
 
-            return Ok(rows.into_iter().map(map_product_row).collect());
-        }
+    #[test]
+    fn test_pick_unambiguous_product_match() {
+        let product = |id: &str| ResolvedProductRef {
+            id: id.to_string(),
+            name: format!("Product {}", id),
+            logo_url: None,
+        };
 
-        Ok(Vec::new())
+        assert_eq!(
+            pick_unambiguous_product_match(vec![(4, product("a"))]),
+            Some(product("a"))
+        );
+        assert_eq!(
+            pick_unambiguous_product_match(vec![(3, product("a")), (3, product("b"))]),
+            None
+        );
+        assert_eq!(
+            pick_unambiguous_product_match(vec![
+                (4, product("a")),
+                (1, product("b")),
+                (2, product("c")),
+            ]),
+            Some(product("b"))
+        );
+        // 名称完全匹配优先于同时命中的前缀匹配，与 resolve_product_candidates 的排序一致
+        assert_eq!(
+            pick_unambiguous_product_match(vec![(2, product("a")), (3, product("b"))]),
+            Some(product("a"))
+        );
+        assert_eq!(pick_unambiguous_product_match(Vec::new()), None);
     }
 
-    pub async fn get_product_by_id(&self, id: &str) -> Result<Option<Product>> {
-        if let Some(pool) = &self.postgres {
-            let mut last_err: Option<anyhow::Error> = None;
-            for attempt_idx in 0..2 {
-                let attempt = sqlx::query_as::<_, ProductRow>(
-                    "SELECT \
-                        p.id::text as id, \
-                        p.name, \
-                        p.slogan, \
-                        p.description, \
-                        p.website, \
-                        p.logo_url, \
-                        p.category, \
-                        COALESCE(p.tags, ARRAY[]::text[]) as tags, \
-                        p.maker_name, \
-                        p.maker_email, \
-                        p.maker_website, \
-                        p.language, \
-                        p.status::text as status, \
-                        p.rejection_reason, \
-                        p.created_at, \
-                        p.updated_at, \
-                        (SELECT COUNT(*)::bigint FROM product_likes l WHERE l.product_id = p.id) as likes, \
-                        (SELECT COUNT(*)::bigint FROM product_favorites f WHERE f.product_id = p.id) as favorites, \
-                        COALESCE(d.sponsor_role, NULL::text) as maker_sponsor_role, \
-                        COALESCE(d.sponsor_verified, FALSE) as maker_sponsor_verified \
-                     FROM products p \
-                     LEFT JOIN developers d ON lower(d.email) = lower(p.maker_email) \
-                     WHERE p.id::text = $1 \
-                     LIMIT 1",
-                )
-                .persistent(false)
-                .bind(id)
-                .fetch_optional(pool)
-                .await;
+    #[test]
+    fn test_sponsorship_grant_window_queues_after_busy_slot() {
+        let now = chrono::Utc::now();
 
-                match attempt {
-                    Ok(row) => return Ok(row.map(map_product_row)),
-                    Err(e) => {
-                        let e: anyhow::Error = e.into();
-                        if is_missing_column_error(&e, "rejection_reason")
-                            && !PRODUCTS_REJECTION_REASON_READY.load(Ordering::Relaxed)
-                            && ensure_products_rejection_reason_column(pool).await.is_ok()
-                        {
-                            continue;
-                        }
-                        if (is_missing_column_error(&e, "sponsor_role")
-                            || is_missing_column_error(&e, "sponsor_verified"))
-                            && !DEVELOPERS_SPONSOR_COLUMNS_READY.load(Ordering::Relaxed)
-                            && ensure_developers_sponsor_columns(pool).await.is_ok()
-                        {
-                            continue;
-                        }
-                        last_err = Some(e);
-                        let Some(ref err) = last_err else {
-                            continue;
-                        };
-                        if is_retryable_db_error(err) && self.supabase.is_some() {
-                            break;
-                        }
-                        if attempt_idx == 0 && is_retryable_db_error(err) {
-                            continue;
-                        }
-                        return Err(last_err.unwrap());
-                    }
-                }
-            }
+        assert_eq!(
+            sponsorship_grant_window(now, None, 30),
+            (now, now + chrono::Duration::days(30))
+        );
+        assert_eq!(
+            sponsorship_grant_window(now, Some(now - chrono::Duration::days(1)), 30),
+            (now, now + chrono::Duration::days(30))
+        );
 
-            if let Some(e) = last_err {
-                if !(is_retryable_db_error(&e) && self.supabase.is_some()) {
-                    return Err(e);
-                }
-            }
-        }
+        let busy_until = now + chrono::Duration::days(3);
+        let (starts_at, ends_at) = sponsorship_grant_window(now, Some(busy_until), 30);
+        assert_eq!(starts_at, busy_until);
+        assert_eq!(ends_at, busy_until + chrono::Duration::days(30));
+        assert_eq!(
+            sponsorship_grant_window(now, None, 0),
+            (now, now + chrono::Duration::days(1))
+        );
 
-        let supabase = self
-            .supabase
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No database configured"))?;
+        let dpm = 30;
 
-        let mut url = Url::parse(&format!("{}/rest/v1/products", supabase.supabase_url))?;
-        url.query_pairs_mut()
-            .append_pair("id", &format!("eq.{}", id));
+        assert_eq!(months_to_days(0, dpm), 1);
+        assert_eq!(months_to_days(2, 31), 62);
+    }
 
-        let response = supabase
-            .client
-            .get(url)
-            .header("apikey", &supabase.supabase_key)
-            .header(
-                "Authorization",
-                &format!("Bearer {}", supabase.supabase_key),
-            )
-            .header("Accept", "application/json")
-            .send()
-            .await?;
+    #[test]
+    fn test_sponsorship_receipt_email_includes_order_details_in_both_languages() {
+        let starts_at = chrono::Utc.with_ymd_and_hms(2026, 3, 1, 0, 0, 0).unwrap();
+        let ends_at = chrono::Utc.with_ymd_and_hms(2026, 4, 30, 0, 0, 0).unwrap();
+        let receipt = SponsorshipReceipt {
+            order_id: "3f1c2d9e-0000-4000-8000-000000000001",
+            product_name: "Forge <Pro>",
+            placement: "home_top",
+            months: 2,
+            amount_usd_cents: 1805,
+            starts_at,
+            ends_at,
+        };
 
-        if response.status() == 404 {
-            return Ok(None);
-        }
+        let (subject, html, text) = build_sponsorship_receipt_email_content(&receipt, false);
+        assert_eq!(subject, "Sponsorship receipt: Forge <Pro>");
+        assert!(text.starts_with("Sponsorship payment received"));
+        assert!(text.contains("Order: 3f1c2d9e-0000-4000-8000-000000000001"));
+        assert!(text.contains("Amount paid: $18.05 USD"));
+        assert!(text.contains("Active: 2026-03-01 – 2026-04-30"));
+        assert!(text.contains("实付金额：$18.05 USD"));
+        assert!(html.contains("Forge &lt;Pro&gt;"));
+        assert!(!html.contains("Forge <Pro>"));
+
+        let (subject_zh, _, text_zh) = build_sponsorship_receipt_email_content(&receipt, true);
+        assert_eq!(subject_zh, "赞助收据：Forge <Pro>");
+        assert!(text_zh.starts_with("赞助付款成功"));
+        assert_eq!(format_usd_cents(-250), "-$2.50");
+    }
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            return Err(anyhow::anyhow!(
-                "Failed to fetch product: {}. Body: {}",
-                status,
-                body
-            ));
+    #[test]
+    fn test_sponsorship_slot_index_must_be_within_placement_capacity() {
+        assert!(check_sponsorship_slot(Placement::HomeTop, Some(0)).is_ok());
+        assert!(check_sponsorship_slot(Placement::HomeRight, Some(2)).is_ok());
+
+        let err = check_sponsorship_slot(Placement::HomeTop, Some(99)).unwrap_err();
+        assert_eq!(err.capacity, 2);
+        assert_eq!(err.slot_index, Some(99));
+        assert_eq!(
+            err.localized_message("en"),
+            "home_top requires slot_index between 0 and 1"
+        );
+        assert!(check_sponsorship_slot(Placement::HomeRight, Some(3)).is_err());
+        assert!(check_sponsorship_slot(Placement::HomeRight, Some(-1)).is_err());
+        assert!(check_sponsorship_slot(Placement::HomeRight, None).is_err());
+    }
+
+    #[test]
+    fn test_placement_parses_known_values() {
+        assert_eq!("home_top".parse::<Placement>(), Ok(Placement::HomeTop));
+        assert_eq!(
+            " home_right ".parse::<Placement>(),
+            Ok(Placement::HomeRight)
+        );
+        for p in Placement::ALL {
+            assert_eq!(p.to_string().parse::<Placement>(), Ok(p));
+            assert_eq!(
+                serde_json::to_value(p).unwrap(),
+                serde_json::json!(p.to_db_str())
+            );
         }
+    }
 
-        let products: Vec<Product> = response.json().await?;
-        Ok(products.first().cloned())
+    #[test]
+    fn test_placement_rejects_unknown_values() {
+        let err = "sidebar".parse::<Placement>().unwrap_err();
+        assert_eq!(err.value, "sidebar");
+        assert_eq!(
+            err.localized_message("en"),
+            "Invalid placement \"sidebar\", expected one of: home_top, home_right"
+        );
+        assert!("".parse::<Placement>().is_err());
+        assert!("HOME_TOP".parse::<Placement>().is_err());
+        assert!(serde_json::from_value::<Placement>(serde_json::json!("footer")).is_err());
     }
 
-    pub async fn create_product(&self, product: CreateProductRequest) -> Result<Product> {
-        let mut product = product;
-        sanitize_create_product_request(&mut product);
-        if let Some(pool) = &self.postgres {
-            let row = sqlx::query_as::<_, ProductRow>(
-                "INSERT INTO products \
-                    (name, slogan, description, website, logo_url, category, tags, maker_name, maker_email, maker_website, language, status) \
-                 VALUES \
-                    ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,'pending') \
-                 RETURNING \
-                    id::text as id, \
-                    name, \
-                    slogan, \
-                    description, \
-                    website, \
-                    logo_url, \
-                    category, \
-                    COALESCE(tags, ARRAY[]::text[]) as tags, \
-                    maker_name, \
-                    maker_email, \
-                    maker_website, \
-                    language, \
-                    status::text as status, \
-                    rejection_reason, \
-                    created_at, \
-                    updated_at, \
-                    0::bigint as likes, \
-                    0::bigint as favorites, \
-                    NULL::text as maker_sponsor_role, \
-                    FALSE as maker_sponsor_verified",
-            )
-            .persistent(false)
-            .bind(&product.name)
-            .bind(&product.slogan)
-            .bind(&product.description)
-            .bind(&product.website)
-            .bind(&product.logo_url)
-            .bind(&product.category)
-            .bind(&product.tags)
-            .bind(&product.maker_name)
-            .bind(&product.maker_email)
-            .bind(&product.maker_website)
-            .bind(&product.language)
-            .fetch_one(pool)
-            .await?;
+    #[test]
+    fn test_slot_count_from_env_value_defaults_and_clamps() {
+        assert_eq!(slot_count_from_env_value(None, 3), 3);
+        assert_eq!(slot_count_from_env_value(Some(" 4 "), 3), 4);
+        assert_eq!(slot_count_from_env_value(Some("abc"), 2), 2);
+        assert_eq!(slot_count_from_env_value(Some("0"), 2), 1);
+        assert_eq!(slot_count_from_env_value(Some("50"), 2), 10);
+    }
 
-            self.upsert_developer_pg(
-                pool,
-                &product.maker_email,
-                &product.maker_name,
-                product.maker_website.as_ref(),
+    #[test]
+    fn test_weekly_newsletter_links_follow_recipient_locale() {
+        let now = chrono::Utc::now();
+        let since = now - chrono::Duration::days(7);
+        let rows = sample_newsletter_rows(1);
+        let id = rows[0].id.clone();
+
+        let zh = newsletter_recipient_locale(Some("zh-CN"));
+        let (_subject, html, text) = build_weekly_newsletter_content(
+            now,
+            since,
+            &rows,
+            "https://soloforge.dev",
+            "https://api.soloforge.dev/unsubscribe",
+            zh,
+            None,
+        );
+        let zh_url = build_product_detail_url("https://soloforge.dev", "zh", &id);
+        assert!(zh_url.contains("/zh/products/"));
+        assert!(text.contains(&zh_url));
+        assert!(html.contains(&zh_url));
+        assert!(!text.contains("/en/products/"));
+
+        assert_eq!(newsletter_recipient_locale(None), "en");
+        assert_eq!(newsletter_recipient_locale(Some("fr")), "en");
+    }
+
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_newsletter_unsubscribe_then_resubscribe_round_trip() {
+        let pool = test_pool().await;
+        ensure_newsletter_format_column(&pool)
+            .await
+            .expect("ensure newsletter columns");
+        let db = test_database(&pool);
+        let email = format!("resub-{}@example.com", uuid::Uuid::new_v4());
+        let unsubscribed = |pool: PgPool, email: String| async move {
+            sqlx::query_scalar::<_, bool>(
+                "SELECT unsubscribed FROM newsletter_subscriptions WHERE email = $1",
             )
-            .await?;
+            .bind(email)
+            .fetch_one(&pool)
+            .await
+            .expect("fetch subscription")
+        };
 
-            return Ok(map_product_row(row));
-        }
+        assert!(!db
+            .resubscribe_newsletter(&email)
+            .await
+            .expect("resubscribe"));
 
-        let supabase = self
-            .supabase
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No database configured"))?;
+        db.subscribe_newsletter(&email, Some("text"))
+            .await
+            .expect("subscribe");
+        db.unsubscribe_newsletter(&email)
+            .await
+            .expect("unsubscribe");
+        assert!(unsubscribed(pool.clone(), email.clone()).await);
 
-        let url = Url::parse(&format!("{}/rest/v1/products", supabase.supabase_url))?;
+        assert!(db
+            .resubscribe_newsletter(&email)
+            .await
+            .expect("resubscribe"));
+        assert!(!unsubscribed(pool.clone(), email.clone()).await);
+        let format: String =
+            sqlx::query_scalar("SELECT format FROM newsletter_subscriptions WHERE email = $1")
+                .bind(&email)
+                .fetch_one(&pool)
+                .await
+                .expect("fetch format");
+        assert_eq!(format, "text");
 
-        let response = supabase
-            .client
-            .post(url)
-            .header("apikey", &supabase.supabase_key)
-            .header(
-                "Authorization",
-                &format!("Bearer {}", supabase.supabase_key),
-            )
-            .header("Accept", "application/json")
-            .header("Prefer", "return=representation")
-            .json(&product)
-            .send()
-            .await?;
+        sqlx::query("DELETE FROM newsletter_subscriptions WHERE email = $1")
+            .bind(&email)
+            .execute(&pool)
+            .await
+            .expect("cleanup");
+    }
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            return Err(anyhow::anyhow!(
-                "Failed to create product: {}. Body: {}",
-                status,
-                body
-            ));
-        }
+    #[test]
+    fn test_newsletter_tracking_wraps_links_and_signs_click_targets() {
+        let now = chrono::Utc::now();
+        let since = now - chrono::Duration::days(7);
+        let rows = sample_newsletter_rows(1);
+        let tracking = NewsletterTracking {
+            public_api_base_url: "https://api.soloforge.dev",
+            send_token: "3f1c2d9e-0000-4000-8000-000000000001",
+            secret: "secret",
+        };
+        let (_subject, html, text) = build_weekly_newsletter_content(
+            now,
+            since,
+            &rows,
+            "https://soloforge.dev",
+            "https://api.soloforge.dev/unsubscribe",
+            "en",
+            Some(&tracking),
+        );
+        assert!(html.contains(
+            "https://api.soloforge.dev/api/newsletter/open/3f1c2d9e-0000-4000-8000-000000000001.gif"
+        ));
+        assert!(html.contains("https://api.soloforge.dev/api/newsletter/click?token="));
+        assert!(!text.contains("/api/newsletter/click"));
+
+        let target = "https://example.com";
+        let sig = compute_newsletter_click_signature(tracking.send_token, target, "secret")
+            .expect("sign");
+        assert!(verify_newsletter_click_signature(
+            tracking.send_token,
+            target,
+            &sig,
+            "secret"
+        ));
+        assert!(!verify_newsletter_click_signature(
+            tracking.send_token,
+            "https://evil.example",
+            &sig,
+            "secret"
+        ));
+        assert!(!verify_newsletter_click_signature(
+            tracking.send_token,
+            target,
+            &sig,
+            ""
+        ));
+    }
 
-        let new_product: Product = response.json().await?;
-        Ok(new_product)
+    #[test]
+    fn test_newsletter_click_target_rejects_disallowed_hosts() {
+        let frontend = "https://soloforge.dev";
+        let issue = vec!["maker.app".to_string()];
+        assert!(newsletter_click_target("javascript:alert(1)", frontend, None, &issue).is_none());
+        assert!(newsletter_click_target("https://maker.app/x", frontend, None, &issue).is_some());
+        assert!(newsletter_click_target("https://soloforge.dev/en", frontend, None, &[]).is_some());
+        assert!(newsletter_click_target("https://evil.example", frontend, None, &issue).is_none());
+        assert!(newsletter_click_target("https://maker.app/x", frontend, None, &[]).is_none());
+
+        let allow = Some("maker.app, .trusted.io");
+        assert!(newsletter_click_target("https://maker.app/x", frontend, allow, &[]).is_some());
+        assert!(newsletter_click_target("https://www.trusted.io", frontend, allow, &[]).is_some());
+        assert!(
+            newsletter_click_target("https://soloforge.dev/en", frontend, allow, &[]).is_some()
+        );
+        assert!(newsletter_click_target("https://evil.example", frontend, allow, &[]).is_none());
+        assert!(newsletter_click_target("https://notmaker.app", frontend, allow, &[]).is_none());
+        assert!(newsletter_click_target("https://other.app", frontend, allow, &issue).is_none());
     }
 
-    pub async fn update_product(
-        &self,
-        id: &str,
-        updates: UpdateProductRequest,
-    ) -> Result<Option<Product>> {
-        let mut updates = updates;
-        sanitize_update_product_request(&mut updates);
-        if let Some(pool) = &self.postgres {
-            if updates.name.is_none()
-                && updates.slogan.is_none()
-                && updates.description.is_none()
-                && updates.website.is_none()
-                && updates.logo_url.is_none()
-                && updates.category.is_none()
-                && updates.tags.is_none()
-                && updates.status.is_none()
-                && updates.rejection_reason.is_none()
-            {
-                return self.get_product_by_id(id).await;
-            }
-            let mut last_err: Option<anyhow::Error> = None;
-            for attempt_idx in 0..2 {
-                let mut qb: QueryBuilder<Postgres> = QueryBuilder::new("UPDATE products SET ");
-                let mut first = true;
-                let push_comma = |qb: &mut QueryBuilder<Postgres>, first: &mut bool| {
-                    if !*first {
-                        qb.push(", ");
-                    }
-                    *first = false;
-                };
+    #[test]
+    fn test_detect_language_from_chinese_and_english_text() {
+        assert_eq!(
+            detect_language(&["独立开发者的记账工具", "帮你轻松管理 SaaS 订阅", ""]),
+            "zh"
+        );
+        assert_eq!(
+            detect_language(&["BudgetBee", "Personal finance for creators", ""]),
+            "en"
+        );
+        assert_eq!(
+            detect_language(&[
+                "Forge",
+                "Ship faster",
+                "A long English description that only mentions 你好 once in passing."
+            ]),
+            "en"
+        );
+        assert_eq!(detect_language(&["", "", ""]), "en");
+    }
 
-                if let Some(name) = &updates.name {
-                    push_comma(&mut qb, &mut first);
-                    qb.push("name = ");
-                    qb.push_bind(name);
-                }
-                if let Some(slogan) = &updates.slogan {
-                    push_comma(&mut qb, &mut first);
-                    qb.push("slogan = ");
-                    qb.push_bind(slogan);
-                }
-                if let Some(description) = &updates.description {
-                    push_comma(&mut qb, &mut first);
-                    qb.push("description = ");
-                    qb.push_bind(description);
-                }
-                if let Some(website) = &updates.website {
-                    push_comma(&mut qb, &mut first);
-                    qb.push("website = ");
-                    qb.push_bind(website);
-                }
-                if let Some(logo_url) = &updates.logo_url {
-                    push_comma(&mut qb, &mut first);
-                    qb.push("logo_url = ");
-                    qb.push_bind(logo_url);
-                }
-                if let Some(category) = &updates.category {
-                    push_comma(&mut qb, &mut first);
-                    qb.push("category = ");
-                    qb.push_bind(category);
-                }
-                if let Some(tags) = &updates.tags {
-                    push_comma(&mut qb, &mut first);
-                    qb.push("tags = ");
-                    qb.push_bind(tags);
-                }
-                if let Some(status) = &updates.status {
-                    push_comma(&mut qb, &mut first);
-                    qb.push("status = ");
-                    qb.push_bind(serialize_product_status(status));
-                }
-                if let Some(reason) = &updates.rejection_reason {
-                    push_comma(&mut qb, &mut first);
-                    if reason.trim().is_empty() {
-                        qb.push("rejection_reason = NULL");
-                    } else {
-                        qb.push("rejection_reason = ");
-                        qb.push_bind(reason);
-                    }
-                }
+    #[test]
+    fn test_apply_detected_language_keeps_explicit_value() {
+        let mut product = CreateProductRequest {
+            name: "记账工具".to_string(),
+            slogan: "轻松管理订阅".to_string(),
+            description: "Demo".to_string(),
+            website: "https://example.com".to_string(),
+            logo_url: None,
+            category: "ai".to_string(),
+            tags: Vec::new(),
+            maker_name: "Maker".to_string(),
+            maker_email: "maker@example.com".to_string(),
+            maker_website: None,
+            language: "en".to_string(),
+        };
+        apply_detected_language(&mut product, false);
+        assert_eq!(product.language, "en");
+
+        apply_detected_language(&mut product, true);
+        assert_eq!(product.language, "zh");
+
+        product.language = "  ".to_string();
+        product.name = "Forge".to_string();
+        product.slogan = "Ship faster".to_string();
+        product.description = "Plain English.".to_string();
+        apply_detected_language(&mut product, false);
+        assert_eq!(product.language, "en");
+    }
 
-                push_comma(&mut qb, &mut first);
-                qb.push("updated_at = now()");
+    #[test]
+    fn test_missing_on_conflict_constraint_error_is_detected() {
+        let err = anyhow::anyhow!(
+            "error returned from database: there is no unique or exclusion constraint matching the ON CONFLICT specification"
+        );
+        assert!(is_missing_on_conflict_constraint_error(&err));
+        let other = anyhow::anyhow!(
+            "duplicate key value violates unique constraint \"product_likes_pkey\""
+        );
+        assert!(!is_missing_on_conflict_constraint_error(&other));
+    }
 
-                qb.push(" WHERE id::text = ");
-                qb.push_bind(id);
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_merge_developers_moves_follows_and_rejects_missing_target() {
+        let pool = test_pool().await;
+        let db = test_database(&pool);
 
-                qb.push(
-                    " RETURNING \
-                        id::text as id, \
-                        name, \
-                        slogan, \
-                        description, \
-                        website, \
-                        logo_url, \
-                        category, \
-                        COALESCE(tags, ARRAY[]::text[]) as tags, \
-                        maker_name, \
-                        maker_email, \
-                        maker_website, \
-                        language, \
-                        status::text as status, \
-                        rejection_reason, \
-                        created_at, \
-                        updated_at, \
-                        (SELECT COUNT(*)::bigint FROM product_likes l WHERE l.product_id = products.id) as likes, \
-                        (SELECT COUNT(*)::bigint FROM product_favorites f WHERE f.product_id = products.id) as favorites, \
-                        COALESCE((SELECT d.sponsor_role FROM developers d WHERE lower(d.email) = lower(products.maker_email) LIMIT 1), NULL::text) as maker_sponsor_role, \
-                        COALESCE((SELECT d.sponsor_verified FROM developers d WHERE lower(d.email) = lower(products.maker_email) LIMIT 1), FALSE) as maker_sponsor_verified",
-                );
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        let from_email = format!("Merge-{}@Example.com", suffix);
+        let into_email = format!("merge-{}@example.com", suffix);
+        for (email, role) in [(&from_email, Some("gold")), (&into_email, None)] {
+            sqlx::query(
+                "INSERT INTO developers (email, name, sponsor_role) VALUES ($1, 'Maker', $2)",
+            )
+            .bind(email)
+            .bind(role)
+            .execute(&pool)
+            .await
+            .expect("insert developer");
+        }
+        for (email, user_id) in [
+            (&from_email, "user-a"),
+            (&from_email, "user-b"),
+            (&into_email, "user-a"),
+        ] {
+            sqlx::query("INSERT INTO developer_follows (developer_email, user_id) VALUES ($1, $2)")
+                .bind(email)
+                .bind(user_id)
+                .execute(&pool)
+                .await
+                .expect("insert follow");
+        }
+        let product_id = uuid::Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO products (id, name, slogan, description, website, category, maker_name, maker_email) \
+             VALUES ($1, 'Merge product', 'Slogan', 'Description', 'https://example.com', 'test', 'Maker', $2)",
+        )
+        .bind(product_id)
+        .bind(from_email.to_uppercase())
+        .execute(&pool)
+        .await
+        .expect("insert product");
 
-                let attempt = qb
-                    .build_query_as::<ProductRow>()
-                    .persistent(false)
-                    .fetch_optional(pool)
-                    .await;
+        let missing = db
+            .merge_developers(&from_email, &format!("missing-{}@example.com", suffix))
+            .await
+            .expect_err("missing target");
+        assert_eq!(
+            missing
+                .downcast_ref::<DeveloperNotFoundError>()
+                .map(|e| e.role),
+            Some("target")
+        );
 
-                match attempt {
-                    Ok(row) => return Ok(row.map(map_product_row)),
-                    Err(e) => {
-                        let e: anyhow::Error = e.into();
-                        if (is_missing_column_error(&e, "sponsor_role")
-                            || is_missing_column_error(&e, "sponsor_verified"))
-                            && !DEVELOPERS_SPONSOR_COLUMNS_READY.load(Ordering::Relaxed)
-                            && ensure_developers_sponsor_columns(pool).await.is_ok()
-                        {
-                            continue;
-                        }
-                        last_err = Some(e);
-                        if attempt_idx == 0 {
-                            continue;
-                        }
-                        return Err(last_err.unwrap());
-                    }
-                }
-            }
+        let result = db
+            .merge_developers(&from_email, &into_email.to_uppercase())
+            .await
+            .expect("merge");
+        assert_eq!(result.into_email, into_email);
+        assert_eq!(result.follows_moved, 1);
+        assert_eq!(result.products_moved, 1);
+        let maker_email: String =
+            sqlx::query_scalar("SELECT maker_email FROM products WHERE id = $1")
+                .bind(product_id)
+                .fetch_one(&pool)
+                .await
+                .expect("fetch product");
+        assert_eq!(maker_email, into_email);
 
-            if let Some(e) = last_err {
-                return Err(e);
-            }
-        }
+        let followers: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*)::bigint FROM developer_follows WHERE developer_email = $1",
+        )
+        .bind(&into_email)
+        .fetch_one(&pool)
+        .await
+        .expect("count follows");
+        assert_eq!(followers, 2);
+        let role: Option<String> =
+            sqlx::query_scalar("SELECT sponsor_role FROM developers WHERE email = $1")
+                .bind(&into_email)
+                .fetch_one(&pool)
+                .await
+                .expect("fetch target");
+        assert_eq!(role.as_deref(), Some("gold"));
+        let source_left: i64 =
+            sqlx::query_scalar("SELECT COUNT(*)::bigint FROM developers WHERE email = $1")
+                .bind(&from_email)
+                .fetch_one(&pool)
+                .await
+                .expect("count source");
+        assert_eq!(source_left, 0);
 
-        let supabase = self
-            .supabase
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No database configured"))?;
+        sqlx::query("DELETE FROM products WHERE id = $1")
+            .bind(product_id)
+            .execute(&pool)
+            .await
+            .expect("cleanup product");
+        sqlx::query("DELETE FROM developers WHERE email = $1")
+            .bind(&into_email)
+            .execute(&pool)
+            .await
+            .expect("cleanup");
+    }
 
-        let mut url = Url::parse(&format!("{}/rest/v1/products", supabase.supabase_url))?;
-        url.query_pairs_mut()
-            .append_pair("id", &format!("eq.{}", id));
+    #[test]
+    fn test_stats_window_parse_and_since() {
+        let now = chrono::Utc.with_ymd_and_hms(2026, 5, 31, 12, 0, 0).unwrap();
+        assert_eq!(StatsWindow::parse("Week"), Some(StatsWindow::Week));
+        assert_eq!(StatsWindow::parse("30d"), Some(StatsWindow::Month));
+        assert_eq!(StatsWindow::parse("year"), None);
+        assert_eq!(
+            StatsWindow::Week.since(now),
+            chrono::Utc.with_ymd_and_hms(2026, 5, 24, 12, 0, 0).unwrap()
+        );
+        assert_eq!(
+            StatsWindow::Month.since(now),
+            chrono::Utc.with_ymd_and_hms(2026, 5, 1, 12, 0, 0).unwrap()
+        );
+    }
 
-        let mut payload = serde_json::to_value(&updates)?;
-        if let serde_json::Value::Object(ref mut map) = payload {
-            if let Some(reason) = &updates.rejection_reason {
-                if reason.trim().is_empty() {
-                    map.insert("rejection_reason".to_string(), serde_json::Value::Null);
-                }
-            }
-        }
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_developer_center_stats_window_counts_recent_engagement_only() {
+        let pool = test_pool().await;
+        let db = test_database(&pool);
 
-        let response = supabase
-            .client
-            .patch(url)
-            .header("apikey", &supabase.supabase_key)
-            .header(
-                "Authorization",
-                &format!("Bearer {}", supabase.supabase_key),
+        let maker_email = format!("center-{}@example.com", uuid::Uuid::new_v4().simple());
+        let product_id = uuid::Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO products (id, name, slogan, description, website, category, maker_name, maker_email) \
+             VALUES ($1, 'Center', 'Slogan', 'Description', 'https://example.com', 'test', 'Maker', $2)",
+        )
+        .bind(product_id)
+        .bind(&maker_email)
+        .execute(&pool)
+        .await
+        .expect("insert product");
+        let old = chrono::Utc::now() - chrono::Duration::days(20);
+        for (user_id, created_at) in [
+            ("old-user", old),
+            ("new-user-1", chrono::Utc::now()),
+            ("new-user-2", chrono::Utc::now()),
+        ] {
+            sqlx::query(
+                "INSERT INTO product_likes (product_id, user_id, created_at) VALUES ($1, $2, $3)",
             )
-            .header("Accept", "application/json")
-            .header("Prefer", "return=representation")
-            .json(&payload)
-            .send()
-            .await?;
+            .bind(product_id)
+            .bind(user_id)
+            .bind(created_at)
+            .execute(&pool)
+            .await
+            .expect("insert like");
+        }
+        sqlx::query(
+            "INSERT INTO product_favorites (product_id, user_id, created_at) VALUES ($1, 'old-user', $2)",
+        )
+        .bind(product_id)
+        .bind(old)
+        .execute(&pool)
+        .await
+        .expect("insert favorite");
 
-        if response.status() == 404 {
-            return Ok(None);
-        }
+        let lifetime = db
+            .get_developer_center_stats(&maker_email)
+            .await
+            .expect("lifetime stats");
+        assert_eq!(lifetime.total_likes, 3);
+        assert!(lifetime.windowed.is_none());
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            return Err(anyhow::anyhow!(
-                "Failed to update product: {}. Body: {}",
-                status,
-                body
-            ));
-        }
+        let week = db
+            .get_developer_center_stats_windowed(&maker_email, Some(StatsWindow::Week))
+            .await
+            .expect("weekly stats");
+        assert_eq!(week.total_likes, 3);
+        assert_eq!(week.total_favorites, 1);
+        let windowed = week.windowed.expect("windowed figures");
+        assert_eq!(windowed.window, "week");
+        assert_eq!(windowed.likes, 2);
+        assert_eq!(windowed.favorites, 0);
+
+        let month = db
+            .get_developer_center_stats_windowed(&maker_email, Some(StatsWindow::Month))
+            .await
+            .expect("monthly stats");
+        assert_eq!(month.windowed.map(|w| (w.likes, w.favorites)), Some((3, 1)));
 
-        let updated_product: Product = response.json().await?;
-        Ok(Some(updated_product))
+        sqlx::query("DELETE FROM products WHERE id = $1")
+            .bind(product_id)
+            .execute(&pool)
+            .await
+            .expect("cleanup");
     }
 
-    pub async fn delete_product(&self, id: &str) -> Result<bool> {
-        if let Some(pool) = &self.postgres {
-            let res = sqlx::query("DELETE FROM products WHERE id::text = $1")
-                .persistent(false)
-                .bind(id)
-                .execute(pool)
-                .await?;
-            return Ok(res.rows_affected() > 0);
-        }
-
-        let supabase = self
-            .supabase
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No database configured"))?;
+    #[test]
+    fn test_build_search_snippet_marks_case_insensitive_matches() {
+        let snippet =
+            build_search_snippet("Track <every> Budget & budget line", "BUDGET", 160).unwrap();
+        assert_eq!(
+            snippet,
+            "Track &lt;every&gt; <mark>Budget</mark> &amp; <mark>budget</mark> line"
+        );
 
-        let mut url = Url::parse(&format!("{}/rest/v1/products", supabase.supabase_url))?;
-        url.query_pairs_mut()
-            .append_pair("id", &format!("eq.{}", id));
+        let long = format!("{} needle {}", "a".repeat(300), "b".repeat(300));
+        let snippet = build_search_snippet(&long, "Needle", 40).unwrap();
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert!(snippet.contains("<mark>needle</mark>"));
+        assert_eq!(snippet.chars().count(), 40 + 2 + "<mark></mark>".len());
 
-        let response = supabase
-            .client
-            .delete(url)
-            .header("apikey", &supabase.supabase_key)
-            .header(
-                "Authorization",
-                &format!("Bearer {}", supabase.supabase_key),
-            )
-            .send()
-            .await?;
+        let snippet = build_search_snippet("独立开发者的记账工具", "记账", 160).unwrap();
+        assert_eq!(snippet, "独立开发者的<mark>记账</mark>工具");
 
-        Ok(response.status() == 204)
+        let snippet = build_search_snippet("Ship faster with forge", "forge tool", 160).unwrap();
+        assert_eq!(snippet, "Ship faster with <mark>forge</mark>");
+        assert!(build_search_snippet("Nothing here", "absent", 160).is_none());
     }
 
-    pub async fn get_categories(&self) -> Result<Vec<Category>> {
-        if let Some(pool) = &self.postgres {
-            let rows = sqlx::query_as::<_, CategoryRow>(
-                "SELECT id::text as id, name_en, name_zh, icon, color FROM categories ORDER BY id",
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_claim_product_auto_approves_owner_and_transfers_on_admin_approval() {
+        let pool = test_pool().await;
+        let db = test_database(&pool);
+
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        let maker_email = format!("claim-maker-{}@example.com", suffix);
+        let other_email = format!("claim-other-{}@example.com", suffix);
+        let owned_id = uuid::Uuid::new_v4();
+        let disputed_id = uuid::Uuid::new_v4();
+        for id in [owned_id, disputed_id] {
+            sqlx::query(
+                "INSERT INTO products (id, name, slogan, description, website, category, maker_name, maker_email) \
+                 VALUES ($1, 'Claim', 'Slogan', 'Description', 'https://example.com', 'test', 'Maker', $2)",
             )
-            .persistent(false)
-            .fetch_all(pool)
-            .await?;
-            return Ok(rows.into_iter().map(map_category_row).collect());
+            .bind(id)
+            .bind(&maker_email)
+            .execute(&pool)
+            .await
+            .expect("insert product");
         }
+        let owned = owned_id.to_string();
+        let disputed = disputed_id.to_string();
 
-        let supabase = self
-            .supabase
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No database configured"))?;
+        let pending = db
+            .claim_product(&owned, &other_email, Some("I built this"))
+            .await
+            .expect("claim")
+            .expect("product exists");
+        assert_eq!(pending.status, "pending");
+        let approved = db
+            .claim_product(&owned, &maker_email.to_ascii_uppercase(), None)
+            .await
+            .expect("claim")
+            .expect("product exists");
+        assert_eq!(approved.status, "approved");
+        let err = db
+            .claim_product(&owned, &other_email, None)
+            .await
+            .expect_err("already claimed");
+        assert!(err.downcast_ref::<ProductAlreadyClaimedError>().is_some());
 
-        let url = Url::parse(&format!("{}/rest/v1/categories", supabase.supabase_url))?;
+        let claim = db
+            .claim_product(&disputed, &other_email, None)
+            .await
+            .expect("claim")
+            .expect("product exists");
+        assert_eq!(claim.status, "pending");
+        let decided = db
+            .decide_product_claim(claim.id, true)
+            .await
+            .expect("decide")
+            .expect("claim exists");
+        assert_eq!(decided.status, "approved");
+        let new_owner: String =
+            sqlx::query_scalar("SELECT maker_email FROM products WHERE id = $1")
+                .bind(disputed_id)
+                .fetch_one(&pool)
+                .await
+                .expect("select maker_email");
+        assert_eq!(new_owner, other_email);
+        let err = db
+            .decide_product_claim(claim.id, false)
+            .await
+            .expect_err("not pending");
+        assert!(err.downcast_ref::<ProductClaimNotPendingError>().is_some());
 
-        let response = supabase
-            .client
-            .get(url)
-            .header("apikey", &supabase.supabase_key)
-            .header(
-                "Authorization",
-                &format!("Bearer {}", supabase.supabase_key),
+        sqlx::query("DELETE FROM products WHERE id = ANY($1)")
+            .bind(vec![owned_id, disputed_id])
+            .execute(&pool)
+            .await
+            .expect("cleanup");
+    }
+
+    #[test]
+    fn test_product_visibility_separates_dev_flag_and_grace_period() {
+        let approved_only = ProductVisibility {
+            include_pending: false,
+            grace_hours: None,
+        };
+        let dev = ProductVisibility {
+            include_pending: true,
+            grace_hours: Some(24),
+        };
+        let grace = ProductVisibility {
+            include_pending: false,
+            grace_hours: Some(24),
+        };
+        assert_eq!(approved_only.predicate("p"), "p.status::text = 'approved'");
+        assert_eq!(
+            dev.predicate("p"),
+            "p.status::text IN ('approved','pending')"
+        );
+        assert_eq!(
+            grace.predicate(""),
+            "(status::text = 'approved' OR (status::text = 'pending' AND created_at > NOW() - INTERVAL '24 hours'))"
+        );
+        let now = chrono::Utc.with_ymd_and_hms(2024, 3, 2, 12, 0, 0).unwrap();
+        assert_eq!(
+            approved_only.postgrest_filter(now),
+            ("status", "eq.approved".to_string())
+        );
+        assert_eq!(
+            dev.postgrest_filter(now),
+            ("status", "in.(approved,pending)".to_string())
+        );
+        assert_eq!(
+            grace.postgrest_filter(now),
+            (
+                "or",
+                "(status.eq.approved,and(status.eq.pending,created_at.gt.2024-03-01T12:00:00Z))"
+                    .to_string()
             )
-            .header("Accept", "application/json")
-            .send()
-            .await?;
+        );
+        assert_eq!(grace_hours_from_env_value(None), None);
+        assert_eq!(grace_hours_from_env_value(Some("0")), None);
+        assert_eq!(grace_hours_from_env_value(Some("-3")), None);
+        assert_eq!(grace_hours_from_env_value(Some("abc")), None);
+        assert_eq!(grace_hours_from_env_value(Some(" 48 ")), Some(48));
+        assert_eq!(grace_hours_from_env_value(Some("100000")), Some(720));
+    }
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            return Err(anyhow::anyhow!(
-                "Failed to fetch categories: {}. Body: {}",
-                status,
-                body
-            ));
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_grace_period_includes_pending_only_inside_window() {
+        let pool = test_pool().await;
+
+        let maker_email = format!("grace-{}@example.com", uuid::Uuid::new_v4().simple());
+        let now = chrono::Utc::now();
+        let inside = uuid::Uuid::new_v4();
+        let outside = uuid::Uuid::new_v4();
+        let rejected = uuid::Uuid::new_v4();
+        for (id, status, created_at) in [
+            (
+                inside,
+                "pending",
+                now - chrono::Duration::hours(24) + chrono::Duration::minutes(5),
+            ),
+            (
+                outside,
+                "pending",
+                now - chrono::Duration::hours(24) - chrono::Duration::minutes(5),
+            ),
+            (rejected, "rejected", now),
+        ] {
+            sqlx::query(
+                "INSERT INTO products (id, name, slogan, description, website, category, maker_name, maker_email, status, created_at) \
+                 VALUES ($1, 'Grace', 'Slogan', 'Description', 'https://example.com', 'test', 'Maker', $2, $3, $4)",
+            )
+            .bind(id)
+            .bind(&maker_email)
+            .bind(status)
+            .bind(created_at)
+            .execute(&pool)
+            .await
+            .expect("insert product");
         }
 
-        let categories: Vec<Category> = response.json().await?;
-        Ok(categories)
+        let sql = format!(
+            "SELECT p.id FROM products p WHERE {} AND p.maker_email = $1",
+            ProductVisibility {
+                include_pending: false,
+                grace_hours: Some(24),
+            }
+            .predicate("p")
+        );
+        let visible: Vec<uuid::Uuid> = sqlx::query_scalar(&sql)
+            .bind(&maker_email)
+            .fetch_all(&pool)
+            .await
+            .expect("select visible");
+        assert_eq!(visible, vec![inside]);
+
+        sqlx::query("DELETE FROM products WHERE maker_email = $1")
+            .bind(&maker_email)
+            .execute(&pool)
+            .await
+            .expect("cleanup");
     }
 
-    pub async fn get_top_categories_by_product_count(
-        &self,
-        limit: i64,
-    ) -> Result<Vec<crate::models::CategoryWithCount>> {
-        let limit = limit.clamp(1, 50);
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_get_favorite_products_pages_with_offset_and_total() {
+        let pool = test_pool().await;
+        let db = test_database(&pool);
 
-        if let Some(pool) = &self.postgres {
-            let status_clause = if dev_include_pending_in_approved() {
-                "p.status::text IN ('approved','pending')"
-            } else {
-                "p.status::text = 'approved'"
-            };
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        let maker_email = format!("fav-maker-{}@example.com", suffix);
+        let user_id = format!("fav-user-{}", suffix);
+        let now = chrono::Utc::now();
+        let mut ids = Vec::new();
+        for idx in 0..5i64 {
+            let id = uuid::Uuid::new_v4();
+            sqlx::query(
+                "INSERT INTO products (id, name, slogan, description, website, category, maker_name, maker_email, status) \
+                 VALUES ($1, 'Fav', 'Slogan', 'Description', 'https://example.com', 'test', 'Maker', $2, 'approved')",
+            )
+            .bind(id)
+            .bind(&maker_email)
+            .execute(&pool)
+            .await
+            .expect("insert product");
+            sqlx::query(
+                "INSERT INTO product_favorites (product_id, user_id, created_at) VALUES ($1, $2, $3)",
+            )
+            .bind(id)
+            .bind(&user_id)
+            .bind(now - chrono::Duration::minutes(idx))
+            .execute(&pool)
+            .await
+            .expect("insert favorite");
+            ids.push(id.to_string());
+        }
 
-            let sql = format!(
-                "SELECT \
-                    c.id::text as id, \
-                    c.name_en, \
-                    c.name_zh, \
-                    c.icon, \
-                    c.color, \
-                    COUNT(p.id)::bigint as product_count \
-                 FROM categories c \
-                 JOIN products p ON p.category = c.id \
-                 WHERE {} \
-                 GROUP BY c.id, c.name_en, c.name_zh, c.icon, c.color \
-                 ORDER BY product_count DESC, c.id ASC \
-                 LIMIT $1",
-                status_clause
-            );
+        let mut seen = Vec::new();
+        for offset in [0, 2, 4] {
+            let (page, total) = db
+                .get_favorite_products(&user_id, None, 2, offset)
+                .await
+                .expect("favorites page");
+            assert_eq!(total, 5);
+            assert!(page.len() <= 2);
+            seen.extend(page.into_iter().map(|p| p.id));
+        }
+        assert_eq!(seen, ids);
 
-            let rows = sqlx::query_as::<_, CategoryWithCountRow>(&sql)
-                .persistent(false)
-                .bind(limit)
-                .fetch_all(pool)
-                .await?;
+        let (past_end, total) = db
+            .get_favorite_products(&user_id, None, 2, 10)
+            .await
+            .expect("favorites past end");
+        assert!(past_end.is_empty());
+        assert_eq!(total, 5);
 
-            return Ok(rows.into_iter().map(map_category_with_count_row).collect());
-        }
+        sqlx::query("DELETE FROM products WHERE maker_email = $1")
+            .bind(&maker_email)
+            .execute(&pool)
+            .await
+            .expect("cleanup");
+    }
 
-        Ok(Vec::new())
+    #[test]
+    fn test_product_changes_cursor_round_trips_and_rejects_garbage() {
+        let ts = chrono::Utc.with_ymd_and_hms(2024, 5, 6, 7, 8, 9).unwrap()
+            + chrono::Duration::microseconds(123_456);
+        let cursor = encode_product_changes_cursor(ts, "abc-123");
+        assert_eq!(
+            decode_product_changes_cursor(&cursor),
+            Some((ts, "abc-123".to_string()))
+        );
+        assert_eq!(decode_product_changes_cursor("not a cursor"), None);
+        let no_id = general_purpose::URL_SAFE_NO_PAD.encode("2024-05-06T07:08:09Z|");
+        assert_eq!(decode_product_changes_cursor(&no_id), None);
     }
 
-    /**
-     * upsert_categories
-     * 批量插入/更新 categories，用于开发阶段快速初始化数据。
-     */
-    pub async fn upsert_categories(&self, categories: Vec<Category>) -> Result<usize> {
-        let mut categories = categories;
-        if categories.is_empty() {
-            return Ok(0);
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_get_product_changes_pages_and_reports_deletions() {
+        let pool = test_pool().await;
+        let db = test_database(&pool);
+
+        let maker_email = format!("changes-{}@example.com", uuid::Uuid::new_v4().simple());
+        let since = chrono::Utc::now() + chrono::Duration::days(3650);
+        let mut ids = Vec::new();
+        for idx in 1..=3i64 {
+            let id = uuid::Uuid::new_v4();
+            sqlx::query(
+                "INSERT INTO products (id, name, slogan, description, website, category, maker_name, maker_email, status, updated_at) \
+                 VALUES ($1, 'Changes', 'Slogan', 'Description', 'https://example.com', 'test', 'Maker', $2, 'approved', $3)",
+            )
+            .bind(id)
+            .bind(&maker_email)
+            .bind(since + chrono::Duration::seconds(idx))
+            .execute(&pool)
+            .await
+            .expect("insert product");
+            ids.push(id.to_string());
         }
-        sanitize_categories(&mut categories);
 
-        if let Some(pool) = &self.postgres {
-            let mut qb: QueryBuilder<Postgres> =
-                QueryBuilder::new("INSERT INTO categories (id, name_en, name_zh, icon, color) ");
+        let first = db
+            .get_product_changes(since, None, 2)
+            .await
+            .expect("first page");
+        assert_eq!(
+            first.items.iter().map(|c| c.id.clone()).collect::<Vec<_>>(),
+            ids[..2].to_vec()
+        );
+        assert!(first
+            .items
+            .iter()
+            .all(|c| !c.deleted && c.product.is_some()));
+        let (after, after_id) =
+            decode_product_changes_cursor(first.next_cursor.as_deref().expect("next cursor"))
+                .expect("decode cursor");
+        let second = db
+            .get_product_changes(after, Some(&after_id), 2)
+            .await
+            .expect("second page");
+        assert_eq!(second.items.len(), 1);
+        assert_eq!(second.items[0].id, ids[2]);
+        assert!(second.next_cursor.is_none());
+
+        assert!(db.delete_product(&ids[0]).await.expect("delete"));
+        sqlx::query("UPDATE product_deletions SET deleted_at = $2 WHERE product_id::text = $1")
+            .bind(&ids[0])
+            .bind(since + chrono::Duration::seconds(10))
+            .execute(&pool)
+            .await
+            .expect("pin tombstone time");
+        let after_delete = db
+            .get_product_changes(since + chrono::Duration::seconds(3), None, 10)
+            .await
+            .expect("changes after delete");
+        assert_eq!(after_delete.items.len(), 1);
+        assert_eq!(after_delete.items[0].id, ids[0]);
+        assert!(after_delete.items[0].deleted && after_delete.items[0].product.is_none());
+
+        sqlx::query("DELETE FROM products WHERE maker_email = $1")
+            .bind(&maker_email)
+            .execute(&pool)
+            .await
+            .expect("cleanup");
+        sqlx::query("DELETE FROM product_deletions WHERE product_id::text = $1")
+            .bind(&ids[0])
+            .execute(&pool)
+            .await
+            .expect("cleanup tombstone");
+    }
 
-            qb.push_values(categories.iter(), |mut b, c| {
-                b.push_bind(&c.id)
-                    .push_bind(&c.name_en)
-                    .push_bind(&c.name_zh)
-                    .push_bind(&c.icon)
-                    .push_bind(&c.color);
-            });
+    #[test]
+    fn test_outgoing_webhook_signature_validation_and_backoff() {
+        assert_eq!(
+            compute_webhook_signature("key", "The quick brown fox jumps over the lazy dog")
+                .unwrap(),
+            "97yD9DBThCSxMpjmqm-xQ-9NWaFJRhdZl0edvC0aPNg"
+        );
 
-            qb.push(
-                " ON CONFLICT (id) DO UPDATE SET \
-                    name_en = EXCLUDED.name_en, \
-                    name_zh = EXCLUDED.name_zh, \
-                    icon = EXCLUDED.icon, \
-                    color = EXCLUDED.color",
-            );
+        let mut req = crate::models::UpsertOutgoingWebhookRequest {
+            id: None,
+            url: "https://hooks.example.com/soloforge".to_string(),
+            secret: Some("s3cret".to_string()),
+            events: vec!["product.approved".to_string()],
+            enabled: None,
+        };
+        assert!(validate_outgoing_webhook_request(&req).is_ok());
+        req.secret = None;
+        assert!(validate_outgoing_webhook_request(&req).is_err());
+        req.id = Some(1);
+        assert!(validate_outgoing_webhook_request(&req).is_ok());
+        req.url = "ftp://hooks.example.com".to_string();
+        assert!(validate_outgoing_webhook_request(&req).is_err());
+        req.url = "https://hooks.example.com".to_string();
+        req.events = vec!["product.deleted".to_string()];
+        assert!(validate_outgoing_webhook_request(&req).is_err());
+        req.events.clear();
+        assert!(validate_outgoing_webhook_request(&req).is_err());
+
+        assert_eq!(webhook_retry_delay_secs(1), 30);
+        assert_eq!(webhook_retry_delay_secs(2), 60);
+        assert_eq!(webhook_retry_delay_secs(5), 480);
+        assert_eq!(webhook_retry_delay_secs(20), 3600);
+    }
 
-            let res = qb.build().persistent(false).execute(pool).await?;
-            return Ok(res.rows_affected() as usize);
+    #[test]
+    fn test_classify_resend_failure_separates_recipient_and_auth_errors() {
+        let now = chrono::Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+
+        match classify_resend_failure(429, Some("7"), String::new(), now) {
+            ResendSendError::RateLimited { retry_after } => {
+                assert_eq!(retry_after, Some(Duration::from_secs(7)))
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        match classify_resend_failure(
+            429,
+            Some("Mon, 01 Jan 2024 00:00:30 GMT"),
+            String::new(),
+            now,
+        ) {
+            ResendSendError::RateLimited { retry_after } => {
+                assert_eq!(retry_after, Some(Duration::from_secs(30)))
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(matches!(
+            classify_resend_failure(429, Some("soon"), String::new(), now),
+            ResendSendError::RateLimited { retry_after: None }
+        ));
+        assert!(matches!(
+            classify_resend_failure(422, None, "invalid to".to_string(), now),
+            ResendSendError::Permanent { status: 422, .. }
+        ));
+        assert!(matches!(
+            classify_resend_failure(503, None, String::new(), now),
+            ResendSendError::Transient { status: 503, .. }
+        ));
+        assert!(matches!(
+            classify_resend_failure(408, None, String::new(), now),
+            ResendSendError::Transient { status: 408, .. }
+        ));
+        assert!(matches!(
+            classify_resend_failure(400, None, String::new(), now),
+            ResendSendError::Permanent { status: 400, .. }
+        ));
+        for status in [401, 403] {
+            assert!(matches!(
+                classify_resend_failure(status, None, String::new(), now),
+                ResendSendError::Unauthorized { .. }
+            ));
         }
+        assert!(matches!(
+            classify_resend_failure(404, None, String::new(), now),
+            ResendSendError::Transient { status: 404, .. }
+        ));
+    }
 
-        let supabase = self
-            .supabase
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No database configured"))?;
+    #[test]
+    fn test_locale_path_segment_mapping_and_default_omission() {
+        assert_eq!(resolve_locale_path_segment("zh", None, None), "zh");
+        assert_eq!(resolve_locale_path_segment("en", None, None), "en");
+        assert_eq!(resolve_locale_path_segment("", None, None), "");
+
+        assert_eq!(resolve_locale_path_segment("en", None, Some("en")), "");
+        assert_eq!(resolve_locale_path_segment("EN", None, Some("en")), "");
+        assert_eq!(resolve_locale_path_segment("zh", None, Some("en")), "zh");
+
+        let map = r#"{"zh": "zh-CN", "en": ""}"#;
+        assert_eq!(resolve_locale_path_segment("zh", Some(map), None), "zh-CN");
+        assert_eq!(resolve_locale_path_segment("en", Some(map), None), "");
+        assert_eq!(resolve_locale_path_segment("fr", Some(map), None), "fr");
+        assert_eq!(
+            resolve_locale_path_segment("zh", Some("not json"), None),
+            "zh"
+        );
 
-        let mut url = Url::parse(&format!("{}/rest/v1/categories", supabase.supabase_url))?;
-        url.query_pairs_mut().append_pair("on_conflict", "id");
+        let en = resolve_locale_path_segment("en", Some(map), Some("en"));
+        assert_eq!(
+            build_product_detail_url_with("https://soloforge.dev/", &en, "p-1"),
+            "https://soloforge.dev/products/p-1"
+        );
+        let zh = resolve_locale_path_segment("zh", None, Some("en"));
+        assert_eq!(
+            build_product_detail_url_with("https://soloforge.dev", &zh, "p-1"),
+            "https://soloforge.dev/zh/products/p-1"
+        );
+    }
 
-        let response = supabase
-            .client
-            .post(url)
-            .header("apikey", &supabase.supabase_key)
-            .header(
-                "Authorization",
-                &format!("Bearer {}", supabase.supabase_key),
-            )
-            .header("Accept", "application/json")
-            .header(
-                "Prefer",
-                "resolution=merge-duplicates,return=representation",
-            )
-            .json(&categories)
-            .send()
-            .await?;
+    #[test]
+    fn test_fill_engagement_series_zero_fills_gaps() {
+        let today = chrono::Utc
+            .with_ymd_and_hms(2024, 3, 10, 15, 30, 0)
+            .unwrap();
+        let day = |d: u32| chrono::Utc.with_ymd_and_hms(2024, 3, d, 0, 0, 0).unwrap();
+        let series = fill_engagement_series(&[(day(8), 3, 1), (day(10), 0, 2)], today, 4);
+        assert_eq!(
+            series,
+            vec![
+                ProductEngagementDay {
+                    day: day(7),
+                    likes: 0,
+                    favorites: 0
+                },
+                ProductEngagementDay {
+                    day: day(8),
+                    likes: 3,
+                    favorites: 1
+                },
+                ProductEngagementDay {
+                    day: day(9),
+                    likes: 0,
+                    favorites: 0
+                },
+                ProductEngagementDay {
+                    day: day(10),
+                    likes: 0,
+                    favorites: 2
+                },
+            ]
+        );
+        assert_eq!(fill_engagement_series(&[], today, 10_000).len(), 365);
+    }
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            return Err(anyhow::anyhow!(
-                "Failed to upsert categories: {}. Body: {}",
-                status,
-                body
-            ));
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_list_developers_pages_sorts_and_searches() {
+        let pool = test_pool().await;
+        let db = test_database(&pool);
+
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        let emails: Vec<String> = (0..3)
+            .map(|i| format!("dir-{}-{}@example.com", i, suffix))
+            .collect();
+        for (i, email) in emails.iter().enumerate() {
+            sqlx::query("INSERT INTO developers (email, name) VALUES ($1, $2)")
+                .bind(email)
+                .bind(format!("Dir {} {}", i, suffix))
+                .execute(&pool)
+                .await
+                .expect("insert developer");
+        }
+        // 关注记录的邮箱大小写与 developers 不一致时也应计入
+        for (developer_email, user_id) in
+            [(emails[2].clone(), "u1"), (emails[2].to_uppercase(), "u2")]
+        {
+            sqlx::query("INSERT INTO developer_follows (developer_email, user_id) VALUES ($1, $2)")
+                .bind(&developer_email)
+                .bind(user_id)
+                .execute(&pool)
+                .await
+                .expect("insert follow");
         }
 
-        let returned: Vec<Category> = response.json().await?;
-        Ok(returned.len())
-    }
+        let (page, total) = db
+            .list_developers(Some(&suffix), DeveloperDirectorySort::Name, 2, 0)
+            .await
+            .expect("first page");
+        assert_eq!(total, 3);
+        assert_eq!(
+            page.iter().map(|d| d.email.clone()).collect::<Vec<_>>(),
+            emails[..2].to_vec()
+        );
+        let (page, _) = db
+            .list_developers(Some(&suffix), DeveloperDirectorySort::Name, 2, 2)
+            .await
+            .expect("second page");
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].email, emails[2]);
 
-    pub async fn delete_category(&self, id: &str) -> Result<bool> {
-        if let Some(pool) = &self.postgres {
-            let id = strip_nul_str(id);
-            let res = sqlx::query("DELETE FROM categories WHERE id = $1")
-                .persistent(false)
-                .bind(id.as_ref())
-                .execute(pool)
-                .await?;
-            return Ok(res.rows_affected() > 0);
-        }
+        let (by_followers, _) = db
+            .list_developers(Some(&suffix), DeveloperDirectorySort::Followers, 1, 0)
+            .await
+            .expect("by followers");
+        assert_eq!(by_followers[0].email, emails[2]);
+        assert_eq!(by_followers[0].followers, 2);
 
-        let supabase = self
-            .supabase
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No database configured"))?;
+        sqlx::query("DELETE FROM developer_follows WHERE lower(developer_email) = ANY($1)")
+            .bind(&emails)
+            .execute(&pool)
+            .await
+            .expect("cleanup follows");
+        sqlx::query("DELETE FROM developers WHERE email = ANY($1)")
+            .bind(&emails)
+            .execute(&pool)
+            .await
+            .expect("cleanup developers");
+    }
 
-        let url = Url::parse(&format!(
-            "{}/rest/v1/categories?id=eq.{}",
-            supabase.supabase_url,
-            urlencoding::encode(id)
-        ))?;
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_leaderboard_products_carry_maker_sponsor_badge() {
+        let pool = test_pool().await;
+        let db = test_database(&pool);
 
-        let response = supabase
-            .client
-            .delete(url)
-            .header("apikey", &supabase.supabase_key)
-            .header(
-                "Authorization",
-                &format!("Bearer {}", supabase.supabase_key),
-            )
-            .send()
-            .await?;
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        let maker_email = format!("badge-{}@example.com", suffix);
+        sqlx::query(
+            "INSERT INTO developers (email, name, sponsor_role, sponsor_verified) VALUES ($1, 'Badge', 'gold', TRUE)",
+        )
+        .bind(&maker_email)
+        .execute(&pool)
+        .await
+        .expect("insert developer");
+        let product_id = uuid::Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO products (id, name, slogan, description, website, category, maker_name, maker_email, status) \
+             VALUES ($1, $2, 'Slogan', 'Description', 'https://example.com', 'test', 'Badge', $3, 'approved')",
+        )
+        .bind(product_id)
+        .bind(format!("Badge {}", suffix))
+        .bind(&maker_email)
+        .execute(&pool)
+        .await
+        .expect("insert product");
+
+        let mut params = crate::handlers::leaderboard_query_params(None, 20);
+        params.search = Some(suffix.clone());
+        let products = db.get_products(params).await.expect("leaderboard products");
+        let entry = products
+            .iter()
+            .find(|p| p.id == product_id.to_string())
+            .expect("product in leaderboard");
+        assert_eq!(entry.maker_sponsor_role.as_deref(), Some("gold"));
+        assert!(entry.maker_sponsor_verified);
+
+        sqlx::query("DELETE FROM products WHERE id = $1")
+            .bind(product_id)
+            .execute(&pool)
+            .await
+            .expect("cleanup product");
+        sqlx::query("DELETE FROM developers WHERE email = $1")
+            .bind(&maker_email)
+            .execute(&pool)
+            .await
+            .expect("cleanup developer");
+    }
 
-        Ok(response.status() == 204)
+    #[test]
+    fn test_http_timeout_secs_from_env_value_defaults_and_clamps() {
+        assert_eq!(http_timeout_secs_from_env_value(None, 12), 12);
+        assert_eq!(http_timeout_secs_from_env_value(Some("abc"), 8), 8);
+        assert_eq!(http_timeout_secs_from_env_value(Some(" 30 "), 8), 30);
+        assert_eq!(http_timeout_secs_from_env_value(Some("0"), 8), 1);
+        assert_eq!(http_timeout_secs_from_env_value(Some("600"), 8), 120);
     }
 
-    pub async fn search_developers(&self, query: &str, limit: i64) -> Result<Vec<Developer>> {
-        let limit = limit.clamp(1, 50);
+    #[tokio::test]
+    async fn test_resend_send_times_out_on_hung_server() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind mock server");
+        let addr = listener.local_addr().expect("mock server addr");
+        tokio::spawn(async move {
+            // 接受连接但从不响应，模拟卡住的 Resend。
+            while let Ok((socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    tokio::time::sleep(Duration::from_secs(30)).await;
+                    drop(socket);
+                });
+            }
+        });
 
-        if let Some(pool) = &self.postgres {
-            let query = strip_nul_str(query);
-            let q = format!("%{}%", query);
-            let attempt = sqlx::query_as::<_, DeveloperRow>(
-                "SELECT email, name, avatar_url, website, sponsor_role, sponsor_verified \
-                 FROM developers \
-                 WHERE name ILIKE $1 OR email ILIKE $1 OR website ILIKE $1 \
-                 ORDER BY name ASC \
-                 LIMIT $2",
-            )
-            .persistent(false)
-            .bind(q.as_str())
-            .bind(limit)
-            .fetch_all(pool)
-            .await;
+        let client = resend_http_client_with_timeout(Duration::from_millis(300));
+        let endpoint = format!("http://{}/emails", addr);
+        let payload = serde_json::json!({ "to": ["slow@example.com"] });
+        let started = Instant::now();
+        let err = post_resend_email(&client, &endpoint, "re_test", &payload)
+            .await
+            .expect_err("hung send should time out");
+        assert!(is_http_timeout_error(&err), "unexpected error: {:?}", err);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
 
-            match attempt {
-                Ok(rows) => return Ok(rows.into_iter().map(map_developer_row).collect()),
-                Err(e) => {
-                    let e: anyhow::Error = e.into();
-                    if is_missing_column_error(&e, "sponsor_role")
-                        || is_missing_column_error(&e, "sponsor_verified")
-                    {
-                        let rows = sqlx::query_as::<_, DeveloperRow>(
-                            "SELECT email, name, avatar_url, website, NULL::text as sponsor_role, FALSE as sponsor_verified \
-                             FROM developers \
-                             WHERE name ILIKE $1 OR email ILIKE $1 OR website ILIKE $1 \
-                             ORDER BY name ASC \
-                             LIMIT $2",
-                        )
-                        .persistent(false)
-                        .bind(q.as_str())
-                        .bind(limit)
-                        .fetch_all(pool)
-                        .await?;
-                        return Ok(rows.into_iter().map(map_developer_row).collect());
-                    }
-                    return Err(e);
-                }
+    #[test]
+    fn test_newsletter_concurrency_from_env_value_defaults_and_clamps() {
+        assert_eq!(newsletter_concurrency_from_env_value(None), 5);
+        assert_eq!(newsletter_concurrency_from_env_value(Some("x")), 5);
+        assert_eq!(newsletter_concurrency_from_env_value(Some("0")), 1);
+        assert_eq!(newsletter_concurrency_from_env_value(Some(" 8 ")), 8);
+        assert_eq!(newsletter_concurrency_from_env_value(Some("500")), 20);
+    }
+
+    #[tokio::test]
+    async fn test_run_bounded_concurrent_limits_in_flight_sends() {
+        use std::sync::atomic::AtomicUsize;
+
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_seen = Arc::new(AtomicUsize::new(0));
+        let recipients: Vec<usize> = (0..12).collect();
+        let results = run_bounded_concurrent(recipients, 3, |i: usize| {
+            let in_flight = in_flight.clone();
+            let max_seen = max_seen.clone();
+            async move {
+                // 模拟发送：记录同时在途数量。
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_seen.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                i * 10
             }
-        }
+        })
+        .await;
 
-        Ok(Vec::new())
+        assert_eq!(max_seen.load(Ordering::SeqCst), 3);
+        assert_eq!(
+            results,
+            (0..12)
+                .map(|i| Some(i * 10))
+                .collect::<Vec<Option<usize>>>()
+        );
     }
 
-    pub async fn get_top_developers_by_followers(
-        &self,
-        limit: i64,
-    ) -> Result<Vec<DeveloperWithFollowers>> {
-        let limit = limit.clamp(1, 50);
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_newsletter_dry_run_renders_without_marking_sent() {
+        let pool = test_pool().await;
+        let db = test_database(&pool);
 
-        if let Some(pool) = &self.postgres {
-            let mut tx = pool.begin().await?;
-            let attempt = sqlx::query_as::<_, DeveloperWithFollowersRow>(
-                "SELECT \
-                    d.email, \
-                    d.name, \
-                    d.avatar_url, \
-                    d.website, \
-                    d.sponsor_role, \
-                    d.sponsor_verified, \
-                    COUNT(f.id)::bigint::text as followers \
-                 FROM developers d \
-                 LEFT JOIN developer_follows f ON f.developer_email = d.email \
-                 GROUP BY d.email, d.name, d.avatar_url, d.website, d.sponsor_role, d.sponsor_verified \
-                 HAVING COUNT(f.id) > 0 \
-                 ORDER BY COUNT(f.id) DESC, d.name ASC \
-                 LIMIT $1",
-            )
-            .persistent(false)
-            .bind(limit)
-            .fetch_all(&mut *tx)
-            .await;
+        let email = format!("dry-run-{}@example.com", uuid::Uuid::new_v4().simple());
+        sqlx::query(
+            "INSERT INTO newsletter_subscriptions (email, unsubscribed) VALUES ($1, FALSE)",
+        )
+        .bind(&email)
+        .execute(&pool)
+        .await
+        .expect("insert subscription");
 
-            match attempt {
-                Ok(rows) => {
-                    tx.commit().await?;
-                    return Ok(rows
-                        .into_iter()
-                        .map(map_developer_with_followers_row)
-                        .collect());
-                }
-                Err(e) => {
-                    let _ = tx.rollback().await;
-                    let e: anyhow::Error = e.into();
-                    if is_missing_column_error(&e, "sponsor_role")
-                        || is_missing_column_error(&e, "sponsor_verified")
-                    {
-                        let mut tx = pool.begin().await?;
-                        let rows = sqlx::query_as::<_, DeveloperWithFollowersRow>(
-                            "SELECT \
-                                d.email, \
-                                d.name, \
-                                d.avatar_url, \
-                                d.website, \
-                                NULL::text as sponsor_role, \
-                                FALSE as sponsor_verified, \
-                                COUNT(f.id)::bigint::text as followers \
-                             FROM developers d \
-                             LEFT JOIN developer_follows f ON f.developer_email = d.email \
-                             GROUP BY d.email, d.name, d.avatar_url, d.website \
-                             HAVING COUNT(f.id) > 0 \
-                             ORDER BY COUNT(f.id) DESC, d.name ASC \
-                             LIMIT $1",
-                        )
-                        .persistent(false)
-                        .bind(limit)
-                        .fetch_all(&mut *tx)
-                        .await?;
-                        tx.commit().await?;
-                        return Ok(rows
-                            .into_iter()
-                            .map(map_developer_with_followers_row)
-                            .collect());
-                    }
-                    return Err(e);
-                }
-            }
-        }
+        let report = db
+            .run_weekly_newsletter(NewsletterRunOptions {
+                ignore_schedule: true,
+                dry_run: true,
+            })
+            .await
+            .expect("dry run");
+        assert!(report.recipients >= 1);
+        assert_eq!(report.sent, 0);
+        let sample = report.sample.expect("dry run sample");
+        assert!(!sample.subject.is_empty());
+
+        let last_sent_week: Option<String> = sqlx::query_scalar(
+            "SELECT last_sent_week FROM newsletter_subscriptions WHERE email = $1",
+        )
+        .bind(&email)
+        .fetch_one(&pool)
+        .await
+        .expect("load subscription");
+        assert!(last_sent_week.is_none());
 
-        Ok(Vec::new())
+        sqlx::query("DELETE FROM newsletter_subscriptions WHERE email = $1")
+            .bind(&email)
+            .execute(&pool)
+            .await
+            .expect("cleanup subscription");
     }
 
-    pub async fn get_recent_developers_by_created_at(
-        &self,
-        limit: i64,
-    ) -> Result<Vec<DeveloperWithFollowers>> {
-        let limit = limit.clamp(1, 50);
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_product_active_grants_excludes_expired_and_future() {
+        let pool = test_pool().await;
+        ensure_sponsorship_tables(&pool)
+            .await
+            .expect("ensure tables");
+        let db = test_database(&pool);
 
-        if let Some(pool) = &self.postgres {
-            let mut tx = pool.begin().await?;
-            let attempt = sqlx::query_as::<_, DeveloperWithFollowersRow>(
-                "SELECT \
-                    d.email, \
-                    d.name, \
-                    d.avatar_url, \
-                    d.website, \
-                    d.sponsor_role, \
-                    d.sponsor_verified, \
-                    COUNT(f.id)::bigint::text as followers \
-                 FROM developers d \
-                 LEFT JOIN developer_follows f ON f.developer_email = d.email \
-                 GROUP BY d.email, d.name, d.avatar_url, d.website, d.sponsor_role, d.sponsor_verified, d.created_at \
-                 ORDER BY d.created_at DESC, d.name ASC \
-                 LIMIT $1",
+        let product_id = uuid::Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO products (id, name, slogan, description, website, category, maker_name, maker_email, status) \
+             VALUES ($1, 'Sponsored', 'Slogan', 'Description', 'https://example.com', 'test', 'Maker', 'maker@example.com', 'approved')",
+        )
+        .bind(product_id)
+        .execute(&pool)
+        .await
+        .expect("insert product");
+
+        let now = chrono::Utc::now();
+        let windows = [
+            (
+                now - chrono::Duration::days(40),
+                now - chrono::Duration::days(10),
+            ),
+            (
+                now - chrono::Duration::days(1),
+                now + chrono::Duration::days(20),
+            ),
+            (
+                now + chrono::Duration::days(20),
+                now + chrono::Duration::days(50),
+            ),
+        ];
+        for (starts_at, ends_at) in windows {
+            sqlx::query(
+                "INSERT INTO sponsorship_grants (product_id, placement, slot_index, starts_at, ends_at, source) \
+                 VALUES ($1, 'home_right', 0, $2, $3, 'manual')",
             )
-            .persistent(false)
-            .bind(limit)
-            .fetch_all(&mut *tx)
-            .await;
+            .bind(product_id)
+            .bind(starts_at)
+            .bind(ends_at)
+            .execute(&pool)
+            .await
+            .expect("insert grant");
+        }
 
-            match attempt {
-                Ok(rows) => {
-                    tx.commit().await?;
-                    return Ok(rows
-                        .into_iter()
-                        .map(map_developer_with_followers_row)
-                        .collect());
-                }
-                Err(e) => {
-                    let _ = tx.rollback().await;
-                    let e: anyhow::Error = e.into();
-                    if is_missing_column_error(&e, "sponsor_role")
-                        || is_missing_column_error(&e, "sponsor_verified")
-                    {
-                        let mut tx = pool.begin().await?;
-                        let rows = sqlx::query_as::<_, DeveloperWithFollowersRow>(
-                            "SELECT \
-                                d.email, \
-                                d.name, \
-                                d.avatar_url, \
-                                d.website, \
-                                NULL::text as sponsor_role, \
-                                FALSE as sponsor_verified, \
-                                COUNT(f.id)::bigint::text as followers \
-                             FROM developers d \
-                             LEFT JOIN developer_follows f ON f.developer_email = d.email \
-                             GROUP BY d.email, d.name, d.avatar_url, d.website, d.created_at \
-                             ORDER BY d.created_at DESC, d.name ASC \
-                             LIMIT $1",
-                        )
-                        .persistent(false)
-                        .bind(limit)
-                        .fetch_all(&mut *tx)
-                        .await?;
-                        tx.commit().await?;
-                        return Ok(rows
-                            .into_iter()
-                            .map(map_developer_with_followers_row)
-                            .collect());
-                    }
-                    return Err(e);
-                }
-            }
+        let grants = db
+            .get_product_active_grants(&product_id.to_string(), now)
+            .await
+            .expect("active grants");
+        assert_eq!(grants.len(), 1);
+        assert_eq!(grants[0].placement, "home_right");
+        assert_eq!(grants[0].ends_at.timestamp(), windows[1].1.timestamp());
+
+        sqlx::query("DELETE FROM sponsorship_grants WHERE product_id = $1")
+            .bind(product_id)
+            .execute(&pool)
+            .await
+            .expect("cleanup grants");
+        sqlx::query("DELETE FROM products WHERE id = $1")
+            .bind(product_id)
+            .execute(&pool)
+            .await
+            .expect("cleanup product");
+    }
+
+    #[test]
+    fn test_normalize_website_url_prepends_https() {
+        assert_eq!(
+            normalize_website_url("website", "example.com"),
+            Ok(Some("https://example.com".to_string()))
+        );
+        assert_eq!(
+            normalize_website_url("website", " example.com:8080/about "),
+            Ok(Some("https://example.com:8080/about".to_string()))
+        );
+        assert_eq!(
+            normalize_website_url("website", "http://example.com"),
+            Ok(Some("http://example.com".to_string()))
+        );
+        assert_eq!(
+            normalize_website_url("website", "HTTPS://Example.com/x"),
+            Ok(Some("HTTPS://Example.com/x".to_string()))
+        );
+        assert_eq!(normalize_website_url("website", "   "), Ok(None));
+    }
+
+    #[test]
+    fn test_normalize_website_url_rejects_non_http_schemes() {
+        for raw in [
+            "ftp://example.com",
+            "javascript:alert(1)",
+            "JavaScript:alert(1)",
+            "mailto:me@example.com",
+            "https://",
+        ] {
+            let err = normalize_website_url("maker_website", raw).unwrap_err();
+            assert_eq!(err.field, "maker_website");
         }
+        assert_eq!(
+            normalize_website_url("website", "ftp://example.com")
+                .unwrap_err()
+                .localized_message("en"),
+            "Field website must be an http(s) URL."
+        );
+    }
 
-        Ok(Vec::new())
+    #[test]
+    fn test_truncate_audit_summary_caps_length() {
+        assert_eq!(truncate_audit_summary("  short  "), "short");
+        let long = "x".repeat(ADMIN_AUDIT_SUMMARY_MAX_CHARS + 20);
+        let truncated = truncate_audit_summary(&long);
+        assert_eq!(truncated.chars().count(), ADMIN_AUDIT_SUMMARY_MAX_CHARS);
+        assert!(truncated.ends_with('…'));
     }
 
-    pub async fn get_developer_popularity_last_month(
-        &self,
-        limit: i64,
-    ) -> Result<Vec<DeveloperPopularity>> {
-        let limit = limit.clamp(1, 50);
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_admin_audit_log_records_and_filters() {
+        let pool = test_pool().await;
+        let db = test_database(&pool);
+
+        let entity_id = uuid::Uuid::new_v4().to_string();
+        db.record_admin_audit(
+            "ops@example.com",
+            "category.delete",
+            "category",
+            Some(&entity_id),
+            None,
+        )
+        .await
+        .expect("record delete");
+        db.record_admin_audit(
+            "ops@example.com",
+            "category.upsert",
+            "category",
+            Some(&entity_id),
+            Some("upserted=1"),
+        )
+        .await
+        .expect("record upsert");
+
+        let (items, total) = db
+            .list_admin_audit_log(AdminAuditLogListParams {
+                entity_id: Some(entity_id.clone()),
+                ..AdminAuditLogListParams::default()
+            })
+            .await
+            .expect("list audit log");
+        assert_eq!(total, 2);
+        assert_eq!(items[0].action, "category.upsert");
+        assert_eq!(items[0].payload_summary.as_deref(), Some("upserted=1"));
+
+        let (items, total) = db
+            .list_admin_audit_log(AdminAuditLogListParams {
+                entity_id: Some(entity_id.clone()),
+                action: Some("category.delete".to_string()),
+                ..AdminAuditLogListParams::default()
+            })
+            .await
+            .expect("list filtered audit log");
+        assert_eq!(total, 1);
+        assert_eq!(items[0].actor, "ops@example.com");
 
-        if let Some(pool) = &self.postgres {
-            let now = chrono::Utc::now();
-            let first_day_current_month = chrono::Utc
-                .with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
-                .single()
-                .unwrap_or_else(chrono::Utc::now);
-            let first_day_last_month = (first_day_current_month - chrono::Duration::days(1))
-                .with_day(1)
-                .unwrap_or(first_day_current_month - chrono::Duration::days(30));
+        sqlx::query("DELETE FROM admin_audit_log WHERE entity_id = $1")
+            .bind(&entity_id)
+            .execute(&pool)
+            .await
+            .expect("cleanup audit log");
+    }
 
-            let mut tx = pool.begin().await?;
-            let attempt = sqlx::query_as::<_, DeveloperPopularityRow>(
-                "WITH likes AS ( \
-                    SELECT p.maker_email as email, COUNT(l.id)::bigint as likes \
-                    FROM products p \
-                    JOIN product_likes l ON l.product_id = p.id \
-                    WHERE l.created_at >= $1 AND l.created_at < $2 \
-                    GROUP BY p.maker_email \
-                 ), \
-                 favorites AS ( \
-                    SELECT p.maker_email as email, COUNT(f.id)::bigint as favorites \
-                    FROM products p \
-                    JOIN product_favorites f ON f.product_id = p.id \
-                    WHERE f.created_at >= $1 AND f.created_at < $2 \
-                    GROUP BY p.maker_email \
-                 ) \
-                 SELECT \
-                    d.email, \
-                    d.name, \
-                    d.avatar_url, \
-                    d.website, \
-                    d.sponsor_role, \
-                    d.sponsor_verified, \
-                    COALESCE(l.likes, 0)::bigint as likes, \
-                    COALESCE(f.favorites, 0)::bigint as favorites, \
-                    (COALESCE(l.likes, 0) + COALESCE(f.favorites, 0))::bigint as score \
-                 FROM developers d \
-                 LEFT JOIN likes l ON l.email = d.email \
-                 LEFT JOIN favorites f ON f.email = d.email \
-                 ORDER BY score DESC, favorites DESC, likes DESC, d.name ASC \
-                 LIMIT $3",
-            )
-            .persistent(false)
-            .bind(first_day_last_month)
-            .bind(first_day_current_month)
-            .bind(limit)
-            .fetch_all(&mut *tx)
-            .await;
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_patch_home_module_state_mode_only_preserves_ids() {
+        let pool = test_pool().await;
+        let db = test_database(&pool);
+
+        let key = format!("test_patch_{}", uuid::Uuid::new_v4().simple());
+        let today_ids = vec!["a".to_string(), "b".to_string()];
+        db.upsert_home_module_state(HomeModuleState {
+            key: key.clone(),
+            mode: Some("manual".to_string()),
+            day_key: None,
+            remaining_ids: vec!["c".to_string()],
+            today_ids: today_ids.clone(),
+        })
+        .await
+        .expect("seed state");
 
-            match attempt {
-                Ok(rows) => {
-                    tx.commit().await?;
-                    return Ok(rows.into_iter().map(map_developer_popularity_row).collect());
-                }
-                Err(e) => {
-                    let _ = tx.rollback().await;
-                    let e: anyhow::Error = e.into();
-                    if is_missing_column_error(&e, "sponsor_role")
-                        || is_missing_column_error(&e, "sponsor_verified")
-                    {
-                        let mut tx = pool.begin().await?;
-                        let rows = sqlx::query_as::<_, DeveloperPopularityRow>(
-                            "WITH likes AS ( \
-                                SELECT p.maker_email as email, COUNT(l.id)::bigint as likes \
-                                FROM products p \
-                                JOIN product_likes l ON l.product_id = p.id \
-                                WHERE l.created_at >= $1 AND l.created_at < $2 \
-                                GROUP BY p.maker_email \
-                             ), \
-                             favorites AS ( \
-                                SELECT p.maker_email as email, COUNT(f.id)::bigint as favorites \
-                                FROM products p \
-                                JOIN product_favorites f ON f.product_id = p.id \
-                                WHERE f.created_at >= $1 AND f.created_at < $2 \
-                                GROUP BY p.maker_email \
-                             ) \
-                             SELECT \
-                                d.email, \
-                                d.name, \
-                                d.avatar_url, \
-                                d.website, \
-                                NULL::text as sponsor_role, \
-                                FALSE as sponsor_verified, \
-                                COALESCE(l.likes, 0)::bigint as likes, \
-                                COALESCE(f.favorites, 0)::bigint as favorites, \
-                                (COALESCE(l.likes, 0) + COALESCE(f.favorites, 0))::bigint as score \
-                             FROM developers d \
-                             LEFT JOIN likes l ON l.email = d.email \
-                             LEFT JOIN favorites f ON f.email = d.email \
-                             ORDER BY score DESC, favorites DESC, likes DESC, d.name ASC \
-                             LIMIT $3",
-                        )
-                        .persistent(false)
-                        .bind(first_day_last_month)
-                        .bind(first_day_current_month)
-                        .bind(limit)
-                        .fetch_all(&mut *tx)
-                        .await?;
-                        tx.commit().await?;
-                        return Ok(rows.into_iter().map(map_developer_popularity_row).collect());
-                    }
-                    return Err(e);
-                }
-            }
-        }
+        db.patch_home_module_state(
+            &key,
+            HomeModuleStatePatch {
+                mode: Some("free".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("patch mode");
 
-        Ok(Vec::new())
+        let state = db
+            .get_home_module_state(&key)
+            .await
+            .expect("load state")
+            .expect("state exists");
+        assert_eq!(state.mode.as_deref(), Some("free"));
+        assert_eq!(state.today_ids, today_ids);
+        assert_eq!(state.remaining_ids, vec!["c".to_string()]);
+
+        sqlx::query("DELETE FROM home_module_state WHERE key = $1")
+            .bind(&key)
+            .execute(&pool)
+            .await
+            .ok();
     }
 
-    pub async fn get_developer_popularity_last_week(
-        &self,
-        limit: i64,
-    ) -> Result<Vec<DeveloperPopularity>> {
-        let limit = limit.clamp(1, 50);
+    #[tokio::test]
+    async fn test_get_products_by_ids_rejects_oversized_list() {
+        let db = Database {
+            supabase: None,
+            postgres: None,
+            overview_stats_cache: Mutex::new(None),
+            featured_history_recorded: Mutex::new(None),
+        };
+        let ids: Vec<String> = (0..=MAX_PRODUCTS_BY_IDS)
+            .map(|i| format!("id-{}", i))
+            .collect();
+        let err = db.get_products_by_ids(&ids, None).await.unwrap_err();
+        let too_many = err
+            .downcast_ref::<TooManyProductIdsError>()
+            .expect("TooManyProductIdsError");
+        assert_eq!(too_many.actual, MAX_PRODUCTS_BY_IDS + 1);
+    }
 
-        if let Some(pool) = &self.postgres {
-            let now = chrono::Utc::now();
-            let since = now - chrono::Duration::days(7);
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_get_products_by_ids_preserves_order_and_filters_language() {
+        let pool = test_pool().await;
+        let db = test_database(&pool);
 
-            let mut tx = pool.begin().await?;
-            let attempt = sqlx::query_as::<_, DeveloperPopularityRow>(
-                "WITH likes AS ( \
-                    SELECT p.maker_email as email, COUNT(l.id)::bigint as likes \
-                    FROM products p \
-                    JOIN product_likes l ON l.product_id = p.id \
-                    WHERE l.created_at >= $1 \
-                    GROUP BY p.maker_email \
-                 ), \
-                 favorites AS ( \
-                    SELECT p.maker_email as email, COUNT(f.id)::bigint as favorites \
-                    FROM products p \
-                    JOIN product_favorites f ON f.product_id = p.id \
-                    WHERE f.created_at >= $1 \
-                    GROUP BY p.maker_email \
-                 ) \
-                 SELECT \
-                    d.email, \
-                    d.name, \
-                    d.avatar_url, \
-                    d.website, \
-                    d.sponsor_role, \
-                    d.sponsor_verified, \
-                    COALESCE(l.likes, 0)::bigint as likes, \
-                    COALESCE(f.favorites, 0)::bigint as favorites, \
-                    (COALESCE(l.likes, 0) + COALESCE(f.favorites, 0))::bigint as score \
-                 FROM developers d \
-                 LEFT JOIN likes l ON l.email = d.email \
-                 LEFT JOIN favorites f ON f.email = d.email \
-                 WHERE (COALESCE(l.likes, 0) + COALESCE(f.favorites, 0)) > 0 \
-                 ORDER BY score DESC, favorites DESC, likes DESC, d.name ASC \
-                 LIMIT $2",
+        let mut ids = Vec::new();
+        for (i, language) in ["en", "zh", "en"].iter().enumerate() {
+            let id = uuid::Uuid::new_v4();
+            sqlx::query(
+                "INSERT INTO products (id, name, slogan, description, website, category, maker_name, maker_email, language, status) \
+                 VALUES ($1, $2, 'slogan', 'description', 'https://example.com', 'tools', 'maker', 'maker@example.com', $3, 'approved')",
             )
-            .persistent(false)
-            .bind(since)
-            .bind(limit)
-            .fetch_all(&mut *tx)
-            .await;
+            .bind(id)
+            .bind(format!("by-ids-{}", i))
+            .bind(*language)
+            .execute(&pool)
+            .await
+            .expect("insert product");
+            ids.push(id.to_string());
+        }
+        let requested = vec![ids[2].clone(), ids[0].clone(), ids[1].clone()];
 
-            match attempt {
-                Ok(rows) => {
-                    tx.commit().await?;
-                    return Ok(rows.into_iter().map(map_developer_popularity_row).collect());
-                }
-                Err(e) => {
-                    let _ = tx.rollback().await;
-                    let e: anyhow::Error = e.into();
-                    if is_missing_column_error(&e, "sponsor_role")
-                        || is_missing_column_error(&e, "sponsor_verified")
-                    {
-                        let mut tx = pool.begin().await?;
-                        let rows = sqlx::query_as::<_, DeveloperPopularityRow>(
-                            "WITH likes AS ( \
-                                SELECT p.maker_email as email, COUNT(l.id)::bigint as likes \
-                                FROM products p \
-                                JOIN product_likes l ON l.product_id = p.id \
-                                WHERE l.created_at >= $1 \
-                                GROUP BY p.maker_email \
-                             ), \
-                             favorites AS ( \
-                                SELECT p.maker_email as email, COUNT(f.id)::bigint as favorites \
-                                FROM products p \
-                                JOIN product_favorites f ON f.product_id = p.id \
-                                WHERE f.created_at >= $1 \
-                                GROUP BY p.maker_email \
-                             ) \
-                             SELECT \
-                                d.email, \
-                                d.name, \
-                                d.avatar_url, \
-                                d.website, \
-                                NULL::text as sponsor_role, \
-                                FALSE as sponsor_verified, \
-                                COALESCE(l.likes, 0)::bigint as likes, \
-                                COALESCE(f.favorites, 0)::bigint as favorites, \
-                                (COALESCE(l.likes, 0) + COALESCE(f.favorites, 0))::bigint as score \
-                             FROM developers d \
-                             LEFT JOIN likes l ON l.email = d.email \
-                             LEFT JOIN favorites f ON f.email = d.email \
-                             WHERE (COALESCE(l.likes, 0) + COALESCE(f.favorites, 0)) > 0 \
-                             ORDER BY score DESC, favorites DESC, likes DESC, d.name ASC \
-                             LIMIT $2",
-                        )
-                        .persistent(false)
-                        .bind(since)
-                        .bind(limit)
-                        .fetch_all(&mut *tx)
-                        .await?;
-                        tx.commit().await?;
-                        return Ok(rows.into_iter().map(map_developer_popularity_row).collect());
-                    }
-                    return Err(e);
-                }
-            }
+        let all = db.get_products_by_ids(&requested, None).await.unwrap();
+        let got: Vec<String> = all.into_iter().map(|p| p.id).collect();
+        assert_eq!(got, requested);
+
+        let en = db
+            .get_products_by_ids(&requested, Some("en"))
+            .await
+            .unwrap();
+        let got: Vec<String> = en.into_iter().map(|p| p.id).collect();
+        assert_eq!(got, vec![ids[2].clone(), ids[0].clone()]);
+
+        for id in &ids {
+            sqlx::query("DELETE FROM products WHERE id = $1::uuid")
+                .bind(id)
+                .execute(&pool)
+                .await
+                .ok();
         }
+    }
 
-        Ok(Vec::new())
+    /**
+     * seed_sponsorship_order
+     * 集成测试用：插入一个产品及其 created 状态的订单，槽位随机以免与其他数据互相影响。
+     */
+    async fn seed_sponsorship_order(pool: &PgPool, slot_index: i32) -> (uuid::Uuid, uuid::Uuid) {
+        let product_id = uuid::Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO products (id, name, slogan, description, website, category, maker_name, maker_email) \
+             VALUES ($1, 'Grant flow', 'Slogan', 'Description', 'https://example.com', 'test', 'Maker', 'maker@example.com')",
+        )
+        .bind(product_id)
+        .execute(pool)
+        .await
+        .expect("insert product");
+        let order_id = uuid::Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO sponsorship_orders (id, user_email, product_id, placement, slot_index, requested_months) \
+             VALUES ($1, 'maker@example.com', $2, 'home_right', $3, 1)",
+        )
+        .bind(order_id)
+        .bind(product_id)
+        .bind(slot_index)
+        .execute(pool)
+        .await
+        .expect("insert order");
+        (product_id, order_id)
+    }
+
+    // 数据库集成测试标记为 #[ignore]：设置 TEST_DATABASE_URL 后以 `cargo test -- --ignored` 运行。
+    async fn test_pool() -> PgPool {
+        let url = env::var("TEST_DATABASE_URL").expect(
+            "TEST_DATABASE_URL must be set to run database tests (cargo test -- --ignored)",
+        );
+        PgPool::connect(&url)
+            .await
+            .expect("connect TEST_DATABASE_URL")
+    }
+
+    fn test_database(pool: &PgPool) -> Database {
+        Database::with_postgres_pool(pool.clone())
+    }
+
+    async fn grant_flow_db() -> (PgPool, Database) {
+        let pool = test_pool().await;
+        ensure_sponsorship_tables(&pool)
+            .await
+            .expect("ensure sponsorship");
+        let db = test_database(&pool);
+        (pool, db)
     }
 
-    pub async fn get_developer_center_stats(&self, email: &str) -> Result<DeveloperCenterStats> {
-        if let Some(pool) = &self.postgres {
-            let email = strip_nul_str(email);
-            let row = sqlx::query_as::<_, DeveloperCenterStatsRow>(
-                "SELECT \
-                    (SELECT COUNT(*)::bigint FROM developer_follows f WHERE lower(f.developer_email) = lower($1)) as followers, \
-                    (SELECT COUNT(*)::bigint FROM product_likes l JOIN products p ON p.id = l.product_id WHERE lower(p.maker_email) = lower($1)) as total_likes, \
-                    (SELECT COUNT(*)::bigint FROM product_favorites f2 JOIN products p2 ON p2.id = f2.product_id WHERE lower(p2.maker_email) = lower($1)) as total_favorites",
-            )
-            .persistent(false)
-            .bind(email.as_ref())
-            .fetch_one(pool)
-            .await?;
+    fn isolated_test_slot() -> i32 {
+        1_000 + (uuid::Uuid::new_v4().as_u128() % 1_000_000) as i32
+    }
 
-            return Ok(map_developer_center_stats_row(row));
+    async fn cleanup_grant_flow_products(pool: &PgPool, product_ids: &[uuid::Uuid]) {
+        for id in product_ids {
+            sqlx::query("DELETE FROM sponsorship_grants WHERE product_id = $1")
+                .bind(id)
+                .execute(pool)
+                .await
+                .ok();
+            sqlx::query("DELETE FROM products WHERE id = $1")
+                .bind(id)
+                .execute(pool)
+                .await
+                .ok();
         }
+    }
 
-        let supabase = self
-            .supabase
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No database configured"))?;
-
-        let email = strip_nul_str(email).into_owned();
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_grant_flow_double_webhook_creates_one_grant() {
+        let (pool, db) = grant_flow_db().await;
+        let (product_id, order_id) = seed_sponsorship_order(&pool, isolated_test_slot()).await;
+        let order = order_id.to_string();
 
-        let followers = supabase_count(
-            supabase,
-            "developer_follows",
-            &[
-                ("select", "id".to_string()),
-                ("developer_email", format!("eq.{}", email)),
-            ],
-        )
-        .await?;
+        let (a, b) = tokio::join!(
+            db.create_sponsorship_grant_and_mark_order_paid(&order, Some("evt_1"), 500, 1, "creem"),
+            db.create_sponsorship_grant_and_mark_order_paid(&order, Some("evt_1"), 500, 1, "creem"),
+        );
+        let (a, b) = (a.expect("first delivery"), b.expect("second delivery"));
+        assert_eq!(a.id, b.id);
 
-        let total_likes = supabase_count(
-            supabase,
-            "product_likes",
-            &[
-                ("select", "id,products!inner(maker_email)".to_string()),
-                ("products.maker_email", format!("eq.{}", email)),
-            ],
-        )
-        .await?;
+        let again = db
+            .create_sponsorship_grant_and_mark_order_paid(&order, Some("evt_1"), 500, 1, "creem")
+            .await
+            .expect("late redelivery");
+        assert_eq!(again.id, a.id);
 
-        let total_favorites = supabase_count(
-            supabase,
-            "product_favorites",
-            &[
-                ("select", "id,products!inner(maker_email)".to_string()),
-                ("products.maker_email", format!("eq.{}", email)),
-            ],
-        )
-        .await?;
+        let grants: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM sponsorship_grants WHERE order_id = $1")
+                .bind(order_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(grants, 1);
 
-        Ok(DeveloperCenterStats {
-            followers,
-            total_likes,
-            total_favorites,
-        })
+        cleanup_grant_flow_products(&pool, &[product_id]).await;
     }
 
-    pub async fn follow_developer(&self, email: &str, user_id: &str) -> Result<()> {
-        if let Some(pool) = &self.postgres {
-            let email = strip_nul_str(email);
-            let user_id = strip_nul_str(user_id);
-            sqlx::query(
-                "INSERT INTO developer_follows (developer_email, user_id) \
-                 VALUES ($1, $2) \
-                 ON CONFLICT (developer_email, user_id) DO NOTHING",
-            )
-            .persistent(false)
-            .bind(email.as_ref())
-            .bind(user_id.as_ref())
-            .execute(pool)
-            .await?;
-            return Ok(());
-        }
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_grant_flow_back_to_back_grants_do_not_overlap() {
+        let (pool, db) = grant_flow_db().await;
+        let slot = isolated_test_slot();
+        let (first_product, first_order) = seed_sponsorship_order(&pool, slot).await;
+        let (second_product, second_order) = seed_sponsorship_order(&pool, slot).await;
+
+        let (first, second) = tokio::join!(
+            db.create_sponsorship_grant_and_mark_order_paid(
+                &first_order.to_string(),
+                None,
+                500,
+                1,
+                "manual"
+            ),
+            db.create_sponsorship_grant_and_mark_order_paid(
+                &second_order.to_string(),
+                None,
+                500,
+                1,
+                "manual"
+            ),
+        );
+        let mut grants = vec![first.expect("first grant"), second.expect("second grant")];
+        grants.sort_by_key(|g| g.starts_at);
+        assert_eq!(grants[1].starts_at, grants[0].ends_at);
 
-        Err(anyhow::anyhow!("No database configured"))
+        cleanup_grant_flow_products(&pool, &[first_product, second_product]).await;
     }
 
-    pub async fn unfollow_developer(&self, email: &str, user_id: &str) -> Result<()> {
-        if let Some(pool) = &self.postgres {
-            let email = strip_nul_str(email);
-            let user_id = strip_nul_str(user_id);
-            sqlx::query(
-                "DELETE FROM developer_follows \
-                 WHERE developer_email = $1 AND user_id = $2",
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_grant_flow_rejects_non_created_order() {
+        let (pool, db) = grant_flow_db().await;
+        let (product_id, order_id) = seed_sponsorship_order(&pool, isolated_test_slot()).await;
+        sqlx::query("UPDATE sponsorship_orders SET status = 'canceled' WHERE id = $1")
+            .bind(order_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let err = db
+            .create_sponsorship_grant_and_mark_order_paid(
+                &order_id.to_string(),
+                None,
+                500,
+                1,
+                "manual",
             )
-            .persistent(false)
-            .bind(email.as_ref())
-            .bind(user_id.as_ref())
-            .execute(pool)
-            .await?;
-            return Ok(());
-        }
+            .await
+            .expect_err("canceled order must not be paid");
+        assert!(err.to_string().contains("not payable"));
+        let grants: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM sponsorship_grants WHERE order_id = $1")
+                .bind(order_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(grants, 0);
 
-        Err(anyhow::anyhow!("No database configured"))
+        cleanup_grant_flow_products(&pool, &[product_id]).await;
     }
 
-    pub async fn like_product(&self, product_id: &str, user_id: &str) -> Result<()> {
-        if let Some(pool) = &self.postgres {
-            sqlx::query(
-                "INSERT INTO product_likes (product_id, user_id) \
-                 VALUES ($1::uuid, $2) \
-                 ON CONFLICT (product_id, user_id) DO NOTHING",
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_grant_flow_reuses_existing_grant_for_order() {
+        let (pool, db) = grant_flow_db().await;
+        let slot = isolated_test_slot();
+        let (product_id, order_id) = seed_sponsorship_order(&pool, slot).await;
+        let existing_id: i64 = sqlx::query_scalar(
+            "INSERT INTO sponsorship_grants (order_id, product_id, placement, slot_index, starts_at, ends_at, source) \
+             VALUES ($1, $2, 'home_right', $3, NOW(), NOW() + INTERVAL '30 days', 'manual') \
+             RETURNING id",
+        )
+        .bind(order_id)
+        .bind(product_id)
+        .bind(slot)
+        .fetch_one(&pool)
+        .await
+        .expect("insert grant");
+
+        let grant = db
+            .create_sponsorship_grant_and_mark_order_paid(
+                &order_id.to_string(),
+                Some("evt_2"),
+                700,
+                2,
+                "creem",
             )
-            .persistent(false)
-            .bind(product_id)
-            .bind(user_id)
-            .execute(pool)
-            .await?;
-            return Ok(());
-        }
+            .await
+            .expect("mark paid");
+        assert_eq!(grant.id, existing_id);
 
-        Err(anyhow::anyhow!("No database configured"))
-    }
+        let (status, grant_id): (String, Option<i64>) =
+            sqlx::query_as("SELECT status, grant_id FROM sponsorship_orders WHERE id = $1")
+                .bind(order_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(status, "paid");
+        assert_eq!(grant_id, Some(existing_id));
 
-    pub async fn unlike_product(&self, product_id: &str, user_id: &str) -> Result<()> {
-        if let Some(pool) = &self.postgres {
-            sqlx::query(
-                "DELETE FROM product_likes \
-                 WHERE product_id = $1::uuid AND user_id = $2",
-            )
-            .persistent(false)
-            .bind(product_id)
-            .bind(user_id)
-            .execute(pool)
-            .await?;
-            return Ok(());
-        }
+        cleanup_grant_flow_products(&pool, &[product_id]).await;
+    }
 
-        Err(anyhow::anyhow!("No database configured"))
+    #[test]
+    fn test_supabase_fallback_disabled_from_env_value() {
+        assert!(!supabase_fallback_disabled_from_env_value(None));
+        assert!(!supabase_fallback_disabled_from_env_value(Some("0")));
+        assert!(!supabase_fallback_disabled_from_env_value(Some("")));
+        assert!(supabase_fallback_disabled_from_env_value(Some("1")));
+        assert!(supabase_fallback_disabled_from_env_value(Some(" TRUE ")));
     }
 
-    pub async fn favorite_product(&self, product_id: &str, user_id: &str) -> Result<()> {
-        if let Some(pool) = &self.postgres {
-            sqlx::query(
-                "INSERT INTO product_favorites (product_id, user_id) \
-                 VALUES ($1::uuid, $2) \
-                 ON CONFLICT (product_id, user_id) DO NOTHING",
-            )
-            .persistent(false)
-            .bind(product_id)
-            .bind(user_id)
-            .execute(pool)
-            .await?;
-            return Ok(());
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_case_insensitive_email_lookups_use_lower_email_indexes() {
+        let pool = test_pool().await;
+        ensure_lower_email_indexes(&pool).await;
+
+        for (sql, index) in [
+            (
+                "EXPLAIN SELECT email FROM developers WHERE lower(email) = lower('Maker@Example.com')",
+                "idx_developers_lower_email",
+            ),
+            (
+                "EXPLAIN SELECT id FROM products WHERE lower(maker_email) = lower('Maker@Example.com')",
+                "idx_products_lower_maker_email",
+            ),
+        ] {
+            let mut tx = pool.begin().await.expect("begin");
+            sqlx::query("SET LOCAL enable_seqscan = off")
+                .execute(&mut *tx)
+                .await
+                .expect("disable seqscan");
+            let plan: Vec<(String,)> = sqlx::query_as(sql)
+                .fetch_all(&mut *tx)
+                .await
+                .expect("explain");
+            tx.rollback().await.expect("rollback");
+
+            let plan = plan
+                .into_iter()
+                .map(|(line,)| line)
+                .collect::<Vec<_>>()
+                .join("\n");
+            assert!(plan.contains(index), "unexpected plan: {}", plan);
         }
+    }
 
-        Err(anyhow::anyhow!("No database configured"))
+    #[test]
+    fn test_constraint_violation_detects_supabase_body_codes() {
+        let err = anyhow::anyhow!(
+            "Failed to create product: 409 Conflict. Body: {{\"code\": \"23505\",\"message\":\"duplicate key\"}}"
+        );
+        assert_eq!(
+            constraint_violation(&err),
+            Some(ConstraintViolation::Unique)
+        );
+        let err = anyhow::anyhow!("Body: {{\"code\":\"23503\"}}");
+        assert_eq!(
+            constraint_violation(&err),
+            Some(ConstraintViolation::ForeignKey)
+        );
+        assert_eq!(
+            constraint_violation(&anyhow::anyhow!("connection refused")),
+            None
+        );
     }
 
-    pub async fn unfavorite_product(&self, product_id: &str, user_id: &str) -> Result<()> {
-        if let Some(pool) = &self.postgres {
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_constraint_violation_classifies_postgres_errors() {
+        let pool = test_pool().await;
+        ensure_sponsorship_tables(&pool)
+            .await
+            .expect("ensure sponsorship");
+
+        let product_id = uuid::Uuid::new_v4();
+        let insert_product = || {
             sqlx::query(
-                "DELETE FROM product_favorites \
-                 WHERE product_id = $1::uuid AND user_id = $2",
+                "INSERT INTO products (id, name, slogan, description, website, category, maker_name, maker_email) \
+                 VALUES ($1, 'Dup', 'Slogan', 'Description', 'https://example.com', 'test', 'Maker', 'maker@example.com')",
             )
-            .persistent(false)
             .bind(product_id)
-            .bind(user_id)
-            .execute(pool)
-            .await?;
-            return Ok(());
-        }
+            .execute(&pool)
+        };
+        insert_product().await.expect("insert product");
+        let dup: anyhow::Error = insert_product().await.unwrap_err().into();
+        assert_eq!(
+            constraint_violation(&dup),
+            Some(ConstraintViolation::Unique)
+        );
 
-        Err(anyhow::anyhow!("No database configured"))
-    }
+        let missing_ref: anyhow::Error = sqlx::query(
+            "INSERT INTO sponsorship_orders (id, user_email, product_id, placement, requested_months) \
+             VALUES ($1, 'maker@example.com', $2, 'home_top', 1)",
+        )
+        .bind(uuid::Uuid::new_v4())
+        .bind(uuid::Uuid::new_v4())
+        .execute(&pool)
+        .await
+        .unwrap_err()
+        .into();
+        assert_eq!(
+            constraint_violation(&missing_ref),
+            Some(ConstraintViolation::ForeignKey)
+        );
 
-    pub async fn subscribe_newsletter(&self, email: &str) -> Result<()> {
-        let email = strip_nul_str(email);
-        let normalized = email.trim().to_ascii_lowercase();
-        if normalized.is_empty() {
-            return Err(anyhow::anyhow!("Missing email"));
-        }
+        sqlx::query("DELETE FROM products WHERE id = $1")
+            .bind(product_id)
+            .execute(&pool)
+            .await
+            .ok();
+    }
 
-        if let Some(pool) = &self.postgres {
-            sqlx::query(
-                "INSERT INTO newsletter_subscriptions (email, unsubscribed) \
-                 VALUES ($1, FALSE) \
-                 ON CONFLICT (email) DO UPDATE SET \
-                    unsubscribed = FALSE, \
-                    updated_at = NOW()",
-            )
-            .persistent(false)
-            .bind(normalized)
-            .execute(pool)
-            .await?;
-            return Ok(());
-        }
+    #[test]
+    fn test_dev_include_pending_flag_ignored_in_production() {
+        assert!(dev_include_pending_from_env_value(Some("true"), false));
+        assert!(dev_include_pending_from_env_value(Some(" 1 "), false));
+        assert!(!dev_include_pending_from_env_value(Some("true"), true));
+        assert!(!dev_include_pending_from_env_value(None, false));
+        assert!(!dev_include_pending_from_env_value(Some("0"), false));
+    }
 
-        Err(anyhow::anyhow!("No database configured"))
+    #[test]
+    fn test_bump_cooldown_days_from_env_value() {
+        assert_eq!(bump_cooldown_days_from_env_value(None), 7);
+        assert_eq!(bump_cooldown_days_from_env_value(Some("14")), 14);
+        assert_eq!(bump_cooldown_days_from_env_value(Some("0")), 1);
+        assert_eq!(bump_cooldown_days_from_env_value(Some("1000")), 365);
+        assert_eq!(bump_cooldown_days_from_env_value(Some("abc")), 7);
     }
 
-    pub async fn unsubscribe_newsletter(&self, email: &str) -> Result<()> {
-        let email = strip_nul_str(email);
-        let normalized = email.trim().to_ascii_lowercase();
-        if normalized.is_empty() {
-            return Err(anyhow::anyhow!("Missing email"));
-        }
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_bump_product_enforces_cooldown_and_sorts_recent_first() {
+        let pool = test_pool().await;
+        let db = test_database(&pool);
 
-        if let Some(pool) = &self.postgres {
+        let maker_email = format!("bump-{}@example.com", uuid::Uuid::new_v4().simple());
+        let mut ids = Vec::new();
+        for (name, age_days) in [("older", 30), ("newer", 1)] {
+            let id = uuid::Uuid::new_v4();
             sqlx::query(
-                "INSERT INTO newsletter_subscriptions (email, unsubscribed) \
-                 VALUES ($1, TRUE) \
-                 ON CONFLICT (email) DO UPDATE SET \
-                    unsubscribed = TRUE, \
-                    updated_at = NOW()",
+                "INSERT INTO products (id, name, slogan, description, website, category, maker_name, maker_email, status, created_at) \
+                 VALUES ($1, $2, 'Slogan', 'Description', 'https://example.com', 'test', 'Maker', $3, 'approved', NOW() - make_interval(days => $4))",
             )
-            .persistent(false)
-            .bind(normalized)
-            .execute(pool)
-            .await?;
-            return Ok(());
+            .bind(id)
+            .bind(name)
+            .bind(&maker_email)
+            .bind(age_days)
+            .execute(&pool)
+            .await
+            .expect("insert product");
+            ids.push(id.to_string());
         }
 
-        Err(anyhow::anyhow!("No database configured"))
-    }
+        let now = chrono::Utc::now();
+        let bumped = db
+            .bump_product(&ids[0], now, 7)
+            .await
+            .expect("first bump")
+            .expect("product exists");
+        assert_eq!(bumped.timestamp(), now.timestamp());
 
-    /**
-     * send_admin_product_submission_notification
-     * 产品提交后给管理员发送通知邮件（可选：包含一键通过/拒绝链接）。
-     */
-    pub async fn send_admin_product_submission_notification(
-        &self,
-        product: &Product,
-    ) -> Result<()> {
-        let resend_key = env::var("RESEND_API_KEY").ok().unwrap_or_default();
-        if resend_key.trim().is_empty() {
-            return Ok(());
-        }
+        let err = db
+            .bump_product(&ids[0], now + chrono::Duration::days(1), 7)
+            .await
+            .expect_err("second bump is on cooldown");
+        let cooldown = err
+            .downcast_ref::<ProductBumpCooldownError>()
+            .expect("cooldown error");
+        assert_eq!(
+            cooldown.next_allowed_at.timestamp(),
+            (bumped + chrono::Duration::days(7)).timestamp()
+        );
 
-        let to = env::var("ADMIN_REVIEW_EMAIL")
-            .ok()
-            .unwrap_or_else(|| "2217021563@qq.com".to_string())
-            .trim()
-            .to_string();
-        if to.is_empty() {
-            return Ok(());
-        }
+        let params = QueryParams {
+            category: None,
+            tags: None,
+            language: None,
+            status: Some("approved".to_string()),
+            search: None,
+            maker_email: Some(maker_email.clone()),
+            sort: Some("recent".to_string()),
+            dir: Some("desc".to_string()),
+            limit: Some(10),
+            offset: None,
+            user_id: None,
+            created_after: None,
+            created_before: None,
+            min_likes: None,
+            sponsor_verified: None,
+            include_pending: None,
+            seed: None,
+        };
+        let listed: Vec<String> = db
+            .get_products(params)
+            .await
+            .expect("list products")
+            .into_iter()
+            .map(|p| p.id)
+            .collect();
+        assert_eq!(listed, vec![ids[0].clone(), ids[1].clone()]);
+
+        sqlx::query("DELETE FROM products WHERE maker_email = $1")
+            .bind(&maker_email)
+            .execute(&pool)
+            .await
+            .ok();
+    }
 
-        let from = env::var("ADMIN_REVIEW_FROM")
-            .ok()
-            .filter(|v| !v.trim().is_empty())
-            .or_else(|| env::var("NEWSLETTER_FROM").ok())
-            .unwrap_or_default();
-        if from.trim().is_empty() {
-            log::warn!(
-                "Admin notify sender not configured: ADMIN_REVIEW_FROM/NEWSLETTER_FROM missing"
-            );
-            return Ok(());
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_developer_following_count_is_case_insensitive() {
+        let pool = test_pool().await;
+        let db = test_database(&pool);
+
+        let follower = format!("Follower-{}@Example.com", uuid::Uuid::new_v4().simple());
+        let targets: Vec<String> = (0..2)
+            .map(|i| format!("target-{}-{}@example.com", i, uuid::Uuid::new_v4().simple()))
+            .collect();
+        for target in &targets {
+            db.follow_developer(target, &follower)
+                .await
+                .expect("follow");
         }
 
-        let token_secret = env::var("ADMIN_REVIEW_TOKEN_SECRET")
-            .ok()
-            .unwrap_or_default();
-        let frontend_base_url = env::var("FRONTEND_BASE_URL")
-            .ok()
-            .unwrap_or_else(|| "http://localhost:3000".to_string());
-        let public_api_base_url = env::var("BACKEND_PUBLIC_URL")
-            .ok()
-            .unwrap_or_else(|| "http://localhost:8080".to_string());
-
-        let client = Client::builder()
-            .timeout(Duration::from_secs(12))
-            .http1_only()
-            .build()
-            .unwrap_or_else(|_| Client::new());
+        let count = db
+            .get_developer_following_count(&follower.to_ascii_lowercase())
+            .await
+            .expect("following count");
+        assert_eq!(count, 2);
 
-        let (subject, html, text) = build_admin_product_submission_email_content(
-            product,
-            &frontend_base_url,
-            &public_api_base_url,
-            &token_secret,
+        sqlx::query("DELETE FROM developer_follows WHERE developer_email = ANY($1)")
+            .bind(&targets)
+            .execute(&pool)
+            .await
+            .ok();
+    }
+
+    #[test]
+    fn test_check_requested_months_rejects_out_of_range() {
+        assert_eq!(check_requested_months(1).unwrap(), 1);
+        assert_eq!(
+            check_requested_months(MAX_SPONSORSHIP_MONTHS).unwrap(),
+            MAX_SPONSORSHIP_MONTHS
         );
+        for months in [0, -3, MAX_SPONSORSHIP_MONTHS + 1, 36] {
+            let err = check_requested_months(months).unwrap_err();
+            assert_eq!(err.requested, months);
+            assert_eq!(err.max, MAX_SPONSORSHIP_MONTHS);
+        }
+    }
 
-        send_email_resend(&client, &resend_key, &from, &to, &subject, &html, &text).await?;
-        Ok(())
+    #[test]
+    fn test_random_sort_seed_defaults_to_today() {
+        let today = chrono::NaiveDate::from_ymd_opt(2026, 3, 9).unwrap();
+        assert_eq!(random_sort_seed(None, today), "2026-03-09");
+        assert_eq!(random_sort_seed(Some("   "), today), "2026-03-09");
+        assert_eq!(random_sort_seed(Some(" abc "), today), "abc");
+        assert_eq!(random_sort_seed(Some(&"x".repeat(100)), today).len(), 64);
     }
 
-    /**
-     * send_maker_product_review_notification
-     * 产品审核状态变更为通过/拒绝时，给提交者发送通知邮件（拒绝包含理由）。
-     */
-    pub async fn send_maker_product_review_notification(&self, product: &Product) -> Result<()> {
-        let resend_key = env::var("RESEND_API_KEY").ok().unwrap_or_default();
-        if resend_key.trim().is_empty() {
-            return Ok(());
-        }
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_random_sort_is_stable_per_seed() {
+        let pool = test_pool().await;
+        let db = test_database(&pool);
 
-        let to = product.maker_email.trim().to_string();
-        if to.is_empty() {
-            return Ok(());
+        let maker_email = format!("random-{}@example.com", uuid::Uuid::new_v4().simple());
+        for i in 0..12 {
+            sqlx::query(
+                "INSERT INTO products (id, name, slogan, description, website, category, maker_name, maker_email, status) \
+                 VALUES ($1, $2, 'Slogan', 'Description', 'https://example.com', 'test', 'Maker', $3, 'approved')",
+            )
+            .bind(uuid::Uuid::new_v4())
+            .bind(format!("random-{}", i))
+            .bind(&maker_email)
+            .execute(&pool)
+            .await
+            .expect("insert product");
         }
 
-        let from = env::var("PRODUCT_REVIEW_FROM")
-            .ok()
-            .filter(|v| !v.trim().is_empty())
-            .or_else(|| {
-                env::var("ADMIN_REVIEW_FROM")
-                    .ok()
-                    .filter(|v| !v.trim().is_empty())
-            })
-            .or_else(|| {
-                env::var("NEWSLETTER_FROM")
-                    .ok()
-                    .filter(|v| !v.trim().is_empty())
-            })
-            .unwrap_or_default();
-        if from.trim().is_empty() {
-            log::warn!("Maker review sender not configured: PRODUCT_REVIEW_FROM/ADMIN_REVIEW_FROM/NEWSLETTER_FROM missing");
-            return Ok(());
-        }
+        let list = |seed: &str, limit: i64, offset: i64| {
+            let params = QueryParams {
+                category: None,
+                tags: None,
+                language: None,
+                status: Some("approved".to_string()),
+                search: None,
+                maker_email: Some(maker_email.clone()),
+                sort: Some("random".to_string()),
+                dir: None,
+                limit: Some(limit),
+                offset: Some(offset),
+                user_id: None,
+                created_after: None,
+                created_before: None,
+                min_likes: None,
+                sponsor_verified: None,
+                include_pending: None,
+                seed: Some(seed.to_string()),
+            };
+            let db = &db;
+            async move {
+                db.get_products(params)
+                    .await
+                    .expect("list products")
+                    .into_iter()
+                    .map(|p| p.id)
+                    .collect::<Vec<String>>()
+            }
+        };
 
-        let frontend_base_url = env::var("FRONTEND_BASE_URL")
-            .ok()
-            .unwrap_or_else(|| "http://localhost:3000".to_string());
+        let first = list("alpha", 12, 0).await;
+        assert_eq!(first.len(), 12);
+        assert_eq!(list("alpha", 12, 0).await, first);
 
-        let client = Client::builder()
-            .timeout(Duration::from_secs(12))
-            .http1_only()
-            .build()
-            .unwrap_or_else(|_| Client::new());
+        let mut paged = list("alpha", 5, 0).await;
+        paged.extend(list("alpha", 7, 5).await);
+        assert_eq!(paged, first);
 
-        let (subject, html, text) =
-            build_maker_product_review_email_content(product, &frontend_base_url);
-        send_email_resend(&client, &resend_key, &from, &to, &subject, &html, &text).await?;
-        Ok(())
+        assert_ne!(list("beta", 12, 0).await, first);
+
+        sqlx::query("DELETE FROM products WHERE maker_email = $1")
+            .bind(&maker_email)
+            .execute(&pool)
+            .await
+            .ok();
     }
 
-    pub async fn send_weekly_newsletter_if_due(&self) -> Result<usize> {
-        let pool = match &self.postgres {
-            Some(v) => v,
-            None => return Ok(0),
-        };
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_expand_product_categories_attaches_localized_names() {
+        let pool = test_pool().await;
+        let db = test_database(&pool);
+
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        let tools = format!("tools-{}", suffix);
+        let games = format!("games-{}", suffix);
+        db.upsert_categories(vec![
+            Category {
+                id: tools.clone(),
+                name_en: "Tools".to_string(),
+                name_zh: "工具".to_string(),
+                icon: "🛠".to_string(),
+                color: "#111111".to_string(),
+            },
+            Category {
+                id: games.clone(),
+                name_en: "Games".to_string(),
+                name_zh: "游戏".to_string(),
+                icon: "🎮".to_string(),
+                color: "#222222".to_string(),
+            },
+        ])
+        .await
+        .expect("upsert categories");
+
+        let mut products = vec![
+            sample_product(ProductStatus::Approved),
+            sample_product(ProductStatus::Approved),
+            sample_product(ProductStatus::Approved),
+        ];
+        products[0].category = tools.clone();
+        products[1].category = games.clone();
+        products[2].category = format!("missing-{}", suffix);
+
+        db.expand_product_categories(&mut products)
+            .await
+            .expect("expand categories");
 
-        let now = chrono::Utc::now();
-        if now.weekday() != chrono::Weekday::Thu {
-            return Ok(0);
-        }
-        let hour = now.hour();
-        if !(8..10).contains(&hour) {
-            return Ok(0);
-        }
+        let first = products[0].category_detail.as_ref().expect("tools detail");
+        assert_eq!(
+            (first.name_en.as_str(), first.name_zh.as_str()),
+            ("Tools", "工具")
+        );
+        let second = products[1].category_detail.as_ref().expect("games detail");
+        assert_eq!(
+            (second.name_en.as_str(), second.name_zh.as_str()),
+            ("Games", "游戏")
+        );
+        assert!(products[2].category_detail.is_none());
 
-        let resend_key = env::var("RESEND_API_KEY").ok().unwrap_or_default();
-        let from = env::var("NEWSLETTER_FROM").ok().unwrap_or_default();
-        if resend_key.trim().is_empty() || from.trim().is_empty() {
-            log::warn!("Newsletter sender not configured: RESEND_API_KEY/NEWSLETTER_FROM missing");
-            return Ok(0);
+        for id in [&tools, &games] {
+            db.delete_category(id).await.ok();
         }
+    }
 
-        let iso = now.iso_week();
-        let week_key = format!("{}-W{:02}", iso.year(), iso.week());
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_manual_sponsorship_grant_creates_paid_order_and_grant() {
+        let (pool, db) = grant_flow_db().await;
+        let (product_id, seeded_order) = seed_sponsorship_order(&pool, 0).await;
+        sqlx::query("DELETE FROM sponsorship_orders WHERE id = $1")
+            .bind(seeded_order)
+            .execute(&pool)
+            .await
+            .expect("drop seeded order");
+
+        let mut input = CreateManualSponsorshipGrant {
+            user_email: "maker@example.com".to_string(),
+            product_id: product_id.to_string(),
+            placement: Placement::HomeRight,
+            slot_index: Some(0),
+            months: MAX_SPONSORSHIP_MONTHS + 1,
+            amount_usd_cents: 12_000,
+            external_reference: format!("INV-{}", uuid::Uuid::new_v4()),
+        };
+        assert!(db.create_manual_sponsorship_grant(&input).await.is_err());
+        let orders: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM sponsorship_orders WHERE product_id = $1")
+                .bind(product_id)
+                .fetch_one(&pool)
+                .await
+                .expect("count orders");
+        assert_eq!(orders, 0);
 
-        let mut conn = pool.acquire().await?;
-        let lock_key: i64 = 9_876_543_210;
-        let locked = sqlx::query_scalar::<_, bool>("SELECT pg_try_advisory_lock($1)")
-            .persistent(false)
-            .bind(lock_key)
-            .fetch_one(&mut *conn)
+        input.months = 2;
+        let created = db
+            .create_manual_sponsorship_grant(&input)
             .await
-            .unwrap_or(false);
-        if !locked {
-            return Ok(0);
-        }
+            .expect("manual grant");
+        assert_eq!(created.order.status, "paid");
+        assert_eq!(created.order.provider, "manual");
+        assert_eq!(
+            created.order.provider_order_id.as_deref(),
+            Some(input.external_reference.as_str())
+        );
+        assert_eq!(created.order.paid_months, Some(2));
+        assert_eq!(created.order.grant_id, Some(created.grant.id));
+        assert_eq!(created.grant.source, "manual");
+        assert_eq!(created.grant.amount_usd_cents, Some(12_000));
+        assert_eq!(
+            (created.grant.ends_at - created.grant.starts_at).num_days(),
+            i64::from(months_to_days(2, days_per_month()))
+        );
 
-        let since = now - chrono::Duration::days(7);
-        let products = sqlx::query_as::<_, NewsletterTopProductRow>(
-            "WITH likes AS ( \
-                SELECT product_id, COUNT(*)::bigint as likes \
-                FROM product_likes \
-                WHERE created_at >= $1 \
-                GROUP BY product_id \
-             ), favorites AS ( \
-                SELECT product_id, COUNT(*)::bigint as favorites \
-                FROM product_favorites \
-                WHERE created_at >= $1 \
-                GROUP BY product_id \
-             ) \
-             SELECT \
-                p.id::text as id, \
-                p.name, \
-                p.slogan, \
-                p.website, \
-                p.logo_url, \
-                p.maker_name, \
-                p.maker_email, \
-                COALESCE(l.likes, 0)::bigint as weekly_likes, \
-                COALESCE(f.favorites, 0)::bigint as weekly_favorites, \
-                (COALESCE(l.likes, 0) + COALESCE(f.favorites, 0))::bigint as score \
-             FROM products p \
-             LEFT JOIN likes l ON l.product_id = p.id \
-             LEFT JOIN favorites f ON f.product_id = p.id \
-             WHERE p.status = 'approved' \
-             ORDER BY score DESC, p.created_at DESC \
-             LIMIT $2",
-        )
-        .persistent(false)
-        .bind(since)
-        .bind(5i64)
-        .fetch_all(&mut *conn)
-        .await?;
+        let replayed = db
+            .create_manual_sponsorship_grant(&input)
+            .await
+            .expect("replayed manual grant");
+        assert_eq!(replayed.order.id, created.order.id);
+        assert_eq!(replayed.grant.id, created.grant.id);
+        let grants: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM sponsorship_grants WHERE product_id = $1")
+                .bind(product_id)
+                .fetch_one(&pool)
+                .await
+                .expect("count grants");
+        assert_eq!(grants, 1);
 
-        let recipients = sqlx::query_as::<_, NewsletterRecipientRow>(
-            "SELECT email \
-             FROM newsletter_subscriptions \
-             WHERE unsubscribed = FALSE AND (last_sent_week IS DISTINCT FROM $1) \
-             ORDER BY created_at ASC \
-             LIMIT 1000",
-        )
-        .persistent(false)
-        .bind(&week_key)
-        .fetch_all(&mut *conn)
-        .await?;
+        cleanup_grant_flow_products(&pool, &[product_id]).await;
+    }
 
-        if recipients.is_empty() {
-            let _ = sqlx::query("SELECT pg_advisory_unlock($1)")
-                .persistent(false)
-                .bind(lock_key)
-                .execute(&mut *conn)
-                .await;
-            return Ok(0);
-        }
+    #[test]
+    fn test_missing_schema_objects_reports_absent_objects() {
+        let tables: HashSet<String> = ["products".to_string()].into_iter().collect();
+        let columns: HashSet<(String, String)> =
+            [("products".to_string(), "bumped_at".to_string())]
+                .into_iter()
+                .collect();
+        let indexes: HashSet<String> = HashSet::new();
+        let expects = [
+            SchemaObject::Table("products"),
+            SchemaObject::Table("product_claims"),
+            SchemaObject::Column("products", "bumped_at"),
+            SchemaObject::Column("products", "rejection_reason"),
+            SchemaObject::Index("idx_products_lower_maker_email"),
+        ];
+        assert_eq!(
+            missing_schema_objects(&expects, &tables, &columns, &indexes),
+            vec![
+                "table product_claims".to_string(),
+                "column products.rejection_reason".to_string(),
+                "index idx_products_lower_maker_email".to_string(),
+            ]
+        );
+    }
 
-        let frontend_base_url = env::var("FRONTEND_BASE_URL")
-            .ok()
-            .unwrap_or_else(|| "http://localhost:3000".to_string());
-        let public_api_base_url = env::var("BACKEND_PUBLIC_URL")
-            .ok()
-            .unwrap_or_else(|| "http://localhost:8080".to_string());
-        let token_secret = env::var("NEWSLETTER_TOKEN_SECRET").ok().unwrap_or_default();
-        let client = Client::builder()
-            .timeout(Duration::from_secs(12))
-            .http1_only()
-            .build()
-            .unwrap_or_else(|_| Client::new());
+    #[test]
+    fn test_migration_names_are_unique() {
+        let names: HashSet<&str> = MIGRATIONS.iter().map(|m| m.name).collect();
+        assert_eq!(names.len(), MIGRATIONS.len());
+    }
 
-        let mut sent: Vec<String> = Vec::new();
-        for r in recipients {
-            let to = r.email.trim().to_string();
-            if to.is_empty() {
-                continue;
-            }
-            let token =
-                compute_newsletter_unsubscribe_token(&to, &token_secret).unwrap_or_default();
-            let unsubscribe_url = if token.trim().is_empty() {
-                let base = normalize_base_url(&public_api_base_url);
-                let email_q = urlencoding::encode(&to);
-                format!("{}/api/newsletter/unsubscribe?email={}", base, email_q)
-            } else {
-                build_newsletter_unsubscribe_url(&public_api_base_url, &to, &token)
-            };
-            let (subject, html, text) = build_weekly_newsletter_content(
-                now,
-                since,
-                &products,
-                &frontend_base_url,
-                &unsubscribe_url,
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_run_all_migrations_brings_schema_up_to_date() {
+        let pool = test_pool().await;
+        let db = test_database(&pool);
+
+        let statuses = db.run_all_migrations().await.expect("run all");
+        assert_eq!(statuses.len(), MIGRATIONS.len());
+        for status in statuses {
+            assert!(
+                status.error.is_none(),
+                "{}: {:?}",
+                status.name,
+                status.error
+            );
+            assert!(status.ready_in_process, "{} not ready", status.name);
+            assert!(
+                status.schema_ok,
+                "{} missing {:?}",
+                status.name, status.missing
             );
-            let res =
-                send_email_resend(&client, &resend_key, &from, &to, &subject, &html, &text).await;
-            match res {
-                Ok(()) => sent.push(to),
-                Err(e) => log::warn!("Newsletter send failed to={} err={:?}", r.email, e),
-            }
         }
+    }
 
-        if !sent.is_empty() {
+    #[test]
+    fn test_product_ref_match_rank_order() {
+        assert_eq!(ProductRefMatch::from_rank(0), ProductRefMatch::ExactId);
+        assert_eq!(ProductRefMatch::from_rank(3), ProductRefMatch::Prefix);
+        assert_eq!(ProductRefMatch::from_rank(9), ProductRefMatch::Substring);
+        assert!(ProductRefMatch::ExactWebsite < ProductRefMatch::ExactName);
+        assert!(ProductRefMatch::ExactName < ProductRefMatch::Prefix);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_resolve_product_candidates_ranks_ambiguous_refs() {
+        let pool = test_pool().await;
+        let db = test_database(&pool);
+
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        let maker_email = format!("ref-maker-{}@example.com", suffix);
+        let base = format!("Ref{}", &suffix[..8]);
+        let now = chrono::Utc::now();
+        // 最老的是名称完全匹配，其余更新的产品只是前缀或子串匹配。
+        let exact_name = uuid::Uuid::new_v4();
+        let prefix = uuid::Uuid::new_v4();
+        let substring = uuid::Uuid::new_v4();
+        let exact_website = uuid::Uuid::new_v4();
+        for (id, name, website, age_hours) in [
+            (
+                exact_name,
+                base.clone(),
+                format!("https://{}-one.example.com", suffix),
+                4,
+            ),
+            (
+                prefix,
+                format!("{} Pro", base),
+                format!("https://{}-two.example.com", suffix),
+                3,
+            ),
+            (
+                substring,
+                format!("Super {}", base),
+                format!("https://{}-three.example.com", suffix),
+                2,
+            ),
+            (
+                exact_website,
+                "Unrelated".to_string(),
+                format!("https://www.{}-site.example.com/", suffix),
+                1,
+            ),
+        ] {
             sqlx::query(
-                "UPDATE newsletter_subscriptions \
-                 SET last_sent_week = $1, last_sent_at = NOW(), updated_at = NOW() \
-                 WHERE email = ANY($2)",
+                "INSERT INTO products (id, name, slogan, description, website, category, maker_name, maker_email, created_at) \
+                 VALUES ($1, $2, 'Slogan', 'Description', $3, 'test', 'Maker', $4, $5)",
             )
-            .persistent(false)
-            .bind(&week_key)
-            .bind(&sent)
-            .execute(&mut *conn)
-            .await?;
+            .bind(id)
+            .bind(&name)
+            .bind(&website)
+            .bind(&maker_email)
+            .bind(now - chrono::Duration::hours(age_hours))
+            .execute(&pool)
+            .await
+            .expect("insert product");
         }
 
-        let _ = sqlx::query("SELECT pg_advisory_unlock($1)")
-            .persistent(false)
-            .bind(lock_key)
-            .execute(&mut *conn)
-            .await;
+        let candidates = db
+            .resolve_product_candidates(&base.to_lowercase(), 10)
+            .await
+            .expect("candidates");
+        let ranked: Vec<(String, ProductRefMatch)> = candidates
+            .iter()
+            .map(|c| (c.id.clone(), c.match_kind))
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![
+                (exact_name.to_string(), ProductRefMatch::ExactName),
+                (prefix.to_string(), ProductRefMatch::Prefix),
+                (substring.to_string(), ProductRefMatch::Substring),
+            ]
+        );
+        assert_eq!(
+            db.resolve_product_id_by_ref(&base).await.expect("resolve"),
+            Some(exact_name.to_string())
+        );
+
+        let by_website = db
+            .resolve_product_id_by_ref(&format!("{}-site.example.com", suffix))
+            .await
+            .expect("resolve website");
+        assert_eq!(by_website, Some(exact_website.to_string()));
+
+        // 同名的新产品与原产品同处最高匹配等级：视为歧义，不再默认取最新的一个。
+        sqlx::query(
+            "INSERT INTO products (id, name, slogan, description, website, category, maker_name, maker_email) \
+             VALUES ($1, $2, 'Slogan', 'Description', $3, 'test', 'Maker', $4)",
+        )
+        .bind(uuid::Uuid::new_v4())
+        .bind(&base)
+        .bind(format!("https://{}-dup.example.com", suffix))
+        .bind(&maker_email)
+        .execute(&pool)
+        .await
+        .expect("insert duplicate name");
+        assert_eq!(
+            db.resolve_product_id_by_ref(&base).await.expect("resolve"),
+            None
+        );
+
+        let wildcard = db
+            .resolve_product_candidates(&format!("{}%", base), 10)
+            .await
+            .expect("wildcard");
+        assert!(wildcard.is_empty());
+
+        sqlx::query("DELETE FROM products WHERE maker_email = $1")
+            .bind(&maker_email)
+            .execute(&pool)
+            .await
+            .expect("cleanup");
+    }
+
+    #[test]
+    fn test_sponsorship_grant_state_is_computed_at_map_time() {
+        let now = chrono::Utc::now();
+        let row = |starts_at, ends_at| SponsorshipGrantFullRow {
+            id: 1,
+            product_id: "00000000-0000-0000-0000-000000000001".to_string(),
+            placement: "home_top".to_string(),
+            slot_index: Some(0),
+            starts_at,
+            ends_at,
+            source: "manual".to_string(),
+            amount_usd_cents: None,
+            created_at: now - chrono::Duration::days(60),
+        };
+
+        let expired = map_sponsorship_grant_full_row_at(
+            row(
+                now - chrono::Duration::days(30),
+                now - chrono::Duration::seconds(1),
+            ),
+            now,
+        );
+        assert_eq!(expired.state, SponsorshipGrantState::Expired);
+        assert!(!expired.active);
+        assert_eq!(expired.ends_at, now - chrono::Duration::seconds(1));
+
+        let active = map_sponsorship_grant_full_row_at(
+            row(
+                now - chrono::Duration::days(1),
+                now + chrono::Duration::days(1),
+            ),
+            now,
+        );
+        assert_eq!(active.state, SponsorshipGrantState::Active);
+        assert!(active.active);
+
+        let upcoming = map_sponsorship_grant_full_row_at(
+            row(
+                now + chrono::Duration::days(1),
+                now + chrono::Duration::days(31),
+            ),
+            now,
+        );
+        assert_eq!(upcoming.state, SponsorshipGrantState::Upcoming);
+        assert!(!upcoming.active);
+
+        // ends_at 为开区间：恰好到期即视为已结束。
+        let boundary =
+            map_sponsorship_grant_full_row_at(row(now - chrono::Duration::days(1), now), now);
+        assert_eq!(boundary.state, SponsorshipGrantState::Expired);
+    }
+
+    #[tokio::test]
+    async fn test_postgres_only_methods_return_typed_error_without_postgres() {
+        let db = Database {
+            supabase: None,
+            postgres: None,
+            overview_stats_cache: Mutex::new(None),
+            featured_history_recorded: Mutex::new(None),
+        };
+        let err = db
+            .list_sponsorship_orders(None, 10, 0)
+            .await
+            .expect_err("requires postgres");
+        assert!(err.downcast_ref::<PostgresNotConfiguredError>().is_some());
+        assert_eq!(err.to_string(), "Postgres is not configured");
+
+        // 读路径同样报错，而不是返回与真实空结果无法区分的空数据
+        let err = db
+            .get_home_module_state("home_featured")
+            .await
+            .expect_err("requires postgres");
+        assert!(err.downcast_ref::<PostgresNotConfiguredError>().is_some());
+        let err = db
+            .get_favorite_products("user-1", None, 10, 0)
+            .await
+            .expect_err("requires postgres");
+        assert!(err.downcast_ref::<PostgresNotConfiguredError>().is_some());
+    }
+
+    fn sample_sponsorship_request() -> SponsorshipRequest {
+        SponsorshipRequest {
+            id: 42,
+            email: "buyer@example.com".to_string(),
+            product_ref: "forge".to_string(),
+            placement: "home_top".to_string(),
+            slot_index: Some(1),
+            duration_days: 60,
+            note: Some("<b>launch</b>".to_string()),
+            status: "pending".to_string(),
+            processed_grant_id: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_admin_sponsorship_request_email_includes_request_details() {
+        let request = sample_sponsorship_request();
+        let product = sample_product(ProductStatus::Approved);
+        let review_url = build_admin_sponsorship_request_review_url(
+            Some("https://admin.example.com/"),
+            "http://localhost:3000",
+            request.id,
+        );
+        assert_eq!(review_url, "https://admin.example.com/sponsorship?id=42");
+
+        let (subject, html, text) =
+            build_admin_sponsorship_request_email_content(&request, Some(&product), &review_url);
+        assert_eq!(subject, "New sponsorship request #42: Forge");
+        assert!(text.contains("Email: buyer@example.com"));
+        assert!(text.contains(&format!("Product: Forge ({})", product.id)));
+        assert!(text.contains("Placement: home_top (slot 1)"));
+        assert!(text.contains("Duration: 60 days"));
+        assert!(text.contains(&review_url));
+        assert!(html.contains("&lt;b&gt;launch&lt;/b&gt;"));
+        assert!(!html.contains("<b>launch</b>"));
+
+        let (subject, _, text) =
+            build_admin_sponsorship_request_email_content(&request, None, &review_url);
+        assert_eq!(subject, "New sponsorship request #42: forge");
+        assert!(text.contains("not matched"));
+    }
+
+    #[test]
+    fn test_admin_sponsorship_request_review_url_falls_back_to_frontend() {
+        assert_eq!(
+            build_admin_sponsorship_request_review_url(None, "https://soloforge.dev/", 7),
+            "https://soloforge.dev/admin/sponsorship?id=7"
+        );
+        assert_eq!(
+            build_admin_sponsorship_request_review_url(Some("  "), "https://soloforge.dev", 7),
+            "https://soloforge.dev/admin/sponsorship?id=7"
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_sponsorship_request_admin_notification_is_claimed_once() {
+        let pool = test_pool().await;
+        let db = test_database(&pool);
+
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        let created = db
+            .create_sponsorship_request(CreateSponsorshipRequest {
+                email: format!("notify-{}@example.com", suffix),
+                product_ref: format!("missing-{}", suffix),
+                placement: Placement::HomeTop,
+                slot_index: None,
+                duration_days: 30,
+                note: None,
+            })
+            .await
+            .expect("create request");
+
+        let first = db
+            .claim_sponsorship_request_admin_notification(created.id)
+            .await
+            .expect("first claim");
+        assert_eq!(first.map(|r| r.id), Some(created.id));
+        let second = db
+            .claim_sponsorship_request_admin_notification(created.id)
+            .await
+            .expect("second claim");
+        assert!(second.is_none());
 
-        Ok(sent.len())
+        sqlx::query("DELETE FROM sponsorship_requests WHERE id = $1")
+            .bind(created.id)
+            .execute(&pool)
+            .await
+            .expect("cleanup");
     }
 
-    pub async fn seed_engagement(&self, product_ids: &[String]) -> Result<()> {
-        if product_ids.is_empty() {
-            return Ok(());
-        }
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_developer_profile_includes_join_date() {
+        let pool = test_pool().await;
+        let db = test_database(&pool);
+
+        let email = format!("joined-{}@example.com", uuid::Uuid::new_v4().simple());
+        let joined_at = chrono::Utc::now() - chrono::Duration::days(90);
+        sqlx::query("INSERT INTO developers (email, name, created_at) VALUES ($1, 'Joined', $2)")
+            .bind(&email)
+            .bind(joined_at)
+            .execute(&pool)
+            .await
+            .expect("insert developer");
 
-        let pool = self
-            .postgres
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Postgres is not configured"))?;
+        let developer = db
+            .get_developer_by_email(&email)
+            .await
+            .expect("get developer")
+            .expect("developer exists");
+        let created_at = developer.created_at.expect("created_at");
+        assert_eq!(created_at.timestamp(), joined_at.timestamp());
+        assert!(developer.updated_at.is_some());
 
-        let products = {
-            let sql = "SELECT \
-                p.id::text as id, \
-                p.name, \
-                p.slogan, \
-                p.description, \
-                p.website, \
-                p.logo_url, \
-                p.category, \
-                COALESCE(p.tags, ARRAY[]::text[]) as tags, \
-                p.maker_name, \
-                p.maker_email, \
-                p.maker_website, \
-                p.language, \
-                p.status::text as status, \
-                p.rejection_reason, \
-                p.created_at, \
-                p.updated_at, \
-                0::bigint as likes, \
-                0::bigint as favorites, \
-                COALESCE(d.sponsor_role, NULL::text) as maker_sponsor_role, \
-                COALESCE(d.sponsor_verified, FALSE) as maker_sponsor_verified \
-             FROM products p \
-             LEFT JOIN developers d ON lower(d.email) = lower(p.maker_email) \
-             WHERE p.id::text = ANY($1)";
+        let json = serde_json::to_value(&developer).expect("serialize");
+        assert!(json["created_at"].is_string());
 
-            let attempt = sqlx::query_as::<_, ProductRow>(sql)
-                .persistent(false)
-                .bind(product_ids)
-                .fetch_all(pool)
-                .await;
+        let updated = db
+            .update_developer_profile(&email, Some("Renamed".to_string()), None, None)
+            .await
+            .expect("update profile");
+        assert_eq!(
+            updated.created_at.map(|v| v.timestamp()),
+            Some(joined_at.timestamp())
+        );
 
-            match attempt {
-                Ok(rows) => rows,
-                Err(e) => {
-                    let e: anyhow::Error = e.into();
-                    if is_missing_column_error(&e, "rejection_reason")
-                        && !PRODUCTS_REJECTION_REASON_READY.load(Ordering::Relaxed)
-                    {
-                        if ensure_products_rejection_reason_column(pool).await.is_ok() {
-                            sqlx::query_as::<_, ProductRow>(sql)
-                                .persistent(false)
-                                .bind(product_ids)
-                                .fetch_all(pool)
-                                .await?
-                        } else {
-                            return Err(e);
-                        }
-                    } else if (is_missing_column_error(&e, "sponsor_role")
-                        || is_missing_column_error(&e, "sponsor_verified"))
-                        && !DEVELOPERS_SPONSOR_COLUMNS_READY.load(Ordering::Relaxed)
-                    {
-                        if ensure_developers_sponsor_columns(pool).await.is_ok() {
-                            sqlx::query_as::<_, ProductRow>(sql)
-                                .persistent(false)
-                                .bind(product_ids)
-                                .fetch_all(pool)
-                                .await?
-                        } else {
-                            return Err(e);
-                        }
-                    } else {
-                        return Err(e);
-                    }
-                }
-            }
-        };
+        let (listed, _) = db
+            .list_developers(Some(&email), DeveloperDirectorySort::Name, 10, 0)
+            .await
+            .expect("list developers");
+        assert_eq!(listed.len(), 1);
+        assert_eq!(
+            listed[0].created_at.map(|v| v.timestamp()),
+            Some(joined_at.timestamp())
+        );
+        assert!(listed[0].updated_at.is_some());
 
-        for p in &products {
-            self.upsert_developer_pg(
-                pool,
-                &p.maker_email,
-                &p.maker_name,
-                p.maker_website.as_ref(),
-            )
-            .await?;
-        }
+        sqlx::query("DELETE FROM developers WHERE email = $1")
+            .bind(&email)
+            .execute(&pool)
+            .await
+            .expect("cleanup");
+    }
 
-        let mut emails: Vec<String> = products.iter().map(|p| p.maker_email.clone()).collect();
-        emails.sort();
-        emails.dedup();
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_slug_history_keeps_first_owner_of_old_slug() {
+        let pool = test_pool().await;
+        ensure_product_slug_history_table(&pool)
+            .await
+            .expect("ensure slug history");
+        let db = test_database(&pool);
 
-        for (idx, email) in emails.iter().enumerate() {
-            let follows = 10 + (idx as i64 * 7);
-            let mut qb: QueryBuilder<Postgres> = QueryBuilder::new(
-                "INSERT INTO developer_follows (developer_email, user_id, created_at) ",
-            );
-            qb.push_values(0..follows, |mut b, i| {
-                let user_id = format!("seed_user_{}_{}", idx, i);
-                let created_at = if i % 3 == 0 {
-                    chrono::Utc::now() - chrono::Duration::days(35)
-                } else {
-                    chrono::Utc::now() - chrono::Duration::days(5)
-                };
-                b.push_bind(email).push_bind(user_id).push_bind(created_at);
-            });
-            qb.push(" ON CONFLICT (developer_email, user_id) DO NOTHING");
-            qb.build().persistent(false).execute(pool).await?;
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        let old_name = format!("Shared {}", suffix);
+        let ids = [uuid::Uuid::new_v4(), uuid::Uuid::new_v4()];
+        for id in ids {
+            sqlx::query(
+                "INSERT INTO products (id, name, slogan, description, website, category, maker_name, maker_email) \
+                 VALUES ($1, $2, 'Slogan', 'Description', 'https://example.com', 'test', 'Maker', 'maker@example.com')",
+            )
+            .bind(id)
+            .bind(&old_name)
+            .execute(&pool)
+            .await
+            .expect("insert product");
         }
 
-        for (idx, p) in products.iter().enumerate() {
-            let product_uuid = match uuid::Uuid::parse_str(&p.id) {
-                Ok(v) => v,
-                Err(_) => continue,
-            };
-            let likes = 20 + (idx as i64 * 9);
-            let favorites = 12 + (idx as i64 * 5);
+        record_product_slug_change(&pool, &ids[0].to_string(), &old_name, "First").await;
+        record_product_slug_change(&pool, &ids[1].to_string(), &old_name, "Second").await;
+        assert_eq!(
+            db.resolve_product_slug_redirect(&old_name)
+                .await
+                .expect("resolve"),
+            Some(ids[0].to_string())
+        );
 
-            let mut likes_qb: QueryBuilder<Postgres> =
-                QueryBuilder::new("INSERT INTO product_likes (product_id, user_id, created_at) ");
-            likes_qb.push_values(0..likes, |mut b, i| {
-                let user_id = format!("seed_like_{}_{}", idx, i);
-                let created_at = if i % 2 == 0 {
-                    chrono::Utc::now() - chrono::Duration::days(33)
-                } else {
-                    chrono::Utc::now() - chrono::Duration::days(3)
-                };
-                b.push_bind(product_uuid)
-                    .push_bind(user_id)
-                    .push_bind(created_at);
-            });
-            likes_qb.push(" ON CONFLICT (product_id, user_id) DO NOTHING");
-            likes_qb.build().persistent(false).execute(pool).await?;
+        let current_name = format!("Current {}!", suffix);
+        sqlx::query("UPDATE products SET name = $1 WHERE id = $2")
+            .bind(&current_name)
+            .bind(ids[1])
+            .execute(&pool)
+            .await
+            .expect("rename product");
+        assert_eq!(
+            db.resolve_product_current_slug(&format!("current-{}", suffix))
+                .await
+                .expect("resolve current"),
+            Some(ids[1].to_string())
+        );
+        assert_eq!(
+            db.resolve_product_current_slug(&format!("missing-{}", suffix))
+                .await
+                .expect("resolve missing"),
+            None
+        );
 
-            let mut fav_qb: QueryBuilder<Postgres> = QueryBuilder::new(
-                "INSERT INTO product_favorites (product_id, user_id, created_at) ",
-            );
-            fav_qb.push_values(0..favorites, |mut b, i| {
-                let user_id = format!("seed_fav_{}_{}", idx, i);
-                let created_at = if i % 2 == 0 {
-                    chrono::Utc::now() - chrono::Duration::days(34)
-                } else {
-                    chrono::Utc::now() - chrono::Duration::days(4)
-                };
-                b.push_bind(product_uuid)
-                    .push_bind(user_id)
-                    .push_bind(created_at);
-            });
-            fav_qb.push(" ON CONFLICT (product_id, user_id) DO NOTHING");
-            fav_qb.build().persistent(false).execute(pool).await?;
+        for id in ids {
+            sqlx::query("DELETE FROM products WHERE id = $1")
+                .bind(id)
+                .execute(&pool)
+                .await
+                .ok();
         }
-
-        Ok(())
     }
 
-    /**
-     * bootstrap_schema
-     * 在直连 Postgres 的情况下自动创建必要表结构与索引（开发环境使用）。
-     */
-    pub async fn bootstrap_schema(&self) -> Result<()> {
-        let pool = self
-            .postgres
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Postgres is not configured"))?;
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_grant_from_request_marks_sponsor_without_overwriting_role() {
+        let (pool, db) = grant_flow_db().await;
+        ensure_developers_sponsor_columns(&pool)
+            .await
+            .expect("ensure sponsor columns");
+        let (product_id, order_id) = seed_sponsorship_order(&pool, 0).await;
+        sqlx::query("DELETE FROM sponsorship_orders WHERE id = $1")
+            .bind(order_id)
+            .execute(&pool)
+            .await
+            .expect("drop seeded order");
 
-        let sql = include_str!("../database_schema.sql");
-        for stmt in split_sql_statements(sql) {
-            sqlx::query(&stmt).persistent(false).execute(pool).await?;
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        let gold_sponsor = format!("gold-{}@example.com", suffix);
+        let newcomer = format!("newcomer-{}@example.com", suffix);
+        sqlx::query(
+            "INSERT INTO developers (email, name, sponsor_role, sponsor_verified) VALUES ($1, 'Gold', 'gold', FALSE)",
+        )
+        .bind(&gold_sponsor)
+        .execute(&pool)
+        .await
+        .expect("insert gold sponsor");
+
+        for email in [&gold_sponsor, &newcomer] {
+            let request = db
+                .create_sponsorship_request(CreateSponsorshipRequest {
+                    email: email.clone(),
+                    product_ref: product_id.to_string(),
+                    placement: Placement::HomeRight,
+                    slot_index: Some(0),
+                    duration_days: 1,
+                    note: None,
+                })
+                .await
+                .expect("create request");
+            db.create_sponsorship_grant_from_request(CreateSponsorshipGrantFromRequest {
+                request_id: request.id,
+                product_id: product_id.to_string(),
+                placement: Placement::HomeRight,
+                slot_index: Some(0),
+                duration_days: 1,
+                amount_usd_cents: None,
+                starts_at: None,
+                sponsor_email: Some(email.clone()),
+            })
+            .await
+            .expect("grant from request");
         }
 
-        Ok(())
-    }
+        let roles: Vec<(String, Option<String>, bool)> = sqlx::query_as(
+            "SELECT email, sponsor_role, sponsor_verified FROM developers WHERE email = ANY($1) ORDER BY email",
+        )
+        .bind(vec![newcomer.clone(), gold_sponsor.clone()])
+        .fetch_all(&pool)
+        .await
+        .expect("load developers");
+        assert_eq!(
+            roles,
+            vec![
+                (gold_sponsor.clone(), Some("gold".to_string()), true),
+                (newcomer.clone(), Some("sponsor".to_string()), true),
+            ]
+        );
 
-    #[allow(dead_code)]
-    pub async fn get_products_count(&self) -> Result<i64> {
-        if let Some(pool) = &self.postgres {
-            let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*)::bigint FROM products")
-                .persistent(false)
-                .fetch_one(pool)
-                .await?;
-            return Ok(count);
-        }
+        sqlx::query("DELETE FROM sponsorship_requests WHERE email = ANY($1)")
+            .bind(vec![newcomer.clone(), gold_sponsor.clone()])
+            .execute(&pool)
+            .await
+            .ok();
+        sqlx::query("DELETE FROM developers WHERE email = ANY($1)")
+            .bind(vec![newcomer, gold_sponsor])
+            .execute(&pool)
+            .await
+            .ok();
+        cleanup_grant_flow_products(&pool, &[product_id]).await;
+    }
 
-        let supabase = self
-            .supabase
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No database configured"))?;
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_update_developer_sponsor_is_partial_and_reports_missing() {
+        let pool = test_pool().await;
+        ensure_developers_sponsor_columns(&pool)
+            .await
+            .expect("ensure sponsor columns");
+        let db = test_database(&pool);
 
-        let mut url = Url::parse(&format!("{}/rest/v1/products", supabase.supabase_url))?;
-        url.query_pairs_mut().append_pair("select", "count");
+        let email = format!("sponsor-{}@example.com", uuid::Uuid::new_v4().simple());
+        let err = db
+            .update_developer_sponsor(&email, Some(Some("gold")), Some(true))
+            .await
+            .expect_err("missing developer");
+        assert!(err.downcast_ref::<DeveloperNotFoundError>().is_some());
 
-        let response = supabase
-            .client
-            .get(url)
-            .header("apikey", &supabase.supabase_key)
-            .header(
-                "Authorization",
-                &format!("Bearer {}", supabase.supabase_key),
+        sqlx::query(
+            "INSERT INTO developers (email, name, sponsor_role, sponsor_verified) VALUES ($1, 'Sponsor', 'gold', TRUE)",
+        )
+        .bind(&email)
+        .execute(&pool)
+        .await
+        .expect("insert developer");
+        let load = || async {
+            sqlx::query_as::<_, (Option<String>, bool)>(
+                "SELECT sponsor_role, sponsor_verified FROM developers WHERE email = $1",
             )
-            .header("Accept", "application/json")
-            .send()
-            .await?;
+            .bind(&email)
+            .fetch_one(&pool)
+            .await
+            .expect("load developer")
+        };
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            return Err(anyhow::anyhow!(
-                "Failed to fetch products count: {}. Body: {}",
-                status,
-                body
-            ));
-        }
+        db.update_developer_sponsor(&email.to_uppercase(), None, Some(false))
+            .await
+            .expect("verify only");
+        assert_eq!(load().await, (Some("gold".to_string()), false));
 
-        let result: Vec<serde_json::Value> = response.json().await?;
-        Ok(result
-            .first()
-            .and_then(|v| v.get("count"))
-            .and_then(|v| v.as_i64())
-            .unwrap_or(0))
+        db.update_developer_sponsor(&email, Some(None), None)
+            .await
+            .expect("clear role");
+        assert_eq!(load().await, (None, false));
+
+        sqlx::query("DELETE FROM developers WHERE email = $1")
+            .bind(&email)
+            .execute(&pool)
+            .await
+            .ok();
+    }
+
+    async fn featured_history_count(pool: &PgPool, pattern: &str) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM home_featured_history WHERE product_id LIKE $1")
+            .bind(pattern)
+            .fetch_one(pool)
+            .await
+            .expect("count history")
+    }
+
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_record_featured_ids_writes_only_when_selection_changes() {
+        let pool = test_pool().await;
+        let db = test_database(&pool);
+        let today = chrono::NaiveDate::from_ymd_opt(2001, 1, 1).expect("date");
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        let first = vec![
+            format!("featured-a-{}", suffix),
+            format!("featured-b-{}", suffix),
+        ];
+        let pattern = format!("featured-%-{}", suffix);
+
+        db.record_featured_ids(today, &first).await.expect("record");
+        assert_eq!(featured_history_count(&pool, &pattern).await, 2);
+
+        // 同一天相同入选（顺序不同）不再写库：手动删除的记录不会被补回。
+        sqlx::query("DELETE FROM home_featured_history WHERE product_id LIKE $1")
+            .bind(&pattern)
+            .execute(&pool)
+            .await
+            .expect("clear history");
+        let reordered: Vec<String> = first.iter().rev().cloned().collect();
+        db.record_featured_ids(today, &reordered)
+            .await
+            .expect("record again");
+        assert_eq!(featured_history_count(&pool, &pattern).await, 0);
+
+        let changed = vec![first[0].clone(), format!("featured-c-{}", suffix)];
+        db.record_featured_ids(today, &changed)
+            .await
+            .expect("record changed");
+        assert_eq!(featured_history_count(&pool, &pattern).await, 2);
+
+        sqlx::query("DELETE FROM home_featured_history WHERE product_id LIKE $1")
+            .bind(&pattern)
+            .execute(&pool)
+            .await
+            .ok();
     }
 }
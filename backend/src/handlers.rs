@@ -1,13 +1,29 @@
-use crate::db::Database;
+use crate::db::{
+    check_requested_months, compose_home_module_ids, constraint_violation, featured_cooldown_days,
+    is_valid_email_basic, max_sponsorship_duration_days, pick_featured_with_cooldown,
+    ActiveSponsorshipGrant, AdminAuditLogListParams, ConstraintViolation, Database,
+    DeveloperNotFoundError, HomeModuleMode, NewsletterRunOptions, PostgresNotConfiguredError,
+    PricingPlanInUseError, PricingPlanValidationError, ProductAlreadyClaimedError,
+    ProductBumpCooldownError, ProductClaimNotPendingError, ProductFieldTooLongError,
+    SponsorshipGrantListParams, SponsorshipRequestListParams, SponsorshipRequestNotPendingError,
+    SponsorshipRequestSort, UnsupportedFilterError, MAX_SPONSORSHIP_MONTHS,
+};
 use crate::models::{
-    ApiError, ApiResponse, Category, CreateProductRequest, CreateSponsorshipGrantFromRequest,
-    CreateSponsorshipRequest, DeveloperCenterStats, EmptyApiResponse, NewsletterSubscribeRequest,
-    Product, ProductApiResponse, ProductsApiResponse, QueryParams, SearchApiResponse, SearchResult,
-    SponsorshipRequest, UpdateProductRequest, UpsertPricingPlanRequest,
+    ApiError, ApiResponse, Category, CategoryWithCount, CreateManualSponsorshipGrant,
+    CreateProductRequest, CreateSponsorshipGrantFromRequest, CreateSponsorshipRequest,
+    DeveloperCenterStats, DeveloperProfileExport, EmptyApiResponse, FieldError,
+    NewsletterSubscribeRequest, OrphanMaker, OverviewStats, PagedList, Placement, PricingPlan,
+    PricingPlanBenefitGroups, Product, ProductActiveGrant, ProductApiResponse,
+    ProductSponsorshipStatus, ProductsApiResponse, QueryParams, ReorderPricingPlansRequest,
+    ResolvedProductRef, SearchApiResponse, SearchHighlight, SearchResult, SponsorshipGrant,
+    SponsorshipRequest, UpdateProductRequest, UpsertOutgoingWebhookRequest,
+    UpsertPricingPlanRequest, UserSponsorshipOrder,
 };
-use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use actix_web::error::JsonPayloadError;
+use actix_web::http::StatusCode;
+use actix_web::{get, web, HttpRequest, HttpResponse, HttpResponseBuilder, Responder};
 use base64::{engine::general_purpose, Engine as _};
-use chrono::{Duration, Utc};
+use chrono::{Duration, TimeZone, Utc};
 use hmac::{Hmac, Mac};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
@@ -15,6 +31,7 @@ use sha2::Sha256;
 use std::collections::hash_map::DefaultHasher;
 use std::env;
 use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration as StdDuration;
 use utoipa::{IntoParams, ToSchema};
@@ -62,6 +79,31 @@ fn get_language_from_request(req: &HttpRequest) -> &str {
         .unwrap_or("en")
 }
 
+pub(crate) const PAGE_LIMIT_HEADER: &str = "X-Page-Limit";
+pub(crate) const PAGE_OFFSET_HEADER: &str = "X-Page-Offset";
+pub(crate) const TOTAL_COUNT_HEADER: &str = "X-Total-Count";
+
+/**
+ * insert_pagination_headers
+ * 在响应头回显实际生效（夹取后）的分页参数，便于排查客户端分页问题；值为 None 时不写该头。
+ */
+fn insert_pagination_headers(
+    builder: &mut HttpResponseBuilder,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    total: Option<i64>,
+) {
+    for (name, value) in [
+        (PAGE_LIMIT_HEADER, limit),
+        (PAGE_OFFSET_HEADER, offset),
+        (TOTAL_COUNT_HEADER, total),
+    ] {
+        if let Some(value) = value {
+            builder.insert_header((name, value.to_string()));
+        }
+    }
+}
+
 fn new_trace_id() -> String {
     Uuid::new_v4().to_string()
 }
@@ -100,7 +142,140 @@ fn make_db_degraded_error(endpoint: &str, err: &anyhow::Error) -> ApiError {
         degraded: true,
         hint: Some("查看后端日志并按 trace_id 定位具体数据库错误。".to_string()),
         detail: error_detail_for_client(err),
+        fields: None,
+    }
+}
+
+/**
+ * MAX_JSON_BODY_BYTES
+ * JSON 请求体大小上限（64KB），防止超大 description/tags 耗尽内存。
+ */
+const MAX_JSON_BODY_BYTES: usize = 64 * 1024;
+
+/**
+ * json_config
+ * 全局 JSON 解析配置：限制请求体大小，解析失败统一返回 code 为 invalid_body 的 ApiError。
+ */
+pub fn json_config() -> web::JsonConfig {
+    web::JsonConfig::default()
+        .limit(MAX_JSON_BODY_BYTES)
+        .error_handler(json_error_handler)
+}
+
+fn json_error_handler(err: JsonPayloadError, req: &HttpRequest) -> actix_web::Error {
+    let is_zh = get_language_from_request(req).starts_with("zh");
+    let too_large = matches!(
+        err,
+        JsonPayloadError::Overflow { .. } | JsonPayloadError::OverflowKnownLength { .. }
+    );
+    let (status, message) = match (too_large, is_zh) {
+        (true, true) => (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("请求体过大（上限 {} 字节）。", MAX_JSON_BODY_BYTES),
+        ),
+        (true, false) => (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!(
+                "Request body is too large (limit {} bytes).",
+                MAX_JSON_BODY_BYTES
+            ),
+        ),
+        (false, true) => (
+            StatusCode::BAD_REQUEST,
+            "请求体不是合法的 JSON。".to_string(),
+        ),
+        (false, false) => (
+            StatusCode::BAD_REQUEST,
+            "Request body is not valid JSON.".to_string(),
+        ),
+    };
+    let response = HttpResponse::build(status).json(ApiResponse::<()> {
+        success: false,
+        data: None,
+        message: Some(message),
+        error: Some(ApiError {
+            code: "invalid_body".to_string(),
+            trace_id: new_trace_id(),
+            degraded: false,
+            hint: None,
+            detail: Some(err.to_string()),
+            fields: None,
+        }),
+    });
+    actix_web::error::InternalError::from_response(err, response).into()
+}
+
+/**
+ * constraint_violation_response
+ * 将写入时的约束冲突转换为客户端错误：唯一约束 → 409 duplicate，外键约束 → 400 invalid_reference。
+ */
+fn constraint_violation_response(
+    req: &HttpRequest,
+    violation: ConstraintViolation,
+    err: &anyhow::Error,
+) -> HttpResponse {
+    let is_zh = get_language_from_request(req).starts_with("zh");
+    let (status, code, message) = match (violation, is_zh) {
+        (ConstraintViolation::Unique, true) => (StatusCode::CONFLICT, "duplicate", "记录已存在。"),
+        (ConstraintViolation::Unique, false) => (
+            StatusCode::CONFLICT,
+            "duplicate",
+            "The record already exists.",
+        ),
+        (ConstraintViolation::ForeignKey, true) => (
+            StatusCode::BAD_REQUEST,
+            "invalid_reference",
+            "引用的记录不存在或已被删除。",
+        ),
+        (ConstraintViolation::ForeignKey, false) => (
+            StatusCode::BAD_REQUEST,
+            "invalid_reference",
+            "A referenced record does not exist or was deleted.",
+        ),
+    };
+    log::info!("constraint violation code={} err={:?}", code, err);
+    HttpResponse::build(status).json(ApiResponse::<()> {
+        success: false,
+        data: None,
+        message: Some(message.to_string()),
+        error: Some(ApiError {
+            code: code.to_string(),
+            trace_id: new_trace_id(),
+            degraded: false,
+            hint: None,
+            detail: error_detail_for_client(err),
+            fields: None,
+        }),
+    })
+}
+
+/**
+ * database_error_response
+ * 数据库错误的统一响应：缺少 Postgres（仅 Supabase 部署）时返回 501 + feature_requires_postgres，其余为 500。
+ */
+fn database_error_response(err: &anyhow::Error) -> HttpResponse {
+    if err.downcast_ref::<PostgresNotConfiguredError>().is_some() {
+        return HttpResponse::NotImplemented().json(ApiResponse::<()> {
+            success: false,
+            data: None,
+            message: Some(
+                "This feature requires a Postgres database; this deployment only has Supabase configured."
+                    .to_string(),
+            ),
+            error: Some(ApiError {
+                code: "feature_requires_postgres".to_string(),
+                trace_id: new_trace_id(),
+                degraded: false,
+                hint: Some("Set DATABASE_URL to enable this feature.".to_string()),
+                detail: None,
+                fields: None,
+            }),
+        });
     }
+    HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!(
+        "Database error: {:?}",
+        err
+    )))
 }
 
 fn make_db_degraded_response<T>(
@@ -123,7 +298,9 @@ pub struct CreateSponsorshipRequestBody {
     pub product_ref: String,
     pub placement: String,
     pub slot_index: Option<i32>,
+    #[serde(default)]
     pub duration_days: i32,
+    pub duration_months: Option<i32>,
     pub note: Option<String>,
 }
 
@@ -138,7 +315,13 @@ pub async fn create_sponsorship_request(
     let email = body.email.trim().to_string();
     let product_ref = body.product_ref.trim().to_string();
     let placement = body.placement.trim().to_string();
-    let duration_days = body.duration_days;
+    let duration_days = match body.duration_months {
+        Some(months) => match validate_requested_months(lang, months) {
+            Ok(months) => crate::db::months_to_days(months, crate::db::days_per_month()),
+            Err(resp) => return resp,
+        },
+        None => body.duration_days,
+    };
 
     if email.is_empty() || product_ref.is_empty() || placement.is_empty() || duration_days <= 0 {
         return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
@@ -150,47 +333,22 @@ pub async fn create_sponsorship_request(
         ));
     }
 
-    if placement != "home_top" && placement != "home_right" {
-        return HttpResponse::BadRequest()
-            .json(ApiResponse::<()>::error("Invalid placement".to_string()));
-    }
-
-    if placement == "home_top" {
-        match body.slot_index {
-            Some(0 | 1) => {}
-            _ => {
-                return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
-                    if lang.starts_with("zh") {
-                        "顶部定价位必须指定 slot_index=0(左) 或 1(右)".to_string()
-                    } else {
-                        "home_top requires slot_index 0 (left) or 1 (right)".to_string()
-                    },
-                ))
-            }
-        }
-    }
+    let duration_days = match validate_requested_duration_days(lang, duration_days) {
+        Ok(days) => days,
+        Err(resp) => return resp,
+    };
 
-    if placement == "home_right" {
-        match body.slot_index {
-            Some(0..=2) => {}
-            _ => {
-                return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
-                    if lang.starts_with("zh") {
-                        "右侧定价位必须指定 slot_index=0/1/2（对应 1/2/3 槽位）".to_string()
-                    } else {
-                        "home_right requires slot_index 0/1/2".to_string()
-                    },
-                ))
-            }
-        }
-    }
+    let placement = match validate_sponsorship_slot(lang, &placement, body.slot_index) {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
 
     let req_model = CreateSponsorshipRequest {
         email,
         product_ref,
         placement,
         slot_index: body.slot_index,
-        duration_days: duration_days.clamp(1, 365),
+        duration_days,
         note: body
             .note
             .map(|v| v.trim().to_string())
@@ -198,7 +356,23 @@ pub async fn create_sponsorship_request(
     };
 
     match db.create_sponsorship_request(req_model).await {
-        Ok(created) => HttpResponse::Ok().json(ApiResponse::success(created)),
+        Ok(created) => {
+            let db_for_email = db.get_ref().clone();
+            let request_id = created.id;
+            tokio::spawn(async move {
+                if let Err(e) = db_for_email
+                    .send_admin_sponsorship_request_notification(request_id)
+                    .await
+                {
+                    log::warn!(
+                        "Sponsorship request admin notify failed for {}: {:?}",
+                        request_id,
+                        e
+                    );
+                }
+            });
+            HttpResponse::Ok().json(ApiResponse::success(created))
+        }
         Err(e) => {
             if is_db_unavailable_error(&e) {
                 return HttpResponse::Ok().json(make_db_degraded_response(
@@ -225,16 +399,146 @@ pub async fn create_sponsorship_request(
                     &e,
                 ));
             }
-            HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)))
+            if let Some(violation) = constraint_violation(&e) {
+                return constraint_violation_response(&req, violation, &e);
+            }
+            database_error_response(&e)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MySponsorshipOrdersQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/**
+ * list_my_sponsorship_orders
+ * 用户端：通过 Bearer token 识别当前用户邮箱，仅返回本人的赞助订单。
+ */
+pub async fn list_my_sponsorship_orders(
+    req: HttpRequest,
+    query: web::Query<MySponsorshipOrdersQuery>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    let lang = get_language_from_request(&req);
+
+    let email = match extract_bearer_token(&req) {
+        Some(token) => resolve_supabase_email_from_bearer(&token).await,
+        None => None,
+    };
+    let Some(email) = email else {
+        return HttpResponse::Unauthorized().json(ApiResponse::<()>::error(
+            if lang.starts_with("zh") {
+                "请先登录".to_string()
+            } else {
+                "Unauthorized".to_string()
+            },
+        ));
+    };
+
+    let limit = query.limit.unwrap_or(20);
+    let offset = query.offset.unwrap_or(0);
+
+    match db
+        .list_sponsorship_orders_for_email(&email, limit, offset)
+        .await
+    {
+        Ok(list) => HttpResponse::Ok().json(ApiResponse::success(list)),
+        Err(e) => {
+            if is_db_unavailable_error(&e) {
+                return HttpResponse::Ok().json(make_db_degraded_response(
+                    "GET /api/sponsorship/orders",
+                    Vec::<UserSponsorshipOrder>::new(),
+                    if lang.starts_with("zh") {
+                        "数据库连接不可用，已降级返回空订单列表。".to_string()
+                    } else {
+                        "Database is unavailable. Degraded mode: returning empty order list."
+                            .to_string()
+                    },
+                    &e,
+                ));
+            }
+            database_error_response(&e)
         }
     }
 }
 
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BackgroundTaskStatus {
+    pub name: String,
+    pub last_tick_at: Option<String>,
+    pub stale_secs: Option<u64>,
+    pub healthy: bool,
+}
+
 #[derive(Debug, Serialize, ToSchema)]
 pub struct HealthCheckResponse {
     pub status: String,
     pub timestamp: String,
+    pub postgres_configured: bool,
+    pub supabase_configured: bool,
+    pub supabase_fallback_enabled: bool,
+    pub background_tasks: Vec<BackgroundTaskStatus>,
+}
+
+/**
+ * TaskHeartbeat
+ * 后台任务心跳：每轮循环记录一次 Unix 秒，超过 stale_after_secs 未更新即视为任务停摆。
+ */
+pub struct TaskHeartbeat {
+    pub name: &'static str,
+    pub stale_after_secs: u64,
+    last_tick_unix: AtomicU64,
+}
+
+impl TaskHeartbeat {
+    pub fn new(name: &'static str, stale_after_secs: u64) -> Self {
+        Self {
+            name,
+            stale_after_secs,
+            last_tick_unix: AtomicU64::new(0),
+        }
+    }
+
+    pub fn tick(&self) {
+        self.tick_at(Utc::now().timestamp().max(0) as u64);
+    }
+
+    pub fn tick_at(&self, now_unix: u64) {
+        self.last_tick_unix.store(now_unix, Ordering::Relaxed);
+    }
+
+    pub fn status(&self, now_unix: u64) -> BackgroundTaskStatus {
+        let last = self.last_tick_unix.load(Ordering::Relaxed);
+        if last == 0 {
+            return BackgroundTaskStatus {
+                name: self.name.to_string(),
+                last_tick_at: None,
+                stale_secs: None,
+                healthy: false,
+            };
+        }
+        let stale_secs = now_unix.saturating_sub(last);
+        BackgroundTaskStatus {
+            name: self.name.to_string(),
+            last_tick_at: Utc
+                .timestamp_opt(last as i64, 0)
+                .single()
+                .map(|t| t.to_rfc3339()),
+            stale_secs: Some(stale_secs),
+            healthy: stale_secs <= self.stale_after_secs,
+        }
+    }
+}
+
+/**
+ * BackgroundTasks
+ * 启动时注册的后台任务心跳列表，供健康检查汇报。
+ */
+pub struct BackgroundTasks {
+    pub tasks: Vec<Arc<TaskHeartbeat>>,
 }
 
 #[utoipa::path(
@@ -243,10 +547,29 @@ pub struct HealthCheckResponse {
     responses((status = 200, body = HealthCheckResponse))
 )]
 #[get("/health")]
-pub async fn health_check() -> impl Responder {
+pub async fn health_check(
+    db: web::Data<Arc<Database>>,
+    background: web::Data<BackgroundTasks>,
+) -> impl Responder {
+    let (postgres_configured, supabase_configured) = db.configured_backends();
+    let now = Utc::now();
+    let background_tasks: Vec<BackgroundTaskStatus> = background
+        .tasks
+        .iter()
+        .map(|task| task.status(now.timestamp().max(0) as u64))
+        .collect();
+    let status = if postgres_configured && background_tasks.iter().all(|t| t.healthy) {
+        "ok"
+    } else {
+        "degraded"
+    };
     HttpResponse::Ok().json(HealthCheckResponse {
-        status: "ok".to_string(),
-        timestamp: Utc::now().to_rfc3339(),
+        status: status.to_string(),
+        timestamp: now.to_rfc3339(),
+        postgres_configured,
+        supabase_configured,
+        supabase_fallback_enabled: postgres_configured && supabase_configured,
+        background_tasks,
     })
 }
 
@@ -256,15 +579,85 @@ pub async fn health_check() -> impl Responder {
     params(QueryParams),
     responses(
         (status = 200, body = ProductsApiResponse),
+        (status = 501, body = EmptyApiResponse),
         (status = 500, body = EmptyApiResponse)
     )
 )]
 pub async fn get_products(
+    req: HttpRequest,
     query: web::Query<QueryParams>,
+    expand: web::Query<ProductExpandQuery>,
     db: web::Data<Arc<Database>>,
 ) -> impl Responder {
-    match db.get_products(query.into_inner()).await {
-        Ok(products) => HttpResponse::Ok().json(ApiResponse::success(products)),
+    let lang = get_language_from_request(&req);
+    let mut params = query.into_inner();
+    params.status = params
+        .status
+        .map(|v| v.trim().to_ascii_lowercase())
+        .filter(|v| !v.is_empty());
+    params.maker_email = params
+        .maker_email
+        .map(|v| v.trim().to_ascii_lowercase())
+        .filter(|v| !v.is_empty());
+
+    // 仅在需要校验 maker 归属或回填 user_id 时才去 Supabase 解析 Bearer，避免公开列表每次多一次外部请求
+    let has_user_id = params
+        .user_id
+        .as_deref()
+        .is_some_and(|v| !v.trim().is_empty());
+    let needs_bearer_email = params.maker_email.is_some() || !has_user_id;
+    let bearer_email = match extract_bearer_token(&req) {
+        Some(token) if needs_bearer_email => resolve_supabase_email_from_bearer(&token).await,
+        _ => None,
+    };
+
+    if params.include_pending == Some(true) && validate_admin_token(&req).is_err() {
+        return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+            if lang.starts_with("zh") {
+                "仅管理员可使用 include_pending".to_string()
+            } else {
+                "include_pending is only available to admins".to_string()
+            },
+        ));
+    }
+
+    if let Some(maker_email) = params.maker_email.as_deref() {
+        let wants_private = params.status.as_deref() != Some("approved");
+        let is_owner = bearer_email
+            .as_deref()
+            .is_some_and(|email| is_same_user_email(maker_email, email));
+        if wants_private && !is_owner && validate_admin_token(&req).is_err() {
+            return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+                if lang.starts_with("zh") {
+                    "无权查看该创作者的未公开产品".to_string()
+                } else {
+                    "Not allowed to view this maker's unpublished products".to_string()
+                },
+            ));
+        }
+    }
+
+    params.user_id = match params
+        .user_id
+        .as_deref()
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+    {
+        Some(v) if is_anonymous_user_id(v) => None,
+        Some(v) => Some(v.to_string()),
+        None => bearer_email,
+    };
+
+    let (page_limit, page_offset) = (params.limit, params.offset);
+    match db.get_products(params).await {
+        Ok(mut products) => {
+            if expands_category(expand.expand.as_deref()) {
+                attach_product_categories(&db, &mut products).await;
+            }
+            let mut resp = HttpResponse::Ok();
+            insert_pagination_headers(&mut resp, page_limit, page_offset, None);
+            resp.json(ApiResponse::success(products))
+        }
         Err(e) => {
             if is_db_unavailable_error(&e) {
                 return HttpResponse::Ok().json(make_db_degraded_response(
@@ -274,18 +667,92 @@ pub async fn get_products(
                     &e,
                 ));
             }
+            if e.downcast_ref::<UnsupportedFilterError>().is_some() {
+                return HttpResponse::NotImplemented().json(ApiResponse::<()> {
+                    success: false,
+                    data: None,
+                    message: Some(if lang.starts_with("zh") {
+                        "当前数据库后端不支持该过滤条件。".to_string()
+                    } else {
+                        "This filter is not supported by the current database backend.".to_string()
+                    }),
+                    error: Some(ApiError {
+                        code: "FILTER_UNSUPPORTED".to_string(),
+                        trace_id: new_trace_id(),
+                        degraded: false,
+                        hint: Some(
+                            "Configure DATABASE_URL (Postgres) to use this filter.".to_string(),
+                        ),
+                        detail: Some(e.to_string()),
+                        fields: None,
+                    }),
+                });
+            }
 
-            HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)))
+            database_error_response(&e)
         }
     }
 }
 
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+pub struct ProductExpandQuery {
+    pub expand: Option<String>,
+}
+
+/**
+ * expands_category
+ * 解析 ?expand=（逗号分隔）是否包含 category；默认不展开，保持响应精简。
+ */
+fn expands_category(expand: Option<&str>) -> bool {
+    expand.is_some_and(|v| {
+        v.split(',')
+            .any(|part| part.trim().eq_ignore_ascii_case("category"))
+    })
+}
+
+/**
+ * attach_product_categories
+ * 为产品批量附加分类详情；失败只记日志并返回未展开的产品。
+ */
+async fn attach_product_categories(db: &Database, products: &mut [Product]) {
+    if let Err(e) = db.expand_product_categories(products).await {
+        log::warn!("category expansion failed: {:?}", e);
+    }
+}
+
 #[derive(Debug, Deserialize, IntoParams, ToSchema)]
 pub struct SearchQuery {
     pub q: Option<String>,
     pub limit: Option<i64>,
+    pub category: Option<String>,
     pub language: Option<String>,
+    pub highlight: Option<bool>,
+}
+
+/**
+ * build_search_highlights
+ * 为搜索结果生成命中片段：优先 description，其次 slogan；均未命中的产品不返回片段。
+ */
+fn build_search_highlights(products: &[Product], q: &str) -> Vec<SearchHighlight> {
+    products
+        .iter()
+        .filter_map(|p| {
+            [
+                ("description", p.description.as_str()),
+                ("slogan", p.slogan.as_str()),
+            ]
+            .into_iter()
+            .find_map(|(field, text)| {
+                crate::db::build_search_snippet(text, q, crate::db::SEARCH_SNIPPET_MAX_CHARS).map(
+                    |snippet| SearchHighlight {
+                        product_id: p.id.clone(),
+                        field: field.to_string(),
+                        snippet,
+                    },
+                )
+            })
+        })
+        .collect()
 }
 
 #[utoipa::path(
@@ -308,14 +775,20 @@ pub async fn search(
         return HttpResponse::Ok().json(ApiResponse::success(SearchResult {
             products: Vec::new(),
             developers: Vec::new(),
+            highlights: None,
         }));
     }
 
     let limit = query.limit.unwrap_or(8).clamp(1, 20);
+    let normalize_filter = |v: &Option<String>| {
+        v.as_deref()
+            .map(|v| v.trim().to_ascii_lowercase())
+            .filter(|v| !v.is_empty())
+    };
     let params = QueryParams {
-        category: None,
+        category: normalize_filter(&query.category),
         tags: None,
-        language: query.language.clone(),
+        language: normalize_filter(&query.language),
         status: Some("approved".to_string()),
         search: Some(q.to_string()),
         maker_email: None,
@@ -323,6 +796,13 @@ pub async fn search(
         dir: None,
         limit: Some(limit),
         offset: None,
+        user_id: None,
+        created_after: None,
+        created_before: None,
+        min_likes: None,
+        sponsor_verified: None,
+        include_pending: None,
+        seed: None,
     };
 
     let result = async {
@@ -333,10 +813,17 @@ pub async fn search(
     .await;
 
     match result {
-        Ok((products, developers)) => HttpResponse::Ok().json(ApiResponse::success(SearchResult {
-            products,
-            developers,
-        })),
+        Ok((products, developers)) => {
+            let highlights = query
+                .highlight
+                .unwrap_or(false)
+                .then(|| build_search_highlights(&products, q));
+            HttpResponse::Ok().json(ApiResponse::success(SearchResult {
+                products,
+                developers,
+                highlights,
+            }))
+        }
         Err(e) => {
             if is_db_unavailable_error(&e) {
                 let lang = get_language_from_request(&req);
@@ -351,18 +838,30 @@ pub async fn search(
                     SearchResult {
                         products: Vec::new(),
                         developers: Vec::new(),
+                        highlights: None,
                     },
                     message.to_string(),
                     &e,
                 ));
             }
 
-            HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)))
+            database_error_response(&e)
         }
     }
 }
 
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+pub struct ProductViewerQuery {
+    pub user_id: Option<String>,
+    pub expand: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProductRedirectPayload {
+    pub product_id: String,
+    pub redirect_to: String,
+}
+
 #[utoipa::path(
     get,
     path = "/api/products/{id}",
@@ -376,12 +875,51 @@ pub async fn search(
 pub async fn get_product_by_id(
     req: HttpRequest,
     path: web::Path<String>,
+    query: web::Query<ProductViewerQuery>,
     db: web::Data<Arc<Database>>,
 ) -> impl Responder {
     let id = path.into_inner();
 
-    match db.get_product_by_id(&id).await {
-        Ok(Some(product)) => {
+    let mut lookup = db.get_product_by_id(&id).await;
+    if matches!(lookup, Ok(None)) && Uuid::parse_str(id.trim()).is_err() {
+        // 当前名称派生的 slug 直接返回产品；只有改名前的旧 slug 才 301 到前台详情页
+        if let Ok(Some(current_id)) = db.resolve_product_current_slug(&id).await {
+            lookup = db.get_product_by_id(&current_id).await;
+        } else if let Ok(Some(canonical_id)) = db.resolve_product_slug_redirect(&id).await {
+            let frontend_base_url = env::var("FRONTEND_BASE_URL")
+                .ok()
+                .unwrap_or_else(|| "http://localhost:3000".to_string());
+            let locale = if get_language_from_request(&req).starts_with("zh") {
+                "zh"
+            } else {
+                "en"
+            };
+            let location =
+                crate::db::build_product_detail_url(&frontend_base_url, locale, &canonical_id);
+            return HttpResponse::MovedPermanently()
+                .insert_header(("Location", location.clone()))
+                .json(ApiResponse::success(ProductRedirectPayload {
+                    product_id: canonical_id,
+                    redirect_to: location,
+                }));
+        }
+    }
+
+    match lookup {
+        Ok(Some(mut product)) => {
+            if expands_category(query.expand.as_deref()) {
+                attach_product_categories(&db, std::slice::from_mut(&mut product)).await;
+            }
+            if let Some(viewer_id) = resolve_viewer_id(&req, query.user_id.as_deref()).await {
+                if let Ok((has_liked, has_favorited)) = db
+                    .get_product_interaction_flags(&product.id, &viewer_id)
+                    .await
+                {
+                    product.has_liked = Some(has_liked);
+                    product.has_favorited = Some(has_favorited);
+                }
+            }
+
             let is_admin = validate_admin_token(&req).is_ok();
             if matches!(product.status, crate::models::ProductStatus::Approved) || is_admin {
                 return HttpResponse::Ok().json(ApiResponse::success(product));
@@ -400,34 +938,137 @@ pub async fn get_product_by_id(
         Ok(None) => {
             HttpResponse::NotFound().json(ApiResponse::<()>::error("Product not found".to_string()))
         }
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<()>::error(format!("Database error: {:?}", e))),
+        Err(e) => database_error_response(&e),
     }
 }
 
-#[utoipa::path(
-    post,
-    path = "/api/products",
-    request_body = CreateProductRequest,
-    responses(
-        (status = 201, body = ProductApiResponse),
-        (status = 400, body = EmptyApiResponse),
-        (status = 500, body = EmptyApiResponse)
-    )
-)]
-pub async fn create_product(
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ProductOgQuery {
+    pub lang: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProductOgMetadata {
+    pub title: String,
+    pub description: String,
+    pub image: String,
+    pub url: String,
+    pub site_name: String,
+}
+
+/**
+ * get_product_og
+ * 分享元数据：返回产品的 OpenGraph 字段（slogan 作描述，logo 缺失时用站点默认图），仅限已通过审核的产品。
+ */
+pub async fn get_product_og(
     req: HttpRequest,
-    product_data: web::Json<CreateProductRequest>,
+    path: web::Path<String>,
+    query: web::Query<ProductOgQuery>,
     db: web::Data<Arc<Database>>,
 ) -> impl Responder {
-    // Get language from Accept-Language header
-    let lang = req
-        .headers()
-        .get("Accept-Language")
-        .and_then(|h| h.to_str().ok())
-        .unwrap_or("en");
-
-    /**
+    let id = path.into_inner();
+    let lang = query
+        .lang
+        .as_deref()
+        .map(|v| v.trim().to_ascii_lowercase())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| get_language_from_request(&req).to_ascii_lowercase());
+    let locale = if lang.starts_with("zh") { "zh" } else { "en" };
+
+    let product = match db.get_product_by_id(&id).await {
+        Ok(Some(product)) if matches!(product.status, crate::models::ProductStatus::Approved) => {
+            product
+        }
+        Ok(_) => {
+            return HttpResponse::NotFound()
+                .json(ApiResponse::<()>::error("Product not found".to_string()))
+        }
+        Err(e) => return database_error_response(&e),
+    };
+
+    let frontend_base_url = env::var("FRONTEND_BASE_URL")
+        .ok()
+        .unwrap_or_else(|| "http://localhost:3000".to_string());
+    let base = frontend_base_url.trim().trim_end_matches('/').to_string();
+
+    let slogan = product.slogan.trim();
+    let description = if !slogan.is_empty() {
+        slogan.to_string()
+    } else if locale == "zh" {
+        "独立开发者展示产品的平台".to_string()
+    } else {
+        "The Forge for Solo Makers' Products".to_string()
+    };
+    let image = product
+        .logo_url
+        .as_deref()
+        .map(|v| v.trim())
+        .filter(|v| v.starts_with("https://") || v.starts_with("http://"))
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| format!("{}/docs/imgs/image.jpg", base));
+
+    HttpResponse::Ok().json(ApiResponse::success(ProductOgMetadata {
+        title: format!("{} - SoloForge", product.name.trim()),
+        description,
+        image,
+        url: crate::db::build_product_detail_url(&frontend_base_url, locale, &product.id),
+        site_name: "SoloForge".to_string(),
+    }))
+}
+
+/**
+ * tag_limit_response
+ * 标签数量超过 MAX_PRODUCT_TAGS 时返回本地化的 400；创建与更新产品共用。
+ */
+fn tag_limit_response(lang: &str, tag_count: usize) -> Option<HttpResponse> {
+    if tag_count <= crate::db::MAX_PRODUCT_TAGS {
+        return None;
+    }
+    let message = if lang.starts_with("zh") {
+        format!(
+            "标签最多 {} 个（当前 {}）。",
+            crate::db::MAX_PRODUCT_TAGS,
+            tag_count
+        )
+    } else {
+        format!(
+            "At most {} tags are allowed (current {}).",
+            crate::db::MAX_PRODUCT_TAGS,
+            tag_count
+        )
+    };
+    Some(HttpResponse::BadRequest().json(ApiResponse::<()>::error(message)))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateProductQuery {
+    pub auto_detect_language: Option<bool>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/products",
+    request_body = CreateProductRequest,
+    responses(
+        (status = 201, body = ProductApiResponse),
+        (status = 400, body = EmptyApiResponse),
+        (status = 500, body = EmptyApiResponse)
+    )
+)]
+pub async fn create_product(
+    req: HttpRequest,
+    query: web::Query<CreateProductQuery>,
+    product_data: web::Json<CreateProductRequest>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    // Get language from Accept-Language header
+    let lang = req
+        .headers()
+        .get("Accept-Language")
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or("en");
+
+    /**
      * count_unicode_characters
      * 统计字符串的 Unicode 字符数量（按 Rust char 计数）。
      */
@@ -437,7 +1078,23 @@ pub async fn create_product(
 
     const MIN_PRODUCT_DESCRIPTION_CHARS: usize = 250;
 
-    let product = product_data.into_inner();
+    let mut product = product_data.into_inner();
+    crate::db::apply_detected_language(&mut product, query.auto_detect_language.unwrap_or(false));
+    if let Some(resp) = tag_limit_response(lang, crate::db::normalize_tags(&product.tags).len()) {
+        return resp;
+    }
+
+    product.maker_website = match crate::db::normalize_website_url(
+        "maker_website",
+        product.maker_website.as_deref().unwrap_or(""),
+    ) {
+        Ok(v) => v,
+        Err(e) => {
+            return HttpResponse::BadRequest()
+                .json(ApiResponse::<()>::error(e.localized_message(lang)))
+        }
+    };
+
     let desc_len = count_unicode_characters(product.description.trim());
     if desc_len < MIN_PRODUCT_DESCRIPTION_CHARS {
         let message = if lang.starts_with("zh") {
@@ -454,20 +1111,46 @@ pub async fn create_product(
         return HttpResponse::BadRequest().json(ApiResponse::<()>::error(message));
     }
 
-    match db.create_product(product).await {
+    let verified_email = match extract_bearer_token(&req) {
+        Some(token) => resolve_supabase_email_from_bearer(&token).await,
+        None => None,
+    };
+    let maker_verified = verified_email
+        .as_deref()
+        .is_some_and(|email| is_same_user_email(email, &product.maker_email));
+
+    if let Err(resp) =
+        check_maker_account_age(&db, lang, &product.maker_email, maker_verified).await
+    {
+        return resp;
+    }
+
+    match db.create_product(product, maker_verified).await {
         Ok(product) => {
+            let auto_approved = matches!(product.status, crate::models::ProductStatus::Approved);
+            spawn_product_webhook_event(&db, "product.created", &product);
+            if auto_approved {
+                spawn_product_webhook_event(&db, "product.approved", &product);
+            }
             let db_for_email = db.get_ref().clone();
             let product_for_email = product.clone();
             tokio::spawn(async move {
-                let _ = db_for_email
-                    .send_admin_product_submission_notification(&product_for_email)
-                    .await;
+                let _ = if auto_approved {
+                    db_for_email
+                        .send_maker_product_review_notification(&product_for_email)
+                        .await
+                } else {
+                    db_for_email
+                        .send_admin_product_submission_notification(&product_for_email)
+                        .await
+                };
             });
 
-            let message = if lang.starts_with("zh") {
-                "产品提交成功，等待审核"
-            } else {
-                "Product submitted successfully, pending review"
+            let message = match (auto_approved, lang.starts_with("zh")) {
+                (true, true) => "产品提交成功，已自动通过审核",
+                (true, false) => "Product submitted successfully and auto-approved",
+                (false, true) => "产品提交成功，等待审核",
+                (false, false) => "Product submitted successfully, pending review",
             };
 
             HttpResponse::Created().json(ApiResponse {
@@ -477,9 +1160,126 @@ pub async fn create_product(
                 error: None,
             })
         }
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<()>::error(format!("Database error: {:?}", e))),
+        Err(e) => {
+            if let Some(too_long) = e.downcast_ref::<ProductFieldTooLongError>() {
+                return HttpResponse::BadRequest()
+                    .json(ApiResponse::<()>::error(too_long.localized_message(lang)));
+            }
+            if let Some(violation) = constraint_violation(&e) {
+                return constraint_violation_response(&req, violation, &e);
+            }
+            database_error_response(&e)
+        }
+    }
+}
+
+/**
+ * min_maker_age_from_env_value
+ * 解析 MIN_MAKER_AGE_HOURS：正整数小时开启提交门槛，未设置 / 0 / 非法值关闭（最大 8760）。
+ */
+fn min_maker_age_from_env_value(raw: Option<&str>) -> Option<Duration> {
+    raw.and_then(|v| v.trim().parse::<i64>().ok())
+        .filter(|hours| *hours > 0)
+        .map(|hours| Duration::hours(hours.min(8760)))
+}
+
+/**
+ * maker_age_remaining
+ * 创作者账号距满足最短注册时长还差多久；已满足返回 None（created_at 恰好达到边界即放行）。
+ */
+fn maker_age_remaining(
+    created_at: chrono::DateTime<Utc>,
+    min_age: Duration,
+    now: chrono::DateTime<Utc>,
+) -> Option<Duration> {
+    let eligible_at = created_at + min_age;
+    (eligible_at > now).then(|| eligible_at - now)
+}
+
+/**
+ * check_maker_account_age
+ * MIN_MAKER_AGE_HOURS 开启时：提交者须以 maker_email 登录（Bearer token 验证邮箱），
+ * 且其 developers 记录已存在满指定时长，否则返回 403 与剩余等待时间。
+ */
+async fn check_maker_account_age(
+    db: &Database,
+    lang: &str,
+    maker_email: &str,
+    maker_verified: bool,
+) -> Result<(), HttpResponse> {
+    let Some(min_age) =
+        min_maker_age_from_env_value(env::var("MIN_MAKER_AGE_HOURS").ok().as_deref())
+    else {
+        return Ok(());
+    };
+
+    let created_at = if maker_verified {
+        db.get_developer_created_at(maker_email)
+            .await
+            .map_err(|e| database_error_response(&e))?
+    } else {
+        None
+    };
+    maker_account_age_gate(lang, maker_verified, created_at, min_age, Utc::now())
+}
+
+/**
+ * maker_account_age_gate
+ * 账号时长门槛的判定：未验证邮箱提示登录；已登录但还没有 developers 记录（首次提交）时提示先创建创作者资料，
+ * 资料创建时间即计时起点；未满时长返回剩余等待时间。
+ */
+fn maker_account_age_gate(
+    lang: &str,
+    maker_verified: bool,
+    created_at: Option<chrono::DateTime<Utc>>,
+    min_age: Duration,
+    now: chrono::DateTime<Utc>,
+) -> Result<(), HttpResponse> {
+    if !maker_verified {
+        return Err(HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+            if lang.starts_with("zh") {
+                "请先使用创作者邮箱登录以完成验证，再提交产品。".to_string()
+            } else {
+                "Please sign in with your maker email to verify it before submitting.".to_string()
+            },
+        )));
     }
+    let Some(created_at) = created_at else {
+        let message = if lang.starts_with("zh") {
+            format!(
+                "请先在开发者中心创建创作者资料，资料创建满 {} 小时后即可提交产品。",
+                min_age.num_hours()
+            )
+        } else {
+            format!(
+                "Please create your maker profile in the developer center first. You can submit products {} hours after it is created.",
+                min_age.num_hours()
+            )
+        };
+        return Err(HttpResponse::Forbidden().json(ApiResponse::<()>::error(message)));
+    };
+
+    let Some(remaining) = maker_age_remaining(created_at, min_age, now) else {
+        return Ok(());
+    };
+    let minutes = (remaining.num_seconds() + 59) / 60;
+    let (hours, minutes) = (minutes / 60, minutes % 60);
+    let message = if lang.starts_with("zh") {
+        format!(
+            "账号需注册满 {} 小时才能提交产品，请在 {} 小时 {} 分钟后重试。",
+            min_age.num_hours(),
+            hours,
+            minutes
+        )
+    } else {
+        format!(
+            "Your maker account must be at least {} hours old to submit. Try again in {}h {}m.",
+            min_age.num_hours(),
+            hours,
+            minutes
+        )
+    };
+    Err(HttpResponse::Forbidden().json(ApiResponse::<()>::error(message)))
 }
 
 fn verify_admin_review_token(
@@ -618,6 +1418,19 @@ pub async fn admin_review_product(
 
     match db.update_product(&product_id, updates).await {
         Ok(Some(product)) => {
+            if let Err(e) = db
+                .record_admin_audit(
+                    "review-link",
+                    &format!("product.{}", action),
+                    "product",
+                    Some(&product_id),
+                    None,
+                )
+                .await
+            {
+                log::warn!("Admin audit log write failed action=product.{} err={:?}", action, e);
+            }
+            spawn_product_status_webhook_event(&db, &product);
             let db_for_email = db.get_ref().clone();
             let product_for_email = product.clone();
             tokio::spawn(async move {
@@ -626,6 +1439,8 @@ pub async fn admin_review_product(
                     .await;
             });
 
+            db.invalidate_caches();
+
             if action == "approve" {
                 HttpResponse::Ok().content_type("text/html; charset=utf-8").body(
                     "<h2>审核通过</h2><p>该产品已被标记为 approved。</p><hr/><h2>Approved</h2><p>The product is now approved.</p>",
@@ -652,6 +1467,19 @@ pub async fn admin_review_product(
     }
 }
 
+/**
+ * updates_change_review_status
+ * 更新请求是否试图修改审核状态（status / rejection_reason），这两个字段仅管理员可写；
+ * 例外：创作者可把自己的产品重新提交为 pending。
+ */
+fn updates_change_review_status(updates: &UpdateProductRequest) -> bool {
+    let status_change = !matches!(
+        updates.status,
+        None | Some(crate::models::ProductStatus::Pending)
+    );
+    status_change || updates.rejection_reason.is_some()
+}
+
 pub async fn update_product(
     req: HttpRequest,
     path: web::Path<String>,
@@ -693,18 +1521,43 @@ pub async fn update_product(
             return HttpResponse::BadRequest().json(ApiResponse::<()>::error(message));
         }
     }
+    if let Some(tags) = updates.tags.as_deref() {
+        if let Some(resp) = tag_limit_response(
+            get_language_from_request(&req),
+            crate::db::normalize_tags(tags).len(),
+        ) {
+            return resp;
+        }
+    }
+    let is_admin = validate_admin_token(&req).is_ok();
+    if !is_admin && updates_change_review_status(&updates) {
+        return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+            if get_language_from_request(&req).starts_with("zh") {
+                "仅管理员可修改审核状态".to_string()
+            } else {
+                "Only admins can change the review status".to_string()
+            },
+        ));
+    }
+
     let existing = match db.get_product_by_id(&id).await {
         Ok(Some(v)) => v,
         Ok(None) => {
             return HttpResponse::NotFound()
                 .json(ApiResponse::<()>::error("Product not found".to_string()))
         }
-        Err(e) => {
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)))
-        }
+        Err(e) => return database_error_response(&e),
     };
 
+    let review_fields =
+        crate::db::review_on_edit_fields(env::var("REVIEW_ON_EDIT_FIELDS").ok().as_deref());
+    // 非管理员最多只能携带 pending（上面已拒绝其他值），编辑审核字段时总是重置为待审核。
+    let reset_to_pending = (!is_admin || updates.status.is_none())
+        && crate::db::requires_re_review(&existing, &updates, &review_fields, is_admin);
+    if reset_to_pending {
+        updates.status = Some(crate::models::ProductStatus::Pending);
+    }
+
     if let Some(status) = updates.status.clone() {
         match status {
             crate::models::ProductStatus::Rejected => {
@@ -735,6 +1588,7 @@ pub async fn update_product(
                     crate::models::ProductStatus::Approved | crate::models::ProductStatus::Rejected
                 );
             if should_notify {
+                spawn_product_status_webhook_event(&db, &product);
                 let db_for_email = db.get_ref().clone();
                 let product_for_email = product.clone();
                 tokio::spawn(async move {
@@ -743,14 +1597,184 @@ pub async fn update_product(
                         .await;
                 });
             }
+            if reset_to_pending {
+                db.invalidate_caches();
+                let db_for_email = db.get_ref().clone();
+                let product_for_email = product.clone();
+                tokio::spawn(async move {
+                    let _ = db_for_email
+                        .send_admin_product_submission_notification(&product_for_email)
+                        .await;
+                });
+            }
+            if is_admin {
+                audit_admin_action(
+                    &db,
+                    &req,
+                    "product.update",
+                    "product",
+                    Some(&id),
+                    Some(format!(
+                        "status={:?} -> {:?}",
+                        existing.status, product.status
+                    )),
+                )
+                .await;
+            }
 
             HttpResponse::Ok().json(ApiResponse::success(product))
         }
         Ok(None) => {
             HttpResponse::NotFound().json(ApiResponse::<()>::error("Product not found".to_string()))
         }
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<()>::error(format!("Database error: {:?}", e))),
+        Err(e) => match e.downcast_ref::<ProductFieldTooLongError>() {
+            Some(too_long) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+                too_long.localized_message(get_language_from_request(&req)),
+            )),
+            None => database_error_response(&e),
+        },
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+pub struct ProductEngagementQuery {
+    pub days: Option<i64>,
+}
+
+/**
+ * get_product_engagement
+ * 产品每日点赞 / 收藏时间序列（默认 30 天，最多 365 天），仅产品创作者或管理员可查看。
+ */
+pub async fn get_product_engagement(
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<ProductEngagementQuery>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    let product_id = path.into_inner().trim().to_string();
+    if Uuid::parse_str(&product_id).is_err() {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<()>::error("Invalid product id".to_string()));
+    }
+
+    let product = match db.get_product_by_id(&product_id).await {
+        Ok(Some(v)) => v,
+        Ok(None) => {
+            return HttpResponse::NotFound()
+                .json(ApiResponse::<()>::error("Product not found".to_string()))
+        }
+        Err(e) => return database_error_response(&e),
+    };
+
+    let bearer_email = match extract_bearer_token(&req) {
+        Some(token) => resolve_supabase_email_from_bearer(&token).await,
+        None => None,
+    };
+    let is_owner = bearer_email
+        .as_deref()
+        .is_some_and(|v| is_same_user_email(&product.maker_email, v));
+    if !is_owner && validate_admin_token(&req).is_err() {
+        return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+            if get_language_from_request(&req).starts_with("zh") {
+                "无权查看该产品的互动数据".to_string()
+            } else {
+                "Not allowed to view this product's engagement".to_string()
+            },
+        ));
+    }
+
+    let days = query.days.unwrap_or(30).clamp(1, 365);
+    match db.get_product_engagement_series(&product_id, days).await {
+        Ok(series) => HttpResponse::Ok().json(ApiResponse::success(series)),
+        Err(e) => database_error_response(&e),
+    }
+}
+
+/**
+ * get_product_sponsorship_status
+ * 产品当前是否占有付费展示位及截止时间（取所有生效 grants 的最晚 ends_at）；不返回金额等内部字段。
+ */
+pub async fn get_product_sponsorship_status(
+    path: web::Path<String>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    let product_id = path.into_inner().trim().to_string();
+    if Uuid::parse_str(&product_id).is_err() {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<()>::error("Invalid product id".to_string()));
+    }
+
+    match db.get_product_active_grants(&product_id, Utc::now()).await {
+        Ok(grants) => {
+            let sponsored_until = grants.iter().map(|g| g.ends_at).max();
+            HttpResponse::Ok().json(ApiResponse::success(ProductSponsorshipStatus {
+                product_id,
+                sponsored: !grants.is_empty(),
+                sponsored_until,
+                grants: grants
+                    .into_iter()
+                    .map(|g| ProductActiveGrant {
+                        placement: g.placement,
+                        slot_index: g.slot_index,
+                        starts_at: g.starts_at,
+                        ends_at: g.ends_at,
+                        active: g.active,
+                        state: g.state,
+                    })
+                    .collect(),
+            }))
+        }
+        Err(e) => {
+            if is_db_unavailable_error(&e) {
+                return HttpResponse::Ok().json(make_db_degraded_response(
+                    "GET /api/products/{id}/sponsorship-status",
+                    ProductSponsorshipStatus {
+                        product_id,
+                        sponsored: false,
+                        sponsored_until: None,
+                        grants: Vec::new(),
+                    },
+                    "Sponsorship status temporarily unavailable".to_string(),
+                    &e,
+                ));
+            }
+            database_error_response(&e)
+        }
+    }
+}
+
+/**
+ * spawn_product_webhook_event
+ * 异步为订阅者入队产品事件（product.created / product.approved / product.rejected），不阻塞响应。
+ */
+fn spawn_product_webhook_event(
+    db: &web::Data<Arc<Database>>,
+    event: &'static str,
+    product: &Product,
+) {
+    let db = db.get_ref().clone();
+    let product = product.clone();
+    tokio::spawn(async move {
+        if let Err(e) = db.enqueue_product_webhook_event(event, &product).await {
+            log::warn!(
+                "Webhook enqueue failed event={} product_id={} err={:?}",
+                event,
+                product.id,
+                e
+            );
+        }
+    });
+}
+
+fn spawn_product_status_webhook_event(db: &web::Data<Arc<Database>>, product: &Product) {
+    match product.status {
+        crate::models::ProductStatus::Approved => {
+            spawn_product_webhook_event(db, "product.approved", product)
+        }
+        crate::models::ProductStatus::Rejected => {
+            spawn_product_webhook_event(db, "product.rejected", product)
+        }
+        crate::models::ProductStatus::Pending => {}
     }
 }
 
@@ -770,8 +1794,7 @@ pub async fn delete_product(
         Ok(false) => {
             HttpResponse::NotFound().json(ApiResponse::<()>::error("Product not found".to_string()))
         }
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<()>::error(format!("Database error: {:?}", e))),
+        Err(e) => database_error_response(&e),
     }
 }
 
@@ -788,63 +1811,320 @@ pub async fn get_categories(db: web::Data<Arc<Database>>) -> impl Responder {
                 ));
             }
 
-            HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)))
+            database_error_response(&e)
         }
     }
 }
 
 #[derive(Debug, Deserialize, IntoParams, ToSchema)]
-pub struct TopCategoriesQuery {
+pub struct TagSuggestQuery {
+    pub q: Option<String>,
     pub limit: Option<i64>,
 }
 
-pub async fn get_top_categories(
-    query: web::Query<TopCategoriesQuery>,
+/**
+ * suggest_tags
+ * 标签自动补全：按前缀返回已有标签及使用次数。
+ */
+pub async fn suggest_tags(
+    req: HttpRequest,
+    query: web::Query<TagSuggestQuery>,
     db: web::Data<Arc<Database>>,
 ) -> impl Responder {
+    let q = query.q.as_deref().unwrap_or("").trim().to_string();
     let limit = query.limit.unwrap_or(10).clamp(1, 50);
-    match db.get_top_categories_by_product_count(limit).await {
+
+    match db.suggest_tags(&q, limit).await {
         Ok(list) => HttpResponse::Ok().json(ApiResponse::success(list)),
         Err(e) => {
             if is_db_unavailable_error(&e) {
+                let message = if get_language_from_request(&req).starts_with("zh") {
+                    "数据库连接不可用，已降级返回空列表。"
+                } else {
+                    "Database is unavailable. Returning empty list in degraded mode."
+                };
                 return HttpResponse::Ok().json(make_db_degraded_response(
-                    "GET /api/categories/top",
-                    Vec::<crate::models::CategoryWithCount>::new(),
-                    "数据库连接不可用，已降级返回空列表。".to_string(),
+                    "GET /api/tags/suggest",
+                    Vec::<crate::models::TagSuggestion>::new(),
+                    message.to_string(),
                     &e,
                 ));
             }
 
-            HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)))
+            database_error_response(&e)
         }
     }
 }
 
-#[derive(Debug, Deserialize, IntoParams, ToSchema)]
-pub struct TopDevelopersQuery {
-    pub limit: Option<i64>,
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FormSchemaOption {
+    pub value: String,
+    pub label: String,
 }
 
-pub async fn get_top_developers(
-    query: web::Query<TopDevelopersQuery>,
-    db: web::Data<Arc<Database>>,
-) -> impl Responder {
-    let limit = query.limit.unwrap_or(4).clamp(1, 20);
-    match db.get_top_developers_by_followers(limit).await {
-        Ok(list) => HttpResponse::Ok().json(ApiResponse::success(list)),
-        Err(e) => {
-            if is_db_unavailable_error(&e) {
-                return HttpResponse::Ok().json(make_db_degraded_response(
-                    "GET /api/developers/top",
-                    Vec::<crate::models::DeveloperWithFollowers>::new(),
-                    "数据库连接不可用，已降级返回空列表。".to_string(),
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FormSchemaPayload {
+    pub statuses: Vec<FormSchemaOption>,
+    pub languages: Vec<FormSchemaOption>,
+    pub placements: Vec<FormSchemaOption>,
+    pub categories: Vec<FormSchemaOption>,
+}
+
+/**
+ * get_form_schema
+ * 返回前端表单所需的枚举（产品状态/语言/展示位）与分类列表，标签按请求语言本地化。
+ */
+pub async fn get_form_schema(req: HttpRequest, db: web::Data<Arc<Database>>) -> impl Responder {
+    use crate::models::ProductStatus;
+
+    let is_zh = get_language_from_request(&req).starts_with("zh");
+    let option = |value: &str, en: &str, zh: &str| FormSchemaOption {
+        value: value.to_string(),
+        label: if is_zh {
+            zh.to_string()
+        } else {
+            en.to_string()
+        },
+    };
+
+    let statuses = [
+        (ProductStatus::Pending, "Pending", "待审核"),
+        (ProductStatus::Approved, "Approved", "已通过"),
+        (ProductStatus::Rejected, "Rejected", "已拒绝"),
+    ]
+    .iter()
+    .map(|(status, en, zh)| option(crate::db::serialize_product_status(status), en, zh))
+    .collect();
+    let languages = vec![
+        option("en", "English", "英文"),
+        option("zh", "Chinese", "中文"),
+    ];
+    let placements = vec![
+        option("home_top", "Homepage top", "首页顶部"),
+        option("home_right", "Homepage sidebar", "首页右侧"),
+    ];
+
+    let (categories, degraded) = match db.get_categories().await {
+        Ok(list) => (list, None),
+        Err(e) if is_db_unavailable_error(&e) => (Vec::new(), Some(e)),
+        Err(e) => return database_error_response(&e),
+    };
+    let categories = categories
+        .into_iter()
+        .map(|c| FormSchemaOption {
+            label: if is_zh { c.name_zh } else { c.name_en },
+            value: c.id,
+        })
+        .collect();
+
+    let payload = FormSchemaPayload {
+        statuses,
+        languages,
+        placements,
+        categories,
+    };
+
+    match degraded {
+        Some(e) => HttpResponse::Ok().json(make_db_degraded_response(
+            "GET /api/meta/form-schema",
+            payload,
+            if is_zh {
+                "数据库连接不可用，分类列表已降级为空。".to_string()
+            } else {
+                "Database is unavailable. Categories are empty in degraded mode.".to_string()
+            },
+            &e,
+        )),
+        None => HttpResponse::Ok().json(ApiResponse::success(payload)),
+    }
+}
+
+/**
+ * StaticCapabilities
+ * 启动时根据环境变量计算一次的静态能力（支付渠道、登录、语言）。
+ */
+#[derive(Debug, Clone)]
+pub struct StaticCapabilities {
+    pub payments: Vec<String>,
+    pub auth_required: bool,
+    pub locales: Vec<String>,
+}
+
+impl StaticCapabilities {
+    pub fn from_env() -> Self {
+        let non_empty = |key: &str| env::var(key).ok().is_some_and(|v| !v.trim().is_empty());
+        let mut payments = Vec::new();
+        if non_empty("CREEM_API_KEY") {
+            payments.push("creem".to_string());
+        }
+        Self {
+            payments,
+            auth_required: non_empty("SUPABASE_URL") && non_empty("SUPABASE_KEY"),
+            locales: vec!["en".to_string(), "zh".to_string()],
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CapabilitiesPayload {
+    pub payments: Vec<String>,
+    pub newsletter_enabled: bool,
+    pub postgres: bool,
+    pub supabase_fallback: bool,
+    pub auth_required: bool,
+    pub locales: Vec<String>,
+    pub max_sponsorship_months: i32,
+    pub unavailable_features: Vec<String>,
+}
+
+/**
+ * POSTGRES_ONLY_FEATURES
+ * 仅在配置 Postgres 时可用的功能；Supabase-only 部署调用这些接口会返回 501 feature_requires_postgres。
+ * 新增返回 PostgresNotConfiguredError 的接口时需同步登记。
+ */
+const POSTGRES_ONLY_FEATURES: &[&str] = &[
+    "sponsorship_requests",
+    "sponsorship_orders",
+    "sponsorship_grants",
+    "payments_summary",
+    "pricing_admin",
+    "newsletter",
+    "webhooks",
+    "product_claims",
+    "product_bumps",
+    "product_changes",
+    "engagement_series",
+    "overview_stats",
+    "product_status_counts",
+    "tag_suggestions",
+    "developer_directory",
+    "developer_center_stats",
+    "developer_merge",
+    "developer_backfill",
+    "developer_sponsor_admin",
+    "home_module_admin",
+    "maker_account_age_gate",
+    "admin_audit_log",
+    "migrations",
+];
+
+fn unavailable_features(postgres: bool) -> Vec<String> {
+    if postgres {
+        return Vec::new();
+    }
+    POSTGRES_ONLY_FEATURES
+        .iter()
+        .map(|f| f.to_string())
+        .collect()
+}
+
+/**
+ * get_capabilities
+ * 返回当前部署可用的功能开关：静态部分启动时计算，周报开关与数据后端按请求读取。
+ */
+pub async fn get_capabilities(
+    db: web::Data<Arc<Database>>,
+    static_caps: web::Data<StaticCapabilities>,
+) -> impl Responder {
+    let (postgres, supabase) = db.configured_backends();
+    let payload = CapabilitiesPayload {
+        payments: static_caps.payments.clone(),
+        newsletter_enabled: postgres
+            && crate::db::newsletter_flag_enabled()
+            && crate::db::newsletter_sender_configured(),
+        postgres,
+        supabase_fallback: postgres && supabase,
+        auth_required: static_caps.auth_required,
+        locales: static_caps.locales.clone(),
+        max_sponsorship_months: MAX_SPONSORSHIP_MONTHS,
+        unavailable_features: unavailable_features(postgres),
+    };
+    HttpResponse::Ok().json(ApiResponse::success(payload))
+}
+
+/**
+ * get_overview_stats
+ * 首页概览统计（一次请求返回产品/创作者/互动总数）。
+ */
+pub async fn get_overview_stats(req: HttpRequest, db: web::Data<Arc<Database>>) -> impl Responder {
+    match db.get_overview_stats().await {
+        Ok(stats) => HttpResponse::Ok().json(ApiResponse::success(stats)),
+        Err(e) => {
+            if is_db_unavailable_error(&e) {
+                let message = if get_language_from_request(&req).starts_with("zh") {
+                    "数据库连接不可用，已降级返回空统计。"
+                } else {
+                    "Database is unavailable. Returning empty stats in degraded mode."
+                };
+                return HttpResponse::Ok().json(make_db_degraded_response(
+                    "GET /api/stats/overview",
+                    OverviewStats {
+                        total_products: 0,
+                        approved_products: 0,
+                        total_developers: 0,
+                        total_likes: 0,
+                        total_favorites: 0,
+                        products_this_week: 0,
+                    },
+                    message.to_string(),
                     &e,
                 ));
             }
-            HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)))
+
+            database_error_response(&e)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+pub struct TopCategoriesQuery {
+    pub limit: Option<i64>,
+}
+
+pub async fn get_top_categories(
+    query: web::Query<TopCategoriesQuery>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    let limit = query.limit.unwrap_or(10).clamp(1, 50);
+    match db.get_top_categories_by_product_count(limit).await {
+        Ok(list) => HttpResponse::Ok().json(ApiResponse::success(list)),
+        Err(e) => {
+            if is_db_unavailable_error(&e) {
+                return HttpResponse::Ok().json(make_db_degraded_response(
+                    "GET /api/categories/top",
+                    Vec::<crate::models::CategoryWithCount>::new(),
+                    "数据库连接不可用，已降级返回空列表。".to_string(),
+                    &e,
+                ));
+            }
+
+            database_error_response(&e)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+pub struct TopDevelopersQuery {
+    pub limit: Option<i64>,
+}
+
+pub async fn get_top_developers(
+    query: web::Query<TopDevelopersQuery>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    let limit = query.limit.unwrap_or(4).clamp(1, 20);
+    match db.get_top_developers_by_followers(limit).await {
+        Ok(list) => HttpResponse::Ok().json(ApiResponse::success(list)),
+        Err(e) => {
+            if is_db_unavailable_error(&e) {
+                return HttpResponse::Ok().json(make_db_degraded_response(
+                    "GET /api/developers/top",
+                    Vec::<crate::models::DeveloperWithFollowers>::new(),
+                    "数据库连接不可用，已降级返回空列表。".to_string(),
+                    &e,
+                ));
+            }
+            database_error_response(&e)
         }
     }
 }
@@ -865,8 +2145,66 @@ pub async fn get_recent_developers(
                     &e,
                 ));
             }
-            HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)))
+            database_error_response(&e)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+pub struct DevelopersDirectoryQuery {
+    pub q: Option<String>,
+    pub sort: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/**
+ * list_developers
+ * 创作者目录：分页浏览全部创作者（sort=name|created_at|followers，可选 q 搜索），返回总数供分页使用。
+ */
+pub async fn list_developers(
+    query: web::Query<DevelopersDirectoryQuery>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    let Some(sort) =
+        crate::db::DeveloperDirectorySort::parse(query.sort.as_deref().unwrap_or("name"))
+    else {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+            "Invalid sort (expected name / created_at / followers)".to_string(),
+        ));
+    };
+    let limit = query.limit.unwrap_or(24).clamp(1, 100);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    match db
+        .list_developers(query.q.as_deref(), sort, limit, offset)
+        .await
+    {
+        Ok((items, total)) => {
+            let mut resp = HttpResponse::Ok();
+            insert_pagination_headers(&mut resp, Some(limit), Some(offset), Some(total));
+            resp.json(ApiResponse::success(PagedList {
+                items,
+                total,
+                limit,
+                offset,
+            }))
+        }
+        Err(e) => {
+            if is_db_unavailable_error(&e) {
+                return HttpResponse::Ok().json(make_db_degraded_response(
+                    "GET /api/developers",
+                    PagedList {
+                        items: Vec::<crate::models::DeveloperWithFollowers>::new(),
+                        total: 0,
+                        limit,
+                        offset,
+                    },
+                    "数据库连接不可用，已降级返回空列表。".to_string(),
+                    &e,
+                ));
+            }
+            database_error_response(&e)
         }
     }
 }
@@ -874,6 +2212,22 @@ pub async fn get_recent_developers(
 #[derive(Debug, Deserialize, IntoParams, ToSchema)]
 pub struct DeveloperPopularityQuery {
     pub limit: Option<i64>,
+    pub metric: Option<String>,
+}
+
+/**
+ * parse_popularity_metric
+ * 解析 ?metric=（default / weighted），非法值返回 400。
+ */
+fn parse_popularity_metric(raw: Option<&str>) -> Result<crate::db::PopularityMetric, HttpResponse> {
+    match raw {
+        None => Ok(crate::db::PopularityMetric::Default),
+        Some(v) => crate::db::PopularityMetric::parse(v).ok_or_else(|| {
+            HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+                "Invalid metric (expected default / weighted)".to_string(),
+            ))
+        }),
+    }
 }
 
 pub async fn get_developer_popularity_last_month(
@@ -881,7 +2235,11 @@ pub async fn get_developer_popularity_last_month(
     db: web::Data<Arc<Database>>,
 ) -> impl Responder {
     let limit = query.limit.unwrap_or(10).clamp(1, 50);
-    match db.get_developer_popularity_last_month(limit).await {
+    let metric = match parse_popularity_metric(query.metric.as_deref()) {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+    match db.get_developer_popularity_last_month(limit, metric).await {
         Ok(list) => HttpResponse::Ok().json(ApiResponse::success(list)),
         Err(e) => {
             if is_db_unavailable_error(&e) {
@@ -892,8 +2250,7 @@ pub async fn get_developer_popularity_last_month(
                     &e,
                 ));
             }
-            HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)))
+            database_error_response(&e)
         }
     }
 }
@@ -903,7 +2260,11 @@ pub async fn get_developer_popularity_last_week(
     db: web::Data<Arc<Database>>,
 ) -> impl Responder {
     let limit = query.limit.unwrap_or(10).clamp(1, 50);
-    match db.get_developer_popularity_last_week(limit).await {
+    let metric = match parse_popularity_metric(query.metric.as_deref()) {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+    match db.get_developer_popularity_last_week(limit, metric).await {
         Ok(list) => HttpResponse::Ok().json(ApiResponse::success(list)),
         Err(e) => {
             if is_db_unavailable_error(&e) {
@@ -914,8 +2275,7 @@ pub async fn get_developer_popularity_last_week(
                     &e,
                 ));
             }
-            HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)))
+            database_error_response(&e)
         }
     }
 }
@@ -946,6 +2306,210 @@ fn extract_user_id(body: &Option<web::Json<InteractionBody>>) -> Option<String>
         .filter(|v| !v.is_empty())
 }
 
+/**
+ * engagement_requires_auth_from_env_value
+ * 解析 ENGAGEMENT_REQUIRE_AUTH：1/true 时点赞/收藏必须携带已登录的 Bearer token，其余（含未设置）沿用请求体 user_id。
+ */
+fn engagement_requires_auth_from_env_value(raw: Option<&str>) -> bool {
+    matches!(
+        raw.map(|v| v.trim().to_ascii_lowercase()).as_deref(),
+        Some("1") | Some("true")
+    )
+}
+
+/**
+ * resolve_engagement_user
+ * 解析点赞/收藏的用户：要求登录时使用 Bearer token 对应的邮箱（忽略请求体），否则使用请求体中的非匿名 user_id。
+ */
+async fn resolve_engagement_user(
+    req: &HttpRequest,
+    body: &Option<web::Json<InteractionBody>>,
+) -> Option<String> {
+    if engagement_requires_auth_from_env_value(env::var("ENGAGEMENT_REQUIRE_AUTH").ok().as_deref())
+    {
+        let token = extract_bearer_token(req)?;
+        return resolve_supabase_email_from_bearer(&token).await;
+    }
+    extract_user_id(body).filter(|v| !is_anonymous_user_id(v))
+}
+
+/**
+ * trusted_proxies_from_env_value
+ * 解析 TRUSTED_PROXIES（逗号分隔的反向代理 IP）；未设置时不信任任何 X-Forwarded-For。
+ */
+fn trusted_proxies_from_env_value(raw: Option<&str>) -> Vec<std::net::IpAddr> {
+    raw.unwrap_or("")
+        .split(',')
+        .filter_map(|v| v.trim().parse::<std::net::IpAddr>().ok())
+        .collect()
+}
+
+/**
+ * resolve_client_ip
+ * 解析真实客户端 IP：直连对端不是受信代理时直接使用对端地址；
+ * 否则从 X-Forwarded-For 最右侧开始跳过受信代理，取第一个不受信的地址（无法解析时回退到对端地址）。
+ */
+fn resolve_client_ip(
+    peer: Option<std::net::IpAddr>,
+    forwarded_for: Option<&str>,
+    trusted: &[std::net::IpAddr],
+) -> Option<std::net::IpAddr> {
+    let peer = peer?;
+    if !trusted.contains(&peer) {
+        return Some(peer);
+    }
+    let Some(forwarded_for) = forwarded_for else {
+        return Some(peer);
+    };
+    for hop in forwarded_for.rsplit(',') {
+        let Ok(ip) = hop.trim().parse::<std::net::IpAddr>() else {
+            return Some(peer);
+        };
+        if !trusted.contains(&ip) {
+            return Some(ip);
+        }
+    }
+    Some(peer)
+}
+
+/**
+ * client_ip_hash
+ * 请求来源 IP 的加盐 SHA-256 摘要（IP_HASH_SALT），不落库原始 IP；
+ * 仅当直连对端在 TRUSTED_PROXIES 中时才采信 X-Forwarded-For。
+ */
+fn client_ip_hash(req: &HttpRequest) -> Option<String> {
+    let forwarded_for = req
+        .headers()
+        .get("X-Forwarded-For")
+        .and_then(|v| v.to_str().ok());
+    let ip = resolve_client_ip(
+        req.peer_addr().map(|addr| addr.ip()),
+        forwarded_for,
+        &trusted_proxies_from_env_value(env::var("TRUSTED_PROXIES").ok().as_deref()),
+    )?
+    .to_string();
+    let salt = env::var("IP_HASH_SALT").unwrap_or_default();
+    let digest = <Sha256 as sha2::Digest>::digest(format!("{}:{}", salt.trim(), ip).as_bytes());
+    Some(
+        digest
+            .iter()
+            .take(16)
+            .map(|b| format!("{:02x}", b))
+            .collect(),
+    )
+}
+
+/**
+ * EngagementRateLimiter
+ * 点赞/收藏的按 IP 限流（固定窗口，进程内）：同一 IP 哈希在 window 内最多 max_hits 次写入，max_hits 为 0 时关闭。
+ */
+pub struct EngagementRateLimiter {
+    max_hits: u32,
+    window: StdDuration,
+    hits: std::sync::Mutex<std::collections::HashMap<String, (std::time::Instant, u32)>>,
+}
+
+impl EngagementRateLimiter {
+    pub fn new(max_hits: u32, window: StdDuration) -> Self {
+        Self {
+            max_hits,
+            window,
+            hits: std::sync::Mutex::new(std::collections::HashMap::new()),
+        }
+    }
+
+    /**
+     * from_env
+     * ENGAGEMENT_RATE_LIMIT_PER_MINUTE：每个 IP 每分钟允许的点赞/收藏次数（0 关闭，最大 1000）；
+     * 未设置时仅在配置了 TRUSTED_PROXIES 后默认 30，否则关闭（经代理转发时对端地址是代理本身）。
+     */
+    pub fn from_env() -> Self {
+        let trusted_proxies =
+            trusted_proxies_from_env_value(env::var("TRUSTED_PROXIES").ok().as_deref());
+        Self::new(
+            engagement_rate_limit_from_env_value(
+                env::var("ENGAGEMENT_RATE_LIMIT_PER_MINUTE").ok().as_deref(),
+                !trusted_proxies.is_empty(),
+            ),
+            StdDuration::from_secs(60),
+        )
+    }
+
+    /**
+     * check
+     * 记录一次命中；超出上限时返回距窗口结束的剩余时间（用于 Retry-After）。
+     */
+    pub fn check(&self, key: &str, now: std::time::Instant) -> Result<(), StdDuration> {
+        if self.max_hits == 0 {
+            return Ok(());
+        }
+        let Ok(mut hits) = self.hits.lock() else {
+            return Ok(());
+        };
+        if hits.len() > 10_000 {
+            let window = self.window;
+            hits.retain(|_, (started, _)| now.duration_since(*started) < window);
+        }
+        let entry = hits.entry(key.to_string()).or_insert((now, 0));
+        if now.duration_since(entry.0) >= self.window {
+            *entry = (now, 0);
+        }
+        if entry.1 >= self.max_hits {
+            return Err(self.window.saturating_sub(now.duration_since(entry.0)));
+        }
+        entry.1 += 1;
+        Ok(())
+    }
+}
+
+fn engagement_rate_limit_from_env_value(
+    raw: Option<&str>,
+    trusted_proxies_configured: bool,
+) -> u32 {
+    let default = if trusted_proxies_configured { 30 } else { 0 };
+    raw.and_then(|v| v.trim().parse::<u32>().ok())
+        .unwrap_or(default)
+        .min(1000)
+}
+
+/**
+ * check_engagement_rate_limit
+ * 按 IP 哈希限流，超限返回 429（RATE_LIMITED）与 Retry-After；无法识别 IP 时不限流。
+ */
+fn check_engagement_rate_limit(
+    req: &HttpRequest,
+    limiter: &EngagementRateLimiter,
+    ip_hash: Option<&str>,
+) -> Result<(), HttpResponse> {
+    let Some(ip_hash) = ip_hash else {
+        return Ok(());
+    };
+    let retry_after = match limiter.check(ip_hash, std::time::Instant::now()) {
+        Ok(()) => return Ok(()),
+        Err(retry_after) => retry_after.as_secs().max(1),
+    };
+    let message = if get_language_from_request(req).starts_with("zh") {
+        "操作过于频繁，请稍后再试。"
+    } else {
+        "Too many requests. Please try again later."
+    };
+    Err(HttpResponse::TooManyRequests()
+        .insert_header(("Retry-After", retry_after.to_string()))
+        .json(ApiResponse::<()> {
+            success: false,
+            data: None,
+            message: Some(message.to_string()),
+            error: Some(ApiError {
+                code: "RATE_LIMITED".to_string(),
+                trace_id: new_trace_id(),
+                degraded: false,
+                hint: None,
+                detail: None,
+                fields: None,
+            }),
+        }))
+}
+
 /**
  * is_anonymous_user_id
  * 判断是否为匿名用户标识（前端曾使用 anon_ 前缀）。
@@ -954,6 +2518,21 @@ fn is_anonymous_user_id(user_id: &str) -> bool {
     user_id.to_ascii_lowercase().starts_with("anon_")
 }
 
+/**
+ * resolve_viewer_id
+ * 识别当前请求的用户：优先使用显式传入的 user_id，其次解析 Bearer token 的邮箱；匿名用户返回 None。
+ */
+async fn resolve_viewer_id(req: &HttpRequest, explicit: Option<&str>) -> Option<String> {
+    if let Some(v) = explicit.map(|v| v.trim()).filter(|v| !v.is_empty()) {
+        if is_anonymous_user_id(v) {
+            return None;
+        }
+        return Some(v.to_string());
+    }
+    let token = extract_bearer_token(req)?;
+    resolve_supabase_email_from_bearer(&token).await
+}
+
 fn is_same_user_email(a: &str, b: &str) -> bool {
     let left = a.trim();
     let right = b.trim();
@@ -963,22 +2542,6 @@ fn is_same_user_email(a: &str, b: &str) -> bool {
     left.eq_ignore_ascii_case(right)
 }
 
-fn is_valid_email_basic(email: &str) -> bool {
-    let e = email.trim();
-    if e.is_empty() || e.len() > 320 {
-        return false;
-    }
-    let at = match e.find('@') {
-        Some(v) => v,
-        None => return false,
-    };
-    if at == 0 || at + 1 >= e.len() {
-        return false;
-    }
-    let domain = &e[at + 1..];
-    domain.contains('.') && !domain.starts_with('.') && !domain.ends_with('.')
-}
-
 /**
  * verify_newsletter_unsubscribe_token
  * 校验退订 token（HMAC-SHA256 + URL-safe base64，无 padding）。
@@ -1022,7 +2585,24 @@ pub async fn subscribe_newsletter(
         return HttpResponse::BadRequest().json(ApiResponse::<()>::error(msg.to_string()));
     }
 
-    match db.subscribe_newsletter(&email).await {
+    let format = body
+        .format
+        .as_deref()
+        .map(|v| v.trim().to_ascii_lowercase())
+        .filter(|v| !v.is_empty());
+    if let Some(f) = format.as_deref() {
+        if f != "html" && f != "text" {
+            let lang = get_language_from_request(&req);
+            let msg = if lang.starts_with("zh") {
+                "邮件格式仅支持 html 或 text。"
+            } else {
+                "Newsletter format must be html or text."
+            };
+            return HttpResponse::BadRequest().json(ApiResponse::<()>::error(msg.to_string()));
+        }
+    }
+
+    match db.subscribe_newsletter(&email, format.as_deref()).await {
         Ok(()) => HttpResponse::Ok().json(ApiResponse::success(OkPayload { ok: true })),
         Err(e) => {
             if is_db_unavailable_error(&e) {
@@ -1039,8 +2619,7 @@ pub async fn subscribe_newsletter(
                     &e,
                 ));
             }
-            HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)))
+            database_error_response(&e)
         }
     }
 }
@@ -1090,13 +2669,21 @@ pub async fn unsubscribe_newsletter(
 
     match db.unsubscribe_newsletter(&email).await {
         Ok(()) => {
-            let html = r#"<div style="font-family:-apple-system,BlinkMacSystemFont,Segoe UI,Roboto,Helvetica,Arial,sans-serif;line-height:1.6;">
+            let resubscribe_url = format!(
+                "/api/newsletter/resubscribe?email={}&token={}",
+                urlencoding::encode(&email),
+                urlencoding::encode(token)
+            );
+            let html = format!(
+                r#"<div style="font-family:-apple-system,BlinkMacSystemFont,Segoe UI,Roboto,Helvetica,Arial,sans-serif;line-height:1.6;">
 <h2>退订成功</h2>
-<p>你已成功退订 SoloForge 周报。</p>
+<p>你已成功退订 SoloForge 周报。误操作？<a href="{url}">重新订阅</a></p>
 <hr style="border:none;border-top:1px solid #eee;margin:18px 0;"/>
 <h2>Unsubscribed</h2>
-<p>You have successfully unsubscribed from the SoloForge weekly brief.</p>
-</div>"#;
+<p>You have successfully unsubscribed from the SoloForge weekly brief. Changed your mind? <a href="{url}">Resubscribe</a></p>
+</div>"#,
+                url = resubscribe_url
+            );
             HttpResponse::Ok()
                 .content_type("text/html; charset=utf-8")
                 .body(html)
@@ -1129,12 +2716,224 @@ pub async fn unsubscribe_newsletter(
     }
 }
 
-pub async fn preview_newsletter() -> impl Responder {
-    if !cfg!(debug_assertions) {
-        return HttpResponse::NotFound().finish();
-    }
+const TRANSPARENT_GIF: &[u8] = b"GIF89a\x01\x00\x01\x00\x80\x00\x00\x00\x00\x00\xff\xff\xff!\xf9\x04\x01\x00\x00\x00\x00,\x00\x00\x00\x00\x01\x00\x01\x00\x00\x02\x02D\x01\x00;";
 
-    let now = chrono::Utc::now();
+/**
+ * newsletter_open_pixel
+ * 周报打开追踪像素：记录 open 事件后始终返回 1x1 透明 GIF（禁止缓存）。
+ */
+pub async fn newsletter_open_pixel(
+    path: web::Path<String>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Ok(token) = uuid::Uuid::parse_str(path.into_inner().trim()) {
+        if let Err(e) = db.record_newsletter_event(token, "open", None).await {
+            log::debug!("Newsletter open tracking failed: {:?}", e);
+        }
+    }
+
+    HttpResponse::Ok()
+        .content_type("image/gif")
+        .insert_header(("Cache-Control", "no-store, max-age=0"))
+        .body(TRANSPARENT_GIF)
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct NewsletterClickQuery {
+    pub token: String,
+    pub url: String,
+    pub sig: String,
+}
+
+/**
+ * newsletter_click
+ * 周报点击追踪：校验签名与目标域名后记录 click 事件并 302 跳转，拒绝未签名或不允许的目标，避免开放跳转。
+ */
+pub async fn newsletter_click(
+    query: web::Query<NewsletterClickQuery>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    let secret = env::var("NEWSLETTER_TOKEN_SECRET").ok().unwrap_or_default();
+    let token = query.token.trim();
+    if !crate::db::verify_newsletter_click_signature(token, &query.url, &query.sig, &secret) {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+            "Invalid tracking link".to_string(),
+        ));
+    }
+
+    let frontend_base_url = env::var("FRONTEND_BASE_URL")
+        .ok()
+        .unwrap_or_else(|| "http://localhost:3000".to_string());
+    let allowed_hosts = env::var("NEWSLETTER_CLICK_ALLOWED_HOSTS")
+        .ok()
+        .filter(|v| !v.trim().is_empty());
+    let send_token = uuid::Uuid::parse_str(token).ok();
+    // 未配置白名单时只放行该期周报里的产品官网（以及前台域名）
+    let issue_hosts = match (allowed_hosts.as_deref(), send_token) {
+        (None, Some(send_token)) => db
+            .get_newsletter_send_link_hosts(send_token)
+            .await
+            .unwrap_or_else(|e| {
+                log::debug!("Newsletter link hosts lookup failed: {:?}", e);
+                Vec::new()
+            }),
+        _ => Vec::new(),
+    };
+    let Some(target) = crate::db::newsletter_click_target(
+        &query.url,
+        &frontend_base_url,
+        allowed_hosts.as_deref(),
+        &issue_hosts,
+    ) else {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+            "Redirect target is not allowed".to_string(),
+        ));
+    };
+
+    if let Some(token) = send_token {
+        if let Err(e) = db
+            .record_newsletter_event(token, "click", Some(target.as_str()))
+            .await
+        {
+            log::debug!("Newsletter click tracking failed: {:?}", e);
+        }
+    }
+
+    HttpResponse::Found()
+        .insert_header(("Location", target.as_str()))
+        .insert_header(("Cache-Control", "no-store"))
+        .finish()
+}
+
+/**
+ * resubscribe_newsletter
+ * 重新订阅周报（退订页中的撤销链接，与退订共用同一 HMAC token），无需再次确认。
+ */
+pub async fn resubscribe_newsletter(
+    query: web::Query<NewsletterUnsubscribeQuery>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    let email = query.email.trim().to_ascii_lowercase();
+    let secret = env::var("NEWSLETTER_TOKEN_SECRET").ok().unwrap_or_default();
+    let token = query.token.as_deref().unwrap_or("");
+    if !is_valid_email_basic(&email) || !verify_newsletter_unsubscribe_token(&email, token, &secret)
+    {
+        let html = r#"<div style="font-family:-apple-system,BlinkMacSystemFont,Segoe UI,Roboto,Helvetica,Arial,sans-serif;line-height:1.6;">
+<h2>重新订阅失败</h2>
+<p>链接无效或已过期。</p>
+<hr style="border:none;border-top:1px solid #eee;margin:18px 0;"/>
+<h2>Resubscribe failed</h2>
+<p>The link is invalid or expired.</p>
+</div>"#;
+        return HttpResponse::BadRequest()
+            .content_type("text/html; charset=utf-8")
+            .body(html);
+    }
+
+    match db.resubscribe_newsletter(&email).await {
+        Ok(true) => {
+            let html = r#"<div style="font-family:-apple-system,BlinkMacSystemFont,Segoe UI,Roboto,Helvetica,Arial,sans-serif;line-height:1.6;">
+<h2>已重新订阅</h2>
+<p>你将继续收到 SoloForge 周报。</p>
+<hr style="border:none;border-top:1px solid #eee;margin:18px 0;"/>
+<h2>Resubscribed</h2>
+<p>You will keep receiving the SoloForge weekly brief.</p>
+</div>"#;
+            HttpResponse::Ok()
+                .content_type("text/html; charset=utf-8")
+                .body(html)
+        }
+        Ok(false) => {
+            let html = r#"<div style="font-family:-apple-system,BlinkMacSystemFont,Segoe UI,Roboto,Helvetica,Arial,sans-serif;line-height:1.6;">
+<h2>重新订阅失败</h2>
+<p>未找到该邮箱的订阅记录，请重新订阅。</p>
+<hr style="border:none;border-top:1px solid #eee;margin:18px 0;"/>
+<h2>Resubscribe failed</h2>
+<p>No subscription was found for this email. Please subscribe again.</p>
+</div>"#;
+            HttpResponse::NotFound()
+                .content_type("text/html; charset=utf-8")
+                .body(html)
+        }
+        Err(e) => {
+            log::warn!("Newsletter resubscribe failed: {:?}", e);
+            let html = r#"<div style="font-family:-apple-system,BlinkMacSystemFont,Segoe UI,Roboto,Helvetica,Arial,sans-serif;line-height:1.6;">
+<h2>重新订阅失败</h2>
+<p>服务器错误，请稍后重试。</p>
+<hr style="border:none;border-top:1px solid #eee;margin:18px 0;"/>
+<h2>Resubscribe failed</h2>
+<p>Server error. Please try again later.</p>
+</div>"#;
+            HttpResponse::InternalServerError()
+                .content_type("text/html; charset=utf-8")
+                .body(html)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct FeedQuery {
+    pub lang: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/**
+ * get_json_feed
+ * JSON Feed 1.1：最近通过审核的产品（?lang=zh 按语言过滤），与 RSS 共用 get_feed_products。
+ */
+pub async fn get_json_feed(
+    query: web::Query<FeedQuery>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    let lang = query
+        .lang
+        .as_deref()
+        .map(|v| v.trim().to_ascii_lowercase())
+        .filter(|v| !v.is_empty());
+    let limit = query.limit.unwrap_or(30);
+
+    let products = match db.get_feed_products(lang.as_deref(), limit).await {
+        Ok(products) => products,
+        Err(e) => {
+            if is_db_unavailable_error(&e) {
+                Vec::new()
+            } else {
+                return database_error_response(&e);
+            }
+        }
+    };
+
+    let frontend_base_url = env::var("FRONTEND_BASE_URL")
+        .ok()
+        .unwrap_or_else(|| "http://localhost:3000".to_string());
+    let public_api_base_url = env::var("BACKEND_PUBLIC_URL")
+        .ok()
+        .unwrap_or_else(|| "http://localhost:8080".to_string());
+    let mut feed_url = format!(
+        "{}/api/feed.json",
+        public_api_base_url.trim().trim_end_matches('/')
+    );
+    if let Some(l) = lang.as_deref() {
+        feed_url.push_str(&format!("?lang={}", urlencoding::encode(l)));
+    }
+    let locale = if lang.as_deref() == Some("zh") {
+        "zh"
+    } else {
+        "en"
+    };
+
+    let feed = crate::db::build_json_feed(&products, &frontend_base_url, &feed_url, locale);
+    HttpResponse::Ok()
+        .content_type("application/feed+json; charset=utf-8")
+        .json(feed)
+}
+
+pub async fn preview_newsletter(req: HttpRequest) -> impl Responder {
+    if !cfg!(debug_assertions) {
+        return HttpResponse::NotFound().finish();
+    }
+
+    let now = chrono::Utc::now();
     let since = now - chrono::Duration::days(7);
 
     let frontend_base_url = env::var("FRONTEND_BASE_URL")
@@ -1150,6 +2949,7 @@ pub async fn preview_newsletter() -> impl Responder {
             name: "PromptDock".to_string(),
             slogan: "Manage prompts & snippets fast".to_string(),
             website: "https://example.com/promptdock".to_string(),
+            logo_url: None,
             maker_name: "Alex".to_string(),
             maker_email: "alex@example.com".to_string(),
             weekly_likes: 128,
@@ -1161,6 +2961,7 @@ pub async fn preview_newsletter() -> impl Responder {
             name: "写作加速器".to_string(),
             slogan: "让内容产出更快".to_string(),
             website: "https://example.com/writing-booster".to_string(),
+            logo_url: None,
             maker_name: "小王".to_string(),
             maker_email: "xiaowang@example.com".to_string(),
             weekly_likes: 97,
@@ -1172,6 +2973,7 @@ pub async fn preview_newsletter() -> impl Responder {
             name: "LaunchKit".to_string(),
             slogan: "Landing page + waitlist template".to_string(),
             website: "https://example.com/launchkit".to_string(),
+            logo_url: None,
             maker_name: "Chen".to_string(),
             maker_email: "chen@example.com".to_string(),
             weekly_likes: 66,
@@ -1183,6 +2985,7 @@ pub async fn preview_newsletter() -> impl Responder {
             name: "API 体检".to_string(),
             slogan: "自动化检查接口健康".to_string(),
             website: "https://example.com/api-health".to_string(),
+            logo_url: None,
             maker_name: "阿杰".to_string(),
             maker_email: "ajie@example.com".to_string(),
             weekly_likes: 59,
@@ -1194,6 +2997,7 @@ pub async fn preview_newsletter() -> impl Responder {
             name: "BudgetBee".to_string(),
             slogan: "Personal finance for creators".to_string(),
             website: "https://example.com/budgetbee".to_string(),
+            logo_url: None,
             maker_name: "Sana".to_string(),
             maker_email: "sana@example.com".to_string(),
             weekly_likes: 41,
@@ -1208,6 +3012,8 @@ pub async fn preview_newsletter() -> impl Responder {
         &products,
         &frontend_base_url,
         &unsubscribe_url,
+        crate::db::newsletter_recipient_locale(Some(&get_language_from_request(&req))),
+        None,
     );
 
     HttpResponse::Ok()
@@ -1246,8 +3052,7 @@ pub async fn follow_developer(
                     &e,
                 ));
             }
-            HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)))
+            database_error_response(&e)
         }
     }
 }
@@ -1267,18 +3072,138 @@ pub async fn get_developer_by_email(
         Ok(Some(dev)) => HttpResponse::Ok().json(ApiResponse::success(dev)),
         Ok(None) => HttpResponse::NotFound()
             .json(ApiResponse::<()>::error("Developer not found".to_string())),
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<()>::error(format!("Database error: {:?}", e))),
+        Err(e) => database_error_response(&e),
     }
 }
 
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+pub struct DeveloperProductsQuery {
+    pub status: Option<String>,
+    pub sort: Option<String>,
+    pub dir: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/**
+ * get_developer_products
+ * 创作者产品列表：默认仅返回已通过审核的产品；本人（Bearer）或管理员可查看全部状态或按 status 过滤。
+ */
+pub async fn get_developer_products(
+    req: HttpRequest,
+    path: web::Path<DeveloperPath>,
+    query: web::Query<DeveloperProductsQuery>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    let email = path.into_inner().email.trim().to_ascii_lowercase();
+
+    match db.get_developer_by_email(&email).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound()
+                .json(ApiResponse::<()>::error("Developer not found".to_string()))
+        }
+        Err(e) => {
+            if is_db_unavailable_error(&e) {
+                return HttpResponse::Ok().json(make_db_degraded_response(
+                    "GET /api/developers/{email}/products",
+                    Vec::<Product>::new(),
+                    "数据库连接不可用，已降级返回空列表。".to_string(),
+                    &e,
+                ));
+            }
+            return database_error_response(&e);
+        }
+    }
+
+    let bearer_email = match extract_bearer_token(&req) {
+        Some(token) => resolve_supabase_email_from_bearer(&token).await,
+        None => None,
+    };
+    let is_owner = bearer_email
+        .as_deref()
+        .is_some_and(|v| is_same_user_email(&email, v));
+    let can_view_private = is_owner || validate_admin_token(&req).is_ok();
+
+    let query = query.into_inner();
+    let status = if can_view_private {
+        query
+            .status
+            .map(|v| v.trim().to_ascii_lowercase())
+            .filter(|v| !v.is_empty() && v != "all")
+    } else {
+        Some("approved".to_string())
+    };
+
+    let params = QueryParams {
+        category: None,
+        tags: None,
+        language: None,
+        status,
+        search: None,
+        maker_email: Some(email),
+        sort: query.sort,
+        dir: query.dir,
+        limit: Some(query.limit.unwrap_or(20).clamp(1, 100)),
+        offset: Some(query.offset.unwrap_or(0).max(0)),
+        user_id: bearer_email,
+        created_after: None,
+        created_before: None,
+        min_likes: None,
+        sponsor_verified: None,
+        include_pending: None,
+        seed: None,
+    };
+
+    match db.get_products(params).await {
+        Ok(products) => HttpResponse::Ok().json(ApiResponse::success(products)),
+        Err(e) => {
+            if is_db_unavailable_error(&e) {
+                return HttpResponse::Ok().json(make_db_degraded_response(
+                    "GET /api/developers/{email}/products",
+                    Vec::<Product>::new(),
+                    "数据库连接不可用，已降级返回空列表。".to_string(),
+                    &e,
+                ));
+            }
+            database_error_response(&e)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DeveloperCenterStatsQuery {
+    pub window: Option<String>,
+}
+
+/**
+ * get_developer_center_stats
+ * 创作者中心统计（累计）；?window=week|month 时额外返回窗口内新增的点赞/收藏/关注。
+ */
 pub async fn get_developer_center_stats(
     path: web::Path<DeveloperPath>,
+    query: web::Query<DeveloperCenterStatsQuery>,
     db: web::Data<Arc<Database>>,
 ) -> impl Responder {
     let email = path.into_inner().email.trim().to_ascii_lowercase();
+    let window = match query
+        .window
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+    {
+        None => None,
+        Some(raw) => match crate::db::StatsWindow::parse(raw) {
+            Some(w) => Some(w),
+            None => {
+                return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+                    "Invalid window (expected week / month)".to_string(),
+                ))
+            }
+        },
+    };
 
-    match db.get_developer_center_stats(&email).await {
+    match db.get_developer_center_stats_windowed(&email, window).await {
         Ok(stats) => HttpResponse::Ok().json(ApiResponse::success(stats)),
         Err(e) => {
             if is_db_unavailable_error(&e) {
@@ -1288,17 +3213,118 @@ pub async fn get_developer_center_stats(
                         followers: 0,
                         total_likes: 0,
                         total_favorites: 0,
+                        windowed: None,
                     },
                     "数据库连接不可用，已降级返回空统计。".to_string(),
                     &e,
                 ));
             }
-            HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)))
+            database_error_response(&e)
         }
     }
 }
 
+/**
+ * export_developer_profile
+ * 导出创作者完整公开资料（资料、已通过产品、关注数、近一月热度）为单个 JSON 文档；
+ * 本人（Bearer 邮箱一致）或管理员请求时包含未公开产品与关注的人数。
+ */
+pub async fn export_developer_profile(
+    req: HttpRequest,
+    path: web::Path<DeveloperPath>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    let email = path.into_inner().email.trim().to_ascii_lowercase();
+    if !is_valid_email_basic(&email) {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<()>::error("Invalid email".to_string()));
+    }
+
+    let developer = match db.get_developer_by_email(&email).await {
+        Ok(Some(v)) => v,
+        Ok(None) => {
+            return HttpResponse::NotFound()
+                .json(ApiResponse::<()>::error("Developer not found".to_string()))
+        }
+        Err(e) => return database_error_response(&e),
+    };
+
+    let bearer_email = match extract_bearer_token(&req) {
+        Some(token) => resolve_supabase_email_from_bearer(&token).await,
+        None => None,
+    };
+    let includes_private = bearer_email
+        .as_deref()
+        .is_some_and(|v| is_same_user_email(&email, v))
+        || validate_admin_token(&req).is_ok();
+
+    let params = QueryParams {
+        category: None,
+        tags: None,
+        language: None,
+        status: (!includes_private).then(|| "approved".to_string()),
+        search: None,
+        maker_email: Some(email.clone()),
+        sort: Some("created_at".to_string()),
+        dir: Some("desc".to_string()),
+        limit: None,
+        offset: None,
+        user_id: None,
+        created_after: None,
+        created_before: None,
+        min_likes: None,
+        sponsor_verified: None,
+        include_pending: None,
+        seed: None,
+    };
+    let products = match db.get_products(params).await {
+        Ok(list) => list,
+        Err(e) => return database_error_response(&e),
+    };
+    let stats = match db
+        .get_developer_center_stats_windowed(&email, Some(crate::db::StatsWindow::Month))
+        .await
+    {
+        Ok(v) => v,
+        Err(e) => return database_error_response(&e),
+    };
+    let following = if includes_private {
+        match db.get_developer_following_count(&email).await {
+            Ok(v) => Some(v),
+            Err(e) => {
+                log::warn!("Following count unavailable for export: {:?}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let filename = format!(
+        "soloforge-{}.json",
+        email
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect::<String>()
+    );
+    HttpResponse::Ok()
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", filename),
+        ))
+        .insert_header(("Cache-Control", "private, no-store"))
+        .json(DeveloperProfileExport {
+            exported_at: Utc::now(),
+            developer,
+            product_count: products.len(),
+            products,
+            followers: stats.followers,
+            following,
+            stats,
+            includes_private,
+        })
+}
+
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateDeveloperRequest {
     pub user_id: Option<String>,
@@ -1308,6 +3334,7 @@ pub struct UpdateDeveloperRequest {
 }
 
 pub async fn update_developer_profile(
+    req: HttpRequest,
     path: web::Path<DeveloperPath>,
     body: web::Json<UpdateDeveloperRequest>,
     db: web::Data<Arc<Database>>,
@@ -1336,24 +3363,24 @@ pub async fn update_developer_profile(
             }
         })
     });
-    let website = body.website.clone().map(|v| {
-        v.and_then(|s| {
-            let trimmed = s.trim().to_string();
-            if trimmed.is_empty() {
-                None
-            } else {
-                Some(trimmed)
+    let website = match body.website.as_ref() {
+        Some(v) => match crate::db::normalize_website_url("website", v.as_deref().unwrap_or("")) {
+            Ok(normalized) => Some(normalized),
+            Err(e) => {
+                return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+                    e.localized_message(get_language_from_request(&req)),
+                ))
             }
-        })
-    });
+        },
+        None => None,
+    };
 
     match db
         .update_developer_profile(&email, name, avatar_url, website)
         .await
     {
         Ok(dev) => HttpResponse::Ok().json(ApiResponse::success(dev)),
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<()>::error(format!("Database error: {:?}", e))),
+        Err(e) => database_error_response(&e),
     }
 }
 
@@ -1387,24 +3414,30 @@ pub async fn unfollow_developer(
                     &e,
                 ));
             }
-            HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)))
+            database_error_response(&e)
         }
     }
 }
 
 pub async fn like_product(
+    req: HttpRequest,
     path: web::Path<String>,
     body: Option<web::Json<InteractionBody>>,
     db: web::Data<Arc<Database>>,
+    limiter: web::Data<EngagementRateLimiter>,
 ) -> impl Responder {
-    let product_id = path.into_inner();
-    let user_id = match extract_user_id(&body) {
-        Some(v) if !is_anonymous_user_id(&v) => v,
-        _ => {
-            return HttpResponse::Unauthorized()
-                .json(ApiResponse::<()>::error("Unauthorized".to_string()))
-        }
+    let product_id = path.into_inner().trim().to_string();
+    if Uuid::parse_str(&product_id).is_err() {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<()>::error("Invalid product id".to_string()));
+    }
+    let ip_hash = client_ip_hash(&req);
+    if let Err(resp) = check_engagement_rate_limit(&req, &limiter, ip_hash.as_deref()) {
+        return resp;
+    }
+    let Some(user_id) = resolve_engagement_user(&req, &body).await else {
+        return HttpResponse::Unauthorized()
+            .json(ApiResponse::<()>::error("Unauthorized".to_string()));
     };
 
     let product = match db.get_product_by_id(&product_id).await {
@@ -1422,17 +3455,24 @@ pub async fn like_product(
                     &e,
                 ));
             }
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)));
+            return database_error_response(&e);
         }
     };
+    if !matches!(product.status, crate::models::ProductStatus::Approved) {
+        return HttpResponse::Conflict().json(ApiResponse::<()>::error(
+            "Product is not approved".to_string(),
+        ));
+    }
     if is_same_user_email(&product.maker_email, &user_id) {
         return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
             "Cannot like your own product".to_string(),
         ));
     }
 
-    match db.like_product(&product_id, &user_id).await {
+    match db
+        .like_product(&product_id, &user_id, ip_hash.as_deref())
+        .await
+    {
         Ok(()) => HttpResponse::Ok().json(ApiResponse::success(OkPayload { ok: true })),
         Err(e) => {
             if is_db_unavailable_error(&e) {
@@ -1443,8 +3483,150 @@ pub async fn like_product(
                     &e,
                 ));
             }
-            HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)))
+            database_error_response(&e)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ClaimProductRequest {
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProductBumpResult {
+    pub product_id: String,
+    pub bumped_at: chrono::DateTime<Utc>,
+    pub next_bump_allowed_at: chrono::DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProductBumpCooldown {
+    pub next_allowed_at: chrono::DateTime<Utc>,
+}
+
+/**
+ * bump_product
+ * 创作者“顶一下”自己的产品（更新 bumped_at，在 sort=recent 中重新靠前）；每 BUMP_COOLDOWN_DAYS 天一次，冷却中返回 429 与下次可用时间。
+ */
+pub async fn bump_product(
+    req: HttpRequest,
+    path: web::Path<String>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    let product_id = path.into_inner().trim().to_string();
+    if Uuid::parse_str(&product_id).is_err() {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<()>::error("Invalid product id".to_string()));
+    }
+    let lang = get_language_from_request(&req);
+
+    let email = match extract_bearer_token(&req) {
+        Some(token) => resolve_supabase_email_from_bearer(&token).await,
+        None => None,
+    };
+    let Some(email) = email else {
+        return HttpResponse::Unauthorized()
+            .json(ApiResponse::<()>::error("Unauthorized".to_string()));
+    };
+
+    let product = match db.get_product_by_id(&product_id).await {
+        Ok(Some(v)) => v,
+        Ok(None) => {
+            return HttpResponse::NotFound()
+                .json(ApiResponse::<()>::error("Product not found".to_string()))
+        }
+        Err(e) => return database_error_response(&e),
+    };
+    if !is_same_user_email(&product.maker_email, &email) {
+        return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+            if lang.starts_with("zh") {
+                "只有产品创作者可以顶帖".to_string()
+            } else {
+                "Only the product's maker can bump it".to_string()
+            },
+        ));
+    }
+
+    let now = Utc::now();
+    let cooldown_days = crate::db::bump_cooldown_days();
+    match db.bump_product(&product_id, now, cooldown_days).await {
+        Ok(Some(bumped_at)) => HttpResponse::Ok().json(ApiResponse::success(ProductBumpResult {
+            product_id,
+            bumped_at,
+            next_bump_allowed_at: bumped_at + Duration::days(cooldown_days),
+        })),
+        Ok(None) => {
+            HttpResponse::NotFound().json(ApiResponse::<()>::error("Product not found".to_string()))
+        }
+        Err(e) => {
+            if let Some(cooldown) = e.downcast_ref::<ProductBumpCooldownError>() {
+                let retry_after = (cooldown.next_allowed_at - now).num_seconds().max(1);
+                return HttpResponse::TooManyRequests()
+                    .insert_header(("Retry-After", retry_after.to_string()))
+                    .json(ApiResponse {
+                        success: false,
+                        data: Some(ProductBumpCooldown {
+                            next_allowed_at: cooldown.next_allowed_at,
+                        }),
+                        message: Some(cooldown.localized_message(lang)),
+                        error: None,
+                    });
+            }
+            database_error_response(&e)
+        }
+    }
+}
+
+/**
+ * claim_product
+ * 创作者认领导入的产品：需登录（Supabase 已验证邮箱）。邮箱与 maker_email 一致时立即认领成功，
+ * 否则进入 pending 等待管理员审核；已被他人认领返回 409。
+ */
+pub async fn claim_product(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: Option<web::Json<ClaimProductRequest>>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    let product_id = path.into_inner().trim().to_string();
+    if Uuid::parse_str(&product_id).is_err() {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<()>::error("Invalid product id".to_string()));
+    }
+    let lang = get_language_from_request(&req);
+
+    let email = match extract_bearer_token(&req) {
+        Some(token) => resolve_supabase_email_from_bearer(&token).await,
+        None => None,
+    };
+    let Some(email) = email else {
+        return HttpResponse::Unauthorized()
+            .json(ApiResponse::<()>::error("Unauthorized".to_string()));
+    };
+
+    let note = body.as_ref().and_then(|b| b.note.as_deref());
+    match db.claim_product(&product_id, &email, note).await {
+        Ok(Some(claim)) => {
+            if claim.status == "approved" {
+                db.invalidate_caches();
+            }
+            HttpResponse::Ok().json(ApiResponse::success(claim))
+        }
+        Ok(None) => {
+            HttpResponse::NotFound().json(ApiResponse::<()>::error("Product not found".to_string()))
+        }
+        Err(e) => {
+            if e.downcast_ref::<ProductAlreadyClaimedError>().is_some() {
+                return HttpResponse::Conflict().json(ApiResponse::<()>::error(
+                    if lang.starts_with("zh") {
+                        "该产品已被其他创作者认领".to_string()
+                    } else {
+                        "This product has already been claimed by another maker".to_string()
+                    },
+                ));
+            }
+            database_error_response(&e)
         }
     }
 }
@@ -1454,7 +3636,11 @@ pub async fn unlike_product(
     body: Option<web::Json<InteractionBody>>,
     db: web::Data<Arc<Database>>,
 ) -> impl Responder {
-    let product_id = path.into_inner();
+    let product_id = path.into_inner().trim().to_string();
+    if Uuid::parse_str(&product_id).is_err() {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<()>::error("Invalid product id".to_string()));
+    }
     let user_id = match extract_user_id(&body) {
         Some(v) if !is_anonymous_user_id(&v) => v,
         _ => {
@@ -1478,8 +3664,7 @@ pub async fn unlike_product(
                     &e,
                 ));
             }
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)));
+            return database_error_response(&e);
         }
     };
     if is_same_user_email(&product.maker_email, &user_id) {
@@ -1499,24 +3684,30 @@ pub async fn unlike_product(
                     &e,
                 ));
             }
-            HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)))
+            database_error_response(&e)
         }
     }
 }
 
 pub async fn favorite_product(
+    req: HttpRequest,
     path: web::Path<String>,
     body: Option<web::Json<InteractionBody>>,
     db: web::Data<Arc<Database>>,
+    limiter: web::Data<EngagementRateLimiter>,
 ) -> impl Responder {
-    let product_id = path.into_inner();
-    let user_id = match extract_user_id(&body) {
-        Some(v) if !is_anonymous_user_id(&v) => v,
-        _ => {
-            return HttpResponse::Unauthorized()
-                .json(ApiResponse::<()>::error("Unauthorized".to_string()))
-        }
+    let product_id = path.into_inner().trim().to_string();
+    if Uuid::parse_str(&product_id).is_err() {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<()>::error("Invalid product id".to_string()));
+    }
+    let ip_hash = client_ip_hash(&req);
+    if let Err(resp) = check_engagement_rate_limit(&req, &limiter, ip_hash.as_deref()) {
+        return resp;
+    }
+    let Some(user_id) = resolve_engagement_user(&req, &body).await else {
+        return HttpResponse::Unauthorized()
+            .json(ApiResponse::<()>::error("Unauthorized".to_string()));
     };
 
     let product = match db.get_product_by_id(&product_id).await {
@@ -1534,17 +3725,24 @@ pub async fn favorite_product(
                     &e,
                 ));
             }
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)));
+            return database_error_response(&e);
         }
     };
+    if !matches!(product.status, crate::models::ProductStatus::Approved) {
+        return HttpResponse::Conflict().json(ApiResponse::<()>::error(
+            "Product is not approved".to_string(),
+        ));
+    }
     if is_same_user_email(&product.maker_email, &user_id) {
         return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
             "Cannot favorite your own product".to_string(),
         ));
     }
 
-    match db.favorite_product(&product_id, &user_id).await {
+    match db
+        .favorite_product(&product_id, &user_id, ip_hash.as_deref())
+        .await
+    {
         Ok(()) => HttpResponse::Ok().json(ApiResponse::success(OkPayload { ok: true })),
         Err(e) => {
             if is_db_unavailable_error(&e) {
@@ -1555,8 +3753,7 @@ pub async fn favorite_product(
                     &e,
                 ));
             }
-            HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)))
+            database_error_response(&e)
         }
     }
 }
@@ -1566,7 +3763,11 @@ pub async fn unfavorite_product(
     body: Option<web::Json<InteractionBody>>,
     db: web::Data<Arc<Database>>,
 ) -> impl Responder {
-    let product_id = path.into_inner();
+    let product_id = path.into_inner().trim().to_string();
+    if Uuid::parse_str(&product_id).is_err() {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<()>::error("Invalid product id".to_string()));
+    }
     let user_id = match extract_user_id(&body) {
         Some(v) if !is_anonymous_user_id(&v) => v,
         _ => {
@@ -1590,8 +3791,7 @@ pub async fn unfavorite_product(
                     &e,
                 ));
             }
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)));
+            return database_error_response(&e);
         }
     };
     if is_same_user_email(&product.maker_email, &user_id) {
@@ -1611,8 +3811,7 @@ pub async fn unfavorite_product(
                     &e,
                 ));
             }
-            HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)))
+            database_error_response(&e)
         }
     }
 }
@@ -1621,6 +3820,7 @@ pub async fn unfavorite_product(
 pub struct FavoriteProductsQuery {
     pub user_id: String,
     pub limit: Option<i64>,
+    pub offset: Option<i64>,
     pub language: Option<String>,
 }
 
@@ -1630,6 +3830,7 @@ pub async fn get_favorite_products(
     db: web::Data<Arc<Database>>,
 ) -> impl Responder {
     let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
     let user_id = query.user_id.trim().to_string();
     let language = query.language.clone();
 
@@ -1639,10 +3840,14 @@ pub async fn get_favorite_products(
     }
 
     match db
-        .get_favorite_products(&user_id, language.as_deref(), limit)
+        .get_favorite_products(&user_id, language.as_deref(), limit, offset)
         .await
     {
-        Ok(list) => HttpResponse::Ok().json(ApiResponse::success(list)),
+        Ok((list, total)) => {
+            let mut resp = HttpResponse::Ok();
+            insert_pagination_headers(&mut resp, Some(limit), Some(offset), Some(total));
+            resp.json(ApiResponse::success(list))
+        }
         Err(e) => {
             if is_db_unavailable_error(&e) {
                 let lang = get_language_from_request(&req);
@@ -1660,9 +3865,53 @@ pub async fn get_favorite_products(
                 ));
             }
 
-            HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)))
+            database_error_response(&e)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+pub struct ProductChangesQuery {
+    pub since: Option<chrono::DateTime<Utc>>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/**
+ * get_product_changes
+ * 增量同步接口：返回 since 之后变更的产品（按 updated_at、id 升序），删除或下线的产品以 deleted=true 标记返回；
+ * 翻页时传入上一页的 next_cursor（优先于 since）。
+ */
+pub async fn get_product_changes(
+    query: web::Query<ProductChangesQuery>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    let limit = query.limit.unwrap_or(100).clamp(1, 500);
+    let (after, after_id) = match query.cursor.as_deref().map(str::trim) {
+        Some(cursor) if !cursor.is_empty() => {
+            match crate::db::decode_product_changes_cursor(cursor) {
+                Some((updated_at, id)) => (updated_at, Some(id)),
+                None => {
+                    return HttpResponse::BadRequest()
+                        .json(ApiResponse::<()>::error("Invalid cursor".to_string()))
+                }
+            }
         }
+        _ => match query.since {
+            Some(since) => (since, None),
+            None => {
+                return HttpResponse::BadRequest()
+                    .json(ApiResponse::<()>::error("Missing since".to_string()))
+            }
+        },
+    };
+
+    match db
+        .get_product_changes(after, after_id.as_deref(), limit)
+        .await
+    {
+        Ok(page) => HttpResponse::Ok().json(ApiResponse::success(page)),
+        Err(e) => database_error_response(&e),
     }
 }
 
@@ -1678,6 +3927,8 @@ pub struct MakerRank {
     pub maker_name: String,
     pub maker_email: String,
     pub avatar_url: Option<String>,
+    pub sponsor_role: Option<String>,
+    pub sponsor_verified: bool,
     pub product_count: usize,
 }
 
@@ -1687,6 +3938,32 @@ pub struct LeaderboardData {
     pub top_makers: Vec<MakerRank>,
 }
 
+/**
+ * leaderboard_query_params
+ * 排行榜候选产品的查询参数：走 get_products（LEFT JOIN developers），产品自带 maker_sponsor_role / maker_sponsor_verified。
+ */
+pub(crate) fn leaderboard_query_params(language: Option<String>, limit: usize) -> QueryParams {
+    QueryParams {
+        category: None,
+        tags: None,
+        language,
+        status: Some("approved".to_string()),
+        search: None,
+        maker_email: None,
+        sort: None,
+        dir: None,
+        limit: Some((limit as i64) * 5),
+        offset: None,
+        user_id: None,
+        created_after: None,
+        created_before: None,
+        min_likes: None,
+        sponsor_verified: None,
+        include_pending: None,
+        seed: None,
+    }
+}
+
 pub async fn get_leaderboard(
     req: HttpRequest,
     query: web::Query<LeaderboardQuery>,
@@ -1708,18 +3985,7 @@ pub async fn get_leaderboard(
         _ => Some(Utc::now() - Duration::days(7)),
     };
 
-    let params = QueryParams {
-        category: None,
-        tags: None,
-        language: query.language.clone(),
-        status: Some("approved".to_string()),
-        search: None,
-        maker_email: None,
-        sort: None,
-        dir: None,
-        limit: Some((limit as i64) * 5),
-        offset: None,
-    };
+    let params = leaderboard_query_params(query.language.clone(), limit);
 
     let products = match db.get_products(params).await {
         Ok(products) => products,
@@ -1743,8 +4009,7 @@ pub async fn get_leaderboard(
                 ));
             }
 
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)));
+            return database_error_response(&e);
         }
     };
 
@@ -1789,14 +4054,17 @@ pub async fn get_leaderboard(
             .get(&maker_email)
             .cloned()
             .unwrap_or_else(|| maker_email.clone());
-        let avatar_url = match db.get_developer_by_email(&maker_email).await {
-            Ok(Some(dev)) => dev.avatar_url,
-            _ => None,
-        };
+        let (avatar_url, sponsor_role, sponsor_verified) =
+            match db.get_developer_by_email(&maker_email).await {
+                Ok(Some(dev)) => (dev.avatar_url, dev.sponsor_role, dev.sponsor_verified),
+                _ => (None, None, false),
+            };
         top_makers.push(MakerRank {
             maker_name,
             maker_email,
             avatar_url,
+            sponsor_role,
+            sponsor_verified,
             product_count,
         });
     }
@@ -1813,12 +4081,59 @@ pub struct HomeModuleQuery {
     pub limit: Option<i64>,
 }
 
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct HomeProductsPayload {
     pub products: Vec<Product>,
     pub next_refresh_at: String,
 }
 
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct HomeSponsorshipInfo {
+    pub placement: String,
+    pub slot_index: Option<i32>,
+    pub ends_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct HomeSponsoredProduct {
+    #[serde(flatten)]
+    pub product: Product,
+    pub sponsored: bool,
+    pub sponsorship: Option<HomeSponsorshipInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct HomeSponsoredProductsPayload {
+    pub products: Vec<HomeSponsoredProduct>,
+    pub next_refresh_at: String,
+}
+
+/**
+ * label_home_products
+ * 为首页付费位产品附加 grant 信息（placement / slot_index / ends_at）；免费轮播产品 sponsored=false。
+ */
+fn label_home_products(
+    products: Vec<Product>,
+    placement: &str,
+    paid: &std::collections::HashMap<String, ActiveSponsorshipGrant>,
+) -> Vec<HomeSponsoredProduct> {
+    products
+        .into_iter()
+        .map(|product| {
+            let sponsorship = paid.get(&product.id).map(|g| HomeSponsorshipInfo {
+                placement: placement.to_string(),
+                slot_index: g.slot_index,
+                ends_at: g.ends_at.to_rfc3339(),
+            });
+            HomeSponsoredProduct {
+                product,
+                sponsored: sponsorship.is_some(),
+                sponsorship,
+            }
+        })
+        .collect()
+}
+
 #[allow(dead_code)]
 fn start_of_next_day_utc(now: chrono::DateTime<Utc>) -> chrono::DateTime<Utc> {
     let today = now.date_naive();
@@ -1879,6 +4194,14 @@ fn stable_sponsor_assign_to_top(product_id: &str, day_key: chrono::NaiveDate) ->
     (hasher.finish() & 1) == 0
 }
 
+/**
+ * home_sponsored_capacity
+ * 首页付费位的展示数量，与 placement_slot_count 配置的槽位数保持一致。
+ */
+fn home_sponsored_capacity(placement: Placement) -> usize {
+    crate::db::placement_slot_count(placement) as usize
+}
+
 async fn get_or_refresh_free_sponsor_queue_ids(
     db: &Database,
     now: chrono::DateTime<Utc>,
@@ -1891,31 +4214,36 @@ async fn get_or_refresh_free_sponsor_queue_ids(
         Utc,
     );
     let mode_key = day_key.to_string();
+    // 免费队列依次填充顶部与右侧，长度为两处槽位之和。
+    let queue_size =
+        home_sponsored_capacity(Placement::HomeTop) + home_sponsored_capacity(Placement::HomeRight);
 
     let state_key = "home_sponsored_free_queue";
     if let Ok(Some(state)) = db.get_home_module_state(state_key).await {
-        if state.mode.as_deref() == Some("manual") && state.today_ids.len() == 5 {
+        if state.mode.as_deref() == Some("manual") && state.today_ids.len() == queue_size {
             return Ok((state.today_ids, next_refresh));
         }
-        if state.mode.as_deref() == Some(mode_key.as_str()) && state.today_ids.len() == 5 {
+        if state.mode.as_deref() == Some(mode_key.as_str()) && state.today_ids.len() == queue_size {
             return Ok((state.today_ids, next_refresh));
         }
     }
 
-    let total = db.count_products_for_sponsorship_rotation(language).await?;
+    let total = postgres_optional(db.count_products_for_sponsorship_rotation(language).await)?;
     let eligible = if total <= 50 {
-        db.get_first_product_ids_by_created_at(50, language).await?
+        postgres_optional(db.get_first_product_ids_by_created_at(50, language).await)?
     } else {
         let prev_day = day_key.pred_opt().unwrap_or(day_key);
-        db.get_popular_product_ids_by_day(prev_day, 200, language)
-            .await?
+        postgres_optional(
+            db.get_popular_product_ids_by_day(prev_day, 200, language)
+                .await,
+        )?
     };
     if eligible.is_empty() {
         return Ok((Vec::new(), next_refresh));
     }
 
     let seed = stable_seed_from_day_key(day_key, 0xD6E8FEB86659FD93);
-    let today_ids = stable_pick_ids(&eligible, 5, seed);
+    let today_ids = stable_pick_ids(&eligible, queue_size, seed);
 
     let _ = db
         .upsert_home_module_state(crate::db::HomeModuleState {
@@ -1935,103 +4263,115 @@ fn stable_seed_from_window_key(window_start_ts: i64, extra: u64) -> u64 {
     (window_start_ts as u64) ^ extra.wrapping_mul(2654435761)
 }
 
-pub async fn get_home_sponsored_top(
-    req: HttpRequest,
-    query: web::Query<HomeModuleQuery>,
-    db: web::Data<Arc<Database>>,
-) -> impl Responder {
-    let now = Utc::now();
+/**
+ * home_products_error
+ * 首页模块加载失败时的响应：数据库不可用降级为空列表，其余错误返回 500。
+ */
+fn home_products_error(
+    req: &HttpRequest,
+    endpoint: &str,
+    next_refresh: chrono::DateTime<Utc>,
+    e: &anyhow::Error,
+) -> HttpResponse {
+    if !is_db_unavailable_error(e) {
+        return database_error_response(e);
+    }
+    let message = if get_language_from_request(req).starts_with("zh") {
+        "数据库连接不可用，已降级返回空列表。"
+    } else {
+        "Database is unavailable. Returning empty list in degraded mode."
+    };
+    HttpResponse::Ok().json(make_db_degraded_response(
+        endpoint,
+        HomeProductsPayload {
+            products: Vec::new(),
+            next_refresh_at: next_refresh.to_rfc3339(),
+        },
+        message.to_string(),
+        e,
+    ))
+}
+
+/**
+ * next_utc_midnight
+ * 首页付费位按 UTC 自然日轮换，返回下一次刷新的时间点。
+ */
+fn next_utc_midnight(now: chrono::DateTime<Utc>) -> chrono::DateTime<Utc> {
     let day_key = now.date_naive();
     let next_day = day_key.succ_opt().unwrap_or(day_key);
-    let next_refresh = chrono::DateTime::<Utc>::from_naive_utc_and_offset(
+    chrono::DateTime::<Utc>::from_naive_utc_and_offset(
         next_day.and_hms_opt(0, 0, 0).unwrap_or_default(),
         Utc,
-    );
+    )
+}
+
+/**
+ * home_sponsored_top_payload
+ * 首页顶部付费位（默认 2 个，见 SPONSORSHIP_HOME_TOP_SLOTS）：按模块 mode 组合（默认 mixed），见 compose_home_module_ids 的填充顺序。
+ */
+async fn home_sponsored_top_payload(
+    db: &Database,
+    language: Option<&str>,
+    now: chrono::DateTime<Utc>,
+) -> anyhow::Result<HomeSponsoredProductsPayload> {
+    const PLACEMENT: Placement = Placement::HomeTop;
+    let capacity = home_sponsored_capacity(PLACEMENT);
+
+    let day_key = now.date_naive();
+    let next_refresh = next_utc_midnight(now);
 
     let key = "home_sponsored_top";
+    let mut mode = HomeModuleMode::Mixed;
     let mut ids: Vec<String> = Vec::new();
     if let Ok(Some(state)) = db.get_home_module_state(key).await {
-        if state.mode.as_deref() == Some("manual") && state.today_ids.len() == 2 {
+        mode = HomeModuleMode::resolve(state.mode.as_deref(), HomeModuleMode::Mixed);
+        if mode == HomeModuleMode::Manual && state.today_ids.len() == capacity {
             ids = state.today_ids;
         }
     }
+    if mode == HomeModuleMode::Manual {
+        mode = HomeModuleMode::Mixed;
+    }
 
+    let mut paid_by_id: std::collections::HashMap<String, ActiveSponsorshipGrant> =
+        std::collections::HashMap::new();
     if ids.is_empty() {
-        let mut selected: Vec<String> = Vec::new();
-        let mut exclude: std::collections::HashSet<String> = std::collections::HashSet::new();
-
-        let paid_grants = match db
-            .get_active_sponsorship_grants("home_top", now, query.language.as_deref())
-            .await
-        {
-            Ok(list) => list,
-            Err(e) => {
-                if is_db_unavailable_error(&e) {
-                    let message = if get_language_from_request(&req).starts_with("zh") {
-                        "数据库连接不可用，已降级返回空列表。"
-                    } else {
-                        "Database is unavailable. Returning empty list in degraded mode."
-                    };
-                    return HttpResponse::Ok().json(make_db_degraded_response(
-                        "GET /api/home/sponsored-top",
-                        HomeProductsPayload {
-                            products: Vec::new(),
-                            next_refresh_at: next_refresh.to_rfc3339(),
-                        },
-                        message.to_string(),
-                        &e,
-                    ));
+        let mut paid_slots: Vec<Option<String>> = Vec::new();
+        if mode.uses_paid() {
+            let paid_grants = db
+                .get_active_sponsorship_grants(PLACEMENT, now, language)
+                .await;
+            let paid_grants = postgres_optional(paid_grants)?;
+
+            let paid_ids: Vec<String> = paid_grants.iter().map(|g| g.product_id.clone()).collect();
+            let seed_paid = stable_seed_from_day_key(day_key, 0x9E3779B97F4A7C15);
+            paid_slots = stable_pick_ids(&paid_ids, capacity, seed_paid ^ 0xA1B2C3D4E5F60718)
+                .into_iter()
+                .map(Some)
+                .collect();
+            for grant in paid_grants {
+                if paid_slots.contains(&Some(grant.product_id.clone())) {
+                    paid_by_id.entry(grant.product_id.clone()).or_insert(grant);
                 }
-                return HttpResponse::InternalServerError()
-                    .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)));
             }
-        };
-
-        let paid_ids: Vec<String> = paid_grants.into_iter().map(|(_, id)| id).collect();
-        let seed_paid = stable_seed_from_day_key(day_key, 0x9E3779B97F4A7C15);
-        let paid_pick = stable_pick_ids(&paid_ids, 2, seed_paid ^ 0xA1B2C3D4E5F60718);
-        for id in paid_pick {
-            exclude.insert(id.clone());
-            selected.push(id);
         }
 
-        if selected.len() < 2 {
-            let free_today = match get_or_refresh_free_sponsor_queue_ids(
-                db.get_ref().as_ref(),
-                now,
-                query.language.as_deref(),
-            )
-            .await
-            {
-                Ok((ids, _)) => ids,
-                Err(e) => {
-                    if is_db_unavailable_error(&e) {
-                        Vec::new()
-                    } else {
-                        return HttpResponse::InternalServerError()
-                            .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)));
-                    }
-                }
+        let mut free_top: Vec<String> = Vec::new();
+        if mode.uses_free() {
+            free_top = match get_or_refresh_free_sponsor_queue_ids(db, now, language).await {
+                Ok((ids, _)) => ids.into_iter().take(capacity).collect(),
+                Err(e) if is_db_unavailable_error(&e) => Vec::new(),
+                Err(e) => return Err(e),
             };
+        }
 
-            let free_top = free_today.into_iter().take(2).collect::<Vec<_>>();
-            for id in free_top {
-                if selected.len() >= 2 {
-                    break;
-                }
-                if exclude.contains(&id) {
-                    continue;
-                }
-                exclude.insert(id.clone());
-                selected.push(id);
-            }
-        }
+        ids = compose_home_module_ids(mode, &paid_slots, &free_top, &[], capacity);
 
-        if selected.len() < 2 {
+        if ids.len() < capacity && mode.uses_free() {
             let params = QueryParams {
                 category: None,
                 tags: None,
-                language: query.language.clone(),
+                language: language.map(str::to_string),
                 status: Some("approved".to_string()),
                 search: None,
                 maker_email: None,
@@ -2039,198 +4379,160 @@ pub async fn get_home_sponsored_top(
                 dir: Some("desc".to_string()),
                 limit: Some(50),
                 offset: None,
+                user_id: None,
+                created_after: None,
+                created_before: None,
+                min_likes: None,
+                sponsor_verified: None,
+                include_pending: None,
+                seed: None,
             };
-            let fallback = match db.get_products(params).await {
-                Ok(list) => list,
-                Err(e) => {
-                    if is_db_unavailable_error(&e) {
-                        let message = if get_language_from_request(&req).starts_with("zh") {
-                            "数据库连接不可用，已降级返回空列表。"
-                        } else {
-                            "Database is unavailable. Returning empty list in degraded mode."
-                        };
-                        return HttpResponse::Ok().json(make_db_degraded_response(
-                            "GET /api/home/sponsored-top",
-                            HomeProductsPayload {
-                                products: Vec::new(),
-                                next_refresh_at: next_refresh.to_rfc3339(),
-                            },
-                            message.to_string(),
-                            &e,
-                        ));
-                    }
-                    return HttpResponse::InternalServerError()
-                        .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)));
-                }
-            };
-
-            for p in fallback {
-                if selected.len() >= 2 {
-                    break;
-                }
-                if exclude.contains(&p.id) {
-                    continue;
-                }
-                exclude.insert(p.id.clone());
-                selected.push(p.id);
-            }
-        }
-
-        if selected.is_empty() {
-            return HttpResponse::Ok().json(ApiResponse::success(HomeProductsPayload {
-                products: Vec::new(),
-                next_refresh_at: next_refresh.to_rfc3339(),
-            }));
+            let fallback: Vec<String> = db
+                .get_products(params)
+                .await?
+                .into_iter()
+                .map(|p| p.id)
+                .collect();
+            ids = compose_home_module_ids(mode, &paid_slots, &free_top, &fallback, capacity);
         }
+    }
 
-        let products = match db.get_products_by_ids(&selected).await {
-            Ok(list) => list,
-            Err(e) => {
-                return HttpResponse::InternalServerError()
-                    .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)));
-            }
-        };
-
-        return HttpResponse::Ok().json(ApiResponse::success(HomeProductsPayload {
-            products,
+    if ids.is_empty() {
+        return Ok(HomeSponsoredProductsPayload {
+            products: Vec::new(),
             next_refresh_at: next_refresh.to_rfc3339(),
-        }));
+        });
     }
 
-    let products = match db.get_products_by_ids(&ids).await {
-        Ok(list) => list,
-        Err(e) => {
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)));
-        }
-    };
+    let products = db.get_products_by_ids(&ids, None).await?;
 
-    HttpResponse::Ok().json(ApiResponse::success(HomeProductsPayload {
-        products,
+    Ok(HomeSponsoredProductsPayload {
+        products: label_home_products(products, PLACEMENT.to_db_str(), &paid_by_id),
         next_refresh_at: next_refresh.to_rfc3339(),
-    }))
+    })
 }
 
-pub async fn get_home_sponsored_right(
+/**
+ * get_home_sponsored_top
+ * 首页顶部 2 个位，组合逻辑见 home_sponsored_top_payload。
+ */
+pub async fn get_home_sponsored_top(
     req: HttpRequest,
     query: web::Query<HomeModuleQuery>,
     db: web::Data<Arc<Database>>,
 ) -> impl Responder {
     let now = Utc::now();
+    match home_sponsored_top_payload(db.get_ref(), query.language.as_deref(), now).await {
+        Ok(payload) => HttpResponse::Ok().json(ApiResponse::success(payload)),
+        Err(e) => home_products_error(
+            &req,
+            "GET /api/home/sponsored-top",
+            next_utc_midnight(now),
+            &e,
+        ),
+    }
+}
+
+/**
+ * home_sponsored_right_payload
+ * 首页右侧付费位（默认 3 个，见 SPONSORSHIP_HOME_RIGHT_SLOTS）：付费 grants 优先落在各自 slot_index，其余按 mode 组合（默认 mixed）。
+ */
+async fn home_sponsored_right_payload(
+    db: &Database,
+    language: Option<&str>,
+    now: chrono::DateTime<Utc>,
+) -> anyhow::Result<HomeSponsoredProductsPayload> {
+    const PLACEMENT: Placement = Placement::HomeRight;
+    let capacity = home_sponsored_capacity(PLACEMENT);
+
     let day_key = now.date_naive();
-    let next_day = day_key.succ_opt().unwrap_or(day_key);
-    let next_refresh = chrono::DateTime::<Utc>::from_naive_utc_and_offset(
-        next_day.and_hms_opt(0, 0, 0).unwrap_or_default(),
-        Utc,
-    );
+    let next_refresh = next_utc_midnight(now);
     let key = "home_sponsored_right";
 
+    let mut mode = HomeModuleMode::Mixed;
     let mut today_ids: Vec<String> = Vec::new();
-
     if let Ok(Some(state)) = db.get_home_module_state(key).await {
-        if state.mode.as_deref() == Some("manual") && state.today_ids.len() == 3 {
+        mode = HomeModuleMode::resolve(state.mode.as_deref(), HomeModuleMode::Mixed);
+        if mode == HomeModuleMode::Manual && state.today_ids.len() == capacity {
             today_ids = state.today_ids;
         }
     }
+    if mode == HomeModuleMode::Manual {
+        mode = HomeModuleMode::Mixed;
+    }
 
+    let mut paid_by_id: std::collections::HashMap<String, ActiveSponsorshipGrant> =
+        std::collections::HashMap::new();
     if today_ids.is_empty() {
-        let mut slots: [Option<String>; 3] = [None, None, None];
-        let mut exclude: std::collections::HashSet<String> = std::collections::HashSet::new();
-
-        let paid_grants = match db
-            .get_active_sponsorship_grants("home_right", now, query.language.as_deref())
-            .await
-        {
-            Ok(list) => list,
-            Err(e) => {
-                if is_db_unavailable_error(&e) {
-                    let message = if get_language_from_request(&req).starts_with("zh") {
-                        "数据库连接不可用，已降级返回空列表。"
-                    } else {
-                        "Database is unavailable. Returning empty list in degraded mode."
-                    };
-                    return HttpResponse::Ok().json(make_db_degraded_response(
-                        "GET /api/home/sponsored-right",
-                        HomeProductsPayload {
-                            products: Vec::new(),
-                            next_refresh_at: next_refresh.to_rfc3339(),
-                        },
-                        message.to_string(),
-                        &e,
-                    ));
+        let mut slots: Vec<Option<String>> = vec![None; capacity];
+        if mode.uses_paid() {
+            let paid_grants = db
+                .get_active_sponsorship_grants(PLACEMENT, now, language)
+                .await;
+            let paid_grants = postgres_optional(paid_grants)?;
+
+            let mut exclude: std::collections::HashSet<String> = std::collections::HashSet::new();
+            let mut paid_pool: Vec<String> = Vec::new();
+            for grant in &paid_grants {
+                let id = grant.product_id.clone();
+                if exclude.contains(&id) {
+                    continue;
                 }
-                return HttpResponse::InternalServerError()
-                    .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)));
-            }
-        };
-
-        let mut paid_pool: Vec<String> = Vec::new();
-        for (slot_index, id) in paid_grants {
-            if exclude.contains(&id) {
-                continue;
-            }
-            match slot_index {
-                Some(i) if (0..=2).contains(&i) => {
-                    let idx = i as usize;
-                    if slots[idx].is_none() {
-                        exclude.insert(id.clone());
-                        slots[idx] = Some(id);
-                    } else {
-                        paid_pool.push(id);
+                match grant.slot_index {
+                    Some(i) if (0..capacity as i32).contains(&i) => {
+                        let idx = i as usize;
+                        if slots[idx].is_none() {
+                            exclude.insert(id.clone());
+                            slots[idx] = Some(id);
+                        } else {
+                            paid_pool.push(id);
+                        }
                     }
+                    _ => paid_pool.push(id),
                 }
-                _ => paid_pool.push(id),
             }
-        }
 
-        let seed_paid = stable_seed_from_day_key(day_key, 0x9E3779B97F4A7C15) ^ 0xA7F0C3B2D1E4F5A6;
-        let paid_pool_pick = stable_pick_ids(&paid_pool, 3, seed_paid);
-        let mut paid_pool_iter = paid_pool_pick.into_iter();
-        for slot in &mut slots {
-            if slot.is_none() {
-                if let Some(id) = paid_pool_iter.next() {
-                    if !exclude.contains(&id) {
-                        exclude.insert(id.clone());
-                        *slot = Some(id);
+            let seed_paid =
+                stable_seed_from_day_key(day_key, 0x9E3779B97F4A7C15) ^ 0xA7F0C3B2D1E4F5A6;
+            let paid_pool_pick = stable_pick_ids(&paid_pool, capacity, seed_paid);
+            let mut paid_pool_iter = paid_pool_pick.into_iter();
+            for slot in &mut slots {
+                if slot.is_none() {
+                    if let Some(id) = paid_pool_iter.next() {
+                        if !exclude.contains(&id) {
+                            exclude.insert(id.clone());
+                            *slot = Some(id);
+                        }
                     }
                 }
             }
-        }
-
-        let free_today = match get_or_refresh_free_sponsor_queue_ids(
-            db.get_ref().as_ref(),
-            now,
-            query.language.as_deref(),
-        )
-        .await
-        {
-            Ok((ids, _)) => ids,
-            Err(e) => {
-                if is_db_unavailable_error(&e) {
-                    Vec::new()
-                } else {
-                    return HttpResponse::InternalServerError()
-                        .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)));
+            for grant in paid_grants {
+                if slots.contains(&Some(grant.product_id.clone())) {
+                    paid_by_id.entry(grant.product_id.clone()).or_insert(grant);
                 }
             }
-        };
+        }
 
-        let mut free_iter = free_today.into_iter().skip(2).take(3);
-        for slot in &mut slots {
-            if slot.is_none() {
-                if let Some(id) = free_iter.next() {
-                    exclude.insert(id.clone());
-                    *slot = Some(id);
-                }
-            }
+        let mut free_right: Vec<String> = Vec::new();
+        if mode.uses_free() {
+            free_right = match get_or_refresh_free_sponsor_queue_ids(db, now, language).await {
+                Ok((ids, _)) => ids
+                    .into_iter()
+                    .skip(home_sponsored_capacity(Placement::HomeTop))
+                    .take(capacity)
+                    .collect(),
+                Err(e) if is_db_unavailable_error(&e) => Vec::new(),
+                Err(e) => return Err(e),
+            };
         }
 
-        let mut chosen: Vec<String> = slots.into_iter().flatten().collect();
-        if chosen.len() < 3 {
+        today_ids = compose_home_module_ids(mode, &slots, &free_right, &[], capacity);
+
+        if today_ids.len() < capacity && mode.uses_free() {
             let params = QueryParams {
                 category: None,
                 tags: None,
-                language: query.language.clone(),
+                language: language.map(str::to_string),
                 status: Some("approved".to_string()),
                 search: None,
                 maker_email: None,
@@ -2238,116 +4540,421 @@ pub async fn get_home_sponsored_right(
                 dir: Some("desc".to_string()),
                 limit: Some(200),
                 offset: None,
+                user_id: None,
+                created_after: None,
+                created_before: None,
+                min_likes: None,
+                sponsor_verified: None,
+                include_pending: None,
+                seed: None,
             };
-            let fallback = match db.get_products(params).await {
-                Ok(list) => list,
-                Err(e) => {
-                    if is_db_unavailable_error(&e) {
-                        let message = if get_language_from_request(&req).starts_with("zh") {
-                            "数据库连接不可用，已降级返回空列表。"
-                        } else {
-                            "Database is unavailable. Returning empty list in degraded mode."
-                        };
-                        return HttpResponse::Ok().json(make_db_degraded_response(
-                            "GET /api/home/sponsored-right",
-                            HomeProductsPayload {
-                                products: Vec::new(),
-                                next_refresh_at: next_refresh.to_rfc3339(),
-                            },
-                            message.to_string(),
-                            &e,
-                        ));
-                    }
-                    return HttpResponse::InternalServerError()
-                        .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)));
-                }
-            };
-
-            for p in fallback {
-                if chosen.len() >= 3 {
-                    break;
-                }
-                if exclude.contains(&p.id) {
-                    continue;
-                }
-                exclude.insert(p.id.clone());
-                chosen.push(p.id);
-            }
+            let fallback: Vec<String> = db
+                .get_products(params)
+                .await?
+                .into_iter()
+                .map(|p| p.id)
+                .collect();
+            today_ids = compose_home_module_ids(mode, &slots, &free_right, &fallback, capacity);
         }
-
-        today_ids = chosen;
     }
 
     if today_ids.is_empty() {
-        return HttpResponse::Ok().json(ApiResponse::success(HomeProductsPayload {
+        return Ok(HomeSponsoredProductsPayload {
             products: Vec::new(),
             next_refresh_at: next_refresh.to_rfc3339(),
-        }));
+        });
     }
 
-    let products = match db.get_products_by_ids(&today_ids).await {
-        Ok(list) => list,
-        Err(e) => {
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)));
+    let products = db.get_products_by_ids(&today_ids, None).await?;
+
+    Ok(HomeSponsoredProductsPayload {
+        products: label_home_products(products, PLACEMENT.to_db_str(), &paid_by_id),
+        next_refresh_at: next_refresh.to_rfc3339(),
+    })
+}
+
+/**
+ * get_home_sponsored_right
+ * 首页右侧 3 个位，组合逻辑见 home_sponsored_right_payload。
+ */
+pub async fn get_home_sponsored_right(
+    req: HttpRequest,
+    query: web::Query<HomeModuleQuery>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    let now = Utc::now();
+    match home_sponsored_right_payload(db.get_ref(), query.language.as_deref(), now).await {
+        Ok(payload) => HttpResponse::Ok().json(ApiResponse::success(payload)),
+        Err(e) => home_products_error(
+            &req,
+            "GET /api/home/sponsored-right",
+            next_utc_midnight(now),
+            &e,
+        ),
+    }
+}
+
+/**
+ * postgres_optional
+ * 首页公开读接口在仅配置 Supabase 时把 PostgresNotConfiguredError 当作空结果（不展示赞助位），其余错误原样返回。
+ */
+fn postgres_optional<T: Default>(result: anyhow::Result<T>) -> anyhow::Result<T> {
+    match result {
+        Err(e) if e.downcast_ref::<PostgresNotConfiguredError>().is_some() => Ok(T::default()),
+        other => other,
+    }
+}
+
+/**
+ * record_featured_picks
+ * 记录今天按热度入选首页精选的产品，供 FEATURED_COOLDOWN_DAYS 冷却；失败只记日志，不影响响应。
+ */
+async fn record_featured_picks(db: &Database, now: chrono::DateTime<Utc>, ids: &[String]) {
+    if let Err(e) = db.record_featured_ids(now.date_naive(), ids).await {
+        log::warn!("featured cooldown record failed: {:?}", e);
+    }
+}
+
+/**
+ * HOME_FEATURED_REFRESH_SECS
+ * 首页精选的建议刷新间隔（next_refresh_at）。
+ */
+const HOME_FEATURED_REFRESH_SECS: i64 = 15;
+
+/**
+ * home_featured_payload
+ * 首页精选：默认 free（按热度）；paid 仅展示 home_top/home_right 的生效 grants；mixed 先付费再按热度补齐。
+ */
+async fn home_featured_payload(
+    db: &Database,
+    language: Option<&str>,
+    limit: Option<i64>,
+    now: chrono::DateTime<Utc>,
+) -> anyhow::Result<HomeProductsPayload> {
+    let featured_limit = limit.unwrap_or(6).clamp(1, 10) as usize;
+    let next_refresh = now + chrono::Duration::seconds(HOME_FEATURED_REFRESH_SECS);
+
+    let mut mode = HomeModuleMode::Free;
+    let mut pinned: Vec<String> = Vec::new();
+    if let Ok(Some(state)) = db.get_home_module_state("home_featured").await {
+        mode = HomeModuleMode::resolve(state.mode.as_deref(), HomeModuleMode::Free);
+        if mode == HomeModuleMode::Manual {
+            pinned = state.today_ids.into_iter().take(featured_limit).collect();
+        }
+    }
+    if mode == HomeModuleMode::Manual && pinned.is_empty() {
+        mode = HomeModuleMode::Free;
+    }
+
+    let mut paid_slots: Vec<Option<String>> = Vec::new();
+    if mode.uses_paid() {
+        for placement in Placement::ALL {
+            let list = postgres_optional(
+                db.get_active_sponsorship_grants(placement, now, language)
+                    .await,
+            )?;
+            paid_slots.extend(list.into_iter().map(|g| Some(g.product_id)));
+        }
+    }
+
+    let cooldown_days = featured_cooldown_days();
+    let mut popular: Vec<Product> = Vec::new();
+    if mode.uses_free() {
+        // 冷却开启时多取一些候选，便于跳过近期入选过的产品后仍能填满。
+        let candidate_limit = if cooldown_days > 0 {
+            featured_limit * 4
+        } else {
+            featured_limit
+        };
+        let params = QueryParams {
+            category: None,
+            tags: None,
+            language: language.map(str::to_string),
+            status: Some("approved".to_string()),
+            search: None,
+            maker_email: None,
+            sort: Some("popularity".to_string()),
+            dir: Some("desc".to_string()),
+            limit: Some(candidate_limit as i64),
+            offset: None,
+            user_id: None,
+            created_after: None,
+            created_before: None,
+            min_likes: None,
+            sponsor_verified: None,
+            include_pending: None,
+            seed: None,
+        };
+
+        popular = db.get_products(params).await?;
+
+        if cooldown_days > 0 {
+            let today = now.date_naive();
+            let recent = db
+                .get_recently_featured_ids(today, cooldown_days)
+                .await
+                .unwrap_or_else(|e| {
+                    log::warn!("featured cooldown lookup failed: {:?}", e);
+                    std::collections::HashSet::new()
+                });
+            let candidate_ids: Vec<String> = popular.iter().map(|p| p.id.clone()).collect();
+            let picked = pick_featured_with_cooldown(&candidate_ids, &recent, featured_limit);
+            let mut by_id: std::collections::HashMap<String, Product> =
+                popular.into_iter().map(|p| (p.id.clone(), p)).collect();
+            popular = picked.iter().filter_map(|id| by_id.remove(id)).collect();
+        }
+    }
+
+    let ids = match mode {
+        HomeModuleMode::Free => {
+            if cooldown_days > 0 {
+                let shown: Vec<String> = popular.iter().map(|p| p.id.clone()).collect();
+                record_featured_picks(db, now, &shown).await;
+            }
+            return Ok(HomeProductsPayload {
+                products: popular,
+                next_refresh_at: next_refresh.to_rfc3339(),
+            });
+        }
+        HomeModuleMode::Manual => pinned,
+        HomeModuleMode::Paid | HomeModuleMode::Mixed => {
+            let popular_ids: Vec<String> = popular.iter().map(|p| p.id.clone()).collect();
+            let paid_slots: Vec<Option<String>> =
+                paid_slots.into_iter().take(featured_limit).collect();
+            let ids = compose_home_module_ids(mode, &paid_slots, &[], &popular_ids, featured_limit);
+            if cooldown_days > 0 {
+                let shown: Vec<String> = ids
+                    .iter()
+                    .filter(|id| popular_ids.contains(*id))
+                    .cloned()
+                    .collect();
+                record_featured_picks(db, now, &shown).await;
+            }
+            ids
         }
     };
 
-    HttpResponse::Ok().json(ApiResponse::success(HomeProductsPayload {
+    if ids.is_empty() {
+        return Ok(HomeProductsPayload {
+            products: Vec::new(),
+            next_refresh_at: next_refresh.to_rfc3339(),
+        });
+    }
+
+    let products = db.get_products_by_ids(&ids, None).await?;
+
+    Ok(HomeProductsPayload {
         products,
         next_refresh_at: next_refresh.to_rfc3339(),
-    }))
+    })
 }
 
+/**
+ * get_home_featured
+ * 首页精选，选取逻辑见 home_featured_payload。
+ */
 pub async fn get_home_featured(
     req: HttpRequest,
     query: web::Query<HomeModuleQuery>,
     db: web::Data<Arc<Database>>,
 ) -> impl Responder {
-    let featured_limit = query.limit.unwrap_or(6).clamp(1, 10) as usize;
     let now = Utc::now();
-    let next_refresh = now + chrono::Duration::seconds(15);
+    match home_featured_payload(db.get_ref(), query.language.as_deref(), query.limit, now).await {
+        Ok(payload) => HttpResponse::Ok().json(ApiResponse::success(payload)),
+        Err(e) => home_products_error(
+            &req,
+            "GET /api/home/featured",
+            now + chrono::Duration::seconds(HOME_FEATURED_REFRESH_SECS),
+            &e,
+        ),
+    }
+}
 
-    let params = QueryParams {
-        category: None,
-        tags: None,
-        language: query.language.clone(),
-        status: Some("approved".to_string()),
-        search: None,
-        maker_email: None,
-        sort: Some("popularity".to_string()),
-        dir: Some("desc".to_string()),
-        limit: Some(featured_limit as i64),
-        offset: None,
-    };
+/**
+ * HOME_AGGREGATE_TTL
+ * 首页聚合接口各分区的缓存时长；过期后重新加载，加载失败时沿用上一次的结果。
+ */
+const HOME_AGGREGATE_TTL: StdDuration = StdDuration::from_secs(15);
 
-    let products = match db.get_products(params).await {
-        Ok(list) => list,
-        Err(e) => {
-            if is_db_unavailable_error(&e) {
-                let message = if get_language_from_request(&req).starts_with("zh") {
-                    "数据库连接不可用，已降级返回空列表。"
-                } else {
-                    "Database is unavailable. Returning empty list in degraded mode."
-                };
-                return HttpResponse::Ok().json(make_db_degraded_response(
-                    "GET /api/home/featured",
-                    HomeProductsPayload {
-                        products: Vec::new(),
-                        next_refresh_at: next_refresh.to_rfc3339(),
-                    },
-                    message.to_string(),
-                    &e,
-                ));
+/**
+ * HomeSectionCache
+ * 首页聚合的单个分区缓存，按键（语言/条数）分别保存最近一次成功的结果。
+ */
+type HomeSectionCache<T> =
+    std::sync::Mutex<std::collections::HashMap<String, (std::time::Instant, T)>>;
+
+/**
+ * HomeAggregateCache
+ * GET /api/home 的分区缓存：各分区独立过期、独立降级，某一分区失败不会拖垮整页。
+ */
+#[derive(Default)]
+pub struct HomeAggregateCache {
+    featured: HomeSectionCache<HomeProductsPayload>,
+    sponsored_top: HomeSectionCache<HomeSponsoredProductsPayload>,
+    sponsored_right: HomeSectionCache<HomeSponsoredProductsPayload>,
+    top_categories: HomeSectionCache<Vec<CategoryWithCount>>,
+    stats: HomeSectionCache<OverviewStats>,
+}
+
+impl HomeAggregateCache {
+    /**
+     * clear
+     * 清空所有分区缓存（含过期的降级旧值），返回清空前是否有缓存。
+     */
+    pub fn clear(&self) -> bool {
+        fn clear_section<T>(cache: &HomeSectionCache<T>) -> bool {
+            match cache.lock() {
+                Ok(mut guard) => {
+                    let had_entries = !guard.is_empty();
+                    guard.clear();
+                    had_entries
+                }
+                Err(_) => false,
             }
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)));
         }
+
+        let cleared = [
+            clear_section(&self.featured),
+            clear_section(&self.sponsored_top),
+            clear_section(&self.sponsored_right),
+            clear_section(&self.top_categories),
+            clear_section(&self.stats),
+        ];
+        cleared.contains(&true)
+    }
+}
+
+/**
+ * cached_home_section
+ * 读取分区缓存：未过期直接返回；否则调用 load 刷新。加载失败时返回过期的旧值（若有），
+ * 第二个返回值标记该分区是否降级。
+ */
+async fn cached_home_section<T, F>(
+    cache: &HomeSectionCache<T>,
+    key: &str,
+    ttl: StdDuration,
+    load: F,
+) -> (Option<T>, bool)
+where
+    T: Clone,
+    F: std::future::Future<Output = anyhow::Result<T>>,
+{
+    let stale = match cache.lock() {
+        Ok(guard) => match guard.get(key) {
+            Some((at, value)) if at.elapsed() < ttl => return (Some(value.clone()), false),
+            Some((_, value)) => Some(value.clone()),
+            None => None,
+        },
+        Err(_) => None,
     };
 
-    HttpResponse::Ok().json(ApiResponse::success(HomeProductsPayload {
-        products,
-        next_refresh_at: next_refresh.to_rfc3339(),
+    match load.await {
+        Ok(value) => {
+            if let Ok(mut guard) = cache.lock() {
+                guard.insert(key.to_string(), (std::time::Instant::now(), value.clone()));
+            }
+            (Some(value), false)
+        }
+        Err(e) => {
+            log::warn!("home section refresh failed key={} err={:?}", key, e);
+            (stale, true)
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HomeAggregatePayload {
+    pub featured: Option<HomeProductsPayload>,
+    pub sponsored_top: Option<HomeSponsoredProductsPayload>,
+    pub sponsored_right: Option<HomeSponsoredProductsPayload>,
+    pub top_categories: Option<Vec<CategoryWithCount>>,
+    pub stats: Option<OverviewStats>,
+    pub degraded_sections: Vec<String>,
+}
+
+/**
+ * home_aggregate_language
+ * 首页聚合的语言归一化：取主语言子标签，仅保留 en / zh，其余视为不过滤语言。
+ * 缓存键只能落在这有限的几个值上，避免任意 language 参数撑大缓存。
+ */
+fn home_aggregate_language(raw: Option<&str>) -> Option<&'static str> {
+    let primary = raw?.trim().split(['-', '_']).next()?.to_ascii_lowercase();
+    match primary.as_str() {
+        "en" => Some("en"),
+        "zh" => Some("zh"),
+        _ => None,
+    }
+}
+
+/**
+ * get_home
+ * 首页聚合：并发加载精选、顶部/右侧付费位、热门分类与概览统计，一次返回；
+ * 各分区独立缓存 HOME_AGGREGATE_TTL，单个分区失败只记入 degraded_sections。
+ */
+pub async fn get_home(
+    query: web::Query<HomeModuleQuery>,
+    db: web::Data<Arc<Database>>,
+    cache: web::Data<HomeAggregateCache>,
+) -> impl Responder {
+    let db = db.get_ref().as_ref();
+    let language = home_aggregate_language(query.language.as_deref());
+    let lang_key = language.unwrap_or("");
+    let limit = query.limit.unwrap_or(6).clamp(1, 10);
+    let featured_key = format!("{}:{}", lang_key, limit);
+    let now = Utc::now();
+
+    let (featured, sponsored_top, sponsored_right, top_categories, stats) = tokio::join!(
+        cached_home_section(
+            &cache.featured,
+            &featured_key,
+            HOME_AGGREGATE_TTL,
+            home_featured_payload(db, language, Some(limit), now),
+        ),
+        cached_home_section(
+            &cache.sponsored_top,
+            lang_key,
+            HOME_AGGREGATE_TTL,
+            home_sponsored_top_payload(db, language, now),
+        ),
+        cached_home_section(
+            &cache.sponsored_right,
+            lang_key,
+            HOME_AGGREGATE_TTL,
+            home_sponsored_right_payload(db, language, now),
+        ),
+        cached_home_section(
+            &cache.top_categories,
+            "",
+            HOME_AGGREGATE_TTL,
+            db.get_top_categories_by_product_count(10),
+        ),
+        cached_home_section(
+            &cache.stats,
+            "",
+            HOME_AGGREGATE_TTL,
+            db.get_overview_stats(),
+        ),
+    );
+
+    let degraded_sections = [
+        ("featured", featured.1),
+        ("sponsored_top", sponsored_top.1),
+        ("sponsored_right", sponsored_right.1),
+        ("top_categories", top_categories.1),
+        ("stats", stats.1),
+    ]
+    .into_iter()
+    .filter(|(_, degraded)| *degraded)
+    .map(|(name, _)| name.to_string())
+    .collect();
+
+    HttpResponse::Ok().json(ApiResponse::success(HomeAggregatePayload {
+        featured: featured.0,
+        sponsored_top: sponsored_top.0,
+        sponsored_right: sponsored_right.0,
+        top_categories: top_categories.0,
+        stats: stats.0,
+        degraded_sections,
     }))
 }
 
@@ -2450,25 +5057,85 @@ fn is_rls_policy_error(err: &anyhow::Error) -> bool {
 }
 
 /**
- * validate_dev_seed_token
- * 校验开发环境 seed token，避免开放写接口被滥用。
+ * admin_actor
+ * 审计用的管理员身份：优先取 Bearer token 对应的 Supabase 邮箱（已验证）；否则记为所用 admin token 的指纹
+ * token:<sha256 前 8 位>，x-admin-actor 请求头只是自报身份，仅以 (claimed: ..) 附注，不作为身份。
  */
-fn validate_dev_seed_token(req: &HttpRequest) -> Result<(), HttpResponse> {
-    let expected = env::var("DEV_SEED_TOKEN").ok();
-    let expected = match expected {
-        Some(v) if !v.trim().is_empty() => v,
-        _ => {
-            return Err(
-                HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
-                    "DEV_SEED_TOKEN 未配置，拒绝执行 seed".to_string(),
-                )),
-            )
+async fn admin_actor(req: &HttpRequest) -> String {
+    if let Some(token) = extract_bearer_token(req) {
+        if let Some(email) = resolve_supabase_email_from_bearer(&token).await {
+            return email;
         }
-    };
-
-    let provided = req
+    }
+    let identity = req
         .headers()
-        .get("x-seed-token")
+        .get("x-admin-token")
+        .and_then(|h| h.to_str().ok())
+        .filter(|v| !v.is_empty())
+        .map(|token| {
+            let digest = <Sha256 as sha2::Digest>::digest(token.as_bytes());
+            let fingerprint: String = digest
+                .iter()
+                .take(4)
+                .map(|b| format!("{:02x}", b))
+                .collect();
+            format!("token:{}", fingerprint)
+        })
+        .unwrap_or_else(|| "admin-token".to_string());
+    let claimed = req
+        .headers()
+        .get("x-admin-actor")
+        .and_then(|h| h.to_str().ok())
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(|v| v.chars().take(120).collect::<String>());
+    match claimed {
+        Some(claimed) => format!("{} (claimed: {})", identity, claimed),
+        None => identity,
+    }
+}
+
+/**
+ * audit_admin_action
+ * 管理操作成功后写入审计日志；写入失败只记日志，不影响接口响应。
+ */
+async fn audit_admin_action(
+    db: &Database,
+    req: &HttpRequest,
+    action: &str,
+    entity_type: &str,
+    entity_id: Option<&str>,
+    summary: Option<String>,
+) {
+    let actor = admin_actor(req).await;
+    if let Err(e) = db
+        .record_admin_audit(&actor, action, entity_type, entity_id, summary.as_deref())
+        .await
+    {
+        log::warn!("Admin audit log write failed action={} err={:?}", action, e);
+    }
+}
+
+/**
+ * validate_dev_seed_token
+ * 校验开发环境 seed token，避免开放写接口被滥用。
+ */
+fn validate_dev_seed_token(req: &HttpRequest) -> Result<(), HttpResponse> {
+    let expected = env::var("DEV_SEED_TOKEN").ok();
+    let expected = match expected {
+        Some(v) if !v.trim().is_empty() => v,
+        _ => {
+            return Err(
+                HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                    "DEV_SEED_TOKEN 未配置，拒绝执行 seed".to_string(),
+                )),
+            )
+        }
+    };
+
+    let provided = req
+        .headers()
+        .get("x-seed-token")
         .and_then(|h| h.to_str().ok())
         .unwrap_or("");
 
@@ -2645,10 +5312,23 @@ async fn resolve_supabase_email_from_bearer(token: &str) -> Option<String> {
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct AdminSponsorshipRequestsQuery {
     pub status: Option<String>,
+    pub q: Option<String>,
+    pub sort: Option<String>,
     pub limit: Option<i64>,
     pub offset: Option<i64>,
 }
 
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminSponsorshipRequestItem {
+    #[serde(flatten)]
+    pub request: SponsorshipRequest,
+    pub resolved_product: Option<ResolvedProductRef>,
+}
+
+/**
+ * admin_list_sponsorship_requests
+ * 管理端：赞助申请列表，支持 status 过滤、q（邮箱/product_ref）搜索与 sort=created_at|duration_days，返回分页与总数。
+ */
 pub async fn admin_list_sponsorship_requests(
     req: HttpRequest,
     query: web::Query<AdminSponsorshipRequestsQuery>,
@@ -2658,19 +5338,51 @@ pub async fn admin_list_sponsorship_requests(
         return resp;
     }
 
-    let status = query
-        .status
-        .as_deref()
-        .map(|v| v.trim())
-        .filter(|v| !v.is_empty());
-    let limit = query.limit.unwrap_or(200);
-    let offset = query.offset.unwrap_or(0);
+    let query = query.into_inner();
+    let Some(sort) = SponsorshipRequestSort::parse(query.sort.as_deref().unwrap_or("")) else {
+        let message = if get_language_from_request(&req).starts_with("zh") {
+            "sort 仅支持 created_at 或 duration_days".to_string()
+        } else {
+            "sort must be created_at or duration_days".to_string()
+        };
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(message));
+    };
+    let defaults = SponsorshipRequestListParams::default();
+    let params = SponsorshipRequestListParams {
+        status: query.status,
+        q: query.q,
+        sort,
+        limit: query.limit.unwrap_or(defaults.limit),
+        offset: query.offset.unwrap_or(defaults.offset),
+    };
 
-    match db.list_sponsorship_requests(status, limit, offset).await {
-        Ok(list) => HttpResponse::Ok().json(ApiResponse::success(list)),
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<()>::error(format!("Database error: {:?}", e))),
-    }
+    let (requests, total) = match db.list_sponsorship_requests(params.clone()).await {
+        Ok(v) => v,
+        Err(e) => return database_error_response(&e),
+    };
+
+    let refs: Vec<String> = requests.iter().map(|r| r.product_ref.clone()).collect();
+    let resolved = match db.resolve_product_refs(&refs).await {
+        Ok(map) => map,
+        Err(e) => {
+            log::warn!("Resolving sponsorship product refs failed: {:?}", e);
+            std::collections::HashMap::new()
+        }
+    };
+    let items = requests
+        .into_iter()
+        .map(|request| AdminSponsorshipRequestItem {
+            resolved_product: resolved.get(request.product_ref.trim()).cloned(),
+            request,
+        })
+        .collect();
+
+    HttpResponse::Ok().json(ApiResponse::success(PagedList {
+        items,
+        total,
+        limit: params.limit.clamp(1, 200),
+        offset: params.offset.max(0),
+    }))
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
@@ -2711,10 +5423,7 @@ pub async fn admin_sponsorship_request_action(
             .await
         {
             Ok(v) => v,
-            Err(e) => {
-                return HttpResponse::InternalServerError()
-                    .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)))
-            }
+            Err(e) => return database_error_response(&e),
         };
         if !ok {
             return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
@@ -2725,6 +5434,15 @@ pub async fn admin_sponsorship_request_action(
                 },
             ));
         }
+        audit_admin_action(
+            &db,
+            &req,
+            "sponsorship_request.reject",
+            "sponsorship_request",
+            Some(&body.request_id.to_string()),
+            body.note.clone(),
+        )
+        .await;
         return HttpResponse::Ok().json(ApiResponse::success(OkPayload { ok: true }));
     }
 
@@ -2735,10 +5453,7 @@ pub async fn admin_sponsorship_request_action(
                 "Sponsorship request not found".to_string(),
             ))
         }
-        Err(e) => {
-            return HttpResponse::InternalServerError()
-                .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)))
-        }
+        Err(e) => return database_error_response(&e),
     };
 
     if request.status != "pending" {
@@ -2754,69 +5469,31 @@ pub async fn admin_sponsorship_request_action(
         .filter(|v| !v.is_empty())
         .unwrap_or_else(|| request.placement.clone());
     let slot_index = body.slot_index.or(request.slot_index);
-    let duration_days = body
-        .duration_days
-        .unwrap_or(request.duration_days)
-        .clamp(1, 365);
+    let duration_days = match body.duration_days {
+        Some(days) => match validate_requested_duration_days(lang, days) {
+            Ok(days) => days,
+            Err(resp) => return resp,
+        },
+        None => request
+            .duration_days
+            .clamp(1, max_sponsorship_duration_days()),
+    };
 
-    if placement != "home_top" && placement != "home_right" {
-        return HttpResponse::BadRequest()
-            .json(ApiResponse::<()>::error("Invalid placement".to_string()));
-    }
-    if placement == "home_top" {
-        match slot_index {
-            Some(0 | 1) => {}
-            _ => {
-                return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
-                    if lang.starts_with("zh") {
-                        "顶部定价位必须指定 slot_index=0(左) 或 1(右)".to_string()
-                    } else {
-                        "home_top requires slot_index 0 (left) or 1 (right)".to_string()
-                    },
-                ))
-            }
-        }
-    }
-    if placement == "home_right" {
-        match slot_index {
-            Some(0..=2) => {}
-            _ => {
-                return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
-                    if lang.starts_with("zh") {
-                        "右侧定价位必须指定 slot_index=0/1/2".to_string()
-                    } else {
-                        "home_right requires slot_index 0/1/2".to_string()
-                    },
-                ))
-            }
-        }
-    }
+    let placement = match validate_sponsorship_slot(lang, &placement, slot_index) {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
 
-    let product_id = if let Some(v) = body
-        .product_id
-        .as_deref()
-        .map(|v| v.trim())
-        .filter(|v| !v.is_empty())
+    let product_id = match resolve_sponsorship_product_id(
+        &db,
+        lang,
+        body.product_id.as_deref(),
+        &request.product_ref,
+    )
+    .await
     {
-        v.to_string()
-    } else {
-        match db.resolve_product_id_by_ref(&request.product_ref).await {
-            Ok(Some(id)) => id,
-            Ok(None) => {
-                return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
-                    if lang.starts_with("zh") {
-                        "无法根据 product_ref 自动匹配产品，请手动填写 product_id".to_string()
-                    } else {
-                        "Cannot resolve product from product_ref. Please set product_id."
-                            .to_string()
-                    },
-                ))
-            }
-            Err(e) => {
-                return HttpResponse::InternalServerError()
-                    .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)))
-            }
-        }
+        Ok(id) => id,
+        Err(resp) => return resp,
     };
 
     let sponsor_role = body
@@ -2831,8 +5508,7 @@ pub async fn admin_sponsorship_request_action(
         .upsert_developer_sponsor(&request.email, Some(&sponsor_role), sponsor_verified)
         .await
     {
-        return HttpResponse::InternalServerError()
-            .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)));
+        return database_error_response(&e);
     }
 
     let input = CreateSponsorshipGrantFromRequest {
@@ -2843,403 +5519,2030 @@ pub async fn admin_sponsorship_request_action(
         duration_days,
         amount_usd_cents: body.amount_usd_cents,
         starts_at: None,
+        sponsor_email: None,
     };
 
     match db.create_sponsorship_grant_from_request(input).await {
-        Ok(grant) => HttpResponse::Ok().json(ApiResponse::success(grant)),
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<()>::error(format!("Database error: {:?}", e))),
+        Ok(grant) => {
+            audit_admin_action(
+                &db,
+                &req,
+                "sponsorship_request.process",
+                "sponsorship_request",
+                Some(&body.request_id.to_string()),
+                Some(format!(
+                    "grant_id={} product_id={} placement={}",
+                    grant.id, grant.product_id, grant.placement
+                )),
+            )
+            .await;
+            HttpResponse::Ok().json(ApiResponse::success(grant))
+        }
+        Err(e) => database_error_response(&e),
     }
 }
 
-#[derive(Debug, Deserialize, ToSchema)]
-pub struct AdminSponsorshipGrantsQuery {
-    pub placement: Option<String>,
-    pub limit: Option<i64>,
-    pub offset: Option<i64>,
+/**
+ * validate_sponsorship_slot
+ * 校验展示位与槽位：展示位必须是已知的 Placement，slot_index 必须位于该展示位容量 0..capacity 之内。
+ */
+fn validate_sponsorship_slot(
+    lang: &str,
+    placement: &str,
+    slot_index: Option<i32>,
+) -> Result<Placement, HttpResponse> {
+    let placement = placement.parse::<Placement>().map_err(|e| {
+        HttpResponse::BadRequest().json(ApiResponse::<()>::error(e.localized_message(lang)))
+    })?;
+    crate::db::check_sponsorship_slot(placement, slot_index).map_err(|e| {
+        HttpResponse::BadRequest().json(ApiResponse::<()>::error(e.localized_message(lang)))
+    })?;
+    Ok(placement)
 }
 
-pub async fn admin_list_sponsorship_grants(
-    req: HttpRequest,
-    query: web::Query<AdminSponsorshipGrantsQuery>,
-    db: web::Data<Arc<Database>>,
-) -> impl Responder {
-    if let Err(resp) = validate_admin_token(&req) {
-        return resp;
-    }
-
-    let placement = query
-        .placement
-        .as_deref()
-        .map(|v| v.trim())
-        .filter(|v| !v.is_empty());
-    let limit = query.limit.unwrap_or(200);
-    let offset = query.offset.unwrap_or(0);
-
-    match db.list_sponsorship_grants(placement, limit, offset).await {
-        Ok(list) => HttpResponse::Ok().json(ApiResponse::success(list)),
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<()>::error(format!("Database error: {:?}", e))),
-    }
+/**
+ * field_validation_failed
+ * 单字段校验失败的 400 响应（VALIDATION_FAILED，fields 中指明字段）。
+ */
+fn field_validation_failed(field: &str, message: String) -> HttpResponse {
+    HttpResponse::BadRequest().json(ApiResponse::<()> {
+        success: false,
+        data: None,
+        message: Some(message.clone()),
+        error: Some(ApiError {
+            code: "VALIDATION_FAILED".to_string(),
+            trace_id: new_trace_id(),
+            degraded: false,
+            hint: None,
+            detail: None,
+            fields: Some(vec![FieldError {
+                field: field.to_string(),
+                message,
+            }]),
+        }),
+    })
 }
 
-#[derive(Debug, Deserialize, ToSchema)]
-pub struct AdminDeleteSponsorshipGrantQuery {
-    pub id: i64,
+/**
+ * validate_requested_months
+ * 校验赞助月数位于 1..=MAX_SPONSORSHIP_MONTHS 之内，超出范围返回 400（VALIDATION_FAILED），不静默夹取。
+ */
+fn validate_requested_months(lang: &str, months: i32) -> Result<i32, HttpResponse> {
+    check_requested_months(months)
+        .map_err(|e| field_validation_failed("duration_months", e.localized_message(lang)))
 }
 
-pub async fn admin_delete_sponsorship_grant(
-    req: HttpRequest,
-    query: web::Query<AdminDeleteSponsorshipGrantQuery>,
-    db: web::Data<Arc<Database>>,
-) -> impl Responder {
-    if let Err(resp) = validate_admin_token(&req) {
-        return resp;
-    }
-
-    match db.delete_sponsorship_grant(query.id).await {
-        Ok(ok) => HttpResponse::Ok().json(ApiResponse::success(OkPayload { ok })),
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<()>::error(format!("Database error: {:?}", e))),
+/**
+ * validate_requested_duration_days
+ * 校验赞助天数位于 1..=max_sponsorship_duration_days() 之内，超出范围同样返回 400。
+ */
+fn validate_requested_duration_days(lang: &str, days: i32) -> Result<i32, HttpResponse> {
+    let max = max_sponsorship_duration_days();
+    if (1..=max).contains(&days) {
+        return Ok(days);
     }
+    let message = if lang.starts_with("zh") {
+        format!("展示天数必须为 1~{} 天（当前 {}）", max, days)
+    } else {
+        format!(
+            "Sponsorship duration must be between 1 and {} days (got {})",
+            max, days
+        )
+    };
+    Err(field_validation_failed("duration_days", message))
 }
 
 /**
- * get_pricing_plans
- * 前台：读取可用的定价方案（仅 active）。
+ * resolve_sponsorship_product_id
+ * 优先使用管理员指定的 product_id，否则根据申请中的 product_ref 自动匹配产品。
  */
-pub async fn get_pricing_plans(db: web::Data<Arc<Database>>) -> impl Responder {
-    match db.list_pricing_plans(false).await {
-        Ok(list) => HttpResponse::Ok().json(ApiResponse::success(list)),
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<()>::error(format!("Database error: {:?}", e))),
+async fn resolve_sponsorship_product_id(
+    db: &Database,
+    lang: &str,
+    explicit_product_id: Option<&str>,
+    product_ref: &str,
+) -> Result<String, HttpResponse> {
+    if let Some(v) = explicit_product_id
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+    {
+        return Ok(v.to_string());
+    }
+    match db.resolve_product_id_by_ref(product_ref).await {
+        Ok(Some(id)) => Ok(id),
+        Ok(None) => Err(HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+            if lang.starts_with("zh") {
+                "无法根据 product_ref 自动匹配产品，请手动填写 product_id".to_string()
+            } else {
+                "Cannot resolve product from product_ref. Please set product_id.".to_string()
+            },
+        ))),
+        Err(e) => Err(database_error_response(&e)),
     }
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
-pub struct AdminPricingPlansQuery {
-    pub include_inactive: Option<bool>,
+pub struct AdminProductCandidatesQuery {
+    pub limit: Option<i64>,
 }
 
 /**
- * admin_list_pricing_plans
- * 管理端：读取定价方案列表（可选包含 inactive）。
+ * admin_list_sponsorship_request_candidates
+ * 管理端：列出赞助申请 product_ref 可能对应的产品（按匹配程度排序），用于歧义时手动选择 product_id。
  */
-pub async fn admin_list_pricing_plans(
+pub async fn admin_list_sponsorship_request_candidates(
     req: HttpRequest,
-    query: web::Query<AdminPricingPlansQuery>,
+    path: web::Path<i64>,
+    query: web::Query<AdminProductCandidatesQuery>,
     db: web::Data<Arc<Database>>,
 ) -> impl Responder {
     if let Err(resp) = validate_admin_token(&req) {
         return resp;
     }
 
-    let include_inactive = query.include_inactive.unwrap_or(true);
-    match db.list_pricing_plans(include_inactive).await {
-        Ok(list) => HttpResponse::Ok().json(ApiResponse::success(list)),
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<()>::error(format!("Database error: {:?}", e))),
-    }
-}
-
-/**
- * admin_upsert_pricing_plan
- * 管理端：创建或更新定价方案。
- */
-pub async fn admin_upsert_pricing_plan(
-    req: HttpRequest,
-    body: web::Json<UpsertPricingPlanRequest>,
-    db: web::Data<Arc<Database>>,
-) -> impl Responder {
-    if let Err(resp) = validate_admin_token(&req) {
-        return resp;
-    }
+    let request_id = path.into_inner();
+    let request = match db.get_sponsorship_request_by_id(request_id).await {
+        Ok(Some(v)) => v,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(ApiResponse::<()>::error(
+                "Sponsorship request not found".to_string(),
+            ))
+        }
+        Err(e) => return database_error_response(&e),
+    };
 
-    match db.upsert_pricing_plan(body.into_inner()).await {
-        Ok(plan) => HttpResponse::Ok().json(ApiResponse::success(plan)),
-        Err(e) => HttpResponse::BadRequest()
-            .json(ApiResponse::<()>::error(format!("Invalid input: {:?}", e))),
+    let limit = query.limit.unwrap_or(10).clamp(1, 50);
+    match db
+        .resolve_product_candidates(&request.product_ref, limit)
+        .await
+    {
+        Ok(items) => HttpResponse::Ok().json(ApiResponse::success(items)),
+        Err(e) => database_error_response(&e),
     }
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
-pub struct AdminPricingPlanPath {
-    pub id: String,
+pub struct AdminApproveSponsorshipRequestBody {
+    pub starts_at: Option<chrono::DateTime<Utc>>,
+    pub amount_usd_cents: Option<i32>,
 }
 
 /**
- * admin_delete_pricing_plan
- * 管理端：删除定价方案。
+ * existing_grant_for_processed_request
+ * 申请已处理时返回其关联的授权（重复审批视为无操作）；未关联授权时返回 None。
  */
-pub async fn admin_delete_pricing_plan(
-    req: HttpRequest,
-    path: web::Path<AdminPricingPlanPath>,
-    db: web::Data<Arc<Database>>,
-) -> impl Responder {
-    if let Err(resp) = validate_admin_token(&req) {
-        return resp;
-    }
-
-    let id = path.into_inner().id;
-    match db.delete_pricing_plan(&id).await {
-        Ok(ok) => HttpResponse::Ok().json(ApiResponse::success(OkPayload { ok })),
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<()>::error(format!("Database error: {:?}", e))),
+async fn existing_grant_for_processed_request(
+    db: &Database,
+    request: &SponsorshipRequest,
+) -> anyhow::Result<Option<SponsorshipGrant>> {
+    match (request.status.as_str(), request.processed_grant_id) {
+        ("processed", Some(grant_id)) => db.get_sponsorship_grant_by_id(grant_id).await,
+        _ => Ok(None),
     }
 }
 
-#[derive(Debug, Deserialize, ToSchema)]
-pub struct AdminSponsorshipOrdersQuery {
-    pub status: Option<String>,
-    pub limit: Option<i64>,
-    pub offset: Option<i64>,
-}
-
 /**
- * admin_list_sponsorship_orders
- * 管理端：查询支付订单（sponsorship_orders）。
+ * admin_approve_sponsorship_request
+ * 管理端：一步审批赞助申请并创建授权（可覆盖 starts_at/amount_usd_cents）；
+ * 已处理的申请再次审批时直接返回已有授权，已拒绝的申请返回 409。
  */
-pub async fn admin_list_sponsorship_orders(
+pub async fn admin_approve_sponsorship_request(
     req: HttpRequest,
-    query: web::Query<AdminSponsorshipOrdersQuery>,
+    path: web::Path<i64>,
+    body: Option<web::Json<AdminApproveSponsorshipRequestBody>>,
     db: web::Data<Arc<Database>>,
 ) -> impl Responder {
     if let Err(resp) = validate_admin_token(&req) {
         return resp;
     }
 
-    let status = query
-        .status
-        .as_deref()
-        .map(|v| v.trim())
-        .filter(|v| !v.is_empty());
-    let limit = query.limit.unwrap_or(200);
-    let offset = query.offset.unwrap_or(0);
+    let lang = get_language_from_request(&req);
+    let request_id = path.into_inner();
+    let overrides = body.map(|b| b.into_inner());
+    let not_pending = || {
+        HttpResponse::Conflict().json(ApiResponse::<()>::error(if lang.starts_with("zh") {
+            "该赞助申请已被拒绝或处理，无法审批".to_string()
+        } else {
+            "Sponsorship request was already rejected or processed".to_string()
+        }))
+    };
 
-    match db.list_sponsorship_orders(status, limit, offset).await {
-        Ok(list) => HttpResponse::Ok().json(ApiResponse::success(list)),
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<()>::error(format!("Database error: {:?}", e))),
-    }
+    let request = match db.get_sponsorship_request_by_id(request_id).await {
+        Ok(Some(v)) => v,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(ApiResponse::<()>::error(
+                "Sponsorship request not found".to_string(),
+            ))
+        }
+        Err(e) => return database_error_response(&e),
+    };
+
+    if request.status != "pending" {
+        return match existing_grant_for_processed_request(&db, &request).await {
+            Ok(Some(grant)) => HttpResponse::Ok().json(ApiResponse::success(grant)),
+            Ok(None) => not_pending(),
+            Err(e) => database_error_response(&e),
+        };
+    }
+
+    let placement = match validate_sponsorship_slot(lang, &request.placement, request.slot_index) {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
+    let product_id =
+        match resolve_sponsorship_product_id(&db, lang, None, &request.product_ref).await {
+            Ok(id) => id,
+            Err(resp) => return resp,
+        };
+
+    let input = CreateSponsorshipGrantFromRequest {
+        request_id: request.id,
+        product_id,
+        placement,
+        slot_index: request.slot_index,
+        duration_days: request
+            .duration_days
+            .clamp(1, max_sponsorship_duration_days()),
+        amount_usd_cents: overrides.as_ref().and_then(|o| o.amount_usd_cents),
+        starts_at: overrides.as_ref().and_then(|o| o.starts_at),
+        sponsor_email: Some(request.email.clone()),
+    };
+
+    match db.create_sponsorship_grant_from_request(input).await {
+        Ok(grant) => {
+            audit_admin_action(
+                &db,
+                &req,
+                "sponsorship_request.approve",
+                "sponsorship_request",
+                Some(&request_id.to_string()),
+                Some(format!(
+                    "grant_id={} product_id={} placement={}",
+                    grant.id, grant.product_id, grant.placement
+                )),
+            )
+            .await;
+            HttpResponse::Ok().json(ApiResponse::success(grant))
+        }
+        Err(e)
+            if e.downcast_ref::<SponsorshipRequestNotPendingError>()
+                .is_some() =>
+        {
+            // 并发审批：另一请求已处理，返回其授权。
+            let current = db.get_sponsorship_request_by_id(request_id).await;
+            match current {
+                Ok(Some(request)) => {
+                    match existing_grant_for_processed_request(&db, &request).await {
+                        Ok(Some(grant)) => HttpResponse::Ok().json(ApiResponse::success(grant)),
+                        Ok(None) => not_pending(),
+                        Err(e) => database_error_response(&e),
+                    }
+                }
+                Ok(None) => not_pending(),
+                Err(e) => database_error_response(&e),
+            }
+        }
+        Err(e) => database_error_response(&e),
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AdminSponsorshipGrantsQuery {
+    pub placement: Option<String>,
+    pub active: Option<bool>,
+    pub product_id: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/**
+ * admin_list_sponsorship_grants
+ * 管理端：授权列表，支持 placement / product_id 过滤与 active=true|false（生效中/已过期），返回分页与总数。
+ */
+pub async fn admin_list_sponsorship_grants(
+    req: HttpRequest,
+    query: web::Query<AdminSponsorshipGrantsQuery>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    let query = query.into_inner();
+    if query
+        .product_id
+        .as_deref()
+        .map(|v| v.trim())
+        .is_some_and(|v| !v.is_empty() && Uuid::parse_str(v).is_err())
+    {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<()>::error("Invalid product_id".to_string()));
+    }
+    let defaults = SponsorshipGrantListParams::default();
+    let params = SponsorshipGrantListParams {
+        placement: query.placement,
+        active: query.active,
+        product_id: query.product_id,
+        limit: query.limit.unwrap_or(defaults.limit),
+        offset: query.offset.unwrap_or(defaults.offset),
+    };
+
+    match db.list_sponsorship_grants(params.clone()).await {
+        Ok((items, total)) => {
+            let limit = params.limit.clamp(1, 200);
+            let offset = params.offset.max(0);
+            let mut resp = HttpResponse::Ok();
+            insert_pagination_headers(&mut resp, Some(limit), Some(offset), Some(total));
+            resp.json(ApiResponse::success(PagedList {
+                items,
+                total,
+                limit,
+                offset,
+            }))
+        }
+        Err(e) => database_error_response(&e),
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AdminDeleteSponsorshipGrantQuery {
+    pub id: i64,
+}
+
+pub async fn admin_delete_sponsorship_grant(
+    req: HttpRequest,
+    query: web::Query<AdminDeleteSponsorshipGrantQuery>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    match db.delete_sponsorship_grant(query.id).await {
+        Ok(ok) => {
+            if ok {
+                audit_admin_action(
+                    &db,
+                    &req,
+                    "sponsorship_grant.delete",
+                    "sponsorship_grant",
+                    Some(&query.id.to_string()),
+                    None,
+                )
+                .await;
+            }
+            HttpResponse::Ok().json(ApiResponse::success(OkPayload { ok }))
+        }
+        Err(e) => database_error_response(&e),
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PricingPlansQuery {
+    pub group_benefits: Option<bool>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PricingPlanWithBenefitGroups {
+    #[serde(flatten)]
+    pub plan: PricingPlan,
+    pub benefit_groups: PricingPlanBenefitGroups,
+}
+
+/**
+ * get_pricing_plans
+ * 前台：读取可用的定价方案（仅 active），权益按 sort_order 排序；?group_benefits=true 时额外返回可用/不可用分组。
+ */
+pub async fn get_pricing_plans(
+    query: web::Query<PricingPlansQuery>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    match db.list_pricing_plans(false).await {
+        Ok(list) if query.group_benefits.unwrap_or(false) => {
+            let grouped: Vec<PricingPlanWithBenefitGroups> = list
+                .into_iter()
+                .map(|plan| PricingPlanWithBenefitGroups {
+                    benefit_groups: crate::db::group_pricing_plan_benefits(&plan.benefits),
+                    plan,
+                })
+                .collect();
+            HttpResponse::Ok().json(ApiResponse::success(grouped))
+        }
+        Ok(list) => HttpResponse::Ok().json(ApiResponse::success(list)),
+        Err(e) => database_error_response(&e),
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PricingPlanByKeyQuery {
+    pub include_inactive: Option<bool>,
+}
+
+/**
+ * get_pricing_plan_by_key
+ * 前台：按 plan_key 读取单个定价方案（含权益），供直达结算等单方案页面使用；
+ * 未启用或不存在返回 404，管理员携带 ?include_inactive=true 时可读取未启用方案。
+ */
+pub async fn get_pricing_plan_by_key(
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<PricingPlanByKeyQuery>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    let include_inactive = query.include_inactive.unwrap_or(false);
+    if include_inactive {
+        if let Err(resp) = validate_admin_token(&req) {
+            return resp;
+        }
+    }
+
+    let lang = get_language_from_request(&req);
+    let not_found = || {
+        HttpResponse::NotFound().json(ApiResponse::<()>::error(if lang.starts_with("zh") {
+            "未找到该定价方案".to_string()
+        } else {
+            "Pricing plan not found".to_string()
+        }))
+    };
+
+    match db.get_pricing_plan_by_key(&path.into_inner()).await {
+        Ok(Some(plan)) if plan.is_active || include_inactive => {
+            HttpResponse::Ok().json(ApiResponse::success(plan))
+        }
+        Ok(_) => not_found(),
+        Err(e) => database_error_response(&e),
+    }
+}
+
+/**
+ * get_placement_pricing
+ * 前台：按展示位返回默认定价方案 + 最早可用开始时间 + 当前生效折扣，供定价页一次渲染。
+ */
+pub async fn get_placement_pricing(
+    req: HttpRequest,
+    path: web::Path<String>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    let lang = get_language_from_request(&req);
+    let placement = path.into_inner().trim().to_string();
+    let not_found = || {
+        HttpResponse::NotFound().json(ApiResponse::<()>::error(if lang.starts_with("zh") {
+            "未找到该展示位的定价方案".to_string()
+        } else {
+            "No pricing plan for this placement".to_string()
+        }))
+    };
+
+    let Ok(placement) = placement.parse::<Placement>() else {
+        return not_found();
+    };
+
+    match db.get_placement_pricing(placement).await {
+        Ok(Some(pricing)) => HttpResponse::Ok().json(ApiResponse::success(pricing)),
+        Ok(None) => not_found(),
+        Err(e) => database_error_response(&e),
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AdminPricingPlansQuery {
+    pub include_inactive: Option<bool>,
+}
+
+/**
+ * admin_list_pricing_plans
+ * 管理端：读取定价方案列表（可选包含 inactive）。
+ */
+pub async fn admin_list_pricing_plans(
+    req: HttpRequest,
+    query: web::Query<AdminPricingPlansQuery>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    let include_inactive = query.include_inactive.unwrap_or(true);
+    match db.list_pricing_plans(include_inactive).await {
+        Ok(list) => HttpResponse::Ok().json(ApiResponse::success(list)),
+        Err(e) => database_error_response(&e),
+    }
+}
+
+/**
+ * admin_upsert_pricing_plan
+ * 管理端：创建或更新定价方案。
+ */
+pub async fn admin_upsert_pricing_plan(
+    req: HttpRequest,
+    body: web::Json<UpsertPricingPlanRequest>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    match db.upsert_pricing_plan(body.into_inner()).await {
+        Ok(plan) => {
+            audit_admin_action(
+                &db,
+                &req,
+                "pricing_plan.upsert",
+                "pricing_plan",
+                Some(&plan.id),
+                Some(format!(
+                    "plan_key={} active={}",
+                    plan.plan_key, plan.is_active
+                )),
+            )
+            .await;
+            HttpResponse::Ok().json(ApiResponse::success(plan))
+        }
+        Err(e) => {
+            if e.downcast_ref::<PostgresNotConfiguredError>().is_some() {
+                return database_error_response(&e);
+            }
+            match e.downcast_ref::<PricingPlanValidationError>() {
+                Some(invalid) => pricing_plan_validation_response(&req, invalid),
+                None => HttpResponse::BadRequest()
+                    .json(ApiResponse::<()>::error(format!("Invalid input: {:?}", e))),
+            }
+        }
+    }
+}
+
+/**
+ * admin_reorder_pricing_plans
+ * 管理端：按传入的 id 顺序批量重排定价方案。
+ */
+pub async fn admin_reorder_pricing_plans(
+    req: HttpRequest,
+    body: web::Json<ReorderPricingPlansRequest>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    match db.reorder_pricing_plans(body.into_inner()).await {
+        Ok(list) => {
+            let order: Vec<&str> = list.iter().map(|p| p.id.as_str()).collect();
+            audit_admin_action(
+                &db,
+                &req,
+                "pricing_plan.reorder",
+                "pricing_plan",
+                None,
+                Some(format!("order={}", order.join(","))),
+            )
+            .await;
+            HttpResponse::Ok().json(ApiResponse::success(list))
+        }
+        Err(e) => match e.downcast_ref::<PricingPlanValidationError>() {
+            Some(invalid) => pricing_plan_validation_response(&req, invalid),
+            None => database_error_response(&e),
+        },
+    }
+}
+
+/**
+ * pricing_plan_validation_response
+ * 将定价方案字段校验错误转换为 400 响应，逐项字段放在 error.fields 中。
+ */
+fn pricing_plan_validation_response(
+    req: &HttpRequest,
+    invalid: &PricingPlanValidationError,
+) -> HttpResponse {
+    let message = if get_language_from_request(req).starts_with("zh") {
+        "定价方案字段校验失败。".to_string()
+    } else {
+        "Pricing plan validation failed.".to_string()
+    };
+    HttpResponse::BadRequest().json(ApiResponse::<()> {
+        success: false,
+        data: None,
+        message: Some(message),
+        error: Some(ApiError {
+            code: "VALIDATION_FAILED".to_string(),
+            trace_id: new_trace_id(),
+            degraded: false,
+            hint: None,
+            detail: None,
+            fields: Some(invalid.fields.clone()),
+        }),
+    })
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AdminPricingPlanPath {
+    pub id: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AdminDeletePricingPlanQuery {
+    pub force: Option<bool>,
+}
+
+/**
+ * admin_delete_pricing_plan
+ * 管理端：停用定价方案；?force=true 时硬删除（仍被订单引用则返回 409）。
+ */
+pub async fn admin_delete_pricing_plan(
+    req: HttpRequest,
+    path: web::Path<AdminPricingPlanPath>,
+    query: web::Query<AdminDeletePricingPlanQuery>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    let id = path.into_inner().id;
+    let force = query.force.unwrap_or(false);
+    match db.delete_pricing_plan(&id, force).await {
+        Ok(ok) => {
+            if ok {
+                audit_admin_action(
+                    &db,
+                    &req,
+                    "pricing_plan.delete",
+                    "pricing_plan",
+                    Some(&id),
+                    Some(format!("force={}", force)),
+                )
+                .await;
+            }
+            HttpResponse::Ok().json(ApiResponse::success(OkPayload { ok }))
+        }
+        Err(e) if e.downcast_ref::<PricingPlanInUseError>().is_some() => {
+            let message = if get_language_from_request(&req).starts_with("zh") {
+                "该定价方案仍被赞助订单引用，无法删除，请改为停用。".to_string()
+            } else {
+                "This pricing plan is referenced by sponsorship orders and cannot be deleted. Deactivate it instead.".to_string()
+            };
+            HttpResponse::Conflict().json(ApiResponse::<()>::error(message))
+        }
+        Err(e) => database_error_response(&e),
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AdminSponsorshipOrdersQuery {
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    /// 为 true 时以流式 JSON 数组返回，不受单页 200 条上限约束。
+    pub stream: Option<bool>,
+}
+
+/**
+ * json_array_response_stream
+ * 将逐行到达的记录编码为与 `ApiResponse::success(Vec<T>)` 相同结构的 JSON 分块流；
+ * 中途出错时以错误结束流（连接被中断），避免客户端拿到看似完整的截断数组。
+ */
+fn json_array_response_stream<T: Serialize + Send + 'static>(
+    rx: tokio::sync::mpsc::Receiver<anyhow::Result<T>>,
+) -> impl futures_util::Stream<Item = Result<web::Bytes, std::io::Error>> {
+    futures_util::stream::unfold((rx, true, false), |(mut rx, first, done)| async move {
+        if done {
+            return None;
+        }
+        match rx.recv().await {
+            Some(Ok(item)) => {
+                let mut chunk = if first {
+                    br#"{"success":true,"data":["#.to_vec()
+                } else {
+                    b",".to_vec()
+                };
+                if let Err(e) = serde_json::to_writer(&mut chunk, &item) {
+                    return Some((Err(std::io::Error::other(e)), (rx, false, true)));
+                }
+                Some((Ok(web::Bytes::from(chunk)), (rx, false, false)))
+            }
+            Some(Err(e)) => {
+                log::error!("Streaming response aborted: {:?}", e);
+                Some((Err(std::io::Error::other(e.to_string())), (rx, first, true)))
+            }
+            None => {
+                let tail: &'static [u8] = if first {
+                    br#"{"success":true,"data":[],"message":null}"#
+                } else {
+                    br#"],"message":null}"#
+                };
+                Some((Ok(web::Bytes::from_static(tail)), (rx, first, true)))
+            }
+        }
+    })
+}
+
+/**
+ * admin_list_sponsorship_orders
+ * 管理端：查询支付订单（sponsorship_orders）。
+ */
+pub async fn admin_list_sponsorship_orders(
+    req: HttpRequest,
+    query: web::Query<AdminSponsorshipOrdersQuery>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    let status = query
+        .status
+        .as_deref()
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty());
+    let offset = query.offset.unwrap_or(0);
+
+    if query.stream.unwrap_or(false) {
+        return match db
+            .stream_sponsorship_orders(status, query.limit, offset)
+            .await
+        {
+            Ok(rx) => HttpResponse::Ok()
+                .content_type("application/json")
+                .streaming(json_array_response_stream(rx)),
+            Err(e) => database_error_response(&e),
+        };
+    }
+
+    let limit = query.limit.unwrap_or(200);
+    match db.list_sponsorship_orders(status, limit, offset).await {
+        Ok(list) => HttpResponse::Ok().json(ApiResponse::success(list)),
+        Err(e) => database_error_response(&e),
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AdminSponsorshipOrderActionBody {
+    pub action: String,
+    pub order_id: String,
+    pub provider_order_id: Option<String>,
+    pub paid_months: Option<i32>,
+    pub amount_usd_cents: Option<i32>,
+}
+
+pub async fn admin_sponsorship_order_action(
+    req: HttpRequest,
+    body: web::Json<AdminSponsorshipOrderActionBody>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    let input = body.into_inner();
+    let action = input.action.trim().to_ascii_lowercase();
+    if action != "mark_paid" {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<()>::error("Invalid action".to_string()));
+    }
+
+    let order_id = input.order_id.trim().to_string();
+    if order_id.is_empty() {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<()>::error("Missing order_id".to_string()));
+    }
+
+    match db
+        .admin_mark_sponsorship_order_paid(
+            &order_id,
+            input.provider_order_id.as_deref(),
+            input.amount_usd_cents,
+            input.paid_months,
+        )
+        .await
+    {
+        Ok(grant) => {
+            audit_admin_action(
+                &db,
+                &req,
+                "sponsorship_order.mark_paid",
+                "sponsorship_order",
+                Some(&order_id),
+                Some(format!(
+                    "grant_id={} amount_usd_cents={:?} paid_months={:?}",
+                    grant.id, input.amount_usd_cents, input.paid_months
+                )),
+            )
+            .await;
+            let db_for_email = db.get_ref().clone();
+            let grant_for_email = grant.clone();
+            tokio::spawn(async move {
+                if let Err(e) = db_for_email
+                    .send_sponsorship_order_receipt(&order_id, &grant_for_email)
+                    .await
+                {
+                    log::warn!("Sponsorship receipt email failed for {}: {:?}", order_id, e);
+                }
+            });
+            HttpResponse::Ok().json(ApiResponse::success(grant))
+        }
+        Err(e) => {
+            if e.downcast_ref::<PostgresNotConfiguredError>().is_some() {
+                return database_error_response(&e);
+            }
+            HttpResponse::BadRequest()
+                .json(ApiResponse::<()>::error(format!("Invalid input: {:?}", e)))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AdminManualSponsorshipGrantBody {
+    pub user_email: String,
+    pub product_id: String,
+    pub placement: String,
+    pub slot_index: Option<i32>,
+    pub duration_months: i32,
+    pub amount_usd_cents: i32,
+    pub external_reference: String,
+}
+
+/**
+ * admin_create_manual_sponsorship_grant
+ * 管理端：线下付款（银行转账/发票）直接创建已付订单与 grant，外部流水号记入 provider_order_id。
+ */
+pub async fn admin_create_manual_sponsorship_grant(
+    req: HttpRequest,
+    body: web::Json<AdminManualSponsorshipGrantBody>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    let lang = get_language_from_request(&req);
+    let input = body.into_inner();
+    let user_email = input.user_email.trim().to_ascii_lowercase();
+    let product_id = input.product_id.trim().to_string();
+    let external_reference = input.external_reference.trim().to_string();
+    if !is_valid_email_basic(&user_email)
+        || product_id.is_empty()
+        || external_reference.is_empty()
+        || input.amount_usd_cents < 0
+    {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+            if lang.starts_with("zh") {
+                "缺少必填字段（邮箱 / 产品 / 外部流水号 / 金额）".to_string()
+            } else {
+                "Missing required fields (email / product / external reference / amount)"
+                    .to_string()
+            },
+        ));
+    }
+
+    let placement = match validate_sponsorship_slot(lang, &input.placement, input.slot_index) {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
+    let months = match validate_requested_months(lang, input.duration_months) {
+        Ok(m) => m,
+        Err(resp) => return resp,
+    };
+
+    let manual = CreateManualSponsorshipGrant {
+        user_email,
+        product_id,
+        placement,
+        slot_index: input.slot_index,
+        months,
+        amount_usd_cents: input.amount_usd_cents,
+        external_reference,
+    };
+
+    match db.create_manual_sponsorship_grant(&manual).await {
+        Ok(created) => {
+            audit_admin_action(
+                &db,
+                &req,
+                "sponsorship_grant.manual",
+                "sponsorship_order",
+                Some(&created.order.id),
+                Some(format!(
+                    "grant_id={} external_reference={} amount_usd_cents={}",
+                    created.grant.id, manual.external_reference, manual.amount_usd_cents
+                )),
+            )
+            .await;
+            let db_for_email = db.get_ref().clone();
+            let order_id = created.order.id.clone();
+            let grant_for_email = created.grant.clone();
+            tokio::spawn(async move {
+                if let Err(e) = db_for_email
+                    .send_sponsorship_order_receipt(&order_id, &grant_for_email)
+                    .await
+                {
+                    log::warn!("Sponsorship receipt email failed for {}: {:?}", order_id, e);
+                }
+            });
+            HttpResponse::Ok().json(ApiResponse::success(created))
+        }
+        Err(e) => {
+            if let Some(violation) = constraint_violation(&e) {
+                return constraint_violation_response(&req, violation, &e);
+            }
+            database_error_response(&e)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AdminPaymentsSummaryQuery {
+    pub days: Option<i64>,
+}
+
+/**
+ * admin_get_payments_summary
+ * 管理端：支付汇总统计（默认近 30 天）。
+ */
+pub async fn admin_get_payments_summary(
+    req: HttpRequest,
+    query: web::Query<AdminPaymentsSummaryQuery>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    let days = query.days.unwrap_or(30);
+    match db.get_payments_summary(days).await {
+        Ok(summary) => HttpResponse::Ok().json(ApiResponse::success(summary)),
+        Err(e) => database_error_response(&e),
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AdminNewsletterStatsQuery {
+    pub weeks: Option<i64>,
+}
+
+/**
+ * admin_get_newsletter_stats
+ * 管理端：周报打开/点击统计（默认最近 12 周）。
+ */
+pub async fn admin_get_newsletter_stats(
+    req: HttpRequest,
+    query: web::Query<AdminNewsletterStatsQuery>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    match db
+        .get_newsletter_tracking_stats(query.weeks.unwrap_or(12))
+        .await
+    {
+        Ok(stats) => HttpResponse::Ok().json(ApiResponse::success(stats)),
+        Err(e) => database_error_response(&e),
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AdminSendNewsletterQuery {
+    pub force: Option<bool>,
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminSendNewsletterResult {
+    pub dry_run: bool,
+    pub recipient_count: usize,
+    pub sent: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sample: Option<NewsletterSamplePayload>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NewsletterSamplePayload {
+    pub to: String,
+    pub subject: String,
+    pub html: Option<String>,
+    pub text: String,
+}
+
+/**
+ * admin_send_newsletter
+ * 管理端：立即执行一次周报发送（跳过周四时间窗）；dry_run=true 只渲染并统计收件人，返回第一封样例。
+ * 生产构建下需显式 force=true，避免误发。
+ */
+pub async fn admin_send_newsletter(
+    req: HttpRequest,
+    query: web::Query<AdminSendNewsletterQuery>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    let is_production = !cfg!(debug_assertions);
+    if is_production && !query.force.unwrap_or(false) {
+        let is_zh = get_language_from_request(&req).starts_with("zh");
+        let msg = if is_zh {
+            "生产环境手动发送周报需要 force=true"
+        } else {
+            "Manual newsletter send in production requires force=true"
+        };
+        return HttpResponse::Forbidden().json(ApiResponse::<()>::error(msg.to_string()));
+    }
+
+    let dry_run = query.dry_run.unwrap_or(false);
+    match db
+        .run_weekly_newsletter(NewsletterRunOptions {
+            ignore_schedule: true,
+            dry_run,
+        })
+        .await
+    {
+        Ok(report) => {
+            if !dry_run {
+                audit_admin_action(
+                    &db,
+                    &req,
+                    "newsletter.send",
+                    "newsletter",
+                    None,
+                    Some(format!(
+                        "recipients={} sent={}",
+                        report.recipients, report.sent
+                    )),
+                )
+                .await;
+            }
+            HttpResponse::Ok().json(ApiResponse::success(AdminSendNewsletterResult {
+                dry_run,
+                recipient_count: report.recipients,
+                sent: report.sent,
+                sample: report.sample.map(|s| NewsletterSamplePayload {
+                    to: s.to,
+                    subject: s.subject,
+                    html: s.html,
+                    text: s.text,
+                }),
+            }))
+        }
+        Err(e) => database_error_response(&e),
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AdminUpsertCategoriesRequest {
+    pub categories: Vec<Category>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminUpsertCategoriesResult {
+    pub upserted: usize,
+}
+
+pub async fn admin_get_categories(
+    req: HttpRequest,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    match db.get_categories().await {
+        Ok(categories) => HttpResponse::Ok().json(ApiResponse::success(categories)),
+        Err(e) => database_error_response(&e),
+    }
+}
+
+pub async fn admin_upsert_categories(
+    req: HttpRequest,
+    body: web::Json<AdminUpsertCategoriesRequest>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    let categories = body.into_inner().categories;
+    let category_ids: Vec<String> = categories.iter().map(|c| c.id.clone()).collect();
+    match db.upsert_categories(categories).await {
+        Ok(upserted) => {
+            db.invalidate_caches();
+            audit_admin_action(
+                &db,
+                &req,
+                "category.upsert",
+                "category",
+                None,
+                Some(format!("ids={}", category_ids.join(","))),
+            )
+            .await;
+            HttpResponse::Ok().json(ApiResponse::success(AdminUpsertCategoriesResult {
+                upserted,
+            }))
+        }
+        Err(e) => database_error_response(&e),
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AdminCategoryPath {
+    pub id: String,
+}
+
+pub async fn admin_delete_category(
+    req: HttpRequest,
+    path: web::Path<AdminCategoryPath>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    let id = path.into_inner().id;
+    match db.delete_category(&id).await {
+        Ok(ok) => {
+            if ok {
+                audit_admin_action(&db, &req, "category.delete", "category", Some(&id), None).await;
+            }
+            HttpResponse::Ok().json(ApiResponse::success(OkPayload { ok }))
+        }
+        Err(e) => database_error_response(&e),
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminHomeModuleStatePayload {
+    pub key: String,
+    pub mode: Option<String>,
+    pub day_key: Option<String>,
+    pub remaining_ids: Vec<String>,
+    pub today_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AdminHomeModuleUpdateRequest {
+    pub mode: Option<String>,
+    pub today_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AdminHomeModulePatchRequest {
+    pub mode: Option<String>,
+    pub today_ids: Option<Vec<String>>,
+    pub remaining_ids: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AdminHomeModulePath {
+    pub key: String,
+}
+
+pub async fn admin_get_home_module_state(
+    req: HttpRequest,
+    path: web::Path<AdminHomeModulePath>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    let key = path.into_inner().key;
+    match db.get_home_module_state(&key).await {
+        Ok(Some(state)) => {
+            HttpResponse::Ok().json(ApiResponse::success(AdminHomeModuleStatePayload {
+                key: state.key,
+                mode: state.mode,
+                day_key: state.day_key.map(|d| d.to_string()),
+                remaining_ids: state.remaining_ids,
+                today_ids: state.today_ids,
+            }))
+        }
+        Ok(None) => HttpResponse::Ok().json(ApiResponse::success(AdminHomeModuleStatePayload {
+            key,
+            mode: None,
+            day_key: None,
+            remaining_ids: Vec::new(),
+            today_ids: Vec::new(),
+        })),
+        Err(e) => database_error_response(&e),
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AdminHomeModuleDebugQuery {
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminHomeModuleDebugGrant {
+    pub slot_index: Option<i32>,
+    pub product_id: String,
+    pub ends_at: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminHomeModuleDebugPayload {
+    pub state: AdminHomeModuleStatePayload,
+    pub placement: Option<String>,
+    pub rotation_candidate_count: i64,
+    pub active_grants: Vec<AdminHomeModuleDebugGrant>,
+}
+
+/**
+ * home_module_key_placement
+ * 首页模块 key 与付费展示位的对应关系；无付费位的模块返回 None。
+ */
+fn home_module_key_placement(key: &str) -> Option<Placement> {
+    match key {
+        "home_sponsored_top" => Some(Placement::HomeTop),
+        "home_sponsored_right" => Some(Placement::HomeRight),
+        _ => None,
+    }
+}
+
+/**
+ * admin_debug_home_module
+ * 管理后台：查看首页模块当天的输入（存储的状态、轮播候选数量、生效中的付费 grants），便于排查轮播问题。
+ */
+pub async fn admin_debug_home_module(
+    req: HttpRequest,
+    path: web::Path<AdminHomeModulePath>,
+    query: web::Query<AdminHomeModuleDebugQuery>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    let key = path.into_inner().key;
+    let language = query.language.as_deref();
+
+    let state = match db.get_home_module_state(&key).await {
+        Ok(Some(state)) => AdminHomeModuleStatePayload {
+            key: state.key,
+            mode: state.mode,
+            day_key: state.day_key.map(|d| d.to_string()),
+            remaining_ids: state.remaining_ids,
+            today_ids: state.today_ids,
+        },
+        Ok(None) => AdminHomeModuleStatePayload {
+            key: key.clone(),
+            mode: None,
+            day_key: None,
+            remaining_ids: Vec::new(),
+            today_ids: Vec::new(),
+        },
+        Err(e) => return database_error_response(&e),
+    };
+
+    let rotation_candidate_count = match db.count_products_for_sponsorship_rotation(language).await
+    {
+        Ok(v) => v,
+        Err(e) => return database_error_response(&e),
+    };
+
+    let placement = home_module_key_placement(&key);
+    let active_grants = match placement {
+        Some(placement) => match db
+            .get_active_sponsorship_grants(placement, Utc::now(), language)
+            .await
+        {
+            Ok(list) => list
+                .into_iter()
+                .map(|g| AdminHomeModuleDebugGrant {
+                    slot_index: g.slot_index,
+                    product_id: g.product_id,
+                    ends_at: g.ends_at.to_rfc3339(),
+                })
+                .collect(),
+            Err(e) => return database_error_response(&e),
+        },
+        None => Vec::new(),
+    };
+
+    HttpResponse::Ok().json(ApiResponse::success(AdminHomeModuleDebugPayload {
+        state,
+        placement: placement.map(|v| v.to_string()),
+        rotation_candidate_count,
+        active_grants,
+    }))
+}
+
+pub async fn admin_put_home_module_state(
+    req: HttpRequest,
+    path: web::Path<AdminHomeModulePath>,
+    body: web::Json<AdminHomeModuleUpdateRequest>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    let key = path.into_inner().key;
+    let body = body.into_inner();
+    let mode = match body.mode.as_deref() {
+        None => HomeModuleMode::Manual,
+        Some(raw) => match HomeModuleMode::parse(raw) {
+            Some(mode) => mode,
+            None => {
+                return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+                    "Invalid mode (expected manual / free / paid / mixed)".to_string(),
+                ))
+            }
+        },
+    };
+
+    let summary = format!(
+        "mode={} today_ids={}",
+        mode.as_str(),
+        body.today_ids.join(",")
+    );
+    let state = crate::db::HomeModuleState {
+        key: key.clone(),
+        mode: Some(mode.as_str().to_string()),
+        day_key: None,
+        remaining_ids: Vec::new(),
+        today_ids: body.today_ids,
+    };
+
+    match db.upsert_home_module_state(state).await {
+        Ok(()) => {
+            audit_admin_action(
+                &db,
+                &req,
+                "home_module.update",
+                "home_module",
+                Some(&key),
+                Some(summary),
+            )
+            .await;
+            HttpResponse::Ok().json(ApiResponse::success(OkPayload { ok: true }))
+        }
+        Err(e) => database_error_response(&e),
+    }
+}
+
+/**
+ * admin_patch_home_module_state
+ * 管理端：局部更新首页模块状态，只修改请求中出现的字段（如仅切换 mode 时保留 today_ids）。
+ */
+pub async fn admin_patch_home_module_state(
+    req: HttpRequest,
+    path: web::Path<AdminHomeModulePath>,
+    body: web::Json<AdminHomeModulePatchRequest>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    let key = path.into_inner().key;
+    let body = body.into_inner();
+    let mode = match body.mode.as_deref() {
+        None => None,
+        Some(raw) => match HomeModuleMode::parse(raw) {
+            Some(mode) => Some(mode),
+            None => {
+                return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+                    "Invalid mode (expected manual / free / paid / mixed)".to_string(),
+                ))
+            }
+        },
+    };
+
+    let mut changed = Vec::new();
+    if let Some(mode) = mode {
+        changed.push(format!("mode={}", mode.as_str()));
+    }
+    if let Some(ids) = body.today_ids.as_ref() {
+        changed.push(format!("today_ids={}", ids.join(",")));
+    }
+    if let Some(ids) = body.remaining_ids.as_ref() {
+        changed.push(format!("remaining_ids={}", ids.join(",")));
+    }
+    let patch = crate::db::HomeModuleStatePatch {
+        mode: mode.map(|m| m.as_str().to_string()),
+        remaining_ids: body.remaining_ids,
+        today_ids: body.today_ids,
+    };
+
+    match db.patch_home_module_state(&key, patch).await {
+        Ok(()) => {
+            audit_admin_action(
+                &db,
+                &req,
+                "home_module.patch",
+                "home_module",
+                Some(&key),
+                Some(changed.join(" ")),
+            )
+            .await;
+            HttpResponse::Ok().json(ApiResponse::success(OkPayload { ok: true }))
+        }
+        Err(e) => database_error_response(&e),
+    }
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
-pub struct AdminSponsorshipOrderActionBody {
-    pub action: String,
-    pub order_id: String,
-    pub provider_order_id: Option<String>,
-    pub paid_months: Option<i32>,
-    pub amount_usd_cents: Option<i32>,
+pub struct AdminCacheInvalidateQuery {
+    pub warm: Option<bool>,
 }
 
-pub async fn admin_sponsorship_order_action(
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminCacheInvalidateResult {
+    pub invalidated: Vec<String>,
+    pub warmed: bool,
+}
+
+/**
+ * admin_get_product_status_counts
+ * 管理端：按审核状态统计产品数量，供审核看板展示。
+ */
+pub async fn admin_get_product_status_counts(
     req: HttpRequest,
-    body: web::Json<AdminSponsorshipOrderActionBody>,
     db: web::Data<Arc<Database>>,
 ) -> impl Responder {
     if let Err(resp) = validate_admin_token(&req) {
         return resp;
     }
 
-    let input = body.into_inner();
-    let action = input.action.trim().to_ascii_lowercase();
-    if action != "mark_paid" {
-        return HttpResponse::BadRequest()
-            .json(ApiResponse::<()>::error("Invalid action".to_string()));
+    match db.get_product_status_counts().await {
+        Ok(counts) => HttpResponse::Ok().json(ApiResponse::success(counts)),
+        Err(e) => database_error_response(&e),
     }
+}
 
-    let order_id = input.order_id.trim().to_string();
-    if order_id.is_empty() {
+/**
+ * admin_invalidate_caches
+ * 管理端：批量修改数据后清空进程内缓存（含首页聚合缓存）；warm=true 时立即重新计算。
+ */
+pub async fn admin_invalidate_caches(
+    req: HttpRequest,
+    query: web::Query<AdminCacheInvalidateQuery>,
+    db: web::Data<Arc<Database>>,
+    home_cache: web::Data<HomeAggregateCache>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    let mut invalidated = db.invalidate_caches();
+    if home_cache.clear() {
+        invalidated.push("home_aggregate".to_string());
+    }
+    let warmed = if query.warm.unwrap_or(false) {
+        match db.get_overview_stats().await {
+            Ok(_) => true,
+            Err(e) => {
+                log::warn!("Cache warm-up failed: {:?}", e);
+                false
+            }
+        }
+    } else {
+        false
+    };
+
+    HttpResponse::Ok().json(ApiResponse::success(AdminCacheInvalidateResult {
+        invalidated,
+        warmed,
+    }))
+}
+
+/**
+ * deserialize_present
+ * 配合 #[serde(default)] 区分“字段缺省”（None）与“显式传值或 null”（Some(..)）。
+ */
+fn deserialize_present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AdminDeveloperSponsorRequest {
+    #[serde(default, deserialize_with = "deserialize_present")]
+    pub sponsor_role: Option<Option<String>>,
+    pub sponsor_verified: Option<bool>,
+}
+
+/**
+ * admin_update_developer_sponsor
+ * 管理端：调整创作者的赞助角色与认证状态（sponsor_role 仅允许白名单值或 null，缺省时保留原角色）；
+ * 创作者不存在时返回 404。
+ */
+pub async fn admin_update_developer_sponsor(
+    req: HttpRequest,
+    path: web::Path<DeveloperPath>,
+    body: web::Json<AdminDeveloperSponsorRequest>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    let email = path.into_inner().email.trim().to_ascii_lowercase();
+    if !is_valid_email_basic(&email) {
         return HttpResponse::BadRequest()
-            .json(ApiResponse::<()>::error("Missing order_id".to_string()));
+            .json(ApiResponse::<()>::error("Invalid email".to_string()));
+    }
+
+    let body = body.into_inner();
+    let sponsor_role: Option<Option<String>> = body.sponsor_role.map(|role| {
+        role.map(|v| v.trim().to_ascii_lowercase())
+            .filter(|v| !v.is_empty())
+    });
+    if let Some(Some(role)) = sponsor_role.as_ref() {
+        if !crate::db::DEVELOPER_SPONSOR_ROLES.contains(&role.as_str()) {
+            return HttpResponse::BadRequest().json(ApiResponse::<()>::error(format!(
+                "Invalid sponsor_role (expected one of {} or null)",
+                crate::db::DEVELOPER_SPONSOR_ROLES.join(" / ")
+            )));
+        }
+    }
+    // 只改角色未指定认证状态时，设置角色即认证、清空角色即取消认证；两者都缺省则保持不变。
+    let sponsor_verified = body
+        .sponsor_verified
+        .or_else(|| sponsor_role.as_ref().map(|role| role.is_some()));
+
+    if let Err(e) = db
+        .update_developer_sponsor(
+            &email,
+            sponsor_role.as_ref().map(|role| role.as_deref()),
+            sponsor_verified,
+        )
+        .await
+    {
+        if let Some(not_found) = e.downcast_ref::<DeveloperNotFoundError>() {
+            return HttpResponse::NotFound().json(ApiResponse::<()>::error(not_found.to_string()));
+        }
+        return database_error_response(&e);
+    }
+    db.invalidate_caches();
+    audit_admin_action(
+        &db,
+        &req,
+        "developer.sponsor_update",
+        "developer",
+        Some(&email),
+        Some(format!(
+            "sponsor_role={} sponsor_verified={}",
+            match sponsor_role.as_ref() {
+                Some(Some(role)) => role.as_str(),
+                Some(None) => "null",
+                None => "unchanged",
+            },
+            sponsor_verified
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "unchanged".to_string())
+        )),
+    )
+    .await;
+
+    match db.get_developer_by_email(&email).await {
+        Ok(Some(dev)) => HttpResponse::Ok().json(ApiResponse::success(dev)),
+        Ok(None) => HttpResponse::NotFound()
+            .json(ApiResponse::<()>::error("Developer not found".to_string())),
+        Err(e) => database_error_response(&e),
+    }
+}
+
+/**
+ * admin_list_outgoing_webhooks
+ * 管理端：列出对外 webhook 订阅（不返回 secret）。
+ */
+pub async fn admin_list_outgoing_webhooks(
+    req: HttpRequest,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    match db.list_outgoing_webhooks().await {
+        Ok(list) => HttpResponse::Ok().json(ApiResponse::success(list)),
+        Err(e) => database_error_response(&e),
+    }
+}
+
+/**
+ * admin_upsert_outgoing_webhook
+ * 管理端：新建（不带 id）或更新 webhook 订阅；events 取值 product.created / product.approved / product.rejected。
+ */
+pub async fn admin_upsert_outgoing_webhook(
+    req: HttpRequest,
+    body: web::Json<UpsertOutgoingWebhookRequest>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    let body = body.into_inner();
+    if let Err(message) = crate::db::validate_outgoing_webhook_request(&body) {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(message));
+    }
+
+    match db.upsert_outgoing_webhook(body).await {
+        Ok(Some(webhook)) => {
+            audit_admin_action(
+                &db,
+                &req,
+                "outgoing_webhook.upsert",
+                "outgoing_webhook",
+                Some(&webhook.id.to_string()),
+                Some(format!(
+                    "url={} events={}",
+                    webhook.url,
+                    webhook.events.join(",")
+                )),
+            )
+            .await;
+            HttpResponse::Ok().json(ApiResponse::success(webhook))
+        }
+        Ok(None) => {
+            HttpResponse::NotFound().json(ApiResponse::<()>::error("Webhook not found".to_string()))
+        }
+        Err(e) => database_error_response(&e),
+    }
+}
+
+pub async fn admin_delete_outgoing_webhook(
+    req: HttpRequest,
+    path: web::Path<i64>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    let id = path.into_inner();
+    match db.delete_outgoing_webhook(id).await {
+        Ok(true) => {
+            audit_admin_action(
+                &db,
+                &req,
+                "outgoing_webhook.delete",
+                "outgoing_webhook",
+                Some(&id.to_string()),
+                None,
+            )
+            .await;
+            HttpResponse::Ok().json(ApiResponse::success(OkPayload { ok: true }))
+        }
+        Ok(false) => {
+            HttpResponse::NotFound().json(ApiResponse::<()>::error("Webhook not found".to_string()))
+        }
+        Err(e) => database_error_response(&e),
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AdminWebhookDeliveriesQuery {
+    pub limit: Option<i64>,
+}
+
+/**
+ * admin_list_outgoing_webhook_deliveries
+ * 管理端：查看某个 webhook 订阅最近的投递记录。
+ */
+pub async fn admin_list_outgoing_webhook_deliveries(
+    req: HttpRequest,
+    path: web::Path<i64>,
+    query: web::Query<AdminWebhookDeliveriesQuery>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
     }
 
     match db
-        .admin_mark_sponsorship_order_paid(
-            &order_id,
-            input.provider_order_id.as_deref(),
-            input.amount_usd_cents,
-            input.paid_months,
+        .list_outgoing_webhook_deliveries(path.into_inner(), query.limit.unwrap_or(50))
+        .await
+    {
+        Ok(list) => HttpResponse::Ok().json(ApiResponse::success(list)),
+        Err(e) => database_error_response(&e),
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AdminProductClaimsQuery {
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/**
+ * admin_list_product_claims
+ * 管理端：列出产品认领（默认全部，可按 status=pending/approved/rejected 过滤）。
+ */
+pub async fn admin_list_product_claims(
+    req: HttpRequest,
+    query: web::Query<AdminProductClaimsQuery>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    let status = query
+        .status
+        .as_deref()
+        .map(|v| v.trim().to_ascii_lowercase())
+        .filter(|v| !v.is_empty());
+    match db
+        .list_product_claims(
+            status.as_deref(),
+            query.limit.unwrap_or(50),
+            query.offset.unwrap_or(0),
         )
         .await
     {
-        Ok(grant) => HttpResponse::Ok().json(ApiResponse::success(grant)),
-        Err(e) => HttpResponse::BadRequest()
-            .json(ApiResponse::<()>::error(format!("Invalid input: {:?}", e))),
+        Ok(claims) => HttpResponse::Ok().json(ApiResponse::success(claims)),
+        Err(e) => database_error_response(&e),
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AdminProductClaimActionBody {
+    pub claim_id: i64,
+    pub action: String,
+}
+
+/**
+ * admin_product_claim_action
+ * 管理端：审核有争议的产品认领；approve 会把产品编辑权（maker_email）转给认领人。
+ */
+pub async fn admin_product_claim_action(
+    req: HttpRequest,
+    body: web::Json<AdminProductClaimActionBody>,
+    db: web::Data<Arc<Database>>,
+) -> impl Responder {
+    if let Err(resp) = validate_admin_token(&req) {
+        return resp;
+    }
+
+    let approve = match body.action.trim().to_ascii_lowercase().as_str() {
+        "approve" => true,
+        "reject" => false,
+        _ => {
+            return HttpResponse::BadRequest()
+                .json(ApiResponse::<()>::error("Invalid action".to_string()))
+        }
+    };
+
+    match db.decide_product_claim(body.claim_id, approve).await {
+        Ok(Some(claim)) => {
+            if approve {
+                db.invalidate_caches();
+            }
+            audit_admin_action(
+                &db,
+                &req,
+                if approve {
+                    "product_claim.approve"
+                } else {
+                    "product_claim.reject"
+                },
+                "product_claim",
+                Some(&claim.id.to_string()),
+                Some(format!(
+                    "product_id={} claimant_email={}",
+                    claim.product_id, claim.claimant_email
+                )),
+            )
+            .await;
+            HttpResponse::Ok().json(ApiResponse::success(claim))
+        }
+        Ok(None) => HttpResponse::NotFound().json(ApiResponse::<()>::error(
+            "Product claim not found".to_string(),
+        )),
+        Err(e) => {
+            if e.downcast_ref::<ProductClaimNotPendingError>().is_some()
+                || e.downcast_ref::<ProductAlreadyClaimedError>().is_some()
+            {
+                return HttpResponse::Conflict().json(ApiResponse::<()>::error(e.to_string()));
+            }
+            database_error_response(&e)
+        }
     }
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
-pub struct AdminPaymentsSummaryQuery {
-    pub days: Option<i64>,
+pub struct AdminMergeDevelopersRequest {
+    pub from_email: String,
+    pub into_email: String,
 }
 
 /**
- * admin_get_payments_summary
- * 管理端：支付汇总统计（默认近 30 天）。
+ * admin_merge_developers
+ * 管理端：合并重复的开发者账号（如大小写不同的邮箱），产品、关注者与赞助信息并入目标后删除源账号。
  */
-pub async fn admin_get_payments_summary(
+pub async fn admin_merge_developers(
     req: HttpRequest,
-    query: web::Query<AdminPaymentsSummaryQuery>,
+    body: web::Json<AdminMergeDevelopersRequest>,
     db: web::Data<Arc<Database>>,
 ) -> impl Responder {
     if let Err(resp) = validate_admin_token(&req) {
         return resp;
     }
 
-    let days = query.days.unwrap_or(30);
-    match db.get_payments_summary(days).await {
-        Ok(summary) => HttpResponse::Ok().json(ApiResponse::success(summary)),
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<()>::error(format!("Database error: {:?}", e))),
+    let from_email = body.from_email.trim().to_string();
+    let into_email = body.into_email.trim().to_ascii_lowercase();
+    if from_email.is_empty() || !is_valid_email_basic(&into_email) {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<()>::error("Invalid email".to_string()));
+    }
+    if from_email == into_email {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+            "from_email and into_email must differ".to_string(),
+        ));
+    }
+
+    match db.merge_developers(&from_email, &into_email).await {
+        Ok(result) => {
+            db.invalidate_caches();
+            audit_admin_action(
+                &db,
+                &req,
+                "developer.merge",
+                "developer",
+                Some(&into_email),
+                Some(format!("from_email={}", from_email)),
+            )
+            .await;
+            HttpResponse::Ok().json(ApiResponse::success(result))
+        }
+        Err(e) => {
+            if let Some(not_found) = e.downcast_ref::<DeveloperNotFoundError>() {
+                return HttpResponse::NotFound()
+                    .json(ApiResponse::<()>::error(not_found.to_string()));
+            }
+            database_error_response(&e)
+        }
     }
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
-pub struct AdminUpsertCategoriesRequest {
-    pub categories: Vec<Category>,
+pub struct AdminDevelopersBackfillQuery {
+    pub dry_run: Option<bool>,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
-pub struct AdminUpsertCategoriesResult {
-    pub upserted: usize,
+pub struct AdminDevelopersBackfillResult {
+    pub orphans: Vec<OrphanMaker>,
+    pub created: usize,
+    pub failed: Vec<String>,
+    pub dry_run: bool,
 }
 
-pub async fn admin_get_categories(
+/**
+ * admin_backfill_developers
+ * 管理端：为 products 中存在但 developers 表缺失的创作者补齐 developers 行；dry_run=true 时仅列出。
+ */
+pub async fn admin_backfill_developers(
     req: HttpRequest,
+    query: web::Query<AdminDevelopersBackfillQuery>,
     db: web::Data<Arc<Database>>,
 ) -> impl Responder {
     if let Err(resp) = validate_admin_token(&req) {
         return resp;
     }
 
-    match db.get_categories().await {
-        Ok(categories) => HttpResponse::Ok().json(ApiResponse::success(categories)),
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<()>::error(format!("Database error: {:?}", e))),
-    }
-}
-
-pub async fn admin_upsert_categories(
-    req: HttpRequest,
-    body: web::Json<AdminUpsertCategoriesRequest>,
-    db: web::Data<Arc<Database>>,
-) -> impl Responder {
-    if let Err(resp) = validate_admin_token(&req) {
-        return resp;
-    }
+    let dry_run = query.dry_run.unwrap_or(false);
+    let orphans = match db.find_orphan_maker_emails().await {
+        Ok(list) => list,
+        Err(e) => return database_error_response(&e),
+    };
 
-    match db.upsert_categories(body.into_inner().categories).await {
-        Ok(upserted) => {
-            HttpResponse::Ok().json(ApiResponse::success(AdminUpsertCategoriesResult {
-                upserted,
-            }))
+    let (created, failed) = if dry_run || orphans.is_empty() {
+        (0, Vec::new())
+    } else {
+        match db.backfill_orphan_developers(&orphans).await {
+            Ok(v) => v,
+            Err(e) => return database_error_response(&e),
         }
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<()>::error(format!("Database error: {:?}", e))),
+    };
+    if created > 0 {
+        db.invalidate_caches();
+        audit_admin_action(
+            &db,
+            &req,
+            "developer.backfill",
+            "developer",
+            None,
+            Some(format!("created={} failed={}", created, failed.len())),
+        )
+        .await;
     }
+
+    HttpResponse::Ok().json(ApiResponse::success(AdminDevelopersBackfillResult {
+        orphans,
+        created,
+        failed,
+        dry_run,
+    }))
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
-pub struct AdminCategoryPath {
-    pub id: String,
+pub struct AdminAuditLogQuery {
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub entity_type: Option<String>,
+    pub entity_id: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
 }
 
-pub async fn admin_delete_category(
+/**
+ * admin_list_audit_log
+ * 管理端：分页查询管理操作审计日志，可按 actor / action / entity_type / entity_id 精确过滤，按时间倒序。
+ */
+pub async fn admin_list_audit_log(
     req: HttpRequest,
-    path: web::Path<AdminCategoryPath>,
+    query: web::Query<AdminAuditLogQuery>,
     db: web::Data<Arc<Database>>,
 ) -> impl Responder {
     if let Err(resp) = validate_admin_token(&req) {
         return resp;
     }
 
-    let id = path.into_inner().id;
-    match db.delete_category(&id).await {
-        Ok(ok) => HttpResponse::Ok().json(ApiResponse::success(OkPayload { ok })),
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<()>::error(format!("Database error: {:?}", e))),
+    let query = query.into_inner();
+    let defaults = AdminAuditLogListParams::default();
+    let params = AdminAuditLogListParams {
+        actor: query.actor,
+        action: query.action,
+        entity_type: query.entity_type,
+        entity_id: query.entity_id,
+        limit: query.limit.unwrap_or(defaults.limit).clamp(1, 200),
+        offset: query.offset.unwrap_or(defaults.offset).max(0),
+    };
+    let (limit, offset) = (params.limit, params.offset);
+
+    match db.list_admin_audit_log(params).await {
+        Ok((items, total)) => HttpResponse::Ok().json(ApiResponse::success(PagedList {
+            items,
+            total,
+            limit,
+            offset,
+        })),
+        Err(e) => database_error_response(&e),
     }
 }
 
-#[derive(Debug, Serialize, ToSchema)]
-pub struct AdminHomeModuleStatePayload {
-    pub key: String,
-    pub mode: Option<String>,
-    pub day_key: Option<String>,
-    pub remaining_ids: Vec<String>,
-    pub today_ids: Vec<String>,
-}
-
-#[derive(Debug, Deserialize, ToSchema)]
-pub struct AdminHomeModuleUpdateRequest {
-    pub mode: Option<String>,
-    pub today_ids: Vec<String>,
-}
-
-#[derive(Debug, Deserialize, ToSchema)]
-pub struct AdminHomeModulePath {
-    pub key: String,
-}
-
-pub async fn admin_get_home_module_state(
+/**
+ * admin_get_migration_status
+ * 管理端：查看各懒迁移在本进程的就绪标记，以及数据库中预期的表/列/索引是否存在。
+ */
+pub async fn admin_get_migration_status(
     req: HttpRequest,
-    path: web::Path<AdminHomeModulePath>,
     db: web::Data<Arc<Database>>,
 ) -> impl Responder {
     if let Err(resp) = validate_admin_token(&req) {
         return resp;
     }
 
-    let key = path.into_inner().key;
-    match db.get_home_module_state(&key).await {
-        Ok(Some(state)) => {
-            HttpResponse::Ok().json(ApiResponse::success(AdminHomeModuleStatePayload {
-                key: state.key,
-                mode: state.mode,
-                day_key: state.day_key.map(|d| d.to_string()),
-                remaining_ids: state.remaining_ids,
-                today_ids: state.today_ids,
-            }))
-        }
-        Ok(None) => HttpResponse::Ok().json(ApiResponse::success(AdminHomeModuleStatePayload {
-            key,
-            mode: None,
-            day_key: None,
-            remaining_ids: Vec::new(),
-            today_ids: Vec::new(),
-        })),
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<()>::error(format!("Database error: {:?}", e))),
+    match db.get_migration_status().await {
+        Ok(items) => HttpResponse::Ok().json(ApiResponse::success(items)),
+        Err(e) => database_error_response(&e),
     }
 }
 
-pub async fn admin_put_home_module_state(
+/**
+ * admin_run_all_migrations
+ * 管理端：主动执行全部懒迁移（部署后预热用），返回执行后的状态；单个迁移失败记录在对应条目的 error 中。
+ */
+pub async fn admin_run_all_migrations(
     req: HttpRequest,
-    path: web::Path<AdminHomeModulePath>,
-    body: web::Json<AdminHomeModuleUpdateRequest>,
     db: web::Data<Arc<Database>>,
 ) -> impl Responder {
     if let Err(resp) = validate_admin_token(&req) {
         return resp;
     }
 
-    let key = path.into_inner().key;
-    let body = body.into_inner();
-    let mode = body.mode.unwrap_or_else(|| "manual".to_string());
-
-    let state = crate::db::HomeModuleState {
-        key: key.clone(),
-        mode: Some(mode),
-        day_key: None,
-        remaining_ids: Vec::new(),
-        today_ids: body.today_ids,
-    };
-
-    match db.upsert_home_module_state(state).await {
-        Ok(()) => HttpResponse::Ok().json(ApiResponse::success(OkPayload { ok: true })),
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<()>::error(format!("Database error: {:?}", e))),
+    match db.run_all_migrations().await {
+        Ok(items) => {
+            let failed: Vec<&str> = items
+                .iter()
+                .filter(|m| m.error.is_some() || !m.schema_ok)
+                .map(|m| m.name.as_str())
+                .collect();
+            let summary = if failed.is_empty() {
+                format!("all {} migrations ok", items.len())
+            } else {
+                format!("failed: {}", failed.join(", "))
+            };
+            audit_admin_action(
+                &db,
+                &req,
+                "migrations.run_all",
+                "migration",
+                None,
+                Some(summary),
+            )
+            .await;
+            HttpResponse::Ok().json(ApiResponse::success(items))
+        }
+        Err(e) => database_error_response(&e),
     }
 }
 
@@ -3257,8 +7560,7 @@ pub async fn dev_bootstrap(req: HttpRequest, db: web::Data<Arc<Database>>) -> im
         Ok(()) => HttpResponse::Ok().json(ApiResponse::success(DevBootstrapResult {
             bootstrapped: true,
         })),
-        Err(e) => HttpResponse::InternalServerError()
-            .json(ApiResponse::<()>::error(format!("Database error: {:?}", e))),
+        Err(e) => database_error_response(&e),
     }
 }
 
@@ -3279,8 +7581,7 @@ pub async fn dev_seed(req: HttpRequest, db: web::Data<Arc<Database>>) -> impl Re
             if is_rls_policy_error(&e) {
                 0
             } else {
-                return HttpResponse::InternalServerError()
-                    .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)));
+                return database_error_response(&e);
             }
         }
     };
@@ -3441,7 +7742,7 @@ pub async fn dev_seed(req: HttpRequest, db: web::Data<Arc<Database>>) -> impl Re
 
     let mut product_ids = Vec::new();
     for p in sample_products {
-        match db.create_product(p).await {
+        match db.create_product(p, false).await {
             Ok(created) => {
                 let id = created.id;
                 product_ids.push(id.clone());
@@ -3461,28 +7762,707 @@ pub async fn dev_seed(req: HttpRequest, db: web::Data<Arc<Database>>) -> impl Re
                     Ok(_) => {}
                     Err(e) => {
                         if !is_rls_policy_error(&e) {
-                            return HttpResponse::InternalServerError().json(
-                                ApiResponse::<()>::error(format!("Database error: {:?}", e)),
-                            );
+                            return database_error_response(&e);
                         }
                     }
                 }
             }
-            Err(e) => {
-                return HttpResponse::InternalServerError()
-                    .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)))
-            }
+            Err(e) => return database_error_response(&e),
         }
     }
 
     if let Err(e) = db.seed_engagement(&product_ids).await {
-        return HttpResponse::InternalServerError()
-            .json(ApiResponse::<()>::error(format!("Database error: {:?}", e)));
+        return database_error_response(&e);
     }
 
+    db.invalidate_caches();
+
     HttpResponse::Ok().json(ApiResponse::success(DevSeedResult {
         categories_upserted,
         products_created: product_ids.len(),
         product_ids,
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+
+    async fn post_json_echo(body: String) -> (StatusCode, serde_json::Value) {
+        let app = test::init_service(App::new().app_data(json_config()).route(
+            "/echo",
+            web::post().to(|body: web::Json<serde_json::Value>| async move {
+                HttpResponse::Ok().json(body.into_inner())
+            }),
+        ))
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/echo")
+            .insert_header(("Content-Type", "application/json"))
+            .set_payload(body)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let status = resp.status();
+        (status, test::read_body_json(resp).await)
+    }
+
+    #[actix_web::test]
+    async fn test_oversized_json_body_returns_invalid_body() {
+        let body = serde_json::json!({ "description": "x".repeat(MAX_JSON_BODY_BYTES) });
+        let (status, json) = post_json_echo(body.to_string()).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(json["success"], false);
+        assert_eq!(json["error"]["code"], "invalid_body");
+    }
+
+    #[actix_web::test]
+    async fn test_malformed_json_body_returns_invalid_body() {
+        let (status, json) = post_json_echo("{\"name\": ".to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["error"]["code"], "invalid_body");
+    }
+
+    #[test]
+    fn test_task_heartbeat_reports_staleness() {
+        let heartbeat = TaskHeartbeat::new("newsletter", 300);
+        assert!(!heartbeat.status(1_000).healthy);
+
+        let now = 1_700_000_000;
+        heartbeat.tick_at(now);
+        let fresh = heartbeat.status(now + 10);
+        assert!(fresh.healthy);
+        assert_eq!(fresh.stale_secs, Some(10));
+        assert!(fresh.last_tick_at.is_some());
+
+        assert!(heartbeat.status(now + 300).healthy);
+        let stale = heartbeat.status(now + 301);
+        assert!(!stale.healthy);
+        assert_eq!(stale.stale_secs, Some(301));
+        assert_eq!(stale.name, "newsletter");
+    }
+
+    #[actix_web::test]
+    async fn test_validate_sponsorship_slot_rejects_out_of_range_index() {
+        assert!(validate_sponsorship_slot("en", "home_right", Some(2)).is_ok());
+
+        let resp = validate_sponsorship_slot("en", "home_top", Some(99)).unwrap_err();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            json["message"],
+            "home_top requires slot_index between 0 and 1"
+        );
+
+        let resp = validate_sponsorship_slot("zh", "home_right", None).unwrap_err();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_validate_sponsorship_slot_rejects_unknown_placement() {
+        assert_eq!(
+            validate_sponsorship_slot("en", " home_top ", Some(1)).ok(),
+            Some(Placement::HomeTop)
+        );
+
+        let resp = validate_sponsorship_slot("en", "category_top", Some(0)).unwrap_err();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            json["message"],
+            "Invalid placement \"category_top\", expected one of: home_top, home_right"
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_admin_actor_records_token_fingerprint_and_unverified_claim() {
+        // sha256("secret") = 2bb80d53...
+        let req = test::TestRequest::default()
+            .insert_header(("x-admin-token", "secret"))
+            .insert_header(("x-admin-actor", "  ops@example.com "))
+            .to_http_request();
+        assert_eq!(
+            admin_actor(&req).await,
+            "token:2bb80d53 (claimed: ops@example.com)"
+        );
+
+        let req = test::TestRequest::default()
+            .insert_header(("x-admin-token", "secret"))
+            .insert_header(("x-admin-actor", "   "))
+            .to_http_request();
+        assert_eq!(admin_actor(&req).await, "token:2bb80d53");
+
+        let req = test::TestRequest::default().to_http_request();
+        assert_eq!(admin_actor(&req).await, "admin-token");
+    }
+
+    #[test]
+    fn test_insert_pagination_headers_echoes_effective_values() {
+        let mut builder = HttpResponse::Ok();
+        insert_pagination_headers(&mut builder, Some(200), Some(0), Some(42));
+        let resp = builder.finish();
+        let header = |name: &str| {
+            resp.headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string())
+        };
+        assert_eq!(header(PAGE_LIMIT_HEADER).as_deref(), Some("200"));
+        assert_eq!(header(PAGE_OFFSET_HEADER).as_deref(), Some("0"));
+        assert_eq!(header(TOTAL_COUNT_HEADER).as_deref(), Some("42"));
+
+        let mut builder = HttpResponse::Ok();
+        insert_pagination_headers(&mut builder, None, Some(10), None);
+        let resp = builder.finish();
+        assert!(resp.headers().get(PAGE_LIMIT_HEADER).is_none());
+        assert!(resp.headers().get(TOTAL_COUNT_HEADER).is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_constraint_violation_response_maps_status_and_code() {
+        let req = test::TestRequest::default().to_http_request();
+        let err = anyhow::anyhow!("duplicate key value violates unique constraint");
+
+        let resp = constraint_violation_response(&req, ConstraintViolation::Unique, &err);
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "duplicate");
+
+        let resp = constraint_violation_response(&req, ConstraintViolation::ForeignKey, &err);
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "invalid_reference");
+    }
+
+    #[actix_web::test]
+    async fn test_json_array_response_stream_matches_buffered_envelope() {
+        use futures_util::StreamExt;
+
+        async fn collect(items: Vec<serde_json::Value>) -> serde_json::Value {
+            let (tx, rx) = tokio::sync::mpsc::channel(4);
+            tokio::spawn(async move {
+                for item in items {
+                    let _ = tx.send(Ok(item)).await;
+                }
+            });
+            let chunks: Vec<_> = json_array_response_stream(rx).collect().await;
+            let mut body = Vec::new();
+            for chunk in chunks {
+                body.extend_from_slice(&chunk.expect("chunk"));
+            }
+            serde_json::from_slice(&body).expect("valid json")
+        }
+
+        let empty = collect(Vec::new()).await;
+        assert_eq!(
+            empty,
+            serde_json::to_value(ApiResponse::success(Vec::<serde_json::Value>::new())).unwrap()
+        );
+
+        let items = vec![
+            serde_json::json!({"id": "a"}),
+            serde_json::json!({"id": "b"}),
+        ];
+        let streamed = collect(items.clone()).await;
+        assert_eq!(
+            streamed,
+            serde_json::to_value(ApiResponse::success(items)).unwrap()
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_json_array_response_stream_errors_on_row_failure() {
+        use futures_util::StreamExt;
+
+        let (tx, rx) = tokio::sync::mpsc::channel(4);
+        tx.send(Ok(serde_json::json!({"id": "a"}))).await.unwrap();
+        tx.send(Err(anyhow::anyhow!("boom"))).await.unwrap();
+        drop(tx);
+
+        let chunks: Vec<_> = json_array_response_stream(rx).collect().await;
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].is_ok());
+        assert!(chunks[1].is_err());
+    }
+
+    #[actix_web::test]
+    async fn test_validate_requested_months_rejects_out_of_range() {
+        assert_eq!(validate_requested_months("en", 3).unwrap(), 3);
+
+        let resp = validate_requested_months("en", 36).unwrap_err();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["success"], false);
+        assert_eq!(json["error"]["code"], "VALIDATION_FAILED");
+        assert_eq!(json["error"]["fields"][0]["field"], "duration_months");
+
+        assert!(validate_requested_months("en", 0).is_err());
+    }
+
+    #[actix_web::test]
+    async fn test_validate_requested_duration_days_shares_the_months_maximum() {
+        let max = max_sponsorship_duration_days();
+        assert_eq!(
+            max,
+            crate::db::months_to_days(MAX_SPONSORSHIP_MONTHS, crate::db::days_per_month())
+        );
+        assert_eq!(validate_requested_duration_days("en", max).unwrap(), max);
+
+        let resp = validate_requested_duration_days("en", max + 1).unwrap_err();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "VALIDATION_FAILED");
+        assert_eq!(json["error"]["fields"][0]["field"], "duration_days");
+
+        assert!(validate_requested_duration_days("en", 0).is_err());
+    }
+
+    #[actix_web::test]
+    async fn test_cached_home_section_serves_stale_value_on_failure() {
+        let cache: HomeSectionCache<i32> = Default::default();
+        let ttl = StdDuration::from_secs(60);
+
+        assert_eq!(
+            cached_home_section(&cache, "en", ttl, async { Err(anyhow::anyhow!("down")) }).await,
+            (None, true)
+        );
+        assert_eq!(
+            cached_home_section(&cache, "en", ttl, async { Ok(1) }).await,
+            (Some(1), false)
+        );
+        // 未过期时直接命中缓存，不会使用新加载的值。
+        assert_eq!(
+            cached_home_section(&cache, "en", ttl, async { Ok(2) }).await,
+            (Some(1), false)
+        );
+        // 过期后刷新失败，沿用旧值并标记降级。
+        assert_eq!(
+            cached_home_section(&cache, "en", StdDuration::ZERO, async {
+                Err(anyhow::anyhow!("down"))
+            })
+            .await,
+            (Some(1), true)
+        );
+        assert_eq!(
+            cached_home_section(&cache, "zh", ttl, async { Ok(3) }).await,
+            (Some(3), false)
+        );
+    }
+
+    #[test]
+    fn test_admin_developer_sponsor_request_distinguishes_missing_and_null_role() {
+        let parse = |raw: &str| {
+            serde_json::from_str::<AdminDeveloperSponsorRequest>(raw)
+                .unwrap()
+                .sponsor_role
+        };
+        assert_eq!(parse(r#"{"sponsor_verified":true}"#), None);
+        assert_eq!(parse(r#"{"sponsor_role":null}"#), Some(None));
+        assert_eq!(
+            parse(r#"{"sponsor_role":"gold"}"#),
+            Some(Some("gold".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_home_aggregate_language_limits_cache_keys() {
+        assert_eq!(home_aggregate_language(Some(" zh-CN ")), Some("zh"));
+        assert_eq!(home_aggregate_language(Some("ZH")), Some("zh"));
+        assert_eq!(home_aggregate_language(Some("en_US")), Some("en"));
+        assert_eq!(home_aggregate_language(Some("fr")), None);
+        assert_eq!(home_aggregate_language(Some("")), None);
+        assert_eq!(home_aggregate_language(None), None);
+    }
+
+    #[test]
+    fn test_expands_category_parses_comma_list() {
+        assert!(!expands_category(None));
+        assert!(!expands_category(Some("")));
+        assert!(!expands_category(Some("maker")));
+        assert!(expands_category(Some("category")));
+        assert!(expands_category(Some("maker, Category")));
+    }
+
+    #[test]
+    fn test_engagement_rate_limiter_blocks_after_max_hits() {
+        let limiter = EngagementRateLimiter::new(2, StdDuration::from_secs(60));
+        let start = std::time::Instant::now();
+        assert!(limiter.check("ip-a", start).is_ok());
+        assert!(limiter.check("ip-a", start).is_ok());
+        let retry_after = limiter
+            .check("ip-a", start + StdDuration::from_secs(10))
+            .unwrap_err();
+        assert_eq!(retry_after, StdDuration::from_secs(50));
+        // 其他 IP 不受影响，窗口结束后重新计数。
+        assert!(limiter.check("ip-b", start).is_ok());
+        assert!(limiter
+            .check("ip-a", start + StdDuration::from_secs(60))
+            .is_ok());
+
+        let disabled = EngagementRateLimiter::new(0, StdDuration::from_secs(60));
+        for _ in 0..100 {
+            assert!(disabled.check("ip-a", start).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_engagement_env_values() {
+        assert_eq!(engagement_rate_limit_from_env_value(None, true), 30);
+        assert_eq!(engagement_rate_limit_from_env_value(None, false), 0);
+        assert_eq!(engagement_rate_limit_from_env_value(Some("20"), false), 20);
+        assert_eq!(engagement_rate_limit_from_env_value(Some(" 0 "), true), 0);
+        assert_eq!(
+            engagement_rate_limit_from_env_value(Some("5000"), true),
+            1000
+        );
+        assert_eq!(engagement_rate_limit_from_env_value(Some("abc"), true), 30);
+        assert!(!engagement_requires_auth_from_env_value(None));
+        assert!(engagement_requires_auth_from_env_value(Some(" TRUE ")));
+        assert!(!engagement_requires_auth_from_env_value(Some("no")));
+    }
+
+    #[test]
+    fn test_client_ip_hash_uses_peer_and_ignores_spoofed_forwarded_for() {
+        let req = test::TestRequest::default()
+            .peer_addr("203.0.113.7:4000".parse().unwrap())
+            .insert_header(("X-Forwarded-For", "198.51.100.1"))
+            .to_http_request();
+        let hash = client_ip_hash(&req).expect("hash");
+        assert_eq!(hash.len(), 32);
+        assert!(!hash.contains("203.0.113.7"));
+
+        let spoofed = test::TestRequest::default()
+            .peer_addr("203.0.113.7:4001".parse().unwrap())
+            .insert_header(("X-Forwarded-For", "198.51.100.2"))
+            .to_http_request();
+        assert_eq!(client_ip_hash(&spoofed).expect("hash"), hash);
+
+        let other = test::TestRequest::default()
+            .peer_addr("203.0.113.8:4000".parse().unwrap())
+            .to_http_request();
+        assert_ne!(client_ip_hash(&other).expect("hash"), hash);
+    }
+
+    #[test]
+    fn test_resolve_client_ip_honours_forwarded_for_only_behind_trusted_proxy() {
+        let ip = |v: &str| v.parse::<std::net::IpAddr>().unwrap();
+        let trusted = trusted_proxies_from_env_value(Some("10.0.0.1, 10.0.0.2,bogus"));
+        assert_eq!(trusted, vec![ip("10.0.0.1"), ip("10.0.0.2")]);
+
+        // 非受信对端：忽略 X-Forwarded-For。
+        assert_eq!(
+            resolve_client_ip(Some(ip("203.0.113.7")), Some("198.51.100.1"), &trusted),
+            Some(ip("203.0.113.7"))
+        );
+        // 受信代理：取最右侧的非受信地址，客户端伪造的左侧条目不生效。
+        assert_eq!(
+            resolve_client_ip(
+                Some(ip("10.0.0.1")),
+                Some("1.2.3.4, 198.51.100.1, 10.0.0.2"),
+                &trusted
+            ),
+            Some(ip("198.51.100.1"))
+        );
+        assert_eq!(
+            resolve_client_ip(Some(ip("10.0.0.1")), None, &trusted),
+            Some(ip("10.0.0.1"))
+        );
+        assert_eq!(
+            resolve_client_ip(None, Some("198.51.100.1"), &trusted),
+            None
+        );
+    }
+
+    #[test]
+    fn test_min_maker_age_from_env_value_is_opt_in() {
+        assert_eq!(min_maker_age_from_env_value(None), None);
+        assert_eq!(min_maker_age_from_env_value(Some("")), None);
+        assert_eq!(min_maker_age_from_env_value(Some("0")), None);
+        assert_eq!(min_maker_age_from_env_value(Some("-3")), None);
+        assert_eq!(min_maker_age_from_env_value(Some("abc")), None);
+        assert_eq!(
+            min_maker_age_from_env_value(Some(" 48 ")),
+            Some(Duration::hours(48))
+        );
+        assert_eq!(
+            min_maker_age_from_env_value(Some("100000")),
+            Some(Duration::hours(8760))
+        );
+    }
+
+    #[test]
+    fn test_maker_age_remaining_boundary() {
+        let now = Utc::now();
+        let min_age = Duration::hours(24);
+
+        assert_eq!(maker_age_remaining(now - min_age, min_age, now), None);
+        assert_eq!(
+            maker_age_remaining(now - Duration::hours(25), min_age, now),
+            None
+        );
+        assert_eq!(
+            maker_age_remaining(now - min_age + Duration::seconds(1), min_age, now),
+            Some(Duration::seconds(1))
+        );
+        assert_eq!(
+            maker_age_remaining(now, min_age, now),
+            Some(Duration::hours(24))
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_database_error_response_maps_missing_postgres_to_501() {
+        let resp = database_error_response(&anyhow::Error::new(PostgresNotConfiguredError));
+        assert_eq!(resp.status(), StatusCode::NOT_IMPLEMENTED);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "feature_requires_postgres");
+
+        let resp = database_error_response(&anyhow::anyhow!("boom"));
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn test_unavailable_features_only_without_postgres() {
+        assert!(unavailable_features(true).is_empty());
+        let features = unavailable_features(false);
+        for feature in [
+            "sponsorship_requests",
+            "pricing_admin",
+            "product_bumps",
+            "engagement_series",
+            "developer_merge",
+            "home_module_admin",
+            "maker_account_age_gate",
+        ] {
+            assert!(features.contains(&feature.to_string()), "{}", feature);
+        }
+    }
+
+    #[test]
+    fn test_updates_change_review_status_flags_status_and_reason() {
+        let mut updates: UpdateProductRequest =
+            serde_json::from_value(serde_json::json!({ "name": "Renamed" })).unwrap();
+        assert!(!updates_change_review_status(&updates));
+        updates.status = Some(crate::models::ProductStatus::Approved);
+        assert!(updates_change_review_status(&updates));
+        updates.status = Some(crate::models::ProductStatus::Rejected);
+        assert!(updates_change_review_status(&updates));
+        updates.status = Some(crate::models::ProductStatus::Pending);
+        assert!(!updates_change_review_status(&updates));
+        updates.status = None;
+        updates.rejection_reason = Some("spam".to_string());
+        assert!(updates_change_review_status(&updates));
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_developer_profile_endpoint_returns_join_date() {
+        let url = env::var("TEST_DATABASE_URL")
+            .expect("TEST_DATABASE_URL must be set to run database tests");
+        let pool = sqlx::PgPool::connect(&url)
+            .await
+            .expect("connect TEST_DATABASE_URL");
+        let email = format!("joined-api-{}@example.com", uuid::Uuid::new_v4().simple());
+        let joined_at = Utc::now() - chrono::Duration::days(30);
+        sqlx::query("INSERT INTO developers (email, name, created_at) VALUES ($1, 'Joined', $2)")
+            .bind(&email)
+            .bind(joined_at)
+            .execute(&pool)
+            .await
+            .expect("insert developer");
+
+        let db = web::Data::new(Arc::new(Database::with_postgres_pool(pool.clone())));
+        let app = test::init_service(App::new().app_data(db).route(
+            "/api/developers/{email}",
+            web::get().to(get_developer_by_email),
+        ))
+        .await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/developers/{}", email))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let json: serde_json::Value = test::read_body_json(resp).await;
+        let created_at = json["data"]["created_at"]
+            .as_str()
+            .expect("created_at in profile");
+        assert_eq!(
+            chrono::DateTime::parse_from_rfc3339(created_at)
+                .expect("rfc3339")
+                .timestamp(),
+            joined_at.timestamp()
+        );
+
+        sqlx::query("DELETE FROM developers WHERE email = $1")
+            .bind(&email)
+            .execute(&pool)
+            .await
+            .ok();
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_month_request_and_paid_order_grant_the_same_duration() {
+        let url = env::var("TEST_DATABASE_URL")
+            .expect("TEST_DATABASE_URL must be set to run database tests");
+        let pool = sqlx::PgPool::connect(&url)
+            .await
+            .expect("connect TEST_DATABASE_URL");
+        let db = Arc::new(Database::with_postgres_pool(pool.clone()));
+        let product_id = uuid::Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO products (id, name, slogan, description, website, category, maker_name, maker_email) \
+             VALUES ($1, 'Month grant', 'Slogan', 'Description', 'https://example.com', 'test', 'Maker', 'maker@example.com')",
+        )
+        .bind(product_id)
+        .execute(&pool)
+        .await
+        .expect("insert product");
+
+        // 申请路径：公开接口提交 duration_months=1，再由管理端按申请授权
+        let app = test::init_service(App::new().app_data(web::Data::new(db.clone())).route(
+            "/api/sponsorship/requests",
+            web::post().to(create_sponsorship_request),
+        ))
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/api/sponsorship/requests")
+            .set_json(serde_json::json!({
+                "email": "maker@example.com",
+                "product_ref": product_id.to_string(),
+                "placement": "home_right",
+                "slot_index": 0,
+                "duration_months": 1,
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let json: serde_json::Value = test::read_body_json(resp).await;
+        let request_id = json["data"]["id"].as_i64().expect("request id");
+        let duration_days = json["data"]["duration_days"]
+            .as_i64()
+            .expect("duration_days") as i32;
+        let request_grant = db
+            .create_sponsorship_grant_from_request(CreateSponsorshipGrantFromRequest {
+                request_id,
+                product_id: product_id.to_string(),
+                placement: Placement::HomeRight,
+                slot_index: Some(0),
+                duration_days,
+                amount_usd_cents: None,
+                starts_at: None,
+                sponsor_email: None,
+            })
+            .await
+            .expect("grant from request");
+
+        // 订单路径：paid_months=1 的支付回调
+        let order_id = uuid::Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO sponsorship_orders (id, user_email, product_id, placement, slot_index, requested_months) \
+             VALUES ($1, 'maker@example.com', $2, 'home_right', 0, 1)",
+        )
+        .bind(order_id)
+        .bind(product_id)
+        .execute(&pool)
+        .await
+        .expect("insert order");
+        let order_grant = db
+            .create_sponsorship_grant_and_mark_order_paid(
+                &order_id.to_string(),
+                None,
+                500,
+                1,
+                "manual",
+            )
+            .await
+            .expect("grant from order");
+
+        assert_eq!(
+            request_grant.ends_at - request_grant.starts_at,
+            order_grant.ends_at - order_grant.starts_at
+        );
+
+        sqlx::query("DELETE FROM sponsorship_requests WHERE id = $1")
+            .bind(request_id)
+            .execute(&pool)
+            .await
+            .ok();
+        sqlx::query("DELETE FROM sponsorship_grants WHERE product_id = $1")
+            .bind(product_id)
+            .execute(&pool)
+            .await
+            .ok();
+        sqlx::query("DELETE FROM sponsorship_orders WHERE id = $1")
+            .bind(order_id)
+            .execute(&pool)
+            .await
+            .ok();
+        sqlx::query("DELETE FROM products WHERE id = $1")
+            .bind(product_id)
+            .execute(&pool)
+            .await
+            .ok();
+    }
+
+    #[test]
+    fn test_postgres_optional_only_swallows_missing_postgres() {
+        let missing: anyhow::Result<Vec<String>> =
+            Err(anyhow::Error::new(PostgresNotConfiguredError));
+        assert_eq!(postgres_optional(missing).unwrap(), Vec::<String>::new());
+        let failed: anyhow::Result<i64> = Err(anyhow::anyhow!("connection reset"));
+        assert!(postgres_optional(failed).is_err());
+        assert_eq!(postgres_optional(Ok(7_i64)).unwrap(), 7);
+    }
+
+    #[actix_web::test]
+    async fn test_maker_account_age_gate_asks_new_makers_to_create_a_profile() {
+        let now = Utc::now();
+        let min_age = Duration::hours(48);
+
+        let resp = maker_account_age_gate("en", true, None, min_age, now).unwrap_err();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let message = json["message"].as_str().unwrap();
+        assert!(message.contains("create your maker profile"), "{}", message);
+
+        let resp = maker_account_age_gate("zh", false, None, min_age, now).unwrap_err();
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(json["message"].as_str().unwrap().contains("登录"));
+
+        assert!(maker_account_age_gate("en", true, Some(now - min_age), min_age, now).is_ok());
+        assert!(
+            maker_account_age_gate("en", true, Some(now - Duration::hours(1)), min_age, now)
+                .is_err()
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_home_aggregate_cache_clear_drops_all_sections() {
+        let cache = HomeAggregateCache::default();
+        assert!(!cache.clear());
+
+        let (value, degraded) = cached_home_section(
+            &cache.top_categories,
+            "en",
+            StdDuration::from_secs(60),
+            async { Ok(Vec::<CategoryWithCount>::new()) },
+        )
+        .await;
+        assert!(value.is_some());
+        assert!(!degraded);
+
+        assert!(cache.clear());
+        assert!(cache.top_categories.lock().unwrap().is_empty());
+        assert!(!cache.clear());
+    }
+}
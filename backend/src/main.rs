@@ -25,6 +25,7 @@ use utoipa_swagger_ui::SwaggerUi;
     ),
     components(schemas(
         models::ApiError,
+        models::FieldError,
         models::EmptyApiResponse,
         models::Product,
         models::ProductApiResponse,
@@ -35,6 +36,8 @@ use utoipa_swagger_ui::SwaggerUi;
         models::QueryParams,
         models::SearchApiResponse,
         models::SearchResult,
+        models::SearchHighlight,
+        handlers::BackgroundTaskStatus,
         handlers::HealthCheckResponse,
         handlers::SearchQuery
     ))
@@ -57,35 +60,76 @@ async fn main() -> std::io::Result<()> {
     log::info!("Starting SoloForge API server at http://{}", bind_address);
 
     let db = Arc::new(Database::new());
+    let capabilities = handlers::StaticCapabilities::from_env();
     let db_for_newsletter = db.clone();
+    let newsletter_heartbeat = Arc::new(handlers::TaskHeartbeat::new("newsletter", 300));
+    let webhook_heartbeat = Arc::new(handlers::TaskHeartbeat::new("webhooks", 120));
+    let home_cache = web::Data::new(handlers::HomeAggregateCache::default());
+    let engagement_limiter = web::Data::new(handlers::EngagementRateLimiter::from_env());
+    let background_tasks = web::Data::new(handlers::BackgroundTasks {
+        tasks: vec![newsletter_heartbeat.clone(), webhook_heartbeat.clone()],
+    });
     tokio::spawn(async move {
         loop {
-            let enabled = !matches!(
-                env::var("NEWSLETTER_ENABLED").ok().as_deref(),
-                Some("0") | Some("false") | Some("FALSE")
-            );
-            if enabled {
-                match db_for_newsletter.send_weekly_newsletter_if_due().await {
-                    Ok(sent) if sent > 0 => {
+            newsletter_heartbeat.tick();
+            if db::newsletter_flag_enabled() {
+                // 每轮单独 spawn，单次发送 panic 不会终止循环。
+                let db_for_round = db_for_newsletter.clone();
+                let round =
+                    tokio::spawn(async move { db_for_round.send_weekly_newsletter_if_due().await })
+                        .await;
+                match round {
+                    Ok(Ok(sent)) if sent > 0 => {
                         log::info!("Newsletter sent count={}", sent);
                     }
-                    Ok(_) => {}
-                    Err(e) => log::warn!("Newsletter task failed err={:?}", e),
+                    Ok(Ok(_)) => {}
+                    Ok(Err(e)) => log::warn!("Newsletter task failed err={:?}", e),
+                    Err(e) => log::error!("Newsletter task panicked err={:?}", e),
                 }
             }
             tokio::time::sleep(Duration::from_secs(60)).await;
         }
     });
 
+    let db_for_webhooks = db.clone();
+    tokio::spawn(async move {
+        loop {
+            webhook_heartbeat.tick();
+            let db_for_round = db_for_webhooks.clone();
+            let round =
+                tokio::spawn(async move { db_for_round.dispatch_due_webhook_deliveries(20).await })
+                    .await;
+            match round {
+                Ok(Ok(delivered)) if delivered > 0 => {
+                    log::info!("Webhooks delivered count={}", delivered);
+                }
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => log::warn!("Webhook dispatch failed err={:?}", e),
+                Err(e) => log::error!("Webhook dispatch panicked err={:?}", e),
+            }
+            tokio::time::sleep(Duration::from_secs(15)).await;
+        }
+    });
+
     HttpServer::new(move || {
         let cors = Cors::default()
             .allow_any_origin()
             .allow_any_method()
             .allow_any_header()
+            .expose_headers([
+                handlers::PAGE_LIMIT_HEADER,
+                handlers::PAGE_OFFSET_HEADER,
+                handlers::TOTAL_COUNT_HEADER,
+            ])
             .max_age(3600);
 
         App::new()
             .app_data(web::Data::new(db.clone()))
+            .app_data(web::Data::new(capabilities.clone()))
+            .app_data(handlers::json_config())
+            .app_data(background_tasks.clone())
+            .app_data(home_cache.clone())
+            .app_data(engagement_limiter.clone())
             .wrap(Logger::default())
             .wrap(cors)
             .service(SwaggerUi::new("/api/docs/{_:.*}").url("/api/openapi.json", ApiDoc::openapi()))
@@ -97,11 +141,23 @@ async fn main() -> std::io::Result<()> {
                             .route("", web::get().to(handlers::get_products))
                             .route("", web::post().to(handlers::create_product))
                             .route("/favorites", web::get().to(handlers::get_favorite_products))
+                            .route("/changes", web::get().to(handlers::get_product_changes))
                             .route("/{id}", web::get().to(handlers::get_product_by_id))
                             .route("/{id}", web::put().to(handlers::update_product))
                             .route("/{id}", web::delete().to(handlers::delete_product))
+                            .route("/{id}/og", web::get().to(handlers::get_product_og))
                             .route("/{id}/like", web::post().to(handlers::like_product))
                             .route("/{id}/unlike", web::post().to(handlers::unlike_product))
+                            .route("/{id}/claim", web::post().to(handlers::claim_product))
+                            .route("/{id}/bump", web::post().to(handlers::bump_product))
+                            .route(
+                                "/{id}/sponsorship-status",
+                                web::get().to(handlers::get_product_sponsorship_status),
+                            )
+                            .route(
+                                "/{id}/engagement",
+                                web::get().to(handlers::get_product_engagement),
+                            )
                             .route("/{id}/favorite", web::post().to(handlers::favorite_product))
                             .route(
                                 "/{id}/unfavorite",
@@ -110,6 +166,7 @@ async fn main() -> std::io::Result<()> {
                     )
                     .service(
                         web::scope("/developers")
+                            .route("", web::get().to(handlers::list_developers))
                             .route("/top", web::get().to(handlers::get_top_developers))
                             .route("/recent", web::get().to(handlers::get_recent_developers))
                             .route(
@@ -124,6 +181,14 @@ async fn main() -> std::io::Result<()> {
                                 "/{email}/center-stats",
                                 web::get().to(handlers::get_developer_center_stats),
                             )
+                            .route(
+                                "/{email}/products",
+                                web::get().to(handlers::get_developer_products),
+                            )
+                            .route(
+                                "/{email}/export.json",
+                                web::get().to(handlers::export_developer_profile),
+                            )
                             .route("/{email}", web::get().to(handlers::get_developer_by_email))
                             .route(
                                 "/{email}",
@@ -148,6 +213,20 @@ async fn main() -> std::io::Result<()> {
                             .route("", web::get().to(handlers::get_leaderboard)),
                     )
                     .service(web::scope("/search").route("", web::get().to(handlers::search)))
+                    .route("/feed.json", web::get().to(handlers::get_json_feed))
+                    .service(
+                        web::scope("/tags")
+                            .route("/suggest", web::get().to(handlers::suggest_tags)),
+                    )
+                    .service(
+                        web::scope("/meta")
+                            .route("/form-schema", web::get().to(handlers::get_form_schema))
+                            .route("/capabilities", web::get().to(handlers::get_capabilities)),
+                    )
+                    .service(
+                        web::scope("/stats")
+                            .route("/overview", web::get().to(handlers::get_overview_stats)),
+                    )
                     .service(
                         web::scope("/newsletter")
                             .route("/subscribe", web::post().to(handlers::subscribe_newsletter))
@@ -155,8 +234,18 @@ async fn main() -> std::io::Result<()> {
                             .route(
                                 "/unsubscribe",
                                 web::get().to(handlers::unsubscribe_newsletter),
-                            ),
+                            )
+                            .route(
+                                "/resubscribe",
+                                web::get().to(handlers::resubscribe_newsletter),
+                            )
+                            .route(
+                                "/open/{token}.gif",
+                                web::get().to(handlers::newsletter_open_pixel),
+                            )
+                            .route("/click", web::get().to(handlers::newsletter_click)),
                     )
+                    .route("/home", web::get().to(handlers::get_home))
                     .service(
                         web::scope("/home")
                             .route(
@@ -171,12 +260,27 @@ async fn main() -> std::io::Result<()> {
                     )
                     .service(
                         web::scope("/pricing-plans")
-                            .route("", web::get().to(handlers::get_pricing_plans)),
+                            .route("", web::get().to(handlers::get_pricing_plans))
+                            .route(
+                                "/key/{plan_key}",
+                                web::get().to(handlers::get_pricing_plan_by_key),
+                            )
+                            .route(
+                                "/{placement}",
+                                web::get().to(handlers::get_placement_pricing),
+                            ),
+                    )
+                    .service(
+                        web::scope("/sponsorship")
+                            .route(
+                                "/requests",
+                                web::post().to(handlers::create_sponsorship_request),
+                            )
+                            .route(
+                                "/orders",
+                                web::get().to(handlers::list_my_sponsorship_orders),
+                            ),
                     )
-                    .service(web::scope("/sponsorship").route(
-                        "/requests",
-                        web::post().to(handlers::create_sponsorship_request),
-                    ))
                     .service(
                         web::scope("/dev")
                             .route("/bootstrap", web::post().to(handlers::dev_bootstrap))
@@ -193,6 +297,18 @@ async fn main() -> std::io::Result<()> {
                                 "/review-product",
                                 web::get().to(handlers::admin_review_product),
                             )
+                            .route(
+                                "/newsletter/stats",
+                                web::get().to(handlers::admin_get_newsletter_stats),
+                            )
+                            .route(
+                                "/newsletter/send",
+                                web::post().to(handlers::admin_send_newsletter),
+                            )
+                            .route(
+                                "/products/status-counts",
+                                web::get().to(handlers::admin_get_product_status_counts),
+                            )
                             .route(
                                 "/categories/{id}",
                                 web::delete().to(handlers::admin_delete_category),
@@ -205,6 +321,10 @@ async fn main() -> std::io::Result<()> {
                                 "/pricing-plans",
                                 web::post().to(handlers::admin_upsert_pricing_plan),
                             )
+                            .route(
+                                "/pricing-plans/reorder",
+                                web::post().to(handlers::admin_reorder_pricing_plans),
+                            )
                             .route(
                                 "/pricing-plans/{id}",
                                 web::delete().to(handlers::admin_delete_pricing_plan),
@@ -229,6 +349,18 @@ async fn main() -> std::io::Result<()> {
                                 "/sponsorship/requests/action",
                                 web::post().to(handlers::admin_sponsorship_request_action),
                             )
+                            .route(
+                                "/sponsorship/requests/{id}/product-candidates",
+                                web::get().to(handlers::admin_list_sponsorship_request_candidates),
+                            )
+                            .route(
+                                "/sponsorship/requests/{id}/approve",
+                                web::post().to(handlers::admin_approve_sponsorship_request),
+                            )
+                            .route(
+                                "/sponsorship/grants/manual",
+                                web::post().to(handlers::admin_create_manual_sponsorship_grant),
+                            )
                             .route(
                                 "/sponsorship/grants",
                                 web::get().to(handlers::admin_list_sponsorship_grants),
@@ -244,6 +376,63 @@ async fn main() -> std::io::Result<()> {
                             .route(
                                 "/home-modules/{key}",
                                 web::put().to(handlers::admin_put_home_module_state),
+                            )
+                            .route(
+                                "/home-modules/{key}",
+                                web::patch().to(handlers::admin_patch_home_module_state),
+                            )
+                            .route(
+                                "/home-modules/{key}/debug",
+                                web::get().to(handlers::admin_debug_home_module),
+                            )
+                            .route(
+                                "/cache/invalidate",
+                                web::post().to(handlers::admin_invalidate_caches),
+                            )
+                            .route(
+                                "/developers/backfill",
+                                web::post().to(handlers::admin_backfill_developers),
+                            )
+                            .route(
+                                "/webhooks",
+                                web::get().to(handlers::admin_list_outgoing_webhooks),
+                            )
+                            .route(
+                                "/webhooks",
+                                web::post().to(handlers::admin_upsert_outgoing_webhook),
+                            )
+                            .route(
+                                "/webhooks/{id}",
+                                web::delete().to(handlers::admin_delete_outgoing_webhook),
+                            )
+                            .route(
+                                "/webhooks/{id}/deliveries",
+                                web::get().to(handlers::admin_list_outgoing_webhook_deliveries),
+                            )
+                            .route(
+                                "/product-claims",
+                                web::get().to(handlers::admin_list_product_claims),
+                            )
+                            .route(
+                                "/product-claims/action",
+                                web::post().to(handlers::admin_product_claim_action),
+                            )
+                            .route(
+                                "/developers/merge",
+                                web::post().to(handlers::admin_merge_developers),
+                            )
+                            .route(
+                                "/developers/{email}/sponsor",
+                                web::post().to(handlers::admin_update_developer_sponsor),
+                            )
+                            .route("/audit-log", web::get().to(handlers::admin_list_audit_log))
+                            .route(
+                                "/migrations/status",
+                                web::get().to(handlers::admin_get_migration_status),
+                            )
+                            .route(
+                                "/migrations/run-all",
+                                web::post().to(handlers::admin_run_all_migrations),
                             ),
                     ),
             )
@@ -29,6 +29,12 @@ pub struct Product {
     pub likes: i64,
     #[serde(default)]
     pub favorites: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub has_liked: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub has_favorited: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category_detail: Option<Category>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
@@ -39,6 +45,84 @@ pub enum ProductStatus {
     Rejected,
 }
 
+/**
+ * Placement
+ * 赞助展示位（唯一出处）：新增展示位只需在此加一个变体并在 placement_slot_count 配置槽位数。
+ */
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Placement {
+    HomeTop,
+    HomeRight,
+}
+
+impl Placement {
+    pub const ALL: [Placement; 2] = [Placement::HomeTop, Placement::HomeRight];
+
+    pub fn to_db_str(self) -> &'static str {
+        match self {
+            Placement::HomeTop => "home_top",
+            Placement::HomeRight => "home_right",
+        }
+    }
+}
+
+impl std::fmt::Display for Placement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.to_db_str())
+    }
+}
+
+impl std::str::FromStr for Placement {
+    type Err = InvalidPlacementError;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let value = raw.trim();
+        Placement::ALL
+            .into_iter()
+            .find(|p| p.to_db_str() == value)
+            .ok_or_else(|| InvalidPlacementError {
+                value: value.to_string(),
+            })
+    }
+}
+
+/**
+ * InvalidPlacementError
+ * 未知的展示位取值，由处理器按请求语言转换为 400，而不是依赖数据库 CHECK 约束报错。
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidPlacementError {
+    pub value: String,
+}
+
+impl InvalidPlacementError {
+    pub fn localized_message(&self, lang: &str) -> String {
+        let allowed: Vec<&str> = Placement::ALL.iter().map(|p| p.to_db_str()).collect();
+        if lang.starts_with("zh") {
+            format!(
+                "无效的展示位 {:?}，可选值：{}",
+                self.value,
+                allowed.join(" / ")
+            )
+        } else {
+            format!(
+                "Invalid placement {:?}, expected one of: {}",
+                self.value,
+                allowed.join(", ")
+            )
+        }
+    }
+}
+
+impl std::fmt::Display for InvalidPlacementError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid placement: {:?}", self.value)
+    }
+}
+
+impl std::error::Error for InvalidPlacementError {}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreateProductRequest {
     pub name: String,
@@ -51,6 +135,7 @@ pub struct CreateProductRequest {
     pub maker_name: String,
     pub maker_email: String,
     pub maker_website: Option<String>,
+    #[serde(default)]
     pub language: String,
 }
 
@@ -70,6 +155,8 @@ pub struct UpdateProductRequest {
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct NewsletterSubscribeRequest {
     pub email: String,
+    #[serde(default)]
+    pub format: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -99,6 +186,29 @@ pub struct Developer {
     pub website: Option<String>,
     pub sponsor_role: Option<String>,
     pub sponsor_verified: bool,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct DeveloperMergeResult {
+    pub from_email: String,
+    pub into_email: String,
+    pub products_moved: u64,
+    pub follows_moved: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ProductClaim {
+    pub id: i64,
+    pub product_id: String,
+    pub claimant_email: String,
+    pub status: String,
+    pub note: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub decided_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
@@ -110,6 +220,10 @@ pub struct DeveloperWithFollowers {
     pub sponsor_role: Option<String>,
     pub sponsor_verified: bool,
     pub followers: i64,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
@@ -122,6 +236,8 @@ pub struct DeveloperPopularity {
     pub sponsor_verified: bool,
     pub likes: i64,
     pub favorites: i64,
+    #[serde(default)]
+    pub new_followers: i64,
     pub score: i64,
 }
 
@@ -140,16 +256,87 @@ pub struct SponsorshipRequest {
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct ResolvedProductRef {
+    pub id: String,
+    pub name: String,
+    pub logo_url: Option<String>,
+}
+
+/**
+ * ProductRefMatch
+ * product_ref 与产品的匹配程度，按声明顺序从强到弱（UUID > 官网完全匹配 > 名称完全匹配 > 前缀 > 子串）。
+ */
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProductRefMatch {
+    ExactId,
+    ExactWebsite,
+    ExactName,
+    Prefix,
+    Substring,
+}
+
+impl ProductRefMatch {
+    pub fn from_rank(rank: i32) -> Self {
+        match rank {
+            0 => ProductRefMatch::ExactId,
+            1 => ProductRefMatch::ExactWebsite,
+            2 => ProductRefMatch::ExactName,
+            3 => ProductRefMatch::Prefix,
+            _ => ProductRefMatch::Substring,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct ProductRefCandidate {
+    pub id: String,
+    pub name: String,
+    pub website: String,
+    pub logo_url: Option<String>,
+    pub match_kind: ProductRefMatch,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct CreateSponsorshipRequest {
     pub email: String,
     pub product_ref: String,
-    pub placement: String,
+    pub placement: Placement,
     pub slot_index: Option<i32>,
     pub duration_days: i32,
     pub note: Option<String>,
 }
 
+/**
+ * SponsorshipGrantState
+ * grant 相对某时刻的状态：未开始 / 生效中（starts_at <= t < ends_at）/ 已结束。
+ */
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SponsorshipGrantState {
+    Upcoming,
+    Active,
+    Expired,
+}
+
+impl SponsorshipGrantState {
+    pub fn at(
+        starts_at: chrono::DateTime<chrono::Utc>,
+        ends_at: chrono::DateTime<chrono::Utc>,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Self {
+        if now < starts_at {
+            SponsorshipGrantState::Upcoming
+        } else if now < ends_at {
+            SponsorshipGrantState::Active
+        } else {
+            SponsorshipGrantState::Expired
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct SponsorshipGrant {
     pub id: i64,
@@ -161,17 +348,21 @@ pub struct SponsorshipGrant {
     pub source: String,
     pub amount_usd_cents: Option<i32>,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    pub active: bool,
+    pub state: SponsorshipGrantState,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct CreateSponsorshipGrantFromRequest {
     pub request_id: i64,
     pub product_id: String,
-    pub placement: String,
+    pub placement: Placement,
     pub slot_index: Option<i32>,
     pub duration_days: i32,
     pub amount_usd_cents: Option<i32>,
     pub starts_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// 设置时在同一事务内把该邮箱标记为已认证赞助者（仅在 sponsor_role 为空时写入 sponsor）。
+    pub sponsor_email: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
@@ -183,6 +374,12 @@ pub struct PricingPlanBenefit {
     pub available: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct PricingPlanBenefitGroups {
+    pub available: Vec<PricingPlanBenefit>,
+    pub unavailable: Vec<PricingPlanBenefit>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct PricingPlanCampaign {
     pub active: bool,
@@ -214,6 +411,66 @@ pub struct PricingPlan {
     pub updated_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct OutgoingWebhook {
+    pub id: i64,
+    pub url: String,
+    pub events: Vec<String>,
+    pub enabled: bool,
+    pub consecutive_failures: i32,
+    pub disabled_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct UpsertOutgoingWebhookRequest {
+    pub id: Option<i64>,
+    pub url: String,
+    pub secret: Option<String>,
+    pub events: Vec<String>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct OutgoingWebhookDelivery {
+    pub id: i64,
+    pub webhook_id: i64,
+    pub event: String,
+    pub status: String,
+    pub attempts: i32,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub next_attempt_at: chrono::DateTime<chrono::Utc>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub delivered_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/**
+ * MigrationStatus
+ * 单个懒迁移（ensure_*）的状态：ready_in_process 为本进程的 *_READY 标记，missing 为数据库中缺失的表/列/索引。
+ */
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct MigrationStatus {
+    pub name: String,
+    pub ready_in_process: bool,
+    pub schema_ok: bool,
+    pub missing: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct AdminAuditEntry {
+    pub id: i64,
+    pub actor: String,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: Option<String>,
+    pub payload_summary: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct UpsertPricingPlanRequest {
     pub id: Option<String>,
@@ -233,6 +490,11 @@ pub struct UpsertPricingPlanRequest {
     pub campaign: PricingPlanCampaign,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ReorderPricingPlansRequest {
+    pub ids: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct UpsertPricingPlanBenefit {
     pub id: Option<i64>,
@@ -262,6 +524,62 @@ pub struct SponsorshipOrder {
     pub updated_at: DateTime<Utc>,
 }
 
+/**
+ * CreateManualSponsorshipGrant
+ * 线下付款（银行转账/发票）的授权输入：external_reference 记入订单的 provider_order_id。
+ */
+#[derive(Debug, Clone)]
+pub struct CreateManualSponsorshipGrant {
+    pub user_email: String,
+    pub product_id: String,
+    pub placement: Placement,
+    pub slot_index: Option<i32>,
+    pub months: i32,
+    pub amount_usd_cents: i32,
+    pub external_reference: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ManualSponsorshipGrantResult {
+    pub order: SponsorshipOrder,
+    pub grant: SponsorshipGrant,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct UserSponsorshipOrder {
+    pub id: String,
+    pub product_id: String,
+    pub product_name: Option<String>,
+    pub placement: String,
+    pub slot_index: Option<i32>,
+    pub requested_months: i32,
+    pub paid_months: Option<i32>,
+    pub status: String,
+    pub amount_usd_cents: Option<i32>,
+    pub grant_id: Option<i64>,
+    pub grant_starts_at: Option<DateTime<Utc>>,
+    pub grant_ends_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct OrphanMaker {
+    pub email: String,
+    pub name: String,
+    pub website: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct PlacementPricing {
+    pub placement: String,
+    pub plan: PricingPlan,
+    pub next_available_slot_index: i32,
+    pub next_available_starts_at: DateTime<Utc>,
+    pub discount_percent_off: Option<i32>,
+    pub effective_monthly_usd_cents: Option<i32>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct PaymentsDayAgg {
     pub day: DateTime<Utc>,
@@ -269,6 +587,48 @@ pub struct PaymentsDayAgg {
     pub gross_usd_cents: i64,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct ProductEngagementDay {
+    pub day: DateTime<Utc>,
+    pub likes: i64,
+    pub favorites: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ProductActiveGrant {
+    pub placement: String,
+    pub slot_index: Option<i32>,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub active: bool,
+    pub state: SponsorshipGrantState,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ProductSponsorshipStatus {
+    pub product_id: String,
+    pub sponsored: bool,
+    pub sponsored_until: Option<DateTime<Utc>>,
+    pub grants: Vec<ProductActiveGrant>,
+}
+
+/**
+ * DeveloperProfileExport
+ * 单个创作者的可移植资料文档（/developers/{email}/export.json）；following 与非公开产品仅本人或管理员可见。
+ */
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct DeveloperProfileExport {
+    pub exported_at: DateTime<Utc>,
+    pub developer: Developer,
+    pub products: Vec<Product>,
+    pub product_count: usize,
+    pub followers: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub following: Option<i64>,
+    pub stats: DeveloperCenterStats,
+    pub includes_private: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct PaymentsSummary {
     pub created_orders: i64,
@@ -279,11 +639,47 @@ pub struct PaymentsSummary {
     pub by_day: Vec<PaymentsDayAgg>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct NewsletterWeekStats {
+    pub week_key: String,
+    pub sends: i64,
+    pub unique_opens: i64,
+    pub unique_clicks: i64,
+    pub total_clicks: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct TagSuggestion {
+    pub tag: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct OverviewStats {
+    pub total_products: i64,
+    pub approved_products: i64,
+    pub total_developers: i64,
+    pub total_likes: i64,
+    pub total_favorites: i64,
+    pub products_this_week: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct DeveloperCenterStats {
     pub followers: i64,
     pub total_likes: i64,
     pub total_favorites: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub windowed: Option<DeveloperCenterWindowStats>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct DeveloperCenterWindowStats {
+    pub window: String,
+    pub since: DateTime<Utc>,
+    pub new_followers: i64,
+    pub likes: i64,
+    pub favorites: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
@@ -308,6 +704,29 @@ pub struct DeveloperActivity {
     pub score: i64,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ProductChange {
+    pub id: String,
+    pub updated_at: DateTime<Utc>,
+    pub deleted: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product: Option<Product>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ProductChangesPage {
+    pub items: Vec<ProductChange>,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PagedList<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct ApiError {
     pub code: String,
@@ -317,6 +736,14 @@ pub struct ApiError {
     pub hint: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub detail: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<FieldError>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -359,6 +786,15 @@ pub struct ProductsApiResponse {
 pub struct SearchResult {
     pub products: Vec<Product>,
     pub developers: Vec<Developer>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub highlights: Option<Vec<SearchHighlight>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct SearchHighlight {
+    pub product_id: String,
+    pub field: String,
+    pub snippet: String,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -397,10 +833,16 @@ pub struct QueryParams {
     pub language: Option<String>,
     pub status: Option<String>,
     pub search: Option<String>,
-    #[allow(dead_code)]
     pub maker_email: Option<String>,
     pub sort: Option<String>,
     pub dir: Option<String>,
     pub limit: Option<i64>,
     pub offset: Option<i64>,
+    pub user_id: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub min_likes: Option<i64>,
+    pub sponsor_verified: Option<bool>,
+    pub include_pending: Option<bool>,
+    pub seed: Option<String>,
 }
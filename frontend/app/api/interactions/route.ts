@@ -23,6 +23,16 @@ function getForwardUserAgent(request: NextRequest): string {
   return request.headers.get('User-Agent') || request.headers.get('user-agent') || 'Mozilla/5.0';
 }
 
+/**
+ * getForwardedFor
+ * 透传浏览器的 X-Forwarded-For（缺失时用 X-Real-IP），后端据此按真实客户端 IP 限流与记录 ip_hash。
+ */
+function getForwardedFor(request: NextRequest): string | null {
+  const forwardedFor = request.headers.get('x-forwarded-for')?.trim();
+  if (forwardedFor) return forwardedFor;
+  return request.headers.get('x-real-ip')?.trim() || null;
+}
+
 type ApiResponse<T> = { success: boolean; data?: T; message?: string };
 
 export const dynamic = 'force-dynamic';
@@ -68,14 +78,17 @@ export async function POST(request: NextRequest) {
 
     const backendUrl = `${BACKEND_API_URL}/products/${encodeURIComponent(productId)}/${subPath}`;
     const payload = JSON.stringify({ user_id: userId });
+    const headers: Record<string, string> = {
+      'Content-Type': 'application/json',
+      Accept: 'application/json',
+      'Accept-Language': request.headers.get('Accept-Language') || 'en',
+      'User-Agent': getForwardUserAgent(request),
+    };
+    const forwardedFor = getForwardedFor(request);
+    if (forwardedFor) headers['X-Forwarded-For'] = forwardedFor;
     let response = await fetch(backendUrl, {
       method: 'POST',
-      headers: {
-        'Content-Type': 'application/json',
-        Accept: 'application/json',
-        'Accept-Language': request.headers.get('Accept-Language') || 'en',
-        'User-Agent': getForwardUserAgent(request),
-      },
+      headers,
       body: payload,
       cache: 'no-store',
     });
@@ -86,12 +99,7 @@ export async function POST(request: NextRequest) {
         const directUrl = backendUrl.replace(BACKEND_API_URL, directBase);
         response = await fetch(directUrl, {
           method: 'POST',
-          headers: {
-            'Content-Type': 'application/json',
-            Accept: 'application/json',
-            'Accept-Language': request.headers.get('Accept-Language') || 'en',
-            'User-Agent': getForwardUserAgent(request),
-          },
+          headers,
           body: payload,
           cache: 'no-store',
         });